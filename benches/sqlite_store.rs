@@ -0,0 +1,62 @@
+//! Throughput of read-heavy SqliteStore workloads.
+//!
+//! Runs concurrent kv_get/kv_list traffic (with a trickle of writes) against
+//! a file database, once with every query on the single write connection and
+//! once with the default pool of read connections.
+//!
+//! Run with `cargo bench --bench sqlite_store`.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use aegis::memory::{MemoryStore, SqliteStore, DEFAULT_READ_CONNECTIONS};
+
+const KEYS: usize = 1_000;
+const TASKS: usize = 64;
+const OPS_PER_TASK: usize = 500;
+
+async fn workload(store: Arc<SqliteStore>) -> f64 {
+    let started = Instant::now();
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                for op in 0..OPS_PER_TASK {
+                    let key = format!("bench:{}", (task * OPS_PER_TASK + op) % KEYS);
+                    match op % 20 {
+                        0 => store.kv_set(&key, serde_json::json!(op), None).await.unwrap(),
+                        1..=4 => {
+                            store.kv_list(Some(&key[..key.len() - 1])).await.unwrap();
+                        }
+                        _ => {
+                            store.kv_get(&key).await.unwrap();
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    (TASKS * OPS_PER_TASK) as f64 / started.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    for readers in [0, DEFAULT_READ_CONNECTIONS] {
+        let path = dir.path().join(format!("bench-{}.db", readers));
+        let store = Arc::new(SqliteStore::with_read_connections(path.to_str().unwrap(), readers).unwrap());
+        let ops = runtime.block_on(async {
+            for i in 0..KEYS {
+                store.kv_set(&format!("bench:{}", i), serde_json::json!(i), None).await.unwrap();
+            }
+            // Warm up, then measure
+            workload(store.clone()).await;
+            workload(store.clone()).await
+        });
+        println!("sqlite_store read connections={:<2} {:>10.0} ops/sec", readers, ops);
+    }
+}
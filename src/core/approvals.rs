@@ -0,0 +1,311 @@
+//! Human approval of sensitive tool calls.
+//!
+//! Client calls to tools matching `approvals.tools` are parked as pending
+//! approvals before they run. Someone then approves or rejects each one:
+//! from the dashboard, with the `approval.*` tools from another session, or
+//! at a prompt on the terminal in stdio mode. An approved call runs as
+//! usual, while a rejected one fails with the reason. Calls nobody decides
+//! on within `timeout_secs` are rejected.
+//!
+//! ```json
+//! "approvals": { "tools": ["cmd.exec", "fs.write_file", "docker.*"], "timeout_secs": 300 }
+//! ```
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::core::events::EventBus;
+use crate::core::RequestContext;
+
+/// Approval configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// Tool patterns ("cmd.exec", "docker.*") whose calls need approval.
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Seconds a call waits for a decision before it is rejected.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// In stdio mode, also ask on the controlling terminal.
+    #[serde(default = "default_terminal_prompt")]
+    pub terminal_prompt: bool,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            tools: Vec::new(),
+            timeout_secs: default_timeout_secs(),
+            terminal_prompt: default_terminal_prompt(),
+        }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
+fn default_terminal_prompt() -> bool {
+    true
+}
+
+/// A call waiting for approval.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    /// Approval ID.
+    pub id: String,
+    /// Tool that was called.
+    pub tool: String,
+    /// Arguments of the call.
+    pub arguments: Value,
+    /// Session that made the call.
+    pub session_id: String,
+    /// Name of the API key the caller authenticated with, if any.
+    pub api_key_name: Option<String>,
+    /// When the call was parked.
+    pub requested_at: String,
+}
+
+/// The decision on a pending call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approved,
+    /// Rejected, with an optional reason shown to the caller.
+    Rejected(Option<String>),
+}
+
+#[derive(Debug)]
+struct Pending {
+    request: ApprovalRequest,
+    decide: tokio::sync::oneshot::Sender<Decision>,
+}
+
+/// Pending approvals.
+#[derive(Debug)]
+pub struct Approvals {
+    tools: Vec<String>,
+    timeout: Duration,
+    terminal_prompt: bool,
+    pending: Mutex<HashMap<String, Pending>>,
+    /// Whether the terminal can be asked (stdio mode).
+    terminal: AtomicBool,
+    /// One question on the terminal at a time.
+    terminal_turn: tokio::sync::Mutex<()>,
+}
+
+impl Approvals {
+    /// Creates the approvals for a configuration.
+    pub fn from_config(config: &ApprovalConfig) -> Self {
+        Self {
+            tools: config.tools.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+            terminal_prompt: config.terminal_prompt,
+            pending: Mutex::new(HashMap::new()),
+            terminal: AtomicBool::new(false),
+            terminal_turn: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Whether calls to any tool need approval.
+    pub fn is_enabled(&self) -> bool {
+        !self.tools.is_empty()
+    }
+
+    /// Whether calls to `tool` need approval. Deciding on approvals never
+    /// does, even under a `*` pattern.
+    pub fn requires(&self, tool: &str) -> bool {
+        !tool.starts_with("approval.")
+            && self.tools.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => tool.starts_with(prefix),
+                None => pattern == tool,
+            })
+    }
+
+    /// Asks on the controlling terminal too, unless the configuration
+    /// turned the prompt off. Called in stdio mode, where no dashboard runs.
+    pub fn enable_terminal_prompt(&self) {
+        if self.terminal_prompt {
+            self.terminal.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Pending calls, oldest first.
+    pub fn list(&self) -> Vec<ApprovalRequest> {
+        let mut requests: Vec<_> = self.pending.lock().values().map(|pending| pending.request.clone()).collect();
+        requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+        requests
+    }
+
+    /// A pending call by ID.
+    pub fn get(&self, id: &str) -> Option<ApprovalRequest> {
+        self.pending.lock().get(id).map(|pending| pending.request.clone())
+    }
+
+    /// Decides on a pending call. Returns it, or `None` if no call with
+    /// that ID is waiting.
+    pub fn decide(&self, id: &str, decision: Decision) -> Option<ApprovalRequest> {
+        let pending = self.pending.lock().remove(id)?;
+        // The caller may have gone away meanwhile
+        let _ = pending.decide.send(decision);
+        Some(pending.request)
+    }
+
+    /// Waits for approval of a call if its tool needs one. Fails with the
+    /// reason when the call is rejected or times out.
+    pub async fn check(&self, tool: &str, arguments: &Value, context: &RequestContext, events: &EventBus) -> Result<(), String> {
+        if !self.requires(tool) {
+            return Ok(());
+        }
+
+        let (decide, decision) = tokio::sync::oneshot::channel();
+        let request = ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            session_id: context.session_id.clone(),
+            api_key_name: context.key_name().map(str::to_string),
+            requested_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.pending.lock().insert(request.id.clone(), Pending { request: request.clone(), decide });
+        // A call that times out or is cancelled leaves nothing behind
+        let _pending = PendingGuard { approvals: self, id: &request.id };
+        info!("Call to {} is waiting for approval ({})", tool, request.id);
+        events.publish("approval.requested", tool, json!(request));
+
+        let decision = tokio::select! {
+            decision = decision => decision.unwrap_or(Decision::Rejected(None)),
+            Some(decision) = self.ask_terminal(&request) => decision,
+            _ = tokio::time::sleep(self.timeout) => {
+                Decision::Rejected(Some(format!("not approved within {}s", self.timeout.as_secs())))
+            }
+        };
+
+        match decision {
+            Decision::Approved => {
+                info!("Call to {} approved ({})", tool, request.id);
+                events.publish("approval.approved", tool, json!({ "id": request.id }));
+                Ok(())
+            }
+            Decision::Rejected(reason) => {
+                warn!("Call to {} rejected ({}): {}", tool, request.id, reason.as_deref().unwrap_or("no reason given"));
+                events.publish("approval.rejected", tool, json!({ "id": request.id, "reason": reason }));
+                Err(match reason {
+                    Some(reason) => format!("Call to '{}' was rejected: {}", tool, reason),
+                    None => format!("Call to '{}' was rejected", tool),
+                })
+            }
+        }
+    }
+
+    /// Asks on the terminal, if enabled and there is one. `None` when the
+    /// terminal can't decide.
+    async fn ask_terminal(&self, request: &ApprovalRequest) -> Option<Decision> {
+        if !self.terminal.load(Ordering::Relaxed) {
+            return None;
+        }
+        let _turn = self.terminal_turn.lock().await;
+        // Someone may have decided while an earlier question was open
+        self.get(&request.id)?;
+
+        let question = format!(
+            "\nApprove call to {} with {}? [y/N] ",
+            request.tool,
+            serde_json::to_string(&request.arguments).unwrap_or_default()
+        );
+        let answer = tokio::task::spawn_blocking(move || prompt(&question)).await.ok()??;
+        Some(match answer.as_str() {
+            "y" | "yes" => Decision::Approved,
+            _ => Decision::Rejected(Some("rejected at the terminal".to_string())),
+        })
+    }
+}
+
+impl Default for Approvals {
+    fn default() -> Self {
+        Self::from_config(&ApprovalConfig::default())
+    }
+}
+
+/// Removes a pending call when its caller stops waiting.
+struct PendingGuard<'a> {
+    approvals: &'a Approvals,
+    id: &'a str,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.approvals.pending.lock().remove(self.id);
+    }
+}
+
+/// Asks a question on the controlling terminal; stdin and stdout carry
+/// the protocol in stdio mode.
+fn prompt(question: &str) -> Option<String> {
+    let mut tty = std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    tty.write_all(question.as_bytes()).ok()?;
+    tty.flush().ok()?;
+    let mut answer = String::new();
+    BufReader::new(tty).read_line(&mut answer).ok()?;
+    Some(answer.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_calls_wait_for_a_decision() {
+        let approvals = Arc::new(Approvals::from_config(&ApprovalConfig {
+            tools: vec!["cmd.exec".to_string(), "docker.*".to_string()],
+            timeout_secs: 1,
+            ..Default::default()
+        }));
+        let events = EventBus::new();
+        let context = RequestContext::new("agent");
+        assert!(approvals.requires("docker.run"));
+        assert!(!approvals.requires("fs.read_file"));
+        assert!(!Approvals::from_config(&ApprovalConfig { tools: vec!["*".to_string()], ..Default::default() })
+            .requires("approval.approve"));
+        assert!(approvals.check("fs.read_file", &json!({}), &context, &events).await.is_ok());
+
+        let decider = approvals.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(request) = decider.list().pop() {
+                    assert_eq!(request.arguments, json!({"command": "rm"}));
+                    decider.decide(&request.id, Decision::Rejected(Some("too risky".to_string())));
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+        let rejected = approvals.check("cmd.exec", &json!({"command": "rm"}), &context, &events).await;
+        assert_eq!(rejected, Err("Call to 'cmd.exec' was rejected: too risky".to_string()));
+
+        let decider = approvals.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(request) = decider.list().pop() {
+                    decider.decide(&request.id, Decision::Approved);
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+        assert!(approvals.check("cmd.exec", &json!({"command": "ls"}), &context, &events).await.is_ok());
+
+        // Nobody answers
+        let timed_out = approvals.check("docker.run", &json!({}), &context, &events).await;
+        assert!(timed_out.unwrap_err().contains("not approved within 1s"));
+        assert!(approvals.list().is_empty());
+    }
+}
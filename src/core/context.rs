@@ -0,0 +1,91 @@
+//! Per-request caller context.
+
+use std::sync::Arc;
+
+use crate::core::session::Session;
+
+/// Session used when the transport does not identify one (e.g. stdio).
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Identity of an authenticated API key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyIdentity {
+    /// Key name (for unnamed keys, a prefix of the hash).
+    pub name: String,
+
+    /// SHA-256 hash of the key.
+    pub key_hash: String,
+
+    /// Tools the key may call, as names or globs (`memory.*`, `*`).
+    pub scopes: Vec<String>,
+}
+
+impl KeyIdentity {
+    /// Returns whether the key's scopes cover a tool.
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.scopes.iter().any(|scope| match scope.strip_suffix('*') {
+            Some(prefix) => tool.starts_with(prefix),
+            None => scope == tool,
+        })
+    }
+}
+
+/// Caller information threaded from the transport down to tool middleware.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Session the request belongs to. Per-session state keys off this.
+    pub session_id: String,
+
+    /// SHA-256 hash of the API key the request authenticated with, if any.
+    pub api_key_hash: Option<String>,
+
+    /// Identity of the API key the request authenticated with, if any.
+    pub identity: Option<Arc<KeyIdentity>>,
+
+    /// State of the session, attached by the router.
+    pub session: Option<Arc<Session>>,
+}
+
+impl RequestContext {
+    /// Creates a context for the given session.
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            api_key_hash: None,
+            identity: None,
+            session: None,
+        }
+    }
+
+    /// Sets the authenticated API key hash.
+    pub fn with_api_key_hash(mut self, hash: Option<String>) -> Self {
+        self.api_key_hash = hash;
+        self
+    }
+
+    /// Sets the authenticated key identity (and its hash).
+    pub fn with_identity(mut self, identity: Option<Arc<KeyIdentity>>) -> Self {
+        if let Some(identity) = &identity {
+            self.api_key_hash = Some(identity.key_hash.clone());
+        }
+        self.identity = identity;
+        self
+    }
+
+    /// Attaches the session's state.
+    pub fn with_session(mut self, session: Arc<Session>) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Name of the authenticated API key, if any.
+    pub fn key_name(&self) -> Option<&str> {
+        self.identity.as_ref().map(|identity| identity.name.as_str())
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION)
+    }
+}
@@ -0,0 +1,454 @@
+//! Guard for outbound HTTP requests made by tools.
+//!
+//! URL patterns such as `http_client.blocked_urls` only see the URL as
+//! written, so they miss a hostname that resolves to a private address, an
+//! address spelled differently (`http://[::1]`, `http://2130706433`) and a
+//! redirect from a public host to an internal one. Clients built by
+//! [`EgressGuard::client_builder`] close those gaps:
+//!
+//! - hostnames are resolved by the guard when the connection is made, and a
+//!   host with any private, loopback or link-local address is refused, so a
+//!   host that re-resolves to an internal address after a check is caught
+//! - every redirect hop is checked like the first URL
+//!
+//! IP literals never reach a resolver, so tools also call
+//! [`EgressGuard::check_url`] before sending. Hosts and ranges listed in
+//! `http_client.allowed_private` may be private for every tool. The
+//! servers configured for a category (LLM providers, Ollama and the
+//! transcription service for `llm`, the GitHub API for `github`, and the
+//! category's proxy) may be private only for that category's tools, and
+//! only on their own port.
+//!
+//! Clients also go through the proxy configured for their
+//! [`ToolCategory`]. A proxy resolves the hostnames it is asked for, so
+//! behind one only IP literals and redirects are checked here.
+
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::{Attempt, Policy};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tracing::warn;
+use url::{Host, Url};
+
+use crate::core::config::ProxyConfig;
+use crate::core::Config;
+
+/// Redirects followed before a request fails.
+pub const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug)]
+struct Rules {
+    block_private: bool,
+    /// Lowercased hostnames that may resolve to private addresses.
+    allowed_hosts: Vec<String>,
+    allowed_networks: Vec<IpNet>,
+    /// Lowercased hosts and ports of the servers configured for the
+    /// category, which may be private.
+    endpoints: Vec<(String, u16)>,
+}
+
+impl Rules {
+    fn allows_host(&self, host: &str) -> bool {
+        !self.block_private
+            || self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+            || self.endpoints.iter().any(|(allowed, _)| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Whether `host` is a configured server of the category, but not on
+    /// `port`. The resolver can't see ports, so the port is checked here.
+    fn is_other_port(&self, host: &str, port: Option<u16>) -> bool {
+        self.block_private
+            && !self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+            && self.endpoints.iter().any(|(allowed, _)| allowed.eq_ignore_ascii_case(host))
+            && !self.endpoints.iter().any(|(allowed, allowed_port)| {
+                allowed.eq_ignore_ascii_case(host) && Some(*allowed_port) == port
+            })
+    }
+
+    fn allows_ip(&self, ip: IpAddr) -> bool {
+        !self.block_private || !is_private(ip) || self.allowed_networks.iter().any(|net| net.contains(&ip))
+    }
+
+    fn check_url(&self, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Only http(s) URLs can be fetched: {}", url));
+        }
+        let port = url.port_or_known_default();
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+            Some(Host::Domain(host)) if self.is_other_port(host, port) => {
+                return Err(format!("{} may only be reached on its configured port", host));
+            }
+            Some(Host::Domain(_)) => return Ok(()),
+            None => return Err(format!("URL has no host: {}", url)),
+        };
+        let host = ip.to_string();
+        if self.allows_ip(ip) || (self.allows_host(&host) && !self.is_other_port(&host, port)) {
+            Ok(())
+        } else {
+            Err(format!("Requests to private address {} are blocked", ip))
+        }
+    }
+}
+
+/// The lowercased host and port of a configured server's URL.
+fn endpoint(url: &str) -> Option<(String, u16)> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+    Some((host, url.port_or_known_default()?))
+}
+
+/// Tools that share proxy settings (keys of `http_client.proxies`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToolCategory {
+    /// http.request
+    Http,
+    /// web.*
+    Web,
+    /// notify.*
+    Notify,
+    /// llm.*, ollama.*
+    Llm,
+    /// github.*
+    Github,
+}
+
+impl ToolCategory {
+    pub const ALL: [ToolCategory; 5] = [Self::Http, Self::Web, Self::Notify, Self::Llm, Self::Github];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Web => "web",
+            Self::Notify => "notify",
+            Self::Llm => "llm",
+            Self::Github => "github",
+        }
+    }
+}
+
+/// How a category of tools connects.
+#[derive(Debug, Clone)]
+enum Route {
+    /// Proxies from the environment, if any.
+    Environment,
+    Direct,
+    Proxy(Box<reqwest::Proxy>),
+}
+
+impl Route {
+    fn from_config(category: &str, proxy: &ProxyConfig) -> Self {
+        let Some(url) = &proxy.url else {
+            return Route::Direct;
+        };
+        match reqwest::Proxy::all(url) {
+            Ok(route) => Route::Proxy(Box::new(route.no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy.join(","))))),
+            Err(e) => {
+                // The URL may hold a password
+                warn!("Ignoring invalid proxy for {} tools: {}", category, e);
+                Route::Environment
+            }
+        }
+    }
+}
+
+/// Checks the destinations of outbound requests, and builds the clients
+/// that send them.
+#[derive(Debug, Clone)]
+pub struct EgressGuard {
+    rules: Arc<HashMap<ToolCategory, Arc<Rules>>>,
+    routes: Arc<HashMap<ToolCategory, Route>>,
+}
+
+impl EgressGuard {
+    /// Creates the guard for a configuration.
+    pub fn from_config(config: &Config) -> Self {
+        let http = &config.http_client;
+        let mut allowed_hosts = Vec::new();
+        let mut allowed_networks = Vec::new();
+        for entry in &http.allowed_private {
+            match entry.parse::<IpNet>().or_else(|_| entry.parse::<IpAddr>().map(IpNet::from)) {
+                Ok(net) => allowed_networks.push(net),
+                Err(_) => allowed_hosts.push(entry.to_lowercase()),
+            }
+        }
+
+        // Operators point LLM providers at local servers (Ollama, vLLM, Whisper),
+        // GitHub at an Enterprise Server and proxies at internal hosts
+        let providers = config.llm.providers.iter().filter_map(|provider| provider.base_url.as_deref());
+        let llm: Vec<&str> = providers
+            .chain([config.llm.ollama.base_url.as_str(), config.llm.transcription.base_url.as_str()])
+            .collect();
+
+        let mut routes = HashMap::new();
+        let mut rules = HashMap::new();
+        for category in ToolCategory::ALL {
+            let proxy = match (http.proxies.get(category.as_str()), &http.proxy.url) {
+                (Some(proxy), _) => Some(proxy),
+                (None, Some(_)) => Some(&http.proxy),
+                (None, None) => None,
+            };
+            let route = match proxy {
+                Some(proxy) => Route::from_config(category.as_str(), proxy),
+                None => Route::Environment,
+            };
+            routes.insert(category, route);
+
+            let servers = match category {
+                ToolCategory::Llm => llm.clone(),
+                ToolCategory::Github => vec![config.github.api_url.as_str()],
+                _ => Vec::new(),
+            };
+            let proxy_url = proxy.and_then(|proxy| proxy.url.as_deref());
+            let endpoints = servers.into_iter().chain(proxy_url).filter_map(endpoint).collect();
+            rules.insert(category, Arc::new(Rules {
+                block_private: http.block_private_networks,
+                allowed_hosts: allowed_hosts.clone(),
+                allowed_networks: allowed_networks.clone(),
+                endpoints,
+            }));
+        }
+        Self {
+            rules: Arc::new(rules),
+            routes: Arc::new(routes),
+        }
+    }
+
+    fn rules(&self, category: ToolCategory) -> &Arc<Rules> {
+        &self.rules[&category]
+    }
+
+    /// Checks a URL before a tool of `category` requests it: the scheme,
+    /// the address when the host is an IP literal, and the port when the
+    /// host is one of the category's configured servers.
+    pub fn check_url(&self, category: ToolCategory, url: &str) -> Result<(), String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+        self.rules(category).check_url(&url)
+    }
+
+    /// Whether requests may reach an address.
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        self.rules(ToolCategory::Http).allows_ip(ip)
+    }
+
+    /// A client builder for a category of tools, with its proxy and with
+    /// connections and redirects guarded.
+    pub fn client_builder(&self, category: ToolCategory) -> reqwest::ClientBuilder {
+        let rules = self.rules(category).clone();
+        let redirects = Policy::custom(move |attempt: Attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+            }
+            match rules.check_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(reason) => attempt.error(format!("redirect refused: {}", reason)),
+            }
+        });
+        let builder = reqwest::Client::builder()
+            .dns_resolver(Arc::new(GuardedResolver { rules: self.rules(category).clone() }))
+            .redirect(redirects);
+        match self.routes.get(&category) {
+            Some(Route::Proxy(proxy)) => builder.proxy(proxy.as_ref().clone()),
+            Some(Route::Direct) => builder.no_proxy(),
+            Some(Route::Environment) | None => builder,
+        }
+    }
+
+    /// A client for a category of tools with default settings.
+    pub fn client(&self, category: ToolCategory) -> reqwest::Client {
+        self.client_builder(category).build().unwrap_or_default()
+    }
+}
+
+/// Resolves hostnames and refuses those with blocked addresses.
+#[derive(Debug)]
+struct GuardedResolver {
+    rules: Arc<Rules>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let rules = self.rules.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if !rules.allows_host(&host) {
+                if let Some(blocked) = addrs.iter().find(|addr| !rules.allows_ip(addr.ip())) {
+                    return Err(format!("{} resolves to private address {}, which is blocked", host, blocked.ip()).into());
+                }
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether an address is loopback, private, link-local or otherwise not
+/// on the public internet.
+pub fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_private_v4(mapped),
+            None => is_private_v6(ip),
+        },
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || a == 0
+        || a >= 240
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // Site-local (deprecated), fec0::/10
+        || (first & 0xffc0) == 0xfec0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_addresses() {
+        for private in ["127.0.0.1", "10.1.2.3", "172.20.0.1", "192.168.1.1", "169.254.169.254", "100.100.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(is_private(private.parse().unwrap()), "{}", private);
+        }
+        for public in ["8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111", "::ffff:1.1.1.1"] {
+            assert!(!is_private(public.parse().unwrap()), "{}", public);
+        }
+    }
+
+    #[test]
+    fn test_check_url() {
+        let mut config = Config::default();
+        config.http_client.allowed_private = vec!["10.0.5.0/24".to_string(), "build.internal".to_string()];
+        let guard = EgressGuard::from_config(&config);
+
+        let check = |url: &str| guard.check_url(ToolCategory::Http, url);
+        assert!(check("https://example.com/").is_ok());
+        assert!(check("http://127.0.0.1:8080/").is_err());
+        // Other spellings of loopback parse to the same address
+        assert!(check("http://2130706433/").is_err());
+        assert!(check("http://[::ffff:127.0.0.1]/").is_err());
+        assert!(check("http://10.0.5.7/").is_ok());
+        assert!(check("http://10.0.6.7/").is_err());
+        assert!(check("file:///etc/passwd").is_err());
+        let http = guard.rules(ToolCategory::Http);
+        assert!(http.allows_host("build.internal") && !http.allows_host("db.internal"));
+
+        config.http_client.block_private_networks = false;
+        assert!(EgressGuard::from_config(&config).check_url(ToolCategory::Http, "http://127.0.0.1/").is_ok());
+    }
+
+    #[test]
+    fn test_configured_servers_are_exempt_only_for_their_category_and_port() {
+        let mut config = Config::default();
+        config.llm.ollama.base_url = "http://127.0.0.1:11434".to_string();
+        config.github.api_url = "https://ghe.internal/api/v3".to_string();
+        let guard = EgressGuard::from_config(&config);
+
+        // The default transcription server and the Ollama server, for LLM tools only
+        assert!(guard.check_url(ToolCategory::Llm, "http://127.0.0.1:11434/api/chat").is_ok());
+        assert!(guard.check_url(ToolCategory::Llm, "http://127.0.0.1:6379/").is_err());
+        assert!(guard.check_url(ToolCategory::Http, "http://127.0.0.1:11434/api/chat").is_err());
+        assert!(guard.rules(ToolCategory::Llm).allows_host("api.openai.com"));
+        assert!(!guard.rules(ToolCategory::Http).allows_host("127.0.0.1"));
+
+        // GitHub Enterprise, for GitHub tools only, on its own port
+        assert!(guard.rules(ToolCategory::Github).allows_host("ghe.internal"));
+        assert!(!guard.rules(ToolCategory::Web).allows_host("ghe.internal"));
+        assert!(guard.check_url(ToolCategory::Github, "https://ghe.internal/api/v3/repos").is_ok());
+        assert!(guard.check_url(ToolCategory::Github, "http://ghe.internal:8080/").is_err());
+
+        // Listing a host in allowed_private exempts it everywhere, on any port
+        config.http_client.allowed_private = vec!["127.0.0.1".to_string()];
+        let guard = EgressGuard::from_config(&config);
+        assert!(guard.check_url(ToolCategory::Http, "http://127.0.0.1:6379/").is_ok());
+        assert!(guard.check_url(ToolCategory::Llm, "http://127.0.0.1:6379/").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_resolved_and_redirected_addresses_are_checked() {
+        use axum::{response::Redirect, routing::get, Router};
+
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/hop", get(|| async { Redirect::temporary("http://127.0.0.1:9/") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // "localhost" is only reachable through the resolver
+        let mut config = Config::default();
+        config.llm.ollama.base_url = "http://ollama.test:11434".to_string();
+        let blocked = EgressGuard::from_config(&config).client(ToolCategory::Http).get(format!("http://localhost:{}/", port)).send().await;
+        assert!(blocked.unwrap_err().is_connect());
+
+        config.http_client.allowed_private = vec!["localhost".to_string()];
+        let guard = EgressGuard::from_config(&config);
+        let ok = guard.client(ToolCategory::Http).get(format!("http://localhost:{}/", port)).send().await.unwrap();
+        assert_eq!(ok.text().await.unwrap(), "ok");
+
+        // A redirect to a literal private address is refused
+        let redirected = guard.client(ToolCategory::Http).get(format!("http://localhost:{}/hop", port)).send().await;
+        assert!(redirected.unwrap_err().is_redirect());
+    }
+
+    #[tokio::test]
+    async fn test_categories_use_their_proxy() {
+        use crate::core::config::ProxyConfig;
+        use axum::{routing::get, Router};
+
+        // Answers whatever it is asked for, as a forwarding proxy would
+        let app = Router::new().fallback(get(|| async { "via proxy" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut config = Config::default();
+        config.llm.ollama.base_url = "http://ollama.test:11434".to_string();
+        config.http_client.proxy = ProxyConfig { url: Some(proxy.clone()), no_proxy: vec![] };
+        config.http_client.proxies.insert("notify".to_string(), ProxyConfig::default());
+        config.http_client.proxies.insert(
+            "llm".to_string(),
+            ProxyConfig { url: Some(proxy), no_proxy: vec!["localhost".to_string()] },
+        );
+        assert!(config.validate().is_empty());
+        let guard = EgressGuard::from_config(&config);
+
+        // "localhost" is left to the proxy, and never resolved here
+        let proxied = guard.client(ToolCategory::Web).get("http://localhost:1/page").send().await.unwrap();
+        assert_eq!(proxied.text().await.unwrap(), "via proxy");
+        for direct in [ToolCategory::Notify, ToolCategory::Llm] {
+            let blocked = guard.client(direct).get("http://localhost:1/page").send().await;
+            assert!(blocked.unwrap_err().is_connect(), "{:?}", direct);
+        }
+
+        config.http_client.proxies.insert("ftp".to_string(), ProxyConfig { url: Some("ftp://proxy".to_string()), no_proxy: vec![] });
+        assert_eq!(config.validate().len(), 2);
+    }
+}
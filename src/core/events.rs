@@ -0,0 +1,408 @@
+//! Internal event bus and event rules.
+//!
+//! Client tool calls, scheduled tasks and memory writes publish events;
+//! rules from `event_rules` (or added at runtime with `events.subscribe`)
+//! run a tool or saved workflow when a matching event arrives, so
+//! automations can react without polling. Rule actions go through
+//! `tools/call` as the caller that added the rule, so its API key scopes,
+//! the policy, approvals and middleware apply to them.
+//!
+//! | Event | Source | Data |
+//! |-------|--------|------|
+//! | `tool.succeeded` / `tool.failed` | tool name | `arguments`, `output` or `error` |
+//! | `task.succeeded` / `task.failed` | task ID | `name`, `tool`, `output` or `error` |
+//! | `memory.set` / `memory.deleted` | memory key | `value` (for `memory.set`) |
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::core::config::{EventRule, RuleOwner};
+use crate::core::{RequestContext, RuntimeState, Session};
+use crate::handlers::handle_tools_call_with_context;
+use crate::memory::{MemoryError, MemoryStore};
+use crate::tools::extras::substitute_context;
+use crate::transport::middleware::ApiKeys;
+
+/// KV key the rules added with `events.subscribe` are persisted under.
+pub const EVENT_RULES_KEY: &str = "aegis:event_rules";
+
+/// How many rules may trigger each other in a row before events are
+/// dropped, so rules can't loop forever.
+pub const MAX_CHAIN_DEPTH: u8 = 4;
+
+/// Events kept for slow rule dispatch before the oldest are dropped.
+const CHANNEL_CAPACITY: usize = 256;
+
+tokio::task_local! {
+    static DEPTH: u8;
+}
+
+/// Something that happened inside the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Event type, e.g. "tool.succeeded".
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// What the event is about: a tool name, task ID or memory key.
+    pub source: String,
+    /// Event details.
+    pub data: Value,
+    /// When the event was published (RFC 3339).
+    pub timestamp: String,
+    /// Number of rules that led to this event.
+    #[serde(skip)]
+    depth: u8,
+}
+
+/// Publishes events and holds the rules added at runtime.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    subscriptions: RwLock<Vec<EventRule>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    /// Creates a bus with no listeners.
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            subscriptions: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Publishes an event. Events raised by a rule's action carry the
+    /// rule's depth, so chains of rules can be cut off.
+    pub fn publish(&self, kind: &str, source: &str, data: Value) {
+        let event = Event {
+            kind: kind.to_string(),
+            source: source.to_string(),
+            data,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            depth: DEPTH.try_with(|depth| *depth).unwrap_or(0),
+        };
+        // No receivers just means nobody listens yet
+        let _ = self.sender.send(event);
+    }
+
+    /// Receives events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Rules added with `events.subscribe`.
+    pub fn subscriptions(&self) -> Vec<EventRule> {
+        self.subscriptions.read().clone()
+    }
+
+    /// Adds a rule and persists the set.
+    pub async fn add_subscription(&self, store: &dyn MemoryStore, rule: EventRule) -> Result<(), MemoryError> {
+        let rules = {
+            let mut subscriptions = self.subscriptions.write();
+            subscriptions.push(rule);
+            subscriptions.clone()
+        };
+        store.kv_set(EVENT_RULES_KEY, json!(rules), None).await
+    }
+
+    /// Removes the rule with the given ID. Returns whether it existed.
+    pub async fn remove_subscription(&self, store: &dyn MemoryStore, id: &str) -> Result<bool, MemoryError> {
+        let rules = {
+            let mut subscriptions = self.subscriptions.write();
+            let before = subscriptions.len();
+            subscriptions.retain(|rule| rule.id.as_deref() != Some(id));
+            if subscriptions.len() == before {
+                return Ok(false);
+            }
+            subscriptions.clone()
+        };
+        store.kv_set(EVENT_RULES_KEY, json!(rules), None).await?;
+        Ok(true)
+    }
+
+    /// Loads the rules persisted by an earlier run.
+    pub async fn restore(&self, store: &dyn MemoryStore) {
+        let rules = match store.kv_get(EVENT_RULES_KEY).await {
+            Ok(Some(entry)) => serde_json::from_value::<Vec<EventRule>>(entry.value).unwrap_or_default(),
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load event subscriptions: {}", e);
+                return;
+            }
+        };
+        if !rules.is_empty() {
+            info!("Restored {} event subscription(s)", rules.len());
+        }
+        *self.subscriptions.write() = rules;
+    }
+}
+
+/// Whether the current task runs the action of an event rule.
+pub fn in_rule() -> bool {
+    DEPTH.try_with(|_| ()).is_ok()
+}
+
+/// Whether a tool may not be a rule's action: rules must not decide on
+/// approvals, or they could approve the calls they trigger.
+pub fn is_forbidden_action(tool: &str) -> bool {
+    tool.starts_with("approval.")
+}
+
+/// Matches a value against a pattern with an optional trailing `*`.
+fn matches(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+impl EventRule {
+    /// Whether this rule fires for `event`.
+    pub fn matches(&self, event: &Event) -> bool {
+        matches(&self.on, &event.kind)
+            && self.source.as_deref().is_none_or(|source| matches(source, &event.source))
+    }
+
+    /// The tool call this rule makes for `event`. Workflows also get the
+    /// event as the `event` input.
+    fn call(&self, event: &Event) -> Option<(String, Value)> {
+        let event = json!(event);
+        let context = HashMap::from([("event".to_string(), event.clone())]);
+        let args = substitute_context(&self.args, &context);
+
+        match (&self.tool, &self.workflow) {
+            (Some(tool), _) => Some((tool.clone(), args)),
+            (None, Some(workflow)) => {
+                let mut inputs = match args {
+                    Value::Object(map) => map,
+                    _ => serde_json::Map::new(),
+                };
+                inputs.entry("event").or_insert(event);
+                Some((
+                    "workflow.execute".to_string(),
+                    json!({ "name": workflow, "inputs": inputs }),
+                ))
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// Runs matching rules for every published event until the bus closes.
+async fn dispatch(state: Arc<RuntimeState>, mut events: broadcast::Receiver<Event>) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Event rules fell behind, skipped {} event(s)", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let rules: Vec<EventRule> = state
+            .config
+            .event_rules
+            .iter()
+            .cloned()
+            .chain(state.events.subscriptions())
+            .filter(|rule| rule.matches(&event))
+            .collect();
+        if rules.is_empty() {
+            continue;
+        }
+        if event.depth >= MAX_CHAIN_DEPTH {
+            warn!("Dropping {} from {}: event rules chained {} deep", event.kind, event.source, event.depth);
+            continue;
+        }
+
+        for rule in rules {
+            let Some((tool, args)) = rule.call(&event) else { continue };
+            let state = state.clone();
+            let depth = event.depth + 1;
+            let label = format!("{} from {}", event.kind, event.source);
+            tokio::spawn(DEPTH.scope(depth, async move {
+                match run(&state, &rule, &tool, args).await {
+                    Ok(result) if result["isError"] != true => info!("Event rule for {} ran {}", label, tool),
+                    Ok(result) => warn!("Event rule for {}: {} returned an error: {}", label, tool, result["content"]),
+                    Err(e) => warn!("Event rule for {} failed: {}", label, e),
+                }
+            }));
+        }
+    }
+}
+
+/// Starts dispatching events to rules in the background.
+pub fn start(state: &Arc<RuntimeState>) {
+    // Subscribe before returning, so no event published after this is missed
+    let events = state.events.subscribe();
+    tokio::spawn(dispatch(state.clone(), events));
+}
+
+impl RuleOwner {
+    /// The owner of a rule or task the given caller adds.
+    pub fn of(context: RequestContext) -> Self {
+        RuleOwner {
+            namespace: context.session.as_ref().and_then(|session| session.namespace()),
+            session_id: context.session_id,
+            api_key_hash: context.api_key_hash,
+        }
+    }
+
+    /// The caller to act as. Fails once the owner's API key is removed.
+    pub fn context(&self, state: &RuntimeState) -> Result<RequestContext, String> {
+        let identity = match &self.api_key_hash {
+            Some(hash) => Some(
+                ApiKeys::from_config(&state.config.auth)
+                    .authenticate(hash)
+                    .ok_or_else(|| "the API key that added it is no longer configured".to_string())?,
+            ),
+            None => None,
+        };
+        let session = state.sessions.get(&self.session_id).unwrap_or_else(|| {
+            let session = Session::new(self.session_id.clone());
+            session.set_namespace(self.namespace.clone());
+            Arc::new(session)
+        });
+        Ok(RequestContext::new(self.session_id.clone()).with_identity(identity).with_session(session))
+    }
+}
+
+/// The caller a rule's action runs as: the caller that subscribed it, or
+/// the operator for rules from the configuration.
+fn rule_context(state: &RuntimeState, rule: &EventRule) -> Result<RequestContext, String> {
+    match &rule.owner {
+        Some(owner) => owner.context(state),
+        None => Ok(RequestContext::default()),
+    }
+}
+
+async fn run(state: &Arc<RuntimeState>, rule: &EventRule, tool_name: &str, args: Value) -> Result<Value, String> {
+    let resolved = state.tool_registry.read().resolve(tool_name).to_string();
+    if is_forbidden_action(&resolved) {
+        return Err(format!("{} can't be run by an event rule", resolved));
+    }
+    let context = rule_context(state, rule)?;
+
+    let _running = state.shutdown.begin_call();
+    let params = json!({ "name": tool_name, "arguments": args });
+    let timeout_secs = rule.timeout_secs;
+    tokio::time::timeout(
+        Duration::from_secs(timeout_secs),
+        handle_tools_call_with_context(Some(params), state.clone(), context),
+    )
+    .await
+    .map_err(|_| format!("{} timed out after {}s", tool_name, timeout_secs))?
+    .map_err(|e| format!("{}: {}", tool_name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn rule(on: &str, source: Option<&str>) -> EventRule {
+        serde_json::from_value(json!({
+            "on": on,
+            "source": source,
+            "tool": "memory.store",
+            "args": {"key": "last_{{event.source}}", "value": "{{event.data.output}}"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        bus.publish("tool.succeeded", "git.commit", json!({"output": "abc123"}));
+        let event = events.try_recv().unwrap();
+
+        assert!(rule("tool.succeeded", Some("git.*")).matches(&event));
+        assert!(rule("tool.*", None).matches(&event));
+        assert!(!rule("tool.failed", None).matches(&event));
+        assert!(!rule("tool.succeeded", Some("git.push")).matches(&event));
+
+        let (tool, args) = rule("tool.*", None).call(&event).unwrap();
+        assert_eq!(tool, "memory.store");
+        assert_eq!(args, json!({"key": "last_git.commit", "value": "abc123"}));
+    }
+
+    #[tokio::test]
+    async fn test_rules_run_and_stop_chaining() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            event_rules: vec![rule("task.succeeded", Some("nightly"))],
+            ..Config::default()
+        }));
+        start(&state);
+
+        state.events.publish("task.succeeded", "nightly", json!({"output": "done"}));
+        let mut stored = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            if let Some(entry) = state.memory_store.kv_get("last_nightly").await.unwrap() {
+                stored = Some(entry.value);
+                break;
+            }
+        }
+        assert_eq!(stored, Some(json!("done")));
+
+        // An event raised at the chain limit no longer triggers rules
+        DEPTH
+            .scope(MAX_CHAIN_DEPTH, async {
+                state.events.publish("task.succeeded", "nightly", json!({"output": "looped"}));
+            })
+            .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let entry = state.memory_store.kv_get("last_nightly").await.unwrap().unwrap();
+        assert_eq!(entry.value, json!("done"));
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_rules_run_as_their_owner() {
+        let mut config = Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        };
+        config.auth.keys = vec![serde_json::from_value(json!({
+            "name": "agent",
+            "key_hash": "aaaa",
+            "scopes": ["memory.*"]
+        }))
+        .unwrap()];
+        let state = Arc::new(RuntimeState::new(config));
+        let mut owned = rule("tool.succeeded", None);
+        owned.owner = Some(crate::core::config::RuleOwner {
+            session_id: "s1".to_string(),
+            api_key_hash: Some("aaaa".to_string()),
+            namespace: None,
+        });
+
+        // The action lands in the key's namespace
+        let result = run(&state, &owned, "memory.store", json!({"key": "seen", "value": 1})).await.unwrap();
+        assert_ne!(result["isError"], true);
+        assert!(state.memory_store.kv_get("ns:agent:seen").await.unwrap().is_some());
+
+        // ...and is held to the key's scopes
+        let result = run(&state, &owned, "echo", json!({"text": "hi"})).await.unwrap();
+        assert_eq!(result["isError"], true);
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("not scoped"));
+
+        assert!(run(&state, &owned, "approval.approve", json!({"id": "x"})).await.is_err());
+
+        owned.owner.as_mut().unwrap().api_key_hash = Some("revoked".to_string());
+        assert!(run(&state, &owned, "memory.store", json!({"key": "k", "value": 1})).await.is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Startup and shutdown hooks.
+//!
+//! `hooks.on_start` and `hooks.on_shutdown` list tools (or saved workflows)
+//! run when the server starts serving and when it stops, e.g. to announce a
+//! deployment on Slack, warm caches, or flush queues.
+
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::core::config::HookConfig;
+use crate::core::{AegisError, AegisResult, RequestContext, RuntimeState};
+use crate::tools::caller;
+
+/// When a set of hooks runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPhase {
+    Start,
+    Shutdown,
+}
+
+impl fmt::Display for HookPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookPhase::Start => write!(f, "on_start"),
+            HookPhase::Shutdown => write!(f, "on_shutdown"),
+        }
+    }
+}
+
+impl HookConfig {
+    /// The tool call this hook makes; workflows run via `workflow.execute`.
+    fn call(&self) -> Option<(String, Value)> {
+        match (&self.tool, &self.workflow) {
+            (Some(tool), _) => Some((tool.clone(), self.args.clone())),
+            (None, Some(workflow)) => Some((
+                "workflow.execute".to_string(),
+                json!({ "name": workflow, "inputs": self.args }),
+            )),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Runs the hooks configured for `phase` in order.
+///
+/// Failures are logged and the remaining hooks still run. Returns an error
+/// only when a hook marked `required` fails, so startup can be aborted.
+pub async fn run_hooks(state: &Arc<RuntimeState>, phase: HookPhase) -> AegisResult<()> {
+    let hooks = match phase {
+        HookPhase::Start => &state.config.hooks.on_start,
+        HookPhase::Shutdown => &state.config.hooks.on_shutdown,
+    };
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    info!("Running {} {} hook(s)", hooks.len(), phase);
+
+    let mut required_failure = None;
+    for (index, hook) in hooks.iter().enumerate() {
+        if let Err(e) = run_hook(state, hook).await {
+            let message = format!("{} hook #{} failed: {}", phase, index + 1, e);
+            if hook.required {
+                error!("{}", message);
+                required_failure.get_or_insert(message);
+            } else {
+                warn!("{}", message);
+            }
+        }
+    }
+
+    match required_failure {
+        Some(message) => Err(AegisError::Config(message)),
+        None => Ok(()),
+    }
+}
+
+async fn run_hook(state: &Arc<RuntimeState>, hook: &HookConfig) -> Result<(), String> {
+    let (tool_name, args) = hook
+        .call()
+        .ok_or_else(|| "hook needs either 'tool' or 'workflow'".to_string())?;
+
+    let tool = state
+        .tool_registry
+        .read()
+        .get(&tool_name)
+        .cloned()
+        .ok_or_else(|| format!("tool not found: {}", tool_name))?;
+
+    let started = Instant::now();
+    let result = tokio::time::timeout(
+        Duration::from_secs(hook.timeout_secs),
+        // Hooks are configured by the operator and run as the server
+        caller::with_context(RequestContext::default(), tool.execute(args, state.clone())),
+    )
+    .await
+    .map_err(|_| format!("{} timed out after {}s", tool_name, hook.timeout_secs))?;
+
+    match result {
+        Ok(output) if !output.is_error => {
+            info!("Hook {} completed in {}ms", tool_name, started.elapsed().as_millis());
+            Ok(())
+        }
+        Ok(output) => Err(format!("{} returned an error: {:?}", tool_name, output.content)),
+        Err(e) => Err(format!("{}: {}", tool_name, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::HooksConfig;
+    use crate::core::Config;
+
+    fn hook(tool: &str, required: bool) -> HookConfig {
+        HookConfig {
+            tool: Some(tool.to_string()),
+            workflow: None,
+            args: json!({ "text": "deployed" }),
+            timeout_secs: 5,
+            required,
+        }
+    }
+
+    fn state_with(hooks: HooksConfig) -> Arc<RuntimeState> {
+        Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            hooks,
+            ..Config::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_optional_failures_do_not_abort() {
+        let state = state_with(HooksConfig {
+            on_start: vec![hook("no.such.tool", false), hook("echo", true)],
+            on_shutdown: vec![hook("no.such.tool", false)],
+        });
+        assert!(run_hooks(&state, HookPhase::Start).await.is_ok());
+        assert!(run_hooks(&state, HookPhase::Shutdown).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_required_failure_aborts() {
+        let state = state_with(HooksConfig {
+            on_start: vec![hook("no.such.tool", true), hook("echo", false)],
+            on_shutdown: vec![],
+        });
+        let err = run_hooks(&state, HookPhase::Start).await.unwrap_err();
+        assert!(err.to_string().contains("on_start hook #1"), "{}", err);
+    }
+
+    #[test]
+    fn test_workflow_hook_call() {
+        let hook = HookConfig {
+            tool: None,
+            workflow: Some("warm_cache".to_string()),
+            args: json!({ "region": "eu" }),
+            timeout_secs: 30,
+            required: false,
+        };
+        let (tool, args) = hook.call().unwrap();
+        assert_eq!(tool, "workflow.execute");
+        assert_eq!(args, json!({ "name": "warm_cache", "inputs": { "region": "eu" } }));
+    }
+}
@@ -0,0 +1,487 @@
+//! Response cache for the HTTP and web tools.
+//!
+//! GET responses are kept in SQLite (`<db>.cache`, opened on first use)
+//! so repeated agent queries don't refetch the same pages. Freshness comes
+//! from the response's Cache-Control (`max-age`, `no-cache`, `no-store`)
+//! or a TTL the call passes; stale entries with an ETag or Last-Modified
+//! are revalidated with a conditional request instead of downloaded again.
+
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tracing::warn;
+
+use crate::core::config::HttpCacheConfig;
+
+/// How a call uses the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Always fetch; the cache is neither read nor written.
+    Off,
+    /// Cache for as long as the response's Cache-Control allows, or the
+    /// configured default TTL.
+    Headers,
+    /// Cache for this many seconds, whatever the response says.
+    Ttl(u64),
+}
+
+impl CacheMode {
+    /// The mode for a call with `cache` / `cache_ttl` arguments.
+    pub fn from_args(config: &HttpCacheConfig, arguments: &Value) -> Self {
+        let cache = arguments.get("cache").and_then(|v| v.as_bool());
+        if cache == Some(false) {
+            return Self::Off;
+        }
+        if let Some(ttl) = arguments.get("cache_ttl").and_then(|v| v.as_u64()) {
+            return Self::Ttl(ttl);
+        }
+        if cache == Some(true) || config.enabled {
+            Self::Headers
+        } else {
+            Self::Off
+        }
+    }
+}
+
+/// Where a response came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheStatus {
+    /// Served from the cache without a request.
+    Hit,
+    /// Fetched (and stored, if cacheable).
+    Miss,
+    /// The server confirmed the cached copy is still current.
+    Revalidated,
+    /// Caching was off for the call.
+    Bypass,
+}
+
+/// A response, fetched or cached.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// URL after redirects.
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// First value of a header, by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    async fn read(response: reqwest::Response) -> Result<Self, reqwest::Error> {
+        let url = response.url().to_string();
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.bytes().await?.to_vec();
+        Ok(Self { url, status, headers, body })
+    }
+}
+
+/// Cache usage counters since startup plus what is stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub entries: u64,
+    pub size_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub revalidated: u64,
+    /// Share of cacheable requests answered without a download.
+    pub hit_ratio: f64,
+}
+
+/// SQLite-backed HTTP response cache.
+#[derive(Debug)]
+pub struct HttpCache {
+    path: Option<String>,
+    config: HttpCacheConfig,
+    conn: OnceLock<Option<Mutex<Connection>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    revalidated: AtomicU64,
+}
+
+/// A stored entry with its validators.
+struct Entry {
+    response: CachedResponse,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fresh: bool,
+}
+
+impl HttpCache {
+    /// Creates a cache stored at `path`, or in memory when `None`.
+    pub fn new(path: Option<String>, config: HttpCacheConfig) -> Self {
+        Self {
+            path,
+            config,
+            conn: OnceLock::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            revalidated: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache key for a request: method, URL and the request headers that
+    /// change the response (e.g. Authorization).
+    pub fn key(method: &str, url: &str, headers: &[(String, String)]) -> String {
+        let mut headers: Vec<_> = headers
+            .iter()
+            .map(|(key, value)| (key.to_ascii_lowercase(), value.as_str()))
+            .collect();
+        headers.sort();
+        let mut hasher = Sha256::new();
+        hasher.update(json!([method.to_ascii_uppercase(), url, headers]).to_string());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Sends `request`, answering from the cache when `mode` allows.
+    /// `url` is the requested URL, used by [`clear`](Self::clear).
+    pub async fn send(
+        &self,
+        mode: CacheMode,
+        key: &str,
+        url: &str,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<(CachedResponse, CacheStatus), reqwest::Error> {
+        if mode == CacheMode::Off {
+            return Ok((CachedResponse::read(request.send().await?).await?, CacheStatus::Bypass));
+        }
+
+        let cached = self.lookup(key);
+        if let Some(entry) = &cached {
+            if entry.fresh {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok((entry.response.clone(), CacheStatus::Hit));
+            }
+            if let Some(etag) = &entry.etag {
+                request = request.header("if-none-match", etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header("if-modified-since", last_modified);
+            }
+        }
+
+        let response = CachedResponse::read(request.send().await?).await?;
+        if let (304, Some(entry)) = (response.status, cached) {
+            self.revalidated.fetch_add(1, Ordering::Relaxed);
+            // The 304 may carry fresh caching headers; the body stays ours
+            let refreshed = CachedResponse { headers: merge_headers(entry.response.headers, &response.headers), ..entry.response };
+            self.store(mode, key, url, &refreshed);
+            return Ok((refreshed, CacheStatus::Revalidated));
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        if (200..300).contains(&response.status) {
+            self.store(mode, key, url, &response);
+        }
+        Ok((response, CacheStatus::Miss))
+    }
+
+    /// Removes entries whose requested URL starts with `url_prefix`, or
+    /// all entries. Returns how many were removed.
+    pub fn clear(&self, url_prefix: Option<&str>) -> usize {
+        let Some(conn) = self.connection() else { return 0 };
+        let conn = conn.lock();
+        let removed = match url_prefix {
+            Some(prefix) => conn.execute(
+                "DELETE FROM http_cache WHERE substr(url, 1, length(?1)) = ?1",
+                params![prefix],
+            ),
+            None => conn.execute("DELETE FROM http_cache", []),
+        };
+        removed.unwrap_or_else(|e| {
+            warn!("Failed to clear HTTP cache: {}", e);
+            0
+        })
+    }
+
+    /// Current contents and counters.
+    pub fn stats(&self) -> CacheStats {
+        let (entries, size_bytes) = self
+            .connection()
+            .and_then(|conn| {
+                conn.lock()
+                    .query_row("SELECT COUNT(*), COALESCE(SUM(length(body)), 0) FROM http_cache", [], |row| {
+                        Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64))
+                    })
+                    .ok()
+            })
+            .unwrap_or((0, 0));
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let revalidated = self.revalidated.load(Ordering::Relaxed);
+        let total = hits + misses + revalidated;
+        CacheStats {
+            entries,
+            size_bytes,
+            hits,
+            misses,
+            revalidated,
+            hit_ratio: if total == 0 { 0.0 } else { (hits + revalidated) as f64 / total as f64 },
+        }
+    }
+
+    /// Opens the database on first use. `None` if it can't be opened, in
+    /// which case every request is fetched.
+    fn connection(&self) -> Option<&Mutex<Connection>> {
+        self.conn
+            .get_or_init(|| {
+                let conn = match &self.path {
+                    Some(path) => Connection::open(path),
+                    None => Connection::open_in_memory(),
+                };
+                let conn = conn.and_then(|conn| {
+                    conn.execute_batch(
+                        "PRAGMA journal_mode=WAL;
+                         CREATE TABLE IF NOT EXISTS http_cache (
+                             key TEXT PRIMARY KEY,
+                             url TEXT NOT NULL,
+                             final_url TEXT NOT NULL,
+                             status INTEGER NOT NULL,
+                             headers TEXT NOT NULL,
+                             body BLOB NOT NULL,
+                             etag TEXT,
+                             last_modified TEXT,
+                             stored_at INTEGER NOT NULL,
+                             expires_at INTEGER NOT NULL
+                         );
+                         CREATE INDEX IF NOT EXISTS idx_http_cache_stored ON http_cache(stored_at);",
+                    )?;
+                    Ok(conn)
+                });
+                match conn {
+                    Ok(conn) => Some(Mutex::new(conn)),
+                    Err(e) => {
+                        warn!("HTTP cache unavailable: {}", e);
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    fn lookup(&self, key: &str) -> Option<Entry> {
+        let conn = self.connection()?.lock();
+        let now = chrono::Utc::now().timestamp();
+        conn.query_row(
+            "SELECT final_url, status, headers, body, etag, last_modified, expires_at FROM http_cache WHERE key = ?1",
+            params![key],
+            |row| {
+                let headers: String = row.get(2)?;
+                Ok(Entry {
+                    response: CachedResponse {
+                        url: row.get(0)?,
+                        status: row.get(1)?,
+                        headers: serde_json::from_str(&headers).unwrap_or_default(),
+                        body: row.get(3)?,
+                    },
+                    etag: row.get(4)?,
+                    last_modified: row.get(5)?,
+                    fresh: row.get::<_, i64>(6)? > now,
+                })
+            },
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            warn!("HTTP cache lookup failed: {}", e);
+            None
+        })
+    }
+
+    /// Stores `response` if it may be cached, evicting the oldest entries
+    /// beyond `max_entries`.
+    fn store(&self, mode: CacheMode, key: &str, url: &str, response: &CachedResponse) {
+        if response.body.len() > self.config.max_entry_bytes {
+            return;
+        }
+        let directives = CacheControl::parse(response.header("cache-control"));
+        let ttl = match mode {
+            CacheMode::Ttl(ttl) => ttl,
+            _ if directives.no_store => return,
+            _ if directives.no_cache => 0,
+            _ => directives.max_age.unwrap_or(self.config.default_ttl_secs),
+        };
+        let etag = response.header("etag");
+        let last_modified = response.header("last-modified");
+        // Without validators an entry that is already stale is useless
+        if ttl == 0 && etag.is_none() && last_modified.is_none() {
+            return;
+        }
+
+        let Some(conn) = self.connection() else { return };
+        let conn = conn.lock();
+        let now = chrono::Utc::now().timestamp();
+        let result = conn
+            .execute(
+                "INSERT OR REPLACE INTO http_cache
+                     (key, url, final_url, status, headers, body, etag, last_modified, stored_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    key,
+                    url,
+                    response.url,
+                    response.status,
+                    json!(response.headers).to_string(),
+                    response.body,
+                    etag,
+                    last_modified,
+                    now,
+                    now.saturating_add(ttl as i64),
+                ],
+            )
+            .and_then(|_| {
+                conn.execute(
+                    "DELETE FROM http_cache WHERE key IN (
+                         SELECT key FROM http_cache ORDER BY stored_at DESC LIMIT -1 OFFSET ?1
+                     )",
+                    params![self.config.max_entries as i64],
+                )
+            });
+        if let Err(e) = result {
+            warn!("Failed to store HTTP cache entry: {}", e);
+        }
+    }
+}
+
+/// The Cache-Control directives the cache acts on.
+#[derive(Debug, Default, PartialEq)]
+struct CacheControl {
+    max_age: Option<u64>,
+    no_cache: bool,
+    no_store: bool,
+}
+
+impl CacheControl {
+    fn parse(header: Option<&str>) -> Self {
+        let mut directives = Self::default();
+        for directive in header.unwrap_or("").split(',') {
+            let (name, value) = match directive.trim().split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => directives.no_store = true,
+                "no-cache" => directives.no_cache = true,
+                // s-maxage is meant for shared caches, but is the better hint when both are set
+                "max-age" | "s-maxage" => {
+                    if let Some(seconds) = value.and_then(|v| v.parse().ok()) {
+                        directives.max_age = Some(seconds);
+                    }
+                }
+                _ => {}
+            }
+        }
+        directives
+    }
+}
+
+/// Cached headers updated with those of a 304 response.
+fn merge_headers(mut cached: Vec<(String, String)>, updated: &[(String, String)]) -> Vec<(String, String)> {
+    for (name, value) in updated {
+        if name.eq_ignore_ascii_case("content-length") {
+            continue;
+        }
+        match cached.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            Some(entry) => entry.1 = value.clone(),
+            None => cached.push((name.clone(), value.clone())),
+        }
+    }
+    cached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cache_control() {
+        let parsed = CacheControl::parse(Some("public, max-age=60, s-maxage=\"120\""));
+        assert_eq!(parsed, CacheControl { max_age: Some(120), no_cache: false, no_store: false });
+        assert!(CacheControl::parse(Some("No-Store")).no_store);
+        assert_eq!(CacheControl::parse(None), CacheControl::default());
+
+        let config = HttpCacheConfig::default();
+        assert_eq!(CacheMode::from_args(&config, &json!({})), CacheMode::Off);
+        assert_eq!(CacheMode::from_args(&config, &json!({"cache": true})), CacheMode::Headers);
+        assert_eq!(CacheMode::from_args(&config, &json!({"cache_ttl": 5})), CacheMode::Ttl(5));
+        let enabled = HttpCacheConfig { enabled: true, ..HttpCacheConfig::default() };
+        assert_eq!(CacheMode::from_args(&enabled, &json!({"cache": false})), CacheMode::Off);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_and_revalidation() {
+        use axum::{http::HeaderMap, routing::get, Router};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let app = Router::new()
+            .route("/fresh", get(|| async { ([("cache-control", "max-age=60")], "fresh") }))
+            .route(
+                "/etag",
+                get(move |headers: HeaderMap| {
+                    let counter = counter.clone();
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        if headers.get("if-none-match").is_some_and(|v| v == "\"v1\"") {
+                            return (axum::http::StatusCode::NOT_MODIFIED, [("etag", "\"v1\"")], "");
+                        }
+                        (axum::http::StatusCode::OK, [("etag", "\"v1\"")], "versioned")
+                    }
+                }),
+            )
+            .route("/private", get(|| async { ([("cache-control", "no-store")], "secret") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let cache = HttpCache::new(None, HttpCacheConfig { default_ttl_secs: 0, ..HttpCacheConfig::default() });
+        let client = reqwest::Client::new();
+        let get = |path: &str, mode: CacheMode| {
+            let url = format!("{}{}", base, path);
+            let key = HttpCache::key("GET", &url, &[]);
+            let request = client.get(&url);
+            let cache = &cache;
+            async move {
+                let (response, status) = cache.send(mode, &key, &url, request).await.unwrap();
+                (String::from_utf8(response.body).unwrap(), status)
+            }
+        };
+
+        assert_eq!(get("/fresh", CacheMode::Headers).await.1, CacheStatus::Miss);
+        assert_eq!(get("/fresh", CacheMode::Headers).await, ("fresh".to_string(), CacheStatus::Hit));
+
+        // No max-age and a zero default TTL: revalidated with the ETag every time
+        assert_eq!(get("/etag", CacheMode::Headers).await.1, CacheStatus::Miss);
+        assert_eq!(get("/etag", CacheMode::Headers).await, ("versioned".to_string(), CacheStatus::Revalidated));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        assert_eq!(get("/private", CacheMode::Headers).await.1, CacheStatus::Miss);
+        assert_eq!(get("/private", CacheMode::Headers).await.1, CacheStatus::Miss);
+        assert_eq!(get("/private", CacheMode::Ttl(60)).await.1, CacheStatus::Miss);
+        assert_eq!(get("/private", CacheMode::Ttl(60)).await.1, CacheStatus::Hit);
+        assert_eq!(get("/private", CacheMode::Off).await.1, CacheStatus::Bypass);
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.revalidated), (3, 2, 1));
+        assert_eq!(cache.clear(Some(&format!("{}/private", base))), 1);
+        assert_eq!(cache.clear(None), 2);
+    }
+}
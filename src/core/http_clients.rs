@@ -0,0 +1,138 @@
+//! Shared HTTP clients for tools.
+//!
+//! A `reqwest::Client` owns a connection pool and TLS session cache, so a
+//! client built for every call pays for a new TCP and TLS handshake each
+//! time. Tools ask [`HttpClients`] for a client instead: one is built per
+//! [`ClientProfile`] (tool category, timeout, User-Agent) the first time
+//! it is needed, from the [`EgressGuard`] so it keeps the category's proxy
+//! and the private network checks, and reused afterwards.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::core::config::HttpClientConfig;
+use crate::core::egress::{EgressGuard, ToolCategory};
+
+/// The settings a shared client is built with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientProfile {
+    category: ToolCategory,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+}
+
+impl ClientProfile {
+    /// A profile with the defaults of reqwest: no timeout, its User-Agent.
+    pub fn new(category: ToolCategory) -> Self {
+        Self {
+            category,
+            timeout: None,
+            user_agent: None,
+        }
+    }
+
+    /// Sets the timeout of whole requests.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the User-Agent header.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+}
+
+/// HTTP clients shared by tools, one per profile.
+#[derive(Debug)]
+pub struct HttpClients {
+    egress: EgressGuard,
+    pool_idle_timeout: Duration,
+    pool_max_idle_per_host: usize,
+    clients: Mutex<HashMap<ClientProfile, reqwest::Client>>,
+}
+
+impl HttpClients {
+    /// Creates the shared clients for a configuration.
+    pub fn new(egress: EgressGuard, config: &HttpClientConfig) -> Self {
+        Self {
+            egress,
+            pool_idle_timeout: Duration::from_secs(config.pool_idle_timeout_secs),
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The client for a profile, built on first use. Clones share the
+    /// connection pool.
+    pub fn get(&self, profile: &ClientProfile) -> Result<reqwest::Client, reqwest::Error> {
+        if let Some(client) = self.clients.lock().get(profile) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = self
+            .egress
+            .client_builder(profile.category)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host);
+        if let Some(timeout) = profile.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(user_agent) = &profile.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        let client = builder.build()?;
+        // Another call may have built one meanwhile; either works
+        Ok(self.clients.lock().entry(profile.clone()).or_insert(client).clone())
+    }
+
+    /// Number of clients built so far.
+    pub fn len(&self) -> usize {
+        self.clients.lock().len()
+    }
+
+    /// Whether no client was built yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use axum::{extract::ConnectInfo, routing::get, Router};
+    use std::net::SocketAddr;
+
+    #[tokio::test]
+    async fn test_connections_are_reused() {
+        // Answers with the client's port, which changes with each connection
+        let app = Router::new().route(
+            "/",
+            get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.port().to_string() }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap()
+        });
+
+        let mut config = Config::default();
+        config.http_client.allowed_private = vec!["127.0.0.1".to_string()];
+        let clients = HttpClients::new(EgressGuard::from_config(&config), &config.http_client);
+        let profile = ClientProfile::new(ToolCategory::Web).timeout(Duration::from_secs(5));
+
+        let mut ports = Vec::new();
+        for _ in 0..3 {
+            let client = clients.get(&profile).unwrap();
+            ports.push(client.get(&url).send().await.unwrap().text().await.unwrap());
+        }
+        assert!(ports.iter().all(|port| *port == ports[0]), "{:?}", ports);
+
+        clients.get(&profile.clone().user_agent("other")).unwrap();
+        clients.get(&ClientProfile::new(ToolCategory::Notify).timeout(Duration::from_secs(5))).unwrap();
+        assert_eq!(clients.len(), 3);
+    }
+}
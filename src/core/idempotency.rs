@@ -0,0 +1,229 @@
+//! Reuse of tool results for retried calls.
+//!
+//! Agents retry a `tools/call` after a timeout or a dropped connection
+//! without knowing whether the first attempt ran. A call that carries an
+//! idempotency key in `_meta.idempotencyKey` stores its successful result
+//! in the KV store, and a call with the same key within the TTL gets that
+//! result back instead of running the tool again. Tools listed in
+//! `idempotency.tools` are deduplicated even without a key, by tool name
+//! and arguments. A retry that arrives while the first call still runs
+//! waits for it. Failed calls are not stored, so they can be retried.
+//! A key reused with different arguments is refused rather than answered
+//! with the result of the other call.
+//!
+//! ```json
+//! "idempotency": { "ttl_secs": 300, "tools": { "notify.*": 600, "get_time": 0 } }
+//! ```
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::core::RequestContext;
+use crate::memory::MemoryStore;
+use crate::tools::ToolOutput;
+
+/// KV key prefix under which results are stored.
+pub const IDEMPOTENCY_KEY_PREFIX: &str = "idempotency:";
+
+/// Idempotency configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    /// Honor idempotency keys sent by clients.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Seconds a result is kept for its key.
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Tool patterns ("notify.*") with the seconds their results are kept.
+    /// Calls to these tools are deduplicated by their arguments even
+    /// without a key; 0 never reuses results of the tool, even with one.
+    #[serde(default)]
+    pub tools: HashMap<String, u64>,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            ttl_secs: default_ttl_secs(),
+            tools: HashMap::new(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_ttl_secs() -> u64 {
+    300
+}
+
+/// A call whose result is reused.
+#[derive(Debug, Clone)]
+pub struct IdempotentCall {
+    /// KV key the result is stored under.
+    pub key: String,
+    /// Seconds the result is kept.
+    pub ttl_secs: u64,
+    /// Hash of the call's arguments, stored with the result.
+    arguments: String,
+}
+
+/// A stored result, with the arguments of the call that produced it.
+#[derive(Serialize, Deserialize)]
+struct StoredResult {
+    arguments: String,
+    output: ToolOutput,
+}
+
+/// Stored results of idempotent calls.
+#[derive(Debug)]
+pub struct Idempotency {
+    config: IdempotencyConfig,
+    /// Locks of keys whose calls are running.
+    running: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Idempotency {
+    /// Creates the store for a configuration.
+    pub fn from_config(config: &IdempotencyConfig) -> Self {
+        Self {
+            config: config.clone(),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Seconds results of `tool` are kept, if `tool` is listed. An exact
+    /// name wins over patterns, and longer patterns over shorter ones.
+    fn tool_ttl(&self, tool: &str) -> Option<u64> {
+        if let Some(ttl) = self.config.tools.get(tool) {
+            return Some(*ttl);
+        }
+        self.config
+            .tools
+            .iter()
+            .filter_map(|(pattern, ttl)| {
+                let prefix = pattern.strip_suffix('*')?;
+                tool.starts_with(prefix).then_some((prefix.len(), *ttl))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, ttl)| ttl)
+    }
+
+    /// The storage key and TTL for a call, if its result is reused.
+    /// Results are kept apart per API key.
+    pub fn key(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        client_key: Option<&str>,
+        context: &RequestContext,
+    ) -> Option<IdempotentCall> {
+        let listed = self.tool_ttl(tool);
+        let client_key = client_key.filter(|_| self.config.enabled);
+        let ttl = match (listed, client_key) {
+            (Some(0), _) | (None, None) => return None,
+            (Some(ttl), _) => ttl,
+            (None, Some(_)) => self.config.ttl_secs,
+        };
+
+        // Object keys serialize sorted, so equal arguments hash equally
+        let arguments = hex::encode(Sha256::digest(arguments.to_string().as_bytes()));
+        let mut hasher = Sha256::new();
+        hasher.update(context.api_key_hash.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0]);
+        hasher.update(tool.as_bytes());
+        hasher.update([0]);
+        match client_key {
+            Some(key) => hasher.update(key.as_bytes()),
+            None => hasher.update(arguments.as_bytes()),
+        }
+        Some(IdempotentCall {
+            key: format!("{}{}", IDEMPOTENCY_KEY_PREFIX, hex::encode(hasher.finalize())),
+            ttl_secs: ttl,
+            arguments,
+        })
+    }
+
+    /// Returns the stored result for the call, or runs `call` and stores
+    /// its result if it succeeded. The flag tells whether the result was
+    /// reused. A key stored for other arguments fails the call.
+    pub async fn run<F>(&self, store: &dyn MemoryStore, idempotent: &IdempotentCall, call: F) -> (ToolOutput, bool)
+    where
+        F: Future<Output = ToolOutput>,
+    {
+        let key = idempotent.key.as_str();
+        // Declared first so it runs last, after the lock below is dropped
+        let _release = Release { running: &self.running, key };
+        let lock = self.running.lock().entry(key.to_string()).or_default().clone();
+        let _running = lock.lock().await;
+
+        if let Some(stored) = self.stored(store, key).await {
+            if stored.arguments != idempotent.arguments {
+                warn!("Idempotency key reused with different arguments");
+                let message = "The idempotency key was already used with different arguments";
+                return (ToolOutput::error(message), false);
+            }
+            debug!("Reusing the stored result of an idempotent call");
+            return (stored.output, true);
+        }
+        let output = call.await;
+        if !output.is_error {
+            self.store(store, idempotent, &output).await;
+        }
+        (output, false)
+    }
+
+    async fn stored(&self, store: &dyn MemoryStore, key: &str) -> Option<StoredResult> {
+        match store.kv_get(key).await {
+            Ok(entry) => entry.and_then(|entry| serde_json::from_value(entry.value).ok()),
+            Err(e) => {
+                warn!("Failed to read idempotent result: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn store(&self, store: &dyn MemoryStore, idempotent: &IdempotentCall, output: &ToolOutput) {
+        let stored = StoredResult { arguments: idempotent.arguments.clone(), output: output.clone() };
+        let value = match serde_json::to_value(stored) {
+            Ok(value) => value,
+            Err(e) => return warn!("Failed to serialize idempotent result: {}", e),
+        };
+        match store.kv_set(&idempotent.key, value, Some(idempotent.ttl_secs)).await {
+            Ok(()) => debug!("Stored idempotent result for {}s", idempotent.ttl_secs),
+            Err(e) => warn!("Failed to store idempotent result: {}", e),
+        }
+    }
+}
+
+/// Forgets the lock of a key when no call holds or waits for it any more,
+/// also when the call is cancelled.
+struct Release<'a> {
+    running: &'a Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    key: &'a str,
+}
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        let mut running = self.running.lock();
+        if running.get(self.key).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            running.remove(self.key);
+        }
+    }
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Self::from_config(&IdempotencyConfig::default())
+    }
+}
@@ -0,0 +1,258 @@
+//! In-memory capture of recent log events.
+//!
+//! [`LogLayer`] is installed next to the stderr logger and copies every
+//! event that passes the log filter into a process-wide ring buffer, so the
+//! dashboard (`/dashboard/api/logs`) and `logs.tail` can show recent server
+//! logs without access to the host. Secrets are masked in captured events
+//! and, through [`ScrubbedStderr`], in the stderr log.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::secrets::scrub::{log_secrets, scrub, scrub_value};
+
+/// Events kept in the buffer; older ones are dropped.
+pub const LOG_BUFFER_CAPACITY: usize = 2000;
+
+/// One captured log event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Position in the capture order, for polling with `after`.
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    /// "ERROR", "WARN", "INFO", "DEBUG" or "TRACE".
+    pub level: String,
+    /// Module the event came from.
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message.
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Which entries to return.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Least severe level to include (e.g. WARN includes ERROR).
+    pub level: Option<Level>,
+    /// Case-insensitive text the message, target or fields must contain.
+    pub filter: Option<String>,
+    /// Only entries after this sequence number.
+    pub after: Option<u64>,
+    /// Most recent entries to return.
+    pub limit: usize,
+}
+
+/// Ring buffer of recent log events.
+#[derive(Debug)]
+pub struct LogBuffer {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
+    next_seq: AtomicU64,
+}
+
+static BUFFER: LazyLock<LogBuffer> = LazyLock::new(|| LogBuffer::new(LOG_BUFFER_CAPACITY));
+
+/// The process-wide buffer [`LogLayer`] writes to.
+pub fn buffer() -> &'static LogBuffer {
+    &BUFFER
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer holding up to `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            next_seq: AtomicU64::new(1),
+        }
+    }
+
+    fn push(&self, mut entry: LogEntry) {
+        let mut entries = self.entries.lock();
+        // Assigned under the lock so sequence numbers stay in buffer order
+        entry.seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The newest entries matching `query`, oldest first.
+    pub fn query(&self, query: &LogQuery) -> Vec<LogEntry> {
+        let filter = query.filter.as_ref().map(|f| f.to_lowercase());
+        let entries = self.entries.lock();
+        let mut matched: Vec<LogEntry> = entries
+            .iter()
+            .rev()
+            .take_while(|entry| query.after.is_none_or(|after| entry.seq > after))
+            .filter(|entry| {
+                query.level.is_none_or(|level| {
+                    entry.level.parse::<Level>().is_ok_and(|entry_level| entry_level <= level)
+                })
+            })
+            .filter(|entry| {
+                filter.as_ref().is_none_or(|filter| {
+                    entry.message.to_lowercase().contains(filter)
+                        || entry.target.to_lowercase().contains(filter)
+                        || serde_json::Value::Object(entry.fields.clone()).to_string().to_lowercase().contains(filter)
+                })
+            })
+            .take(query.limit)
+            .cloned()
+            .collect();
+        matched.reverse();
+        matched
+    }
+
+    /// Number of buffered entries.
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Whether nothing has been captured yet.
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+}
+
+/// Tracing layer that copies events into a [`LogBuffer`].
+pub struct LogLayer {
+    buffer: &'static LogBuffer,
+}
+
+impl LogLayer {
+    /// A layer writing to the process-wide [`buffer`].
+    pub fn new() -> Self {
+        Self { buffer: buffer() }
+    }
+}
+
+impl Default for LogLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collects an event's message and fields.
+#[derive(Default)]
+struct EntryVisitor {
+    message: String,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Visit for EntryVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields.insert(field.name().to_string(), value.into());
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.fields.insert(field.name().to_string(), format!("{:?}", value).into());
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = EntryVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let secrets = log_secrets();
+        let mut fields = visitor.fields;
+        fields.values_mut().for_each(|value| scrub_value(value, &secrets));
+        self.buffer.push(LogEntry {
+            seq: 0,
+            timestamp: Utc::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: scrub(&visitor.message, &secrets).into_owned(),
+            fields,
+        });
+    }
+}
+
+/// Writer for the stderr logger that masks secrets in each formatted line.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScrubbedStderr;
+
+impl<'a> MakeWriter<'a> for ScrubbedStderr {
+    type Writer = ScrubbedStderr;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+impl io::Write for ScrubbedStderr {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        io::stderr().write_all(scrub(&text, &log_secrets()).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_capture_and_query() {
+        static TEST_BUFFER: LazyLock<LogBuffer> = LazyLock::new(|| LogBuffer::new(3));
+        let subscriber = tracing_subscriber::registry().with(LogLayer { buffer: &TEST_BUFFER });
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("starting up");
+            tracing::warn!(tool = "cmd.exec", "slow call");
+            tracing::error!("disk full");
+            tracing::debug!(attempt = 2, "retrying");
+        });
+
+        // The oldest entry was dropped
+        assert_eq!(TEST_BUFFER.len(), 3);
+        let all = TEST_BUFFER.query(&LogQuery { limit: 10, ..Default::default() });
+        assert_eq!(all.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["slow call", "disk full", "retrying"]);
+        assert_eq!(all[2].fields["attempt"], 2);
+
+        let warnings = TEST_BUFFER.query(&LogQuery { level: Some(Level::WARN), limit: 10, ..Default::default() });
+        assert_eq!(warnings.len(), 2);
+        let filtered = TEST_BUFFER.query(&LogQuery { filter: Some("CMD.EXEC".into()), limit: 10, ..Default::default() });
+        assert_eq!(filtered[0].message, "slow call");
+        let newer = TEST_BUFFER.query(&LogQuery { after: Some(all[1].seq), limit: 10, ..Default::default() });
+        assert_eq!(newer.len(), 1);
+        let last = TEST_BUFFER.query(&LogQuery { limit: 1, ..Default::default() });
+        assert_eq!(last[0].message, "retrying");
+    }
+}
@@ -4,6 +4,17 @@
 //! - Error types and result aliases
 //! - Configuration management
 //! - Runtime state management
+//! - Per-request caller context
+//! - Per-connection sessions
+//! - Tool authorization policies
+//! - Human approval of sensitive tool calls
+//! - Idempotent tool calls
+//! - Startup and shutdown hooks
+//! - Graceful shutdown coordination
+//! - HTTP response caching
+//! - Outbound request checks and shared HTTP clients
+//! - Resource subscriptions
+//! - Recent log capture
 
 /// Error types for Aegis operations.
 pub mod errors;
@@ -14,7 +25,52 @@ pub mod config;
 /// Runtime state shared across handlers.
 pub mod state;
 
+/// Per-request caller context.
+pub mod context;
+
+/// Per-connection session state.
+pub mod session;
+
+/// Tool authorization policies.
+pub mod policy;
+
+/// Human approval of sensitive tool calls.
+pub mod approvals;
+
+/// Reuse of tool results for retried calls.
+pub mod idempotency;
+
+/// Startup and shutdown hooks.
+pub mod hooks;
+
+/// Graceful shutdown coordination.
+pub mod shutdown;
+
+/// Internal event bus and event rules.
+pub mod events;
+
+/// Response cache for the HTTP and web tools.
+pub mod http_cache;
+
+/// Guard against requests to private networks.
+pub mod egress;
+
+/// HTTP clients shared by tools.
+pub mod http_clients;
+
+/// Resource subscriptions and update announcements.
+pub mod subscriptions;
+
+/// In-memory capture of recent log events.
+pub mod logs;
+
 // Re-exports for convenience
 pub use errors::{AegisError, AegisResult, NexusError, NexusResult};
-pub use config::{Config, PluginConfig};
+pub use config::{ApiKeyConfig, CollectionConfig, CompositeToolConfig, Config, ConfigFormat, PluginConfig, PluginDirConfig, ScriptConfig, UpstreamConfig};
 pub use state::RuntimeState;
+pub use context::{KeyIdentity, RequestContext};
+pub use session::{Session, SessionInfo, Sessions};
+pub use policy::{Policy, PolicyConfig};
+pub use approvals::{ApprovalConfig, Approvals};
+pub use idempotency::{Idempotency, IdempotencyConfig};
+pub use shutdown::Shutdown;
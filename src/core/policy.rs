@@ -0,0 +1,495 @@
+//! Policy engine for tool authorization.
+//!
+//! Rules decide, per API key or session, which tools may be called and with
+//! which arguments. Rules are checked in order and the first rule whose
+//! subject and tool match decides: a `deny` rule rejects the call, an `allow`
+//! rule accepts it if all of its argument constraints hold and rejects it
+//! (with the failed constraint as the reason) otherwise. Calls no rule
+//! matches fall back to `default_effect`.
+//!
+//! Rules can be written as JSON objects:
+//!
+//! ```json
+//! { "effect": "allow", "tools": ["cmd.exec"], "args": { "command": { "one_of": ["git"] } } }
+//! ```
+//!
+//! or in a one-line form:
+//!
+//! ```text
+//! allow cmd.exec if command in [git] and args.0 in [status, log, diff]
+//! allow fs.write if path under /tmp
+//! allow http.request for key:3f2a… if url matches ^https://api\.example\.com/
+//! deny *
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::core::{AegisError, AegisResult, RequestContext};
+
+/// What a matching rule does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// Policy configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// Enable policy enforcement on `tools/call`.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Effect when no rule matches (default: allow).
+    #[serde(default)]
+    pub default_effect: PolicyEffect,
+
+    /// Rules, checked in order.
+    #[serde(default)]
+    pub rules: Vec<PolicyRuleConfig>,
+}
+
+/// A rule as written in the config: a JSON object or a one-line rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PolicyRuleConfig {
+    Line(String),
+    Rule(PolicyRule),
+}
+
+/// A policy rule.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Name used in deny reasons (default: a description of the rule).
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Allow or deny.
+    #[serde(default)]
+    pub effect: PolicyEffect,
+
+    /// Tool patterns ("cmd.exec", "fs.*", "*").
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// API key hashes or names the rule applies to (empty: any caller).
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+
+    /// Sessions the rule applies to (empty: any caller).
+    #[serde(default)]
+    pub sessions: Vec<String>,
+
+    /// Constraints on arguments, keyed by argument path ("command", "args.0").
+    #[serde(default)]
+    pub args: HashMap<String, ArgConstraint>,
+
+    /// Message added to deny reasons.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Constraints on a single argument. Every field set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArgConstraint {
+    /// The argument must equal one of these values.
+    #[serde(default)]
+    pub one_of: Option<Vec<Value>>,
+
+    /// The argument must be a path inside one of these directories.
+    #[serde(default)]
+    pub under: Option<Vec<String>>,
+
+    /// The argument must be a string matching this regex.
+    #[serde(default)]
+    pub matches: Option<String>,
+}
+
+#[derive(Debug)]
+enum Condition {
+    OneOf(Vec<Value>),
+    Under(Vec<PathBuf>),
+    Matches(Regex),
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    name: String,
+    effect: PolicyEffect,
+    tools: Vec<String>,
+    api_keys: Vec<String>,
+    sessions: Vec<String>,
+    conditions: Vec<(String, Condition)>,
+    reason: Option<String>,
+}
+
+/// Compiled policy, checked before every client tool call.
+#[derive(Debug, Default)]
+pub struct Policy {
+    enabled: bool,
+    default_effect: PolicyEffect,
+    rules: Vec<CompiledRule>,
+}
+
+impl Policy {
+    /// Compiles the configured rules.
+    pub fn from_config(config: &PolicyConfig) -> AegisResult<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| match rule {
+                PolicyRuleConfig::Line(line) => parse_line(line).and_then(|rule| compile(&rule)),
+                PolicyRuleConfig::Rule(rule) => compile(rule),
+            })
+            .collect::<AegisResult<Vec<_>>>()?;
+
+        Ok(Self {
+            enabled: config.enabled,
+            default_effect: config.default_effect,
+            rules,
+        })
+    }
+
+    /// A policy that rejects every call (used when the config is invalid).
+    pub fn deny_all() -> Self {
+        Self {
+            enabled: true,
+            default_effect: PolicyEffect::Deny,
+            rules: vec![],
+        }
+    }
+
+    /// Returns whether policy enforcement is on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Checks a tool call. Returns the deny reason if it is not allowed.
+    pub fn check(&self, tool: &str, arguments: &Value, context: &RequestContext) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| rule.applies_to(context) && rule.tools.iter().any(|p| matches_tool(p, tool)))
+        else {
+            return match self.default_effect {
+                PolicyEffect::Allow => Ok(()),
+                PolicyEffect::Deny => Err(format!("no policy rule allows '{}'", tool)),
+            };
+        };
+
+        let with_reason = |message: String| match &rule.reason {
+            Some(reason) => format!("{} ({})", message, reason),
+            None => message,
+        };
+
+        if rule.effect == PolicyEffect::Deny {
+            return Err(with_reason(format!("'{}' is denied by policy rule '{}'", tool, rule.name)));
+        }
+
+        for (arg, condition) in &rule.conditions {
+            if let Err(message) = condition.check(argument(arguments, arg)) {
+                return Err(with_reason(format!(
+                    "policy rule '{}' requires argument '{}' {}",
+                    rule.name, arg, message
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CompiledRule {
+    fn applies_to(&self, context: &RequestContext) -> bool {
+        if self.api_keys.is_empty() && self.sessions.is_empty() {
+            return true;
+        }
+        let key_matches = context.api_key_hash.as_ref().is_some_and(|hash| {
+            self.api_keys
+                .iter()
+                .any(|k| k == "*" || k == hash || Some(k.as_str()) == context.key_name())
+        });
+        let session_matches = self.sessions.iter().any(|s| s == "*" || *s == context.session_id);
+        key_matches || session_matches
+    }
+}
+
+impl Condition {
+    fn check(&self, value: Option<&Value>) -> Result<(), String> {
+        match self {
+            Condition::OneOf(allowed) => match value {
+                Some(v) if allowed.contains(v) => Ok(()),
+                _ => Err(format!("to be one of {}", Value::Array(allowed.clone()))),
+            },
+            Condition::Under(roots) => {
+                let path = value.and_then(|v| v.as_str()).map(normalize_path);
+                match path {
+                    Some(p) if roots.iter().any(|root| p.starts_with(root)) => Ok(()),
+                    _ => Err(format!(
+                        "to be a path under {}",
+                        roots.iter().map(|r| r.display().to_string()).collect::<Vec<_>>().join(", ")
+                    )),
+                }
+            }
+            Condition::Matches(regex) => match value.and_then(|v| v.as_str()) {
+                Some(s) if regex.is_match(s) => Ok(()),
+                _ => Err(format!("to match /{}/", regex.as_str())),
+            },
+        }
+    }
+}
+
+/// Matches a tool name against a pattern with an optional trailing `*`.
+fn matches_tool(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool.starts_with(prefix),
+        None => pattern == tool,
+    }
+}
+
+/// Looks up a dotted argument path ("args.0").
+fn argument<'a>(arguments: &'a Value, path: &str) -> Option<&'a Value> {
+    arguments.pointer(&format!("/{}", path.replace('.', "/")))
+}
+
+/// Makes a path absolute and resolves `.` and `..` without touching the
+/// filesystem, so `/tmp/../etc` is not mistaken for a path under `/tmp`.
+fn normalize_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn compile(rule: &PolicyRule) -> AegisResult<CompiledRule> {
+    if rule.tools.is_empty() {
+        return Err(AegisError::Config("policy rule has no tools".to_string()));
+    }
+
+    let name = rule.name.clone().unwrap_or_else(|| {
+        let verb = match rule.effect {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+        };
+        format!("{} {}", verb, rule.tools.join(","))
+    });
+
+    // Sort for deterministic evaluation (and error) order
+    let mut args: Vec<_> = rule.args.iter().collect();
+    args.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut conditions = Vec::new();
+    for (arg, constraint) in args {
+        if let Some(values) = &constraint.one_of {
+            conditions.push((arg.clone(), Condition::OneOf(values.clone())));
+        }
+        if let Some(roots) = &constraint.under {
+            let roots = roots.iter().map(|r| normalize_path(r)).collect();
+            conditions.push((arg.clone(), Condition::Under(roots)));
+        }
+        if let Some(pattern) = &constraint.matches {
+            let regex = Regex::new(pattern).map_err(|e| {
+                AegisError::Config(format!("policy rule '{}': invalid regex: {}", name, e))
+            })?;
+            conditions.push((arg.clone(), Condition::Matches(regex)));
+        }
+    }
+
+    Ok(CompiledRule {
+        name,
+        effect: rule.effect,
+        tools: rule.tools.clone(),
+        api_keys: rule.api_keys.clone(),
+        sessions: rule.sessions.clone(),
+        conditions,
+        reason: rule.reason.clone(),
+    })
+}
+
+/// Parses a one-line rule:
+/// `<allow|deny> <tools> [for key:<hash>,session:<id>] [if <cond> [and <cond>]...]`
+/// where a condition is `<arg> in [a, b]`, `<arg> under <dir>[, <dir>]` or
+/// `<arg> matches <regex>`.
+fn parse_line(line: &str) -> AegisResult<PolicyRule> {
+    let invalid = |message: &str| AegisError::Config(format!("policy rule '{}': {}", line, message));
+
+    let (head, conditions) = match line.split_once(" if ") {
+        Some((head, conditions)) => (head, Some(conditions)),
+        None => (line, None),
+    };
+
+    let mut words = head.split_whitespace();
+    let effect = match words.next() {
+        Some("allow") => PolicyEffect::Allow,
+        Some("deny") => PolicyEffect::Deny,
+        _ => return Err(invalid("must start with 'allow' or 'deny'")),
+    };
+    let tools = words
+        .next()
+        .ok_or_else(|| invalid("missing tool pattern"))?
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .collect();
+
+    let mut rule = PolicyRule {
+        name: Some(line.to_string()),
+        effect,
+        tools,
+        ..Default::default()
+    };
+
+    match words.next() {
+        None => {}
+        Some("for") => {
+            let subjects: String = words.collect::<Vec<_>>().join(" ");
+            for subject in subjects.split(',').map(str::trim) {
+                match subject.split_once(':') {
+                    Some(("key", hash)) => rule.api_keys.push(hash.to_string()),
+                    Some(("session", id)) => rule.sessions.push(id.to_string()),
+                    _ => return Err(invalid("subjects must be key:<hash> or session:<id>")),
+                }
+            }
+        }
+        Some(other) => return Err(invalid(&format!("unexpected '{}'", other))),
+    }
+
+    for condition in conditions.into_iter().flat_map(|c| c.split(" and ")) {
+        let condition = condition.trim();
+        let (arg, rest) = condition
+            .split_once(' ')
+            .ok_or_else(|| invalid(&format!("incomplete condition '{}'", condition)))?;
+        let (op, operand) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+        let operand = operand.trim();
+        let constraint = rule.args.entry(arg.to_string()).or_default();
+
+        match op {
+            "in" => {
+                let list = operand
+                    .strip_prefix('[')
+                    .and_then(|s| s.strip_suffix(']'))
+                    .ok_or_else(|| invalid("'in' expects a [list]"))?;
+                constraint.one_of = Some(
+                    list.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(|s| serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_string())))
+                        .collect(),
+                );
+            }
+            "under" => {
+                constraint.under = Some(operand.split(',').map(|s| s.trim().to_string()).collect());
+            }
+            "matches" => constraint.matches = Some(operand.to_string()),
+            other => return Err(invalid(&format!("unknown operator '{}'", other))),
+        }
+    }
+
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::KeyIdentity;
+    use serde_json::json;
+
+    fn policy(rules: Value) -> Policy {
+        let config: PolicyConfig =
+            serde_json::from_value(json!({ "enabled": true, "rules": rules })).unwrap();
+        Policy::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_argument_constraints() {
+        let policy = policy(json!([
+            "allow cmd.exec if command in [git] and args.0 in [status, log]",
+            { "effect": "allow", "tools": ["fs.write"], "args": { "path": { "under": ["/tmp"] } } },
+            "deny *"
+        ]));
+        let ctx = RequestContext::default();
+
+        assert!(policy.check("cmd.exec", &json!({"command": "git", "args": ["status"]}), &ctx).is_ok());
+        let err = policy.check("cmd.exec", &json!({"command": "rm", "args": ["status"]}), &ctx).unwrap_err();
+        assert!(err.contains("'command'"), "{}", err);
+
+        assert!(policy.check("fs.write", &json!({"path": "/tmp/out.txt"}), &ctx).is_ok());
+        assert!(policy.check("fs.write", &json!({"path": "/tmp/../etc/passwd"}), &ctx).is_err());
+
+        let err = policy.check("echo", &json!({}), &ctx).unwrap_err();
+        assert!(err.contains("denied by policy rule 'deny *'"), "{}", err);
+    }
+
+    #[test]
+    fn test_rules_scoped_to_api_keys() {
+        let policy = policy(json!([
+            "allow * for key:admin",
+            "deny cmd.exec"
+        ]));
+        let admin = RequestContext::default().with_api_key_hash(Some("admin".to_string()));
+        let other = RequestContext::default().with_api_key_hash(Some("other".to_string()));
+
+        assert!(policy.check("cmd.exec", &json!({}), &admin).is_ok());
+        assert!(policy.check("cmd.exec", &json!({}), &other).is_err());
+        assert!(policy.check("echo", &json!({}), &other).is_ok());
+
+        // Named keys match by name as well as by hash
+        let named = RequestContext::default().with_identity(Some(std::sync::Arc::new(KeyIdentity {
+            name: "admin".to_string(),
+            key_hash: "abc123".to_string(),
+            scopes: vec!["*".to_string()],
+        })));
+        assert!(policy.check("cmd.exec", &json!({}), &named).is_ok());
+    }
+
+    #[test]
+    fn test_default_deny_and_disabled() {
+        let mut config = PolicyConfig {
+            enabled: true,
+            default_effect: PolicyEffect::Deny,
+            rules: vec![PolicyRuleConfig::Line("allow echo".to_string())],
+        };
+        let policy = Policy::from_config(&config).unwrap();
+        assert!(policy.check("echo", &json!({}), &RequestContext::default()).is_ok());
+        assert!(policy.check("fs.read", &json!({}), &RequestContext::default()).is_err());
+
+        config.enabled = false;
+        let policy = Policy::from_config(&config).unwrap();
+        assert!(policy.check("fs.read", &json!({}), &RequestContext::default()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        for line in ["permit echo", "allow echo if path near /tmp", "allow echo for user:bob"] {
+            let config = PolicyConfig {
+                enabled: true,
+                rules: vec![PolicyRuleConfig::Line(line.to_string())],
+                ..Default::default()
+            };
+            assert!(Policy::from_config(&config).is_err(), "{}", line);
+        }
+    }
+}
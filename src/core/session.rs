@@ -0,0 +1,295 @@
+//! Per-connection session state.
+//!
+//! Each connection has a [`Session`]: stdio uses the default session, HTTP
+//! clients get one per `Mcp-Session-Id` (or per API key). The router looks
+//! the session up for every request and attaches it to the request's
+//! [`RequestContext`], so handlers and tools see the client, negotiated
+//! protocol version, namespace and identity of the connection that called
+//! them rather than whichever client connected last.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::context::{KeyIdentity, RequestContext, DEFAULT_SESSION};
+use crate::protocol::mcp::ClientInfo;
+
+/// Sessions idle for longer than this are dropped.
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// State of one client connection.
+#[derive(Debug)]
+pub struct Session {
+    id: String,
+    created_at: DateTime<Utc>,
+    last_seen: Mutex<Instant>,
+    client_info: RwLock<Option<ClientInfo>>,
+    protocol_version: RwLock<Option<String>>,
+    identity: RwLock<Option<Arc<KeyIdentity>>>,
+    namespace: RwLock<Option<String>>,
+    pinned_conversation: RwLock<Option<String>>,
+    roots: RwLock<Option<Vec<PathBuf>>>,
+    requests: AtomicU64,
+    tool_calls: AtomicU64,
+}
+
+/// Summary of a session, as shown by the dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub client: Option<ClientInfo>,
+    pub protocol_version: Option<String>,
+    pub key_name: Option<String>,
+    pub namespace: Option<String>,
+    pub pinned_conversation: Option<String>,
+    /// The client's roots, once fetched.
+    pub roots: Option<Vec<PathBuf>>,
+    pub requests: u64,
+    pub tool_calls: u64,
+    pub created_at: DateTime<Utc>,
+    pub idle_secs: u64,
+}
+
+impl Session {
+    /// Creates an uninitialized session.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            created_at: Utc::now(),
+            last_seen: Mutex::new(Instant::now()),
+            client_info: RwLock::new(None),
+            protocol_version: RwLock::new(None),
+            identity: RwLock::new(None),
+            namespace: RwLock::new(None),
+            pinned_conversation: RwLock::new(None),
+            roots: RwLock::new(None),
+            requests: AtomicU64::new(0),
+            tool_calls: AtomicU64::new(0),
+        }
+    }
+
+    /// The session ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether the client completed `initialize` on this session.
+    pub fn is_initialized(&self) -> bool {
+        self.protocol_version.read().is_some()
+    }
+
+    /// Records the outcome of `initialize`.
+    pub fn set_initialized(&self, client_info: ClientInfo, protocol_version: String) {
+        *self.client_info.write() = Some(client_info);
+        *self.protocol_version.write() = Some(protocol_version);
+    }
+
+    /// The client that initialized the session, if any.
+    pub fn client_info(&self) -> Option<ClientInfo> {
+        self.client_info.read().clone()
+    }
+
+    /// The protocol version negotiated at `initialize`.
+    pub fn protocol_version(&self) -> Option<String> {
+        self.protocol_version.read().clone()
+    }
+
+    /// The API key the session last authenticated with, if any.
+    pub fn identity(&self) -> Option<Arc<KeyIdentity>> {
+        self.identity.read().clone()
+    }
+
+    /// The session's memory namespace: the API key's name when the session
+    /// authenticated with one, otherwise the namespace the client asked for
+    /// at `initialize`.
+    pub fn namespace(&self) -> Option<String> {
+        match self.identity.read().as_ref() {
+            Some(identity) => Some(identity.name.clone()),
+            None => self.namespace.read().clone(),
+        }
+    }
+
+    /// Sets the namespace requested by the client.
+    pub fn set_namespace(&self, namespace: Option<String>) {
+        *self.namespace.write() = namespace;
+    }
+
+    /// The conversation pinned via initialize or `conversation.pin`.
+    pub fn pinned_conversation(&self) -> Option<String> {
+        self.pinned_conversation.read().clone()
+    }
+
+    /// Pins (or unpins, with `None`) the session's default conversation.
+    pub fn pin_conversation(&self, conversation_id: Option<String>) {
+        *self.pinned_conversation.write() = conversation_id;
+    }
+
+    /// The client's roots (canonical directories), or `None` if they
+    /// haven't been fetched since the session started or the client last
+    /// reported a change.
+    pub fn roots(&self) -> Option<Vec<PathBuf>> {
+        self.roots.read().clone()
+    }
+
+    /// Records the client's roots, or marks them stale with `None`.
+    pub fn set_roots(&self, roots: Option<Vec<PathBuf>>) {
+        *self.roots.write() = roots;
+    }
+
+    /// Counts a tool call made on the session.
+    pub fn record_tool_call(&self) {
+        self.tool_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time since the session's last request.
+    pub fn idle(&self) -> Duration {
+        self.last_seen.lock().elapsed()
+    }
+
+    /// Summarizes the session.
+    pub fn info(&self) -> SessionInfo {
+        SessionInfo {
+            id: self.id.clone(),
+            client: self.client_info(),
+            protocol_version: self.protocol_version(),
+            key_name: self.identity().map(|identity| identity.name.clone()),
+            namespace: self.namespace(),
+            pinned_conversation: self.pinned_conversation(),
+            roots: self.roots(),
+            requests: self.requests.load(Ordering::Relaxed),
+            tool_calls: self.tool_calls.load(Ordering::Relaxed),
+            created_at: self.created_at,
+            idle_secs: self.idle().as_secs(),
+        }
+    }
+
+    fn touch(&self, identity: Option<&Arc<KeyIdentity>>) {
+        *self.last_seen.lock() = Instant::now();
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(identity) = identity {
+            *self.identity.write() = Some(identity.clone());
+        }
+    }
+}
+
+/// All sessions, by ID.
+#[derive(Debug, Default)]
+pub struct Sessions {
+    sessions: DashMap<String, Arc<Session>>,
+}
+
+impl Sessions {
+    /// Creates an empty session table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a session, if it exists.
+    pub fn get(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.get(id).map(|session| session.clone())
+    }
+
+    /// Returns a session, creating it on first use.
+    pub fn get_or_create(&self, id: &str) -> Arc<Session> {
+        self.sessions
+            .entry(id.to_string())
+            .or_insert_with(|| Arc::new(Session::new(id)))
+            .clone()
+    }
+
+    /// Returns the session of a request, creating it on first use, and
+    /// records the request and the identity it authenticated with.
+    pub fn touch(&self, context: &RequestContext) -> Arc<Session> {
+        let session = self.get_or_create(&context.session_id);
+        session.touch(context.identity.as_ref());
+        session
+    }
+
+    /// Ends a session.
+    pub fn remove(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.remove(id).map(|(_, session)| session)
+    }
+
+    /// Drops sessions idle for longer than `max_idle` (never the default
+    /// session) and returns their IDs.
+    pub fn remove_idle(&self, max_idle: Duration) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.sessions.retain(|id, session| {
+            let keep = id == DEFAULT_SESSION || session.idle() <= max_idle;
+            if !keep {
+                removed.push(id.clone());
+            }
+            keep
+        });
+        removed
+    }
+
+    /// Summaries of all sessions, most recently active first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self.sessions.iter().map(|session| session.info()).collect();
+        sessions.sort_by_key(|session| session.idle_secs);
+        sessions
+    }
+
+    /// Number of sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Whether there are no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> Arc<KeyIdentity> {
+        Arc::new(KeyIdentity {
+            name: name.to_string(),
+            key_hash: format!("hash-{}", name),
+            scopes: vec!["*".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_sessions_are_separate() {
+        let sessions = Sessions::new();
+        let a = sessions.touch(&RequestContext::new("a"));
+        let b = sessions.touch(&RequestContext::new("b").with_identity(Some(key("agent-b"))));
+        sessions.touch(&RequestContext::new("a"));
+
+        a.set_initialized(ClientInfo { name: "client-a".into(), version: "1".into() }, "2025-03-26".into());
+        a.set_namespace(Some("team-a".into()));
+        b.set_namespace(Some("team-a".into()));
+        a.pin_conversation(Some("conv-a".into()));
+
+        assert!(a.is_initialized());
+        assert!(!b.is_initialized());
+        assert_eq!(a.namespace().as_deref(), Some("team-a"));
+        // An API key's name wins over the namespace the client asked for
+        assert_eq!(b.namespace().as_deref(), Some("agent-b"));
+        assert_eq!(b.pinned_conversation(), None);
+        assert_eq!(a.info().requests, 2);
+        assert_eq!(sessions.list().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_idle_keeps_default_session() {
+        let sessions = Sessions::new();
+        sessions.get_or_create(DEFAULT_SESSION);
+        sessions.get_or_create("http-1");
+
+        assert!(sessions.remove_idle(Duration::from_secs(60)).is_empty());
+        assert_eq!(sessions.remove_idle(Duration::ZERO), vec!["http-1".to_string()]);
+        assert!(sessions.get(DEFAULT_SESSION).is_some());
+        assert_eq!(sessions.len(), 1);
+    }
+}
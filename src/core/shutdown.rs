@@ -0,0 +1,143 @@
+//! Graceful shutdown coordination.
+//!
+//! On a shutdown signal the transports stop taking new work, running tool
+//! calls get until a deadline to finish, long-lived streams close with a
+//! final event, and then the scheduler, background processes and store are
+//! shut down in turn.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Tracks whether shutdown has begun and how many tool calls are running.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks a tool call as running until dropped.
+#[derive(Debug)]
+pub struct CallGuard<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for CallGuard<'_> {
+    fn drop(&mut self) {
+        if self.shutdown.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Creates a controller that hasn't been triggered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Begins shutdown. Idempotent.
+    pub fn trigger(&self) {
+        if !self.token.is_cancelled() {
+            info!("Shutting down");
+            self.token.cancel();
+        }
+    }
+
+    /// Whether shutdown has begun.
+    pub fn is_shutting_down(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolves once shutdown has begun.
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    /// A token fired when shutdown begins, for streams that must close.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Registers a running tool call.
+    pub fn begin_call(&self) -> CallGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        CallGuard { shutdown: self }
+    }
+
+    /// Number of tool calls currently running.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no tool calls are running or `deadline` passes.
+    /// Returns whether all calls finished.
+    pub async fn drain(&self, deadline: Duration) -> bool {
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(deadline, wait).await.is_ok()
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix.
+pub async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("Shutdown signal received");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_calls() {
+        let shutdown = Arc::new(Shutdown::new());
+        assert!(shutdown.drain(Duration::from_millis(10)).await);
+
+        let running = shutdown.clone();
+        let (started_tx, started) = tokio::sync::oneshot::channel();
+        let call = tokio::spawn(async move {
+            let _guard = running.begin_call();
+            started_tx.send(()).unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        started.await.unwrap();
+
+        shutdown.trigger();
+        assert!(shutdown.is_shutting_down());
+        assert!(!shutdown.drain(Duration::from_millis(1)).await);
+        assert!(shutdown.drain(Duration::from_secs(5)).await);
+        assert_eq!(shutdown.in_flight(), 0);
+        call.await.unwrap();
+    }
+}
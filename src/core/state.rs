@@ -1,15 +1,29 @@
 //! Runtime state management for Nexus.
 
-use crate::core::Config;
-use crate::memory::{MemoryStore, SqliteStore};
+use crate::core::events::EventBus;
+use crate::core::context::DEFAULT_SESSION;
+use crate::core::egress::EgressGuard;
+use crate::core::http_clients::HttpClients;
+use crate::core::http_cache::HttpCache;
+use crate::core::session::{Session, Sessions};
+use crate::core::subscriptions::ResourceSubscriptions;
+use crate::core::{Approvals, Config, Idempotency, Policy, RequestContext, Shutdown};
+use crate::memory::{Collections, MemoryError, MemoryStore, SqliteStore};
 use crate::protocol::mcp::{ResourcesCapability, ServerCapabilities, ServerInfo};
 use crate::scheduler::Scheduler;
 use crate::secrets::SecretsManager;
-use crate::tools::{register_core_tools, register_extra_tools, ToolRegistry};
+use crate::tools::file_watch::FileWatches;
+use crate::tools::middleware::MiddlewareChain;
+use crate::tools::{register_core_tools, register_extra_tools, ProcessTable, ToolRegistry};
+use crate::update::Release;
 use parking_lot::RwLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tracing::info;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// KV key the names of runtime-disabled tools are persisted under.
+pub const DISABLED_TOOLS_KEY: &str = "aegis:disabled_tools";
 
 /// Shared runtime state for the Nexus server.
 #[derive(Debug)]
@@ -29,14 +43,64 @@ pub struct RuntimeState {
     /// Tool registry for executing tools.
     pub tool_registry: RwLock<ToolRegistry>,
 
+    /// Middleware applied to client tool calls.
+    pub tool_middleware: MiddlewareChain,
+
+    /// Authorization policy checked before client tool calls.
+    pub policy: Policy,
+
+    /// Client tool calls waiting for a human to approve them.
+    pub approvals: Approvals,
+
+    /// Stored results of idempotent client tool calls.
+    pub idempotency: Idempotency,
+
     /// Memory store for persistent storage.
     pub memory_store: Arc<dyn MemoryStore>,
 
+    /// Schema-validated KV collections.
+    pub collections: Collections,
+
     /// Secrets manager for secure credential storage.
     pub secrets: Arc<SecretsManager>,
 
     /// Task scheduler for automated execution.
     pub scheduler: Arc<Scheduler>,
+
+    /// Background processes started via `process.start`.
+    pub processes: ProcessTable,
+
+    /// Shutdown state and running tool calls, for draining on exit.
+    pub shutdown: Shutdown,
+
+    /// Events from tool calls, tasks and memory, and the rules they trigger.
+    pub events: EventBus,
+
+    /// Cached responses of the HTTP and web tools.
+    pub http_cache: HttpCache,
+
+    /// A newer release found by the startup check.
+    pub available_update: RwLock<Option<Release>>,
+
+    /// Destinations the HTTP, web, notification and LLM tools may reach.
+    pub egress: EgressGuard,
+
+    /// Pooled HTTP clients of the HTTP, web, notification and LLM tools.
+    pub http_clients: HttpClients,
+
+    /// File watches created via `fs.watch`.
+    pub file_watches: FileWatches,
+
+    /// Resources clients subscribed to, and announcements of their changes.
+    pub resource_subscriptions: ResourceSubscriptions,
+
+    /// Per-connection state: client info, protocol version, namespace,
+    /// identity and pinned conversation.
+    pub sessions: Sessions,
+
+    /// Bumped whenever the tool list changes, so transports can send
+    /// `notifications/tools/list_changed`.
+    tools_changed: watch::Sender<u64>,
 }
 
 impl RuntimeState {
@@ -51,8 +115,8 @@ impl RuntimeState {
         let mut tool_registry = ToolRegistry::new();
         
         // Always register core tools
-        register_core_tools(&mut tool_registry, &config);
-        info!("Loaded {} core tools", crate::tools::core::core_tool_count());
+        let core_tools = register_core_tools(&mut tool_registry, &config);
+        info!("Loaded {} core tools", core_tools);
         
         // Register extra tools if enabled
         if config.extras_enabled {
@@ -61,39 +125,97 @@ impl RuntimeState {
             info!("Extra tools disabled (enable with extras_enabled: true in config)");
         }
 
+        if !config.aliases.is_empty() {
+            tool_registry.set_aliases(config.aliases.clone(), config.hide_aliased_tools);
+            info!("Exposing {} tool alias(es)", config.aliases.len());
+        }
+
+        tool_registry.set_internal(config.composite_tools.iter().flat_map(|c| c.hide_tools.iter().cloned()));
+
+        let tool_middleware = MiddlewareChain::from_config(&config);
+        if !tool_middleware.is_empty() {
+            info!("Loaded {} tool middleware", tool_middleware.len());
+        }
+
+        let policy = match Policy::from_config(&config.policy) {
+            Ok(policy) => policy,
+            Err(e) => {
+                // Fail closed rather than run without the intended restrictions
+                tracing::error!("Invalid policy configuration, denying all tool calls: {}", e);
+                Policy::deny_all()
+            }
+        };
+        if policy.is_enabled() {
+            info!("Tool policy enabled ({} rules)", config.policy.rules.len());
+        }
+
+        let approvals = Approvals::from_config(&config.approvals);
+        if approvals.is_enabled() {
+            info!("Calls to {} need approval", config.approvals.tools.join(", "));
+        }
+        let idempotency = Idempotency::from_config(&config.idempotency);
+
         // Create memory store
-        let db_path = config.database_path.clone().unwrap_or_else(|| "aegis.db".to_string());
-        let memory_store: Arc<dyn MemoryStore> = match SqliteStore::new(&db_path) {
+        let memory_store: Arc<dyn MemoryStore> = match crate::memory::open_store(&config) {
             Ok(store) => {
-                info!("Memory store initialized at: {}", db_path);
-                Arc::new(store)
+                match &config.database_url {
+                    Some(url) => info!("Memory store initialized at: {}", crate::memory::redact_url(url)),
+                    None => info!(
+                        "Memory store initialized at: {}",
+                        config.database_path.as_deref().unwrap_or("aegis.db")
+                    ),
+                }
+                if let Some(url) = config.redis.url.as_ref().filter(|_| config.redis.kv) {
+                    info!("Key-value data stored in Redis at: {}", crate::memory::redact_url(url));
+                }
+                store
             }
             Err(e) => {
-                tracing::warn!("Failed to create SQLite store: {}, using in-memory", e);
+                tracing::warn!("Failed to open memory store: {}, using in-memory", e);
                 Arc::new(SqliteStore::in_memory().expect("Failed to create in-memory store"))
             }
         };
 
-        // Create secrets manager
-        let secrets_path = config
-            .database_path
-            .as_ref()
-            .map(|p| p.replace(".db", ".secrets"));
+        let collections = Collections::from_config(&config.collections);
+        if !collections.is_empty() {
+            info!("Loaded {} KV collections", collections.len());
+        }
+
+        // Create secrets manager (in-memory databases keep secrets in memory too)
+        let secrets_path = crate::secrets::file_for_database(config.database_path.as_deref());
         let secrets = Arc::new(SecretsManager::new(secrets_path, None));
+        crate::secrets::scrub::install(&secrets);
+        for warning in secrets.expiring(chrono::Utc::now()) {
+            if warning.expired {
+                tracing::warn!("Secret {} expired on {}; rotate it with secrets.rotate", warning.key, warning.expires_at);
+            } else {
+                tracing::warn!("Secret {} expires in {} days ({})", warning.key, warning.days_left, warning.expires_at);
+            }
+        }
         info!("Secrets manager initialized");
 
+        // The response cache lives next to the database (in memory for in-memory databases)
+        let cache_path = crate::memory::companion_file(config.database_path.as_deref(), "cache");
+        let http_cache = HttpCache::new(cache_path, config.http_client.cache.clone());
+        let egress = EgressGuard::from_config(&config);
+        let http_clients = HttpClients::new(egress.clone(), &config.http_client);
+
         // Create scheduler
-        let scheduler = Arc::new(Scheduler::new());
-        info!("Scheduler initialized");
+        let scheduler = Arc::new(Scheduler::with_timezone(config.timezone()));
+        info!("Scheduler initialized (timezone: {})", scheduler.timezone());
+
+        let processes = ProcessTable::new(config.security.max_background_processes);
+        let file_watches = FileWatches::new(config.security.max_file_watches);
 
         // Build capabilities with resources enabled
         let capabilities = ServerCapabilities {
-            tools: Some(crate::protocol::mcp::ToolsCapability { list_changed: false }),
+            tools: Some(crate::protocol::mcp::ToolsCapability { list_changed: true }),
             prompts: Some(crate::protocol::mcp::PromptsCapability { list_changed: false }),
             resources: Some(ResourcesCapability {
-                subscribe: false,
+                subscribe: true,
                 list_changed: false,
             }),
+            completions: Some(serde_json::json!({})),
         };
 
         Self {
@@ -102,9 +224,25 @@ impl RuntimeState {
             capabilities,
             server_info,
             tool_registry: RwLock::new(tool_registry),
+            tool_middleware,
+            policy,
+            approvals,
+            idempotency,
             memory_store,
+            collections,
             secrets,
             scheduler,
+            processes,
+            shutdown: Shutdown::new(),
+            events: EventBus::new(),
+            http_cache,
+            available_update: RwLock::new(None),
+            egress,
+            http_clients,
+            file_watches,
+            resource_subscriptions: ResourceSubscriptions::new(),
+            sessions: Sessions::new(),
+            tools_changed: watch::Sender::new(0),
         }
     }
 
@@ -118,6 +256,82 @@ impl RuntimeState {
         self.initialized.store(true, Ordering::SeqCst);
     }
 
+    /// Returns the session of a request (the default session if the
+    /// router hasn't attached one).
+    pub fn session(&self, context: &RequestContext) -> Arc<Session> {
+        context
+            .session
+            .clone()
+            .unwrap_or_else(|| self.sessions.get_or_create(&context.session_id))
+    }
+
+    /// Returns the session of the running tool call, or the default session
+    /// outside client calls (e.g. scheduled tasks).
+    pub fn current_session(&self) -> Arc<Session> {
+        crate::tools::caller::session().unwrap_or_else(|| self.sessions.get_or_create(DEFAULT_SESSION))
+    }
+
+    /// Returns the conversation pinned for the current session, if any.
+    pub fn pinned_conversation(&self) -> Option<String> {
+        self.current_session().pinned_conversation()
+    }
+
+    /// Pins (or unpins, with `None`) the current session's default
+    /// conversation scope.
+    pub fn pin_conversation(&self, conversation_id: Option<String>) {
+        self.current_session().pin_conversation(conversation_id);
+    }
+
+    /// Tells connected clients that the tool list changed.
+    pub fn notify_tools_changed(&self) {
+        self.tools_changed.send_modify(|version| *version += 1);
+    }
+
+    /// Subscribes to tool list changes.
+    pub fn subscribe_tools_changed(&self) -> watch::Receiver<u64> {
+        self.tools_changed.subscribe()
+    }
+
+    /// Enables or disables a tool at runtime, persisting the choice in the
+    /// memory store. Returns whether anything changed.
+    pub async fn set_tool_enabled(&self, name: &str, enabled: bool) -> Result<bool, MemoryError> {
+        let (changed, disabled) = {
+            let mut registry = self.tool_registry.write();
+            let changed = if enabled { registry.enable(name) } else { registry.disable(name) };
+            (changed, registry.disabled())
+        };
+        if !changed {
+            return Ok(false);
+        }
+
+        info!("Tool {} {}", name, if enabled { "enabled" } else { "disabled" });
+        self.notify_tools_changed();
+        self.memory_store
+            .kv_set(DISABLED_TOOLS_KEY, serde_json::json!(disabled), None)
+            .await?;
+        Ok(true)
+    }
+
+    /// Disables the tools persisted as disabled by an earlier run.
+    pub async fn restore_disabled_tools(&self) {
+        let disabled = match self.memory_store.kv_get(DISABLED_TOOLS_KEY).await {
+            Ok(Some(entry)) => serde_json::from_value::<Vec<String>>(entry.value).unwrap_or_default(),
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load disabled tools: {}", e);
+                return;
+            }
+        };
+
+        let mut registry = self.tool_registry.write();
+        for name in &disabled {
+            registry.disable(name);
+        }
+        if !disabled.is_empty() {
+            info!("Disabled {} tool(s): {}", disabled.len(), disabled.join(", "));
+        }
+    }
+
     /// Creates a shared reference to the runtime state.
     pub fn into_arc(self) -> Arc<Self> {
         Arc::new(self)
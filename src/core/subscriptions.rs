@@ -0,0 +1,76 @@
+//! Resource subscriptions.
+//!
+//! Clients subscribe to resource URIs with `resources/subscribe`; when a
+//! resource changes, transports send `notifications/resources/updated` to
+//! the sessions subscribed to it.
+
+use dashmap::DashMap;
+use std::collections::HashSet;
+use tokio::sync::broadcast;
+
+/// Updates kept for slow transports before the oldest are dropped.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Which sessions are subscribed to which resources.
+#[derive(Debug)]
+pub struct ResourceSubscriptions {
+    sessions: DashMap<String, HashSet<String>>,
+    updates: broadcast::Sender<String>,
+}
+
+impl Default for ResourceSubscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceSubscriptions {
+    /// Creates an empty subscription table.
+    pub fn new() -> Self {
+        Self {
+            sessions: DashMap::new(),
+            updates: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Subscribes a session to a resource.
+    pub fn subscribe(&self, session_id: &str, uri: &str) {
+        self.sessions.entry(session_id.to_string()).or_default().insert(uri.to_string());
+    }
+
+    /// Unsubscribes a session from a resource. Returns whether it was subscribed.
+    pub fn unsubscribe(&self, session_id: &str, uri: &str) -> bool {
+        self.sessions
+            .get_mut(session_id)
+            .is_some_and(|mut uris| uris.remove(uri))
+    }
+
+    /// Drops every subscription of a session that has ended.
+    pub fn remove_session(&self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+
+    /// Whether a session should be told about changes to `uri`.
+    pub fn is_subscribed(&self, session_id: &str, uri: &str) -> bool {
+        self.sessions
+            .get(session_id)
+            .is_some_and(|uris| uris.contains(uri))
+    }
+
+    /// Sender for resource changes, for producers that outlive a borrow of
+    /// the state (e.g. file watcher threads).
+    pub fn sender(&self) -> broadcast::Sender<String> {
+        self.updates.clone()
+    }
+
+    /// Announces that a resource changed.
+    pub fn notify_updated(&self, uri: &str) {
+        // No receivers just means no transport is listening
+        let _ = self.updates.send(uri.to_string());
+    }
+
+    /// Receives the URIs of resources changed from now on.
+    pub fn updates(&self) -> broadcast::Receiver<String> {
+        self.updates.subscribe()
+    }
+}
@@ -1,15 +1,24 @@
 //! Web dashboard for Nexus monitoring and management.
 
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
+    http::StatusCode,
     response::Html,
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::core::RuntimeState;
+use crate::core::http_cache::CacheStats;
+use crate::core::logs::{self, LogEntry, LogQuery};
+use crate::core::approvals::{ApprovalRequest, Decision};
+use crate::core::{RuntimeState, SessionInfo};
+use crate::memory::{build_analytics, parse_since, parse_window, Analytics, TaskRun};
+use crate::scheduler::TASK_HISTORY_LIMIT;
+use crate::secrets::ExpiryWarning;
+use crate::tools::middleware::{month_start, usage_report, UsageGrouping};
+use crate::update::Release;
 
 /// Dashboard routes.
 pub fn dashboard_routes(state: Arc<RuntimeState>) -> Router {
@@ -17,9 +26,21 @@ pub fn dashboard_routes(state: Arc<RuntimeState>) -> Router {
         .route("/", get(dashboard_page))
         .route("/api/stats", get(stats_api))
         .route("/api/tools", get(tools_api))
+        .route("/api/tools/:name/enable", post(enable_tool_api))
+        .route("/api/tools/:name/disable", post(disable_tool_api))
         .route("/api/memory", get(memory_api))
         .route("/api/secrets", get(secrets_api))
         .route("/api/tasks", get(tasks_api))
+        .route("/api/tasks/:id/runs", get(task_runs_api))
+        .route("/api/sessions", get(sessions_api))
+        .route("/api/approvals", get(approvals_api))
+        .route("/api/approvals/:id/approve", post(approve_api))
+        .route("/api/approvals/:id/reject", post(reject_api))
+        .route("/api/usage", get(usage_api))
+        .route("/api/cache", get(cache_api))
+        .route("/api/cache/clear", post(clear_cache_api))
+        .route("/api/logs", get(logs_api))
+        .route("/api/analytics", get(analytics_api))
         .with_state(state)
 }
 
@@ -36,7 +57,10 @@ struct StatsResponse {
     tools_count: usize,
     secrets_count: usize,
     tasks_count: usize,
+    sessions_count: usize,
     initialized: bool,
+    /// A newer release, if the startup check found one.
+    update: Option<Release>,
 }
 
 /// Stats API handler.
@@ -47,7 +71,9 @@ async fn stats_api(State(state): State<Arc<RuntimeState>>) -> Json<StatsResponse
         tools_count: state.tool_registry.read().list_definitions().len(),
         secrets_count: state.secrets.list().len(),
         tasks_count: state.scheduler.list_tasks().len(),
+        sessions_count: state.sessions.len(),
         initialized: state.is_initialized(),
+        update: state.available_update.read().clone(),
     })
 }
 
@@ -56,24 +82,63 @@ async fn stats_api(State(state): State<Arc<RuntimeState>>) -> Json<StatsResponse
 struct ToolInfo {
     name: String,
     description: Option<String>,
+    enabled: bool,
 }
 
-/// Tools API handler.
+/// Tools API handler: every registered tool, disabled ones included.
 async fn tools_api(State(state): State<Arc<RuntimeState>>) -> Json<Vec<ToolInfo>> {
-    let tools = state
-        .tool_registry
-        .read()
-        .list_definitions()
+    let registry = state.tool_registry.read();
+    let tools = registry
+        .names()
         .into_iter()
-        .map(|def| ToolInfo {
-            name: def.name,
-            description: def.description,
+        .filter_map(|name| {
+            let def = registry.tools.get(&name)?.definition();
+            Some(ToolInfo {
+                enabled: !registry.is_disabled(&name),
+                name,
+                description: def.description,
+            })
         })
         .collect();
 
     Json(tools)
 }
 
+/// Enables a tool at runtime.
+async fn enable_tool_api(
+    State(state): State<Arc<RuntimeState>>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    set_tool_enabled(&state, &name, true).await
+}
+
+/// Disables a tool at runtime.
+async fn disable_tool_api(
+    State(state): State<Arc<RuntimeState>>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    set_tool_enabled(&state, &name, false).await
+}
+
+async fn set_tool_enabled(state: &RuntimeState, name: &str, enabled: bool) -> (StatusCode, Json<serde_json::Value>) {
+    if !state.tool_registry.read().tools.contains_key(name) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("Tool not found: {}", name) })),
+        );
+    }
+    match state.set_tool_enabled(name, enabled).await {
+        Ok(changed) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "name": name, "enabled": enabled, "changed": changed })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
 /// Memory stats for API.
 #[derive(Serialize)]
 struct MemoryStats {
@@ -90,12 +155,17 @@ async fn memory_api(State(state): State<Arc<RuntimeState>>) -> Json<MemoryStats>
 #[derive(Serialize)]
 struct SecretsStats {
     keys: Vec<String>,
+    /// Secrets that expire soon or have expired.
+    expiring: Vec<ExpiryWarning>,
 }
 
 /// Secrets API handler.
 async fn secrets_api(State(state): State<Arc<RuntimeState>>) -> Json<SecretsStats> {
+    let mut keys = state.secrets.list();
+    keys.sort();
     Json(SecretsStats {
-        keys: state.secrets.list(),
+        keys,
+        expiring: state.secrets.expiring(chrono::Utc::now()),
     })
 }
 
@@ -104,10 +174,11 @@ async fn secrets_api(State(state): State<Arc<RuntimeState>>) -> Json<SecretsStat
 struct TaskInfo {
     id: String,
     name: String,
-    cron: String,
+    schedule: String,
     tool: String,
     enabled: bool,
     last_run: Option<String>,
+    next_run: Option<String>,
 }
 
 /// Tasks API handler.
@@ -119,16 +190,161 @@ async fn tasks_api(State(state): State<Arc<RuntimeState>>) -> Json<Vec<TaskInfo>
         .map(|t| TaskInfo {
             id: t.id.clone(),
             name: t.name.clone(),
-            cron: t.cron.clone(),
+            schedule: t.schedule.to_string(),
             tool: t.tool.clone(),
             enabled: t.enabled,
             last_run: t.last_run.clone(),
+            next_run: t.next_run.map(|next_run| next_run.to_rfc3339()),
         })
         .collect();
 
     Json(tasks)
 }
 
+/// Task history API handler: a task's recorded runs, newest first.
+async fn task_runs_api(
+    State(state): State<Arc<RuntimeState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<TaskRun>>, (StatusCode, Json<serde_json::Value>)> {
+    state
+        .memory_store
+        .list_task_runs(&id, TASK_HISTORY_LIMIT)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))))
+}
+
+/// Approvals API handler: tool calls waiting for approval, oldest first.
+async fn approvals_api(State(state): State<Arc<RuntimeState>>) -> Json<Vec<ApprovalRequest>> {
+    Json(state.approvals.list())
+}
+
+/// Approves a pending tool call.
+async fn approve_api(
+    State(state): State<Arc<RuntimeState>>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    decide_approval(&state, &id, Decision::Approved)
+}
+
+/// Rejects a pending tool call.
+async fn reject_api(
+    State(state): State<Arc<RuntimeState>>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    decide_approval(&state, &id, Decision::Rejected(Some("rejected from the dashboard".to_string())))
+}
+
+fn decide_approval(state: &RuntimeState, id: &str, decision: Decision) -> (StatusCode, Json<serde_json::Value>) {
+    let approved = decision == Decision::Approved;
+    match state.approvals.decide(id, decision) {
+        Some(request) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "id": request.id, "tool": request.tool, "approved": approved })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("No pending approval: {}", id) })),
+        ),
+    }
+}
+
+/// Sessions API handler: connected clients, most recently active first.
+async fn sessions_api(State(state): State<Arc<RuntimeState>>) -> Json<Vec<SessionInfo>> {
+    Json(state.sessions.list())
+}
+
+/// LLM usage API handler: month-to-date usage by model.
+async fn usage_api(State(state): State<Arc<RuntimeState>>) -> Json<serde_json::Value> {
+    let now = chrono::Utc::now();
+    let report = usage_report(
+        state.memory_store.as_ref(),
+        &state.config.llm.usage,
+        Some(month_start(now)),
+        UsageGrouping::Model,
+        None,
+        now,
+    )
+    .await
+    .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
+    Json(report)
+}
+
+/// HTTP cache API handler.
+async fn cache_api(State(state): State<Arc<RuntimeState>>) -> Json<CacheStats> {
+    Json(state.http_cache.stats())
+}
+
+/// Removes every cached HTTP response.
+async fn clear_cache_api(State(state): State<Arc<RuntimeState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "removed": state.http_cache.clear(None) }))
+}
+
+/// Query parameters for the logs API.
+#[derive(Debug, Deserialize)]
+struct LogsParams {
+    /// Least severe level to include.
+    level: Option<String>,
+    /// Text the entries must contain.
+    filter: Option<String>,
+    /// Only entries after this sequence number.
+    after: Option<u64>,
+    #[serde(default = "default_logs_limit")]
+    limit: usize,
+}
+
+fn default_logs_limit() -> usize { 200 }
+
+/// Recent server logs API handler.
+async fn logs_api(Query(params): Query<LogsParams>) -> Result<Json<Vec<LogEntry>>, (StatusCode, Json<serde_json::Value>)> {
+    let level = match params.level.as_deref() {
+        Some(level) => Some(level.parse().map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("Unknown log level: {}", level) })),
+            )
+        })?),
+        None => None,
+    };
+    Ok(Json(logs::buffer().query(&LogQuery {
+        level,
+        filter: params.filter,
+        after: params.after,
+        limit: params.limit,
+    })))
+}
+
+/// Query parameters for the analytics API.
+#[derive(Debug, Deserialize)]
+struct AnalyticsParams {
+    /// Window (e.g. `24h`) or start date; default: the last 24 hours.
+    since: Option<String>,
+    /// Width of the time series buckets (e.g. `1h`); default: one hour.
+    bucket: Option<String>,
+    /// Only this tool's calls.
+    tool: Option<String>,
+}
+
+/// Tool usage analytics API handler: per-tool latency, error rates and
+/// argument sizes, and a time series of the same.
+async fn analytics_api(
+    State(state): State<Arc<RuntimeState>>,
+    Query(params): Query<AnalyticsParams>,
+) -> Result<Json<Analytics>, (StatusCode, Json<serde_json::Value>)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error })));
+    let now = chrono::Utc::now();
+    let since = parse_since(params.since.as_deref().unwrap_or("24h"), now).map_err(bad_request)?;
+    let bucket = parse_window(params.bucket.as_deref().unwrap_or("1h")).map_err(bad_request)?;
+    if bucket <= chrono::Duration::zero() {
+        return Err(bad_request("bucket must be positive".to_string()));
+    }
+
+    build_analytics(state.memory_store.as_ref(), Some(since), bucket, params.tool.as_deref())
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))))
+}
+
 /// Embedded dashboard HTML.
 const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
 <html lang="en">
@@ -349,6 +565,44 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             color: var(--error);
         }
         
+        .tag.warning {
+            background: rgba(245, 158, 11, 0.2);
+            color: var(--warning);
+        }
+        
+        .update-banner {
+            display: none;
+            margin-bottom: 1.5rem;
+            padding: 0.75rem 1.25rem;
+            border-radius: 0.5rem;
+            background: rgba(245, 158, 11, 0.2);
+            color: var(--warning);
+        }
+        
+        .update-banner a {
+            color: var(--warning);
+        }
+        
+        .task-runs {
+            padding: 0.5rem 1.5rem 1rem 3rem;
+            border-bottom: 1px solid var(--border);
+            background: var(--bg-secondary);
+        }
+        
+        .task-run {
+            padding: 0.5rem 0;
+            font-size: 0.875rem;
+            color: var(--text-secondary);
+        }
+        
+        .task-run pre {
+            margin-top: 0.25rem;
+            white-space: pre-wrap;
+            word-break: break-word;
+            max-height: 12rem;
+            overflow: auto;
+        }
+        
         .empty-state {
             text-align: center;
             padding: 3rem;
@@ -407,6 +661,8 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             </div>
         </header>
         
+        <div class="update-banner" id="update-banner"></div>
+        
         <div class="grid" id="stats-grid">
             <div class="card">
                 <div class="card-header">
@@ -436,6 +692,20 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 </div>
                 <div class="card-value" id="memory-count">-</div>
             </div>
+            <div class="card">
+                <div class="card-header">
+                    <div class="card-icon">💸</div>
+                    <span class="card-title">LLM Spend (Month)</span>
+                </div>
+                <div class="card-value" id="llm-spend">-</div>
+            </div>
+            <div class="card">
+                <div class="card-header">
+                    <div class="card-icon">🗄️</div>
+                    <span class="card-title">HTTP Cache Hit Rate</span>
+                </div>
+                <div class="card-value" id="cache-hit-rate">-</div>
+            </div>
         </div>
         
         <div class="section">
@@ -447,6 +717,15 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             </div>
         </div>
         
+        <div class="section">
+            <div class="section-header">
+                <h2 class="section-title">✋ Pending Approvals</h2>
+            </div>
+            <div class="list" id="approvals-list">
+                <div class="loading"><div class="spinner"></div></div>
+            </div>
+        </div>
+        
         <div class="section">
             <div class="section-header">
                 <h2 class="section-title">⏰ Scheduled Tasks</h2>
@@ -456,6 +735,27 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             </div>
         </div>
         
+        <div class="section">
+            <div class="section-header">
+                <h2 class="section-title">💸 LLM Usage This Month</h2>
+                <span class="tag" id="llm-budget">No budget</span>
+            </div>
+            <div class="list" id="usage-list">
+                <div class="loading"><div class="spinner"></div></div>
+            </div>
+        </div>
+        
+        <div class="section">
+            <div class="section-header">
+                <h2 class="section-title">🗄️ HTTP Cache</h2>
+                <span class="tag" style="cursor: pointer" title="Remove all cached responses"
+                      onclick="clearCache()">Clear</span>
+            </div>
+            <div class="list" id="cache-list">
+                <div class="loading"><div class="spinner"></div></div>
+            </div>
+        </div>
+        
         <div class="section">
             <div class="section-header">
                 <h2 class="section-title">🔐 Stored Secrets</h2>
@@ -465,6 +765,16 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             </div>
         </div>
         
+        <div class="section">
+            <div class="section-header">
+                <h2 class="section-title">📜 Recent Logs</h2>
+                <span class="tag">Warnings and errors</span>
+            </div>
+            <div class="list" id="logs-list">
+                <div class="loading"><div class="spinner"></div></div>
+            </div>
+        </div>
+        
         <footer>
             <p>Nexus MCP Runtime &bull; <a href="https://github.com/your-org/nexus">GitHub</a></p>
         </footer>
@@ -481,6 +791,12 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 document.getElementById('tasks-count').textContent = stats.tasks_count;
                 document.getElementById('status-text').textContent = 
                     stats.initialized ? 'Initialized' : 'Running';
+                const banner = document.getElementById('update-banner');
+                if (stats.update) {
+                    banner.innerHTML = `Aegis ${escapeHtml(stats.update.version)} is available. ` +
+                        `Run <code>aegis self-update</code> to install it. <a href="${escapeHtml(stats.update.url)}" target="_blank" rel="noopener">Release notes</a>`;
+                    banner.style.display = 'block';
+                }
                 
                 // Fetch memory
                 const memoryRes = await fetch('/dashboard/api/memory');
@@ -492,15 +808,35 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 const tools = await toolsRes.json();
                 renderTools(tools);
                 
+                // Fetch calls waiting for approval
+                const approvalsRes = await fetch('/dashboard/api/approvals');
+                const approvals = await approvalsRes.json();
+                renderApprovals(approvals);
+                
                 // Fetch tasks
                 const tasksRes = await fetch('/dashboard/api/tasks');
                 const tasks = await tasksRes.json();
                 renderTasks(tasks);
                 
+                // Fetch LLM usage
+                const usageRes = await fetch('/dashboard/api/usage');
+                const usage = await usageRes.json();
+                renderUsage(usage);
+                
+                // Fetch HTTP cache stats
+                const cacheRes = await fetch('/dashboard/api/cache');
+                const cache = await cacheRes.json();
+                renderCache(cache);
+                
                 // Fetch secrets
                 const secretsRes = await fetch('/dashboard/api/secrets');
                 const secrets = await secretsRes.json();
                 renderSecrets(secrets);
+                
+                // Fetch recent warnings and errors
+                const logsRes = await fetch('/dashboard/api/logs?level=warn&limit=20');
+                const logs = await logsRes.json();
+                renderLogs(logs);
             } catch (error) {
                 console.error('Failed to fetch data:', error);
             }
@@ -518,10 +854,52 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                         <div class="list-item-name">${tool.name}</div>
                         <div class="list-item-desc">${tool.description || 'No description'}</div>
                     </div>
+                    <span class="tag ${tool.enabled ? 'enabled' : 'disabled'}" style="cursor: pointer"
+                          title="Click to ${tool.enabled ? 'disable' : 'enable'}"
+                          onclick="toggleTool('${tool.name}', ${tool.enabled})">
+                        ${tool.enabled ? 'Enabled' : 'Disabled'}
+                    </span>
+                </div>
+            `).join('');
+        }
+        
+        async function toggleTool(name, enabled) {
+            const action = enabled ? 'disable' : 'enable';
+            await fetch(`/dashboard/api/tools/${encodeURIComponent(name)}/${action}`, { method: 'POST' });
+            fetchData();
+        }
+        
+        function renderApprovals(approvals) {
+            const list = document.getElementById('approvals-list');
+            if (approvals.length === 0) {
+                list.innerHTML = '<div class="empty-state">No calls waiting for approval</div>';
+                return;
+            }
+            list.innerHTML = approvals.map(request => `
+                <div class="list-item">
+                    <div>
+                        <div class="list-item-name">${request.tool}</div>
+                        <div class="list-item-desc">${escapeHtml(JSON.stringify(request.arguments))}</div>
+                        <div class="list-item-desc">
+                            ${escapeHtml(request.api_key_name || request.session_id)} &bull;
+                            ${new Date(request.requested_at).toLocaleString()}
+                        </div>
+                    </div>
+                    <div>
+                        <span class="tag enabled" style="cursor: pointer"
+                              onclick="decideApproval('${request.id}', 'approve')">Approve</span>
+                        <span class="tag disabled" style="cursor: pointer"
+                              onclick="decideApproval('${request.id}', 'reject')">Reject</span>
+                    </div>
                 </div>
             `).join('');
         }
         
+        async function decideApproval(id, action) {
+            await fetch(`/dashboard/api/approvals/${encodeURIComponent(id)}/${action}`, { method: 'POST' });
+            fetchData();
+        }
+        
         function renderTasks(tasks) {
             const list = document.getElementById('tasks-list');
             if (tasks.length === 0) {
@@ -529,15 +907,117 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 return;
             }
             list.innerHTML = tasks.map(task => `
-                <div class="list-item">
+                <div class="list-item" style="cursor: pointer" title="Show run history"
+                     onclick="toggleTaskRuns('${task.id}')">
                     <div>
-                        <div class="list-item-name">${task.name}</div>
-                        <div class="list-item-desc">${task.cron} → ${task.tool}</div>
+                        <div class="list-item-name">${escapeHtml(task.name)}</div>
+                        <div class="list-item-desc">${escapeHtml(task.schedule)} → ${task.tool}</div>
                     </div>
                     <span class="tag ${task.enabled ? 'enabled' : 'disabled'}">
                         ${task.enabled ? 'Enabled' : 'Disabled'}
                     </span>
                 </div>
+                ${task.id === expandedTask ? `<div class="task-runs" id="task-runs"></div>` : ''}
+            `).join('');
+            if (expandedTask) {
+                loadTaskRuns(expandedTask);
+            }
+        }
+        
+        let expandedTask = null;
+        
+        function toggleTaskRuns(id) {
+            expandedTask = expandedTask === id ? null : id;
+            fetchData();
+        }
+        
+        async function loadTaskRuns(id) {
+            const res = await fetch(`/dashboard/api/tasks/${encodeURIComponent(id)}/runs`);
+            const runs = await res.json();
+            const panel = document.getElementById('task-runs');
+            if (!panel) {
+                return;
+            }
+            if (!Array.isArray(runs) || runs.length === 0) {
+                panel.innerHTML = '<div class="task-run">No runs yet</div>';
+                return;
+            }
+            panel.innerHTML = runs.map(run => `
+                <div class="task-run">
+                    <span class="tag ${run.success ? 'enabled' : 'disabled'}">${run.success ? 'OK' : 'Failed'}</span>
+                    ${new Date(run.started_at).toLocaleString()} &bull; ${run.duration_ms}ms
+                    <pre>${escapeHtml(run.success ? (run.output || '') : (run.error || ''))}</pre>
+                </div>
+            `).join('');
+        }
+        
+        function renderCache(cache) {
+            document.getElementById('cache-hit-rate').textContent = Math.round(cache.hit_ratio * 100) + '%';
+            const rows = [
+                ['Entries', `${cache.entries} (${(cache.size_bytes / 1024).toFixed(1)} KiB)`],
+                ['Hits', cache.hits],
+                ['Revalidated', cache.revalidated],
+                ['Misses', cache.misses],
+            ];
+            document.getElementById('cache-list').innerHTML = rows.map(([name, value]) => `
+                <div class="list-item">
+                    <div class="list-item-name">${name}</div>
+                    <span class="tag">${value}</span>
+                </div>
+            `).join('');
+        }
+        
+        function renderLogs(logs) {
+            const list = document.getElementById('logs-list');
+            if (logs.length === 0) {
+                list.innerHTML = '<div class="empty-state">No recent warnings or errors</div>';
+                return;
+            }
+            list.innerHTML = logs.reverse().map(entry => `
+                <div class="list-item">
+                    <div>
+                        <div class="list-item-name">${escapeHtml(entry.message)}</div>
+                        <div class="list-item-desc">${new Date(entry.timestamp).toLocaleString()} &bull; ${entry.target}</div>
+                    </div>
+                    <span class="tag ${entry.level === 'ERROR' ? 'disabled' : ''}">${entry.level}</span>
+                </div>
+            `).join('');
+        }
+        
+        function escapeHtml(text) {
+            const div = document.createElement('div');
+            div.textContent = text;
+            return div.innerHTML;
+        }
+        
+        async function clearCache() {
+            await fetch('/dashboard/api/cache/clear', { method: 'POST' });
+            fetchData();
+        }
+        
+        function renderUsage(usage) {
+            const list = document.getElementById('usage-list');
+            if (usage.error) {
+                list.innerHTML = `<div class="empty-state">${usage.error}</div>`;
+                return;
+            }
+            const budget = usage.budget;
+            document.getElementById('llm-spend').textContent = '$' + budget.month_to_date_usd.toFixed(2);
+            document.getElementById('llm-budget').textContent = budget.monthly_budget_usd == null
+                ? 'No budget'
+                : `$${budget.remaining_usd.toFixed(2)} of $${budget.monthly_budget_usd.toFixed(2)} left`;
+            if (usage.groups.length === 0) {
+                list.innerHTML = '<div class="empty-state">No LLM calls this month</div>';
+                return;
+            }
+            list.innerHTML = usage.groups.map(group => `
+                <div class="list-item">
+                    <div>
+                        <div class="list-item-name">${group.name}</div>
+                        <div class="list-item-desc">${group.calls} calls &bull; ${group.input_tokens} in / ${group.output_tokens} out tokens</div>
+                    </div>
+                    <span class="tag">${group.unpriced_calls === group.calls ? 'unpriced' : '$' + group.cost_usd.toFixed(4)}</span>
+                </div>
             `).join('');
         }
         
@@ -547,12 +1027,19 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 list.innerHTML = '<div class="empty-state">No secrets stored</div>';
                 return;
             }
-            list.innerHTML = secrets.keys.map(key => `
+            const expiring = Object.fromEntries(secrets.expiring.map(w => [w.key, w]));
+            list.innerHTML = secrets.keys.map(key => {
+                const warning = expiring[key];
+                const tag = !warning ? '<span class="tag">••••••••</span>'
+                    : warning.expired ? '<span class="tag disabled">expired</span>'
+                    : `<span class="tag warning">expires in ${warning.days_left}d</span>`;
+                return `
                 <div class="list-item">
                     <div class="list-item-name">${key}</div>
-                    <span class="tag">••••••••</span>
+                    ${tag}
                 </div>
-            `).join('');
+            `;
+            }).join('');
         }
         
         // Initial fetch
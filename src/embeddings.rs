@@ -0,0 +1,284 @@
+//! Local text embedding models (`local-embeddings` feature).
+//!
+//! With `llm.embeddings.backend` set to "local", llm.embed runs an ONNX
+//! sentence-embedding model in-process through fastembed instead of calling
+//! OpenAI, so vector.* and rag.* work offline and without an API key.
+//!
+//! Models come from Hugging Face. The first use, or `aegis models download`,
+//! fetches one into `llm.embeddings.cache_dir`; after that it loads from
+//! disk. `aegis models list` shows the models with their dimensions and which
+//! are cached, and `aegis models remove` frees the space again. Some models
+//! are quantized variants sharing a repository, and so share a cache entry.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::core::config::EmbeddingsConfig;
+
+/// Errors from local embedding models.
+#[derive(Debug, Error)]
+pub enum EmbeddingError {
+    #[error("Local embeddings need Aegis built with the 'local-embeddings' feature")]
+    Unsupported,
+
+    #[error("Unknown embedding model '{0}'; see `aegis models list`")]
+    UnknownModel(String),
+
+    #[error("Failed to load {model}: {message}")]
+    Load { model: String, message: String },
+
+    #[error("Embedding failed: {0}")]
+    Embed(String),
+
+    #[error("Model cache error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A model the local backend can run.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModel {
+    /// Name to select the model by, e.g. "AllMiniLML6V2".
+    pub name: String,
+    /// Hugging Face repository the model is downloaded from.
+    pub repository: String,
+    pub dimensions: usize,
+    pub description: String,
+    /// Size of the repository in the cache, if downloaded.
+    pub cached_bytes: Option<u64>,
+}
+
+/// Embeddings of a batch of texts.
+#[derive(Debug, Clone)]
+pub struct Embeddings {
+    /// Name of the model that produced them.
+    pub model: String,
+    pub dimensions: usize,
+    pub vectors: Vec<Vec<f32>>,
+}
+
+/// Directory models are downloaded to.
+pub fn cache_dir(config: &EmbeddingsConfig) -> PathBuf {
+    config
+        .cache_dir
+        .clone()
+        .or_else(|| std::env::var("FASTEMBED_CACHE_DIR").ok())
+        .unwrap_or_else(|| ".fastembed_cache".to_string())
+        .into()
+}
+
+/// Lists the supported models, with the cache size of those downloaded.
+pub fn list_models(config: &EmbeddingsConfig) -> Result<Vec<LocalModel>, EmbeddingError> {
+    let cache = cache_dir(config);
+    Ok(supported()?.into_iter().map(|model| with_cache_size(model, &cache)).collect())
+}
+
+/// Looks up a model by name. Matching ignores case and punctuation, so
+/// "all-MiniLM-L6-v2" selects "AllMiniLML6V2"; the repository name works too.
+pub fn find_model(config: &EmbeddingsConfig, name: &str) -> Result<LocalModel, EmbeddingError> {
+    let wanted = normalize(name);
+    let models = supported()?;
+    let model = models
+        .iter()
+        .find(|model| normalize(&model.name) == wanted)
+        .or_else(|| models.iter().find(|model| normalize(&model.repository) == wanted))
+        .cloned()
+        .ok_or_else(|| EmbeddingError::UnknownModel(name.to_string()))?;
+    Ok(with_cache_size(model, &cache_dir(config)))
+}
+
+/// Removes a model's repository from the cache and returns the bytes freed,
+/// or `None` if it wasn't downloaded.
+pub fn remove_model(config: &EmbeddingsConfig, name: &str) -> Result<Option<u64>, EmbeddingError> {
+    let model = find_model(config, name)?;
+    let Some(bytes) = model.cached_bytes else {
+        return Ok(None);
+    };
+    std::fs::remove_dir_all(repository_dir(&cache_dir(config), &model.repository))?;
+    Ok(Some(bytes))
+}
+
+/// Lowercases a model name and drops everything but letters and digits.
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// Cache directory of a repository, as laid out by the Hugging Face hub client.
+fn repository_dir(cache: &Path, repository: &str) -> PathBuf {
+    cache.join(format!("models--{}", repository.replace('/', "--")))
+}
+
+fn with_cache_size(mut model: LocalModel, cache: &Path) -> LocalModel {
+    let dir = repository_dir(cache, &model.repository);
+    model.cached_bytes = dir.is_dir().then(|| dir_size(&dir));
+    model
+}
+
+/// Total size of the files under `path`. Symlinks aren't followed: the hub
+/// cache links snapshots to blobs, which would otherwise count twice.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| Some((entry.path(), std::fs::symlink_metadata(entry.path()).ok()?)))
+        .map(|(path, meta)| if meta.is_dir() { dir_size(&path) } else if meta.is_file() { meta.len() } else { 0 })
+        .sum()
+}
+
+#[cfg(feature = "local-embeddings")]
+fn supported() -> Result<Vec<LocalModel>, EmbeddingError> {
+    Ok(fastembed::TextEmbedding::list_supported_models()
+        .into_iter()
+        .map(|info| LocalModel {
+            name: info.model.to_string(),
+            repository: info.model_code,
+            dimensions: info.dim,
+            description: info.description,
+            cached_bytes: None,
+        })
+        .collect())
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+fn supported() -> Result<Vec<LocalModel>, EmbeddingError> {
+    Err(EmbeddingError::Unsupported)
+}
+
+/// Loads a model, downloading it first if it isn't cached. Blocks.
+#[cfg(feature = "local-embeddings")]
+fn load(config: &EmbeddingsConfig, model: &LocalModel) -> Result<fastembed::TextEmbedding, EmbeddingError> {
+    let load_error = |message: String| EmbeddingError::Load { model: model.name.clone(), message };
+    let embedding_model: fastembed::EmbeddingModel = model.name.parse().map_err(load_error)?;
+    let options = fastembed::TextInitOptions::new(embedding_model)
+        .with_cache_dir(cache_dir(config))
+        .with_show_download_progress(false);
+    fastembed::TextEmbedding::try_new(options).map_err(|e| load_error(e.to_string()))
+}
+
+/// Downloads a model into the cache, checking that it loads. Blocks.
+#[cfg(feature = "local-embeddings")]
+pub fn download_model(config: &EmbeddingsConfig, name: &str) -> Result<LocalModel, EmbeddingError> {
+    let model = find_model(config, name)?;
+    load(config, &model)?;
+    Ok(with_cache_size(model, &cache_dir(config)))
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+pub fn download_model(_config: &EmbeddingsConfig, _name: &str) -> Result<LocalModel, EmbeddingError> {
+    Err(EmbeddingError::Unsupported)
+}
+
+/// A loaded model; `None` until its first use.
+#[cfg(feature = "local-embeddings")]
+type ModelSlot = std::sync::Arc<parking_lot::Mutex<Option<fastembed::TextEmbedding>>>;
+
+/// Runs local models for llm.embed, keeping each loaded after its first use.
+pub struct LocalEmbedder {
+    config: EmbeddingsConfig,
+    #[cfg(feature = "local-embeddings")]
+    models: parking_lot::Mutex<std::collections::HashMap<String, ModelSlot>>,
+}
+
+impl std::fmt::Debug for LocalEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalEmbedder").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+impl LocalEmbedder {
+    pub fn new(config: EmbeddingsConfig) -> Self {
+        Self {
+            config,
+            #[cfg(feature = "local-embeddings")]
+            models: Default::default(),
+        }
+    }
+
+    /// Name of the model used when a call doesn't pick one.
+    pub fn default_model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// Embeds texts with the named model, or the configured default. The
+    /// model is loaded (and downloaded, if need be) on first use.
+    #[cfg(feature = "local-embeddings")]
+    pub async fn embed(&self, model: Option<&str>, texts: Vec<String>) -> Result<Embeddings, EmbeddingError> {
+        let model = find_model(&self.config, model.unwrap_or(&self.config.model))?;
+        let slot = self.models.lock().entry(model.name.clone()).or_default().clone();
+        let config = self.config.clone();
+
+        // Loading and inference are CPU-bound; holding the slot's lock keeps
+        // concurrent first calls from loading the model twice
+        tokio::task::spawn_blocking(move || {
+            let mut loaded = slot.lock();
+            if loaded.is_none() {
+                *loaded = Some(load(&config, &model)?);
+            }
+            let vectors = loaded
+                .as_mut()
+                .expect("model loaded above")
+                .embed(&texts, None)
+                .map_err(|e| EmbeddingError::Embed(e.to_string()))?;
+            Ok(Embeddings { model: model.name, dimensions: model.dimensions, vectors })
+        })
+        .await
+        .map_err(|e| EmbeddingError::Embed(e.to_string()))?
+    }
+
+    #[cfg(not(feature = "local-embeddings"))]
+    pub async fn embed(&self, _model: Option<&str>, _texts: Vec<String>) -> Result<Embeddings, EmbeddingError> {
+        Err(EmbeddingError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_cache_layout() {
+        assert_eq!(normalize("all-MiniLM-L6-v2"), normalize("AllMiniLML6V2"));
+        assert_eq!(normalize("bge-small-en-v1.5"), normalize("BGESmallENV15"));
+
+        let cache = tempfile::tempdir().unwrap();
+        let dir = repository_dir(cache.path(), "Qdrant/all-MiniLM-L6-v2-onnx");
+        assert!(dir.ends_with("models--Qdrant--all-MiniLM-L6-v2-onnx"));
+
+        let model = LocalModel {
+            name: "AllMiniLML6V2".to_string(),
+            repository: "Qdrant/all-MiniLM-L6-v2-onnx".to_string(),
+            dimensions: 384,
+            description: String::new(),
+            cached_bytes: None,
+        };
+        assert_eq!(with_cache_size(model.clone(), cache.path()).cached_bytes, None);
+
+        std::fs::create_dir_all(dir.join("blobs")).unwrap();
+        std::fs::write(dir.join("blobs/abc"), vec![0u8; 1000]).unwrap();
+        std::fs::create_dir_all(dir.join("snapshots/main")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("blobs/abc"), dir.join("snapshots/main/model.onnx")).unwrap();
+        assert_eq!(with_cache_size(model, cache.path()).cached_bytes, Some(1000));
+    }
+
+    #[cfg(feature = "local-embeddings")]
+    #[test]
+    fn test_find_model() {
+        let config = EmbeddingsConfig::default();
+        let model = find_model(&config, &config.model).unwrap();
+        assert_eq!((model.name.as_str(), model.dimensions), ("AllMiniLML6V2", 384));
+        let model = find_model(&config, "Xenova/bge-small-en-v1.5").unwrap();
+        assert_eq!(model.name, "BGESmallENV15");
+        assert!(matches!(find_model(&config, "gpt-4"), Err(EmbeddingError::UnknownModel(_))));
+    }
+
+    #[cfg(not(feature = "local-embeddings"))]
+    #[tokio::test]
+    async fn test_local_embeddings_need_feature() {
+        let embedder = LocalEmbedder::new(EmbeddingsConfig::default());
+        let err = embedder.embed(None, vec!["hello".to_string()]).await.unwrap_err();
+        assert!(matches!(err, EmbeddingError::Unsupported));
+    }
+}
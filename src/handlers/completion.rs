@@ -0,0 +1,216 @@
+//! Handler for the `completion/complete` MCP method.
+//!
+//! Suggests values for an argument from the live runtime state: memory keys,
+//! conversation IDs, scheduled task IDs, collection and tool names, and the
+//! `enum` values of a tool's input schema. Besides prompts and resource
+//! templates, tool arguments can be completed with a `ref/tool` reference
+//! (an Aegis extension).
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::core::{NexusError, NexusResult, RuntimeState};
+use crate::memory::ConversationFilter;
+use crate::protocol::mcp::{CompleteParams, CompleteResult, Completion, CompletionReference};
+use crate::tools::core::complete_keys;
+use crate::tools::extras::complete_conversation_ids;
+use crate::tools::ToolError;
+
+/// Most values returned per completion.
+pub const MAX_COMPLETIONS: usize = 100;
+
+/// Recently updated conversations whose IDs are offered.
+const CONVERSATION_SCAN: usize = 1000;
+
+/// Handles the `completion/complete` request.
+pub async fn handle_completion_complete(params: Option<Value>, state: Arc<RuntimeState>) -> NexusResult<Value> {
+    let params: CompleteParams = match params {
+        Some(p) => serde_json::from_value(p)
+            .map_err(|e| NexusError::InvalidRequest(format!("Invalid completion/complete params: {}", e)))?,
+        None => return Err(NexusError::MissingField("params".to_string())),
+    };
+    debug!("Completing argument '{}' of {:?}", params.argument.name, params.reference);
+
+    let arguments = params
+        .context
+        .as_ref()
+        .and_then(|context| context.get("arguments"))
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let prefix = params.argument.value.as_str();
+    let candidates = match &params.reference {
+        // No prompts are served, so no prompt has arguments to complete
+        CompletionReference::Prompt { name } => {
+            return Err(NexusError::InvalidRequest(format!("Unknown prompt: {}", name)))
+        }
+        CompletionReference::Resource { uri } => {
+            complete_resource(uri, &params.argument.name, prefix, &state).await?
+        }
+        CompletionReference::Tool { name } => {
+            complete_tool(name, &params.argument.name, prefix, &arguments, &state).await?
+        }
+    };
+
+    serde_json::to_value(CompleteResult { completion: completion(candidates, prefix) })
+        .map_err(|e| NexusError::Internal(format!("Failed to serialize result: {}", e)))
+}
+
+/// Candidates for a variable of one of the resource templates.
+async fn complete_resource(uri: &str, argument: &str, prefix: &str, state: &RuntimeState) -> NexusResult<Vec<String>> {
+    match (uri, argument) {
+        ("nexus://conversations/{id}", "id") => Ok(state
+            .memory_store
+            .list_conversations(CONVERSATION_SCAN, &ConversationFilter::default())
+            .await
+            .map_err(|e| NexusError::Internal(e.to_string()))?
+            .into_iter()
+            .map(|conversation| conversation.id)
+            .collect()),
+        ("nexus://kv/{key}", "key") => state
+            .memory_store
+            .kv_list(Some(prefix))
+            .await
+            .map_err(|e| NexusError::Internal(e.to_string())),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Candidates for an argument of a tool. `arguments` holds the values of
+/// the call's other arguments, which scope e.g. memory keys.
+async fn complete_tool(
+    name: &str,
+    argument: &str,
+    prefix: &str,
+    arguments: &Value,
+    state: &Arc<RuntimeState>,
+) -> NexusResult<Vec<String>> {
+    let (name, tool) = {
+        let registry = state.tool_registry.read();
+        let name = registry.resolve(name).to_string();
+        let tool = registry.get(&name).cloned();
+        (name, tool)
+    };
+    let tool = tool.ok_or_else(|| NexusError::InvalidRequest(format!("Unknown tool: {}", name)))?;
+
+    let family = name.split('.').next().unwrap_or_default();
+    let candidates = match (family, argument) {
+        ("memory", "key") => complete_keys(state, arguments, prefix).await.map_err(tool_error)?,
+        ("memory", "collection") => state.collections.names(),
+        (_, "conversation_id") => complete_conversation_ids(state, arguments, prefix, CONVERSATION_SCAN)
+            .await
+            .map_err(tool_error)?,
+        ("scheduler", "id") | (_, "task_id") => state.scheduler.list_tasks().into_iter().map(|task| task.id).collect(),
+        (_, "tool") => state.tool_registry.read().list_definitions().into_iter().map(|tool| tool.name).collect(),
+        _ => {
+            let schema = tool.definition().input_schema;
+            let property = schema.get("properties").and_then(|properties| properties.get(argument));
+            property
+                .and_then(|property| property.get("enum"))
+                .and_then(|values| values.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        }
+    };
+    Ok(candidates)
+}
+
+fn tool_error(error: ToolError) -> NexusError {
+    match error {
+        ToolError::InvalidInput(message) => NexusError::InvalidRequest(message),
+        other => NexusError::Internal(other.to_string()),
+    }
+}
+
+/// The sorted, distinct candidates starting with `prefix`, capped at
+/// [`MAX_COMPLETIONS`].
+fn completion(mut candidates: Vec<String>, prefix: &str) -> Completion {
+    candidates.retain(|candidate| candidate.starts_with(prefix));
+    candidates.sort();
+    candidates.dedup();
+    let total = candidates.len();
+    candidates.truncate(MAX_COMPLETIONS);
+    Completion {
+        values: candidates,
+        total: Some(total),
+        has_more: total > MAX_COMPLETIONS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, RequestContext, Session};
+    use crate::tools::caller;
+
+    async fn complete(state: &Arc<RuntimeState>, params: Value) -> Value {
+        handle_completion_complete(Some(params), state.clone()).await.unwrap()["completion"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_complete_tool_arguments() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".into()),
+            ..Config::default()
+        }));
+        crate::tools::register_core_tools(&mut state.tool_registry.write(), &state.config);
+        crate::tools::register_extra_tools(&mut state.tool_registry.write(), &state.config);
+        state.memory_store.kv_set("ns:team:deploy.last", json!(1), None).await.unwrap();
+        state.memory_store.kv_set("ns:team:deploy.next", json!(2), None).await.unwrap();
+        state.memory_store.kv_set("ns:other:deploy.secret", json!(3), None).await.unwrap();
+        let conversation = state.memory_store.create_conversation(None, None, Some("team")).await.unwrap();
+
+        let session = Arc::new(Session::new("s1"));
+        session.set_namespace(Some("team".to_string()));
+        caller::with_context(RequestContext::new("s1").with_session(session), async {
+            let keys = complete(&state, json!({
+                "ref": {"type": "ref/tool", "name": "memory.recall"},
+                "argument": {"name": "key", "value": "deploy."}
+            }))
+            .await;
+            assert_eq!(keys, json!({"values": ["deploy.last", "deploy.next"], "total": 2, "hasMore": false}));
+
+            // Arguments already filled in pick the namespace
+            let keys = complete(&state, json!({
+                "ref": {"type": "ref/tool", "name": "memory.recall"},
+                "argument": {"name": "key", "value": "deploy"},
+                "context": {"arguments": {"namespace": "other"}}
+            }))
+            .await;
+            assert_eq!(keys["values"], json!(["deploy.secret"]));
+
+            let ids = complete(&state, json!({
+                "ref": {"type": "ref/tool", "name": "conversation.get"},
+                "argument": {"name": "conversation_id", "value": ""}
+            }))
+            .await;
+            assert_eq!(ids["values"], json!([conversation]));
+        })
+        .await;
+
+        let tools = complete(&state, json!({
+            "ref": {"type": "ref/tool", "name": "scheduler.create"},
+            "argument": {"name": "tool", "value": "memory.re"}
+        }))
+        .await;
+        assert_eq!(tools["values"], json!(["memory.recall"]));
+
+        let unknown = handle_completion_complete(
+            Some(json!({"ref": {"type": "ref/tool", "name": "nope"}, "argument": {"name": "x", "value": ""}})),
+            state.clone(),
+        )
+        .await;
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_completion_is_capped() {
+        let completion = completion((0..150).map(|i| format!("k{:03}", i)).collect(), "k");
+        assert_eq!(completion.values.len(), MAX_COMPLETIONS);
+        assert_eq!(completion.values[0], "k000");
+        assert_eq!(completion.total, Some(150));
+        assert!(completion.has_more);
+    }
+}
@@ -7,10 +7,12 @@ use serde_json::Value;
 use std::sync::Arc;
 use tracing::{info, debug};
 
-use crate::core::{NexusError, NexusResult, RuntimeState};
+use crate::core::session::SESSION_IDLE_TIMEOUT;
+use crate::core::{NexusError, NexusResult, RequestContext, RuntimeState};
+use crate::memory::check_namespace;
 use crate::protocol::mcp::{
     InitializeParams, InitializeResult, ServerCapabilities,
-    ToolsCapability, PromptsCapability, MCP_VERSION,
+    ToolsCapability, PromptsCapability, negotiate_version,
 };
 
 /// Handles the `initialize` request.
@@ -22,6 +24,16 @@ use crate::protocol::mcp::{
 pub async fn handle_initialize(
     params: Option<Value>,
     state: Arc<RuntimeState>,
+) -> NexusResult<Value> {
+    handle_initialize_with_context(params, state, &RequestContext::default()).await
+}
+
+/// Handles the `initialize` request, recording the client, negotiated
+/// version, namespace and pinned conversation on the caller's session.
+pub async fn handle_initialize_with_context(
+    params: Option<Value>,
+    state: Arc<RuntimeState>,
+    context: &RequestContext,
 ) -> NexusResult<Value> {
     debug!("Handling initialize request");
 
@@ -33,6 +45,9 @@ pub async fn handle_initialize(
             return Err(NexusError::MissingField("params".to_string()));
         }
     };
+    if let Some(namespace) = &init_params.namespace {
+        check_namespace(namespace).map_err(NexusError::InvalidRequest)?;
+    }
 
     info!(
         "Client connecting: {} v{} (protocol: {})",
@@ -41,23 +56,47 @@ pub async fn handle_initialize(
         init_params.protocol_version
     );
 
+    // A new connection is a good time to forget abandoned ones
+    let expired = state.sessions.remove_idle(SESSION_IDLE_TIMEOUT);
+    if !expired.is_empty() {
+        debug!("Dropped {} idle session(s)", expired.len());
+    }
+    let session = state.session(context);
+
+    // Pin the conversation scope if the client asked for one
+    if let Some(conversation_id) = init_params.conversation_id {
+        info!("Pinning conversation: {}", conversation_id);
+        session.pin_conversation(Some(conversation_id));
+    }
+    if let Some(namespace) = init_params.namespace {
+        session.set_namespace(Some(namespace));
+    }
+
+    // Remember what the client supports (e.g. sampling) for requests to it
+    if let Some(peer) = crate::tools::client::current() {
+        peer.set_capabilities(init_params.capabilities.clone());
+    }
+
     // Mark as initialized
     state.set_initialized();
 
     // Build server capabilities
     let capabilities = ServerCapabilities {
-        tools: Some(ToolsCapability { list_changed: false }),
+        tools: Some(ToolsCapability { list_changed: true }),
         prompts: Some(PromptsCapability { list_changed: false }),
         resources: None, // Phase 3
+        completions: Some(serde_json::json!({})),
     };
 
     // Build the response
     let result = InitializeResult {
-        protocol_version: MCP_VERSION.to_string(),
+        protocol_version: negotiate_version(&init_params.protocol_version).to_string(),
         capabilities,
         server_info: state.server_info.clone(),
     };
 
+    session.set_initialized(init_params.client_info, result.protocol_version.clone());
+
     info!(
         "Server initialized: {} v{} (protocol: {})",
         result.server_info.name,
@@ -95,6 +134,57 @@ mod tests {
         assert!(value.get("serverInfo").is_some());
         assert!(value.get("capabilities").is_some());
     }
+
+    #[tokio::test]
+    async fn test_initialize_pins_conversation() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        let params = serde_json::json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0.0" },
+            "conversationId": "conv-123"
+        });
+
+        handle_initialize(Some(params), state.clone()).await.unwrap();
+        assert_eq!(state.pinned_conversation(), Some("conv-123".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_is_per_session() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".into()),
+            ..Config::default()
+        }));
+        let params = |client: &str, version: &str, conversation: &str| {
+            serde_json::json!({
+                "protocolVersion": version,
+                "capabilities": {},
+                "clientInfo": { "name": client, "version": "1.0.0" },
+                "conversationId": conversation,
+                "namespace": client
+            })
+        };
+
+        let first = RequestContext::new("s1");
+        let second = RequestContext::new("s2");
+        handle_initialize_with_context(Some(params("a", "2024-11-05", "conv-a")), state.clone(), &first)
+            .await
+            .unwrap();
+        handle_initialize_with_context(Some(params("b", "2025-03-26", "conv-b")), state.clone(), &second)
+            .await
+            .unwrap();
+
+        let (s1, s2) = (state.sessions.get("s1").unwrap(), state.sessions.get("s2").unwrap());
+        assert_eq!(s1.client_info().unwrap().name, "a");
+        assert_eq!(s1.protocol_version().as_deref(), Some("2024-11-05"));
+        assert_eq!(s1.pinned_conversation().as_deref(), Some("conv-a"));
+        assert_eq!(s1.namespace().as_deref(), Some("a"));
+        assert_eq!(s2.client_info().unwrap().name, "b");
+        assert_eq!(s2.pinned_conversation().as_deref(), Some("conv-b"));
+        // Calls outside a client session see the default session
+        assert_eq!(state.pinned_conversation(), None);
+    }
 }
 
 
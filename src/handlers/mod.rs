@@ -11,11 +11,15 @@ mod tools_call;
 mod prompts;
 mod ping;
 mod resources;
+mod completion;
 
 pub use router::Router;
-pub use initialize::handle_initialize;
+pub use initialize::{handle_initialize, handle_initialize_with_context};
 pub use tools::handle_tools_list;
-pub use tools_call::handle_tools_call;
+pub use tools_call::{handle_tools_call, handle_tools_call_with_context};
 pub use prompts::handle_prompts_list;
 pub use ping::handle_ping;
-pub use resources::{handle_resources_list, handle_resources_read};
+pub use completion::handle_completion_complete;
+pub use resources::{
+    handle_resources_list, handle_resources_read, handle_resources_subscribe, handle_resources_unsubscribe,
+};
@@ -1,14 +1,24 @@
 //! Handlers for MCP resource methods.
 //!
-//! Resources expose the memory store as readable MCP resources.
+//! Resources expose the memory store as readable MCP resources, and files
+//! under `security.allowed_read_paths` as `file://` resources.
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde_json::Value;
 use std::sync::Arc;
 use tracing::debug;
+use url::Url;
 
 use crate::core::{NexusError, NexusResult, RuntimeState};
+use crate::memory::{is_reserved_key, namespaced_key, Conversation, ConversationFilter, MessageFilter};
+use crate::secrets::scrub::scrub;
+use crate::tools::core::{read_allowed_file, resolve_namespace};
+use crate::tools::{caller, roots, ToolError};
+use crate::tools::extras::{export_jsonl, export_markdown};
+use crate::tools::middleware::{output_chunk, output_key};
 use crate::protocol::mcp::{
     Resource, ResourcesListResult, ResourcesReadParams, ResourcesReadResult, ResourceContent,
+    ResourcesSubscribeParams,
 };
 
 /// Handles the `resources/list` request.
@@ -16,8 +26,15 @@ use crate::protocol::mcp::{
 /// Returns a list of available resources including:
 /// - conversations://list - List of conversations
 /// - conversations://{id} - Individual conversation with messages
+/// - nexus://conversations/{id}.md, nexus://conversations/{id}.jsonl - The
+///   conversation exported as a Markdown transcript or JSONL (read only)
 /// - kv://list - List of key-value keys
 /// - kv://{key} - Individual key-value pair
+/// - nexus://outputs/{id} - Full output of a summarized or truncated tool
+///   result (read only); `?chunk=N` reads it in `output_limit.chunk_bytes` pieces
+/// - nexus://watches/{id} - Changes recorded by an fs.watch (subscribable)
+///
+/// Files aren't listed, but `file://` URIs of allowed files can be read.
 pub async fn handle_resources_list(
     _params: Option<Value>,
     state: Arc<RuntimeState>,
@@ -49,8 +66,7 @@ pub async fn handle_resources_list(
     });
 
     // Add individual conversation resources
-    let conversations = state.memory_store.list_conversations(100).await
-        .map_err(|e| NexusError::Internal(e.to_string()))?;
+    let conversations = visible_conversations(&state).await?;
 
     for conv in conversations {
         resources.push(Resource {
@@ -62,8 +78,7 @@ pub async fn handle_resources_list(
     }
 
     // Add individual KV resources
-    let keys = state.memory_store.kv_list(None).await
-        .map_err(|e| NexusError::Internal(e.to_string()))?;
+    let keys = visible_keys(&state).await?;
 
     for key in keys {
         resources.push(Resource {
@@ -74,6 +89,16 @@ pub async fn handle_resources_list(
         });
     }
 
+    // Add file watches
+    for watch in state.file_watches.list() {
+        resources.push(Resource {
+            uri: watch["uri"].as_str().unwrap_or_default().to_string(),
+            name: format!("Watch {}", watch["path"].as_str().unwrap_or_default()),
+            description: Some("Files changed under a watched path (subscribe for updates)".to_string()),
+            mime_type: Some("application/json".to_string()),
+        });
+    }
+
     let result = ResourcesListResult { resources };
 
     debug!("Returning {} resources", result.resources.len());
@@ -109,8 +134,146 @@ pub async fn handle_resources_read(
         .map_err(|e| NexusError::Internal(format!("Failed to serialize: {}", e)))
 }
 
+/// Handles the `resources/subscribe` request.
+///
+/// The session is sent `notifications/resources/updated` whenever the
+/// resource changes, until it unsubscribes or disconnects.
+pub async fn handle_resources_subscribe(
+    params: Option<Value>,
+    state: Arc<RuntimeState>,
+    session_id: &str,
+) -> NexusResult<Value> {
+    let params = subscribe_params(params)?;
+    debug!("Session {} subscribing to {}", session_id, params.uri);
+    state.resource_subscriptions.subscribe(session_id, &params.uri);
+    Ok(serde_json::json!({}))
+}
+
+/// Handles the `resources/unsubscribe` request.
+pub async fn handle_resources_unsubscribe(
+    params: Option<Value>,
+    state: Arc<RuntimeState>,
+    session_id: &str,
+) -> NexusResult<Value> {
+    let params = subscribe_params(params)?;
+    debug!("Session {} unsubscribing from {}", session_id, params.uri);
+    state.resource_subscriptions.unsubscribe(session_id, &params.uri);
+    Ok(serde_json::json!({}))
+}
+
+fn subscribe_params(params: Option<Value>) -> NexusResult<ResourcesSubscribeParams> {
+    match params {
+        Some(p) => serde_json::from_value(p)
+            .map_err(|e| NexusError::InvalidRequest(format!("Invalid params: {}", e))),
+        None => Err(NexusError::MissingField("uri".to_string())),
+    }
+}
+
+/// The memory namespace of the caller, as the memory tools resolve it.
+fn kv_namespace(state: &RuntimeState) -> NexusResult<Option<String>> {
+    resolve_namespace(None, state).map_err(|e| NexusError::InvalidRequest(e.to_string()))
+}
+
+/// Whether the caller may see a key of its namespace. The server's own
+/// keys are hidden, and so are other namespaces' keys from callers
+/// without one.
+fn is_visible_key(key: &str, namespace: Option<&str>) -> bool {
+    !is_reserved_key(key) && (namespace.is_some() || !key.starts_with("ns:"))
+}
+
+/// Keys of the caller's namespace, without the namespace prefix.
+async fn visible_keys(state: &RuntimeState) -> NexusResult<Vec<String>> {
+    let namespace = kv_namespace(state)?;
+    let base = namespaced_key(namespace.as_deref(), "");
+    let keys = state.memory_store.kv_list(Some(&base)).await
+        .map_err(|e| NexusError::Internal(e.to_string()))?;
+    Ok(keys
+        .into_iter()
+        .filter_map(|key| key.strip_prefix(&base).map(str::to_string))
+        .filter(|key| is_visible_key(key, namespace.as_deref()))
+        .collect())
+}
+
+/// The most recent conversations of the caller's namespace (all of them
+/// for callers without one).
+async fn visible_conversations(state: &RuntimeState) -> NexusResult<Vec<Conversation>> {
+    let filter = ConversationFilter::in_namespace(caller::namespace().as_deref());
+    state.memory_store.list_conversations(100, &filter).await
+        .map_err(|e| NexusError::Internal(e.to_string()))
+}
+
+/// A conversation, if it belongs to the caller's namespace; others'
+/// conversations look missing.
+async fn visible_conversation(state: &RuntimeState, id: &str) -> NexusResult<Conversation> {
+    let conversation = state.memory_store.get_conversation(id).await
+        .map_err(|e| NexusError::Internal(e.to_string()))?;
+    match caller::namespace() {
+        Some(namespace) if conversation.namespace.as_deref() != Some(namespace.as_str()) => {
+            Err(NexusError::InvalidRequest(format!("Conversation not found: {}", id)))
+        }
+        _ => Ok(conversation),
+    }
+}
+
+/// Tool whose permissions `file://` reads are held to.
+const FS_READ: &str = "fs.read";
+
+/// Reads a file under `security.allowed_read_paths`: text files as text,
+/// anything else as a base64 blob, up to `output_limit.max_binary_bytes`.
+/// The read is held to what an `fs.read` call by the same caller could
+/// read: its API key scopes, the policy and the client's roots, and
+/// secrets are masked in text.
+async fn read_file_resource(uri: &str, state: &RuntimeState) -> NexusResult<ResourceContent> {
+    let path = Url::parse(uri)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .ok_or_else(|| NexusError::InvalidRequest(format!("Invalid file URI: {}", uri)))?;
+
+    let context = caller::current().unwrap_or_default();
+    let denied = |reason: String| NexusError::InvalidRequest(ToolError::PermissionDenied(reason).to_string());
+    if let Some(identity) = context.identity.as_ref().filter(|identity| !identity.allows_tool(FS_READ)) {
+        return Err(denied(format!("API key '{}' is not scoped for tool '{}'", identity.name, FS_READ)));
+    }
+    let arguments = serde_json::json!({ "path": path.to_string_lossy() });
+    state.policy.check(FS_READ, &arguments, &context).map_err(denied)?;
+    if state.approvals.requires(FS_READ) {
+        return Err(denied(format!("{} needs approval; call it as a tool instead", FS_READ)));
+    }
+    if let Some(session) = context.session.as_ref().filter(|_| state.config.security.client_roots) {
+        roots::refresh(session).await;
+    }
+    if path.exists() {
+        roots::check(&path).map_err(|e| NexusError::InvalidRequest(e.to_string()))?;
+    }
+
+    let path = path.to_string_lossy().into_owned();
+    let allowed = state.config.security.allowed_read_paths.clone();
+    let max_bytes = state.config.output_limit.max_binary_bytes;
+    let file = tokio::task::spawn_blocking(move || read_allowed_file(&allowed, &path, max_bytes))
+        .await
+        .map_err(|e| NexusError::Internal(e.to_string()))?
+        .map_err(|e| NexusError::InvalidRequest(e.to_string()))?;
+
+    let (text, blob) = if file.binary {
+        (None, Some(BASE64.encode(&file.data)))
+    } else {
+        let text = String::from_utf8_lossy(&file.data);
+        (Some(scrub(&text, &state.secrets.values()).into_owned()), None)
+    };
+    Ok(ResourceContent {
+        uri: uri.to_string(),
+        mime_type: Some(file.mime_type.to_string()),
+        text,
+        blob,
+    })
+}
+
 /// Reads a resource by URI.
 async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<ResourceContent> {
+    if uri.starts_with("file://") {
+        return read_file_resource(uri, &state).await;
+    }
+
     // Parse URI
     if !uri.starts_with("nexus://") {
         return Err(NexusError::InvalidRequest(format!("Invalid URI scheme: {}", uri)));
@@ -119,9 +282,8 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
     let path = &uri[8..]; // Remove "nexus://"
 
     if path == "conversations" {
-        // List all conversations
-        let conversations = state.memory_store.list_conversations(100).await
-            .map_err(|e| NexusError::Internal(e.to_string()))?;
+        // List the caller's conversations
+        let conversations = visible_conversations(&state).await?;
 
         let json = serde_json::to_string_pretty(&conversations)
             .map_err(|e| NexusError::Internal(e.to_string()))?;
@@ -132,12 +294,28 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
             text: Some(json),
             blob: None,
         })
-    } else if path.starts_with("conversations/") {
+    } else if let Some((conv_id, format)) = path.strip_prefix("conversations/").and_then(|id| id.rsplit_once('.')) {
+        // A conversation exported as a transcript or JSONL
+        let conversation = visible_conversation(&state, conv_id).await?;
+        let messages = state.memory_store.get_messages(conv_id, i64::MAX as usize).await
+            .map_err(|e| NexusError::Internal(e.to_string()))?;
+
+        let (mime_type, text) = match format {
+            "md" => ("text/markdown", export_markdown(&conversation, &messages)),
+            "jsonl" => ("application/jsonl", export_jsonl(&messages)),
+            _ => return Err(NexusError::InvalidRequest(format!("Unknown export format: {}", format))),
+        };
+
+        Ok(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some(mime_type.to_string()),
+            text: Some(text),
+            blob: None,
+        })
+    } else if let Some(conv_id) = path.strip_prefix("conversations/") {
         // Get specific conversation with messages
-        let conv_id = &path[14..]; // Remove "conversations/"
         
-        let conversation = state.memory_store.get_conversation(conv_id).await
-            .map_err(|e| NexusError::Internal(e.to_string()))?;
+        let conversation = visible_conversation(&state, conv_id).await?;
         
         let messages = state.memory_store.get_messages(conv_id, 1000).await
             .map_err(|e| NexusError::Internal(e.to_string()))?;
@@ -157,9 +335,15 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
             blob: None,
         })
     } else if path == "messages/recent" {
-        // Get recent messages
-        let messages = state.memory_store.get_recent_messages(50).await
-            .map_err(|e| NexusError::Internal(e.to_string()))?;
+        // Get recent messages of the caller's conversations
+        let messages = match caller::namespace() {
+            Some(namespace) => {
+                let filter = MessageFilter { namespace: Some(namespace), ..MessageFilter::default() };
+                state.memory_store.search_messages("", 50, &filter).await
+            }
+            None => state.memory_store.get_recent_messages(50).await,
+        }
+        .map_err(|e| NexusError::Internal(e.to_string()))?;
 
         let json = serde_json::to_string_pretty(&messages)
             .map_err(|e| NexusError::Internal(e.to_string()))?;
@@ -171,9 +355,8 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
             blob: None,
         })
     } else if path == "kv" {
-        // List all keys
-        let keys = state.memory_store.kv_list(None).await
-            .map_err(|e| NexusError::Internal(e.to_string()))?;
+        // List the caller's keys
+        let keys = visible_keys(&state).await?;
 
         let json = serde_json::to_string_pretty(&keys)
             .map_err(|e| NexusError::Internal(e.to_string()))?;
@@ -184,12 +367,15 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
             text: Some(json),
             blob: None,
         })
-    } else if path.starts_with("kv/") {
-        // Get specific key
-        let key = &path[3..]; // Remove "kv/"
-        
-        let kv = state.memory_store.kv_get(key).await
-            .map_err(|e| NexusError::Internal(e.to_string()))?;
+    } else if let Some(key) = path.strip_prefix("kv/") {
+        // Get specific key in the caller's namespace
+        let namespace = kv_namespace(&state)?;
+        let kv = if is_visible_key(key, namespace.as_deref()) {
+            state.memory_store.kv_get(&namespaced_key(namespace.as_deref(), key)).await
+                .map_err(|e| NexusError::Internal(e.to_string()))?
+        } else {
+            None
+        };
 
         match kv {
             Some(entry) => {
@@ -205,6 +391,52 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
             }
             None => Err(NexusError::InvalidRequest(format!("Key not found: {}", key))),
         }
+    } else if let Some(id) = path.strip_prefix("outputs/") {
+        // Full output of a summarized or truncated tool result
+        let (id, chunk) = match id.split_once("?chunk=") {
+            Some((id, chunk)) => {
+                let chunk = chunk.parse::<usize>()
+                    .map_err(|_| NexusError::InvalidRequest(format!("Invalid chunk: {}", chunk)))?;
+                (id, Some(chunk))
+            }
+            None => (id, None),
+        };
+        let kv = state.memory_store.kv_get(&output_key(id)).await
+            .map_err(|e| NexusError::Internal(e.to_string()))?;
+
+        let entry = kv.ok_or_else(|| NexusError::InvalidRequest(format!("Output not found or expired: {}", id)))?;
+        let full = entry.value.as_str().map(|s| s.to_string()).unwrap_or_else(|| entry.value.to_string());
+        let text = match chunk {
+            Some(chunk) => output_chunk(&full, chunk, state.config.output_limit.chunk_bytes)
+                .ok_or_else(|| NexusError::InvalidRequest(format!("Output {} has no chunk {}", id, chunk)))?
+                .to_string(),
+            None => full,
+        };
+
+        Ok(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("text/plain".to_string()),
+            text: Some(text),
+            blob: None,
+        })
+    } else if let Some(id) = path.strip_prefix("watches/") {
+        // A file watch and its most recent changes
+        let mut watch = state.file_watches.info(id)
+            .ok_or_else(|| NexusError::InvalidRequest(format!("File watch not found: {}", id)))?;
+        let recent = state.file_watches.events(id, 0, usize::MAX, None).await
+            .map(|polled| polled.events.into_iter().rev().take(100).rev().collect::<Vec<_>>())
+            .unwrap_or_default();
+        watch["recent_events"] = serde_json::json!(recent);
+
+        let json = serde_json::to_string_pretty(&watch)
+            .map_err(|e| NexusError::Internal(e.to_string()))?;
+
+        Ok(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(json),
+            blob: None,
+        })
     } else {
         Err(NexusError::InvalidRequest(format!("Unknown resource path: {}", path)))
     }
@@ -213,7 +445,8 @@ async fn read_resource(uri: &str, state: Arc<RuntimeState>) -> NexusResult<Resou
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::Config;
+    use crate::core::{Config, KeyIdentity, RequestContext, Session};
+    use crate::tools::middleware::OUTPUT_KEY_PREFIX;
 
     #[tokio::test]
     async fn test_resources_list() {
@@ -240,6 +473,140 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_resources_read_conversation_exports() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let conv_id = state.memory_store.create_conversation(Some("Chat".to_string()), None, None).await.unwrap();
+        state.memory_store.add_message(&conv_id, "user", "Hello", None).await.unwrap();
+
+        let read = |uri: String| handle_resources_read(Some(serde_json::json!({ "uri": uri })), state.clone());
+        let markdown = read(format!("nexus://conversations/{}.md", conv_id)).await.unwrap();
+        assert_eq!(markdown["contents"][0]["mimeType"], "text/markdown");
+        assert!(markdown["contents"][0]["text"].as_str().unwrap().contains("## User"));
+        let jsonl = read(format!("nexus://conversations/{}.jsonl", conv_id)).await.unwrap();
+        assert_eq!(jsonl["contents"][0]["text"].as_str().unwrap().lines().count(), 1);
+        assert!(read(format!("nexus://conversations/{}.pdf", conv_id)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resources_read_files() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        std::fs::write(allowed.path().join("logo.png"), &png).unwrap();
+        std::fs::write(allowed.path().join("notes.txt"), "hello").unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "nope").unwrap();
+
+        let mut config = Config::default();
+        config.security.allowed_read_paths = vec![allowed.path().to_path_buf()];
+        let state = Arc::new(RuntimeState::new(config));
+        let uri = |path: std::path::PathBuf| Url::from_file_path(path).unwrap().to_string();
+        let read = |uri: String| handle_resources_read(Some(serde_json::json!({ "uri": uri })), state.clone());
+
+        let image = read(uri(allowed.path().join("logo.png"))).await.unwrap();
+        assert_eq!(image["contents"][0]["mimeType"], "image/png");
+        assert_eq!(BASE64.decode(image["contents"][0]["blob"].as_str().unwrap()).unwrap(), png);
+        assert!(image["contents"][0].get("text").is_none());
+
+        let text = read(uri(allowed.path().join("notes.txt"))).await.unwrap();
+        assert_eq!(text["contents"][0]["text"], "hello");
+        assert!(text["contents"][0].get("blob").is_none());
+
+        assert!(read(uri(outside.path().join("secret.txt"))).await.is_err());
+        assert!(read(uri(allowed.path().join("missing.txt"))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_resources_follow_fs_read_permissions() {
+        let allowed = tempfile::tempdir().unwrap();
+        let notes = allowed.path().join("notes");
+        let drafts = notes.join("drafts");
+        std::fs::create_dir_all(&drafts).unwrap();
+        std::fs::write(notes.join("config.txt"), "token=s3cr3t-value").unwrap();
+        std::fs::write(drafts.join("todo.txt"), "todo").unwrap();
+        std::fs::write(allowed.path().join("keys.txt"), "nope").unwrap();
+
+        let mut config = Config::default();
+        config.security.allowed_read_paths = vec![allowed.path().to_path_buf()];
+        config.policy = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "rules": [format!("allow fs.read if path under {}", notes.display()), "allow *"]
+        }))
+        .unwrap();
+        let state = Arc::new(RuntimeState::new(config));
+        state.secrets.set("TOKEN", "s3cr3t-value", None);
+        let uri = |path: std::path::PathBuf| Url::from_file_path(path).unwrap().to_string();
+        let read = |uri: String, context: RequestContext| {
+            caller::with_context(context, handle_resources_read(Some(serde_json::json!({ "uri": uri })), state.clone()))
+        };
+
+        // Secrets are masked, and the policy's argument constraints apply
+        let text = read(uri(notes.join("config.txt")), RequestContext::default()).await.unwrap();
+        assert_eq!(text["contents"][0]["text"], "token=[REDACTED:TOKEN]");
+        let err = read(uri(allowed.path().join("keys.txt")), RequestContext::default()).await.unwrap_err();
+        assert!(err.to_string().contains("policy"), "{}", err);
+
+        // A key without fs.read in its scopes can't read files
+        let scoped = RequestContext::new("s1").with_identity(Some(Arc::new(KeyIdentity {
+            name: "agent".to_string(),
+            key_hash: "aaaa".to_string(),
+            scopes: vec!["memory.*".to_string()],
+        })));
+        let err = read(uri(notes.join("config.txt")), scoped).await.unwrap_err();
+        assert!(err.to_string().contains("not scoped"), "{}", err);
+
+        // Neither can a session outside the file's roots
+        let session = Arc::new(Session::new("s2"));
+        session.set_roots(Some(vec![drafts.canonicalize().unwrap()]));
+        let rooted = RequestContext::new("s2").with_session(session);
+        assert!(read(uri(drafts.join("todo.txt")), rooted.clone()).await.is_ok());
+        let err = read(uri(notes.join("config.txt")), rooted).await.unwrap_err();
+        assert!(err.to_string().contains("roots"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_resources_follow_namespaces() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let store = &state.memory_store;
+        store.kv_set("shared", serde_json::json!(1), None).await.unwrap();
+        store.kv_set("ns:team:notes", serde_json::json!(2), None).await.unwrap();
+        store.kv_set("ns:other:notes", serde_json::json!(3), None).await.unwrap();
+        store.kv_set("aegis:event_rules", serde_json::json!([]), None).await.unwrap();
+        store.kv_set("idempotency:abc", serde_json::json!({}), None).await.unwrap();
+        store.kv_set("ns:team:tool_output:out1", serde_json::json!("full"), None).await.unwrap();
+        let mine = store.create_conversation(None, None, Some("team")).await.unwrap();
+        let theirs = store.create_conversation(None, None, Some("other")).await.unwrap();
+
+        let session = Arc::new(Session::new("s1"));
+        session.set_namespace(Some("team".to_string()));
+        let team = RequestContext::new("s1").with_session(session);
+        let read = |uri: &str, context: RequestContext| {
+            caller::with_context(context, handle_resources_read(Some(serde_json::json!({ "uri": uri })), state.clone()))
+        };
+        let text = |result: Value| result["contents"][0]["text"].as_str().unwrap().to_string();
+
+        let keys: Vec<String> = serde_json::from_str(&text(read("nexus://kv", team.clone()).await.unwrap())).unwrap();
+        assert_eq!(keys, vec!["notes"]);
+        assert!(text(read("nexus://kv/notes", team.clone()).await.unwrap()).contains("2"));
+        assert!(read("nexus://kv/tool_output:out1", team.clone()).await.is_err());
+        assert_eq!(text(read("nexus://outputs/out1", team.clone()).await.unwrap()), "full");
+
+        let conversations = text(read("nexus://conversations", team.clone()).await.unwrap());
+        assert!(conversations.contains(&mine) && !conversations.contains(&theirs));
+        assert!(read(&format!("nexus://conversations/{}", theirs), team.clone()).await.is_err());
+        assert!(read(&format!("nexus://conversations/{}.md", theirs), team.clone()).await.is_err());
+        assert!(read(&format!("nexus://conversations/{}", mine), team).await.is_ok());
+
+        // Callers without a namespace see the shared keys, never the server's own
+        let keys: Vec<String> = serde_json::from_str(&text(read("nexus://kv", RequestContext::default()).await.unwrap())).unwrap();
+        assert_eq!(keys, vec!["shared"]);
+        assert!(read("nexus://kv/aegis:event_rules", RequestContext::default()).await.is_err());
+        assert!(read("nexus://outputs/out1", RequestContext::default()).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_resources_read_kv() {
         let state = Arc::new(RuntimeState::new(Config::default()));
@@ -254,5 +621,34 @@ mod tests {
         let result = handle_resources_read(Some(params), state).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_resources_read_output_chunks() {
+        let mut config = Config::default();
+        config.output_limit.chunk_bytes = 4;
+        let state = Arc::new(RuntimeState::new(config));
+        state.memory_store.kv_set(&format!("{}abc", OUTPUT_KEY_PREFIX), serde_json::json!("0123456789"), None).await.unwrap();
+
+        let read = |uri: &str| handle_resources_read(Some(serde_json::json!({ "uri": uri })), state.clone());
+        let chunk = read("nexus://outputs/abc?chunk=2").await.unwrap();
+        assert_eq!(chunk["contents"][0]["text"], "89");
+        let full = read("nexus://outputs/abc").await.unwrap();
+        assert_eq!(full["contents"][0]["text"], "0123456789");
+        assert!(read("nexus://outputs/abc?chunk=3").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resources_subscribe() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let params = serde_json::json!({ "uri": "nexus://watches/abc" });
+
+        handle_resources_subscribe(Some(params.clone()), state.clone(), "s1").await.unwrap();
+        assert!(state.resource_subscriptions.is_subscribed("s1", "nexus://watches/abc"));
+        assert!(!state.resource_subscriptions.is_subscribed("s2", "nexus://watches/abc"));
+
+        handle_resources_unsubscribe(Some(params), state.clone(), "s1").await.unwrap();
+        assert!(!state.resource_subscriptions.is_subscribed("s1", "nexus://watches/abc"));
+        assert!(handle_resources_subscribe(None, state, "s1").await.is_err());
+    }
 }
 
@@ -3,38 +3,100 @@
 //! Routes incoming JSON-RPC requests to the appropriate handler
 //! based on the method name.
 
+use dashmap::DashMap;
 use std::sync::Arc;
-use tracing::{debug, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
-use crate::core::RuntimeState;
-use crate::protocol::{Request, Response, ErrorObject, McpMethod};
+use crate::core::{RequestContext, RuntimeState};
+use crate::protocol::{Request, RequestId, Response, ErrorObject, McpMethod};
+use crate::tools::{caller, cancel};
 use crate::handlers::{
-    handle_initialize, handle_tools_list, handle_tools_call,
-    handle_prompts_list, handle_ping, handle_resources_list, handle_resources_read
+    handle_initialize_with_context, handle_tools_list, handle_tools_call_with_context,
+    handle_prompts_list, handle_ping, handle_resources_list, handle_resources_read,
+    handle_resources_subscribe, handle_resources_unsubscribe, handle_completion_complete,
 };
 
+/// JSON-RPC error code for a request the client cancelled.
+pub const REQUEST_CANCELLED: i32 = -32800;
+
+/// JSON-RPC error code for a tool call refused because the server is
+/// shutting down.
+pub const SHUTTING_DOWN: i32 = -32003;
+
 /// Router for dispatching MCP requests to handlers.
 #[derive(Debug, Clone)]
 pub struct Router {
-    // Future: could hold handler registry, middleware chain, etc.
+    /// Cancellation tokens of running tool calls, by session and request ID.
+    in_flight: Arc<DashMap<(String, String), CancellationToken>>,
 }
 
 impl Router {
     /// Creates a new router.
     pub fn new() -> Self {
-        Self {}
+        Self {
+            in_flight: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Cancels a running request of a session. Returns whether one was found.
+    pub fn cancel(&self, session_id: &str, request_id: &RequestId) -> bool {
+        let key = (session_id.to_string(), in_flight_id(request_id));
+        match self.in_flight.get(&key) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs a tool call that `notifications/cancelled` can abort.
+    async fn run_cancellable<F>(&self, session_id: &str, id: &RequestId, fut: F) -> Response
+    where
+        F: std::future::Future<Output = Response>,
+    {
+        let token = CancellationToken::new();
+        let key = (session_id.to_string(), in_flight_id(id));
+        self.in_flight.insert(key.clone(), token.clone());
+
+        let response = cancel::with_token(token.clone(), async {
+            tokio::select! {
+                response = fut => response,
+                _ = token.cancelled() => {
+                    info!("Request {:?} cancelled by client", id);
+                    Response::error(id.clone(), ErrorObject::new(REQUEST_CANCELLED, "Request cancelled"))
+                }
+            }
+        })
+        .await;
+
+        self.in_flight.remove(&key);
+        response
     }
 
     /// Handles an incoming MCP request and returns a response.
     pub async fn handle(&self, request: Request, state: Arc<RuntimeState>) -> Response {
+        self.handle_with_context(request, state, RequestContext::default()).await
+    }
+
+    /// Handles an incoming MCP request on behalf of a specific caller.
+    pub async fn handle_with_context(
+        &self,
+        request: Request,
+        state: Arc<RuntimeState>,
+        context: RequestContext,
+    ) -> Response {
         let method = McpMethod::from_str(&request.method);
         let id = request.id.clone();
+        let session = state.sessions.touch(&context);
+        let context = context.with_session(session);
 
         debug!("Routing request: method={:?}, id={:?}", method, id);
 
         match method {
             McpMethod::Initialize => {
-                match handle_initialize(request.params, state).await {
+                match handle_initialize_with_context(request.params, state, &context).await {
                     Ok(result) => Response::success(id, result),
                     Err(e) => Response::from_error(id, &e),
                 }
@@ -54,7 +116,55 @@ impl Router {
             }
 
             McpMethod::ToolsCall => {
-                match handle_tools_call(request.params, state).await {
+                if state.shutdown.is_shutting_down() {
+                    return Response::error(id, ErrorObject::new(SHUTTING_DOWN, "Server is shutting down"));
+                }
+                let shutdown_state = state.clone();
+                let _running = shutdown_state.shutdown.begin_call();
+                if let Some(session) = &context.session {
+                    session.record_tool_call();
+                }
+                let session_id = context.session_id.clone();
+                self.run_cancellable(&session_id, &id, async {
+                    match handle_tools_call_with_context(request.params, state, context).await {
+                        Ok(result) => Response::success(id.clone(), result),
+                        Err(e) => Response::from_error(id.clone(), &e),
+                    }
+                })
+                .await
+            }
+
+            McpMethod::Cancelled => {
+                // MCP sends `requestId`; LSP-style `$/cancelRequest` sends `id`
+                let params = request.params.unwrap_or_default();
+                let target = params
+                    .get("requestId")
+                    .or_else(|| params.get("id"))
+                    .cloned()
+                    .and_then(|v| serde_json::from_value::<RequestId>(v).ok());
+                match target {
+                    Some(target) if self.cancel(&context.session_id, &target) => {
+                        let reason = params.get("reason").and_then(|r| r.as_str()).unwrap_or("no reason given");
+                        info!("Cancelling request {:?}: {}", target, reason);
+                    }
+                    // The request may already have finished
+                    _ => debug!("No running request to cancel: {:?}", target),
+                }
+                Response::success(id, serde_json::json!({}))
+            }
+
+            McpMethod::RootsListChanged => {
+                // Fetched again before the session's next tool call
+                debug!("Client roots changed");
+                if let Some(session) = &context.session {
+                    session.set_roots(None);
+                }
+                Response::success(id, serde_json::json!({}))
+            }
+
+            McpMethod::CompletionComplete => {
+                // Completions are scoped like the tool calls they help write
+                match caller::with_context(context, handle_completion_complete(request.params, state)).await {
                     Ok(result) => Response::success(id, result),
                     Err(e) => Response::from_error(id, &e),
                 }
@@ -75,14 +185,30 @@ impl Router {
             }
 
             McpMethod::ResourcesList => {
-                match handle_resources_list(request.params, state).await {
+                match caller::with_context(context, handle_resources_list(request.params, state)).await {
                     Ok(result) => Response::success(id, result),
                     Err(e) => Response::from_error(id, &e),
                 }
             }
 
             McpMethod::ResourcesRead => {
-                match handle_resources_read(request.params, state).await {
+                // Reads are scoped like the tool calls that read the same data:
+                // to the caller's namespace and fs.read permissions
+                match caller::with_context(context, handle_resources_read(request.params, state)).await {
+                    Ok(result) => Response::success(id, result),
+                    Err(e) => Response::from_error(id, &e),
+                }
+            }
+
+            McpMethod::ResourcesSubscribe => {
+                match handle_resources_subscribe(request.params, state, &context.session_id).await {
+                    Ok(result) => Response::success(id, result),
+                    Err(e) => Response::from_error(id, &e),
+                }
+            }
+
+            McpMethod::ResourcesUnsubscribe => {
+                match handle_resources_unsubscribe(request.params, state, &context.session_id).await {
                     Ok(result) => Response::success(id, result),
                     Err(e) => Response::from_error(id, &e),
                 }
@@ -104,8 +230,76 @@ impl Router {
     }
 }
 
+/// Key for a request ID in the in-flight map (1 and "1" are distinct IDs).
+fn in_flight_id(id: &RequestId) -> String {
+    serde_json::to_string(id).unwrap_or_default()
+}
+
 impl Default for Router {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::protocol::mcp::Tool as ToolDefinition;
+    use crate::tools::{Tool, ToolError, ToolOutput};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct SlowTool;
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "test.slow".to_string(),
+                description: None,
+                input_schema: json!({"type": "object"}),
+                output_schema: None,
+            }
+        }
+
+        async fn execute(&self, _arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(ToolOutput::text("done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_tool_call() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(SlowTool));
+        let router = Router::new();
+
+        let call = Request::new("tools/call", Some(json!({"name": "test.slow", "arguments": {}})), 7.into());
+        let running = tokio::spawn({
+            let router = router.clone();
+            let state = state.clone();
+            async move { router.handle(call, state).await }
+        });
+
+        // Another session can't cancel it
+        let cancel = |session: &str| {
+            let request = Request::new("notifications/cancelled", Some(json!({"requestId": 7, "reason": "user"})), RequestId::Null);
+            router.handle_with_context(request, state.clone(), RequestContext::new(session))
+        };
+        while router.in_flight.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        cancel("other").await;
+        assert!(!running.is_finished());
+
+        cancel(&RequestContext::default().session_id).await;
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), running).await.unwrap().unwrap();
+        assert_eq!(response.error.unwrap().code, REQUEST_CANCELLED);
+        assert!(router.in_flight.is_empty());
+    }
+}
@@ -5,10 +5,12 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, info, warn};
 
-use crate::core::{NexusError, NexusResult, RuntimeState};
-use crate::tools::{Tool, ToolOutput, ToolContent};
+use crate::core::{NexusError, NexusResult, RequestContext, RuntimeState};
+use crate::memory::ToolCallRecord;
+use crate::tools::{caller, roots, Tool, ToolCall, ToolError, ToolOutput, ToolContent};
 
 /// Parameters for tools/call request.
 #[derive(Debug, Deserialize)]
@@ -18,6 +20,10 @@ pub struct ToolsCallParams {
     /// The arguments to pass to the tool.
     #[serde(default)]
     pub arguments: Value,
+    /// Request metadata; `idempotencyKey` makes a retried call reuse the
+    /// first call's result.
+    #[serde(default, rename = "_meta")]
+    pub meta: Option<Value>,
 }
 
 /// Result of tools/call request.
@@ -28,14 +34,22 @@ pub struct ToolsCallResult {
     /// Whether the tool execution resulted in an error.
     #[serde(rename = "isError")]
     pub is_error: bool,
+    /// Structured result, for tools that return one.
+    #[serde(rename = "structuredContent", skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
 }
 
 /// Content item in tool output (MCP format).
 #[derive(Debug, Serialize)]
-pub struct ToolContentItem {
-    #[serde(rename = "type")]
-    pub content_type: String,
-    pub text: String,
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ToolContentItem {
+    Text { text: String },
+    /// Base64-encoded image.
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
 }
 
 /// Handles the `tools/call` request.
@@ -44,11 +58,20 @@ pub struct ToolContentItem {
 pub async fn handle_tools_call(
     params: Option<Value>,
     state: Arc<RuntimeState>,
+) -> NexusResult<Value> {
+    handle_tools_call_with_context(params, state, RequestContext::default()).await
+}
+
+/// Handles the `tools/call` request on behalf of a specific caller.
+pub async fn handle_tools_call_with_context(
+    params: Option<Value>,
+    state: Arc<RuntimeState>,
+    context: RequestContext,
 ) -> NexusResult<Value> {
     debug!("Handling tools/call request");
 
     // Parse parameters
-    let call_params: ToolsCallParams = match params {
+    let mut call_params: ToolsCallParams = match params {
         Some(p) => serde_json::from_value(p)
             .map_err(|e| NexusError::InvalidRequest(format!("Invalid tools/call params: {}", e)))?,
         None => {
@@ -58,11 +81,19 @@ pub async fn handle_tools_call(
 
     info!("Calling tool: {} with args: {:?}", call_params.name, call_params.arguments);
 
-    // Get the tool from registry (clone the Arc to release the lock before await)
+    // Get the tool from registry (clone the Arc to release the lock before await).
+    // Aliases resolve to the tool's own name, which scopes, policy rules
+    // and the audit log refer to
     let tool: Arc<dyn Tool> = {
         let registry = state.tool_registry.read();
+        call_params.name = registry.resolve(&call_params.name).to_string();
         match registry.get(&call_params.name) {
             Some(t) => t.clone(),
+            None if registry.is_disabled(&call_params.name) => {
+                warn!("Tool disabled: {}", call_params.name);
+                let output = ToolOutput::error(format!("Tool is disabled: {}", call_params.name));
+                return format_output(output);
+            }
             None => {
                 warn!("Tool not found: {}", call_params.name);
                 let output = ToolOutput::error(format!("Tool not found: {}", call_params.name));
@@ -71,36 +102,156 @@ pub async fn handle_tools_call(
         }
     };
 
-    // Execute the tool (lock is released)
-    let output = match tool.execute(call_params.arguments, state.clone()).await {
-        Ok(output) => output,
-        Err(e) => {
-            warn!("Tool execution failed: {}", e);
-            ToolOutput::error(e.to_string())
+    let output_schema = tool.definition().output_schema;
+    let started_at = chrono::Utc::now();
+    let timer = Instant::now();
+    let tool_name = call_params.name.clone();
+    let session_id = context.session_id.clone();
+    let api_key_hash = context.api_key_hash.clone();
+    let api_key_name = context.key_name().map(|name| name.to_string());
+    let args_bytes = serde_json::to_vec(&call_params.arguments).map_or(0, |json| json.len() as u64);
+
+    // Enforce the key's scopes and the authorization policy before anything runs
+    let output = if let Some(identity) = context.identity.as_ref().filter(|id| !id.allows_tool(&call_params.name)) {
+        warn!("API key '{}' is not scoped for {}", identity.name, call_params.name);
+        let reason = format!("API key '{}' is not scoped for tool '{}'", identity.name, call_params.name);
+        ToolOutput::error(ToolError::PermissionDenied(reason).to_string())
+    } else if let Err(reason) = state.policy.check(&call_params.name, &call_params.arguments, &context) {
+        warn!("Policy denied {} for session '{}': {}", call_params.name, context.session_id, reason);
+        ToolOutput::error(ToolError::PermissionDenied(reason).to_string())
+    } else if let Err(reason) = state
+        .approvals
+        .check(&call_params.name, &call_params.arguments, &context, &state.events)
+        .await
+    {
+        ToolOutput::error(ToolError::PermissionDenied(reason).to_string())
+    } else {
+        // Scope file and git tools to the client's workspace
+        if let Some(session) = context.session.as_ref().filter(|_| state.config.security.client_roots) {
+            roots::refresh(session).await;
         }
+
+        let client_key = call_params.meta.as_ref().and_then(|meta| meta.get("idempotencyKey")).and_then(Value::as_str);
+        let idempotent = state.idempotency.key(&call_params.name, &call_params.arguments, client_key, &context);
+
+        // Execute the tool through the middleware chain (lock is released)
+        let call = ToolCall {
+            name: call_params.name,
+            arguments: call_params.arguments,
+            context: context.clone(),
+        };
+        let execute = async {
+            match caller::with_context(context, state.tool_middleware.execute(tool, call, state.clone())).await {
+                Ok(output) => output,
+                Err(e) => {
+                    warn!("Tool execution failed: {}", e);
+                    ToolOutput::error(e.to_string())
+                }
+            }
+        };
+        match idempotent {
+            Some(idempotent) => {
+                let (output, reused) = state.idempotency.run(state.memory_store.as_ref(), &idempotent, execute).await;
+                if reused {
+                    info!("Returned the stored result of an earlier call to {}", tool_name);
+                }
+                output
+            }
+            None => execute.await,
+        }
+    };
+
+    let output = match &output_schema {
+        Some(schema) => check_output(output, schema),
+        None => output,
     };
 
+    // Record the call in the audit log; a failed write never fails the call
+    let record = ToolCallRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        tool: tool_name,
+        session_id,
+        api_key_hash,
+        api_key_name,
+        success: !output.is_error,
+        error: output.is_error.then(|| first_text(&output)),
+        started_at: started_at.to_rfc3339(),
+        duration_ms: timer.elapsed().as_millis() as u64,
+        args_bytes: Some(args_bytes),
+    };
+    if let Err(e) = state.memory_store.record_tool_call(&record).await {
+        warn!("Failed to record tool call in audit log: {}", e);
+    }
+
     format_output(output)
 }
 
+/// Returns the first text content of an output.
+fn first_text(output: &ToolOutput) -> String {
+    output
+        .content
+        .iter()
+        .find_map(|c| match c {
+            ToolContent::Text { text } => Some(text.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Most schema errors reported for a result that doesn't match.
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Checks a successful result against the tool's output schema. A tool
+/// that returns JSON as text instead of structured content has it parsed.
+fn check_output(mut output: ToolOutput, schema: &Value) -> ToolOutput {
+    if output.is_error {
+        return output;
+    }
+    if output.structured_content.is_none() {
+        output.structured_content = serde_json::from_str(&first_text(&output)).ok();
+    }
+    let Some(structured) = &output.structured_content else {
+        warn!("Tool declares an outputSchema but returned no structured content");
+        return ToolOutput::error("Tool declares an outputSchema but returned no structured content");
+    };
+
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => return ToolOutput::error(format!("Tool has an invalid outputSchema: {}", e)),
+    };
+    let errors: Vec<String> = validator
+        .iter_errors(structured)
+        .take(MAX_REPORTED_ERRORS)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect();
+    if errors.is_empty() {
+        output
+    } else {
+        warn!("Tool result does not match its outputSchema: {}", errors.join("; "));
+        ToolOutput::error(format!("Tool result does not match its outputSchema: {}", errors.join("; ")))
+    }
+}
+
 /// Converts tool output to MCP format.
 fn format_output(output: ToolOutput) -> NexusResult<Value> {
-    let content: Vec<ToolContentItem> = output.content.iter().map(|c| {
+    let content: Vec<ToolContentItem> = output.content.into_iter().map(|c| {
         match c {
-            ToolContent::Text { text } => ToolContentItem {
-                content_type: "text".to_string(),
-                text: text.clone(),
-            },
-            ToolContent::Image { data, mime_type } => ToolContentItem {
-                content_type: "text".to_string(),
-                text: format!("data:{};base64,{}", mime_type, data),
-            },
+            ToolContent::Text { text } => ToolContentItem::Text { text },
+            ToolContent::Image { data, mime_type } => ToolContentItem::Image { data, mime_type },
         }
     }).collect();
 
     let result = ToolsCallResult {
         content,
         is_error: output.is_error,
+        structured_content: output.structured_content,
     };
 
     serde_json::to_value(result)
@@ -150,6 +301,110 @@ mod tests {
         assert_eq!(value.get("isError").unwrap(), false);
     }
 
+    #[tokio::test]
+    async fn test_tools_call_policy_denied() {
+        let config = Config {
+            policy: serde_json::from_value(serde_json::json!({
+                "enabled": true,
+                "rules": ["deny echo"]
+            }))
+            .unwrap(),
+            ..Config::default()
+        };
+        let state = Arc::new(RuntimeState::new(config));
+
+        let params = serde_json::json!({ "name": "echo", "arguments": { "text": "hi" } });
+        let value = handle_tools_call(Some(params), state).await.unwrap();
+
+        assert_eq!(value.get("isError").unwrap(), true);
+        let text = value["content"][0]["text"].as_str().unwrap();
+        assert!(text.starts_with("Permission denied:"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_recorded_in_audit_log() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        let params = serde_json::json!({ "name": "echo", "arguments": { "text": "hi" } });
+        handle_tools_call_with_context(Some(params), state.clone(), RequestContext::new("s1"))
+            .await
+            .unwrap();
+
+        let calls = state.memory_store.list_tool_calls(None).await.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].tool, "echo");
+        assert_eq!(calls[0].session_id, "s1");
+        assert!(calls[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_outside_key_scopes() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let identity = Arc::new(crate::core::KeyIdentity {
+            name: "reader".to_string(),
+            key_hash: "abc".to_string(),
+            scopes: vec!["memory.*".to_string()],
+        });
+        let context = RequestContext::new("s1").with_identity(Some(identity));
+
+        let params = serde_json::json!({ "name": "echo", "arguments": { "text": "hi" } });
+        let value = handle_tools_call_with_context(Some(params), state.clone(), context.clone())
+            .await
+            .unwrap();
+        assert_eq!(value.get("isError").unwrap(), true);
+        assert!(value["content"][0]["text"].as_str().unwrap().contains("not scoped"));
+
+        let params = serde_json::json!({ "name": "memory.list", "arguments": {} });
+        let value = handle_tools_call_with_context(Some(params), state.clone(), context)
+            .await
+            .unwrap();
+        assert_eq!(value.get("isError").unwrap(), false);
+
+        let calls = state.memory_store.list_tool_calls(None).await.unwrap();
+        assert_eq!(calls[0].api_key_name.as_deref(), Some("reader"));
+        assert!(!calls[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_alias() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            aliases: [("say".to_string(), "echo".to_string())].into_iter().collect(),
+            hide_aliased_tools: true,
+            ..Config::default()
+        }));
+        let names: Vec<String> = state
+            .tool_registry
+            .read()
+            .list_definitions()
+            .into_iter()
+            .map(|def| def.name)
+            .collect();
+        assert!(names.contains(&"say".to_string()));
+        assert!(!names.contains(&"echo".to_string()));
+
+        let identity = Arc::new(crate::core::KeyIdentity {
+            name: "echoer".to_string(),
+            key_hash: "abc".to_string(),
+            scopes: vec!["echo".to_string()],
+        });
+        let context = RequestContext::new("s1").with_identity(Some(identity));
+        let params = serde_json::json!({ "name": "say", "arguments": { "text": "hi" } });
+        let value = handle_tools_call_with_context(Some(params), state.clone(), context)
+            .await
+            .unwrap();
+        assert_eq!(value.get("isError").unwrap(), false);
+
+        let calls = state.memory_store.list_tool_calls(None).await.unwrap();
+        assert_eq!(calls[0].tool, "echo");
+    }
+
     #[tokio::test]
     async fn test_tools_call_unknown() {
         let state = Arc::new(RuntimeState::new(Config::default()));
@@ -166,4 +421,130 @@ mod tests {
         // Should return error in output, not fail the request
         assert_eq!(value.get("isError").unwrap(), true);
     }
+
+    #[tokio::test]
+    async fn test_tools_call_idempotency() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            idempotency: crate::core::IdempotencyConfig {
+                tools: [("test.*".to_string(), 60), ("test.report".to_string(), 0)].into_iter().collect(),
+                ..Default::default()
+            },
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(CountTool::default()));
+        state.tool_registry.write().register(Arc::new(ReportTool));
+        let call = |name: &str, arguments: Value, key: Option<&str>| {
+            let mut params = serde_json::json!({"name": name, "arguments": arguments});
+            if let Some(key) = key {
+                params["_meta"] = serde_json::json!({"idempotencyKey": key});
+            }
+            handle_tools_call_with_context(Some(params), state.clone(), RequestContext::new("s1"))
+        };
+        let count = |value: Value| value["structuredContent"]["count"].as_u64().unwrap();
+
+        // A listed tool reuses results for the same arguments
+        assert_eq!(count(call("test.count", serde_json::json!({"a": 1, "b": 2}), None).await.unwrap()), 1);
+        assert_eq!(count(call("test.count", serde_json::json!({"b": 2, "a": 1}), None).await.unwrap()), 1);
+        assert_eq!(count(call("test.count", serde_json::json!({"a": 2}), None).await.unwrap()), 2);
+
+        // Other tools only with a key, and not when turned off
+        let echo = |key| call("echo", serde_json::json!({"text": "hi"}), key);
+        assert_eq!(echo(Some("k1")).await.unwrap(), echo(Some("k1")).await.unwrap());
+
+        // A key reused with other arguments is refused
+        let reused = call("echo", serde_json::json!({"text": "bye"}), Some("k1")).await.unwrap();
+        assert_eq!(reused["isError"], true);
+        assert!(reused["content"][0]["text"].as_str().unwrap().contains("different arguments"));
+        assert!(state.idempotency.key("echo", &serde_json::json!({}), None, &RequestContext::new("s1")).is_none());
+        assert!(state.idempotency.key("test.report", &serde_json::json!({}), Some("k1"), &RequestContext::new("s1")).is_none());
+
+        // Failures are not kept
+        let failed = call("test.count", serde_json::json!({"fail": true}), None).await.unwrap();
+        assert_eq!(failed["isError"], true);
+        let retried = call("test.count", serde_json::json!({"fail": true}), None).await.unwrap();
+        assert_eq!(retried["content"][0]["text"], "Execution failed: attempt 4");
+    }
+
+    /// Counts its calls; fails when asked to.
+    #[derive(Debug, Default)]
+    struct CountTool {
+        calls: std::sync::atomic::AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for CountTool {
+        fn definition(&self) -> crate::protocol::mcp::Tool {
+            crate::protocol::mcp::Tool {
+                name: "test.count".to_string(),
+                description: None,
+                input_schema: serde_json::json!({"type": "object"}),
+                output_schema: None,
+            }
+        }
+
+        async fn execute(&self, arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            let count = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if arguments["fail"] == true {
+                return Err(ToolError::ExecutionFailed(format!("attempt {}", count)));
+            }
+            Ok(ToolOutput::structured(serde_json::json!({ "count": count })))
+        }
+    }
+
+    /// Returns `result`, as structured content or (with `as_text`) JSON text.
+    #[derive(Debug)]
+    struct ReportTool;
+
+    #[async_trait::async_trait]
+    impl Tool for ReportTool {
+        fn definition(&self) -> crate::protocol::mcp::Tool {
+            crate::protocol::mcp::Tool {
+                name: "test.report".to_string(),
+                description: None,
+                input_schema: serde_json::json!({"type": "object"}),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {"count": {"type": "integer"}},
+                    "required": ["count"]
+                })),
+            }
+        }
+
+        async fn execute(&self, arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            let result = arguments["result"].clone();
+            if arguments["as_text"] == true {
+                Ok(ToolOutput::text(result.to_string()))
+            } else {
+                Ok(ToolOutput::structured(result))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_output_schema() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(ReportTool));
+        let call = |arguments: Value| {
+            handle_tools_call(Some(serde_json::json!({"name": "test.report", "arguments": arguments})), state.clone())
+        };
+
+        let value = call(serde_json::json!({"result": {"count": 3}})).await.unwrap();
+        assert_eq!(value["isError"], false);
+        assert_eq!(value["structuredContent"], serde_json::json!({"count": 3}));
+        assert_eq!(value["content"][0]["text"], r#"{"count":3}"#);
+
+        // JSON returned as text is lifted into structured content
+        let value = call(serde_json::json!({"result": {"count": 4}, "as_text": true})).await.unwrap();
+        assert_eq!(value["structuredContent"]["count"], 4);
+
+        let value = call(serde_json::json!({"result": {"count": "many"}})).await.unwrap();
+        assert_eq!(value["isError"], true);
+        assert!(value.get("structuredContent").is_none());
+        let text = value["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("does not match its outputSchema") && text.contains("/count"), "{}", text);
+    }
 }
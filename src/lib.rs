@@ -11,6 +11,12 @@
 //! - `handlers`: MCP request handlers
 //! - `tools`: Tool execution and management
 //! - `memory`: Persistent storage for conversations and state
+//! - `proxy`: Upstream MCP servers re-exported as namespaced tools
+//! - `plugins`: Plugin tools loaded from a directory (WASM)
+//! - `repl`: Interactive shell for calling tools by hand
+//! - `update`: Release checks and self-update
+//! - `service`: Installation as a systemd or Windows service
+//! - `embeddings`: Local embedding models for llm.embed
 
 /// Core module containing configuration, errors, and state management.
 pub mod core;
@@ -38,3 +44,21 @@ pub mod scheduler;
 
 /// Dashboard module for web UI.
 pub mod dashboard;
+
+/// Proxy module for mounting upstream MCP servers.
+pub mod proxy;
+
+/// Plugins module for tools loaded from a plugin directory.
+pub mod plugins;
+
+/// REPL module for the interactive tool shell.
+pub mod repl;
+
+/// Update module for release checks and self-update.
+pub mod update;
+
+/// Service module for running Aegis as a system service.
+pub mod service;
+
+/// Embeddings module for local embedding models.
+pub mod embeddings;
@@ -14,19 +14,51 @@
 //!
 //! # Start HTTP/SSE server
 //! aegis serve --port 9000
+//!
+//! # Export the last week of tool call metrics as CSV
+//! aegis export metrics --since 7d --format csv
+//!
+//! # List the slowest and most error-prone tools of the last day
+//! aegis report --since 24h --top 5
+//!
+//! # Call tools interactively
+//! aegis repl
+//!
+//! # Rotate a secret, reading the new value from stdin
+//! aegis secrets rotate OPENAI_KEY --expires 90d < new-key.txt
+//!
+//! # Check a config file and print the effective settings
+//! aegis --config aegis.toml config validate
+//!
+//! # Replace this binary with the latest release
+//! aegis self-update
+//!
+//! # Run the server as a systemd service under the aegis account
+//! sudo aegis --config /etc/aegis/aegis.toml service install --user aegis
 //! ```
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, error, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, EnvFilter};
 
-use aegis::core::{Config, RuntimeState};
+use aegis::core::{Config, ConfigFormat, RequestContext, RuntimeState};
+use aegis::core::hooks::{run_hooks, HookPhase};
+use aegis::core::logs::{LogLayer, ScrubbedStderr};
+use aegis::memory::{ExportFormat, ExportKind};
 use aegis::handlers::Router;
-use aegis::transport::{Transport, StdioTransport};
+use aegis::protocol::McpMethod;
+use aegis::tools::caller;
+use aegis::tools::client::ClientPeer;
+use aegis::tools::manifest::{ManifestFormat, StubLanguage};
+use aegis::tools::stream::{Progress, SinkFilter};
+use aegis::transport::{Incoming, Transport, StdioTransport};
 use aegis::transport::sse::{SseState, start_server};
+use aegis::update::{Release, Updater};
 
 /// Aegis - MCP Tool Server for AI Agents
 #[derive(Parser, Debug)]
@@ -35,15 +67,23 @@ use aegis::transport::sse::{SseState, start_server};
 #[command(after_help = "EXAMPLES:\n  \
     aegis run echo --args '{\"text\": \"hello\"}'\n  \
     aegis serve --port 9000\n  \
+    aegis export usage --since 7d --format csv\n  \
+    aegis config validate --format toml\n  \
+    aegis db migrate --dry-run\n  \
+    aegis secrets rotate OPENAI_KEY --expires 90d\n  \
+    aegis tools export --format openapi --output tools.json\n  \
+    aegis self-update --check\n  \
+    aegis service install --user aegis\n  \
+    aegis repl\n  \
     aegis --stdio")]
 struct Cli {
-    /// Path to configuration file
-    #[arg(short, long, default_value = "aegis.json")]
-    config: PathBuf,
+    /// Path to configuration file (JSON, TOML or YAML) [default: aegis.json, aegis.toml or aegis.yaml]
+    #[arg(short, long)]
+    config: Option<PathBuf>,
 
-    /// Log level (trace, debug, info, warn, error)
-    #[arg(short, long, default_value = "info")]
-    log_level: String,
+    /// Log level (trace, debug, info, warn, error) [default: config log_level]
+    #[arg(short, long)]
+    log_level: Option<String>,
 
     /// Run in stdio mode (JSON-RPC over stdin/stdout)
     #[arg(long)]
@@ -61,13 +101,13 @@ struct Cli {
 enum Commands {
     /// Start the HTTP/SSE server
     Serve {
-        /// Host to bind to
-        #[arg(short = 'H', long, default_value = "127.0.0.1")]
-        host: String,
+        /// Host to bind to [default: config host]
+        #[arg(short = 'H', long)]
+        host: Option<String>,
 
-        /// Port to listen on
-        #[arg(short, long, default_value_t = 9000)]
-        port: u16,
+        /// Port to listen on [default: config port]
+        #[arg(short, long)]
+        port: Option<u16>,
     },
 
     /// Execute a single tool and exit
@@ -84,25 +124,248 @@ enum Commands {
         format: String,
     },
 
-    /// List all available tools
-    Tools,
+    /// List all available tools, or export their definitions
+    Tools {
+        #[command(subcommand)]
+        action: Option<ToolsAction>,
+    },
+
+    /// Call tools interactively, with tab completion and colored output
+    Repl,
 
     /// Show server version and capabilities
     Info,
+
+    /// Export reporting data (metrics, audit, usage) from the embedded store
+    Export {
+        /// Report to export (metrics, audit, usage)
+        report: ExportKind,
+
+        /// Only include calls since a window (30m, 24h, 7d, 2w) or a date
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Output format (csv, json)
+        #[arg(short, long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the slowest and most error-prone tools over a window
+    Report {
+        /// Only include calls since a window (30m, 24h, 7d, 2w) or a date
+        #[arg(short, long, default_value = "24h")]
+        since: String,
+
+        /// Number of tools per list
+        #[arg(short, long, default_value_t = 10)]
+        top: usize,
+
+        /// Print the full analytics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Manage the SQLite database
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
+    /// Manage stored secrets (a running server picks up changes on restart)
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
+    /// Manage the local embedding models llm.embed can use (`local-embeddings` feature)
+    Models {
+        #[command(subcommand)]
+        action: ModelsAction,
+    },
+
+    /// Replace this binary with the latest release, after verifying its checksum
+    SelfUpdate {
+        /// Only report whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Run the server as a system service (systemd on Linux, WinSW on Windows)
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+    /// Register, enable and start the service; it runs `aegis serve` from
+    /// the current directory with the given config file
+    Install {
+        /// Service name
+        #[arg(long, default_value = "aegis")]
+        name: String,
+
+        /// Account to run as [default: root on Linux, LocalSystem on Windows]
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// Print the service file instead of installing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Stop and remove the service
+    Uninstall {
+        /// Service name
+        #[arg(long, default_value = "aegis")]
+        name: String,
+    },
+
+    /// Show whether the service is installed and running
+    Status {
+        /// Service name
+        #[arg(long, default_value = "aegis")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ToolsAction {
+    /// Write the tool definitions as a manifest
+    Export {
+        /// Output format (json, openapi)
+        #[arg(short, long, default_value = "json")]
+        format: ManifestFormat,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Generate typed client stubs for the tools
+    Stubs {
+        /// Language (typescript, python)
+        #[arg(short, long, default_value = "typescript")]
+        lang: StubLanguage,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbAction {
+    /// Upgrade the database schema (also done on every start)
+    Migrate {
+        /// Only list the migrations that would run
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretsAction {
+    /// List secrets with their version and expiry
+    List,
+
+    /// Store a secret (the value is read from stdin unless given)
+    Set {
+        key: String,
+        value: Option<String>,
+
+        /// Description of the secret
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Rotate by a date (2025-06-30) or after a period (90d, 12w)
+        #[arg(short, long)]
+        expires: Option<String>,
+    },
+
+    /// Replace a secret's value, keeping the previous one for rollback
+    Rotate {
+        key: String,
+        value: Option<String>,
+
+        /// Rotate again by a date (2025-06-30) or after a period (90d, 12w)
+        #[arg(short, long)]
+        expires: Option<String>,
+    },
+
+    /// List a secret's versions
+    Versions { key: String },
+
+    /// Restore the previous value of a secret, or the given version
+    Rollback {
+        key: String,
+
+        /// Version to restore
+        #[arg(long)]
+        version: Option<u32>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ModelsAction {
+    /// List the supported models, their dimensions and which are downloaded
+    List,
+
+    /// Download a model into the cache [default: llm.embeddings.model]
+    Download { model: Option<String> },
+
+    /// Delete a downloaded model from the cache
+    Remove { model: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Load the config file and AEGIS_* overrides, report problems and print the effective configuration
+    Validate {
+        /// Output format (json, toml, yaml)
+        #[arg(short, long, default_value = "json")]
+        format: ConfigFormat,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    // Load configuration: file, then AEGIS_* environment overrides, then CLI flags
+    let config_path = cli.config.clone().unwrap_or_else(Config::default_path);
+    let loaded = Config::load(&config_path);
+
     // Initialize logging (to stderr to avoid corrupting JSON-RPC on stdout)
-    init_logging(&cli.log_level);
+    // The REPL shares the terminal with logs, so it only shows warnings unless asked
+    let log_level = cli
+        .log_level
+        .clone()
+        .or_else(|| matches!(cli.command, Some(Commands::Repl)).then(|| "warn".to_string()))
+        .or_else(|| loaded.as_ref().ok().map(|config| config.log_level.clone()))
+        .unwrap_or_else(|| "info".to_string());
+    init_logging(&log_level);
+
+    if let Some(Commands::Config { action: ConfigAction::Validate { format } }) = cli.command {
+        validate_config(&config_path, loaded, format);
+    }
 
-    // Load configuration
-    let mut config = Config::load_from_file(&cli.config).unwrap_or_else(|e| {
+    let mut config = loaded.unwrap_or_else(|e| {
         info!("Could not load config file: {}. Using defaults.", e);
         Config::default()
     });
+    config.log_level = log_level;
 
     // Handle --core-only flag
     if cli.core_only {
@@ -117,20 +380,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Handle subcommands
     match cli.command {
         Some(Commands::Serve { host, port }) => {
-            config.host = host;
-            config.port = port;
+            if let Some(host) = host {
+                config.host = host;
+            }
+            if let Some(port) = port {
+                config.port = port;
+            }
             run_serve_mode(config).await
         }
         Some(Commands::Run { tool, args, format }) => {
             run_oneshot_mode(config, &tool, &args, &format).await
         }
-        Some(Commands::Tools) => {
+        Some(Commands::Tools { action: None }) => {
             list_tools(config).await
         }
+        Some(Commands::Tools { action: Some(action) }) => {
+            export_tools(config, action).await
+        }
+        Some(Commands::Repl) => {
+            run_repl_mode(config).await
+        }
         Some(Commands::Info) => {
-            show_info(&config);
+            let update = match config.update.check {
+                true => Updater::new(&config.update).check().await.unwrap_or_else(|e| {
+                    warn!("Update check failed: {}", e);
+                    None
+                }),
+                false => None,
+            };
+            show_info(&config, update.as_ref());
             Ok(())
         }
+        Some(Commands::Export { report, since, format, output }) => {
+            export_report(&config, report, since.as_deref(), format, output).await
+        }
+        Some(Commands::Report { since, top, json }) => {
+            print_tool_report(&config, &since, top, json).await
+        }
+        Some(Commands::Db { action: DbAction::Migrate { dry_run } }) => {
+            migrate_database(&config, dry_run)
+        }
+        Some(Commands::Secrets { action }) => {
+            manage_secrets(&config, action)
+        }
+        Some(Commands::Models { action }) => {
+            manage_models(&config, action)
+        }
+        Some(Commands::SelfUpdate { check }) => {
+            self_update(&config, check).await
+        }
+        Some(Commands::Service { action }) => {
+            manage_service(&config_path, action)
+        }
+        Some(Commands::Config { .. }) => unreachable!("handled before the config fallback"),
         None => {
             // Default: show banner and usage
             print_banner(&config);
@@ -147,8 +449,10 @@ fn init_logging(level: &str) {
 
     fmt()
         .with_env_filter(filter)
-        .with_writer(std::io::stderr) // Important: logs go to stderr
+        .with_writer(ScrubbedStderr) // Important: logs go to stderr
         .with_target(false)
+        .finish()
+        .with(LogLayer::new()) // Recent events, for the dashboard and logs.tail
         .init();
 }
 
@@ -157,41 +461,174 @@ async fn run_stdio_mode(config: Config) -> Result<(), Box<dyn std::error::Error>
     info!("Starting Aegis in stdio mode");
 
     let state = Arc::new(RuntimeState::new(config));
+    // No dashboard runs in stdio mode, so ask on the terminal
+    state.approvals.enable_terminal_prompt();
+    aegis::proxy::mount_upstreams(&state).await;
+    aegis::plugins::load_plugins(&state);
+    aegis::tools::extras::mount_composite_tools(&state).await;
+    state.restore_disabled_tools().await;
+    start_scheduler(&state);
+    start_event_rules(&state).await;
+    aegis::update::start_check(&state);
+    run_hooks(&state, HookPhase::Start).await?;
     let router = Router::new();
+    let peer = Arc::new(ClientPeer::new());
+    let mut outbound = peer.attach();
+
+    // Read on a separate task so the client's responses to our requests
+    // (e.g. sampling) and cancellations arrive while a tool call is still
+    // in progress
+    let (requests_tx, mut requests) = tokio::sync::mpsc::unbounded_channel();
+    let reader_peer = peer.clone();
+    let reader_router = router.clone();
+    let reader_state = state.clone();
+    tokio::spawn(async move {
+        let mut reader = StdioTransport::new();
+        loop {
+            match reader.read_message().await {
+                Ok(Some(Incoming::Request(request))) if McpMethod::from_str(&request.method) == McpMethod::Cancelled => {
+                    reader_router.handle(request, reader_state.clone()).await;
+                }
+                Ok(Some(Incoming::Request(request))) => {
+                    if requests_tx.send(request).is_err() {
+                        break;
+                    }
+                }
+                Ok(Some(Incoming::Response(response))) => {
+                    if !reader_peer.handle_response(&response) {
+                        warn!("Ignoring response to unknown request: {}", response["id"]);
+                    }
+                }
+                Ok(None) => {
+                    // EOF - client disconnected
+                    info!("EOF received, shutting down");
+                    break;
+                }
+                Err(e) => {
+                    error!("Failed to read request: {}", e);
+                    // For parse errors, we should continue
+                    // For IO errors, we might want to break
+                    if matches!(e, aegis::core::AegisError::Io(_)) {
+                        break;
+                    }
+                }
+            }
+        }
+        reader_peer.close();
+    });
+
     let mut transport = StdioTransport::new();
 
     info!("Ready to accept JSON-RPC requests on stdin");
 
     // Main request loop
+    let signalled = state.clone();
+    tokio::spawn(async move {
+        aegis::core::shutdown::signal().await;
+        signalled.shutdown.trigger();
+    });
+
+    let mut tools_changed = state.subscribe_tools_changed();
+    let mut resource_updates = state.resource_subscriptions.updates();
     loop {
-        match transport.read_request().await {
-            Ok(Some(request)) => {
-                let response = router.handle(request, state.clone()).await;
-                if let Err(e) = transport.write_response(response).await {
-                    error!("Failed to write response: {}", e);
+        let request = tokio::select! {
+            request = requests.recv() => request,
+            Ok(()) = tools_changed.changed() => {
+                if let Err(e) = transport.write_notification("notifications/tools/list_changed", serde_json::json!({})).await {
+                    error!("Failed to write notification: {}", e);
                 }
+                continue;
             }
-            Ok(None) => {
-                // EOF - client disconnected
-                info!("EOF received, shutting down");
-                break;
-            }
-            Err(e) => {
-                error!("Failed to read request: {}", e);
-                // For parse errors, we should continue
-                // For IO errors, we might want to break
-                if matches!(e, aegis::core::AegisError::Io(_)) {
-                    break;
+            Ok(uri) = resource_updates.recv() => {
+                if state.resource_subscriptions.is_subscribed(aegis::core::context::DEFAULT_SESSION, &uri) {
+                    let params = serde_json::json!({ "uri": uri });
+                    if let Err(e) = transport.write_notification("notifications/resources/updated", params).await {
+                        error!("Failed to write notification: {}", e);
+                    }
                 }
+                continue;
+            }
+            _ = state.shutdown.triggered() => None,
+        };
+        let Some(request) = request else {
+            break;
+        };
+
+        let notification = request.is_notification();
+        let response = tokio::select! {
+            response = handle_streaming(&router, request, &state, &peer, &mut outbound, &mut transport) => response,
+            _ = shutdown_deadline(&state) => {
+                error!("Tool call still running after {}s, shutting down anyway", state.config.shutdown_timeout_secs);
+                break;
             }
+        };
+        if notification {
+            continue;
+        }
+        if let Err(e) = transport.write_response(response).await {
+            error!("Failed to write response: {}", e);
         }
     }
 
+    shut_down(&state).await;
     transport.close().await?;
     info!("Aegis stdio mode shut down cleanly");
     Ok(())
 }
 
+/// Handles a request on behalf of the stdio client: forwards streamed tool
+/// output as MCP progress notifications when the client asked for them with
+/// a progress token, and writes requests tools make to the client (e.g.
+/// sampling) while the call runs.
+async fn handle_streaming(
+    router: &Router,
+    request: aegis::protocol::Request,
+    state: &Arc<RuntimeState>,
+    peer: &Arc<ClientPeer>,
+    outbound: &mut tokio::sync::mpsc::UnboundedReceiver<serde_json::Value>,
+    transport: &mut StdioTransport,
+) -> aegis::protocol::Response {
+    let progress_token = request
+        .params
+        .as_ref()
+        .and_then(|p| p.pointer("/_meta/progressToken"))
+        .cloned();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let call = aegis::tools::client::with_peer(peer.clone(), router.handle(request, state.clone()));
+    let handle = async {
+        if progress_token.is_some() {
+            aegis::tools::stream::with_sink(tx, SinkFilter::from_state(state), call).await
+        } else {
+            call.await
+        }
+    };
+    tokio::pin!(handle);
+
+    let mut progress = Progress::new(progress_token.clone().unwrap_or_default());
+    loop {
+        tokio::select! {
+            response = &mut handle => {
+                // Flush chunks emitted right before the call returned
+                while let Ok(update) = rx.try_recv() {
+                    let _ = transport.write_notification("notifications/progress", progress.params(update)).await;
+                }
+                return response;
+            }
+            Some(update) = rx.recv() => {
+                if let Err(e) = transport.write_notification("notifications/progress", progress.params(update)).await {
+                    error!("Failed to write progress notification: {}", e);
+                }
+            }
+            Some(message) = outbound.recv() => {
+                if let Err(e) = transport.write_message(&message).await {
+                    error!("Failed to write request to client: {}", e);
+                }
+            }
+        }
+    }
+}
+
 /// Runs Aegis in HTTP/SSE serve mode.
 async fn run_serve_mode(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     use aegis::transport::Metrics;
@@ -200,20 +637,50 @@ async fn run_serve_mode(config: Config) -> Result<(), Box<dyn std::error::Error>
 
     let addr = config.socket_addr();
     let state = Arc::new(RuntimeState::new(config.clone()));
+    aegis::proxy::mount_upstreams(&state).await;
+    aegis::plugins::load_plugins(&state);
+    aegis::tools::extras::mount_composite_tools(&state).await;
+    state.restore_disabled_tools().await;
+    start_scheduler(&state);
+    start_event_rules(&state).await;
+    aegis::update::start_check(&state);
+    run_hooks(&state, HookPhase::Start).await?;
     let router = Arc::new(Router::new());
     let metrics = Metrics::new();
 
     let sse_state = SseState {
-        runtime: state,
+        runtime: state.clone(),
         router,
         metrics,
+        peers: Default::default(),
+        sessions: Default::default(),
     };
 
-    start_server(sse_state, &config, addr).await?;
+    let result = start_server(sse_state, &config, addr).await;
+    shut_down(&state).await;
+    result?;
 
     Ok(())
 }
 
+/// Resolves once shutdown has begun and the drain deadline has passed.
+async fn shutdown_deadline(state: &RuntimeState) {
+    state.shutdown.triggered().await;
+    tokio::time::sleep(std::time::Duration::from_secs(state.config.shutdown_timeout_secs)).await;
+}
+
+/// Stops background work and makes pending writes durable, after the
+/// transport has stopped taking requests.
+async fn shut_down(state: &Arc<RuntimeState>) {
+    state.shutdown.trigger();
+    state.scheduler.stop();
+    let _ = run_hooks(state, HookPhase::Shutdown).await;
+    state.processes.shutdown().await;
+    if let Err(e) = state.memory_store.flush().await {
+        error!("Failed to flush the memory store: {}", e);
+    }
+}
+
 /// Runs a single tool and exits.
 async fn run_oneshot_mode(
     config: Config,
@@ -224,14 +691,25 @@ async fn run_oneshot_mode(
     use aegis::tools::ToolContent;
 
     let state = Arc::new(RuntimeState::new(config));
-    let registry = state.tool_registry.read();
+    aegis::proxy::mount_upstreams(&state).await;
+    aegis::plugins::load_plugins(&state);
+    aegis::tools::extras::mount_composite_tools(&state).await;
 
     // Parse arguments
     let arguments: serde_json::Value = serde_json::from_str(args_json)
         .map_err(|e| format!("Invalid JSON arguments: {}", e))?;
 
+    // Get the tool (clone the Arc to release the lock before await)
+    let tool = state.tool_registry.read().get(tool_name).cloned();
+
     // Execute the tool
-    match registry.execute(tool_name, arguments, state.clone()).await {
+    let result = match tool {
+        // The operator runs the tool, with no API key scoping it
+        Some(tool) => caller::with_context(RequestContext::default(), tool.execute(arguments, state.clone())).await,
+        None => Err(aegis::tools::ToolError::NotFound(tool_name.to_string())),
+    };
+
+    match result {
         Ok(output) => {
             if format == "json" {
                 println!("{}", serde_json::to_string_pretty(&output)?);
@@ -255,6 +733,31 @@ async fn run_oneshot_mode(
     }
 }
 
+/// Runs the interactive REPL.
+async fn run_repl_mode(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let state = Arc::new(RuntimeState::new(config));
+    aegis::proxy::mount_upstreams(&state).await;
+    aegis::plugins::load_plugins(&state);
+    aegis::tools::extras::mount_composite_tools(&state).await;
+
+    let result = aegis::repl::run(state.clone()).await;
+    state.processes.shutdown().await;
+    result
+}
+
+/// Runs the scheduler (cron tasks and heartbeat watchdog) in the background.
+fn start_scheduler(state: &Arc<RuntimeState>) {
+    let scheduler = state.scheduler.clone();
+    let state = state.clone();
+    tokio::spawn(async move { scheduler.start(state).await });
+}
+
+/// Loads saved event subscriptions and starts running event rules.
+async fn start_event_rules(state: &Arc<RuntimeState>) {
+    state.events.restore(state.memory_store.as_ref()).await;
+    aegis::core::events::start(state);
+}
+
 /// Lists all available tools.
 async fn list_tools(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     let extras_enabled = config.extras_enabled;
@@ -267,15 +770,18 @@ async fn list_tools(config: Config) -> Result<(), Box<dyn std::error::Error>> {
 
     // Core tools list (approximate - these are the core tool names)
     let core_tools: std::collections::HashSet<&str> = [
-        "echo", "get_time", "uuid.generate",
+        "echo", "get_time", "time.now", "uuid.generate",
         "fs.read_file", "fs.write_file", "cmd.exec",
-        "memory.store", "memory.recall", "memory.delete", "memory.list",
+        "process.start", "process.list", "process.logs", "process.stop",
+        "memory.store", "memory.recall", "memory.delete", "memory.list", "memory.transaction",
         "http.request",
         "env.get", "env.list", "sys.info",
         "base64.encode", "base64.decode",
         "json.parse", "json.query",
         "hash.sha256",
         "regex.match", "regex.replace",
+        "path.join", "path.normalize", "path.relative", "path.basename",
+        "admin.tools", "logs.tail",
     ].iter().cloned().collect();
 
     let mut tools: Vec<_> = registry.tools.iter().collect();
@@ -330,8 +836,329 @@ async fn list_tools(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Writes the tool definitions clients see as a manifest or client stubs.
+async fn export_tools(config: Config, action: ToolsAction) -> Result<(), Box<dyn std::error::Error>> {
+    let state = Arc::new(RuntimeState::new(config));
+    aegis::proxy::mount_upstreams(&state).await;
+    aegis::plugins::load_plugins(&state);
+    aegis::tools::extras::mount_composite_tools(&state).await;
+    state.restore_disabled_tools().await;
+    let tools = state.tool_registry.read().list_definitions();
+
+    let (rendered, output) = match action {
+        ToolsAction::Export { format, output } => {
+            let manifest = aegis::tools::manifest::export(&tools, format);
+            (format!("{}\n", serde_json::to_string_pretty(&manifest)?), output)
+        }
+        ToolsAction::Stubs { lang, output } => (aegis::tools::manifest::stubs(&tools, lang), output),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            eprintln!("{} Wrote {} tools to {}", "✓".green(), tools.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Exports a report from the configured store to stdout or a file.
+async fn export_report(
+    config: &Config,
+    report: ExportKind,
+    since: Option<&str>,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let since = since
+        .map(|s| aegis::memory::parse_since(s, chrono::Utc::now()))
+        .transpose()?;
+
+    let store = aegis::memory::open_store(config)?;
+    let rendered = aegis::memory::build_report(store.as_ref(), report, since).await?.render(format);
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            eprintln!("{} Exported to {}", "✓".green(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Prints the slowest and most error-prone tools since `since`.
+async fn print_tool_report(config: &Config, since: &str, top: usize, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let since = aegis::memory::parse_since(since, chrono::Utc::now())?;
+    let store = aegis::memory::open_store(config)?;
+    let analytics = aegis::memory::build_analytics(store.as_ref(), Some(since), chrono::Duration::hours(1), None).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analytics)?);
+        return Ok(());
+    }
+
+    let calls: u64 = analytics.tools.iter().map(|t| t.stats.calls).sum();
+    println!("{} calls to {} tools since {}", calls, analytics.tools.len(), since.format("%Y-%m-%d %H:%M UTC"));
+    if calls == 0 {
+        return Ok(());
+    }
+
+    println!();
+    println!("{}", "Slowest tools (by p95)".bold());
+    println!("  {:<32} {:>8} {:>9} {:>9} {:>9}", "TOOL", "CALLS", "P50 MS", "P95 MS", "MAX MS");
+    for tool in analytics.slowest(top) {
+        let stats = &tool.stats;
+        println!(
+            "  {:<32} {:>8} {:>9} {:>9} {:>9}",
+            tool.tool, stats.calls, stats.p50_ms, stats.p95_ms, stats.max_ms
+        );
+    }
+
+    println!();
+    println!("{}", "Most error-prone tools".bold());
+    let failing = analytics.most_failing(top);
+    if failing.is_empty() {
+        println!("  {} No failed calls", "✓".green());
+    } else {
+        println!("  {:<32} {:>8} {:>8} {:>9}", "TOOL", "CALLS", "ERRORS", "RATE");
+        for tool in failing {
+            let stats = &tool.stats;
+            let rate = format!("{:.1}%", stats.error_rate * 100.0);
+            println!("  {:<32} {:>8} {:>8} {:>9}", tool.tool, stats.calls, stats.errors, rate.red());
+        }
+    }
+    Ok(())
+}
+
+/// Applies (or with `dry_run` lists) pending SQLite schema migrations.
+fn migrate_database(config: &Config, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if config.database_url.is_some() {
+        return Err("db migrate only handles SQLite; the Postgres schema is upgraded on startup".into());
+    }
+    let path = config.database_path.as_deref().unwrap_or("aegis.db");
+    let (version, migrations) = aegis::memory::migrate_database(path, dry_run)?;
+
+    if migrations.is_empty() {
+        println!("{} {} is up to date (schema version {})", "✓".green(), path, version);
+        return Ok(());
+    }
+    let verb = if dry_run { "Would apply" } else { "Applied" };
+    println!("{} to {} (schema version {}):", verb, path, version);
+    for migration in &migrations {
+        println!("  {} {}", format!("{:>4}", migration.version).cyan(), migration.description);
+    }
+    if !dry_run {
+        println!("{} Now at schema version {}", "✓".green(), aegis::memory::latest_version());
+    }
+    Ok(())
+}
+
+/// Runs a `secrets` subcommand against the secrets file of the configured
+/// database.
+fn manage_secrets(config: &Config, action: SecretsAction) -> Result<(), Box<dyn std::error::Error>> {
+    let path = aegis::secrets::file_for_database(config.database_path.as_deref())
+        .ok_or("secrets of an in-memory database can't be managed from the command line")?;
+    let secrets = aegis::secrets::SecretsManager::new(Some(path.clone()), None);
+    let now = chrono::Utc::now();
+    let expiry = |expires: Option<String>| {
+        expires.map(|expires| aegis::secrets::parse_expiry(&expires, now)).transpose()
+    };
+
+    match action {
+        SecretsAction::List => {
+            let mut keys = secrets.list();
+            keys.sort();
+            let warnings = secrets.expiring(now);
+            for key in keys {
+                let version = secrets.versions(&key).and_then(|v| v.first().map(|v| v.version)).unwrap_or(1);
+                let status = match warnings.iter().find(|warning| warning.key == key) {
+                    Some(warning) if warning.expired => format!("expired {}", warning.expires_at).red().to_string(),
+                    Some(warning) => format!("expires in {}d", warning.days_left).yellow().to_string(),
+                    None => String::new(),
+                };
+                println!("{:<32} {} {}", key, format!("v{}", version).dimmed(), status);
+            }
+        }
+        SecretsAction::Set { key, value, description, expires } => {
+            let expires = expiry(expires)?;
+            secrets.set(&key, &secret_value(value)?, description.as_deref());
+            if expires.is_some() {
+                secrets.set_expiry(&key, expires);
+            }
+            println!("{} Stored {} in {}", "✓".green(), key, path);
+        }
+        SecretsAction::Rotate { key, value, expires } => {
+            let expires = expiry(expires)?;
+            let version = secrets
+                .rotate(&key, &secret_value(value)?, expires)
+                .ok_or_else(|| format!("secret '{}' not found", key))?;
+            println!("{} Rotated {} to version {}", "✓".green(), key, version);
+        }
+        SecretsAction::Versions { key } => {
+            let versions = secrets.versions(&key).ok_or_else(|| format!("secret '{}' not found", key))?;
+            for version in versions {
+                let current = if version.current { "current".green().to_string() } else { String::new() };
+                println!("{:>4} {} {}", format!("v{}", version.version).cyan(), version.created_at, current);
+            }
+        }
+        SecretsAction::Rollback { key, version } => {
+            let restored = secrets.rollback(&key, version)?;
+            println!("{} Restored {} as version {}", "✓".green(), key, restored);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a `models` subcommand against the local embedding model cache.
+fn manage_models(config: &Config, action: ModelsAction) -> Result<(), Box<dyn std::error::Error>> {
+    let embeddings = &config.llm.embeddings;
+    let cache = aegis::embeddings::cache_dir(embeddings);
+    let megabytes = |bytes: u64| format!("{:.1} MB", bytes as f64 / 1_048_576.0);
+
+    match action {
+        ModelsAction::List => {
+            let default = aegis::embeddings::find_model(embeddings, &embeddings.model).ok();
+            for model in aegis::embeddings::list_models(embeddings)? {
+                let marker = if default.as_ref().is_some_and(|d| d.name == model.name) { "*" } else { " " };
+                let cached = model.cached_bytes.map(|bytes| megabytes(bytes).green().to_string()).unwrap_or_default();
+                println!(
+                    "{} {:<28} {:>5} {:<48} {}",
+                    marker,
+                    model.name,
+                    model.dimensions.to_string().cyan(),
+                    model.repository.dimmed(),
+                    cached
+                );
+            }
+            println!("\nCache: {} (* = llm.embeddings.model)", cache.display());
+        }
+        ModelsAction::Download { model } => {
+            let name = model.as_deref().unwrap_or(&embeddings.model);
+            println!("Downloading {} into {}...", name, cache.display());
+            let model = aegis::embeddings::download_model(embeddings, name)?;
+            println!(
+                "{} {} ready ({} dimensions, {})",
+                "✓".green(),
+                model.name,
+                model.dimensions,
+                megabytes(model.cached_bytes.unwrap_or_default())
+            );
+        }
+        ModelsAction::Remove { model } => match aegis::embeddings::remove_model(embeddings, &model)? {
+            Some(bytes) => println!("{} Removed {} ({} freed)", "✓".green(), model, megabytes(bytes)),
+            None => println!("{} is not downloaded", model),
+        },
+    }
+    Ok(())
+}
+
+/// A secret value from the command line, or else the first line of stdin,
+/// which keeps it out of the shell history.
+fn secret_value(value: Option<String>) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(value) = value {
+        return Ok(value);
+    }
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let value = line.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        return Err("no secret value given on the command line or stdin".into());
+    }
+    Ok(value)
+}
+
+/// Prints the effective configuration to stdout and any problems to
+/// stderr, then exits non-zero if the config is unusable.
+fn validate_config(path: &std::path::Path, loaded: Result<Config, aegis::core::NexusError>, format: ConfigFormat) -> ! {
+    let config = match loaded {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} {}: {}", "✗".red(), path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    match config.render(format) {
+        Ok(rendered) => println!("{}", rendered.trim_end()),
+        Err(e) => {
+            eprintln!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    }
+
+    let problems = config.validate();
+    if !path.exists() {
+        eprintln!("{} {} not found, using defaults", "○".dimmed(), path.display());
+    }
+    for problem in &problems {
+        eprintln!("{} {}", "✗".red(), problem);
+    }
+    if problems.is_empty() {
+        eprintln!("{} Configuration is valid", "✓".green());
+        std::process::exit(0);
+    }
+    std::process::exit(1);
+}
+
+/// Updates the running binary to the latest release.
+async fn self_update(config: &Config, check_only: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let current = aegis::update::current_version();
+    let updater = Updater::new(&config.update);
+    let Some(release) = updater.check().await? else {
+        println!("{} Aegis {} is the latest release", "✓".green(), current);
+        return Ok(());
+    };
+    println!("Aegis {} is available (running {}): {}", release.version.white().bold(), current, release.url);
+    if check_only {
+        return Ok(());
+    }
+
+    let target = aegis::update::current_executable()?;
+    println!("Downloading {}...", aegis::update::asset_name());
+    let binary = updater.download(&release).await?;
+    aegis::update::install(&binary, &target)?;
+    println!("{} Updated {} to {}; restart running servers to use it", "✓".green(), target.display(), release.version);
+    Ok(())
+}
+
+/// Runs a `service` subcommand.
+fn manage_service(config_path: &std::path::Path, action: ServiceAction) -> Result<(), Box<dyn std::error::Error>> {
+    let binary = std::env::current_exe()?.canonicalize()?;
+    match action {
+        ServiceAction::Install { name, user, dry_run } => {
+            let spec = aegis::service::ServiceSpec {
+                name,
+                binary,
+                // A missing file means defaults, which the service gets without --config
+                config: config_path.canonicalize().ok(),
+                working_dir: std::env::current_dir()?,
+                user,
+            };
+            if dry_run {
+                match cfg!(windows) {
+                    true => print!("{}", spec.winsw_config()),
+                    false => print!("{}", spec.systemd_unit()),
+                }
+                return Ok(());
+            }
+            let path = aegis::service::install(&spec)?;
+            println!("{} Installed and started service {} ({})", "✓".green(), spec.name, path.display());
+        }
+        ServiceAction::Uninstall { name } => {
+            aegis::service::uninstall(&name, &binary)?;
+            println!("{} Removed service {}", "✓".green(), name);
+        }
+        ServiceAction::Status { name } => {
+            print!("{}", aegis::service::status(&name)?);
+        }
+    }
+    Ok(())
+}
+
 /// Shows server info.
-fn show_info(config: &Config) {
+fn show_info(config: &Config, update: Option<&Release>) {
     let version = env!("CARGO_PKG_VERSION");
 
     println!();
@@ -339,12 +1166,21 @@ fn show_info(config: &Config) {
     println!("{}", "═".repeat(40).cyan());
     println!();
     println!("  {} {}", "Version:".dimmed(), version.white());
+    if let Some(release) = update {
+        println!("  {} {} available, run {}", "Update:".dimmed(), release.version.yellow(), "aegis self-update".yellow());
+    }
     println!("  {} {} v{}", "Server:".dimmed(), 
              config.server_name.white(), config.server_version);
     println!("  {} {}:{}", "Bind:".dimmed(), 
              config.host.white(), config.port.to_string().white());
-    println!("  {} {}", "Memory:".dimmed(), 
-             config.database_path.as_deref().unwrap_or("aegis.db").white());
+    let memory = match &config.database_url {
+        Some(url) => aegis::memory::redact_url(url),
+        None => config.database_path.clone().unwrap_or_else(|| "aegis.db".to_string()),
+    };
+    println!("  {} {}", "Memory:".dimmed(), memory.white());
+    if let Some(url) = &config.redis.url {
+        println!("  {} {}", "Redis:".dimmed(), aegis::memory::redact_url(url).white());
+    }
     println!();
     println!("{}", "Tools".cyan().bold());
     println!("{}", "─".repeat(40).cyan());
@@ -358,9 +1194,9 @@ fn show_info(config: &Config) {
     println!();
     println!("{}", "Capabilities".cyan().bold());
     println!("{}", "─".repeat(40).cyan());
-    println!("  {} MCP 2024-11-05 compliant", "✓".green());
+    println!("  {} MCP 2025-03-26 & 2024-11-05 compliant", "✓".green());
     println!("  {} persistent memory (SQLite)", "✓".green());
-    println!("  {} stdio & HTTP/SSE transports", "✓".green());
+    println!("  {} stdio, streamable HTTP & HTTP/SSE transports", "✓".green());
     println!();
 }
 
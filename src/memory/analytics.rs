@@ -0,0 +1,207 @@
+//! Tool usage analytics built from the audit log.
+//!
+//! Backs `/dashboard/api/analytics` and `aegis report`: per-tool latency
+//! percentiles, error rates and argument sizes over a window, plus the same
+//! figures per time bucket for charting.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::memory::store::{MemoryError, MemoryStore, ToolCallRecord};
+
+/// Call statistics of one tool (or of every tool, in a time bucket).
+#[derive(Debug, Clone, Serialize)]
+pub struct CallStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    /// Argument size percentiles, over the calls that recorded one.
+    pub args_p50_bytes: Option<u64>,
+    pub args_p95_bytes: Option<u64>,
+    pub args_max_bytes: Option<u64>,
+}
+
+/// Statistics of one tool over the whole window.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolStats {
+    pub tool: String,
+    #[serde(flatten)]
+    pub stats: CallStats,
+}
+
+/// Statistics of the calls that started in one time bucket.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    /// Start of the bucket (RFC 3339).
+    pub start: String,
+    #[serde(flatten)]
+    pub stats: CallStats,
+}
+
+/// Tool usage over a window.
+#[derive(Debug, Clone, Serialize)]
+pub struct Analytics {
+    /// Start of the window, if bounded.
+    pub since: Option<String>,
+    /// Width of each bucket in `series`, in seconds.
+    pub bucket_secs: i64,
+    /// Per-tool statistics, most called first.
+    pub tools: Vec<ToolStats>,
+    /// Statistics per bucket, oldest first; buckets without calls are left out.
+    pub series: Vec<Bucket>,
+}
+
+impl Analytics {
+    /// The `top` tools with the highest p95 latency.
+    pub fn slowest(&self, top: usize) -> Vec<&ToolStats> {
+        let mut tools: Vec<&ToolStats> = self.tools.iter().collect();
+        tools.sort_by(|a, b| b.stats.p95_ms.cmp(&a.stats.p95_ms).then(b.stats.max_ms.cmp(&a.stats.max_ms)));
+        tools.truncate(top);
+        tools
+    }
+
+    /// The `top` tools with the highest error rate, leaving out those
+    /// without errors.
+    pub fn most_failing(&self, top: usize) -> Vec<&ToolStats> {
+        let mut tools: Vec<&ToolStats> = self.tools.iter().filter(|t| t.stats.errors > 0).collect();
+        tools.sort_by(|a, b| {
+            b.stats
+                .error_rate
+                .total_cmp(&a.stats.error_rate)
+                .then(b.stats.errors.cmp(&a.stats.errors))
+        });
+        tools.truncate(top);
+        tools
+    }
+}
+
+/// Builds analytics from the tool calls recorded since `since`, optionally
+/// for one tool only, bucketing the series by `bucket`.
+pub async fn build_analytics(
+    store: &dyn MemoryStore,
+    since: Option<DateTime<Utc>>,
+    bucket: Duration,
+    tool: Option<&str>,
+) -> Result<Analytics, MemoryError> {
+    let calls = store.list_tool_calls(since.map(|ts| ts.to_rfc3339()).as_deref()).await?;
+    let calls: Vec<&ToolCallRecord> = calls.iter().filter(|c| tool.is_none_or(|tool| c.tool == tool)).collect();
+    Ok(analyze(&calls, since, bucket))
+}
+
+fn analyze(calls: &[&ToolCallRecord], since: Option<DateTime<Utc>>, bucket: Duration) -> Analytics {
+    let bucket_secs = bucket.num_seconds().max(1);
+
+    let mut by_tool: BTreeMap<&str, Vec<&ToolCallRecord>> = BTreeMap::new();
+    let mut by_bucket: BTreeMap<i64, Vec<&ToolCallRecord>> = BTreeMap::new();
+    for &call in calls {
+        by_tool.entry(&call.tool).or_default().push(call);
+        if let Ok(started_at) = DateTime::parse_from_rfc3339(&call.started_at) {
+            let start = started_at.timestamp().div_euclid(bucket_secs) * bucket_secs;
+            by_bucket.entry(start).or_default().push(call);
+        }
+    }
+
+    let mut tools: Vec<ToolStats> = by_tool
+        .into_iter()
+        .map(|(tool, calls)| ToolStats { tool: tool.to_string(), stats: call_stats(&calls) })
+        .collect();
+    tools.sort_by_key(|t| std::cmp::Reverse(t.stats.calls));
+
+    let series = by_bucket
+        .into_iter()
+        .filter_map(|(start, calls)| {
+            let start = DateTime::from_timestamp(start, 0)?;
+            Some(Bucket { start: start.to_rfc3339(), stats: call_stats(&calls) })
+        })
+        .collect();
+
+    Analytics { since: since.map(|ts| ts.to_rfc3339()), bucket_secs, tools, series }
+}
+
+fn call_stats(calls: &[&ToolCallRecord]) -> CallStats {
+    let mut durations: Vec<u64> = calls.iter().map(|c| c.duration_ms).collect();
+    durations.sort_unstable();
+    let mut sizes: Vec<u64> = calls.iter().filter_map(|c| c.args_bytes).collect();
+    sizes.sort_unstable();
+
+    let count = calls.len() as u64;
+    let errors = calls.iter().filter(|c| !c.success).count() as u64;
+    CallStats {
+        calls: count,
+        errors,
+        error_rate: if count == 0 { 0.0 } else { (errors as f64 / count as f64 * 10_000.0).round() / 10_000.0 },
+        p50_ms: percentile(&durations, 50).unwrap_or(0),
+        p95_ms: percentile(&durations, 95).unwrap_or(0),
+        p99_ms: percentile(&durations, 99).unwrap_or(0),
+        max_ms: durations.last().copied().unwrap_or(0),
+        args_p50_bytes: percentile(&sizes, 50),
+        args_p95_bytes: percentile(&sizes, 95),
+        args_max_bytes: sizes.last().copied(),
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[u64], p: usize) -> Option<u64> {
+    let rank = (sorted.len() * p).div_ceil(100).saturating_sub(1);
+    sorted.get(rank).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteStore;
+
+    fn call(tool: &str, started_at: &str, success: bool, duration_ms: u64, args_bytes: Option<u64>) -> ToolCallRecord {
+        ToolCallRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.to_string(),
+            session_id: "default".to_string(),
+            api_key_hash: None,
+            api_key_name: None,
+            success,
+            error: None,
+            started_at: started_at.to_string(),
+            duration_ms,
+            args_bytes,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_analytics() {
+        let store = SqliteStore::in_memory().unwrap();
+        for minute in 0..10u64 {
+            let started_at = format!("2024-01-01T10:{:02}:00+00:00", minute * 5);
+            store.record_tool_call(&call("echo", &started_at, true, minute + 1, Some(20))).await.unwrap();
+        }
+        for (started_at, success) in [("2024-01-01T10:10:00+00:00", false), ("2024-01-01T11:30:00+00:00", true)] {
+            store.record_tool_call(&call("web.crawl", started_at, success, 900, None)).await.unwrap();
+        }
+
+        let analytics = build_analytics(&store, None, Duration::hours(1), None).await.unwrap();
+        let echo = &analytics.tools[0];
+        assert_eq!((echo.tool.as_str(), echo.stats.calls), ("echo", 10));
+        assert_eq!((echo.stats.p50_ms, echo.stats.p95_ms, echo.stats.max_ms), (5, 10, 10));
+        assert_eq!(echo.stats.args_p95_bytes, Some(20));
+
+        let crawl = &analytics.tools[1];
+        assert_eq!(crawl.stats.error_rate, 0.5);
+        assert_eq!(crawl.stats.args_max_bytes, None);
+
+        let starts: Vec<&str> = analytics.series.iter().map(|b| b.start.as_str()).collect();
+        assert_eq!(starts, ["2024-01-01T10:00:00+00:00", "2024-01-01T11:00:00+00:00"]);
+        assert_eq!(analytics.series[0].stats.calls, 11);
+
+        assert_eq!(analytics.slowest(1)[0].tool, "web.crawl");
+        let failing: Vec<&str> = analytics.most_failing(5).iter().map(|t| t.tool.as_str()).collect();
+        assert_eq!(failing, ["web.crawl"]);
+
+        let echo_only = build_analytics(&store, None, Duration::hours(1), Some("echo")).await.unwrap();
+        assert_eq!(echo_only.tools.len(), 1);
+        assert_eq!(echo_only.series.len(), 1);
+    }
+}
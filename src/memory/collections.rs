@@ -0,0 +1,248 @@
+//! Schema-validated KV collections.
+//!
+//! A collection is a named slice of the KV store (`col:{name}:{key}`) whose
+//! values must satisfy a JSON Schema. Collections also declare indexed
+//! fields, which `memory.list` can filter and sort by.
+
+use jsonschema::Validator;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tracing::error;
+
+use crate::core::CollectionConfig;
+
+/// Maximum number of validation errors reported for one value.
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// A compiled collection.
+#[derive(Debug)]
+pub struct Collection {
+    /// Collection name.
+    pub name: String,
+    /// Fields that may be filtered and sorted by.
+    pub indexes: Vec<String>,
+    validator: Validator,
+}
+
+impl Collection {
+    /// Compiles a collection from its configuration.
+    pub fn new(config: &CollectionConfig) -> Result<Self, String> {
+        let validator = jsonschema::validator_for(&config.schema)
+            .map_err(|e| format!("invalid schema for collection '{}': {}", config.name, e))?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            indexes: config.indexes.clone(),
+            validator,
+        })
+    }
+
+    /// Prefix of the KV keys holding this collection's entries.
+    pub fn key_prefix(&self) -> String {
+        format!("col:{}:", self.name)
+    }
+
+    /// Validates a value against the collection's schema.
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(value)
+            .take(MAX_REPORTED_ERRORS)
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                if path.is_empty() {
+                    e.to_string()
+                } else {
+                    format!("{}: {}", path, e)
+                }
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "value does not match the schema of collection '{}': {}",
+                self.name,
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// Ensures a field is indexed.
+    pub fn check_indexed(&self, field: &str) -> Result<(), String> {
+        if self.indexes.iter().any(|i| i == field) {
+            Ok(())
+        } else {
+            Err(format!(
+                "'{}' is not an indexed field of collection '{}' (indexed: {})",
+                field,
+                self.name,
+                self.indexes.join(", ")
+            ))
+        }
+    }
+
+    /// Returns whether a value matches every `field: expected` pair of a
+    /// filter. Only indexed fields may be filtered on.
+    pub fn matches(&self, value: &Value, filter: &Map<String, Value>) -> Result<bool, String> {
+        for (path, expected) in filter {
+            self.check_indexed(path)?;
+            if field(value, path) != Some(expected) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Sorts `(key, value)` entries by an indexed field.
+    pub fn sort(&self, entries: &mut [(String, Value)], path: &str, descending: bool) -> Result<(), String> {
+        self.check_indexed(path)?;
+        entries.sort_by(|(_, a), (_, b)| {
+            let (a, b) = (field(a, path), field(b, path));
+            match (a, b, descending) {
+                // Missing values stay last in either direction
+                (Some(_), Some(_), true) => compare_fields(b, a),
+                _ => compare_fields(a, b),
+            }
+        });
+        Ok(())
+    }
+}
+
+/// All configured collections.
+#[derive(Debug, Default)]
+pub struct Collections {
+    collections: HashMap<String, Collection>,
+}
+
+impl Collections {
+    /// Compiles the configured collections. Collections with an invalid
+    /// schema are logged and left out, so writes to them are rejected.
+    pub fn from_config(configs: &[CollectionConfig]) -> Self {
+        let collections = configs
+            .iter()
+            .filter_map(|config| match Collection::new(config) {
+                Ok(collection) => Some((config.name.clone(), collection)),
+                Err(e) => {
+                    error!("{}", e);
+                    None
+                }
+            })
+            .collect();
+
+        Self { collections }
+    }
+
+    /// Looks up a collection by name.
+    pub fn get(&self, name: &str) -> Option<&Collection> {
+        self.collections.get(name)
+    }
+
+    /// Names of the collections, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.collections.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the number of collections.
+    pub fn len(&self) -> usize {
+        self.collections.len()
+    }
+
+    /// Returns whether no collections are configured.
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+}
+
+/// Looks up a dotted field path ("address.city", "tags.0") in a value.
+fn field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    value.pointer(&format!("/{}", path.replace('.', "/")))
+}
+
+/// Orders two field values: numbers numerically, strings lexically, and
+/// missing values last.
+fn compare_fields(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(x)), Some(Value::String(y))) => x.cmp(y),
+        (Some(x), Some(y)) => x.to_string().cmp(&y.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tasks() -> Collection {
+        Collection::new(&CollectionConfig {
+            name: "tasks".to_string(),
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "priority": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["title"]
+            }),
+            indexes: vec!["priority".to_string()],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validation() {
+        let tasks = tasks();
+        assert!(tasks.validate(&json!({"title": "Ship it", "priority": 1})).is_ok());
+
+        let err = tasks.validate(&json!({"priority": 0})).unwrap_err();
+        assert!(err.contains("title"), "{}", err);
+        assert!(err.contains("/priority"), "{}", err);
+    }
+
+    #[test]
+    fn test_indexes_and_ordering() {
+        let tasks = tasks();
+        assert!(tasks.check_indexed("priority").is_ok());
+        assert!(tasks.check_indexed("title").is_err());
+
+        let filter = json!({"priority": 2});
+        let filter = filter.as_object().unwrap();
+        assert!(tasks.matches(&json!({"title": "a", "priority": 2}), filter).unwrap());
+        assert!(!tasks.matches(&json!({"title": "b"}), filter).unwrap());
+        assert!(tasks.matches(&json!({}), json!({"title": "a"}).as_object().unwrap()).is_err());
+
+        let mut entries = vec![
+            ("none".to_string(), json!({"title": "x"})),
+            ("low".to_string(), json!({"priority": 2})),
+            ("high".to_string(), json!({"priority": 10})),
+        ];
+        tasks.sort(&mut entries, "priority", false).unwrap();
+        let keys: Vec<_> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["low", "high", "none"]);
+
+        tasks.sort(&mut entries, "priority", true).unwrap();
+        let keys: Vec<_> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["high", "low", "none"]);
+    }
+
+    #[test]
+    fn test_invalid_schema_is_skipped() {
+        let collections = Collections::from_config(&[CollectionConfig {
+            name: "broken".to_string(),
+            schema: json!({"type": 12}),
+            indexes: vec![],
+        }]);
+        assert!(collections.get("broken").is_none());
+    }
+}
@@ -0,0 +1,341 @@
+//! Reporting exports built from the audit log.
+//!
+//! Backs `aegis export metrics|audit|usage`: raw tool call records, per-tool
+//! metrics, and per-day usage by caller, rendered as CSV or JSON.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::memory::store::{MemoryError, MemoryStore, ToolCallRecord};
+
+/// Which report to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    /// Per-tool call counts, error rates, and latencies.
+    Metrics,
+    /// Every recorded tool call.
+    Audit,
+    /// Calls per day, caller, and tool.
+    Usage,
+}
+
+impl FromStr for ExportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "metrics" => Ok(Self::Metrics),
+            "audit" => Ok(Self::Audit),
+            "usage" => Ok(Self::Usage),
+            other => Err(format!("unknown report '{}' (expected metrics, audit, or usage)", other)),
+        }
+    }
+}
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    Csv,
+    /// A JSON array of objects.
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown format '{}' (expected csv or json)", other)),
+        }
+    }
+}
+
+/// A tabular report.
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// Column names.
+    pub columns: Vec<&'static str>,
+    /// Rows, one value per column.
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Report {
+    /// Renders the report in the given format.
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => {
+                let json = serde_json::to_string_pretty(&self.to_json()).unwrap_or_else(|_| "[]".to_string());
+                format!("{}\n", json)
+            }
+        }
+    }
+
+    /// Renders the report as CSV (RFC 4180 quoting).
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = self.columns.iter().map(|c| csv_field(c)).collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
+
+        for row in &self.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    Value::Null => String::new(),
+                    Value::String(s) => csv_field(s),
+                    other => csv_field(&other.to_string()),
+                })
+                .collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the report as an array of objects keyed by column.
+    pub fn to_json(&self) -> Value {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object: Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| (column.to_string(), value.clone()))
+                    .collect();
+                Value::Object(object)
+            })
+            .collect();
+        Value::Array(rows)
+    }
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote, or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parses a `--since` value: a relative window (`30m`, `24h`, `7d`, `2w`),
+/// a date (`2024-01-31`), or an RFC 3339 timestamp.
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let window = parse_window(value)
+        .map_err(|_| format!("invalid --since '{}' (use e.g. 24h, 7d, or 2024-01-31)", value))?;
+    Ok(now - window)
+}
+
+/// Parses a relative window: `30m`, `24h`, `7d` or `2w`.
+pub fn parse_window(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("invalid window '{}' (use e.g. 30m, 24h, or 7d)", value);
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Builds a report from the tool calls recorded since `since`.
+pub async fn build_report(
+    store: &dyn MemoryStore,
+    kind: ExportKind,
+    since: Option<DateTime<Utc>>,
+) -> Result<Report, MemoryError> {
+    let since = since.map(|ts| ts.to_rfc3339());
+    let calls = store.list_tool_calls(since.as_deref()).await?;
+
+    Ok(match kind {
+        ExportKind::Audit => audit_report(&calls),
+        ExportKind::Metrics => metrics_report(&calls),
+        ExportKind::Usage => usage_report(&calls),
+    })
+}
+
+fn audit_report(calls: &[ToolCallRecord]) -> Report {
+    Report {
+        columns: vec![
+            "started_at", "tool", "session_id", "api_key_name", "api_key_hash", "success", "duration_ms", "error",
+        ],
+        rows: calls
+            .iter()
+            .map(|c| {
+                vec![
+                    json!(c.started_at),
+                    json!(c.tool),
+                    json!(c.session_id),
+                    json!(c.api_key_name),
+                    json!(c.api_key_hash),
+                    json!(c.success),
+                    json!(c.duration_ms),
+                    json!(c.error),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn metrics_report(calls: &[ToolCallRecord]) -> Report {
+    let mut by_tool: BTreeMap<&str, Vec<&ToolCallRecord>> = BTreeMap::new();
+    for call in calls {
+        by_tool.entry(&call.tool).or_default().push(call);
+    }
+
+    let rows = by_tool
+        .into_iter()
+        .map(|(tool, calls)| {
+            let mut durations: Vec<u64> = calls.iter().map(|c| c.duration_ms).collect();
+            durations.sort_unstable();
+
+            let count = calls.len();
+            let errors = calls.iter().filter(|c| !c.success).count();
+            let total: u64 = durations.iter().sum();
+            let p95 = durations[((count * 95).div_ceil(100)).saturating_sub(1)];
+
+            vec![
+                json!(tool),
+                json!(count),
+                json!(errors),
+                json!(round(errors as f64 / count as f64, 4)),
+                json!(round(total as f64 / count as f64, 1)),
+                json!(p95),
+                json!(durations[count - 1]),
+            ]
+        })
+        .collect();
+
+    Report {
+        columns: vec!["tool", "calls", "errors", "error_rate", "avg_ms", "p95_ms", "max_ms"],
+        rows,
+    }
+}
+
+/// Usage grouping: (day, api key, session, tool).
+type UsageKey<'a> = (&'a str, &'a str, &'a str, &'a str);
+
+fn usage_report(calls: &[ToolCallRecord]) -> Report {
+    // key -> (calls, errors, total ms)
+    let mut usage: BTreeMap<UsageKey, (u64, u64, u64)> = BTreeMap::new();
+    for call in calls {
+        let day = call.started_at.get(..10).unwrap_or(&call.started_at);
+        let key = call
+            .api_key_name
+            .as_deref()
+            .or(call.api_key_hash.as_deref())
+            .unwrap_or("");
+        let entry = usage.entry((day, key, &call.session_id, &call.tool)).or_default();
+        entry.0 += 1;
+        entry.1 += u64::from(!call.success);
+        entry.2 += call.duration_ms;
+    }
+
+    let rows = usage
+        .into_iter()
+        .map(|((day, key, session, tool), (count, errors, total_ms))| {
+            vec![
+                json!(day),
+                if key.is_empty() { Value::Null } else { json!(key) },
+                json!(session),
+                json!(tool),
+                json!(count),
+                json!(errors),
+                json!(total_ms),
+            ]
+        })
+        .collect();
+
+    Report {
+        columns: vec!["day", "api_key", "session_id", "tool", "calls", "errors", "total_ms"],
+        rows,
+    }
+}
+
+fn round(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteStore;
+
+    fn call(id: &str, tool: &str, started_at: &str, success: bool, duration_ms: u64) -> ToolCallRecord {
+        ToolCallRecord {
+            id: id.to_string(),
+            tool: tool.to_string(),
+            session_id: "default".to_string(),
+            api_key_hash: None,
+            api_key_name: None,
+            success,
+            error: (!success).then(|| "boom, \"quoted\"".to_string()),
+            started_at: started_at.to_string(),
+            duration_ms,
+            args_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2024-01-10T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_since("7d", now).unwrap().to_rfc3339(), "2024-01-03T12:00:00+00:00");
+        assert_eq!(parse_since("2h", now).unwrap().to_rfc3339(), "2024-01-10T10:00:00+00:00");
+        assert_eq!(parse_since("2024-01-01", now).unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert!(parse_since("7x", now).is_err());
+        assert!(parse_since("", now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reports() {
+        let store = SqliteStore::in_memory().unwrap();
+        for record in [
+            call("1", "echo", "2024-01-01T10:00:00+00:00", true, 10),
+            call("2", "echo", "2024-01-02T10:00:00+00:00", false, 30),
+            call("3", "cmd.exec", "2024-01-02T11:00:00+00:00", true, 5),
+        ] {
+            store.record_tool_call(&record).await.unwrap();
+        }
+
+        let metrics = build_report(&store, ExportKind::Metrics, None).await.unwrap();
+        let json = metrics.to_json();
+        assert_eq!(json[1]["tool"], "echo");
+        assert_eq!(json[1]["calls"], 2);
+        assert_eq!(json[1]["error_rate"], 0.5);
+        assert_eq!(json[1]["avg_ms"], 20.0);
+        assert_eq!(json[1]["max_ms"], 30);
+
+        let since = parse_since("2024-01-02", Utc::now()).unwrap();
+        let usage = build_report(&store, ExportKind::Usage, Some(since)).await.unwrap();
+        assert_eq!(usage.rows.len(), 2);
+        assert_eq!(usage.rows[0][0], "2024-01-02");
+
+        let audit = build_report(&store, ExportKind::Audit, Some(since)).await.unwrap();
+        let csv = audit.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "started_at,tool,session_id,api_key_name,api_key_hash,success,duration_ms,error");
+        assert_eq!(lines[1], "2024-01-02T10:00:00+00:00,echo,default,,,false,30,\"boom, \"\"quoted\"\"\"");
+    }
+}
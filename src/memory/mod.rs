@@ -2,15 +2,103 @@
 //!
 //! This module provides:
 //! - SQLite-based storage for conversations, messages, and key-value data
+//! - Postgres-based storage shared by several instances (`postgres` feature)
+//! - Redis-backed key-value data shared by several instances (`redis` feature)
 //! - Memory trait for abstraction over storage backends
+//! - Versioned migrations of the SQLite schema
+//! - Schema-validated KV collections
+//! - CSV/JSON reporting exports of the audit log
+//! - Tool usage analytics (latency percentiles, error rates, time series)
 //! - Resource types for MCP resources/list and resources/read
 
 mod store;
 mod sqlite;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "redis")]
+mod redis_kv;
 mod schema;
+mod collections;
+mod export;
+mod analytics;
 
-pub use store::{MemoryStore, Conversation, Message, KeyValue};
-pub use sqlite::SqliteStore;
-pub use schema::initialize_schema;
+pub use store::{check_namespace, is_reserved_key, namespaced_key, RESERVED_KEY_PREFIXES, MemoryError, MemoryStore, Conversation, ConversationFilter, ConversationUpdate, Message, MessageFilter, KeyValue, KvOp, LlmUsageRecord, TaskRun, ToolCallRecord, WorkflowVersion, WorkflowRun};
+pub use sqlite::{SqliteStore, DEFAULT_READ_CONNECTIONS};
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+#[cfg(feature = "redis")]
+pub use redis_kv::{RedisConnection, RedisKvStore};
+pub use schema::{initialize_schema, latest_version, migrate_database, Migration, MIGRATIONS};
+pub use collections::{Collection, Collections};
+pub use export::{build_report, parse_since, parse_window, ExportFormat, ExportKind, Report};
+pub use analytics::{build_analytics, Analytics, Bucket, CallStats, ToolStats};
 
 
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::core::Config;
+
+/// Path of a file kept next to the SQLite database, with its extension
+/// replaced by `extension` (`aegis.db` keeps secrets in `aegis.secrets`).
+/// `None` for in-memory databases, whose companions stay in memory too.
+pub fn companion_file(database_path: Option<&str>, extension: &str) -> Option<String> {
+    let database_path = database_path.filter(|path| !SqliteStore::is_in_memory(path))?;
+    let database = Path::new(database_path);
+    let mut companion = database.with_extension(extension);
+    // Never the database itself (e.g. a database named `aegis.secrets`)
+    if companion == database {
+        companion = format!("{}.{}", database_path, extension).into();
+    }
+    Some(companion.to_string_lossy().into_owned())
+}
+
+/// Opens the memory store selected by the configuration: Postgres when
+/// `database_url` is set, otherwise SQLite at `database_path`, with its
+/// key-value data in Redis when `redis.url` is set.
+pub fn open_store(config: &Config) -> Result<Arc<dyn MemoryStore>, MemoryError> {
+    let store = open_database(config)?;
+
+    match &config.redis.url {
+        Some(url) if config.redis.kv => {
+            #[cfg(feature = "redis")]
+            return Ok(Arc::new(RedisKvStore::new(store, url, &config.redis.key_prefix)?));
+            #[cfg(not(feature = "redis"))]
+            return Err(MemoryError::Database(format!(
+                "redis.url '{}' needs Aegis built with the 'redis' feature",
+                redact_url(url)
+            )));
+        }
+        _ => Ok(store),
+    }
+}
+
+fn open_database(config: &Config) -> Result<Arc<dyn MemoryStore>, MemoryError> {
+    if let Some(url) = &config.database_url {
+        #[cfg(feature = "postgres")]
+        return Ok(Arc::new(PostgresStore::new(url)?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(MemoryError::Database(format!(
+            "database_url '{}' needs Aegis built with the 'postgres' feature",
+            redact_url(url)
+        )));
+    }
+
+    let db_path = config.database_path.as_deref().unwrap_or("aegis.db");
+    Ok(Arc::new(SqliteStore::new(db_path)?))
+}
+
+/// Hides the password of a database URL for display.
+pub fn redact_url(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme), Some(at)) if at > scheme => {
+            let credentials = &url[scheme + 3..at];
+            match credentials.split_once(':') {
+                Some((user, _)) => format!("{}{}:***{}", &url[..scheme + 3], user, &url[at..]),
+                None => url.to_string(),
+            }
+        }
+        _ => url.to_string(),
+    }
+}
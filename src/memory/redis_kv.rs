@@ -0,0 +1,510 @@
+//! Redis-backed key-value storage.
+//!
+//! Several Aegis instances behind a load balancer each have their own
+//! SQLite file, so key-value data written through one replica is invisible
+//! to the others. [`RedisKvStore`] moves the key-value part of a store to
+//! Redis and leaves everything else (conversations, workflows, the audit
+//! log) with the wrapped store. Enabled by the `redis` cargo feature and
+//! selected with `redis.url`.
+//!
+//! Each entry is a hash at `<key_prefix>kv:<key>` holding the JSON value and
+//! its timestamps; TTLs are native Redis expiries.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use redis::aio::{ConnectionManager, MultiplexedConnection};
+use redis::{AsyncCommands, Pipeline};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+
+use crate::memory::store::{
+    Conversation, ConversationFilter, ConversationUpdate, KeyValue, KvOp, LlmUsageRecord, MemoryError, MemoryStore,
+    Message, MessageFilter, TaskRun, ToolCallRecord, WorkflowRun, WorkflowVersion,
+};
+
+/// How often a batch is retried when another client changes its keys.
+const MAX_BATCH_ATTEMPTS: usize = 8;
+
+/// A lazily opened, reconnecting Redis connection with a key prefix.
+pub struct RedisConnection {
+    client: redis::Client,
+    manager: OnceCell<ConnectionManager>,
+    prefix: String,
+}
+
+impl std::fmt::Debug for RedisConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisConnection")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisConnection {
+    /// Creates a connection for the given URL; nothing is opened until
+    /// first use.
+    pub fn new(url: &str, prefix: &str) -> Result<Self, MemoryError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| MemoryError::Database(format!("Invalid redis.url: {}", e)))?;
+        Ok(Self {
+            client,
+            manager: OnceCell::new(),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// Returns the shared connection, opening it the first time.
+    pub async fn manager(&self) -> Result<ConnectionManager, MemoryError> {
+        let manager = self
+            .manager
+            .get_or_try_init(|| async {
+                let manager = self.client.get_connection_manager().await.map_err(db_err)?;
+                info!("Connected to Redis");
+                Ok::<_, MemoryError>(manager)
+            })
+            .await?;
+        Ok(manager.clone())
+    }
+
+    /// Opens a connection of its own, for commands like WATCH that change
+    /// connection state.
+    async fn dedicated(&self) -> Result<MultiplexedConnection, MemoryError> {
+        self.client.get_multiplexed_async_connection().await.map_err(db_err)
+    }
+
+    /// Returns the full Redis key for a name.
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+fn db_err(e: impl std::fmt::Display) -> MemoryError {
+    MemoryError::Database(e.to_string())
+}
+
+fn parse_json(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or(Value::Null)
+}
+
+/// Escapes the glob characters of a SCAN pattern.
+fn escape_pattern(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Queues the commands writing one entry, keeping its original creation
+/// time.
+fn queue_write(pipe: &mut Pipeline, key: &str, value: &Value, ttl_secs: Option<u64>) -> Result<(), MemoryError> {
+    let now = Utc::now();
+    let now_str = now.to_rfc3339();
+    let value_str =
+        serde_json::to_string(value).map_err(|e| MemoryError::Serialization(e.to_string()))?;
+
+    pipe.hset_nx(key, "created_at", &now_str).ignore();
+    pipe.hset_multiple(key, &[("value", &value_str), ("updated_at", &now_str)]).ignore();
+    match ttl_secs {
+        Some(secs) => {
+            let expires_at = (now + chrono::Duration::seconds(secs as i64)).to_rfc3339();
+            pipe.hset(key, "expires_at", expires_at).ignore();
+            pipe.expire(key, secs as i64).ignore();
+        }
+        None => {
+            pipe.hdel(key, "expires_at").ignore();
+            pipe.persist(key).ignore();
+        }
+    }
+    Ok(())
+}
+
+/// A memory store keeping key-value data in Redis and delegating everything
+/// else to another store.
+#[derive(Debug)]
+pub struct RedisKvStore {
+    inner: Arc<dyn MemoryStore>,
+    redis: RedisConnection,
+}
+
+impl RedisKvStore {
+    /// Wraps `inner`, moving its key-value data to Redis at `url`.
+    pub fn new(inner: Arc<dyn MemoryStore>, url: &str, prefix: &str) -> Result<Self, MemoryError> {
+        info!("Redis key-value store configured");
+        Ok(Self {
+            inner,
+            redis: RedisConnection::new(url, prefix)?,
+        })
+    }
+
+    fn kv_key(&self, key: &str) -> String {
+        self.redis.key(&format!("kv:{}", key))
+    }
+
+    /// Runs a batch once under WATCH. Returns None if another client
+    /// changed one of its keys before it committed.
+    async fn try_batch(
+        &self,
+        conn: &mut MultiplexedConnection,
+        ops: &[KvOp],
+    ) -> Result<Option<Vec<Value>>, MemoryError> {
+        let keys: BTreeSet<String> = ops.iter().map(|op| self.kv_key(op.key())).collect();
+        redis::cmd("WATCH").arg(&keys).query_async::<()>(conn).await.map_err(db_err)?;
+
+        // Batch writes, applied together at the end (None = deleted)
+        let mut pending: HashMap<String, Option<(Value, Option<u64>)>> = HashMap::new();
+        let mut results = Vec::with_capacity(ops.len());
+        for op in ops {
+            match self.apply_kv_op(conn, &mut pending, op).await {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    redis::cmd("UNWATCH").query_async::<()>(conn).await.ok();
+                    return Err(e);
+                }
+            }
+        }
+
+        if pending.is_empty() {
+            redis::cmd("UNWATCH").query_async::<()>(conn).await.map_err(db_err)?;
+            return Ok(Some(results));
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for (key, state) in &pending {
+            match state {
+                Some((value, ttl_secs)) => queue_write(&mut pipe, key, value, *ttl_secs)?,
+                None => {
+                    pipe.del(key).ignore();
+                }
+            }
+        }
+        let committed: Option<redis::Value> = pipe.query_async(conn).await.map_err(db_err)?;
+        Ok(committed.map(|_| results))
+    }
+
+    /// Applies a single batch operation to the pending writes.
+    async fn apply_kv_op(
+        &self,
+        conn: &mut MultiplexedConnection,
+        pending: &mut HashMap<String, Option<(Value, Option<u64>)>>,
+        op: &KvOp,
+    ) -> Result<Value, MemoryError> {
+        let key = self.kv_key(op.key());
+        let current = match pending.get(&key) {
+            Some(state) => state.as_ref().map(|(value, _)| value.clone()),
+            None => {
+                let value: Option<String> = conn.hget(&key, "value").await.map_err(db_err)?;
+                value.as_deref().map(parse_json)
+            }
+        };
+
+        match op {
+            KvOp::Get { .. } => Ok(current.unwrap_or(Value::Null)),
+            KvOp::Set { value, ttl_secs, .. } => {
+                pending.insert(key, Some((value.clone(), *ttl_secs)));
+                Ok(Value::Null)
+            }
+            KvOp::Delete { .. } => {
+                pending.insert(key, None);
+                Ok(json!(current.is_some()))
+            }
+            KvOp::Incr { key: name, by } => {
+                let current = match current {
+                    None => 0,
+                    Some(value) => value.as_i64().ok_or_else(|| {
+                        MemoryError::InvalidOperation(format!("'{}' does not hold an integer", name))
+                    })?,
+                };
+                let next = current.checked_add(*by).ok_or_else(|| {
+                    MemoryError::InvalidOperation(format!("'{}' would overflow", name))
+                })?;
+                pending.insert(key, Some((json!(next), None)));
+                Ok(json!(next))
+            }
+            KvOp::Check { key: name, expected } => {
+                let actual = current.unwrap_or(Value::Null);
+                if actual != *expected {
+                    return Err(MemoryError::InvalidOperation(format!(
+                        "check failed for '{}': expected {}, found {}",
+                        name, expected, actual
+                    )));
+                }
+                Ok(json!(true))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryStore for RedisKvStore {
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        metadata: Option<String>,
+        namespace: Option<&str>,
+    ) -> Result<String, MemoryError> {
+        self.inner.create_conversation(name, metadata, namespace).await
+    }
+
+    async fn get_conversation(&self, id: &str) -> Result<Conversation, MemoryError> {
+        self.inner.get_conversation(id).await
+    }
+
+    async fn list_conversations(&self, limit: usize, filter: &ConversationFilter) -> Result<Vec<Conversation>, MemoryError> {
+        self.inner.list_conversations(limit, filter).await
+    }
+
+    async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
+        self.inner.delete_conversation(id).await
+    }
+
+    async fn update_conversation(&self, id: &str, update: ConversationUpdate) -> Result<Conversation, MemoryError> {
+        self.inner.update_conversation(id, update).await
+    }
+
+    async fn add_message(
+        &self,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+        metadata: Option<String>,
+    ) -> Result<String, MemoryError> {
+        self.inner.add_message(conversation_id, role, content, metadata).await
+    }
+
+    async fn tag_conversation(&self, id: &str, add: &[String], remove: &[String]) -> Result<Vec<String>, MemoryError> {
+        self.inner.tag_conversation(id, add, remove).await
+    }
+
+    async fn update_message(
+        &self,
+        conversation_id: &str,
+        id: &str,
+        content: Option<&str>,
+        metadata: Option<String>,
+    ) -> Result<Message, MemoryError> {
+        self.inner.update_message(conversation_id, id, content, metadata).await
+    }
+
+    async fn delete_message(&self, conversation_id: &str, id: &str) -> Result<(), MemoryError> {
+        self.inner.delete_message(conversation_id, id).await
+    }
+
+    async fn get_messages(&self, conversation_id: &str, limit: usize) -> Result<Vec<Message>, MemoryError> {
+        self.inner.get_messages(conversation_id, limit).await
+    }
+
+    async fn get_recent_messages(&self, limit: usize) -> Result<Vec<Message>, MemoryError> {
+        self.inner.get_recent_messages(limit).await
+    }
+
+    async fn search_messages(&self, query: &str, limit: usize, filter: &MessageFilter) -> Result<Vec<Message>, MemoryError> {
+        self.inner.search_messages(query, limit, filter).await
+    }
+
+    async fn kv_set(&self, key: &str, value: Value, ttl_secs: Option<u64>) -> Result<(), MemoryError> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        queue_write(&mut pipe, &self.kv_key(key), &value, ttl_secs)?;
+        pipe.query_async::<()>(&mut self.redis.manager().await?)
+            .await
+            .map_err(db_err)?;
+        debug!("Set key: {}", key);
+        Ok(())
+    }
+
+    async fn kv_get(&self, key: &str) -> Result<Option<KeyValue>, MemoryError> {
+        let mut fields: HashMap<String, String> = self
+            .redis
+            .manager()
+            .await?
+            .hgetall(self.kv_key(key))
+            .await
+            .map_err(db_err)?;
+
+        let Some(value) = fields.remove("value") else {
+            return Ok(None);
+        };
+        Ok(Some(KeyValue {
+            key: key.to_string(),
+            value: parse_json(&value),
+            created_at: fields.remove("created_at").unwrap_or_default(),
+            updated_at: fields.remove("updated_at").unwrap_or_default(),
+            expires_at: fields.remove("expires_at"),
+        }))
+    }
+
+    async fn kv_delete(&self, key: &str) -> Result<(), MemoryError> {
+        self.redis
+            .manager()
+            .await?
+            .del::<_, ()>(self.kv_key(key))
+            .await
+            .map_err(db_err)?;
+        debug!("Deleted key: {}", key);
+        Ok(())
+    }
+
+    async fn kv_list(&self, prefix: Option<&str>) -> Result<Vec<String>, MemoryError> {
+        let base = self.kv_key("");
+        let pattern = format!("{}{}*", escape_pattern(&base), escape_pattern(prefix.unwrap_or("")));
+
+        let mut conn = self.redis.manager().await?;
+        let mut iter = conn
+            .scan_match::<_, String>(pattern)
+            .await
+            .map_err(db_err)?;
+        let mut keys = BTreeSet::new();
+        while let Some(key) = iter.next_item().await {
+            if let Some(key) = key.strip_prefix(&base) {
+                keys.insert(key.to_string());
+            }
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    async fn kv_batch(&self, ops: Vec<KvOp>) -> Result<Vec<Value>, MemoryError> {
+        // Optimistic: watch the keys, compute the outcome, and commit only
+        // if no other client wrote them meanwhile
+        let mut conn = self.redis.dedicated().await?;
+        for _ in 0..MAX_BATCH_ATTEMPTS {
+            if let Some(results) = self.try_batch(&mut conn, &ops).await? {
+                debug!("Applied KV batch of {} operations", ops.len());
+                return Ok(results);
+            }
+        }
+        Err(MemoryError::Database(format!(
+            "KV batch conflicted with concurrent writes {} times",
+            MAX_BATCH_ATTEMPTS
+        )))
+    }
+
+    async fn save_workflow(&self, name: &str, definition: Value) -> Result<i64, MemoryError> {
+        self.inner.save_workflow(name, definition).await
+    }
+
+    async fn get_workflow(&self, name: &str, version: Option<i64>) -> Result<Option<WorkflowVersion>, MemoryError> {
+        self.inner.get_workflow(name, version).await
+    }
+
+    async fn list_workflow_versions(&self, name: &str) -> Result<Vec<WorkflowVersion>, MemoryError> {
+        self.inner.list_workflow_versions(name).await
+    }
+
+    async fn list_workflows(&self) -> Result<Vec<WorkflowVersion>, MemoryError> {
+        self.inner.list_workflows().await
+    }
+
+    async fn record_workflow_run(&self, run: &WorkflowRun) -> Result<(), MemoryError> {
+        self.inner.record_workflow_run(run).await
+    }
+
+    async fn list_workflow_runs(&self, workflow: Option<&str>, limit: usize) -> Result<Vec<WorkflowRun>, MemoryError> {
+        self.inner.list_workflow_runs(workflow, limit).await
+    }
+
+    async fn record_task_run(&self, run: &TaskRun, keep: usize) -> Result<(), MemoryError> {
+        self.inner.record_task_run(run, keep).await
+    }
+
+    async fn list_task_runs(&self, task_id: &str, limit: usize) -> Result<Vec<TaskRun>, MemoryError> {
+        self.inner.list_task_runs(task_id, limit).await
+    }
+
+    async fn record_tool_call(&self, record: &ToolCallRecord) -> Result<(), MemoryError> {
+        self.inner.record_tool_call(record).await
+    }
+
+    async fn list_tool_calls(&self, since: Option<&str>) -> Result<Vec<ToolCallRecord>, MemoryError> {
+        self.inner.list_tool_calls(since).await
+    }
+
+    async fn record_llm_usage(&self, record: &LlmUsageRecord) -> Result<(), MemoryError> {
+        self.inner.record_llm_usage(record).await
+    }
+
+    async fn list_llm_usage(&self, since: Option<&str>) -> Result<Vec<LlmUsageRecord>, MemoryError> {
+        self.inner.list_llm_usage(since).await
+    }
+
+    async fn llm_cost_since(&self, since: &str, api_key_hash: Option<&str>) -> Result<f64, MemoryError> {
+        self.inner.llm_cost_since(since, api_key_hash).await
+    }
+
+    async fn flush(&self) -> Result<(), MemoryError> {
+        self.inner.flush().await
+    }
+
+    // Redis expires KV entries itself, so there's nothing to purge
+
+    async fn compact(&self) -> Result<u64, MemoryError> {
+        self.inner.compact().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteStore;
+
+    #[test]
+    fn test_escape_pattern() {
+        assert_eq!(escape_pattern("aegis:kv:ns:a*b?"), "aegis:kv:ns:a\\*b\\?");
+        assert_eq!(escape_pattern("[x]\\"), "\\[x\\]\\\\");
+    }
+
+    /// Runs against a real server: `AEGIS_TEST_REDIS_URL=redis://127.0.0.1
+    /// cargo test --features redis -- --ignored`.
+    #[tokio::test]
+    #[ignore = "needs a Redis server"]
+    async fn test_redis_kv() {
+        let url = std::env::var("AEGIS_TEST_REDIS_URL").expect("AEGIS_TEST_REDIS_URL");
+        let prefix = format!("aegis-test-{}:", uuid::Uuid::new_v4());
+        let inner = Arc::new(SqliteStore::new(":memory:").unwrap());
+        let store = RedisKvStore::new(inner, &url, &prefix).unwrap();
+
+        store.kv_set("a:1", json!({"n": 1}), None).await.unwrap();
+        store.kv_set("a:2", json!("two"), Some(60)).await.unwrap();
+        store.kv_set("b*", json!(true), None).await.unwrap();
+
+        let entry = store.kv_get("a:1").await.unwrap().unwrap();
+        assert_eq!(entry.value, json!({"n": 1}));
+        assert!(entry.expires_at.is_none());
+        assert!(store.kv_get("a:2").await.unwrap().unwrap().expires_at.is_some());
+        assert_eq!(store.kv_list(Some("a:")).await.unwrap(), vec!["a:1", "a:2"]);
+        assert_eq!(store.kv_list(Some("b*")).await.unwrap(), vec!["b*"]);
+
+        let results = store
+            .kv_batch(vec![
+                KvOp::Incr { key: "counter".into(), by: 5 },
+                KvOp::Incr { key: "counter".into(), by: 1 },
+                KvOp::Delete { key: "a:1".into() },
+                KvOp::Get { key: "a:1".into() },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(results, vec![json!(5), json!(6), json!(true), Value::Null]);
+
+        // A failed check discards the whole batch
+        let err = store
+            .kv_batch(vec![
+                KvOp::Set { key: "counter".into(), value: json!(0), ttl_secs: None },
+                KvOp::Check { key: "counter".into(), expected: json!(6) },
+            ])
+            .await;
+        assert!(err.is_err());
+        assert_eq!(store.kv_get("counter").await.unwrap().unwrap().value, json!(6));
+
+        for key in store.kv_list(None).await.unwrap() {
+            store.kv_delete(&key).await.unwrap();
+        }
+        assert!(store.kv_list(None).await.unwrap().is_empty());
+    }
+}
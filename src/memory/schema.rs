@@ -1,9 +1,19 @@
-//! Database schema initialization.
+//! Database schema and migrations.
+//!
+//! The SQLite schema is built by [`MIGRATIONS`], applied in order on
+//! startup. Each applied step is recorded in the `schema_migrations` table,
+//! so a database is only ever moved forward from the version it is at.
+//! Schema changes go in a new step at the end of the list; released steps
+//! must never be edited.
 
-use rusqlite::Connection;
+use chrono::Utc;
+use rusqlite::{params, Connection, OpenFlags};
+use tracing::info;
 
-/// SQL schema for the memory database.
-pub const SCHEMA: &str = r#"
+use super::MemoryError;
+
+/// Tables of the first schema version.
+const SCHEMA: &str = r#"
 -- Conversations table
 CREATE TABLE IF NOT EXISTS conversations (
     id TEXT PRIMARY KEY,
@@ -33,16 +43,368 @@ CREATE TABLE IF NOT EXISTS kv_store (
     expires_at TEXT
 );
 
+-- Workflow definitions (one row per version)
+CREATE TABLE IF NOT EXISTS workflows (
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (name, version)
+);
+
+-- Workflow execution history
+CREATE TABLE IF NOT EXISTS workflow_runs (
+    id TEXT PRIMARY KEY,
+    workflow TEXT NOT NULL,
+    version INTEGER,
+    success INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    steps TEXT NOT NULL
+);
+
+-- Audit log of client tool calls
+CREATE TABLE IF NOT EXISTS tool_calls (
+    id TEXT PRIMARY KEY,
+    tool TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    api_key_hash TEXT,
+    api_key_name TEXT,
+    success INTEGER NOT NULL,
+    error TEXT,
+    started_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL
+);
+
+-- Token usage and estimated cost of LLM calls
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id TEXT PRIMARY KEY,
+    tool TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    api_key_hash TEXT,
+    api_key_name TEXT,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    estimated INTEGER NOT NULL,
+    cost_usd REAL,
+    created_at TEXT NOT NULL
+);
+
 -- Indexes for performance
 CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
 CREATE INDEX IF NOT EXISTS idx_messages_created ON messages(created_at DESC);
 CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at DESC);
 CREATE INDEX IF NOT EXISTS idx_kv_expires ON kv_store(expires_at);
+CREATE INDEX IF NOT EXISTS idx_workflow_runs_workflow ON workflow_runs(workflow, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_tool_calls_started ON tool_calls(started_at);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_created ON llm_usage(created_at);
 "#;
 
-/// Initializes the database schema.
-pub fn initialize_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
-    conn.execute_batch(SCHEMA)?;
+/// One step of the SQLite schema.
+#[derive(Debug)]
+pub struct Migration {
+    /// Schema version the step brings the database to.
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Connection) -> Result<(), rusqlite::Error>,
+}
+
+/// Every schema step, in order. Versions start at 1 and have no gaps.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create conversation, KV, workflow, audit and LLM usage tables",
+        apply: |conn| conn.execute_batch(SCHEMA),
+    },
+    Migration {
+        version: 2,
+        description: "Add namespaces to conversations",
+        apply: |conn| {
+            add_column_if_missing(conn, "conversations", "namespace", "TEXT")?;
+            conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_conversations_namespace ON conversations(namespace, updated_at DESC);",
+            )
+        },
+    },
+    Migration {
+        version: 3,
+        description: "Add scheduled task run history",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS task_runs (
+                    id TEXT PRIMARY KEY,
+                    task_id TEXT NOT NULL,
+                    task_name TEXT NOT NULL,
+                    success INTEGER NOT NULL,
+                    started_at TEXT NOT NULL,
+                    duration_ms INTEGER NOT NULL,
+                    output TEXT,
+                    error TEXT
+                );
+                CREATE INDEX IF NOT EXISTS idx_task_runs_task ON task_runs(task_id, started_at DESC);",
+            )
+        },
+    },
+    Migration {
+        version: 4,
+        description: "Add argument sizes to the audit log",
+        apply: |conn| add_column_if_missing(conn, "tool_calls", "args_bytes", "INTEGER"),
+    },
+    Migration {
+        version: 5,
+        description: "Add archived flag to conversations",
+        apply: |conn| add_column_if_missing(conn, "conversations", "archived", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 6,
+        description: "Add conversation tags",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS conversation_tags (
+                    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                    tag TEXT NOT NULL,
+                    PRIMARY KEY (conversation_id, tag)
+                );
+                CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag ON conversation_tags(tag);",
+            )
+        },
+    },
+];
+
+/// The schema version this build creates.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// SQL schema for the Postgres memory store; the same tables as
+/// [`MIGRATIONS`] build in SQLite.
+///
+/// Timestamps stay RFC 3339 text, as in SQLite, so both stores compare
+/// and return them the same way.
+#[cfg(feature = "postgres")]
+pub const POSTGRES_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    metadata TEXT,
+    namespace TEXT
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    metadata TEXT
+);
+
+CREATE TABLE IF NOT EXISTS kv_store (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    expires_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS workflows (
+    name TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    definition TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (name, version)
+);
+
+CREATE TABLE IF NOT EXISTS workflow_runs (
+    id TEXT PRIMARY KEY,
+    workflow TEXT NOT NULL,
+    version BIGINT,
+    success BOOLEAN NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    steps TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tool_calls (
+    id TEXT PRIMARY KEY,
+    tool TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    api_key_hash TEXT,
+    api_key_name TEXT,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    started_at TEXT NOT NULL,
+    duration_ms BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id TEXT PRIMARY KEY,
+    tool TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    api_key_hash TEXT,
+    api_key_name TEXT,
+    input_tokens BIGINT NOT NULL,
+    output_tokens BIGINT NOT NULL,
+    estimated BOOLEAN NOT NULL,
+    cost_usd DOUBLE PRECISION,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS task_runs (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    task_name TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    output TEXT,
+    error TEXT
+);
+
+CREATE TABLE IF NOT EXISTS conversation_tags (
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (conversation_id, tag)
+);
+
+-- Columns added after the first release
+ALTER TABLE conversations ADD COLUMN IF NOT EXISTS namespace TEXT;
+ALTER TABLE tool_calls ADD COLUMN IF NOT EXISTS args_bytes BIGINT;
+ALTER TABLE conversations ADD COLUMN IF NOT EXISTS archived BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
+CREATE INDEX IF NOT EXISTS idx_messages_created ON messages(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_conversations_namespace ON conversations(namespace, updated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_kv_expires ON kv_store(expires_at);
+CREATE INDEX IF NOT EXISTS idx_workflow_runs_workflow ON workflow_runs(workflow, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_tool_calls_started ON tool_calls(started_at);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_created ON llm_usage(created_at);
+CREATE INDEX IF NOT EXISTS idx_task_runs_task ON task_runs(task_id, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag ON conversation_tags(tag);
+"#;
+
+/// Brings the database schema up to date.
+pub fn initialize_schema(conn: &Connection) -> Result<(), MemoryError> {
+    migrate(conn).map(|_| ())
+}
+
+/// The schema version of a database; 0 before any migration ran.
+pub fn schema_version(conn: &Connection) -> Result<u32, MemoryError> {
+    let tracked: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+    if !tracked {
+        return Ok(0);
+    }
+    conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", [], |row| row.get(0))
+        .map_err(db_err)
+}
+
+/// The migrations a database still needs, in order.
+///
+/// Fails for a database written by a newer release, which this build
+/// can't safely use.
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>, MemoryError> {
+    let version = schema_version(conn)?;
+    if version > latest_version() {
+        return Err(MemoryError::Database(format!(
+            "database schema version {} is newer than this release supports ({}); upgrade Aegis",
+            version,
+            latest_version()
+        )));
+    }
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+/// Applies the pending migrations, each in its own transaction, and
+/// returns them.
+pub fn migrate(conn: &Connection) -> Result<Vec<&'static Migration>, MemoryError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );",
+    )
+    .map_err(db_err)?;
+
+    let pending = pending_migrations(conn)?;
+    for migration in &pending {
+        let tx = conn.unchecked_transaction().map_err(db_err)?;
+        (migration.apply)(&tx).map_err(|e| {
+            MemoryError::Database(format!("migration {} ({}) failed: {}", migration.version, migration.description, e))
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.description, Utc::now().to_rfc3339()],
+        )
+        .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        info!("Migrated database schema to version {}: {}", migration.version, migration.description);
+    }
+    Ok(pending)
+}
+
+/// Opens the SQLite database at `path` and migrates it, or with `dry_run`
+/// only reports what would run. Returns the version the database was at and
+/// the migrations applied (or pending).
+pub fn migrate_database(path: &str, dry_run: bool) -> Result<(u32, Vec<&'static Migration>), MemoryError> {
+    if dry_run {
+        if !std::path::Path::new(path).exists() {
+            return Ok((0, MIGRATIONS.iter().collect()));
+        }
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(db_err)?;
+        return Ok((schema_version(&conn)?, pending_migrations(&conn)?));
+    }
+
+    let conn = Connection::open(path).map_err(db_err)?;
+    let version = schema_version(&conn)?;
+    Ok((version, migrate(&conn)?))
+}
+
+fn db_err(e: rusqlite::Error) -> MemoryError {
+    MemoryError::Database(e.to_string())
+}
+
+/// Initializes (or migrates) the Postgres schema.
+///
+/// Instances sharing a database may start together, so the DDL runs under
+/// a transaction-scoped advisory lock.
+#[cfg(feature = "postgres")]
+pub async fn initialize_postgres_schema(
+    client: &mut deadpool_postgres::tokio_postgres::Client,
+) -> Result<(), deadpool_postgres::tokio_postgres::Error> {
+    let tx = client.transaction().await?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext('aegis_schema'))", &[]).await?;
+    // Skip the "already exists" notices on every start
+    tx.batch_execute("SET LOCAL client_min_messages = warning").await?;
+    tx.batch_execute(POSTGRES_SCHEMA).await?;
+    tx.commit().await
+}
+
+/// Adds a column to an existing table unless it already has it.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, ty: &str) -> Result<(), rusqlite::Error> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1", table),
+        [column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, ty))?;
+    }
     Ok(())
 }
 
@@ -68,6 +430,65 @@ mod tests {
         assert!(tables.contains(&"conversations".to_string()));
         assert!(tables.contains(&"messages".to_string()));
         assert!(tables.contains(&"kv_store".to_string()));
+        assert!(tables.contains(&"workflows".to_string()));
+        assert!(tables.contains(&"workflow_runs".to_string()));
+        assert!(tables.contains(&"tool_calls".to_string()));
+        assert!(tables.contains(&"task_runs".to_string()));
+        assert!(tables.contains(&"conversation_tags".to_string()));
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1, "{}", migration.description);
+        }
+    }
+
+    #[test]
+    fn test_migrate_records_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aegis.db");
+        let path = path.to_str().unwrap();
+
+        // A dry run changes nothing, not even creating the file
+        let (version, pending) = migrate_database(path, true).unwrap();
+        assert_eq!((version, pending.len()), (0, MIGRATIONS.len()));
+        assert!(!std::path::Path::new(path).exists());
+
+        let (version, applied) = migrate_database(path, false).unwrap();
+        assert_eq!((version, applied.len()), (0, MIGRATIONS.len()));
+        let (version, pending) = migrate_database(path, true).unwrap();
+        assert_eq!((version, pending.len()), (latest_version(), 0));
+
+        // A database from a newer release is left alone
+        let conn = Connection::open(path).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations VALUES (?1, 'future', 'now')",
+            [latest_version() + 1],
+        )
+        .unwrap();
+        let err = initialize_schema(&conn).unwrap_err();
+        assert!(err.to_string().contains("newer than this release"), "{}", err);
+    }
+
+    #[test]
+    fn test_adds_namespace_to_old_conversations() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (id TEXT PRIMARY KEY, name TEXT, created_at TEXT NOT NULL, \
+             updated_at TEXT NOT NULL, metadata TEXT);
+             INSERT INTO conversations VALUES ('c1', NULL, 'now', 'now', NULL);",
+        )
+        .unwrap();
+
+        initialize_schema(&conn).unwrap();
+        initialize_schema(&conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        let namespace: Option<String> = conn
+            .query_row("SELECT namespace FROM conversations WHERE id = 'c1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(namespace, None);
     }
 }
 
@@ -1,30 +1,85 @@
 //! SQLite implementation of the MemoryStore trait.
+//!
+//! Queries run on tokio's blocking pool, never on the async runtime. A file
+//! database has one writer connection and, since it runs in WAL mode, a few
+//! read connections, so reads proceed in parallel with each other and with
+//! the writer. An in-memory database exists only within its connection, so
+//! it has the writer alone.
 
 use async_trait::async_trait;
 use chrono::Utc;
 use parking_lot::Mutex;
 use rusqlite::Connection;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::memory::schema::initialize_schema;
-use crate::memory::store::{Conversation, KeyValue, MemoryError, MemoryStore, Message};
+use crate::memory::store::{
+    split_tags, Conversation, ConversationFilter, ConversationUpdate, KeyValue, KvOp, LlmUsageRecord, MemoryError,
+    MemoryStore, Message, MessageFilter, TaskRun, ToolCallRecord, WorkflowRun, WorkflowVersion,
+};
 
-/// SQLite-based memory store.
+/// Read connections of a file database.
+pub const DEFAULT_READ_CONNECTIONS: usize = 4;
+
+/// How long a connection waits for a lock held by another before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections of a store: one writer, and readers for WAL databases.
 #[derive(Debug)]
+struct Connections {
+    writer: Mutex<Connection>,
+    readers: Vec<Mutex<Connection>>,
+    next_reader: AtomicUsize,
+}
+
+impl Connections {
+    /// Runs `f` on a free read connection, waiting for one if all are busy.
+    /// Without readers, reads go to the writer.
+    fn read<T>(&self, f: impl FnOnce(&Connection) -> T) -> T {
+        if self.readers.is_empty() {
+            return f(&self.writer.lock());
+        }
+        let start = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        let count = self.readers.len();
+        for i in 0..count {
+            if let Some(conn) = self.readers[(start + i) % count].try_lock() {
+                return f(&conn);
+            }
+        }
+        f(&self.readers[start % count].lock())
+    }
+
+    /// Runs `f` on the write connection.
+    fn write<T>(&self, f: impl FnOnce(&mut Connection) -> T) -> T {
+        f(&mut self.writer.lock())
+    }
+}
+
+/// SQLite-based memory store.
+#[derive(Debug, Clone)]
 pub struct SqliteStore {
-    conn: Arc<Mutex<Connection>>,
+    connections: Arc<Connections>,
 }
 
 impl SqliteStore {
     /// Creates a new SQLite store with the given database path.
     /// Use ":memory:" for an in-memory database.
     pub fn new(path: &str) -> Result<Self, MemoryError> {
+        Self::with_read_connections(path, DEFAULT_READ_CONNECTIONS)
+    }
+
+    /// Creates a store with `readers` read connections (ignored for
+    /// in-memory databases). With none, reads share the write connection.
+    pub fn with_read_connections(path: &str, readers: usize) -> Result<Self, MemoryError> {
         info!("Opening SQLite database: {}", path);
 
-        let conn = if path == ":memory:" {
+        let in_memory = Self::is_in_memory(path);
+        let writer = if in_memory {
             Connection::open_in_memory()
         } else {
             // Create parent directories if needed
@@ -35,26 +90,224 @@ impl SqliteStore {
             }
             Connection::open(path)
         }
-        .map_err(|e| MemoryError::Database(e.to_string()))?;
+        .map_err(db_err)?;
 
-        // Enable WAL mode for better concurrency
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
+        // Enable WAL mode so readers don't block the writer or each other
+        writer
+            .execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
+            .map_err(db_err)?;
+        writer.busy_timeout(BUSY_TIMEOUT).map_err(db_err)?;
 
         // Initialize schema
-        initialize_schema(&conn).map_err(|e| MemoryError::Database(e.to_string()))?;
+        initialize_schema(&writer)?;
 
-        info!("SQLite database initialized successfully");
+        let readers = if in_memory {
+            Vec::new()
+        } else {
+            (0..readers)
+                .map(|_| {
+                    let reader = Connection::open(path).map_err(db_err)?;
+                    reader.busy_timeout(BUSY_TIMEOUT).map_err(db_err)?;
+                    reader.execute_batch("PRAGMA query_only=ON;").map_err(db_err)?;
+                    Ok(Mutex::new(reader))
+                })
+                .collect::<Result<Vec<_>, MemoryError>>()?
+        };
+
+        info!("SQLite database initialized successfully ({} read connections)", readers.len());
 
         Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
+            connections: Arc::new(Connections {
+                writer: Mutex::new(writer),
+                readers,
+                next_reader: AtomicUsize::new(0),
+            }),
         })
     }
 
+    /// Whether SQLite keeps a database at `path` in memory (or in a private
+    /// temporary file) rather than in a file of that name: `:memory:`, an
+    /// empty path, or a `file:` URI with `mode=memory`.
+    pub fn is_in_memory(path: &str) -> bool {
+        path.is_empty()
+            || path == ":memory:"
+            || (path.starts_with("file:") && path.split(['?', '&']).any(|param| param == "mode=memory"))
+    }
+
     /// Creates an in-memory SQLite store.
     pub fn in_memory() -> Result<Self, MemoryError> {
         Self::new(":memory:")
     }
+
+    /// Runs a query on a read connection off the async runtime.
+    async fn read<T, F>(&self, f: F) -> Result<T, MemoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, MemoryError> + Send + 'static,
+    {
+        let connections = self.connections.clone();
+        tokio::task::spawn_blocking(move || connections.read(f))
+            .await
+            .map_err(|e| MemoryError::Database(format!("Database task failed: {}", e)))?
+    }
+
+    /// Runs statements on the write connection off the async runtime.
+    async fn write<T, F>(&self, f: F) -> Result<T, MemoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, MemoryError> + Send + 'static,
+    {
+        let connections = self.connections.clone();
+        tokio::task::spawn_blocking(move || connections.write(f))
+            .await
+            .map_err(|e| MemoryError::Database(format!("Database task failed: {}", e)))?
+    }
+}
+
+fn db_err(e: rusqlite::Error) -> MemoryError {
+    MemoryError::Database(e.to_string())
+}
+
+/// Writes a KV entry, keeping its original creation time.
+fn write_kv(
+    conn: &Connection,
+    key: &str,
+    value: &serde_json::Value,
+    ttl_secs: Option<u64>,
+) -> Result<(), MemoryError> {
+    let now = Utc::now();
+    let now_str = now.to_rfc3339();
+    let value_str =
+        serde_json::to_string(value).map_err(|e| MemoryError::Serialization(e.to_string()))?;
+    let expires_at = ttl_secs.map(|secs| {
+        (now + chrono::Duration::seconds(secs as i64)).to_rfc3339()
+    });
+
+    conn.execute(
+        "INSERT OR REPLACE INTO kv_store (key, value, created_at, updated_at, expires_at) VALUES (?1, ?2, COALESCE((SELECT created_at FROM kv_store WHERE key = ?1), ?3), ?3, ?4)",
+        (&key, &value_str, &now_str, &expires_at),
+    )
+    .map_err(db_err)?;
+    Ok(())
+}
+
+/// Reads the value of a live (unexpired) KV entry.
+fn read_kv(conn: &Connection, key: &str) -> Result<Option<serde_json::Value>, MemoryError> {
+    let now_str = Utc::now().to_rfc3339();
+    let result = conn.query_row(
+        "SELECT value FROM kv_store WHERE key = ?1 AND (expires_at IS NULL OR expires_at >= ?2)",
+        [key, &now_str],
+        |row| row.get::<_, String>(0),
+    );
+
+    match result {
+        Ok(value) => Ok(Some(
+            serde_json::from_str(&value).unwrap_or(serde_json::Value::Null),
+        )),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(db_err(e)),
+    }
+}
+
+/// Applies a single batch operation inside a transaction.
+fn apply_kv_op(conn: &Connection, op: &KvOp) -> Result<serde_json::Value, MemoryError> {
+    use serde_json::{json, Value};
+
+    match op {
+        KvOp::Get { key } => Ok(read_kv(conn, key)?.unwrap_or(Value::Null)),
+        KvOp::Set { key, value, ttl_secs } => {
+            write_kv(conn, key, value, *ttl_secs)?;
+            Ok(Value::Null)
+        }
+        KvOp::Delete { key } => {
+            let deleted = conn
+                .execute("DELETE FROM kv_store WHERE key = ?1", [key])
+                .map_err(db_err)?;
+            Ok(json!(deleted > 0))
+        }
+        KvOp::Incr { key, by } => {
+            let current = match read_kv(conn, key)? {
+                None => 0,
+                Some(value) => value.as_i64().ok_or_else(|| {
+                    MemoryError::InvalidOperation(format!("'{}' does not hold an integer", key))
+                })?,
+            };
+            let next = current.checked_add(*by).ok_or_else(|| {
+                MemoryError::InvalidOperation(format!("'{}' would overflow", key))
+            })?;
+            write_kv(conn, key, &json!(next), None)?;
+            Ok(json!(next))
+        }
+        KvOp::Check { key, expected } => {
+            let actual = read_kv(conn, key)?.unwrap_or(Value::Null);
+            if actual != *expected {
+                return Err(MemoryError::InvalidOperation(format!(
+                    "check failed for '{}': expected {}, found {}",
+                    key, expected, actual
+                )));
+            }
+            Ok(json!(true))
+        }
+    }
+}
+
+/// Columns of a `conversations` row, with its tags joined by commas.
+const CONVERSATION_COLUMNS: &str = "id, name, created_at, updated_at, metadata, namespace, archived, \
+     (SELECT group_concat(tag) FROM conversation_tags WHERE conversation_id = conversations.id)";
+
+/// Matches rows whose conversation (`conversation_id`) has every tag of the
+/// JSON array in parameter `?N`.
+fn has_tags_condition(conversation_id: &str, param: usize) -> String {
+    format!(
+        "(?{param} IS NULL OR (SELECT COUNT(*) FROM conversation_tags t WHERE t.conversation_id = {conversation_id} \
+         AND t.tag IN (SELECT value FROM json_each(?{param}))) = json_array_length(?{param}))"
+    )
+}
+
+/// Escapes `%`, `_` and `\` in text matched literally by `LIKE ... ESCAPE '\'`.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Binds the tags of a filter as a JSON array, or NULL to match all.
+fn tags_param(tags: &[String]) -> Option<String> {
+    let mut tags = tags.to_vec();
+    tags.sort();
+    tags.dedup();
+    (!tags.is_empty()).then(|| serde_json::json!(tags).to_string())
+}
+
+/// Maps a `conversations` row to a [`Conversation`].
+fn conversation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        metadata: row.get(4)?,
+        namespace: row.get(5)?,
+        archived: row.get(6)?,
+        tags: split_tags(row.get(7)?),
+    })
+}
+
+/// Maps a `messages` row to a [`Message`].
+fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Message> {
+    Ok(Message {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        role: row.get(2)?,
+        content: row.get(3)?,
+        created_at: row.get(4)?,
+        metadata: row.get(5)?,
+    })
 }
 
 #[async_trait]
@@ -63,16 +316,21 @@ impl MemoryStore for SqliteStore {
         &self,
         title: Option<String>,
         metadata: Option<String>,
+        namespace: Option<&str>,
     ) -> Result<String, MemoryError> {
         let id = Uuid::new_v4().to_string();
         let now_str = Utc::now().to_rfc3339();
+        let namespace = namespace.map(str::to_string);
 
-        let conn = self.conn.lock();
-        conn.execute(
-            "INSERT INTO conversations (id, name, created_at, updated_at, metadata) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (&id, &title, &now_str, &now_str, &metadata),
-        )
-        .map_err(|e| MemoryError::Database(e.to_string()))?;
+        let created = id.clone();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO conversations (id, name, created_at, updated_at, metadata, namespace) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (&created, &title, &now_str, &now_str, &metadata, &namespace),
+            )
+            .map_err(db_err)
+        })
+        .await?;
 
         debug!("Created conversation: {}", id);
 
@@ -80,79 +338,104 @@ impl MemoryStore for SqliteStore {
     }
 
     async fn get_conversation(&self, id: &str) -> Result<Conversation, MemoryError> {
-        let conn = self.conn.lock();
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, name, created_at, updated_at, metadata FROM conversations WHERE id = ?1",
+        let id = id.to_string();
+        self.read(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM conversations WHERE id = ?1", CONVERSATION_COLUMNS),
+                [&id],
+                conversation_from_row,
             )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        let conversation = stmt
-            .query_row([id], |row| {
-                Ok(Conversation {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    created_at: row.get(2)?,
-                    updated_at: row.get(3)?,
-                    metadata: row.get(4)?,
-                })
-            })
             .map_err(|e| match e {
                 rusqlite::Error::QueryReturnedNoRows => {
                     MemoryError::NotFound(format!("Conversation not found: {}", id))
                 }
-                _ => MemoryError::Database(e.to_string()),
-            })?;
+                _ => db_err(e),
+            })
+        })
+        .await
+    }
 
-        Ok(conversation)
+    async fn list_conversations(&self, limit: usize, filter: &ConversationFilter) -> Result<Vec<Conversation>, MemoryError> {
+        let filter = filter.clone();
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT {} FROM conversations
+                     WHERE (?2 IS NULL OR namespace = ?2)
+                       AND (?3 IS NULL OR updated_at >= ?3)
+                       AND (?4 IS NULL OR updated_at < ?4)
+                       AND (?5 IS NULL OR archived = ?5)
+                       AND {}
+                     ORDER BY updated_at DESC LIMIT ?1",
+                    CONVERSATION_COLUMNS,
+                    has_tags_condition("conversations.id", 6),
+                ))
+                .map_err(db_err)?;
+
+            let conversations = stmt
+                .query_map(
+                    (limit, &filter.namespace, &filter.since, &filter.until, filter.archived, tags_param(&filter.tags)),
+                    conversation_from_row,
+                )
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(conversations)
+        })
+        .await
     }
 
-    async fn list_conversations(&self, limit: usize) -> Result<Vec<Conversation>, MemoryError> {
-        let conn = self.conn.lock();
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, name, created_at, updated_at, metadata FROM conversations ORDER BY updated_at DESC LIMIT ?1",
-            )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        let conversations = stmt
-            .query_map([limit], |row| {
-                Ok(Conversation {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    created_at: row.get(2)?,
-                    updated_at: row.get(3)?,
-                    metadata: row.get(4)?,
-                })
-            })
-            .map_err(|e| MemoryError::Database(e.to_string()))?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
+    async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
+        let id = id.to_string();
+        self.write(move |conn| {
+            // Delete messages and tags first (foreign key)
+            conn.execute("DELETE FROM messages WHERE conversation_id = ?1", [&id])
+                .map_err(db_err)?;
+            conn.execute("DELETE FROM conversation_tags WHERE conversation_id = ?1", [&id])
+                .map_err(db_err)?;
+
+            // Delete conversation
+            let deleted = conn
+                .execute("DELETE FROM conversations WHERE id = ?1", [&id])
+                .map_err(db_err)?;
+
+            if deleted == 0 {
+                return Err(MemoryError::NotFound(format!(
+                    "Conversation not found: {}",
+                    id
+                )));
+            }
 
-        Ok(conversations)
+            debug!("Deleted conversation: {}", id);
+            Ok(())
+        })
+        .await
     }
 
-    async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
-        let conn = self.conn.lock();
-
-        // Delete messages first (foreign key)
-        conn.execute("DELETE FROM messages WHERE conversation_id = ?1", [id])
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        // Delete conversation
-        let deleted = conn
-            .execute("DELETE FROM conversations WHERE id = ?1", [id])
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        if deleted == 0 {
-            return Err(MemoryError::NotFound(format!(
-                "Conversation not found: {}",
-                id
-            )));
-        }
+    async fn update_conversation(&self, id: &str, update: ConversationUpdate) -> Result<Conversation, MemoryError> {
+        let id = id.to_string();
+        let now_str = Utc::now().to_rfc3339();
+        self.write(move |conn| {
+            let updated = conn
+                .execute(
+                    "UPDATE conversations SET name = COALESCE(?1, name), metadata = COALESCE(?2, metadata),
+                     archived = COALESCE(?3, archived), updated_at = ?4 WHERE id = ?5",
+                    (&update.title, &update.metadata, update.archived, &now_str, &id),
+                )
+                .map_err(db_err)?;
+            if updated == 0 {
+                return Err(MemoryError::NotFound(format!("Conversation not found: {}", id)));
+            }
 
-        debug!("Deleted conversation: {}", id);
-        Ok(())
+            debug!("Updated conversation: {}", id);
+            conn.query_row(
+                &format!("SELECT {} FROM conversations WHERE id = ?1", CONVERSATION_COLUMNS),
+                [&id],
+                conversation_from_row,
+            )
+            .map_err(db_err)
+        })
+        .await
     }
 
     async fn add_message(
@@ -164,26 +447,139 @@ impl MemoryStore for SqliteStore {
     ) -> Result<String, MemoryError> {
         let id = Uuid::new_v4().to_string();
         let now_str = Utc::now().to_rfc3339();
+        let (conversation_id, role, content) = (conversation_id.to_string(), role.to_string(), content.to_string());
+
+        let message_id = id.clone();
+        self.write(move |conn| {
+            // Insert message
+            conn.execute(
+                "INSERT INTO messages (id, conversation_id, role, content, created_at, metadata) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (&message_id, &conversation_id, &role, &content, &now_str, &metadata),
+            )
+            .map_err(db_err)?;
 
-        let conn = self.conn.lock();
+            // Update conversation updated_at
+            conn.execute(
+                "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+                (&now_str, &conversation_id),
+            )
+            .map_err(db_err)?;
 
-        // Insert message
-        conn.execute(
-            "INSERT INTO messages (id, conversation_id, role, content, created_at, metadata) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            (&id, conversation_id, role, content, &now_str, &metadata),
-        )
-        .map_err(|e| MemoryError::Database(e.to_string()))?;
+            debug!("Added message {} to conversation {}", message_id, conversation_id);
+            Ok(())
+        })
+        .await?;
 
-        // Update conversation updated_at
-        conn.execute(
-            "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
-            (&now_str, conversation_id),
-        )
-        .map_err(|e| MemoryError::Database(e.to_string()))?;
+        Ok(id)
+    }
 
-        debug!("Added message {} to conversation {}", id, conversation_id);
+    async fn tag_conversation(&self, id: &str, add: &[String], remove: &[String]) -> Result<Vec<String>, MemoryError> {
+        let (id, add, remove) = (id.to_string(), add.to_vec(), remove.to_vec());
+        self.write(move |conn| {
+            let tx = conn.transaction().map_err(db_err)?;
+            let exists: bool = tx
+                .query_row("SELECT COUNT(*) > 0 FROM conversations WHERE id = ?1", [&id], |row| row.get(0))
+                .map_err(db_err)?;
+            if !exists {
+                return Err(MemoryError::NotFound(format!("Conversation not found: {}", id)));
+            }
 
-        Ok(id)
+            for tag in &add {
+                tx.execute(
+                    "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)",
+                    (&id, tag),
+                )
+                .map_err(db_err)?;
+            }
+            for tag in &remove {
+                tx.execute(
+                    "DELETE FROM conversation_tags WHERE conversation_id = ?1 AND tag = ?2",
+                    (&id, tag),
+                )
+                .map_err(db_err)?;
+            }
+
+            let tags = tx
+                .prepare("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")
+                .map_err(db_err)?
+                .query_map([&id], |row| row.get(0))
+                .map_err(db_err)?
+                .collect::<Result<Vec<String>, _>>()
+                .map_err(db_err)?;
+            tx.commit().map_err(db_err)?;
+
+            debug!("Tagged conversation {}: {:?}", id, tags);
+            Ok(tags)
+        })
+        .await
+    }
+
+    async fn update_message(
+        &self,
+        conversation_id: &str,
+        id: &str,
+        content: Option<&str>,
+        metadata: Option<String>,
+    ) -> Result<Message, MemoryError> {
+        let now_str = Utc::now().to_rfc3339();
+        let (conversation_id, id, content) = (conversation_id.to_string(), id.to_string(), content.map(str::to_string));
+        self.write(move |conn| {
+            let tx = conn.transaction().map_err(db_err)?;
+            let updated = tx
+                .execute(
+                    "UPDATE messages SET content = COALESCE(?1, content), metadata = COALESCE(?2, metadata)
+                     WHERE id = ?3 AND conversation_id = ?4",
+                    (&content, &metadata, &id, &conversation_id),
+                )
+                .map_err(db_err)?;
+            if updated == 0 {
+                return Err(MemoryError::NotFound(format!("Message not found: {}", id)));
+            }
+            tx.execute(
+                "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+                (&now_str, &conversation_id),
+            )
+            .map_err(db_err)?;
+            let message = tx
+                .query_row(
+                    "SELECT id, conversation_id, role, content, created_at, metadata FROM messages WHERE id = ?1",
+                    [&id],
+                    message_from_row,
+                )
+                .map_err(db_err)?;
+            tx.commit().map_err(db_err)?;
+
+            debug!("Updated message {} of conversation {}", id, conversation_id);
+            Ok(message)
+        })
+        .await
+    }
+
+    async fn delete_message(&self, conversation_id: &str, id: &str) -> Result<(), MemoryError> {
+        let now_str = Utc::now().to_rfc3339();
+        let (conversation_id, id) = (conversation_id.to_string(), id.to_string());
+        self.write(move |conn| {
+            let tx = conn.transaction().map_err(db_err)?;
+            let deleted = tx
+                .execute(
+                    "DELETE FROM messages WHERE id = ?1 AND conversation_id = ?2",
+                    (&id, &conversation_id),
+                )
+                .map_err(db_err)?;
+            if deleted == 0 {
+                return Err(MemoryError::NotFound(format!("Message not found: {}", id)));
+            }
+            tx.execute(
+                "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+                (&now_str, &conversation_id),
+            )
+            .map_err(db_err)?;
+            tx.commit().map_err(db_err)?;
+
+            debug!("Deleted message {} of conversation {}", id, conversation_id);
+            Ok(())
+        })
+        .await
     }
 
     async fn get_messages(
@@ -191,88 +587,82 @@ impl MemoryStore for SqliteStore {
         conversation_id: &str,
         limit: usize,
     ) -> Result<Vec<Message>, MemoryError> {
-        let conn = self.conn.lock();
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, conversation_id, role, content, created_at, metadata FROM messages WHERE conversation_id = ?1 ORDER BY created_at ASC LIMIT ?2",
-            )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        let messages = stmt
-            .query_map([conversation_id, &limit.to_string()], |row| {
-                Ok(Message {
-                    id: row.get(0)?,
-                    conversation_id: row.get(1)?,
-                    role: row.get(2)?,
-                    content: row.get(3)?,
-                    created_at: row.get(4)?,
-                    metadata: row.get(5)?,
-                })
-            })
-            .map_err(|e| MemoryError::Database(e.to_string()))?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        Ok(messages)
+        let conversation_id = conversation_id.to_string();
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, conversation_id, role, content, created_at, metadata FROM messages WHERE conversation_id = ?1 ORDER BY created_at ASC LIMIT ?2",
+                )
+                .map_err(db_err)?;
+
+            let messages = stmt
+                .query_map((&conversation_id, limit), message_from_row)
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(messages)
+        })
+        .await
     }
 
     async fn get_recent_messages(&self, limit: usize) -> Result<Vec<Message>, MemoryError> {
-        let conn = self.conn.lock();
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, conversation_id, role, content, created_at, metadata FROM messages ORDER BY created_at DESC LIMIT ?1",
-            )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        let messages = stmt
-            .query_map([limit], |row| {
-                Ok(Message {
-                    id: row.get(0)?,
-                    conversation_id: row.get(1)?,
-                    role: row.get(2)?,
-                    content: row.get(3)?,
-                    created_at: row.get(4)?,
-                    metadata: row.get(5)?,
-                })
-            })
-            .map_err(|e| MemoryError::Database(e.to_string()))?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        Ok(messages)
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, conversation_id, role, content, created_at, metadata FROM messages ORDER BY created_at DESC LIMIT ?1",
+                )
+                .map_err(db_err)?;
+
+            let messages = stmt
+                .query_map([limit], message_from_row)
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(messages)
+        })
+        .await
     }
 
-    async fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<Message>, MemoryError> {
-        let conn = self.conn.lock();
-        
+    async fn search_messages(&self, query: &str, limit: usize, filter: &MessageFilter) -> Result<Vec<Message>, MemoryError> {
         // Use LIKE for basic search (FTS would be better for large datasets)
         let pattern = format!("%{}%", query);
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, conversation_id, role, content, created_at, metadata 
-                 FROM messages 
-                 WHERE content LIKE ?1 
-                 ORDER BY created_at DESC 
-                 LIMIT ?2",
-            )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        let messages = stmt
-            .query_map([&pattern, &limit.to_string()], |row| {
-                Ok(Message {
-                    id: row.get(0)?,
-                    conversation_id: row.get(1)?,
-                    role: row.get(2)?,
-                    content: row.get(3)?,
-                    created_at: row.get(4)?,
-                    metadata: row.get(5)?,
-                })
-            })
-            .map_err(|e| MemoryError::Database(e.to_string()))?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-
-        Ok(messages)
+        let filter = filter.clone();
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT id, conversation_id, role, content, created_at, metadata 
+                     FROM messages 
+                     WHERE content LIKE ?1 
+                       AND (?3 IS NULL OR conversation_id IN (SELECT id FROM conversations WHERE namespace = ?3))
+                       AND (?4 IS NULL OR role = ?4)
+                       AND (?5 IS NULL OR created_at >= ?5)
+                       AND (?6 IS NULL OR created_at < ?6)
+                       AND {}
+                     ORDER BY created_at DESC 
+                     LIMIT ?2",
+                    has_tags_condition("messages.conversation_id", 7),
+                ))
+                .map_err(db_err)?;
+
+            let messages = stmt
+                .query_map(
+                    (
+                        &pattern,
+                        limit,
+                        &filter.namespace,
+                        &filter.role,
+                        &filter.since,
+                        &filter.until,
+                        tags_param(&filter.tags),
+                    ),
+                    message_from_row,
+                )
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(messages)
+        })
+        .await
     }
 
     async fn kv_set(
@@ -281,105 +671,513 @@ impl MemoryStore for SqliteStore {
         value: serde_json::Value,
         ttl_secs: Option<u64>,
     ) -> Result<(), MemoryError> {
-        let now = Utc::now();
-        let now_str = now.to_rfc3339();
-        let value_str =
-            serde_json::to_string(&value).map_err(|e| MemoryError::Serialization(e.to_string()))?;
-        let expires_at = ttl_secs.map(|secs| {
-            (now + chrono::Duration::seconds(secs as i64)).to_rfc3339()
-        });
-
-        let conn = self.conn.lock();
-        conn.execute(
-            "INSERT OR REPLACE INTO kv_store (key, value, created_at, updated_at, expires_at) VALUES (?1, ?2, COALESCE((SELECT created_at FROM kv_store WHERE key = ?1), ?3), ?3, ?4)",
-            (&key, &value_str, &now_str, &expires_at),
-        )
-        .map_err(|e| MemoryError::Database(e.to_string()))?;
+        let key = key.to_string();
+        self.write(move |conn| {
+            write_kv(conn, &key, &value, ttl_secs)?;
 
-        debug!("Set key: {}", key);
-        Ok(())
+            debug!("Set key: {}", key);
+            Ok(())
+        })
+        .await
     }
 
     async fn kv_get(&self, key: &str) -> Result<Option<KeyValue>, MemoryError> {
-        let conn = self.conn.lock();
+        let key = key.to_string();
+        self.read(move |conn| {
+            let now_str = Utc::now().to_rfc3339();
+            // Hot path: reuse the prepared statement across calls
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT key, value, created_at, updated_at, expires_at FROM kv_store \
+                     WHERE key = ?1 AND (expires_at IS NULL OR expires_at >= ?2)",
+                )
+                .map_err(db_err)?;
+            let result = stmt.query_row(
+                [&key, &now_str],
+                |row| {
+                    let value_str: String = row.get(1)?;
+
+                    Ok(KeyValue {
+                        key: row.get(0)?,
+                        value: serde_json::from_str(&value_str).unwrap_or(serde_json::Value::Null),
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                        expires_at: row.get(4)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(kv) => Ok(Some(kv)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(db_err(e)),
+            }
+        })
+        .await
+    }
+
+    async fn kv_delete(&self, key: &str) -> Result<(), MemoryError> {
+        let key = key.to_string();
+        self.write(move |conn| {
+            conn.execute("DELETE FROM kv_store WHERE key = ?1", [&key])
+                .map_err(db_err)?;
+            debug!("Deleted key: {}", key);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn kv_list(&self, prefix: Option<&str>) -> Result<Vec<String>, MemoryError> {
+        let prefix = prefix.unwrap_or("").to_string();
+        let pattern = format!("{}%", escape_like(&prefix));
+        self.read(move |conn| {
+            let now_str = Utc::now().to_rfc3339();
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT key FROM kv_store WHERE key LIKE ?1 ESCAPE '\\' \
+                     AND (expires_at IS NULL OR expires_at >= ?2) ORDER BY key",
+                )
+                .map_err(db_err)?;
+            let keys: Vec<String> = stmt
+                .query_map([&pattern, &now_str], |row| row.get(0))
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            // LIKE ignores ASCII case
+            Ok(keys.into_iter().filter(|key| key.starts_with(&prefix)).collect())
+        })
+        .await
+    }
+
+    async fn kv_batch(&self, ops: Vec<KvOp>) -> Result<Vec<serde_json::Value>, MemoryError> {
+        self.write(move |conn| {
+            // Dropping the transaction without committing rolls it back
+            let tx = conn.transaction().map_err(db_err)?;
+
+            let results = ops
+                .iter()
+                .map(|op| apply_kv_op(&tx, op))
+                .collect::<Result<Vec<_>, _>>()?;
 
-        // Clean up expired entries first
+            tx.commit().map_err(db_err)?;
+            debug!("Applied KV batch of {} operations", ops.len());
+            Ok(results)
+        })
+        .await
+    }
+
+    async fn save_workflow(
+        &self,
+        name: &str,
+        definition: serde_json::Value,
+    ) -> Result<i64, MemoryError> {
         let now_str = Utc::now().to_rfc3339();
-        conn.execute(
-            "DELETE FROM kv_store WHERE expires_at IS NOT NULL AND expires_at < ?1",
-            [&now_str],
-        )
-        .ok();
-
-        let mut stmt = conn
-            .prepare(
-                "SELECT key, value, created_at, updated_at, expires_at FROM kv_store WHERE key = ?1",
+        let definition_str = serde_json::to_string(&definition)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        let name = name.to_string();
+
+        self.write(move |conn| {
+            let version: i64 = conn
+                .query_row(
+                    "SELECT COALESCE(MAX(version), 0) + 1 FROM workflows WHERE name = ?1",
+                    [&name],
+                    |row| row.get(0),
+                )
+                .map_err(db_err)?;
+
+            conn.execute(
+                "INSERT INTO workflows (name, version, definition, created_at) VALUES (?1, ?2, ?3, ?4)",
+                (&name, version, &definition_str, &now_str),
             )
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
+            .map_err(db_err)?;
 
-        let result = stmt.query_row([key], |row| {
-            let value_str: String = row.get(1)?;
+            debug!("Saved workflow {} v{}", name, version);
+            Ok(version)
+        })
+        .await
+    }
 
-            Ok(KeyValue {
-                key: row.get(0)?,
-                value: serde_json::from_str(&value_str).unwrap_or(serde_json::Value::Null),
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-                expires_at: row.get(4)?,
-            })
-        });
+    async fn get_workflow(
+        &self,
+        name: &str,
+        version: Option<i64>,
+    ) -> Result<Option<WorkflowVersion>, MemoryError> {
+        let name = name.to_string();
+        self.read(move |conn| {
+            let result = match version {
+                Some(v) => conn.query_row(
+                    "SELECT name, version, definition, created_at FROM workflows WHERE name = ?1 AND version = ?2",
+                    (&name, v),
+                    workflow_from_row,
+                ),
+                None => conn.query_row(
+                    "SELECT name, version, definition, created_at FROM workflows WHERE name = ?1 ORDER BY version DESC LIMIT 1",
+                    [&name],
+                    workflow_from_row,
+                ),
+            };
+
+            match result {
+                Ok(wf) => Ok(Some(wf)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(db_err(e)),
+            }
+        })
+        .await
+    }
 
-        match result {
-            Ok(kv) => Ok(Some(kv)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(MemoryError::Database(e.to_string())),
-        }
+    async fn list_workflow_versions(&self, name: &str) -> Result<Vec<WorkflowVersion>, MemoryError> {
+        let name = name.to_string();
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT name, version, definition, created_at FROM workflows WHERE name = ?1 ORDER BY version DESC",
+                )
+                .map_err(db_err)?;
+
+            let versions = stmt
+                .query_map([&name], workflow_from_row)
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(versions)
+        })
+        .await
     }
 
-    async fn kv_delete(&self, key: &str) -> Result<(), MemoryError> {
-        let conn = self.conn.lock();
-        conn.execute("DELETE FROM kv_store WHERE key = ?1", [key])
-            .map_err(|e| MemoryError::Database(e.to_string()))?;
-        debug!("Deleted key: {}", key);
-        Ok(())
+    async fn list_workflows(&self) -> Result<Vec<WorkflowVersion>, MemoryError> {
+        self.read(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT w.name, w.version, w.definition, w.created_at FROM workflows w \
+                     WHERE w.version = (SELECT MAX(version) FROM workflows WHERE name = w.name) \
+                     ORDER BY w.name",
+                )
+                .map_err(db_err)?;
+
+            let workflows = stmt
+                .query_map([], workflow_from_row)
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(workflows)
+        })
+        .await
     }
 
-    async fn kv_list(&self, prefix: Option<&str>) -> Result<Vec<String>, MemoryError> {
-        let conn = self.conn.lock();
+    async fn record_workflow_run(&self, run: &WorkflowRun) -> Result<(), MemoryError> {
+        let steps_str = serde_json::to_string(&run.steps)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        let run = run.clone();
+
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO workflow_runs (id, workflow, version, success, started_at, finished_at, duration_ms, steps) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (
+                    &run.id,
+                    &run.workflow,
+                    run.version,
+                    run.success,
+                    &run.started_at,
+                    &run.finished_at,
+                    run.duration_ms as i64,
+                    &steps_str,
+                ),
+            )
+            .map_err(db_err)?;
 
-        // Clean up expired entries first
-        let now_str = Utc::now().to_rfc3339();
-        conn.execute(
-            "DELETE FROM kv_store WHERE expires_at IS NOT NULL AND expires_at < ?1",
-            [&now_str],
-        )
-        .ok();
-
-        let keys: Vec<String> = if let Some(prefix) = prefix {
-            let pattern = format!("{}%", prefix);
+            debug!("Recorded workflow run {} ({})", run.id, run.workflow);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_workflow_runs(
+        &self,
+        workflow: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<WorkflowRun>, MemoryError> {
+        let workflow = workflow.map(str::to_string);
+        self.read(move |conn| {
             let mut stmt = conn
-                .prepare("SELECT key FROM kv_store WHERE key LIKE ?1 ORDER BY key")
-                .map_err(|e| MemoryError::Database(e.to_string()))?;
-            let rows = stmt
-                .query_map([&pattern], |row| row.get(0))
-                .map_err(|e| MemoryError::Database(e.to_string()))?;
-            rows.collect::<Result<Vec<_>, _>>()
-                .map_err(|e| MemoryError::Database(e.to_string()))?
-        } else {
+                .prepare(
+                    "SELECT id, workflow, version, success, started_at, finished_at, duration_ms, steps FROM workflow_runs \
+                     WHERE ?1 IS NULL OR workflow = ?1 ORDER BY started_at DESC LIMIT ?2",
+                )
+                .map_err(db_err)?;
+
+            let runs = stmt
+                .query_map((&workflow, limit), |row| {
+                    let steps_str: String = row.get(7)?;
+                    let duration_ms: i64 = row.get(6)?;
+                    Ok(WorkflowRun {
+                        id: row.get(0)?,
+                        workflow: row.get(1)?,
+                        version: row.get(2)?,
+                        success: row.get(3)?,
+                        started_at: row.get(4)?,
+                        finished_at: row.get(5)?,
+                        duration_ms: duration_ms as u64,
+                        steps: serde_json::from_str(&steps_str).unwrap_or(serde_json::Value::Null),
+                    })
+                })
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(runs)
+        })
+        .await
+    }
+
+    async fn record_task_run(&self, run: &TaskRun, keep: usize) -> Result<(), MemoryError> {
+        let run = run.clone();
+        let keep = i64::try_from(keep).unwrap_or(i64::MAX);
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO task_runs (id, task_id, task_name, success, started_at, duration_ms, output, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (
+                    &run.id,
+                    &run.task_id,
+                    &run.task_name,
+                    run.success,
+                    &run.started_at,
+                    run.duration_ms as i64,
+                    &run.output,
+                    &run.error,
+                ),
+            )
+            .map_err(db_err)?;
+            conn.execute(
+                "DELETE FROM task_runs WHERE task_id = ?1 AND id NOT IN \
+                 (SELECT id FROM task_runs WHERE task_id = ?1 ORDER BY started_at DESC LIMIT ?2)",
+                (&run.task_id, keep),
+            )
+            .map_err(db_err)?;
+
+            debug!("Recorded run {} of task {}", run.id, run.task_id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_task_runs(&self, task_id: &str, limit: usize) -> Result<Vec<TaskRun>, MemoryError> {
+        let task_id = task_id.to_string();
+        self.read(move |conn| {
             let mut stmt = conn
-                .prepare("SELECT key FROM kv_store ORDER BY key")
-                .map_err(|e| MemoryError::Database(e.to_string()))?;
-            let rows = stmt
-                .query_map([], |row| row.get(0))
-                .map_err(|e| MemoryError::Database(e.to_string()))?;
-            rows.collect::<Result<Vec<_>, _>>()
-                .map_err(|e| MemoryError::Database(e.to_string()))?
-        };
+                .prepare(
+                    "SELECT id, task_id, task_name, success, started_at, duration_ms, output, error FROM task_runs \
+                     WHERE task_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+                )
+                .map_err(db_err)?;
+
+            let runs = stmt
+                .query_map((&task_id, limit), |row| {
+                    let duration_ms: i64 = row.get(5)?;
+                    Ok(TaskRun {
+                        id: row.get(0)?,
+                        task_id: row.get(1)?,
+                        task_name: row.get(2)?,
+                        success: row.get(3)?,
+                        started_at: row.get(4)?,
+                        duration_ms: duration_ms as u64,
+                        output: row.get(6)?,
+                        error: row.get(7)?,
+                    })
+                })
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(runs)
+        })
+        .await
+    }
 
-        Ok(keys)
+    async fn record_tool_call(&self, record: &ToolCallRecord) -> Result<(), MemoryError> {
+        let record = record.clone();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO tool_calls (id, tool, session_id, api_key_hash, api_key_name, success, error, started_at, duration_ms, args_bytes) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                (
+                    &record.id,
+                    &record.tool,
+                    &record.session_id,
+                    &record.api_key_hash,
+                    &record.api_key_name,
+                    record.success,
+                    &record.error,
+                    &record.started_at,
+                    record.duration_ms as i64,
+                    record.args_bytes.map(|bytes| bytes as i64),
+                ),
+            )
+            .map_err(db_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_tool_calls(&self, since: Option<&str>) -> Result<Vec<ToolCallRecord>, MemoryError> {
+        let since = since.map(str::to_string);
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, tool, session_id, api_key_hash, api_key_name, success, error, started_at, duration_ms, args_bytes FROM tool_calls \
+                     WHERE ?1 IS NULL OR started_at >= ?1 ORDER BY started_at ASC",
+                )
+                .map_err(db_err)?;
+
+            let records = stmt
+                .query_map([&since], |row| {
+                    let duration_ms: i64 = row.get(8)?;
+                    let args_bytes: Option<i64> = row.get(9)?;
+                    Ok(ToolCallRecord {
+                        id: row.get(0)?,
+                        tool: row.get(1)?,
+                        session_id: row.get(2)?,
+                        api_key_hash: row.get(3)?,
+                        api_key_name: row.get(4)?,
+                        success: row.get(5)?,
+                        error: row.get(6)?,
+                        started_at: row.get(7)?,
+                        duration_ms: duration_ms as u64,
+                        args_bytes: args_bytes.map(|bytes| bytes as u64),
+                    })
+                })
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn record_llm_usage(&self, record: &LlmUsageRecord) -> Result<(), MemoryError> {
+        let record = record.clone();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO llm_usage (id, tool, provider, model, session_id, api_key_hash, api_key_name, input_tokens, output_tokens, estimated, cost_usd, created_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                (
+                    &record.id,
+                    &record.tool,
+                    &record.provider,
+                    &record.model,
+                    &record.session_id,
+                    &record.api_key_hash,
+                    &record.api_key_name,
+                    record.input_tokens as i64,
+                    record.output_tokens as i64,
+                    record.estimated,
+                    record.cost_usd,
+                    &record.created_at,
+                ),
+            )
+            .map_err(db_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_llm_usage(&self, since: Option<&str>) -> Result<Vec<LlmUsageRecord>, MemoryError> {
+        let since = since.map(str::to_string);
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, tool, provider, model, session_id, api_key_hash, api_key_name, input_tokens, output_tokens, estimated, cost_usd, created_at \
+                     FROM llm_usage WHERE ?1 IS NULL OR created_at >= ?1 ORDER BY created_at ASC",
+                )
+                .map_err(db_err)?;
+
+            let records = stmt
+                .query_map([&since], |row| {
+                    let input_tokens: i64 = row.get(7)?;
+                    let output_tokens: i64 = row.get(8)?;
+                    Ok(LlmUsageRecord {
+                        id: row.get(0)?,
+                        tool: row.get(1)?,
+                        provider: row.get(2)?,
+                        model: row.get(3)?,
+                        session_id: row.get(4)?,
+                        api_key_hash: row.get(5)?,
+                        api_key_name: row.get(6)?,
+                        input_tokens: input_tokens as u64,
+                        output_tokens: output_tokens as u64,
+                        estimated: row.get(9)?,
+                        cost_usd: row.get(10)?,
+                        created_at: row.get(11)?,
+                    })
+                })
+                .map_err(db_err)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_err)?;
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn llm_cost_since(&self, since: &str, api_key_hash: Option<&str>) -> Result<f64, MemoryError> {
+        let (since, api_key_hash) = (since.to_string(), api_key_hash.map(str::to_string));
+        self.read(move |conn| {
+            conn.query_row(
+                "SELECT COALESCE(SUM(cost_usd), 0) FROM llm_usage WHERE created_at >= ?1 AND (?2 IS NULL OR api_key_hash = ?2)",
+                (&since, &api_key_hash),
+                |row| row.get(0),
+            )
+            .map_err(db_err)
+        })
+        .await
+    }
+
+    async fn flush(&self) -> Result<(), MemoryError> {
+        // Fold the write-ahead log into the main database file
+        self.write(|conn| conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").map_err(db_err))
+            .await
+    }
+
+    async fn purge_expired(&self) -> Result<u64, MemoryError> {
+        let now_str = Utc::now().to_rfc3339();
+        self.write(move |conn| {
+            let purged = conn
+                .execute(
+                    "DELETE FROM kv_store WHERE expires_at IS NOT NULL AND expires_at < ?1",
+                    [&now_str],
+                )
+                .map_err(db_err)?;
+            debug!("Purged {} expired keys", purged);
+            Ok(purged as u64)
+        })
+        .await
+    }
+
+    async fn compact(&self) -> Result<u64, MemoryError> {
+        self.write(|conn| {
+            let size = |conn: &Connection| -> Result<u64, MemoryError> {
+                let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0)).map_err(db_err)?;
+                let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0)).map_err(db_err)?;
+                Ok((pages * page_size) as u64)
+            };
+            let before = size(conn)?;
+            conn.execute_batch("VACUUM; ANALYZE; PRAGMA wal_checkpoint(TRUNCATE);")
+                .map_err(db_err)?;
+            let reclaimed = before.saturating_sub(size(conn)?);
+            debug!("Compacted database, reclaimed {} bytes", reclaimed);
+            Ok(reclaimed)
+        })
+        .await
     }
 }
 
+/// Maps a `workflows` row to a [`WorkflowVersion`].
+fn workflow_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<WorkflowVersion> {
+    let definition_str: String = row.get(2)?;
+    Ok(WorkflowVersion {
+        name: row.get(0)?,
+        version: row.get(1)?,
+        definition: serde_json::from_str(&definition_str).unwrap_or(serde_json::Value::Null),
+        created_at: row.get(3)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,7 +1188,7 @@ mod tests {
 
         // Create
         let conv_id = store
-            .create_conversation(Some("Test".to_string()), None)
+            .create_conversation(Some("Test".to_string()), None, None)
             .await
             .unwrap();
 
@@ -400,20 +1198,40 @@ mod tests {
         assert_eq!(fetched.title, Some("Test".to_string()));
 
         // List
-        let list = store.list_conversations(10).await.unwrap();
+        let list = store.list_conversations(10, &ConversationFilter::default()).await.unwrap();
         assert_eq!(list.len(), 1);
 
         // Delete
         store.delete_conversation(&conv_id).await.unwrap();
-        let list = store.list_conversations(10).await.unwrap();
+        let list = store.list_conversations(10, &ConversationFilter::default()).await.unwrap();
         assert_eq!(list.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_conversation_namespaces() {
+        let store = SqliteStore::in_memory().unwrap();
+        let a = store.create_conversation(None, None, Some("agent-a")).await.unwrap();
+        let b = store.create_conversation(None, None, Some("agent-b")).await.unwrap();
+        store.add_message(&a, "user", "deploy on friday", None).await.unwrap();
+        store.add_message(&b, "user", "deploy on monday", None).await.unwrap();
+
+        assert_eq!(store.get_conversation(&a).await.unwrap().namespace.as_deref(), Some("agent-a"));
+        let listed = store.list_conversations(10, &ConversationFilter::in_namespace(Some("agent-b"))).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, b);
+        assert_eq!(store.list_conversations(10, &ConversationFilter::default()).await.unwrap().len(), 2);
+
+        let found = store.search_messages("deploy", 10, &MessageFilter::in_namespace(Some("agent-a"))).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].conversation_id, a);
+        assert_eq!(store.search_messages("deploy", 10, &MessageFilter::default()).await.unwrap().len(), 2);
+    }
+
     #[tokio::test]
     async fn test_messages() {
         let store = SqliteStore::in_memory().unwrap();
 
-        let conv_id = store.create_conversation(None, None).await.unwrap();
+        let conv_id = store.create_conversation(None, None, None).await.unwrap();
 
         store.add_message(&conv_id, "user", "Hello", None).await.unwrap();
         store.add_message(&conv_id, "assistant", "Hi there!", None).await.unwrap();
@@ -424,19 +1242,43 @@ mod tests {
         assert_eq!(messages[1].role, "assistant");
     }
 
+    #[tokio::test]
+    async fn test_message_and_conversation_updates() {
+        let store = SqliteStore::in_memory().unwrap();
+        let conv_id = store.create_conversation(Some("Old".to_string()), None, None).await.unwrap();
+        let other_id = store.create_conversation(None, None, None).await.unwrap();
+        let msg_id = store.add_message(&conv_id, "user", "Helo", None).await.unwrap();
+
+        let message = store.update_message(&conv_id, &msg_id, Some("Hello"), None).await.unwrap();
+        assert_eq!(message.content, "Hello");
+        let message = store.update_message(&conv_id, &msg_id, None, Some("{\"edited\":true}".to_string())).await.unwrap();
+        assert_eq!((message.content.as_str(), message.metadata.as_deref()), ("Hello", Some("{\"edited\":true}")));
+
+        // Messages are only reachable through their own conversation
+        assert!(matches!(store.delete_message(&other_id, &msg_id).await, Err(MemoryError::NotFound(_))));
+        store.delete_message(&conv_id, &msg_id).await.unwrap();
+        assert!(store.get_messages(&conv_id, 10).await.unwrap().is_empty());
+
+        let update = ConversationUpdate { archived: Some(true), ..Default::default() };
+        let conversation = store.update_conversation(&conv_id, update).await.unwrap();
+        assert_eq!((conversation.title.as_deref(), conversation.archived), (Some("Old"), true));
+        let update = ConversationUpdate { title: Some("New".to_string()), ..Default::default() };
+        assert!(store.update_conversation("missing", update).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_search_messages() {
         let store = SqliteStore::in_memory().unwrap();
 
-        let conv_id = store.create_conversation(None, None).await.unwrap();
+        let conv_id = store.create_conversation(None, None, None).await.unwrap();
 
         store.add_message(&conv_id, "user", "Hello world", None).await.unwrap();
         store.add_message(&conv_id, "assistant", "Goodbye world", None).await.unwrap();
 
-        let results = store.search_messages("world", 10).await.unwrap();
+        let results = store.search_messages("world", 10, &MessageFilter::default()).await.unwrap();
         assert_eq!(results.len(), 2);
 
-        let results = store.search_messages("Hello", 10).await.unwrap();
+        let results = store.search_messages("Hello", 10, &MessageFilter::default()).await.unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -459,9 +1301,133 @@ mod tests {
         let keys = store.kv_list(None).await.unwrap();
         assert_eq!(keys, vec!["test_key"]);
 
+        // Prefixes match literally: '_' and '%' are not wildcards, case counts
+        for key in ["ns:a_b:k", "ns:axb:k", "ns:a%:k", "ns:A_B:k"] {
+            store.kv_set(key, serde_json::json!(1), None).await.unwrap();
+        }
+        assert_eq!(store.kv_list(Some("ns:a_b:")).await.unwrap(), vec!["ns:a_b:k"]);
+        assert_eq!(store.kv_list(Some("ns:a%")).await.unwrap(), vec!["ns:a%:k"]);
+
         // Delete
         store.kv_delete("test_key").await.unwrap();
         let kv = store.kv_get("test_key").await.unwrap();
         assert!(kv.is_none());
     }
+
+    #[tokio::test]
+    async fn test_workflow_versions_and_runs() {
+        let store = SqliteStore::in_memory().unwrap();
+
+        let v1 = store.save_workflow("wf", serde_json::json!({"steps": [1]})).await.unwrap();
+        let v2 = store.save_workflow("wf", serde_json::json!({"steps": [2]})).await.unwrap();
+        assert_eq!((v1, v2), (1, 2));
+
+        let latest = store.get_workflow("wf", None).await.unwrap().unwrap();
+        assert_eq!(latest.version, 2);
+        let first = store.get_workflow("wf", Some(1)).await.unwrap().unwrap();
+        assert_eq!(first.definition, serde_json::json!({"steps": [1]}));
+        assert_eq!(store.list_workflow_versions("wf").await.unwrap().len(), 2);
+        assert_eq!(store.list_workflows().await.unwrap().len(), 1);
+
+        let run = WorkflowRun {
+            id: "run-1".to_string(),
+            workflow: "wf".to_string(),
+            version: Some(2),
+            success: true,
+            started_at: Utc::now().to_rfc3339(),
+            finished_at: Utc::now().to_rfc3339(),
+            duration_ms: 12,
+            steps: serde_json::json!([{"step_id": "a", "duration_ms": 12}]),
+        };
+        store.record_workflow_run(&run).await.unwrap();
+
+        let runs = store.list_workflow_runs(Some("wf"), 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].version, Some(2));
+        assert!(store.list_workflow_runs(Some("other"), 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_runs_are_bounded() {
+        let store = SqliteStore::in_memory().unwrap();
+
+        for i in 0..5 {
+            let run = TaskRun {
+                id: format!("run-{}", i),
+                task_id: "t".to_string(),
+                task_name: "nightly".to_string(),
+                success: i % 2 == 0,
+                started_at: format!("2024-01-0{}T00:00:00+00:00", i + 1),
+                duration_ms: 10,
+                output: None,
+                error: None,
+            };
+            store.record_task_run(&run, 3).await.unwrap();
+        }
+
+        let runs = store.list_task_runs("t", 10).await.unwrap();
+        let ids: Vec<_> = runs.iter().map(|run| run.id.as_str()).collect();
+        assert_eq!(ids, ["run-4", "run-3", "run-2"]);
+        assert_eq!(store.list_task_runs("t", 1).await.unwrap().len(), 1);
+        assert!(store.list_task_runs("other", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls() {
+        let store = SqliteStore::in_memory().unwrap();
+
+        for (id, started_at) in [("a", "2024-01-01T00:00:00+00:00"), ("b", "2024-01-03T00:00:00+00:00")] {
+            store
+                .record_tool_call(&ToolCallRecord {
+                    id: id.to_string(),
+                    tool: "echo".to_string(),
+                    session_id: "default".to_string(),
+                    api_key_hash: None,
+                    api_key_name: None,
+                    success: true,
+                    error: None,
+                    started_at: started_at.to_string(),
+                    duration_ms: 3,
+                    args_bytes: Some(2),
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(store.list_tool_calls(None).await.unwrap().len(), 2);
+        let recent = store.list_tool_calls(Some("2024-01-02T00:00:00+00:00")).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].id, "b");
+        assert_eq!(recent[0].args_bytes, Some(2));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_read_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aegis.db");
+        let store = SqliteStore::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(store.connections.readers.len(), DEFAULT_READ_CONNECTIONS);
+
+        store.kv_set("live", serde_json::json!(1), None).await.unwrap();
+        store.kv_set("gone", serde_json::json!(2), Some(0)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Readers see the writer's commits and skip expired keys
+        let reads: Vec<_> = (0..32)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    store.kv_set(&format!("w{}", i), serde_json::json!(i), None).await.unwrap();
+                    (store.kv_get("live").await.unwrap(), store.kv_get("gone").await.unwrap())
+                })
+            })
+            .collect();
+        for read in reads {
+            let (live, gone) = read.await.unwrap();
+            assert_eq!(live.unwrap().value, serde_json::json!(1));
+            assert!(gone.is_none());
+        }
+        assert_eq!(store.kv_list(Some("w")).await.unwrap().len(), 32);
+        assert_eq!(store.kv_list(Some("gone")).await.unwrap().len(), 0);
+    }
 }
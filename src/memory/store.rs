@@ -33,6 +33,84 @@ pub struct Conversation {
     pub updated_at: String,
     /// Optional metadata as JSON.
     pub metadata: Option<String>,
+    /// Namespace the conversation belongs to (None = shared).
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Whether the conversation is archived (hidden from listings by default).
+    #[serde(default)]
+    pub archived: bool,
+    /// Tags of the conversation, sorted.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Filters of a conversation listing; unset fields match every conversation.
+#[derive(Debug, Clone, Default)]
+pub struct ConversationFilter {
+    /// Only conversations of this namespace.
+    pub namespace: Option<String>,
+    /// Only conversations with all of these tags.
+    pub tags: Vec<String>,
+    /// Only conversations updated at or after this time (RFC 3339).
+    pub since: Option<String>,
+    /// Only conversations updated before this time (RFC 3339).
+    pub until: Option<String>,
+    /// Only archived (`true`) or unarchived (`false`) conversations.
+    pub archived: Option<bool>,
+}
+
+impl ConversationFilter {
+    /// Matches the conversations of one namespace, or all of them if
+    /// `namespace` is None.
+    pub fn in_namespace(namespace: Option<&str>) -> Self {
+        Self { namespace: namespace.map(str::to_string), ..Self::default() }
+    }
+}
+
+/// Filters of a message search; unset fields match every message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    /// Only messages of this namespace's conversations.
+    pub namespace: Option<String>,
+    /// Only messages of conversations with all of these tags.
+    pub tags: Vec<String>,
+    /// Only messages with this role.
+    pub role: Option<String>,
+    /// Only messages created at or after this time (RFC 3339).
+    pub since: Option<String>,
+    /// Only messages created before this time (RFC 3339).
+    pub until: Option<String>,
+}
+
+impl MessageFilter {
+    /// Matches the messages of one namespace's conversations, or all of
+    /// them if `namespace` is None.
+    pub fn in_namespace(namespace: Option<&str>) -> Self {
+        Self { namespace: namespace.map(str::to_string), ..Self::default() }
+    }
+}
+
+/// Splits the comma-joined tags of a conversation row.
+pub(crate) fn split_tags(joined: Option<String>) -> Vec<String> {
+    let mut tags: Vec<String> = joined
+        .unwrap_or_default()
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    tags.sort();
+    tags
+}
+
+/// Changes to a conversation; fields left `None` are kept as they are.
+#[derive(Debug, Clone, Default)]
+pub struct ConversationUpdate {
+    /// New title.
+    pub title: Option<String>,
+    /// New metadata as JSON, replacing the old.
+    pub metadata: Option<String>,
+    /// Archives or unarchives the conversation.
+    pub archived: Option<bool>,
 }
 
 /// A message within a conversation.
@@ -67,23 +145,244 @@ pub struct KeyValue {
     pub expires_at: Option<String>,
 }
 
+/// Prefixes of the keys the server keeps its own state under, which
+/// clients must not see or write.
+pub const RESERVED_KEY_PREFIXES: [&str; 7] = [
+    "aegis:",
+    "idempotency:",
+    "tool_output:",
+    "agent:heartbeat:",
+    "workflow:",
+    "vector:",
+    "vector_namespace:",
+];
+
+/// Whether a key (within its namespace) holds the server's own state.
+pub fn is_reserved_key(key: &str) -> bool {
+    RESERVED_KEY_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// Builds the storage key of a key within an optional namespace.
+///
+/// Key-value namespaces are key prefixes (`ns:<namespace>:<key>`), so a
+/// namespace's keys can be listed with `kv_list(Some("ns:<namespace>:"))`.
+pub fn namespaced_key(namespace: Option<&str>, key: &str) -> String {
+    match namespace {
+        Some(ns) => format!("ns:{}:{}", ns, key),
+        None => key.to_string(),
+    }
+}
+
+/// Checks a namespace name. Names can't contain `:`, or the keys of one
+/// namespace could be read and written as another's (`a` + `b:k` and
+/// `a:b` + `k` are both `ns:a:b:k`).
+pub fn check_namespace(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty() {
+        Err("Namespace must not be empty".to_string())
+    } else if namespace.contains(':') {
+        Err(format!("Namespace '{}' must not contain ':'", namespace))
+    } else {
+        Ok(())
+    }
+}
+
+/// One operation in an atomic key-value batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum KvOp {
+    /// Reads a key (result: its value, or null).
+    Get { key: String },
+    /// Writes a key (result: null).
+    Set {
+        key: String,
+        value: serde_json::Value,
+        #[serde(default)]
+        ttl_secs: Option<u64>,
+    },
+    /// Deletes a key (result: whether it existed).
+    Delete { key: String },
+    /// Adds to an integer key, treating a missing key as 0 (result: the new value).
+    Incr {
+        key: String,
+        #[serde(default = "default_incr")]
+        by: i64,
+    },
+    /// Aborts the batch unless the key holds `expected` (null = absent).
+    Check {
+        key: String,
+        #[serde(default)]
+        expected: serde_json::Value,
+    },
+}
+
+fn default_incr() -> i64 {
+    1
+}
+
+impl KvOp {
+    /// Returns the key this operation touches.
+    pub fn key(&self) -> &str {
+        match self {
+            KvOp::Get { key }
+            | KvOp::Set { key, .. }
+            | KvOp::Delete { key }
+            | KvOp::Incr { key, .. }
+            | KvOp::Check { key, .. } => key,
+        }
+    }
+
+    /// Returns the key this operation touches, for rewriting.
+    pub fn key_mut(&mut self) -> &mut String {
+        match self {
+            KvOp::Get { key }
+            | KvOp::Set { key, .. }
+            | KvOp::Delete { key }
+            | KvOp::Incr { key, .. }
+            | KvOp::Check { key, .. } => key,
+        }
+    }
+}
+
+/// A stored version of a workflow definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowVersion {
+    /// Workflow name.
+    pub name: String,
+    /// Version number (starts at 1).
+    pub version: i64,
+    /// The workflow definition (steps, description, inputs).
+    pub definition: serde_json::Value,
+    /// When this version was saved.
+    pub created_at: String,
+}
+
+/// A recorded workflow execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    /// Unique run ID.
+    pub id: String,
+    /// Workflow name.
+    pub workflow: String,
+    /// Version that was executed (None for ad-hoc runs).
+    pub version: Option<i64>,
+    /// Whether every executed step succeeded.
+    pub success: bool,
+    /// When the run started.
+    pub started_at: String,
+    /// When the run finished.
+    pub finished_at: String,
+    /// Total duration in milliseconds.
+    pub duration_ms: u64,
+    /// Per-step results, including timings and outputs.
+    pub steps: serde_json::Value,
+}
+
+/// A recorded run of a scheduled task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    /// Unique run ID.
+    pub id: String,
+    /// ID of the scheduled task.
+    pub task_id: String,
+    /// Name of the task at the time of the run.
+    pub task_name: String,
+    /// Whether the run succeeded.
+    pub success: bool,
+    /// When the run started.
+    pub started_at: String,
+    /// Duration in milliseconds.
+    pub duration_ms: u64,
+    /// Start of the output of a successful run.
+    pub output: Option<String>,
+    /// Error message of a failed run.
+    pub error: Option<String>,
+}
+
+/// One client tool call, as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// Unique record ID.
+    pub id: String,
+    /// Tool that was called.
+    pub tool: String,
+    /// Session the call belonged to.
+    pub session_id: String,
+    /// Hash of the API key the caller authenticated with, if any.
+    pub api_key_hash: Option<String>,
+    /// Name of the API key the caller authenticated with, if any.
+    pub api_key_name: Option<String>,
+    /// Whether the call succeeded.
+    pub success: bool,
+    /// Error message for failed calls.
+    pub error: Option<String>,
+    /// When the call started.
+    pub started_at: String,
+    /// Duration in milliseconds.
+    pub duration_ms: u64,
+    /// Size of the call's arguments as JSON, in bytes (unknown for calls
+    /// recorded before it was tracked).
+    #[serde(default)]
+    pub args_bytes: Option<u64>,
+}
+
+/// Token usage of one LLM call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmUsageRecord {
+    /// Unique record ID.
+    pub id: String,
+    /// Tool that made the call (e.g. "llm.chat").
+    pub tool: String,
+    /// Provider that served the call (e.g. "openai").
+    pub provider: String,
+    /// Model that served the call.
+    pub model: String,
+    /// Session the call belonged to.
+    pub session_id: String,
+    /// Hash of the API key the caller authenticated with, if any.
+    pub api_key_hash: Option<String>,
+    /// Name of the API key the caller authenticated with, if any.
+    pub api_key_name: Option<String>,
+    /// Prompt tokens.
+    pub input_tokens: u64,
+    /// Completion tokens.
+    pub output_tokens: u64,
+    /// Whether the token counts are estimates (the provider reported none).
+    pub estimated: bool,
+    /// Estimated cost in USD, or `None` if the model's price is unknown.
+    pub cost_usd: Option<f64>,
+    /// When the call was made.
+    pub created_at: String,
+}
+
 /// Trait for memory storage backends.
 #[async_trait]
 pub trait MemoryStore: Send + Sync + std::fmt::Debug {
     // Conversation operations
     
-    /// Creates a new conversation.
-    async fn create_conversation(&self, name: Option<String>, metadata: Option<String>) -> Result<String, MemoryError>;
+    /// Creates a new conversation, optionally within a namespace.
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        metadata: Option<String>,
+        namespace: Option<&str>,
+    ) -> Result<String, MemoryError>;
     
     /// Gets a conversation by ID.
     async fn get_conversation(&self, id: &str) -> Result<Conversation, MemoryError>;
     
-    /// Lists all conversations (most recent first).
-    async fn list_conversations(&self, limit: usize) -> Result<Vec<Conversation>, MemoryError>;
+    /// Lists the conversations matching a filter, most recently updated first.
+    async fn list_conversations(&self, limit: usize, filter: &ConversationFilter) -> Result<Vec<Conversation>, MemoryError>;
     
     /// Deletes a conversation and all its messages.
     async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError>;
 
+    /// Updates the title, metadata or archived flag of a conversation and
+    /// returns it as updated.
+    async fn update_conversation(&self, id: &str, update: ConversationUpdate) -> Result<Conversation, MemoryError>;
+
+    /// Adds and removes tags of a conversation and returns its tags, sorted.
+    async fn tag_conversation(&self, id: &str, add: &[String], remove: &[String]) -> Result<Vec<String>, MemoryError>;
+
     // Message operations
     
     /// Adds a message to a conversation.
@@ -95,14 +394,27 @@ pub trait MemoryStore: Send + Sync + std::fmt::Debug {
         metadata: Option<String>,
     ) -> Result<String, MemoryError>;
     
+    /// Replaces the content and/or metadata of a message of a conversation
+    /// and returns it as updated.
+    async fn update_message(
+        &self,
+        conversation_id: &str,
+        id: &str,
+        content: Option<&str>,
+        metadata: Option<String>,
+    ) -> Result<Message, MemoryError>;
+
+    /// Deletes a message of a conversation.
+    async fn delete_message(&self, conversation_id: &str, id: &str) -> Result<(), MemoryError>;
+
     /// Gets messages for a conversation.
     async fn get_messages(&self, conversation_id: &str, limit: usize) -> Result<Vec<Message>, MemoryError>;
     
     /// Gets the last N messages across all conversations.
     async fn get_recent_messages(&self, limit: usize) -> Result<Vec<Message>, MemoryError>;
 
-    /// Searches messages by content.
-    async fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<Message>, MemoryError>;
+    /// Searches messages matching a filter by content, newest first.
+    async fn search_messages(&self, query: &str, limit: usize, filter: &MessageFilter) -> Result<Vec<Message>, MemoryError>;
 
     // Key-Value operations
     
@@ -117,5 +429,78 @@ pub trait MemoryStore: Send + Sync + std::fmt::Debug {
     
     /// Lists all keys (optionally with prefix filter).
     async fn kv_list(&self, prefix: Option<&str>) -> Result<Vec<String>, MemoryError>;
+
+    /// Applies a batch of operations atomically: either every operation
+    /// succeeds, or none take effect. Returns one result per operation.
+    async fn kv_batch(&self, ops: Vec<KvOp>) -> Result<Vec<serde_json::Value>, MemoryError>;
+
+    // Workflow operations
+
+    /// Saves a new version of a workflow and returns its version number.
+    async fn save_workflow(&self, name: &str, definition: serde_json::Value) -> Result<i64, MemoryError>;
+
+    /// Gets a workflow version (the latest if `version` is None).
+    async fn get_workflow(&self, name: &str, version: Option<i64>) -> Result<Option<WorkflowVersion>, MemoryError>;
+
+    /// Lists all versions of a workflow (newest first).
+    async fn list_workflow_versions(&self, name: &str) -> Result<Vec<WorkflowVersion>, MemoryError>;
+
+    /// Lists the latest version of every workflow.
+    async fn list_workflows(&self) -> Result<Vec<WorkflowVersion>, MemoryError>;
+
+    /// Records a workflow execution.
+    async fn record_workflow_run(&self, run: &WorkflowRun) -> Result<(), MemoryError>;
+
+    /// Lists past runs (newest first), optionally for a single workflow.
+    async fn list_workflow_runs(&self, workflow: Option<&str>, limit: usize) -> Result<Vec<WorkflowRun>, MemoryError>;
+
+    // Scheduled task history
+
+    /// Records a run of a scheduled task, keeping only the task's `keep`
+    /// most recent runs.
+    async fn record_task_run(&self, run: &TaskRun, keep: usize) -> Result<(), MemoryError>;
+
+    /// Lists past runs of a scheduled task (newest first).
+    async fn list_task_runs(&self, task_id: &str, limit: usize) -> Result<Vec<TaskRun>, MemoryError>;
+
+    // Audit operations
+
+    /// Records a client tool call.
+    async fn record_tool_call(&self, record: &ToolCallRecord) -> Result<(), MemoryError>;
+
+    /// Lists recorded tool calls (oldest first), optionally only those
+    /// started at or after `since` (RFC 3339).
+    async fn list_tool_calls(&self, since: Option<&str>) -> Result<Vec<ToolCallRecord>, MemoryError>;
+
+    // LLM usage operations
+
+    /// Records the token usage of an LLM call.
+    async fn record_llm_usage(&self, record: &LlmUsageRecord) -> Result<(), MemoryError>;
+
+    /// Lists recorded LLM usage (oldest first), optionally only calls made
+    /// at or after `since` (RFC 3339).
+    async fn list_llm_usage(&self, since: Option<&str>) -> Result<Vec<LlmUsageRecord>, MemoryError>;
+
+    /// Total estimated cost in USD of calls made at or after `since`,
+    /// optionally only those made with one API key (by hash).
+    async fn llm_cost_since(&self, since: &str, api_key_hash: Option<&str>) -> Result<f64, MemoryError>;
+
+    // Lifecycle
+
+    /// Makes all completed writes durable, e.g. before the process exits.
+    async fn flush(&self) -> Result<(), MemoryError> {
+        Ok(())
+    }
+
+    /// Deletes expired KV entries and returns how many were removed.
+    async fn purge_expired(&self) -> Result<u64, MemoryError> {
+        Ok(0)
+    }
+
+    /// Reclaims free space and refreshes query planner statistics, returning
+    /// the bytes reclaimed (0 if the backend can't tell).
+    async fn compact(&self) -> Result<u64, MemoryError> {
+        Ok(0)
+    }
 }
 
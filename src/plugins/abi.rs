@@ -0,0 +1,216 @@
+//! Stable C ABI for native (cdylib) plugins.
+//!
+//! A native plugin is a `cdylib` crate that depends on `aegis`, implements
+//! [`NativePlugin`] and invokes [`export_plugin!`](crate::export_plugin):
+//!
+//! ```ignore
+//! struct Hello;
+//!
+//! impl aegis::plugins::NativePlugin for Hello {
+//!     fn tools(&self) -> Vec<aegis::protocol::mcp::Tool> { /* ... */ }
+//!     fn call(&self, name: &str, arguments: serde_json::Value) -> Result<serde_json::Value, String> {
+//!         Ok(serde_json::json!({ "greeting": format!("hello from {}", name) }))
+//!     }
+//! }
+//!
+//! aegis::export_plugin!(Hello);
+//! ```
+//!
+//! The macro exports two symbols. `aegis_plugin_abi_version` is checked
+//! against [`ABI_VERSION`] before anything else is touched; only then is
+//! `aegis_plugin_declare` called for the [`PluginDeclaration`]. Only
+//! NUL-terminated JSON strings cross the boundary, and panics are caught on
+//! the plugin side and reported as errors.
+
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::protocol::mcp::Tool as ToolDefinition;
+
+/// Version of the native plugin ABI. Bumped on any incompatible change.
+pub const ABI_VERSION: u32 = 1;
+
+/// Symbol returning the plugin's ABI version.
+pub const ABI_VERSION_SYMBOL: &[u8] = b"aegis_plugin_abi_version\0";
+
+/// Symbol returning the plugin's [`PluginDeclaration`].
+pub const DECLARE_SYMBOL: &[u8] = b"aegis_plugin_declare\0";
+
+/// Function table exported by a native plugin.
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// ABI version the plugin was built against.
+    pub abi_version: u32,
+    /// Returns the tool definitions as a JSON array.
+    pub describe: unsafe extern "C" fn() -> *mut c_char,
+    /// Calls a tool. Returns `{"ok": value}`, `{"error": message}` or
+    /// `{"panic": message}`.
+    pub call: unsafe extern "C" fn(name: *const c_char, arguments: *const c_char) -> *mut c_char,
+    /// Frees a string returned by `describe` or `call`.
+    pub free: unsafe extern "C" fn(*mut c_char),
+}
+
+/// A set of tools implemented in a native plugin.
+pub trait NativePlugin: Send + Sync {
+    /// Returns the tools this plugin provides.
+    fn tools(&self) -> Vec<ToolDefinition>;
+
+    /// Calls one of the plugin's tools.
+    fn call(&self, name: &str, arguments: Value) -> Result<Value, String>;
+}
+
+/// Plugin-side glue used by [`export_plugin!`](crate::export_plugin).
+#[doc(hidden)]
+pub mod sdk {
+    use super::*;
+
+    /// Accessor for the plugin instance, created on first use.
+    pub type PluginFn = fn() -> &'static dyn NativePlugin;
+
+    fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+        payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    }
+
+    fn into_c_string(value: Value) -> *mut c_char {
+        // JSON never contains interior NULs
+        CString::new(value.to_string()).unwrap_or_default().into_raw()
+    }
+
+    /// Implements [`PluginDeclaration::describe`].
+    pub fn describe(plugin: PluginFn) -> *mut c_char {
+        let result = catch_unwind(|| serde_json::to_value(plugin().tools()));
+        into_c_string(match result {
+            Ok(Ok(tools)) => tools,
+            Ok(Err(e)) => json!({ "error": e.to_string() }),
+            Err(payload) => json!({ "panic": panic_message(payload) }),
+        })
+    }
+
+    /// Implements [`PluginDeclaration::call`].
+    ///
+    /// # Safety
+    ///
+    /// `name` and `arguments` must be valid NUL-terminated strings.
+    pub unsafe fn call(plugin: PluginFn, name: *const c_char, arguments: *const c_char) -> *mut c_char {
+        let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+        let arguments = CStr::from_ptr(arguments).to_string_lossy().into_owned();
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let arguments: Value = serde_json::from_str(&arguments).map_err(|e| e.to_string())?;
+            plugin().call(&name, arguments)
+        }));
+
+        into_c_string(match result {
+            Ok(Ok(value)) => json!({ "ok": value }),
+            Ok(Err(message)) => json!({ "error": message }),
+            Err(payload) => json!({ "panic": panic_message(payload) }),
+        })
+    }
+
+    /// Implements [`PluginDeclaration::free`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `describe` or `call` and not be freed twice.
+    pub unsafe extern "C" fn free(ptr: *mut c_char) {
+        if !ptr.is_null() {
+            drop(CString::from_raw(ptr));
+        }
+    }
+}
+
+/// Exports a [`NativePlugin`] from a `cdylib` crate.
+///
+/// Takes an expression that constructs the plugin; it is evaluated once, on
+/// first use.
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:expr) => {
+        fn __aegis_plugin() -> &'static dyn $crate::plugins::NativePlugin {
+            static PLUGIN: ::std::sync::OnceLock<::std::boxed::Box<dyn $crate::plugins::NativePlugin>> =
+                ::std::sync::OnceLock::new();
+            PLUGIN.get_or_init(|| ::std::boxed::Box::new($plugin)).as_ref()
+        }
+
+        unsafe extern "C" fn __aegis_describe() -> *mut ::std::os::raw::c_char {
+            $crate::plugins::abi::sdk::describe(__aegis_plugin)
+        }
+
+        unsafe extern "C" fn __aegis_call(
+            name: *const ::std::os::raw::c_char,
+            arguments: *const ::std::os::raw::c_char,
+        ) -> *mut ::std::os::raw::c_char {
+            $crate::plugins::abi::sdk::call(__aegis_plugin, name, arguments)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn aegis_plugin_abi_version() -> u32 {
+            $crate::plugins::ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn aegis_plugin_declare() -> *const $crate::plugins::PluginDeclaration {
+            static DECLARATION: $crate::plugins::PluginDeclaration = $crate::plugins::PluginDeclaration {
+                abi_version: $crate::plugins::ABI_VERSION,
+                describe: __aegis_describe,
+                call: __aegis_call,
+                free: $crate::plugins::abi::sdk::free,
+            };
+            &DECLARATION
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Panicky;
+
+    impl NativePlugin for Panicky {
+        fn tools(&self) -> Vec<ToolDefinition> {
+            vec![]
+        }
+
+        fn call(&self, name: &str, arguments: Value) -> Result<Value, String> {
+            match name {
+                "echo" => Ok(arguments),
+                "fail" => Err("nope".to_string()),
+                _ => panic!("boom"),
+            }
+        }
+    }
+
+    fn plugin() -> &'static dyn NativePlugin {
+        &Panicky
+    }
+
+    fn call(name: &str, arguments: &str) -> Value {
+        let name = CString::new(name).unwrap();
+        let arguments = CString::new(arguments).unwrap();
+        unsafe {
+            let ptr = sdk::call(plugin, name.as_ptr(), arguments.as_ptr());
+            let result = serde_json::from_str(&CStr::from_ptr(ptr).to_string_lossy()).unwrap();
+            sdk::free(ptr);
+            result
+        }
+    }
+
+    #[test]
+    fn test_sdk_call_results() {
+        assert_eq!(call("echo", r#"{"a":1}"#), json!({ "ok": { "a": 1 } }));
+        assert_eq!(call("fail", "{}"), json!({ "error": "nope" }));
+        assert!(call("echo", "not json")["error"].is_string());
+    }
+
+    #[test]
+    fn test_sdk_call_catches_panics() {
+        assert_eq!(call("explode", "{}"), json!({ "panic": "boom" }));
+    }
+}
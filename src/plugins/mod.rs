@@ -0,0 +1,408 @@
+//! Plugin tools loaded from a directory.
+//!
+//! Every `*.wasm` file in `plugin_dir.dir` becomes a tool. An optional
+//! sidecar manifest with the same stem (`name.json`) supplies the tool name,
+//! description, input schema and the host capabilities the plugin asks for.
+//! With `hot_reload` enabled the directory is polled, and plugins are loaded,
+//! reloaded or removed as their files change.
+//!
+//! Rhai scripts (`*.rhai`) in the same directory are loaded the same way,
+//! with the same sidecar manifests, and scripts can also be defined inline
+//! under `scripts` in the config. They run in-process without any cargo
+//! feature; see [`script`].
+//!
+//! Native plugins are `cdylib`s listed in `plugin_dir.native_paths` that
+//! export tools through the C ABI in [`abi`]. They are loaded once at
+//! startup.
+//!
+//! WASM support needs the `wasm` cargo feature and native plugins need the
+//! `native-plugins` feature.
+
+pub mod abi;
+#[cfg(feature = "native-plugins")]
+mod native;
+pub mod script;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::core::{AegisError, AegisResult, PluginDirConfig, RuntimeState};
+use crate::tools::Tool;
+
+pub use abi::{NativePlugin, PluginDeclaration, ABI_VERSION};
+#[cfg(feature = "native-plugins")]
+pub use native::{load_native_plugins, NativeLibrary, NativeTool};
+pub use script::RhaiTool;
+#[cfg(feature = "wasm")]
+pub use wasm::{WasmRuntime, WasmTool};
+
+/// Host functions a plugin can be granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Write to the server log.
+    Log,
+    /// Read and write KV entries scoped to the plugin.
+    Kv,
+    /// Send HTTP requests (Rhai scripts only).
+    Http,
+}
+
+impl Capability {
+    /// Parses a capability name.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "log" => Some(Self::Log),
+            "kv" => Some(Self::Kv),
+            "http" => Some(Self::Http),
+            _ => None,
+        }
+    }
+}
+
+/// Sidecar manifest describing a plugin.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginManifest {
+    /// Tool name (default: the file stem).
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// JSON Schema for input parameters.
+    #[serde(default)]
+    pub input_schema: Option<Value>,
+
+    /// Capabilities the plugin asks for.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl PluginManifest {
+    /// Reads the manifest next to `path`, if there is one.
+    fn for_plugin(path: &Path) -> AegisResult<Self> {
+        let manifest_path = path.with_extension("json");
+        if !manifest_path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&manifest_path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            AegisError::Config(format!("Invalid manifest {}: {}", manifest_path.display(), e))
+        })
+    }
+}
+
+/// Returns the capabilities in `requested` that are also in `allowed`.
+///
+/// Unknown or disallowed capabilities are logged and dropped; a plugin that
+/// imports a host function it was not granted fails to load.
+pub fn granted_capabilities(plugin: &str, requested: &[String], allowed: &[String]) -> Vec<Capability> {
+    let mut granted = Vec::new();
+    for name in requested {
+        match Capability::parse(name) {
+            Some(cap) if allowed.contains(name) => granted.push(cap),
+            Some(_) => warn!("Plugin '{}' requested capability '{}', which is not allowed", plugin, name),
+            None => warn!("Plugin '{}' requested unknown capability '{}'", plugin, name),
+        }
+    }
+    granted
+}
+
+/// A plugin file the loader has seen.
+#[derive(Debug)]
+struct LoadedPlugin {
+    /// Latest modification time of the module and its manifest.
+    modified: SystemTime,
+    /// Registered tool name, or `None` if loading failed.
+    tool_name: Option<String>,
+}
+
+/// Loads plugins from a directory and keeps the tool registry in sync with it.
+#[derive(Debug)]
+pub struct PluginLoader {
+    dir: PathBuf,
+    config: PluginDirConfig,
+    loaded: HashMap<PathBuf, LoadedPlugin>,
+    #[cfg(feature = "wasm")]
+    runtime: WasmRuntime,
+}
+
+impl PluginLoader {
+    /// Creates a loader for the configured plugin directory.
+    pub fn new(config: &PluginDirConfig) -> AegisResult<Self> {
+        let dir = config
+            .dir
+            .as_ref()
+            .ok_or_else(|| AegisError::Config("plugin_dir.dir is not set".to_string()))?;
+
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            config: config.clone(),
+            loaded: HashMap::new(),
+            #[cfg(feature = "wasm")]
+            runtime: WasmRuntime::new(config)?,
+        })
+    }
+
+    /// Scans the directory once, loading new or changed plugins and
+    /// unregistering removed ones. Returns the number of tools (re)loaded.
+    pub fn scan(&mut self, state: &RuntimeState) -> usize {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Cannot read plugin directory {}: {}", self.dir.display(), e);
+                return 0;
+            }
+        };
+
+        let mut seen = Vec::new();
+        let mut loaded = 0;
+
+        for path in entries.flatten().map(|e| e.path()) {
+            if !matches!(path.extension().and_then(|e| e.to_str()), Some("wasm" | "rhai")) {
+                continue;
+            }
+            seen.push(path.clone());
+
+            let modified = modified_time(&path);
+            if self.loaded.get(&path).is_some_and(|p| p.modified >= modified) {
+                continue;
+            }
+
+            // Drop the previous version before registering the new one
+            if let Some(name) = self.loaded.remove(&path).and_then(|p| p.tool_name) {
+                state.tool_registry.write().unregister(&name);
+            }
+
+            let tool_name = match self.load(&path) {
+                Ok(tool) => {
+                    let name = tool.definition().name;
+                    state.tool_registry.write().register(tool);
+                    info!("Loaded plugin {} as '{}'", path.display(), name);
+                    loaded += 1;
+                    Some(name)
+                }
+                Err(e) => {
+                    warn!("Failed to load plugin {}: {}", path.display(), e);
+                    None
+                }
+            };
+            self.loaded.insert(path, LoadedPlugin { modified, tool_name });
+        }
+
+        let removed: Vec<PathBuf> = self
+            .loaded
+            .keys()
+            .filter(|path| !seen.contains(path))
+            .cloned()
+            .collect();
+        let mut unloaded = 0;
+        for path in removed {
+            if let Some(name) = self.loaded.remove(&path).and_then(|p| p.tool_name) {
+                state.tool_registry.write().unregister(&name);
+                info!("Unloaded plugin '{}' ({} was removed)", name, path.display());
+                unloaded += 1;
+            }
+        }
+
+        if loaded + unloaded > 0 {
+            state.notify_tools_changed();
+        }
+        loaded
+    }
+
+    /// Loads a single plugin file.
+    fn load(&self, path: &Path) -> AegisResult<Arc<dyn Tool>> {
+        let manifest = PluginManifest::for_plugin(path)?;
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("plugin");
+        let name = manifest.name.clone().unwrap_or_else(|| stem.to_string());
+        let capabilities =
+            granted_capabilities(&name, &manifest.capabilities, &self.config.allowed_capabilities);
+
+        if path.extension().and_then(|e| e.to_str()) == Some("rhai") {
+            let tool = RhaiTool::from_file(path, name, &manifest, capabilities, self.config.max_script_operations)?;
+            return Ok(Arc::new(tool));
+        }
+
+        #[cfg(feature = "wasm")]
+        {
+            let tool = self.runtime.load(path, name, &manifest, capabilities)?;
+            Ok(Arc::new(tool))
+        }
+
+        #[cfg(not(feature = "wasm"))]
+        {
+            let _ = capabilities;
+            Err(AegisError::Config(format!(
+                "cannot load '{}': Aegis was built without the `wasm` feature",
+                name
+            )))
+        }
+    }
+}
+
+/// Returns the latest modification time of a plugin and its manifest.
+fn modified_time(path: &Path) -> SystemTime {
+    let mtime = |p: &Path| {
+        std::fs::metadata(p)
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    };
+    mtime(path).max(mtime(&path.with_extension("json")))
+}
+
+/// Loads the configured native plugins.
+fn load_native(state: &RuntimeState) -> usize {
+    let paths = &state.config.plugin_dir.native_paths;
+    if paths.is_empty() {
+        return 0;
+    }
+
+    #[cfg(feature = "native-plugins")]
+    {
+        load_native_plugins(paths, state)
+    }
+
+    #[cfg(not(feature = "native-plugins"))]
+    {
+        warn!("plugin_dir.native_paths is set but Aegis was built without the `native-plugins` feature");
+        0
+    }
+}
+
+/// Registers the scripts defined inline in the config.
+fn load_scripts(state: &RuntimeState) -> usize {
+    let config = &state.config.plugin_dir;
+    let mut loaded = 0;
+    for script in &state.config.scripts {
+        let capabilities = granted_capabilities(&script.name, &script.capabilities, &config.allowed_capabilities);
+        match RhaiTool::from_config(script, capabilities, config.max_script_operations) {
+            Ok(tool) => {
+                state.tool_registry.write().register(Arc::new(tool));
+                loaded += 1;
+            }
+            Err(e) => warn!("Failed to load script '{}': {}", script.name, e),
+        }
+    }
+    loaded
+}
+
+/// Loads native plugins, inline scripts and plugins from the configured
+/// directory and, with `hot_reload`, starts watching the directory. Returns
+/// the number of tools loaded initially.
+pub fn load_plugins(state: &Arc<RuntimeState>) -> usize {
+    let mut loaded = load_native(state) + load_scripts(state);
+
+    let config = &state.config.plugin_dir;
+    if config.dir.is_none() {
+        return loaded;
+    }
+
+    let mut loader = match PluginLoader::new(config) {
+        Ok(loader) => loader,
+        Err(e) => {
+            warn!("Plugin loader disabled: {}", e);
+            return loaded;
+        }
+    };
+
+    loaded += loader.scan(state);
+
+    if config.hot_reload {
+        let loader = Arc::new(Mutex::new(loader));
+        let state = state.clone();
+        let interval = Duration::from_secs(config.poll_secs.max(1));
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let loader = loader.clone();
+                let state = state.clone();
+                // Compiling modules is CPU-bound
+                let _ = tokio::task::spawn_blocking(move || loader.lock().scan(&state)).await;
+            }
+        });
+    }
+
+    loaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_granted_capabilities() {
+        let requested = vec!["log".to_string(), "kv".to_string(), "net".to_string()];
+        let allowed = vec!["log".to_string()];
+        assert_eq!(granted_capabilities("p", &requested, &allowed), vec![Capability::Log]);
+    }
+
+    #[test]
+    fn test_manifest_defaults_without_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = PluginManifest::for_plugin(&dir.path().join("echo.wasm")).unwrap();
+        assert!(manifest.name.is_none());
+        assert!(manifest.capabilities.is_empty());
+    }
+
+    #[test]
+    fn test_scan_loads_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("greet.rhai"), r#""Hello, " + args.name"#).unwrap();
+        std::fs::write(dir.path().join("greet.json"), r#"{"name": "script.greet"}"#).unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a plugin").unwrap();
+
+        let state = RuntimeState::new(crate::core::Config::default());
+        let mut loader = PluginLoader::new(&PluginDirConfig {
+            dir: Some(dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(loader.scan(&state), 1);
+        assert!(state.tool_registry.read().get("script.greet").is_some());
+
+        std::fs::remove_file(dir.path().join("greet.rhai")).unwrap();
+        loader.scan(&state);
+        assert!(state.tool_registry.read().get("script.greet").is_none());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_scan_loads_and_unloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = r#"(module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) i32.const 0)
+          (func (export "run") (param i32 i32) (result i64) i64.const 0))"#;
+        std::fs::write(dir.path().join("noop.wasm"), module).unwrap();
+        std::fs::write(dir.path().join("noop.json"), r#"{"name": "plugin.noop"}"#).unwrap();
+
+        let state = RuntimeState::new(crate::core::Config::default());
+        let mut loader = PluginLoader::new(&PluginDirConfig {
+            dir: Some(dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(loader.scan(&state), 1);
+        assert!(state.tool_registry.read().get("plugin.noop").is_some());
+
+        // Unchanged files are not reloaded
+        assert_eq!(loader.scan(&state), 0);
+
+        std::fs::remove_file(dir.path().join("noop.wasm")).unwrap();
+        loader.scan(&state);
+        assert!(state.tool_registry.read().get("plugin.noop").is_none());
+    }
+}
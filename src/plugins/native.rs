@@ -0,0 +1,298 @@
+//! Host side of native plugins: loads `cdylib`s built with
+//! [`export_plugin!`](crate::export_plugin) and registers their tools.
+
+use async_trait::async_trait;
+use libloading::{Library, Symbol};
+use serde_json::Value;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::abi::{PluginDeclaration, ABI_VERSION, ABI_VERSION_SYMBOL, DECLARE_SYMBOL};
+use crate::core::{AegisError, AegisResult, RuntimeState};
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+/// A loaded native plugin.
+pub struct NativeLibrary {
+    source: String,
+    declaration: &'static PluginDeclaration,
+    // Keeps the code behind `declaration` mapped
+    _library: Option<Library>,
+}
+
+impl std::fmt::Debug for NativeLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeLibrary").field("source", &self.source).finish()
+    }
+}
+
+impl NativeLibrary {
+    /// Loads a plugin library, checking its ABI version first.
+    pub fn open(path: &Path) -> AegisResult<Self> {
+        // SAFETY: loading a library runs its initializers; native plugins are
+        // trusted code by configuration. The symbol types match `export_plugin!`.
+        unsafe {
+            let library = Library::new(path)
+                .map_err(|e| AegisError::Config(format!("Failed to load {}: {}", path.display(), e)))?;
+
+            let version: Symbol<extern "C" fn() -> u32> =
+                library.get(ABI_VERSION_SYMBOL).map_err(|_| {
+                    AegisError::Config(format!(
+                        "{} is not an Aegis plugin (no aegis_plugin_abi_version symbol)",
+                        path.display()
+                    ))
+                })?;
+            let version = version();
+            if version != ABI_VERSION {
+                return Err(AegisError::Config(format!(
+                    "{} was built for plugin ABI v{}, this server supports v{}",
+                    path.display(),
+                    version,
+                    ABI_VERSION
+                )));
+            }
+
+            let declare: Symbol<extern "C" fn() -> *const PluginDeclaration> =
+                library.get(DECLARE_SYMBOL).map_err(|e| {
+                    AegisError::Config(format!("{}: {}", path.display(), e))
+                })?;
+            let declaration = declare();
+            if declaration.is_null() {
+                return Err(AegisError::Config(format!(
+                    "{} returned no plugin declaration",
+                    path.display()
+                )));
+            }
+
+            // The declaration lives as long as the library, which we keep
+            Self::from_declaration(path.display().to_string(), &*declaration, Some(library))
+        }
+    }
+
+    /// Wraps a declaration that is already in memory.
+    pub fn from_declaration(
+        source: impl Into<String>,
+        declaration: &'static PluginDeclaration,
+        library: Option<Library>,
+    ) -> AegisResult<Self> {
+        let source = source.into();
+        if declaration.abi_version != ABI_VERSION {
+            return Err(AegisError::Config(format!(
+                "{} declares plugin ABI v{}, this server supports v{}",
+                source, declaration.abi_version, ABI_VERSION
+            )));
+        }
+
+        Ok(Self {
+            source,
+            declaration,
+            _library: library,
+        })
+    }
+
+    /// Takes ownership of a string returned by the plugin and parses it.
+    fn take_json(&self, ptr: *mut c_char) -> Result<Value, ToolError> {
+        if ptr.is_null() {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Plugin {} returned null",
+                self.source
+            )));
+        }
+
+        // SAFETY: the plugin hands us a NUL-terminated string that we free
+        // exactly once with its own allocator.
+        let text = unsafe {
+            let text = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+            (self.declaration.free)(ptr);
+            text
+        };
+
+        let value: Value = serde_json::from_str(&text).map_err(|e| {
+            ToolError::ExecutionFailed(format!("Plugin {} returned invalid JSON: {}", self.source, e))
+        })?;
+
+        if let Some(message) = value.get("panic").and_then(|m| m.as_str()) {
+            warn!("Native plugin {} panicked: {}", self.source, message);
+            return Err(ToolError::ExecutionFailed(format!("Plugin panicked: {}", message)));
+        }
+        if let Some(message) = value.get("error").and_then(|m| m.as_str()) {
+            return Err(ToolError::ExecutionFailed(message.to_string()));
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the plugin's tools.
+    pub fn tools(self: &Arc<Self>) -> AegisResult<Vec<NativeTool>> {
+        // SAFETY: `describe` takes no arguments and returns an owned string.
+        let described = self
+            .take_json(unsafe { (self.declaration.describe)() })
+            .map_err(|e| AegisError::Config(e.to_string()))?;
+
+        let definitions: Vec<ToolDefinition> = serde_json::from_value(described)?;
+        Ok(definitions
+            .into_iter()
+            .map(|definition| NativeTool {
+                definition,
+                library: self.clone(),
+            })
+            .collect())
+    }
+
+    /// Calls a tool in the plugin.
+    fn call(&self, name: &str, arguments: &Value) -> Result<Value, ToolError> {
+        let name = CString::new(name).map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+        let arguments = CString::new(arguments.to_string())
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        // SAFETY: both pointers are valid NUL-terminated strings for the call.
+        let result = self.take_json(unsafe {
+            (self.declaration.call)(name.as_ptr(), arguments.as_ptr())
+        })?;
+        Ok(result.get("ok").cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// A tool provided by a native plugin.
+#[derive(Debug)]
+pub struct NativeTool {
+    definition: ToolDefinition,
+    library: Arc<NativeLibrary>,
+}
+
+#[async_trait]
+impl Tool for NativeTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let library = self.library.clone();
+        let name = self.definition.name.clone();
+
+        // Plugin code is synchronous and may block
+        let result = tokio::task::spawn_blocking(move || library.call(&name, &arguments))
+            .await
+            .map_err(|e| ToolError::Internal(e.to_string()))??;
+
+        Ok(match result {
+            Value::String(text) => ToolOutput::text(text),
+            other => ToolOutput::text(serde_json::to_string_pretty(&other).unwrap_or_default()),
+        })
+    }
+}
+
+/// Expands `paths` into plugin library files.
+fn library_files(paths: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let Ok(entries) = std::fs::read_dir(&path) else {
+                warn!("Cannot read native plugin directory {}", path.display());
+                continue;
+            };
+            let mut found: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(std::env::consts::DLL_EXTENSION))
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Loads every native plugin under `paths` and registers its tools.
+/// Returns the number of tools registered.
+pub fn load_native_plugins(paths: &[String], state: &RuntimeState) -> usize {
+    let mut registered = 0;
+
+    for path in library_files(paths) {
+        let tools = NativeLibrary::open(&path).map(Arc::new).and_then(|lib| lib.tools());
+        match tools {
+            Ok(tools) => {
+                let count = tools.len();
+                let mut registry = state.tool_registry.write();
+                for tool in tools {
+                    registry.register(Arc::new(tool));
+                }
+                info!("Loaded native plugin {} ({} tools)", path.display(), count);
+                registered += count;
+            }
+            Err(e) => warn!("Failed to load native plugin {}: {}", path.display(), e),
+        }
+    }
+
+    registered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::NativePlugin;
+    use serde_json::json;
+
+    struct Greeter;
+
+    impl NativePlugin for Greeter {
+        fn tools(&self) -> Vec<ToolDefinition> {
+            vec![ToolDefinition {
+                name: "native.greet".to_string(),
+                description: Some("Greets someone".to_string()),
+                input_schema: json!({ "type": "object" }),
+                output_schema: None,
+            }]
+        }
+
+        fn call(&self, _name: &str, arguments: Value) -> Result<Value, String> {
+            match arguments.get("name").and_then(|n| n.as_str()) {
+                Some("panic") => panic!("asked to panic"),
+                Some(name) => Ok(json!(format!("Hello, {}!", name))),
+                None => Err("name is required".to_string()),
+            }
+        }
+    }
+
+    crate::export_plugin!(Greeter);
+
+    fn library() -> Arc<NativeLibrary> {
+        // SAFETY: the declaration comes from `export_plugin!` in this module.
+        let declaration = unsafe { &*aegis_plugin_declare() };
+        Arc::new(NativeLibrary::from_declaration("test", declaration, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_native_tool_calls() {
+        let state = Arc::new(RuntimeState::new(crate::core::Config::default()));
+        let tools = library().tools().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].definition().name, "native.greet");
+
+        let output = tools[0].execute(json!({"name": "Ada"}), state.clone()).await.unwrap();
+        assert!(matches!(&output.content[0], crate::tools::ToolContent::Text { text } if text == "Hello, Ada!"));
+
+        assert!(tools[0].execute(json!({}), state.clone()).await.is_err());
+
+        // A panicking plugin fails the call, not the server
+        let err = tools[0].execute(json!({"name": "panic"}), state).await.unwrap_err();
+        assert!(err.to_string().contains("asked to panic"));
+    }
+
+    #[test]
+    fn test_rejects_non_plugin_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("bogus.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&path, b"not a library").unwrap();
+        assert!(NativeLibrary::open(&path).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! Rhai script plugins.
+//!
+//! A script runs in-process in a sandboxed [Rhai](https://rhai.rs) engine, so
+//! a small transformation doesn't pay for spawning a process. The call's
+//! arguments are in `args`, and the value of the last expression (or of a
+//! top-level `return`) is the result: a string is returned as text, anything
+//! else as JSON. `throw` fails the call with the thrown value as the message.
+//!
+//! Scripts cannot import modules or touch the file system, `print` and
+//! `debug` go to the server log, and every call is bounded by
+//! `plugin_dir.max_script_operations`. Host functions are available for the
+//! capabilities the script was granted:
+//!
+//! - `log`: `log(message)`
+//! - `kv`: `kv_get(key)` (`()` when missing) and `kv_set(key, value)`,
+//!   scoped to `plugin:{name}:` keys like WASM plugins
+//! - `http`: `fetch(url)` and `fetch(url, #{method, headers, body, json})`,
+//!   returning `#{status, body, json}` (`json` is `()` unless the body is
+//!   JSON). Requests pass the same URL patterns and private network checks
+//!   as `http.request`.
+
+use async_trait::async_trait;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tracing::{debug, info};
+
+use super::{Capability, PluginManifest};
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
+use crate::core::{AegisError, AegisResult, RuntimeState, ScriptConfig};
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::core::check_url_patterns;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+/// Nesting limits; deep enough for real scripts, shallow enough that a
+/// runaway recursion fails before it exhausts the thread's stack.
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+
+type HostResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A tool backed by a compiled Rhai script.
+#[derive(Debug, Clone)]
+pub struct RhaiTool {
+    name: String,
+    definition: ToolDefinition,
+    ast: Arc<AST>,
+    capabilities: Vec<Capability>,
+    max_operations: u64,
+}
+
+impl RhaiTool {
+    /// Compiles `source` into a tool.
+    pub fn new(
+        definition: ToolDefinition,
+        source: &str,
+        capabilities: Vec<Capability>,
+        max_operations: u64,
+    ) -> AegisResult<Self> {
+        let ast = Engine::new()
+            .compile(source)
+            .map_err(|e| AegisError::Config(format!("Invalid script '{}': {}", definition.name, e)))?;
+
+        Ok(Self {
+            name: definition.name.clone(),
+            definition,
+            ast: Arc::new(ast),
+            capabilities,
+            max_operations,
+        })
+    }
+
+    /// Compiles a script defined inline in the config.
+    pub fn from_config(config: &ScriptConfig, capabilities: Vec<Capability>, max_operations: u64) -> AegisResult<Self> {
+        let definition = ToolDefinition {
+            name: config.name.clone(),
+            description: config.description.clone(),
+            input_schema: config.input_schema.clone(),
+            output_schema: None,
+        };
+        Self::new(definition, &config.source, capabilities, max_operations)
+    }
+
+    /// Compiles a `.rhai` file from the plugin directory.
+    pub fn from_file(
+        path: &Path,
+        name: String,
+        manifest: &PluginManifest,
+        capabilities: Vec<Capability>,
+        max_operations: u64,
+    ) -> AegisResult<Self> {
+        let source = std::fs::read_to_string(path)?;
+        let definition = ToolDefinition {
+            name,
+            description: manifest.description.clone(),
+            input_schema: manifest
+                .input_schema
+                .clone()
+                .unwrap_or_else(|| serde_json::json!({ "type": "object", "properties": {} })),
+            output_schema: None,
+        };
+        Self::new(definition, &source, capabilities, max_operations)
+    }
+
+    /// Builds an engine with the limits and the granted host functions.
+    fn engine(&self, state: Arc<RuntimeState>, handle: Handle) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(self.max_operations);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+        engine.set_module_resolver(DummyModuleResolver::new());
+
+        // stdout carries the protocol in stdio mode
+        let plugin = self.name.clone();
+        engine.on_print(move |text| info!("[script {}] {}", plugin, text));
+        let plugin = self.name.clone();
+        engine.on_debug(move |text, _, _| debug!("[script {}] {}", plugin, text));
+
+        if self.capabilities.contains(&Capability::Log) {
+            let plugin = self.name.clone();
+            engine.register_fn("log", move |message: &str| info!("[script {}] {}", plugin, message));
+        }
+
+        if self.capabilities.contains(&Capability::Kv) {
+            let plugin = self.name.clone();
+            let (kv_state, kv_handle) = (state.clone(), handle.clone());
+            engine.register_fn("kv_get", move |key: &str| -> HostResult<Dynamic> {
+                let key = format!("plugin:{}:{}", plugin, key);
+                let entry = kv_handle
+                    .block_on(kv_state.memory_store.kv_get(&key))
+                    .map_err(|e| e.to_string())?;
+                match entry {
+                    Some(entry) => rhai::serde::to_dynamic(entry.value),
+                    None => Ok(Dynamic::UNIT),
+                }
+            });
+
+            let plugin = self.name.clone();
+            let (kv_state, kv_handle) = (state.clone(), handle.clone());
+            engine.register_fn("kv_set", move |key: &str, value: Dynamic| -> HostResult<()> {
+                let key = format!("plugin:{}:{}", plugin, key);
+                let value: Value = rhai::serde::from_dynamic(&value)?;
+                kv_handle
+                    .block_on(kv_state.memory_store.kv_set(&key, value, None))
+                    .map_err(|e| e.to_string().into())
+            });
+        }
+
+        if self.capabilities.contains(&Capability::Http) {
+            let (fetch_state, fetch_handle) = (state.clone(), handle.clone());
+            engine.register_fn("fetch", move |url: &str| -> HostResult<Map> {
+                fetch(&fetch_state, &fetch_handle, url, &Map::new())
+            });
+            engine.register_fn("fetch", move |url: &str, options: Map| -> HostResult<Map> {
+                fetch(&state, &handle, url, &options)
+            });
+        }
+
+        engine
+    }
+
+    /// Runs the script on `arguments`.
+    fn call(&self, arguments: Value, state: Arc<RuntimeState>, handle: Handle) -> Result<Value, ToolError> {
+        let engine = self.engine(state, handle);
+        let args = rhai::serde::to_dynamic(arguments).map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+        let mut scope = Scope::new();
+        scope.push("args", args);
+
+        let result = engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|e| self.script_error(*e))?;
+        rhai::serde::from_dynamic(&result).map_err(|e| {
+            ToolError::ExecutionFailed(format!("Script '{}' returned a value that isn't JSON: {}", self.name, e))
+        })
+    }
+
+    fn script_error(&self, e: EvalAltResult) -> ToolError {
+        match e {
+            EvalAltResult::ErrorTooManyOperations(_) => {
+                ToolError::ExecutionFailed(format!("Script '{}' ran out of operations", self.name))
+            }
+            EvalAltResult::ErrorRuntime(value, _) => ToolError::ExecutionFailed(value.to_string()),
+            e => ToolError::ExecutionFailed(format!("Script '{}' failed: {}", self.name, e)),
+        }
+    }
+}
+
+/// Sends a request for a script with the `http` capability.
+fn fetch(state: &RuntimeState, handle: &Handle, url: &str, options: &Map) -> HostResult<Map> {
+    let config = &state.config.http_client;
+    check_url_patterns(config, url).map_err(|e| e.to_string())?;
+    state.egress.check_url(ToolCategory::Http, url)?;
+
+    let option = |name: &str| options.get(name).filter(|value| !value.is_unit());
+    let method = option("method")
+        .map(|method| method.to_string().to_uppercase())
+        .unwrap_or_else(|| "GET".to_string());
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", method))?;
+
+    let profile = ClientProfile::new(ToolCategory::Http)
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .user_agent(&config.user_agent);
+    let client = state.http_clients.get(&profile).map_err(|e| e.to_string())?;
+    let mut request = client.request(method, url);
+
+    if let Some(headers) = option("headers") {
+        let headers = headers.read_lock::<Map>().ok_or("headers must be a map")?;
+        for (name, value) in headers.iter() {
+            request = request.header(name.as_str(), value.to_string());
+        }
+    }
+    if let Some(json) = option("json") {
+        request = request.json(&rhai::serde::from_dynamic::<Value>(json)?);
+    } else if let Some(body) = option("body") {
+        request = request.body(body.to_string());
+    }
+
+    let (status, bytes) = handle
+        .block_on(async {
+            let response = request.send().await?;
+            let status = response.status().as_u16();
+            response.bytes().await.map(|bytes| (status, bytes))
+        })
+        .map_err(|e| format!("HTTP request failed: {}", e))?;
+    if bytes.len() > config.max_response_bytes {
+        return Err(format!("Response too large: {} bytes (max: {})", bytes.len(), config.max_response_bytes).into());
+    }
+
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+    let json = match serde_json::from_str::<Value>(&body) {
+        Ok(json) => rhai::serde::to_dynamic(json)?,
+        Err(_) => Dynamic::UNIT,
+    };
+
+    let mut response = Map::new();
+    response.insert("status".into(), Dynamic::from_int(status as rhai::INT));
+    response.insert("body".into(), body.into());
+    response.insert("json".into(), json);
+    Ok(response)
+}
+
+#[async_trait]
+impl Tool for RhaiTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        // Cloning shares the compiled script
+        let tool = self.clone();
+        let handle = Handle::current();
+
+        let result = tokio::task::spawn_blocking(move || tool.call(arguments, state, handle))
+            .await
+            .map_err(|e| ToolError::Internal(e.to_string()))??;
+
+        match result {
+            Value::String(text) => Ok(ToolOutput::text(text)),
+            value => Ok(ToolOutput::structured(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use serde_json::json;
+
+    fn tool(source: &str, capabilities: Vec<Capability>, max_operations: u64) -> RhaiTool {
+        let config = ScriptConfig {
+            name: "test".to_string(),
+            description: None,
+            source: source.to_string(),
+            input_schema: json!({ "type": "object" }),
+            capabilities: vec![],
+        };
+        RhaiTool::from_config(&config, capabilities, max_operations).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_script_transforms_arguments() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        let upper = tool("args.name.to_upper()", vec![], 10_000);
+        let output = upper.execute(json!({"name": "aegis"}), state.clone()).await.unwrap();
+        let crate::tools::ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        assert_eq!(text, "AEGIS");
+
+        let sum = tool("let total = 0; for n in args.numbers { total += n; } #{ total: total }", vec![], 10_000);
+        let output = sum.execute(json!({"numbers": [1, 2, 3]}), state).await.unwrap();
+        assert_eq!(output.structured_content, Some(json!({"total": 6})));
+    }
+
+    #[tokio::test]
+    async fn test_script_errors_and_limits() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        let err = tool(r#"throw "no such user""#, vec![], 10_000)
+            .execute(json!({}), state.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), ToolError::ExecutionFailed("no such user".to_string()).to_string());
+
+        let err = tool("loop {}", vec![], 10_000).execute(json!({}), state).await.unwrap_err();
+        assert!(err.to_string().contains("ran out of operations"));
+
+        let config = ScriptConfig {
+            name: "broken".to_string(),
+            description: None,
+            source: "let = 1".to_string(),
+            input_schema: json!({}),
+            capabilities: vec![],
+        };
+        assert!(RhaiTool::from_config(&config, vec![], 10_000).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_host_functions_follow_capabilities() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let source = r#"kv_set("count", args.count); kv_get("count") + 1"#;
+
+        assert!(tool(source, vec![], 10_000).execute(json!({"count": 1}), state.clone()).await.is_err());
+
+        let output = tool(source, vec![Capability::Kv], 10_000)
+            .execute(json!({"count": 1}), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(output.structured_content, Some(json!(2)));
+        let entry = state.memory_store.kv_get("plugin:test:count").await.unwrap().unwrap();
+        assert_eq!(entry.value, json!(1));
+
+        // Loopback is refused before anything is sent
+        let err = tool(r#"fetch("http://127.0.0.1:9/")"#, vec![Capability::Http], 10_000)
+            .execute(json!({}), state)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("blocked"), "{}", err);
+    }
+}
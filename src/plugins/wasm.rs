@@ -0,0 +1,428 @@
+//! WASM plugin runtime.
+//!
+//! A plugin module exports `memory`, `alloc(len: i32) -> i32` and
+//! `run(ptr: i32, len: i32) -> i64`. The host writes the JSON arguments into
+//! a buffer from `alloc`, calls `run` and reads the JSON result from the
+//! returned `(ptr << 32) | len`. Every call gets a fresh instance with
+//! bounded memory and fuel. There is no WASI: the only imports available are
+//! the `aegis` host functions for the capabilities the plugin was granted:
+//!
+//! - `log`: `aegis.log(ptr, len)`
+//! - `kv`: `aegis.kv_get(ptr, len) -> i64` (0 when missing) and
+//!   `aegis.kv_set(key_ptr, key_len, value_ptr, value_len) -> i32`, scoped to
+//!   `plugin:{name}:` keys
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tracing::info;
+use wasmtime::{Caller, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use super::{Capability, PluginManifest};
+use crate::core::{AegisError, AegisResult, PluginDirConfig, RuntimeState};
+use crate::memory::MemoryStore;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+/// Shared engine and limits for WASM plugins.
+#[derive(Clone)]
+pub struct WasmRuntime {
+    engine: Engine,
+    max_memory_bytes: usize,
+    max_fuel: u64,
+}
+
+impl std::fmt::Debug for WasmRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmRuntime")
+            .field("max_memory_bytes", &self.max_memory_bytes)
+            .field("max_fuel", &self.max_fuel)
+            .finish()
+    }
+}
+
+impl WasmRuntime {
+    /// Creates a runtime with the configured limits.
+    pub fn new(config: &PluginDirConfig) -> AegisResult<Self> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config)
+            .map_err(|e| AegisError::Internal(format!("Failed to create WASM engine: {}", e)))?;
+
+        Ok(Self {
+            engine,
+            max_memory_bytes: config.max_memory_mb as usize * 1024 * 1024,
+            max_fuel: config.max_fuel,
+        })
+    }
+
+    /// Compiles a plugin module into a tool.
+    pub fn load(
+        &self,
+        path: &Path,
+        name: String,
+        manifest: &PluginManifest,
+        capabilities: Vec<Capability>,
+    ) -> AegisResult<WasmTool> {
+        let module = Module::from_file(&self.engine, path)
+            .map_err(|e| AegisError::Config(format!("Invalid WASM module: {}", e)))?;
+
+        for export in ["memory", "alloc", "run"] {
+            if module.get_export(export).is_none() {
+                return Err(AegisError::Config(format!(
+                    "WASM module does not export '{}'",
+                    export
+                )));
+            }
+        }
+
+        // Reject imports outside the granted capabilities up front
+        for import in module.imports() {
+            let capability = match (import.module(), import.name()) {
+                ("aegis", "log") => Some(Capability::Log),
+                ("aegis", "kv_get" | "kv_set") => Some(Capability::Kv),
+                _ => None,
+            };
+            match capability {
+                Some(cap) if capabilities.contains(&cap) => {}
+                _ => {
+                    return Err(AegisError::Config(format!(
+                        "WASM module imports {}.{}, which its granted capabilities do not provide",
+                        import.module(),
+                        import.name()
+                    )))
+                }
+            }
+        }
+
+        Ok(WasmTool {
+            definition: ToolDefinition {
+                name: name.clone(),
+                description: manifest.description.clone(),
+                input_schema: manifest
+                    .input_schema
+                    .clone()
+                    .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                output_schema: None,
+            },
+            name,
+            runtime: self.clone(),
+            module,
+            capabilities,
+        })
+    }
+}
+
+/// A tool backed by a WASM module.
+#[derive(Debug, Clone)]
+pub struct WasmTool {
+    name: String,
+    definition: ToolDefinition,
+    runtime: WasmRuntime,
+    module: Module,
+    capabilities: Vec<Capability>,
+}
+
+/// Per-call host state.
+struct HostState {
+    plugin: String,
+    limits: StoreLimits,
+    memory_store: Arc<dyn MemoryStore>,
+    handle: Handle,
+}
+
+impl WasmTool {
+    fn linker(&self) -> wasmtime::Result<Linker<HostState>> {
+        let mut linker = Linker::new(&self.runtime.engine);
+
+        if self.capabilities.contains(&Capability::Log) {
+            linker.func_wrap(
+                "aegis",
+                "log",
+                |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+                    let memory = guest_memory(&mut caller)?;
+                    let bytes = read_guest(&memory, &caller, ptr, len)?;
+                    info!("[plugin {}] {}", caller.data().plugin, String::from_utf8_lossy(&bytes));
+                    Ok(())
+                },
+            )?;
+        }
+
+        if self.capabilities.contains(&Capability::Kv) {
+            linker.func_wrap(
+                "aegis",
+                "kv_get",
+                |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> wasmtime::Result<i64> {
+                    let memory = guest_memory(&mut caller)?;
+                    let key = read_guest(&memory, &caller, ptr, len)?;
+                    let key = scoped_key(&caller.data().plugin, &key);
+
+                    let state = caller.data();
+                    let entry = state.handle.block_on(state.memory_store.kv_get(&key))?;
+                    match entry {
+                        Some(entry) => {
+                            let bytes = serde_json::to_vec(&entry.value)?;
+                            write_guest(&mut caller, &memory, &bytes)
+                        }
+                        None => Ok(0),
+                    }
+                },
+            )?;
+
+            linker.func_wrap(
+                "aegis",
+                "kv_set",
+                |mut caller: Caller<'_, HostState>,
+                 key_ptr: i32,
+                 key_len: i32,
+                 value_ptr: i32,
+                 value_len: i32|
+                 -> wasmtime::Result<i32> {
+                    let memory = guest_memory(&mut caller)?;
+                    let key = read_guest(&memory, &caller, key_ptr, key_len)?;
+                    let key = scoped_key(&caller.data().plugin, &key);
+                    let value: Value = match serde_json::from_slice(&read_guest(
+                        &memory, &caller, value_ptr, value_len,
+                    )?) {
+                        Ok(value) => value,
+                        Err(_) => return Ok(-1),
+                    };
+
+                    let state = caller.data();
+                    match state.handle.block_on(state.memory_store.kv_set(&key, value, None)) {
+                        Ok(()) => Ok(0),
+                        Err(_) => Ok(-1),
+                    }
+                },
+            )?;
+        }
+
+        Ok(linker)
+    }
+
+    /// Runs the plugin on `input` in a fresh instance.
+    fn call(
+        &self,
+        input: &[u8],
+        memory_store: Arc<dyn MemoryStore>,
+        handle: Handle,
+    ) -> Result<Vec<u8>, ToolError> {
+        let host = HostState {
+            plugin: self.name.clone(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.runtime.max_memory_bytes)
+                .instances(1)
+                .build(),
+            memory_store,
+            handle,
+        };
+        let mut store = Store::new(&self.runtime.engine, host);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.runtime.max_fuel)
+            .map_err(|e| ToolError::Internal(e.to_string()))?;
+
+        let trap = |e: wasmtime::Error| self.trap_error(e);
+
+        let linker = self.linker().map_err(|e| ToolError::Internal(e.to_string()))?;
+        let instance = linker.instantiate(&mut store, &self.module).map_err(trap)?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| ToolError::ExecutionFailed("Plugin does not export memory".to_string()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(trap)?;
+        let run = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "run")
+            .map_err(trap)?;
+
+        let len = i32::try_from(input.len())
+            .map_err(|_| ToolError::InvalidInput("Arguments too large".to_string()))?;
+        let ptr = alloc.call(&mut store, len).map_err(trap)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Plugin alloc returned bad pointer: {}", e)))?;
+
+        let packed = run.call(&mut store, (ptr, len)).map_err(trap)?;
+        let (out_ptr, out_len) = unpack(packed);
+        read_guest(&memory, &store, out_ptr, out_len)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Plugin returned bad result: {}", e)))
+    }
+
+    fn trap_error(&self, e: wasmtime::Error) -> ToolError {
+        match e.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => {
+                ToolError::ExecutionFailed(format!("Plugin '{}' ran out of fuel", self.name))
+            }
+            _ => ToolError::ExecutionFailed(format!("Plugin '{}' failed: {}", self.name, e)),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WasmTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let input = serde_json::to_vec(&arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        // Cloning shares the compiled module
+        let tool = self.clone();
+        let memory_store = state.memory_store.clone();
+        let handle = Handle::current();
+
+        let output = tokio::task::spawn_blocking(move || tool.call(&input, memory_store, handle))
+            .await
+            .map_err(|e| ToolError::Internal(e.to_string()))??;
+
+        let result: Value = serde_json::from_slice(&output).map_err(|e| {
+            ToolError::ExecutionFailed(format!("Plugin '{}' returned invalid JSON: {}", self.name, e))
+        })?;
+
+        if let Some(error) = result.get("error").and_then(|e| e.as_str()) {
+            return Err(ToolError::ExecutionFailed(error.to_string()));
+        }
+
+        Ok(ToolOutput::text(
+            serde_json::to_string_pretty(&result).unwrap_or_default(),
+        ))
+    }
+}
+
+/// Splits a packed `(ptr << 32) | len` result.
+fn unpack(packed: i64) -> (i32, i32) {
+    ((packed >> 32) as i32, (packed & 0xffff_ffff) as i32)
+}
+
+fn scoped_key(plugin: &str, key: &[u8]) -> String {
+    format!("plugin:{}:{}", plugin, String::from_utf8_lossy(key))
+}
+
+fn guest_memory(caller: &mut Caller<'_, HostState>) -> wasmtime::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export memory"))
+}
+
+fn read_guest(
+    memory: &Memory,
+    store: impl wasmtime::AsContext,
+    ptr: i32,
+    len: i32,
+) -> wasmtime::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len as u32 as usize];
+    memory.read(&store, ptr as u32 as usize, &mut buf)?;
+    Ok(buf)
+}
+
+/// Copies `bytes` into a guest buffer from `alloc` and returns it packed.
+fn write_guest(
+    caller: &mut Caller<'_, HostState>,
+    memory: &Memory,
+    bytes: &[u8],
+) -> wasmtime::Result<i64> {
+    let alloc = caller
+        .get_export("alloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export alloc"))?
+        .typed::<i32, i32>(&*caller)?;
+    let len = i32::try_from(bytes.len())?;
+    let ptr = alloc.call(&mut *caller, len)?;
+    memory.write(&mut *caller, ptr as u32 as usize, bytes)?;
+    Ok(((ptr as u32 as i64) << 32) | len as u32 as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    /// Echoes its input back.
+    const ECHO: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "run") (param i32 i32) (result i64)
+            local.get 0 i64.extend_i32_u i64.const 32 i64.shl
+            local.get 1 i64.extend_i32_u i64.or))
+    "#;
+
+    /// Loops forever.
+    const SPIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "run") (param i32 i32) (result i64)
+            (loop br 0) i64.const 0))
+    "#;
+
+    /// Imports the kv capability.
+    const KV: &str = r#"
+        (module
+          (import "aegis" "kv_get" (func (param i32 i32) (result i64)))
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "run") (param i32 i32) (result i64)
+            local.get 0 i64.extend_i32_u i64.const 32 i64.shl
+            local.get 1 i64.extend_i32_u i64.or))
+    "#;
+
+    fn load(
+        source: &str,
+        capabilities: Vec<Capability>,
+        fuel: u64,
+    ) -> AegisResult<(tempfile::TempDir, WasmTool)> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.wasm");
+        std::fs::write(&path, source).unwrap();
+
+        let runtime = WasmRuntime::new(&PluginDirConfig {
+            max_fuel: fuel,
+            ..Default::default()
+        })
+        .unwrap();
+        let tool = runtime.load(&path, "test".to_string(), &PluginManifest::default(), capabilities)?;
+        Ok((dir, tool))
+    }
+
+    #[tokio::test]
+    async fn test_echo_plugin() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let (_dir, tool) = load(ECHO, vec![], 1_000_000).unwrap();
+
+        let output = tool.execute(json!({"hello": "world"}), state).await.unwrap();
+        let crate::tools::ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap(), json!({"hello": "world"}));
+    }
+
+    #[tokio::test]
+    async fn test_fuel_limit() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let (_dir, tool) = load(SPIN, vec![], 10_000).unwrap();
+
+        let err = tool.execute(json!({}), state).await.unwrap_err();
+        assert!(err.to_string().contains("ran out of fuel"));
+    }
+
+    #[tokio::test]
+    async fn test_ungranted_capability_is_denied() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        assert!(load(KV, vec![Capability::Log], 1_000_000).is_err());
+
+        let (_dir, tool) = load(KV, vec![Capability::Kv], 1_000_000).unwrap();
+        assert!(tool.execute(json!({}), state).await.is_ok());
+    }
+}
@@ -10,14 +10,15 @@ use serde_json::Value;
 pub const JSONRPC_VERSION: &str = "2.0";
 
 /// A JSON-RPC request ID, which can be a string, number, or null.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum RequestId {
     /// String identifier.
     String(String),
     /// Numeric identifier.
     Number(i64),
-    /// Null identifier (for notifications, though MCP doesn't use these much).
+    /// Null identifier; also what a notification (no `id`) parses to.
+    #[default]
     Null,
 }
 
@@ -52,7 +53,8 @@ pub struct Request {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<Value>,
 
-    /// The request identifier.
+    /// The request identifier; absent (null) for notifications.
+    #[serde(default)]
     pub id: RequestId,
 }
 
@@ -68,10 +70,16 @@ impl Request {
     }
 
     /// Parses a JSON-RPC request from a JSON string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self, crate::core::NexusError> {
         serde_json::from_str(s).map_err(Into::into)
     }
 
+    /// Whether this is a notification, which gets no response.
+    pub fn is_notification(&self) -> bool {
+        self.id == RequestId::Null
+    }
+
     /// Validates that this is a proper JSON-RPC 2.0 request.
     pub fn validate(&self) -> Result<(), crate::core::NexusError> {
         if self.jsonrpc != JSONRPC_VERSION {
@@ -218,6 +226,16 @@ mod tests {
         assert_eq!(req.id, RequestId::Number(1));
     }
 
+    #[test]
+    fn test_notification_parsing() {
+        let json = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        let req = Request::from_str(json).unwrap();
+        assert!(req.is_notification());
+
+        let req = Request::from_str(r#"{"jsonrpc":"2.0","method":"ping","id":0}"#).unwrap();
+        assert!(!req.is_notification());
+    }
+
     #[test]
     fn test_response_success() {
         let resp = Response::success(RequestId::Number(1), serde_json::json!({"ok": true}));
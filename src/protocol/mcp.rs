@@ -9,6 +9,21 @@ use serde_json::Value;
 /// MCP protocol version.
 pub const MCP_VERSION: &str = "2024-11-05";
 
+/// First protocol version with the streamable HTTP transport.
+pub const STREAMABLE_HTTP_VERSION: &str = "2025-03-26";
+
+/// Protocol versions the server speaks, newest first.
+pub const SUPPORTED_VERSIONS: &[&str] = &[STREAMABLE_HTTP_VERSION, MCP_VERSION];
+
+/// Picks the protocol version to answer `initialize` with: the client's
+/// if supported, otherwise the newest the server supports.
+pub fn negotiate_version(requested: &str) -> &'static str {
+    SUPPORTED_VERSIONS
+        .iter()
+        .find(|version| **version == requested)
+        .unwrap_or(&SUPPORTED_VERSIONS[0])
+}
+
 /// Known MCP methods.
 #[derive(Debug, Clone, PartialEq)]
 pub enum McpMethod {
@@ -28,25 +43,41 @@ pub enum McpMethod {
     ResourcesList,
     /// Read a resource.
     ResourcesRead,
+    /// Subscribe to updates of a resource.
+    ResourcesSubscribe,
+    /// Stop receiving updates of a resource.
+    ResourcesUnsubscribe,
     /// Ping for health check.
     Ping,
+    /// Notification that the client cancelled an in-flight request.
+    Cancelled,
+    /// Notification that the client's roots changed.
+    RootsListChanged,
+    /// Complete a prompt, resource template or tool argument.
+    CompletionComplete,
     /// Unknown method.
     Unknown(String),
 }
 
 impl McpMethod {
     /// Parses a method string into an McpMethod.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "initialize" => McpMethod::Initialize,
-            "initialized" => McpMethod::Initialized,
+            "initialized" | "notifications/initialized" => McpMethod::Initialized,
             "tools/list" => McpMethod::ToolsList,
             "tools/call" => McpMethod::ToolsCall,
             "prompts/list" => McpMethod::PromptsList,
             "prompts/get" => McpMethod::PromptsGet,
             "resources/list" => McpMethod::ResourcesList,
             "resources/read" => McpMethod::ResourcesRead,
+            "resources/subscribe" => McpMethod::ResourcesSubscribe,
+            "resources/unsubscribe" => McpMethod::ResourcesUnsubscribe,
             "ping" => McpMethod::Ping,
+            "notifications/cancelled" | "$/cancelRequest" => McpMethod::Cancelled,
+            "notifications/roots/list_changed" => McpMethod::RootsListChanged,
+            "completion/complete" => McpMethod::CompletionComplete,
             _ => McpMethod::Unknown(s.to_string()),
         }
     }
@@ -62,7 +93,12 @@ impl McpMethod {
             McpMethod::PromptsGet => "prompts/get",
             McpMethod::ResourcesList => "resources/list",
             McpMethod::ResourcesRead => "resources/read",
+            McpMethod::ResourcesSubscribe => "resources/subscribe",
+            McpMethod::ResourcesUnsubscribe => "resources/unsubscribe",
             McpMethod::Ping => "ping",
+            McpMethod::Cancelled => "notifications/cancelled",
+            McpMethod::RootsListChanged => "notifications/roots/list_changed",
+            McpMethod::CompletionComplete => "completion/complete",
             McpMethod::Unknown(s) => s,
         }
     }
@@ -103,6 +139,10 @@ pub struct ServerCapabilities {
     /// Resource capabilities.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resources: Option<ResourcesCapability>,
+
+    /// Argument completion (`completion/complete`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<Value>,
 }
 
 /// Tools capability details.
@@ -146,6 +186,10 @@ pub struct ClientCapabilities {
     /// Sampling capabilities.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<Value>,
+
+    /// Roots capabilities (the client can list its workspace directories).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<Value>,
 }
 
 /// Parameters for the initialize request.
@@ -158,6 +202,18 @@ pub struct InitializeParams {
     pub capabilities: ClientCapabilities,
     /// Client information.
     pub client_info: ClientInfo,
+    /// Optional conversation to pin for this connection (Aegis extension).
+    ///
+    /// When set, memory.*, conversation.* and vector.* tools default their
+    /// scope to this conversation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    /// Optional memory namespace for this connection (Aegis extension).
+    ///
+    /// Ignored when the connection authenticated with an API key, whose
+    /// name is always its namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
 }
 
 /// Result of the initialize request.
@@ -183,6 +239,10 @@ pub struct Tool {
     pub description: Option<String>,
     /// JSON Schema for the tool's input parameters.
     pub input_schema: Value,
+    /// JSON Schema of the tool's structured result, if it returns one.
+    /// Results are validated against it before they are sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
 }
 
 /// Result of tools/list request.
@@ -264,6 +324,13 @@ pub struct ResourcesReadParams {
     pub uri: String,
 }
 
+/// Parameters for resources/subscribe and resources/unsubscribe requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesSubscribeParams {
+    /// The URI of the resource.
+    pub uri: String,
+}
+
 /// Content item in resource response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -288,6 +355,58 @@ pub struct ResourcesReadResult {
     pub contents: Vec<ResourceContent>,
 }
 
+/// What a completion/complete request completes an argument of.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompletionReference {
+    /// A prompt argument.
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    /// A variable of a resource URI template.
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+    /// A tool argument (Aegis extension).
+    #[serde(rename = "ref/tool")]
+    Tool { name: String },
+}
+
+/// The argument being completed and what the user typed so far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionArgument {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// Parameters for completion/complete request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteParams {
+    #[serde(rename = "ref")]
+    pub reference: CompletionReference,
+    pub argument: CompletionArgument,
+    /// Values of arguments already filled in, under `arguments`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Value>,
+}
+
+/// Completion values for an argument.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+    /// At most 100 values.
+    pub values: Vec<String>,
+    /// Number of matches, which may exceed the values returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    pub has_more: bool,
+}
+
+/// Result of completion/complete request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResult {
+    pub completion: Completion,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,12 +415,20 @@ mod tests {
     fn test_method_parsing() {
         assert_eq!(McpMethod::from_str("initialize"), McpMethod::Initialize);
         assert_eq!(McpMethod::from_str("tools/list"), McpMethod::ToolsList);
+        assert_eq!(McpMethod::from_str("notifications/cancelled"), McpMethod::Cancelled);
         assert_eq!(
             McpMethod::from_str("unknown/method"),
             McpMethod::Unknown("unknown/method".to_string())
         );
     }
 
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version("2024-11-05"), "2024-11-05");
+        assert_eq!(negotiate_version("2025-03-26"), "2025-03-26");
+        assert_eq!(negotiate_version("2099-01-01"), STREAMABLE_HTTP_VERSION);
+    }
+
     #[test]
     fn test_initialize_result_serialization() {
         let result = InitializeResult {
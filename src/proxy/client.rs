@@ -0,0 +1,293 @@
+//! JSON-RPC clients for upstream MCP servers.
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use crate::core::{AegisError, AegisResult, UpstreamConfig};
+
+/// Header carrying the session ID of a streamable HTTP upstream.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// A connection to an upstream MCP server.
+#[async_trait]
+pub trait UpstreamClient: Send + Sync + std::fmt::Debug {
+    /// Sends a request and waits for its result.
+    async fn request(&self, method: &str, params: Value) -> AegisResult<Value>;
+
+    /// Sends a notification (no response expected).
+    async fn notify(&self, method: &str, params: Value) -> AegisResult<()>;
+}
+
+/// Creates a client for the given upstream configuration.
+pub async fn connect(config: &UpstreamConfig) -> AegisResult<Arc<dyn UpstreamClient>> {
+    match (&config.command, &config.url) {
+        (Some(_), None) => Ok(Arc::new(StdioClient::spawn(config)?)),
+        (None, Some(_)) => Ok(Arc::new(HttpClient::new(config)?)),
+        _ => Err(AegisError::Config(format!(
+            "Upstream '{}' needs exactly one of 'command' or 'url'",
+            config.name
+        ))),
+    }
+}
+
+/// Extracts the result (or error) from a JSON-RPC response.
+fn into_result(response: Value) -> AegisResult<Value> {
+    if let Some(error) = response.get("error") {
+        let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(-32603) as i32;
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Unknown upstream error");
+        return Err(AegisError::json_rpc(code, message));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+// ============================================================================
+// Stdio
+// ============================================================================
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// Upstream spoken to over a child process's stdin/stdout.
+#[derive(Debug)]
+pub struct StdioClient {
+    name: String,
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: Pending,
+    next_id: AtomicU64,
+    timeout: Duration,
+    // Held so the child is killed when the client is dropped
+    _child: Child,
+}
+
+impl StdioClient {
+    /// Spawns the upstream process and starts reading its responses.
+    pub fn spawn(config: &UpstreamConfig) -> AegisResult<Self> {
+        let command = config.command.as_deref().unwrap_or_default();
+        let mut child = Command::new(command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                AegisError::Transport(format!("Failed to spawn upstream '{}': {}", config.name, e))
+            })?;
+
+        let stdin = child.stdin.take().ok_or_else(|| {
+            AegisError::Transport(format!("Upstream '{}' has no stdin", config.name))
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            AegisError::Transport(format!("Upstream '{}' has no stdout", config.name))
+        })?;
+
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let reader_pending = pending.clone();
+        let name = config.name.clone();
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let message: Value = match serde_json::from_str(&line) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        warn!("Upstream '{}' sent invalid JSON: {}", name, e);
+                        continue;
+                    }
+                };
+
+                // Notifications and server requests carry no numeric response id
+                match message.get("id").and_then(|id| id.as_u64()) {
+                    Some(id) if message.get("method").is_none() => {
+                        if let Some(tx) = reader_pending.lock().remove(&id) {
+                            let _ = tx.send(message);
+                        }
+                    }
+                    _ => debug!("Ignoring upstream '{}' message: {}", name, line),
+                }
+            }
+            warn!("Upstream '{}' closed its stdout", name);
+            // Fail every in-flight request
+            reader_pending.lock().clear();
+        });
+
+        Ok(Self {
+            name: config.name.clone(),
+            stdin: tokio::sync::Mutex::new(stdin),
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout: Duration::from_secs(config.timeout_secs),
+            _child: child,
+        })
+    }
+
+    async fn write(&self, message: &Value) -> AegisResult<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(line.as_bytes()).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UpstreamClient for StdioClient {
+    async fn request(&self, method: &str, params: Value) -> AegisResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.write(&message).await {
+            self.pending.lock().remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => into_result(response),
+            Ok(Err(_)) => Err(AegisError::Transport(format!(
+                "Upstream '{}' disconnected",
+                self.name
+            ))),
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                Err(AegisError::Transport(format!(
+                    "Upstream '{}' timed out on {}",
+                    self.name, method
+                )))
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> AegisResult<()> {
+        self.write(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+}
+
+// ============================================================================
+// HTTP
+// ============================================================================
+
+/// Upstream spoken to with JSON-RPC over HTTP POST.
+#[derive(Debug)]
+pub struct HttpClient {
+    name: String,
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+    next_id: AtomicU64,
+    session_id: Mutex<Option<String>>,
+}
+
+impl HttpClient {
+    /// Creates a client for an HTTP upstream.
+    pub fn new(config: &UpstreamConfig) -> AegisResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| AegisError::Transport(e.to_string()))?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            url: config.url.clone().unwrap_or_default(),
+            headers: config.headers.clone(),
+            client,
+            next_id: AtomicU64::new(1),
+            session_id: Mutex::new(None),
+        })
+    }
+
+    async fn post(&self, message: Value) -> AegisResult<Option<Value>> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(&message);
+
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if let Some(session) = self.session_id.lock().clone() {
+            request = request.header(SESSION_HEADER, session);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            AegisError::Transport(format!("Upstream '{}' request failed: {}", self.name, e))
+        })?;
+
+        if let Some(session) = response.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            *self.session_id.lock() = Some(session.to_string());
+        }
+
+        let status = response.status();
+        let is_sse = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+        let body = response.text().await.map_err(|e| {
+            AegisError::Transport(format!("Upstream '{}' read failed: {}", self.name, e))
+        })?;
+
+        if !status.is_success() {
+            return Err(AegisError::Transport(format!(
+                "Upstream '{}' returned HTTP {}: {}",
+                self.name, status, body
+            )));
+        }
+
+        if body.trim().is_empty() {
+            return Ok(None);
+        }
+
+        if is_sse {
+            // Take the last JSON-RPC message carried in a data: line
+            let message = body
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+                .rfind(|v| v.get("result").is_some() || v.get("error").is_some());
+            return Ok(message);
+        }
+
+        Ok(Some(serde_json::from_str(&body)?))
+    }
+}
+
+#[async_trait]
+impl UpstreamClient for HttpClient {
+    async fn request(&self, method: &str, params: Value) -> AegisResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        match self.post(message).await? {
+            Some(response) => into_result(response),
+            None => Err(AegisError::Transport(format!(
+                "Upstream '{}' returned an empty response to {}",
+                self.name, method
+            ))),
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> AegisResult<()> {
+        self.post(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,249 @@
+//! Upstream MCP server aggregation.
+//!
+//! Connects to the MCP servers listed under `upstreams` in the config
+//! (stdio child processes or HTTP endpoints), lists their tools and
+//! registers each one as `{prefix}.{tool}`. Calls to those tools are
+//! forwarded to the owning upstream. REST APIs described by the OpenAPI
+//! specs under `openapi` are mounted alongside them (see [`openapi`]).
+
+mod client;
+pub mod openapi;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::core::{AegisResult, RuntimeState, UpstreamConfig};
+use crate::protocol::mcp::{Tool as ToolDefinition, MCP_VERSION};
+use crate::tools::{Tool, ToolContent, ToolError, ToolOutput};
+
+pub use client::{connect, HttpClient, StdioClient, UpstreamClient};
+pub use openapi::{load_openapi, mount_openapi, OpenApiTool};
+
+/// A tool re-exported from an upstream MCP server.
+#[derive(Debug)]
+pub struct ProxyTool {
+    /// Name of the upstream that owns the tool.
+    upstream: String,
+    /// Tool name on the upstream.
+    remote_name: String,
+    /// Definition exposed to our clients (with the prefixed name).
+    definition: ToolDefinition,
+    client: Arc<dyn UpstreamClient>,
+}
+
+#[async_trait]
+impl Tool for ProxyTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let result = self
+            .client
+            .request(
+                "tools/call",
+                json!({ "name": self.remote_name, "arguments": arguments }),
+            )
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionFailed(format!("Upstream '{}': {}", self.upstream, e))
+            })?;
+
+        Ok(convert_result(&result))
+    }
+}
+
+/// Converts a `tools/call` result, from an upstream or this server, into a
+/// [`ToolOutput`].
+pub(crate) fn convert_result(result: &Value) -> ToolOutput {
+    let content = result
+        .get("content")
+        .and_then(|c| c.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| match item.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => ToolContent::Text {
+                        text: item.get("text").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+                    },
+                    Some("image") => ToolContent::Image {
+                        data: item.get("data").and_then(|d| d.as_str()).unwrap_or("").to_string(),
+                        mime_type: item
+                            .get("mimeType")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("application/octet-stream")
+                            .to_string(),
+                    },
+                    // Resources and unknown content types are passed through as JSON
+                    _ => ToolContent::Text { text: item.to_string() },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ToolOutput {
+        content,
+        is_error: result.get("isError").and_then(|v| v.as_bool()).unwrap_or(false),
+        structured_content: result.get("structuredContent").cloned(),
+    }
+}
+
+/// Connects to one upstream and returns its tools, prefixed.
+pub async fn connect_upstream(
+    config: &UpstreamConfig,
+    state: &RuntimeState,
+) -> AegisResult<Vec<ProxyTool>> {
+    let client = connect(config).await?;
+
+    client
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": MCP_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": state.server_info.name,
+                    "version": state.server_info.version
+                }
+            }),
+        )
+        .await?;
+    client.notify("notifications/initialized", json!({})).await?;
+
+    let prefix = config.tool_prefix();
+    let mut tools = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let params = match &cursor {
+            Some(c) => json!({ "cursor": c }),
+            None => json!({}),
+        };
+        let result = client.request("tools/list", params).await?;
+
+        for tool in result.get("tools").and_then(|t| t.as_array()).into_iter().flatten() {
+            let Some(remote_name) = tool.get("name").and_then(|n| n.as_str()) else {
+                continue;
+            };
+            let description = tool
+                .get("description")
+                .and_then(|d| d.as_str())
+                .map(|d| format!("[{}] {}", config.name, d));
+
+            tools.push(ProxyTool {
+                upstream: config.name.clone(),
+                remote_name: remote_name.to_string(),
+                definition: ToolDefinition {
+                    name: format!("{}.{}", prefix, remote_name),
+                    description,
+                    input_schema: tool
+                        .get("inputSchema")
+                        .cloned()
+                        .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    output_schema: tool.get("outputSchema").cloned(),
+                },
+                client: client.clone(),
+            });
+        }
+
+        cursor = result
+            .get("nextCursor")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string());
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(tools)
+}
+
+/// Connects to every configured upstream and registers its tools, then
+/// mounts the OpenAPI specs.
+///
+/// Upstreams that fail to connect are logged and skipped. Returns the
+/// number of tools registered.
+pub async fn mount_upstreams(state: &Arc<RuntimeState>) -> usize {
+    let mut mounted = 0;
+
+    for upstream in &state.config.upstreams {
+        match connect_upstream(upstream, state).await {
+            Ok(tools) => {
+                let count = tools.len();
+                let mut registry = state.tool_registry.write();
+                for tool in tools {
+                    registry.register(Arc::new(tool));
+                }
+                info!(
+                    "Mounted upstream '{}' ({} tools as {}.*)",
+                    upstream.name,
+                    count,
+                    upstream.tool_prefix()
+                );
+                mounted += count;
+            }
+            Err(e) => warn!("Failed to mount upstream '{}': {}", upstream.name, e),
+        }
+    }
+
+    mounted + mount_openapi(state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_result() {
+        let output = convert_result(&json!({
+            "content": [
+                {"type": "text", "text": "hello"},
+                {"type": "image", "data": "aGk=", "mimeType": "image/png"}
+            ],
+            "isError": true
+        }));
+
+        assert!(output.is_error);
+        assert!(matches!(&output.content[0], ToolContent::Text { text } if text == "hello"));
+        assert!(matches!(&output.content[1], ToolContent::Image { mime_type, .. } if mime_type == "image/png"));
+    }
+
+    #[tokio::test]
+    async fn test_mount_stdio_upstream() {
+        // A tiny shell script speaking line-delimited JSON-RPC
+        let script = r#"
+while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"initialize"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{},"serverInfo":{"name":"fake","version":"0"}}}\n' "$id" ;;
+    *'"tools/list"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"ping","description":"Pong","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+    *'"tools/call"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"pong"}]}}\n' "$id" ;;
+  esac
+done
+"#;
+        let config = UpstreamConfig {
+            name: "fake".to_string(),
+            prefix: None,
+            command: Some("sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            url: None,
+            headers: Default::default(),
+            timeout_secs: 5,
+        };
+
+        let state = Arc::new(RuntimeState::new(crate::core::Config::default()));
+        let tools = connect_upstream(&config, &state).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].definition().name, "fake.ping");
+
+        let output = tools[0].execute(json!({}), state).await.unwrap();
+        assert!(matches!(&output.content[0], ToolContent::Text { text } if text == "pong"));
+    }
+}
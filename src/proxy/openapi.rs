@@ -0,0 +1,619 @@
+//! OpenAPI bridge.
+//!
+//! Each spec listed under `openapi` in the config is loaded on startup and
+//! every operation in it is registered as `{name}.{operationId}`. The tool's
+//! input schema has a property per path, query and header parameter, plus
+//! `body` for a JSON request body. Arguments are validated against it
+//! before the request is sent to the API with the configured credential.
+
+use async_trait::async_trait;
+use globset::Glob;
+use jsonschema::Validator;
+use reqwest::{Method, Url};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::core::config::OpenApiConfig;
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+/// Most schema errors reported for invalid arguments.
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Most bytes of an error response included in the tool error.
+const MAX_ERROR_BODY: usize = 1000;
+
+/// HTTP methods an OpenAPI path item may define operations for.
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Client for one API, shared by its tools.
+#[derive(Debug)]
+struct OpenApiClient {
+    config: OpenApiConfig,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+/// Where an operation parameter goes in the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Path,
+    Query,
+    Header,
+}
+
+/// An OpenAPI operation exposed as a tool.
+#[derive(Debug)]
+pub struct OpenApiTool {
+    definition: ToolDefinition,
+    method: Method,
+    /// Path template, e.g. `/pets/{petId}`.
+    path: String,
+    parameters: Vec<(String, Location)>,
+    validator: Validator,
+    api: Arc<OpenApiClient>,
+}
+
+#[async_trait]
+impl Tool for OpenApiTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, arguments: Value, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let arguments = if arguments.is_null() { json!({}) } else { arguments };
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&arguments)
+            .take(MAX_REPORTED_ERRORS)
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                if path.is_empty() { e.to_string() } else { format!("{}: {}", path, e) }
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(ToolError::InvalidInput(errors.join("; ")));
+        }
+
+        let config = &self.api.config;
+        let url = self.url(&arguments)?;
+        let mut request = self.api.client.request(self.method.clone(), url);
+        for (name, location) in &self.parameters {
+            let Some(value) = arguments.get(name) else { continue };
+            match location {
+                Location::Path => {}
+                Location::Query => match value {
+                    Value::Array(items) => {
+                        for item in items {
+                            request = request.query(&[(name, parameter_value(item))]);
+                        }
+                    }
+                    value => request = request.query(&[(name, parameter_value(value))]),
+                },
+                Location::Header => request = request.header(name.as_str(), parameter_value(value)),
+            }
+        }
+        for (name, value) in &config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(secret) = &config.auth_secret {
+            let credential = state
+                .secrets
+                .get(secret)
+                .ok_or_else(|| ToolError::InvalidInput(format!("{} secret not set", secret)))?;
+            let value = if config.auth_scheme.is_empty() {
+                credential
+            } else {
+                format!("{} {}", config.auth_scheme, credential)
+            };
+            request = request.header(config.auth_header.as_str(), value);
+        }
+        if let Some(body) = arguments.get("body") {
+            request = request.json(body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("{} request failed: {}", config.name, e)))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("{} response unreadable: {}", config.name, e)))?;
+
+        if !status.is_success() {
+            let mut end = text.len().min(MAX_ERROR_BODY);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let message = format!("{} returned {}: {}", config.name, status.as_u16(), &text[..end]);
+            return Err(match status.as_u16() {
+                400 | 422 => ToolError::InvalidInput(message),
+                401 | 403 => ToolError::PermissionDenied(message),
+                404 => ToolError::NotFound(message),
+                429 => ToolError::RateLimited(message),
+                _ => ToolError::ExecutionFailed(message),
+            });
+        }
+        Ok(match serde_json::from_str::<Value>(&text) {
+            Ok(value) if value.is_object() => ToolOutput::structured(value),
+            _ => ToolOutput::text(text),
+        })
+    }
+}
+
+impl OpenApiTool {
+    /// The request URL, with the path parameters filled in.
+    fn url(&self, arguments: &Value) -> Result<Url, ToolError> {
+        let mut url = self.api.base_url.clone();
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| ToolError::Internal(format!("{}: base URL can't have a path", self.api.config.name)))?;
+            segments.pop_if_empty();
+            for segment in self.path.split('/').filter(|s| !s.is_empty()) {
+                let mut filled = segment.to_string();
+                for (name, location) in &self.parameters {
+                    let placeholder = format!("{{{}}}", name);
+                    if *location == Location::Path && filled.contains(&placeholder) {
+                        let value = arguments.get(name).map(parameter_value).unwrap_or_default();
+                        filled = filled.replace(&placeholder, &value);
+                    }
+                }
+                if filled == "." || filled == ".." {
+                    return Err(ToolError::InvalidInput(format!("'{}' is not a valid path parameter", filled)));
+                }
+                segments.push(&filled);
+            }
+        }
+        Ok(url)
+    }
+}
+
+/// A parameter value as sent in a URL or header: strings as is, anything
+/// else as JSON.
+fn parameter_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(parameter_value).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+/// Reads a spec from a file or URL, in JSON or YAML.
+async fn load_spec(location: &str, client: &reqwest::Client) -> Result<Value, String> {
+    let text = if location.starts_with("http://") || location.starts_with("https://") {
+        let response = client.get(location).send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("fetching the spec returned {}", response.status()));
+        }
+        response.text().await.map_err(|e| e.to_string())?
+    } else {
+        tokio::fs::read_to_string(location).await.map_err(|e| e.to_string())?
+    };
+    let spec: Value = serde_json::from_str(&text)
+        .or_else(|_| serde_yaml::from_str(&text))
+        .map_err(|e| format!("not JSON or YAML: {}", e))?;
+    match spec.get("openapi").and_then(Value::as_str) {
+        Some(version) if version.starts_with("3.") => Ok(spec),
+        _ => Err("not an OpenAPI 3 spec (Swagger 2 isn't supported)".to_string()),
+    }
+}
+
+/// Builds the tools for the operations of `spec` selected by `config`.
+fn build_tools(config: &OpenApiConfig, spec: &Value, spec_url: Option<&Url>) -> Result<Vec<OpenApiTool>, String> {
+    let server = spec.pointer("/servers/0/url").and_then(Value::as_str);
+    let base_url = match (&config.base_url, server) {
+        (Some(url), _) => Url::parse(url).map_err(|e| format!("invalid base_url: {}", e))?,
+        (None, Some(server)) => match spec_url {
+            // Server URLs may be relative to the spec's
+            Some(spec_url) => spec_url.join(server),
+            None => Url::parse(server),
+        }
+        .map_err(|e| format!("invalid server URL '{}' (set base_url): {}", server, e))?,
+        (None, None) => return Err("the spec lists no servers; set base_url".to_string()),
+    };
+    let selected: Vec<_> = config
+        .operations
+        .iter()
+        .filter_map(|pattern| Glob::new(pattern).ok().map(|glob| glob.compile_matcher()))
+        .collect();
+
+    let api = Arc::new(OpenApiClient {
+        config: config.clone(),
+        base_url,
+        client: reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default(),
+    });
+
+    let mut tools = Vec::new();
+    for (path, item) in spec.get("paths").and_then(Value::as_object).into_iter().flatten() {
+        let item = resolve(spec, item);
+        for method in METHODS {
+            let Some(operation) = item.get(*method) else { continue };
+            let operation_id = operation
+                .get("operationId")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}_{}", method, path));
+            let operation_id = sanitize(&operation_id);
+            if !selected.is_empty() && !selected.iter().any(|glob| glob.is_match(&operation_id)) {
+                continue;
+            }
+
+            let name = format!("{}.{}", config.name, operation_id);
+            match build_tool(spec, item, operation, &name, method, path, api.clone()) {
+                Ok(tool) => tools.push(tool),
+                Err(e) => warn!("Skipping OpenAPI operation {}: {}", name, e),
+            }
+        }
+    }
+    Ok(tools)
+}
+
+fn build_tool(
+    spec: &Value,
+    item: &Value,
+    operation: &Value,
+    name: &str,
+    method: &str,
+    path: &str,
+    api: Arc<OpenApiClient>,
+) -> Result<OpenApiTool, String> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut parameters: Vec<(String, Location)> = Vec::new();
+
+    // Operation parameters override path-level ones with the same name and location
+    let declared = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .chain(item.get("parameters").and_then(Value::as_array).into_iter().flatten());
+    for parameter in declared {
+        let parameter = resolve(spec, parameter);
+        let Some(param_name) = parameter.get("name").and_then(Value::as_str) else { continue };
+        let location = match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => Location::Path,
+            Some("query") => Location::Query,
+            Some("header") => Location::Header,
+            // Cookies aren't supported
+            _ => continue,
+        };
+        if parameters.iter().any(|(n, l)| n == param_name && *l == location) {
+            continue;
+        }
+        if properties.contains_key(param_name) || param_name == "body" {
+            return Err(format!("parameter '{}' appears twice", param_name));
+        }
+
+        let mut schema = parameter.get("schema").cloned().unwrap_or_else(|| json!({}));
+        if let (Some(description), Some(object)) = (parameter.get("description"), schema.as_object_mut()) {
+            object.entry("description").or_insert(description.clone());
+        }
+        properties.insert(param_name.to_string(), schema);
+        if location == Location::Path || parameter.get("required").and_then(Value::as_bool) == Some(true) {
+            required.push(json!(param_name));
+        }
+        parameters.push((param_name.to_string(), location));
+    }
+
+    if let Some(body) = operation.get("requestBody") {
+        let body = resolve(spec, body);
+        let content = body.get("content").and_then(Value::as_object);
+        let schema = content
+            .and_then(|content| content.iter().find(|(media, _)| media.starts_with("application/json")))
+            .map(|(_, media)| media.get("schema").cloned().unwrap_or_else(|| json!({})));
+        match schema {
+            Some(mut schema) => {
+                if let (Some(description), Some(object)) = (body.get("description"), schema.as_object_mut()) {
+                    object.entry("description").or_insert(description.clone());
+                }
+                properties.insert("body".to_string(), schema);
+                if body.get("required").and_then(Value::as_bool) == Some(true) {
+                    required.push(json!("body"));
+                }
+            }
+            None if content.is_some_and(|c| !c.is_empty()) => {
+                return Err("only JSON request bodies are supported".to_string());
+            }
+            None => {}
+        }
+    }
+
+    let mut input_schema = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+    if !required.is_empty() {
+        input_schema["required"] = Value::Array(required);
+    }
+    let mut defs = Map::new();
+    collect_defs(spec, &input_schema, &mut defs);
+    if !defs.is_empty() {
+        input_schema["$defs"] = Value::Object(defs);
+    }
+    normalize(&mut input_schema);
+
+    let validator = jsonschema::validator_for(&input_schema).map_err(|e| format!("unusable schema: {}", e))?;
+
+    let summary = [operation.get("summary"), operation.get("description")]
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" - ");
+    let description = if summary.is_empty() {
+        format!("[{}] {} {}", api.config.name, method.to_uppercase(), path)
+    } else {
+        format!("[{}] {}", api.config.name, summary)
+    };
+
+    Ok(OpenApiTool {
+        definition: ToolDefinition {
+            name: name.to_string(),
+            description: Some(description),
+            input_schema,
+            output_schema: None,
+        },
+        method: Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|e| e.to_string())?,
+        path: path.to_string(),
+        parameters,
+        validator,
+        api,
+    })
+}
+
+/// Follows a local `$ref` (e.g. to `#/components/parameters/...`).
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    // Bounded, in case of reference cycles
+    for _ in 0..8 {
+        match value.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix('#')) {
+            Some(pointer) => match spec.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    value
+}
+
+/// Copies the component schemas `value` refers to, directly or through
+/// other components, into `defs`.
+fn collect_defs(spec: &Value, value: &Value, defs: &mut Map<String, Value>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(name) = object
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.strip_prefix("#/components/schemas/"))
+            {
+                if !defs.contains_key(name) {
+                    if let Some(schema) = spec.pointer(&format!("/components/schemas/{}", name)) {
+                        defs.insert(name.to_string(), schema.clone());
+                        collect_defs(spec, schema, defs);
+                    }
+                }
+            }
+            object.values().for_each(|v| collect_defs(spec, v, defs));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_defs(spec, v, defs)),
+        _ => {}
+    }
+}
+
+/// Turns OpenAPI schema dialect into plain JSON Schema: component
+/// references point to `$defs`, `nullable` becomes a `null` type, and
+/// OpenAPI 3.0's boolean exclusive bounds become numbers.
+fn normalize(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(reference)) = object.get_mut("$ref") {
+                if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+                    *reference = format!("#/$defs/{}", name);
+                }
+            }
+            if object.remove("nullable") == Some(Value::Bool(true)) {
+                if let Some(Value::String(ty)) = object.get("type") {
+                    let ty = ty.clone();
+                    object.insert("type".to_string(), json!([ty, "null"]));
+                }
+            }
+            for (exclusive, bound) in [("exclusiveMinimum", "minimum"), ("exclusiveMaximum", "maximum")] {
+                if let Some(Value::Bool(is_exclusive)) = object.get(exclusive) {
+                    match (is_exclusive, object.get(bound).cloned()) {
+                        (true, Some(limit)) => {
+                            object.remove(bound);
+                            object.insert(exclusive.to_string(), limit);
+                        }
+                        _ => {
+                            object.remove(exclusive);
+                        }
+                    }
+                }
+            }
+            object.values_mut().for_each(normalize);
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize),
+        _ => {}
+    }
+}
+
+/// An operation ID or path usable in a tool name.
+fn sanitize(id: &str) -> String {
+    let mut name = String::new();
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// Loads one spec and returns its tools.
+pub async fn load_openapi(config: &OpenApiConfig) -> Result<Vec<OpenApiTool>, String> {
+    let spec = load_spec(&config.spec, &reqwest::Client::new()).await?;
+    let spec_url = Url::parse(&config.spec).ok().filter(|url| url.scheme().starts_with("http"));
+    build_tools(config, &spec, spec_url.as_ref())
+}
+
+/// Loads every configured OpenAPI spec and registers its operations.
+///
+/// Specs that fail to load are logged and skipped. Returns the number of
+/// tools registered.
+pub async fn mount_openapi(state: &Arc<RuntimeState>) -> usize {
+    let mut mounted = 0;
+
+    for api in &state.config.openapi {
+        match load_openapi(api).await {
+            Ok(tools) => {
+                let count = tools.len();
+                let mut registry = state.tool_registry.write();
+                for tool in tools {
+                    registry.register(Arc::new(tool));
+                }
+                info!("Mounted OpenAPI spec '{}' ({} tools as {}.*)", api.name, count, api.name);
+                mounted += count;
+            }
+            Err(e) => warn!("Failed to load OpenAPI spec '{}' from {}: {}", api.name, api.spec, e),
+        }
+    }
+
+    mounted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use axum::extract::{Path, Query};
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use axum::Json;
+    use std::collections::HashMap;
+
+    const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Pets, version: "1" }
+paths:
+  /pets/{petId}:
+    parameters:
+      - { name: petId, in: path, required: true, schema: { type: integer } }
+    get:
+      operationId: getPet
+      summary: Get a pet
+      parameters:
+        - { name: verbose, in: query, schema: { type: boolean, nullable: true } }
+        - $ref: '#/components/parameters/Trace'
+  /pets:
+    post:
+      operationId: createPet
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/Pet' }
+    get:
+      operationId: listPets
+components:
+  parameters:
+    Trace: { name: X-Trace, in: header, schema: { type: string } }
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name: { type: string }
+        age: { type: integer, minimum: 0, exclusiveMinimum: true }
+        owner: { $ref: '#/components/schemas/Owner' }
+    Owner:
+      type: object
+      properties: { email: { type: string } }
+"#;
+
+    #[tokio::test]
+    async fn test_openapi_tools() {
+        let app = axum::Router::new()
+            .route(
+                "/v1/pets/:id",
+                get(|Path(id): Path<String>, Query(query): Query<HashMap<String, String>>, headers: HeaderMap| async move {
+                    Json(json!({
+                        "id": id,
+                        "verbose": query.get("verbose"),
+                        "trace": headers.get("x-trace").and_then(|v| v.to_str().ok()),
+                        "auth": headers.get("authorization").and_then(|v| v.to_str().ok()),
+                    }))
+                }),
+            )
+            .route("/v1/pets", axum::routing::post(|Json(body): Json<Value>| async move { Json(json!({ "created": body })) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dir = tempfile::tempdir().unwrap();
+        let spec_path = dir.path().join("pets.yaml");
+        std::fs::write(&spec_path, SPEC).unwrap();
+        let config = OpenApiConfig {
+            name: "pets".to_string(),
+            spec: spec_path.to_string_lossy().into_owned(),
+            base_url: Some(base),
+            auth_secret: Some("PETS_TOKEN".to_string()),
+            auth_header: "Authorization".to_string(),
+            auth_scheme: "Bearer".to_string(),
+            headers: HashMap::new(),
+            operations: vec!["*Pet".to_string()],
+            timeout_secs: 5,
+        };
+        let tools = load_openapi(&config).await.unwrap();
+        let names: Vec<_> = tools.iter().map(|t| t.definition.name.as_str()).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"pets.getPet") && names.contains(&"pets.createPet"));
+
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        state.secrets.set("PETS_TOKEN", "s3cret", None);
+        let tool = |name: &str| tools.iter().find(|t| t.definition.name == name).unwrap();
+
+        let output = tool("pets.getPet")
+            .execute(json!({ "petId": 7, "verbose": true, "X-Trace": "abc" }), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            output.structured_content.unwrap(),
+            json!({ "id": "7", "verbose": "true", "trace": "abc", "auth": "Bearer s3cret" })
+        );
+
+        let err = tool("pets.getPet").execute(json!({ "petId": "seven" }), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+
+        let body = json!({ "name": "Rex", "age": 2, "owner": { "email": "a@b.c" } });
+        let output = tool("pets.createPet").execute(json!({ "body": body }), state.clone()).await.unwrap();
+        assert_eq!(output.structured_content.unwrap()["created"], body);
+
+        // The component schemas are checked too, including converted bounds
+        let err = tool("pets.createPet")
+            .execute(json!({ "body": { "name": "Rex", "age": 0 } }), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("get_/pets/{petId}"), "get_pets_petId");
+        assert_eq!(sanitize("list-users"), "list-users");
+    }
+}
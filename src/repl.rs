@@ -0,0 +1,384 @@
+//! Interactive shell for calling tools by hand (`aegis repl`).
+//!
+//! Calls go through the same router as MCP clients, so policy, budgets and
+//! the security settings apply exactly as they would for an agent. Results
+//! print as colored JSON and are kept for `last`/`history`.
+
+use colored::Colorize;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::{RequestContext, RuntimeState};
+use crate::handlers::Router;
+use crate::protocol::{Request, RequestId};
+
+/// Words the REPL understands besides tool names.
+const COMMANDS: &[&str] = &["call", "describe", "help", "history", "last", "tools", "exit", "quit"];
+
+/// A parsed REPL line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Call a tool with JSON arguments.
+    Call { tool: String, arguments: Value },
+    /// List tools, optionally only those containing a substring.
+    Tools(Option<String>),
+    /// Show a tool's description and input schema.
+    Describe(String),
+    /// Show the result of the most recent call, or of call `n`.
+    Last(Option<usize>),
+    /// List previous calls.
+    History,
+    Help,
+    Exit,
+    Empty,
+}
+
+/// Parses a line. A line starting with a known tool name is a call, so
+/// `call` can be left out.
+pub fn parse_line(line: &str, is_tool: impl Fn(&str) -> bool) -> Result<Command, String> {
+    let line = line.trim();
+    let (word, rest) = match line.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (line, ""),
+    };
+
+    let argument = || (!rest.is_empty()).then(|| rest.to_string());
+    match word {
+        "" => Ok(Command::Empty),
+        "exit" | "quit" => Ok(Command::Exit),
+        "help" | "?" => Ok(Command::Help),
+        "history" => Ok(Command::History),
+        "tools" => Ok(Command::Tools(argument())),
+        "describe" => argument().map(Command::Describe).ok_or_else(|| "usage: describe <tool>".to_string()),
+        "last" => match argument() {
+            None => Ok(Command::Last(None)),
+            Some(n) => n
+                .parse()
+                .map(|n| Command::Last(Some(n)))
+                .map_err(|_| format!("'{}' is not a call number", n)),
+        },
+        "call" => {
+            let (tool, arguments) = match rest.split_once(char::is_whitespace) {
+                Some((tool, arguments)) => (tool, arguments.trim()),
+                None => (rest, ""),
+            };
+            if tool.is_empty() {
+                return Err("usage: call <tool> [json arguments]".to_string());
+            }
+            parse_call(tool, arguments)
+        }
+        tool if is_tool(tool) => parse_call(tool, rest),
+        other => Err(format!("unknown command or tool '{}' (type 'help')", other)),
+    }
+}
+
+fn parse_call(tool: &str, arguments: &str) -> Result<Command, String> {
+    let arguments = if arguments.is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(arguments).map_err(|e| format!("invalid JSON arguments: {}", e))?
+    };
+    Ok(Command::Call { tool: tool.to_string(), arguments })
+}
+
+/// Renders JSON pretty-printed with ANSI colors.
+pub fn colorize_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_json(value, 0, &mut out);
+    out
+}
+
+fn write_json(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    let close = "  ".repeat(indent);
+    match value {
+        Value::Null => out.push_str(&"null".dimmed().to_string()),
+        Value::Bool(b) => out.push_str(&b.to_string().magenta().to_string()),
+        Value::Number(n) => out.push_str(&n.to_string().yellow().to_string()),
+        Value::String(_) => out.push_str(&value.to_string().green().to_string()),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad);
+                write_json(item, indent + 1, out);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&close);
+            out.push(']');
+        }
+        Value::Object(map) => {
+            out.push_str("{\n");
+            for (i, (key, item)) in map.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&Value::String(key.clone()).to_string().cyan().to_string());
+                out.push_str(": ");
+                write_json(item, indent + 1, out);
+                out.push_str(if i + 1 < map.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&close);
+            out.push('}');
+        }
+    }
+}
+
+/// Tab completion of commands and tool names.
+struct ReplHelper {
+    tools: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = &before[start..];
+        let previous: Vec<&str> = before[..start].split_whitespace().collect();
+
+        let candidates: Box<dyn Iterator<Item = &str>> = match previous.as_slice() {
+            [] => Box::new(COMMANDS.iter().copied().chain(self.tools.iter().map(String::as_str))),
+            ["call" | "describe"] => Box::new(self.tools.iter().map(String::as_str)),
+            _ => Box::new(std::iter::empty()),
+        };
+        let mut matches: Vec<String> = candidates
+            .filter(|name| name.starts_with(prefix))
+            .map(str::to_string)
+            .collect();
+        matches.sort();
+        matches.dedup();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// A finished call.
+struct Call {
+    tool: String,
+    result: Value,
+    is_error: bool,
+    elapsed: Duration,
+}
+
+fn print_help() {
+    println!("  {} {}   call a tool ({} may be left out)", "call".cyan(), "<tool> [json]".dimmed(), "call".cyan());
+    println!("  {} {}         list tools", "tools".cyan(), "[filter]".dimmed());
+    println!("  {} {}        show a tool's description and input schema", "describe".cyan(), "<tool>".dimmed());
+    println!("  {} {}               show the latest result, or result n", "last".cyan(), "[n]".dimmed());
+    println!("  {}                 list previous calls", "history".cyan());
+    println!("  {}                    leave (or Ctrl+D)", "exit".cyan());
+    println!("  Tab completes commands and tool names; Ctrl+C cancels a running call.");
+}
+
+fn print_result(call: &Call) {
+    let status = if call.is_error { "error".red().bold() } else { "ok".green().bold() };
+    println!("{} {} {}", status, call.tool.white().bold(), format!("({} ms)", call.elapsed.as_millis()).dimmed());
+
+    if let Some(structured) = call.result.get("structuredContent") {
+        println!("{}", colorize_json(structured));
+        return;
+    }
+    let Some(content) = call.result.get("content").and_then(|c| c.as_array()) else {
+        println!("{}", colorize_json(&call.result));
+        return;
+    };
+    for item in content {
+        match item.get("type").and_then(|t| t.as_str()) {
+            Some("text") => {
+                let text = item.get("text").and_then(|t| t.as_str()).unwrap_or_default();
+                match serde_json::from_str::<Value>(text) {
+                    Ok(value) if value.is_object() || value.is_array() => println!("{}", colorize_json(&value)),
+                    _ if call.is_error => println!("{}", text.red()),
+                    _ => println!("{}", text),
+                }
+            }
+            Some("image") => {
+                let mime = item.get("mimeType").and_then(|m| m.as_str()).unwrap_or("image");
+                let size = item.get("data").and_then(|d| d.as_str()).map_or(0, str::len);
+                println!("{}", format!("[Image: {} ({} bytes base64)]", mime, size).dimmed());
+            }
+            _ => println!("{}", colorize_json(item)),
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".aegis_history"))
+}
+
+/// Runs the REPL until `exit` or end of input.
+pub async fn run(state: Arc<RuntimeState>) -> Result<(), Box<dyn std::error::Error>> {
+    let router = Router::new();
+    let context = RequestContext::new("repl");
+    let mut editor: Editor<ReplHelper, FileHistory> = Editor::new()?;
+    editor.set_helper(Some(ReplHelper { tools: Vec::new() }));
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+
+    println!("{} {}", "Aegis REPL".cyan().bold(), "— type 'help' for commands, Tab to complete".dimmed());
+    let mut calls: Vec<Call> = Vec::new();
+    let mut next_id = 0;
+
+    loop {
+        let tools = state.tool_registry.read().names();
+        if let Some(helper) = editor.helper_mut() {
+            helper.tools = tools.clone();
+        }
+
+        let line = match tokio::task::block_in_place(|| editor.readline("aegis> ")) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+
+        let command = match parse_line(&line, |name| tools.iter().any(|tool| tool == name)) {
+            Ok(command) => command,
+            Err(e) => {
+                println!("{} {}", "error:".red().bold(), e);
+                continue;
+            }
+        };
+
+        match command {
+            Command::Empty => {}
+            Command::Exit => break,
+            Command::Help => print_help(),
+            Command::Tools(filter) => {
+                for name in tools.iter().filter(|name| filter.as_deref().is_none_or(|f| name.contains(f))) {
+                    println!("  {} {}", "▸".green(), name);
+                }
+            }
+            Command::Describe(name) => {
+                let tool = state.tool_registry.read().get(&name).cloned();
+                match tool {
+                    Some(tool) => {
+                        let definition = tool.definition();
+                        println!("{}", definition.name.white().bold());
+                        if let Some(description) = &definition.description {
+                            println!("{}", description.dimmed());
+                        }
+                        println!("{}", colorize_json(&definition.input_schema));
+                        if let Some(output_schema) = &definition.output_schema {
+                            println!("{}", "Output schema:".dimmed());
+                            println!("{}", colorize_json(output_schema));
+                        }
+                    }
+                    None => println!("{} no tool named '{}'", "error:".red().bold(), name),
+                }
+            }
+            Command::Last(n) => {
+                let call = match n {
+                    Some(n) => n.checked_sub(1).and_then(|i| calls.get(i)),
+                    None => calls.last(),
+                };
+                match call {
+                    Some(call) => print_result(call),
+                    None => println!("{}", "no such call".dimmed()),
+                }
+            }
+            Command::History => {
+                for (i, call) in calls.iter().enumerate() {
+                    let status = if call.is_error { "✗".red() } else { "✓".green() };
+                    println!("  {:>3} {} {} {}", i + 1, status, call.tool, format!("({} ms)", call.elapsed.as_millis()).dimmed());
+                }
+            }
+            Command::Call { tool, arguments } => {
+                next_id += 1;
+                let request = Request::new(
+                    "tools/call",
+                    Some(json!({"name": tool, "arguments": arguments})),
+                    RequestId::Number(next_id),
+                );
+                let started = Instant::now();
+                let response = tokio::select! {
+                    response = router.handle_with_context(request, state.clone(), context.clone()) => response,
+                    _ = tokio::signal::ctrl_c() => {
+                        println!("{}", "cancelled".yellow());
+                        continue;
+                    }
+                };
+
+                let call = match (response.result, response.error) {
+                    (Some(result), _) => Call {
+                        is_error: result.get("isError").and_then(|e| e.as_bool()).unwrap_or(false),
+                        tool,
+                        result,
+                        elapsed: started.elapsed(),
+                    },
+                    (None, error) => Call {
+                        tool,
+                        result: serde_json::to_value(error).unwrap_or_default(),
+                        is_error: true,
+                        elapsed: started.elapsed(),
+                    },
+                };
+                print_result(&call);
+                calls.push(call);
+            }
+        }
+    }
+
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_tool(name: &str) -> bool {
+        name == "fs.read_file"
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line(r#"call fs.read_file {"path": "x"}"#, is_tool).unwrap(),
+            Command::Call { tool: "fs.read_file".to_string(), arguments: json!({"path": "x"}) }
+        );
+        assert_eq!(
+            parse_line("fs.read_file", is_tool).unwrap(),
+            Command::Call { tool: "fs.read_file".to_string(), arguments: json!({}) }
+        );
+        assert_eq!(parse_line("  ", is_tool).unwrap(), Command::Empty);
+        assert_eq!(parse_line("tools fs", is_tool).unwrap(), Command::Tools(Some("fs".to_string())));
+        assert_eq!(parse_line("last 2", is_tool).unwrap(), Command::Last(Some(2)));
+        assert!(parse_line("call fs.read_file {nope", is_tool).is_err());
+        assert!(parse_line("fs.nope {}", is_tool).is_err());
+        assert!(parse_line("describe", is_tool).is_err());
+    }
+
+    #[test]
+    fn test_colorize_json_layout() {
+        colored::control::set_override(false);
+        let value = json!({"a": [1, true, null], "b": {}, "c": "x"});
+        assert_eq!(colorize_json(&value), serde_json::to_string_pretty(&value).unwrap());
+    }
+}
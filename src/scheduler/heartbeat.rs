@@ -0,0 +1,251 @@
+//! Agent heartbeats and the missed-heartbeat watchdog.
+//!
+//! Agents report liveness with `agent.heartbeat`; each report is stored in
+//! the KV store under `agent:heartbeat:{agent}`. On every scheduler tick the
+//! watchdog looks for agents whose next heartbeat is overdue and fires their
+//! alert tool (e.g. `notify.slack`) once per outage.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::core::{RequestContext, RuntimeState};
+use crate::memory::{MemoryError, MemoryStore};
+use crate::tools::caller;
+
+/// KV prefix heartbeats are stored under.
+pub const HEARTBEAT_PREFIX: &str = "agent:heartbeat:";
+
+/// Tool call fired when an agent misses its heartbeat.
+///
+/// String values in `args` may use `{agent}`, `{last_seen}`, and
+/// `{overdue_secs}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatAlert {
+    /// Tool to call (e.g. "notify.slack").
+    pub tool: String,
+    /// Arguments for the tool.
+    #[serde(default)]
+    pub args: Value,
+}
+
+/// The latest heartbeat of an agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// Agent name.
+    pub agent: String,
+    /// Free-form status blob reported by the agent.
+    #[serde(default)]
+    pub status: Value,
+    /// When the heartbeat was received (RFC 3339).
+    pub last_seen: String,
+    /// Seconds until the next heartbeat is expected.
+    pub interval_secs: u64,
+    /// Extra slack before a late heartbeat counts as missed.
+    pub grace_secs: u64,
+    /// Alert to fire when the heartbeat is missed.
+    #[serde(default)]
+    pub alert: Option<HeartbeatAlert>,
+    /// Whether the current outage has already been alerted.
+    #[serde(default)]
+    pub alerted: bool,
+}
+
+impl Heartbeat {
+    /// When the heartbeat counts as missed.
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        let last_seen = DateTime::parse_from_rfc3339(&self.last_seen).ok()?.with_timezone(&Utc);
+        let window = self.interval_secs.saturating_add(self.grace_secs);
+        Some(last_seen + Duration::seconds(i64::try_from(window).unwrap_or(i64::MAX / 1000)))
+    }
+
+    /// Seconds past the deadline, or `None` if the agent is alive.
+    pub fn overdue_secs(&self, now: DateTime<Utc>) -> Option<i64> {
+        let deadline = self.deadline()?;
+        (now > deadline).then(|| (now - deadline).num_seconds())
+    }
+
+    /// Serializes the heartbeat with its liveness as of `now`.
+    pub fn report(&self, now: DateTime<Utc>) -> Value {
+        let mut report = serde_json::to_value(self).unwrap_or_default();
+        let overdue = self.overdue_secs(now);
+        report["alive"] = Value::Bool(overdue.is_none());
+        report["overdue_secs"] = overdue.map(Value::from).unwrap_or(Value::Null);
+        if let Some(obj) = report.as_object_mut() {
+            obj.remove("alerted");
+        }
+        report
+    }
+}
+
+/// Stores a heartbeat, replacing the agent's previous one.
+pub async fn save(store: &dyn MemoryStore, heartbeat: &Heartbeat) -> Result<(), MemoryError> {
+    let value = serde_json::to_value(heartbeat).map_err(|e| MemoryError::Serialization(e.to_string()))?;
+    store.kv_set(&format!("{}{}", HEARTBEAT_PREFIX, heartbeat.agent), value, None).await
+}
+
+/// Loads an agent's latest heartbeat.
+pub async fn load(store: &dyn MemoryStore, agent: &str) -> Result<Option<Heartbeat>, MemoryError> {
+    let Some(kv) = store.kv_get(&format!("{}{}", HEARTBEAT_PREFIX, agent)).await? else {
+        return Ok(None);
+    };
+    serde_json::from_value(kv.value)
+        .map(Some)
+        .map_err(|e| MemoryError::Serialization(e.to_string()))
+}
+
+/// Loads the latest heartbeat of every agent, sorted by name.
+pub async fn load_all(store: &dyn MemoryStore) -> Result<Vec<Heartbeat>, MemoryError> {
+    let mut heartbeats = Vec::new();
+    for key in store.kv_list(Some(HEARTBEAT_PREFIX)).await? {
+        if let Some(agent) = key.strip_prefix(HEARTBEAT_PREFIX) {
+            if let Some(heartbeat) = load(store, agent).await? {
+                heartbeats.push(heartbeat);
+            }
+        }
+    }
+    heartbeats.sort_by(|a, b| a.agent.cmp(&b.agent));
+    Ok(heartbeats)
+}
+
+/// Fires alerts for agents whose heartbeat is overdue. Returns how many
+/// newly missed heartbeats were found.
+pub async fn check_missed(state: &Arc<RuntimeState>, now: DateTime<Utc>) -> usize {
+    let heartbeats = match load_all(state.memory_store.as_ref()).await {
+        Ok(heartbeats) => heartbeats,
+        Err(e) => {
+            error!("Failed to load heartbeats: {}", e);
+            return 0;
+        }
+    };
+
+    let mut missed = 0;
+    for mut heartbeat in heartbeats {
+        let Some(overdue_secs) = heartbeat.overdue_secs(now) else {
+            continue;
+        };
+        if heartbeat.alerted {
+            continue;
+        }
+
+        missed += 1;
+        warn!(
+            "Agent '{}' missed its heartbeat (last seen {}, {}s overdue)",
+            heartbeat.agent, heartbeat.last_seen, overdue_secs
+        );
+
+        if let Some(alert) = &heartbeat.alert {
+            fire_alert(state, alert, &heartbeat, overdue_secs).await;
+        }
+
+        // Alert once per outage; the next heartbeat re-arms it
+        heartbeat.alerted = true;
+        if let Err(e) = save(state.memory_store.as_ref(), &heartbeat).await {
+            error!("Failed to update heartbeat for '{}': {}", heartbeat.agent, e);
+        }
+    }
+    missed
+}
+
+async fn fire_alert(state: &Arc<RuntimeState>, alert: &HeartbeatAlert, heartbeat: &Heartbeat, overdue_secs: i64) {
+    let tool = state.tool_registry.read().get(&alert.tool).cloned();
+    let Some(tool) = tool else {
+        error!("Heartbeat alert tool not found: {}", alert.tool);
+        return;
+    };
+
+    let args = fill_placeholders(&alert.args, &[
+        ("{agent}", heartbeat.agent.as_str()),
+        ("{last_seen}", heartbeat.last_seen.as_str()),
+        ("{overdue_secs}", &overdue_secs.to_string()),
+    ]);
+
+    match caller::with_context(RequestContext::default(), tool.execute(args, state.clone())).await {
+        Ok(output) if !output.is_error => {
+            info!("Sent missed-heartbeat alert for '{}' via {}", heartbeat.agent, alert.tool);
+        }
+        Ok(output) => error!("Heartbeat alert via {} failed: {:?}", alert.tool, output.content),
+        Err(e) => error!("Heartbeat alert via {} failed: {}", alert.tool, e),
+    }
+}
+
+/// Replaces placeholders in every string inside a JSON value.
+fn fill_placeholders(value: &Value, replacements: &[(&str, &str)]) -> Value {
+    match value {
+        Value::String(s) => {
+            let filled = replacements
+                .iter()
+                .fold(s.clone(), |acc, (placeholder, with)| acc.replace(placeholder, with));
+            Value::String(filled)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| fill_placeholders(v, replacements)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), fill_placeholders(v, replacements)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use serde_json::json;
+
+    fn heartbeat(last_seen: &str, alert: Option<HeartbeatAlert>) -> Heartbeat {
+        Heartbeat {
+            agent: "crawler".to_string(),
+            status: json!({"queue": 3}),
+            last_seen: last_seen.to_string(),
+            interval_secs: 60,
+            grace_secs: 30,
+            alert,
+            alerted: false,
+        }
+    }
+
+    #[test]
+    fn test_overdue() {
+        let hb = heartbeat("2024-01-01T00:00:00Z", None);
+        let at = |ts: &str| DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&Utc);
+
+        assert_eq!(hb.overdue_secs(at("2024-01-01T00:01:30Z")), None);
+        assert_eq!(hb.overdue_secs(at("2024-01-01T00:02:00Z")), Some(30));
+        assert_eq!(hb.report(at("2024-01-01T00:01:00Z"))["alive"], true);
+    }
+
+    #[tokio::test]
+    async fn test_check_missed_alerts_once() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let alert = HeartbeatAlert {
+            tool: "echo".to_string(),
+            args: json!({"text": "{agent} is {overdue_secs}s late"}),
+        };
+        save(state.memory_store.as_ref(), &heartbeat("2024-01-01T00:00:00Z", Some(alert)))
+            .await
+            .unwrap();
+
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:05:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(check_missed(&state, now).await, 1);
+        assert_eq!(check_missed(&state, now).await, 0);
+
+        let stored = load(state.memory_store.as_ref(), "crawler").await.unwrap().unwrap();
+        assert!(stored.alerted);
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        let filled = fill_placeholders(
+            &json!({"text": "{agent} down", "blocks": [{"t": "{agent}"}], "n": 1}),
+            &[("{agent}", "crawler")],
+        );
+        assert_eq!(filled, json!({"text": "crawler down", "blocks": [{"t": "crawler"}], "n": 1}));
+    }
+}
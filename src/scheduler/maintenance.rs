@@ -0,0 +1,159 @@
+//! Database maintenance.
+//!
+//! On every scheduler tick whose time matches `maintenance.purge_cron`,
+//! expired KV entries are deleted; on ticks matching
+//! `maintenance.compact_cron` the database is vacuumed and analyzed. Both run
+//! in the background so a slow VACUUM never delays cron tasks, and their
+//! results are kept in [`MaintenanceStats`], which `/metrics` reports.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use super::Scheduler;
+use crate::core::RuntimeState;
+
+/// Totals of the maintenance runs since startup.
+#[derive(Debug, Default)]
+pub struct MaintenanceStats {
+    purges: AtomicU64,
+    purged_keys: AtomicU64,
+    compactions: AtomicU64,
+    reclaimed_bytes: AtomicU64,
+    failures: AtomicU64,
+    last_purge: RwLock<Option<String>>,
+    last_compaction: RwLock<Option<String>>,
+    last_error: RwLock<Option<String>>,
+    compacting: AtomicBool,
+}
+
+impl MaintenanceStats {
+    fn record_purge(&self, purged: u64) {
+        self.purges.fetch_add(1, Ordering::Relaxed);
+        self.purged_keys.fetch_add(purged, Ordering::Relaxed);
+        *self.last_purge.write() = Some(Utc::now().to_rfc3339());
+    }
+
+    fn record_compaction(&self, reclaimed: u64) {
+        self.compactions.fetch_add(1, Ordering::Relaxed);
+        self.reclaimed_bytes.fetch_add(reclaimed, Ordering::Relaxed);
+        *self.last_compaction.write() = Some(Utc::now().to_rfc3339());
+    }
+
+    fn record_failure(&self, error: String) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.write() = Some(error);
+    }
+
+    /// Serializes the totals for `/metrics`.
+    pub fn snapshot(&self) -> Value {
+        json!({
+            "purges": self.purges.load(Ordering::Relaxed),
+            "purged_keys": self.purged_keys.load(Ordering::Relaxed),
+            "compactions": self.compactions.load(Ordering::Relaxed),
+            "reclaimed_bytes": self.reclaimed_bytes.load(Ordering::Relaxed),
+            "failures": self.failures.load(Ordering::Relaxed),
+            "last_purge": *self.last_purge.read(),
+            "last_compaction": *self.last_compaction.read(),
+            "last_error": *self.last_error.read(),
+        })
+    }
+}
+
+/// Deletes expired KV entries and returns how many were removed.
+pub async fn purge(state: &RuntimeState) -> Option<u64> {
+    let stats = state.scheduler.maintenance();
+    match state.memory_store.purge_expired().await {
+        Ok(purged) => {
+            if purged > 0 {
+                info!("Purged {} expired keys", purged);
+            }
+            stats.record_purge(purged);
+            Some(purged)
+        }
+        Err(e) => {
+            error!("Purging expired keys failed: {}", e);
+            stats.record_failure(format!("purge: {}", e));
+            None
+        }
+    }
+}
+
+/// Vacuums and analyzes the database and returns the bytes reclaimed.
+/// Does nothing if a compaction is already running.
+pub async fn compact(state: &RuntimeState) -> Option<u64> {
+    let stats = state.scheduler.maintenance();
+    if stats.compacting.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+    let result = state.memory_store.compact().await;
+    stats.compacting.store(false, Ordering::SeqCst);
+    match result {
+        Ok(reclaimed) => {
+            info!("Compacted database, reclaimed {} bytes", reclaimed);
+            stats.record_compaction(reclaimed);
+            Some(reclaimed)
+        }
+        Err(e) => {
+            error!("Compacting the database failed: {}", e);
+            stats.record_failure(format!("compact: {}", e));
+            None
+        }
+    }
+}
+
+/// Starts whichever maintenance jobs are due at `now`.
+pub fn run_due(state: &Arc<RuntimeState>, now: DateTime<Utc>) {
+    let config = &state.config.maintenance;
+    if !config.enabled {
+        return;
+    }
+    let now = now.with_timezone(&state.scheduler.timezone());
+    let purge_due = Scheduler::should_trigger(&config.purge_cron, now);
+    let compact_due = Scheduler::should_trigger(&config.compact_cron, now);
+    if !purge_due && !compact_due {
+        return;
+    }
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        if purge_due {
+            purge(&state).await;
+        }
+        if compact_due {
+            compact(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_purge_and_compact() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = RuntimeState::new(crate::core::Config {
+            database_path: Some(dir.path().join("aegis.db").to_string_lossy().into_owned()),
+            ..Default::default()
+        });
+        let store = &state.memory_store;
+        for i in 0..200 {
+            store.kv_set(&format!("tmp:{}", i), json!("x".repeat(1000)), Some(0)).await.unwrap();
+        }
+        store.kv_set("keep", json!(1), None).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        assert_eq!(purge(&state).await, Some(200));
+        assert!(compact(&state).await.unwrap() > 0);
+        assert!(store.kv_get("keep").await.unwrap().is_some());
+
+        let snapshot = state.scheduler.maintenance().snapshot();
+        assert_eq!(snapshot["purged_keys"], 200);
+        assert_eq!(snapshot["compactions"], 1);
+        assert!(snapshot["reclaimed_bytes"].as_u64().unwrap() > 0);
+    }
+}
@@ -1,15 +1,136 @@
 //! Scheduler for automated task execution.
 //!
-//! Provides cron-like scheduling for tools and workflows.
+//! Provides cron-like scheduling for tools and workflows, watches agent
+//! heartbeats for missed check-ins, and runs database maintenance.
+//!
+//! Besides running on a clock, a task can run [`Schedule::After`] another
+//! one completes. The chained task sees the previous run as `{{previous.*}}`
+//! placeholders in its arguments: `task_id`, `name`, `success` and `output`,
+//! which is parsed when it holds JSON so that fields can be addressed as
+//! `{{previous.output.field}}`.
+//!
+//! Tasks are persisted in the memory store with their `next_run` and
+//! `last_run`, so a restart picks them up where they were. Runs that fell
+//! due while the server was down are skipped, or run once on startup for a
+//! task with `catch_up`. A task's [`Concurrency`] decides what happens when
+//! it falls due while a run of it is still going.
+
+/// Agent heartbeats and the missed-heartbeat watchdog.
+pub mod heartbeat;
 
-use chrono::{DateTime, Utc};
-use parking_lot::RwLock;
+/// Purging expired KV entries and compacting the database.
+pub mod maintenance;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::sync::Arc;
+use tokio::sync::Notify;
 use tracing::{debug, error, info, warn};
 
-use crate::core::RuntimeState;
+use crate::core::config::RuleOwner;
+use crate::core::events::is_forbidden_action;
+use crate::core::{RequestContext, RuntimeState};
+use crate::handlers::handle_tools_call_with_context;
+use crate::memory::{MemoryError, MemoryStore, TaskRun};
+use crate::proxy::convert_result;
+use crate::tools::extras::substitute_context;
+use crate::tools::middleware::{output_text, output_value, scrub_result};
+use crate::tools::ToolError;
+
+/// Runs kept in the history of each task.
+pub const TASK_HISTORY_LIMIT: usize = 100;
+
+/// Characters of a run's output or error kept in its history.
+const HISTORY_EXCERPT_CHARS: usize = 2000;
+
+/// KV key the scheduled tasks are persisted under.
+pub const SCHEDULED_TASKS_KEY: &str = "aegis:scheduled_tasks";
+
+/// Tool that scheduled workflow runs call.
+pub const WORKFLOW_TOOL: &str = "workflow.execute";
+
+/// How far ahead a cron expression is searched for its next match; a
+/// Feb 29th schedule must be able to wait for the next leap year.
+const CRON_HORIZON_DAYS: i64 = 4 * 366 + 1;
+
+/// When a task runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// Cron expression (e.g., "0 * * * *" for every hour), evaluated in the
+    /// task's timezone.
+    Cron(String),
+    /// Every this many seconds, starting one interval after the task is
+    /// created or enabled.
+    IntervalSecs(u64),
+    /// Once, at this time.
+    At(DateTime<Utc>),
+    /// Whenever another task finishes with a matching outcome.
+    After {
+        /// ID of the task to follow.
+        task: String,
+        /// Which outcomes of that task trigger this one.
+        #[serde(default)]
+        on: ChainCondition,
+    },
+}
+
+/// Outcomes of a task that trigger the tasks chained after it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainCondition {
+    /// Only a successful run.
+    #[default]
+    Success,
+    /// Only a failed run.
+    Failure,
+    /// Any run.
+    Always,
+}
+
+impl ChainCondition {
+    /// Whether a run with the given outcome triggers the chained task.
+    pub fn matches(self, success: bool) -> bool {
+        match self {
+            ChainCondition::Success => success,
+            ChainCondition::Failure => !success,
+            ChainCondition::Always => true,
+        }
+    }
+}
+
+/// What happens when a task falls due while a run of it is still going.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Concurrency {
+    /// Drop the new run.
+    #[default]
+    Skip,
+    /// Start the new run once the current one finishes. At most one run
+    /// waits; later ones are merged into it.
+    Queue,
+    /// Start the new run alongside the current one.
+    Parallel,
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Cron(cron) => write!(f, "{}", cron),
+            Schedule::IntervalSecs(secs) => write!(f, "every {}s", secs),
+            Schedule::At(at) => write!(f, "once at {}", at.to_rfc3339()),
+            Schedule::After { task, on } => match on {
+                ChainCondition::Success => write!(f, "after {} succeeds", task),
+                ChainCondition::Failure => write!(f, "after {} fails", task),
+                ChainCondition::Always => write!(f, "after {} finishes", task),
+            },
+        }
+    }
+}
 
 /// A scheduled task definition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,11 +139,14 @@ pub struct ScheduledTask {
     pub id: String,
     /// Human-readable name.
     pub name: String,
-    /// Cron expression (e.g., "0 * * * *" for every hour).
-    pub cron: String,
-    /// Tool to execute.
+    /// When the task runs; serialized as a `cron`, `interval_secs`, `at` or
+    /// `after` field.
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Tool to execute; [`WORKFLOW_TOOL`] for a saved workflow.
     pub tool: String,
-    /// Tool arguments.
+    /// Tool arguments, which may use `{{previous.*}}` placeholders when the
+    /// task is chained.
     pub args: serde_json::Value,
     /// Whether the task is enabled.
     pub enabled: bool,
@@ -32,6 +156,35 @@ pub struct ScheduledTask {
     pub last_run: Option<String>,
     /// Last execution result.
     pub last_result: Option<TaskResult>,
+    /// IANA timezone the cron expression is evaluated in
+    /// (default: the scheduler's timezone).
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// When the task runs next; `None` once a one-shot task has run, for a
+    /// chained task, or a disabled task. Set by the scheduler.
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+    /// Run once on startup if runs fell due while the server was down,
+    /// instead of skipping them.
+    #[serde(default)]
+    pub catch_up: bool,
+    /// What happens when the task falls due while it is still running.
+    #[serde(default)]
+    pub concurrency: Concurrency,
+    /// Caller that created the task with `scheduler.create`. Its runs go
+    /// through `tools/call` as this caller; other tasks run as the operator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RuleOwner>,
+}
+
+impl ScheduledTask {
+    /// The saved workflow the task runs, if it targets one.
+    pub fn workflow(&self) -> Option<&str> {
+        if self.tool != WORKFLOW_TOOL {
+            return None;
+        }
+        self.args.get("name").and_then(|name| name.as_str())
+    }
 }
 
 /// Result of a task execution.
@@ -47,30 +200,92 @@ pub struct TaskResult {
     pub duration_ms: u64,
 }
 
+/// Runs of one task in progress.
+#[derive(Debug, Default)]
+struct RunState {
+    running: usize,
+    /// The queued run, with the previous run it is chained after.
+    queued: Option<Option<serde_json::Value>>,
+}
+
 /// Scheduler for managing automated tasks.
 #[derive(Debug)]
 pub struct Scheduler {
     tasks: RwLock<HashMap<String, ScheduledTask>>,
+    runs: Mutex<HashMap<String, RunState>>,
+    /// Serializes writes of the task list, so an older snapshot can't
+    /// overwrite a newer one.
+    persist_lock: tokio::sync::Mutex<()>,
     running: std::sync::atomic::AtomicBool,
+    timezone: Tz,
+    maintenance: maintenance::MaintenanceStats,
+    /// Wakes the loop when tasks change, so it can sleep until the next one.
+    wake: Notify,
 }
 
 impl Scheduler {
-    /// Creates a new scheduler.
+    /// Creates a new scheduler evaluating cron expressions in UTC.
     pub fn new() -> Self {
+        Self::with_timezone(chrono_tz::UTC)
+    }
+
+    /// Creates a new scheduler with the given default timezone.
+    pub fn with_timezone(timezone: Tz) -> Self {
         Self {
             tasks: RwLock::new(HashMap::new()),
+            runs: Mutex::new(HashMap::new()),
+            persist_lock: tokio::sync::Mutex::new(()),
             running: std::sync::atomic::AtomicBool::new(false),
+            timezone,
+            maintenance: Default::default(),
+            wake: Notify::new(),
         }
     }
 
+    /// Returns the scheduler's default timezone.
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Results of database maintenance so far.
+    pub fn maintenance(&self) -> &maintenance::MaintenanceStats {
+        &self.maintenance
+    }
+
+    /// Resolves the timezone a task is evaluated in.
+    fn task_timezone(&self, task: &ScheduledTask) -> Tz {
+        task.timezone
+            .as_deref()
+            .and_then(|tz| tz.parse().ok())
+            .unwrap_or(self.timezone)
+    }
+
     /// Adds a scheduled task.
-    pub fn add_task(&self, task: ScheduledTask) -> Result<(), String> {
-        // Validate cron expression
-        Self::validate_cron(&task.cron)?;
+    pub fn add_task(&self, mut task: ScheduledTask) -> Result<(), String> {
+        if let Some(tz) = &task.timezone {
+            tz.parse::<Tz>()
+                .map_err(|_| format!("Unknown timezone: {}", tz))?;
+        }
+        let now = Utc::now();
+        match &task.schedule {
+            Schedule::Cron(cron) => Self::validate_cron(cron)?,
+            Schedule::IntervalSecs(0) => return Err("Interval must be at least 1 second".to_string()),
+            Schedule::IntervalSecs(_) => {}
+            Schedule::At(at) if *at <= now => return Err(format!("Run time {} is in the past", at.to_rfc3339())),
+            Schedule::At(_) => {}
+            Schedule::After { task: previous, .. } => self.validate_chain(&task.id, previous)?,
+        }
+
+        task.next_run = if task.enabled { self.first_run(&task, now) } else { None };
+        let chained = matches!(task.schedule, Schedule::After { .. });
+        if task.enabled && task.next_run.is_none() && !chained {
+            return Err(format!("Schedule '{}' never fires", task.schedule));
+        }
 
         let id = task.id.clone();
         self.tasks.write().insert(id.clone(), task);
         info!("Added scheduled task: {}", id);
+        self.wake.notify_one();
         Ok(())
     }
 
@@ -89,18 +304,202 @@ impl Scheduler {
         self.tasks.read().values().cloned().collect()
     }
 
-    /// Enables or disables a task.
+    /// Enables or disables a task. Enabling schedules its next run from now.
     pub fn set_enabled(&self, id: &str, enabled: bool) -> bool {
+        let now = Utc::now();
+        let mut tasks = self.tasks.write();
+        let Some(task) = tasks.get_mut(id) else {
+            return false;
+        };
+        task.enabled = enabled;
+        task.next_run = if enabled { self.first_run(task, now) } else { None };
+        drop(tasks);
+        self.wake.notify_one();
+        true
+    }
+
+    /// Records the outcome of a run of a task.
+    pub fn record_result(&self, id: &str, result: TaskResult) {
         if let Some(task) = self.tasks.write().get_mut(id) {
-            task.enabled = enabled;
-            true
-        } else {
-            false
+            task.last_run = Some(result.executed_at.clone());
+            task.last_result = Some(result);
+        }
+    }
+
+    /// Saves the tasks to `store`.
+    pub async fn persist(&self, store: &dyn MemoryStore) -> Result<(), MemoryError> {
+        let _guard = self.persist_lock.lock().await;
+        let mut tasks = self.list_tasks();
+        tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        store.kv_set(SCHEDULED_TASKS_KEY, serde_json::json!(tasks), None).await
+    }
+
+    /// Loads the tasks persisted by an earlier run. Runs missed while the
+    /// server was down are skipped, or for `catch_up` tasks made due now.
+    pub async fn restore(&self, store: &dyn MemoryStore) {
+        let tasks = match store.kv_get(SCHEDULED_TASKS_KEY).await {
+            Ok(Some(entry)) => serde_json::from_value::<Vec<ScheduledTask>>(entry.value).unwrap_or_default(),
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load scheduled tasks: {}", e);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        let mut restored = 0;
+        for mut task in tasks {
+            if self.tasks.read().contains_key(&task.id) {
+                continue;
+            }
+            if task.enabled {
+                task.next_run = self.restored_run(&task, now);
+            }
+            self.tasks.write().insert(task.id.clone(), task);
+            restored += 1;
+        }
+        if restored > 0 {
+            info!("Restored {} scheduled task(s)", restored);
         }
+        self.wake.notify_one();
+    }
+
+    /// The next run of a restored task.
+    fn restored_run(&self, task: &ScheduledTask, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match task.next_run {
+            Some(due) if due <= now && task.catch_up => {
+                info!("Catching up on task {} ({}), due at {}", task.id, task.name, due.to_rfc3339());
+                Some(due)
+            }
+            Some(due) if due <= now => {
+                warn!("Skipping missed runs of task {} ({}) since {}", task.id, task.name, due.to_rfc3339());
+                self.following_run(task, due, now)
+            }
+            Some(due) => Some(due),
+            None => self.first_run(task, now),
+        }
+    }
+
+    /// Claims a run of a task about to start. Returns false if it must not
+    /// start now: it is still running and its concurrency skips or queues
+    /// the run.
+    fn begin_run(&self, task: &ScheduledTask, previous: Option<&serde_json::Value>) -> bool {
+        let mut runs = self.runs.lock();
+        let run = runs.entry(task.id.clone()).or_default();
+        if run.running > 0 {
+            match task.concurrency {
+                Concurrency::Skip => {
+                    warn!("Skipping run of task {} ({}): still running", task.id, task.name);
+                    return false;
+                }
+                Concurrency::Queue => {
+                    debug!("Queueing run of task {} behind the current one", task.id);
+                    run.queued = Some(previous.cloned());
+                    return false;
+                }
+                Concurrency::Parallel => {}
+            }
+        }
+        run.running += 1;
+        true
+    }
+
+    /// Releases a finished run of a task. Returns the queued run to start
+    /// in its place, if any, with the previous run it is chained after.
+    fn finish_run(&self, id: &str) -> Option<Option<serde_json::Value>> {
+        let mut runs = self.runs.lock();
+        let run = runs.get_mut(id)?;
+        run.running = run.running.saturating_sub(1);
+        if run.running > 0 {
+            return None;
+        }
+        match run.queued.take() {
+            Some(previous) => {
+                run.running = 1;
+                Some(previous)
+            }
+            None => {
+                runs.remove(id);
+                None
+            }
+        }
+    }
+
+    /// Checks that task `id` can follow task `previous` without forming a
+    /// loop of chained tasks.
+    fn validate_chain(&self, id: &str, previous: &str) -> Result<(), String> {
+        let tasks = self.tasks.read();
+        let mut current = previous;
+        let mut seen = HashSet::new();
+        loop {
+            if current == id {
+                return Err(format!("Task {} would end up following itself", id));
+            }
+            let task = tasks.get(current).ok_or_else(|| format!("Unknown task to follow: {}", current))?;
+            match &task.schedule {
+                Schedule::After { task, .. } if seen.insert(current) => current = task,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Enabled tasks chained after task `id` that a run with the given
+    /// outcome triggers.
+    pub fn chained_after(&self, id: &str, success: bool) -> Vec<ScheduledTask> {
+        self.tasks
+            .read()
+            .values()
+            .filter(|task| task.enabled)
+            .filter(|task| matches!(&task.schedule, Schedule::After { task, on } if task == id && on.matches(success)))
+            .cloned()
+            .collect()
+    }
+
+    /// The first run of a task created or enabled at `now`.
+    fn first_run(&self, task: &ScheduledTask, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &task.schedule {
+            Schedule::Cron(cron) => Self::next_cron_time(cron, now, self.task_timezone(task)),
+            Schedule::IntervalSecs(secs) => Some(now + Duration::seconds(*secs as i64)),
+            Schedule::At(at) => (*at > now).then_some(*at),
+            Schedule::After { .. } => None,
+        }
+    }
+
+    /// The run after the one due at `due`, skipping runs missed before `now`.
+    fn following_run(&self, task: &ScheduledTask, due: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &task.schedule {
+            Schedule::Cron(cron) => Self::next_cron_time(cron, now.max(due), self.task_timezone(task)),
+            // Keep to the original cadence rather than drifting by the loop's latency
+            Schedule::IntervalSecs(secs) => {
+                let interval = Duration::seconds((*secs).max(1) as i64);
+                let missed = (now - due).num_milliseconds().max(0) / interval.num_milliseconds();
+                Some(due + interval * (missed as i32 + 1))
+            }
+            Schedule::At(_) | Schedule::After { .. } => None,
+        }
+    }
+
+    /// Takes the tasks due at `now` and moves each to its following run.
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<ScheduledTask> {
+        let mut tasks = self.tasks.write();
+        let mut due = Vec::new();
+        for task in tasks.values_mut() {
+            let Some(next_run) = task.next_run.filter(|next_run| task.enabled && *next_run <= now) else {
+                continue;
+            };
+            task.next_run = self.following_run(task, next_run, now);
+            due.push(task.clone());
+        }
+        due
+    }
+
+    /// The earliest next run of any enabled task.
+    fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.tasks.read().values().filter(|task| task.enabled).filter_map(|task| task.next_run).min()
     }
 
     /// Validates a cron expression.
-    fn validate_cron(cron: &str) -> Result<(), String> {
+    pub(crate) fn validate_cron(cron: &str) -> Result<(), String> {
         let parts: Vec<&str> = cron.split_whitespace().collect();
         if parts.len() != 5 {
             return Err(format!(
@@ -112,18 +511,48 @@ impl Scheduler {
         Ok(())
     }
 
+    /// The first whole minute after `after` at which a cron expression
+    /// matches in `timezone`, if any within [`CRON_HORIZON_DAYS`].
+    pub(crate) fn next_cron_time(cron: &str, after: DateTime<Utc>, timezone: Tz) -> Option<DateTime<Utc>> {
+        let parts: Vec<&str> = cron.split_whitespace().collect();
+        if parts.len() != 5 {
+            return None;
+        }
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let horizon = start + Duration::days(CRON_HORIZON_DAYS);
+
+        let mut time = start;
+        while time < horizon {
+            let local = time.with_timezone(&timezone);
+            let hour_matches = Self::matches_cron_part(parts[1], local.hour())
+                && Self::matches_cron_part(parts[2], local.day())
+                && Self::matches_cron_part(parts[3], local.month())
+                && Self::matches_cron_part(parts[4], local.weekday().number_from_monday());
+            if !hour_matches {
+                // Nothing in this local hour can match
+                time += Duration::minutes(60 - local.minute() as i64);
+            } else if Self::matches_cron_part(parts[0], local.minute()) {
+                return Some(time);
+            } else {
+                time += Duration::minutes(1);
+            }
+        }
+        None
+    }
+
     /// Checks if a cron expression should trigger at the given time.
-    fn should_trigger(cron: &str, time: DateTime<Utc>) -> bool {
+    /// Fields are matched against the time's own timezone.
+    fn should_trigger<T: TimeZone>(cron: &str, time: DateTime<T>) -> bool {
         let parts: Vec<&str> = cron.split_whitespace().collect();
         if parts.len() != 5 {
             return false;
         }
 
-        let minute = time.format("%M").to_string().parse::<u32>().unwrap_or(0);
-        let hour = time.format("%H").to_string().parse::<u32>().unwrap_or(0);
-        let day = time.format("%d").to_string().parse::<u32>().unwrap_or(1);
-        let month = time.format("%m").to_string().parse::<u32>().unwrap_or(1);
-        let weekday = time.format("%u").to_string().parse::<u32>().unwrap_or(1); // 1-7
+        let minute = time.minute();
+        let hour = time.hour();
+        let day = time.day();
+        let month = time.month();
+        let weekday = time.weekday().number_from_monday(); // 1-7
 
         Self::matches_cron_part(parts[0], minute)
             && Self::matches_cron_part(parts[1], hour)
@@ -141,7 +570,7 @@ impl Scheduler {
         // Handle step values like */5
         if let Some(step) = part.strip_prefix("*/") {
             if let Ok(step_val) = step.parse::<u32>() {
-                return value % step_val == 0;
+                return value.is_multiple_of(step_val);
             }
         }
 
@@ -165,6 +594,9 @@ impl Scheduler {
     }
 
     /// Starts the scheduler loop.
+    ///
+    /// The loop sleeps until the next task is due, waking at least at every
+    /// minute boundary for heartbeat checks and maintenance.
     pub async fn start(&self, state: Arc<RuntimeState>) {
         if self
             .running
@@ -175,59 +607,37 @@ impl Scheduler {
         }
 
         info!("Starting scheduler");
+        self.restore(state.memory_store.as_ref()).await;
 
+        let mut next_minute = Utc::now();
         loop {
             if !self.running.load(std::sync::atomic::Ordering::SeqCst) {
                 break;
             }
 
             let now = Utc::now();
-            let tasks_to_run: Vec<ScheduledTask> = self
-                .tasks
-                .read()
-                .values()
-                .filter(|t| t.enabled && Self::should_trigger(&t.cron, now))
-                .cloned()
-                .collect();
-
-            for task in tasks_to_run {
-                let state_clone = state.clone();
-                let task_id = task.id.clone();
-
-                tokio::spawn(async move {
-                    let start = std::time::Instant::now();
-                    debug!("Executing scheduled task: {}", task_id);
-
-                    // Get the tool first, release the lock before await
-                    let tool = {
-                        let registry = state_clone.tool_registry.read();
-                        registry.get(&task.tool).cloned()
-                    };
-                    
-                    let result = match tool {
-                        Some(t) => t.execute(task.args.clone(), state_clone.clone()).await,
-                        None => Err(crate::tools::ToolError::NotFound(task.tool.clone())),
-                    };
-
-                    let duration = start.elapsed().as_millis() as u64;
-
-                    match result {
-                        Ok(output) => {
-                            info!(
-                                "Task {} completed successfully in {}ms",
-                                task_id, duration
-                            );
-                            debug!("Task output: {:?}", output);
-                        }
-                        Err(e) => {
-                            error!("Task {} failed: {}", task_id, e);
-                        }
-                    }
-                });
+            let due = self.take_due(now);
+            if !due.is_empty() {
+                if let Err(e) = self.persist(state.memory_store.as_ref()).await {
+                    warn!("Failed to save scheduled tasks: {}", e);
+                }
+            }
+            for task in due {
+                tokio::spawn(run_chain(state.clone(), task));
             }
 
-            // Check every minute
-            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            if now >= next_minute {
+                heartbeat::check_missed(&state, now).await;
+                maintenance::run_due(&state, now);
+                next_minute = now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now) + Duration::minutes(1);
+            }
+
+            let wake_at = self.next_due().map_or(next_minute, |due| due.min(next_minute));
+            let sleep = (wake_at - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(sleep) => {}
+                _ = self.wake.notified() => {}
+            }
         }
 
         info!("Scheduler stopped");
@@ -237,6 +647,7 @@ impl Scheduler {
     pub fn stop(&self) {
         self.running
             .store(false, std::sync::atomic::Ordering::SeqCst);
+        self.wake.notify_one();
     }
 }
 
@@ -246,6 +657,157 @@ impl Default for Scheduler {
     }
 }
 
+/// Parses a run time: RFC 3339, or a local date and time
+/// ("2024-07-01 09:00", "2024-07-01T09:00:30") in `timezone`.
+pub fn parse_run_time(value: &str, timezone: Tz) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .ok_or_else(|| format!("Invalid time '{}': use RFC 3339 or 'YYYY-MM-DD HH:MM'", value))?;
+    // A time skipped by a DST change doesn't exist; a repeated one runs the first time
+    timezone
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| format!("'{}' does not exist in {}", value, timezone.name()))
+}
+
+/// Runs a due task, then the tasks chained after it, each given the
+/// previous run's outcome.
+async fn run_chain(state: Arc<RuntimeState>, task: ScheduledTask) {
+    let mut queue = vec![(task, None)];
+    while let Some((task, mut previous)) = queue.pop() {
+        if !state.scheduler.begin_run(&task, previous.as_ref()) {
+            continue;
+        }
+        loop {
+            let result = run_task(&state, &task, previous.as_ref()).await;
+            let outcome = serde_json::json!({
+                "task_id": task.id,
+                "name": task.name,
+                "success": result.success,
+                "output": serde_json::from_str::<serde_json::Value>(&result.output)
+                    .unwrap_or_else(|_| serde_json::Value::String(result.output.clone()))
+            });
+            for next in state.scheduler.chained_after(&task.id, result.success) {
+                debug!("Task {} triggers chained task {}", task.id, next.id);
+                queue.push((next, Some(outcome.clone())));
+            }
+            if let Err(e) = state.memory_store.record_task_run(&history_entry(&task, &result), TASK_HISTORY_LIMIT).await {
+                warn!("Failed to record run of task {}: {}", task.id, e);
+            }
+            state.scheduler.record_result(&task.id, result);
+            if let Err(e) = state.scheduler.persist(state.memory_store.as_ref()).await {
+                warn!("Failed to save scheduled tasks: {}", e);
+            }
+
+            match state.scheduler.finish_run(&task.id) {
+                Some(queued) => previous = queued,
+                None => break,
+            }
+        }
+    }
+}
+
+/// The history entry of a run, with its output cut to an excerpt.
+fn history_entry(task: &ScheduledTask, result: &TaskResult) -> TaskRun {
+    let excerpt = match result.output.char_indices().nth(HISTORY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &result.output[..end]),
+        None => result.output.clone(),
+    };
+    TaskRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: task.id.clone(),
+        task_name: task.name.clone(),
+        success: result.success,
+        started_at: result.executed_at.clone(),
+        duration_ms: result.duration_ms,
+        output: result.success.then(|| excerpt.clone()),
+        error: (!result.success).then_some(excerpt),
+    }
+}
+
+/// Runs a task and returns its result. `previous` describes the run of the
+/// task it is chained after, substituted into its arguments.
+async fn run_task(state: &Arc<RuntimeState>, task: &ScheduledTask, previous: Option<&serde_json::Value>) -> TaskResult {
+    let start = std::time::Instant::now();
+    let executed_at = Utc::now().to_rfc3339();
+    let task_id = task.id.clone();
+    debug!("Executing scheduled task: {}", task_id);
+
+    let args = match previous {
+        Some(previous) => substitute_context(&task.args, &HashMap::from([("previous".to_string(), previous.clone())])),
+        None => task.args.clone(),
+    };
+
+    let resolved = state.tool_registry.read().resolve(&task.tool).to_string();
+    let context = if is_forbidden_action(&resolved) {
+        Err(format!("{} can't be run by a scheduled task", resolved))
+    } else {
+        match &task.owner {
+            Some(owner) => owner.context(state),
+            None => Ok(RequestContext::default()),
+        }
+    };
+
+    let result = match context {
+        // Runs go through the same checks as the owner's own calls
+        Ok(context) => {
+            let params = serde_json::json!({ "name": task.tool, "arguments": args });
+            handle_tools_call_with_context(Some(params), state.clone(), context)
+                .await
+                .map(|result| convert_result(&result))
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+        }
+        Err(e) => Err(ToolError::PermissionDenied(e)),
+    };
+    // Results end up in events, the run history and the dashboard
+    let result = if state.config.security.scrub_secrets {
+        scrub_result(result, &state.secrets.values())
+    } else {
+        result
+    };
+
+    let duration = start.elapsed().as_millis() as u64;
+
+    let (success, output) = match result {
+        Ok(output) => {
+            info!(
+                "Task {} completed successfully in {}ms",
+                task_id, duration
+            );
+            debug!("Task output: {:?}", output);
+            let kind = if output.is_error { "task.failed" } else { "task.succeeded" };
+            let key = if output.is_error { "error" } else { "output" };
+            state.events.publish(kind, &task_id, serde_json::json!({
+                "name": task.name,
+                "tool": task.tool,
+                key: output_value(&output)
+            }));
+            (!output.is_error, output_text(&output))
+        }
+        Err(e) => {
+            error!("Task {} failed: {}", task_id, e);
+            state.events.publish("task.failed", &task_id, serde_json::json!({
+                "name": task.name,
+                "tool": task.tool,
+                "error": e.to_string()
+            }));
+            (false, e.to_string())
+        }
+    };
+
+    TaskResult {
+        success,
+        output,
+        executed_at,
+        duration_ms: duration,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,6 +837,204 @@ mod tests {
         assert!(!Scheduler::matches_cron_part("1,3,5", 4));
     }
 
+    #[test]
+    fn test_should_trigger_in_timezone() {
+        // 08:00 UTC is 10:00 in Berlin during summer time
+        let utc = Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 0).unwrap();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+
+        assert!(Scheduler::should_trigger("0 8 * * *", utc));
+        assert!(!Scheduler::should_trigger("0 8 * * *", utc.with_timezone(&berlin)));
+        assert!(Scheduler::should_trigger("0 10 * * *", utc.with_timezone(&berlin)));
+    }
+
+    #[test]
+    fn test_next_cron_time() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 30).unwrap();
+
+        let next = |cron: &str, tz: Tz| Scheduler::next_cron_time(cron, after, tz);
+        assert_eq!(next("* * * * *", chrono_tz::UTC), Some(Utc.with_ymd_and_hms(2024, 7, 1, 8, 1, 0).unwrap()));
+        assert_eq!(next("*/15 * * * *", chrono_tz::UTC), Some(Utc.with_ymd_and_hms(2024, 7, 1, 8, 15, 0).unwrap()));
+        // 09:30 in Berlin is 07:30 UTC, already past today
+        assert_eq!(next("30 9 * * *", berlin), Some(Utc.with_ymd_and_hms(2024, 7, 2, 7, 30, 0).unwrap()));
+        assert_eq!(next("0 0 29 2 *", chrono_tz::UTC), Some(Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap()));
+        assert_eq!(next("0 0 31 2 *", chrono_tz::UTC), None);
+    }
+
+    fn task(schedule: Schedule) -> ScheduledTask {
+        ScheduledTask {
+            id: "t".to_string(),
+            name: "t".to_string(),
+            schedule,
+            tool: "echo".to_string(),
+            args: serde_json::json!({}),
+            enabled: true,
+            created_at: Utc::now().to_rfc3339(),
+            last_run: None,
+            last_result: None,
+            timezone: None,
+            next_run: None,
+            catch_up: false,
+            concurrency: Concurrency::default(),
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn test_interval_and_one_shot_runs() {
+        let scheduler = Scheduler::new();
+        scheduler.add_task(task(Schedule::IntervalSecs(90))).unwrap();
+        let first = scheduler.get_task("t").unwrap().next_run.unwrap();
+        assert!(scheduler.take_due(first - Duration::seconds(1)).is_empty());
+
+        // A late tick skips the missed runs but keeps the cadence
+        let late = first + Duration::seconds(200);
+        assert_eq!(scheduler.take_due(late).len(), 1);
+        assert_eq!(scheduler.get_task("t").unwrap().next_run, Some(first + Duration::seconds(270)));
+
+        let at = Utc::now() + Duration::hours(1);
+        scheduler.add_task(task(Schedule::At(at))).unwrap();
+        assert_eq!(scheduler.take_due(at).len(), 1);
+        assert_eq!(scheduler.get_task("t").unwrap().next_run, None);
+        assert!(scheduler.take_due(at + Duration::days(1)).is_empty());
+
+        assert!(scheduler.add_task(task(Schedule::At(Utc::now() - Duration::hours(1)))).is_err());
+        assert!(scheduler.add_task(task(Schedule::IntervalSecs(0))).is_err());
+        assert!(scheduler.add_task(task(Schedule::Cron("0 0 31 2 *".to_string()))).is_err());
+    }
+
+    #[test]
+    fn test_schedule_serialization() {
+        let value = serde_json::to_value(task(Schedule::Cron("0 * * * *".to_string()))).unwrap();
+        assert_eq!(value["cron"], "0 * * * *");
+        let parsed: ScheduledTask = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.schedule, Schedule::Cron("0 * * * *".to_string()));
+
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            parse_run_time("2024-07-01 09:00", berlin),
+            Ok(Utc.with_ymd_and_hms(2024, 7, 1, 7, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_run_time("2024-07-01T09:00:00Z", berlin),
+            Ok(Utc.with_ymd_and_hms(2024, 7, 1, 9, 0, 0).unwrap())
+        );
+        // Skipped by the switch to summer time
+        assert!(parse_run_time("2024-03-31 02:30", berlin).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chained_tasks() {
+        let state = Arc::new(RuntimeState::new(crate::core::Config {
+            database_path: Some(":memory:".into()),
+            ..Default::default()
+        }));
+        crate::tools::register_core_tools(&mut state.tool_registry.write(), &state.config);
+        let chained = |id: &str, after: &str, on: ChainCondition, key: &str| ScheduledTask {
+            id: id.to_string(),
+            name: id.to_string(),
+            tool: "memory.store".to_string(),
+            args: serde_json::json!({"key": key, "value": "{{previous.name}} stored {{previous.output.key}}"}),
+            ..task(Schedule::After { task: after.to_string(), on })
+        };
+
+        let first = ScheduledTask {
+            id: "first".to_string(),
+            name: "first".to_string(),
+            tool: "memory.store".to_string(),
+            args: serde_json::json!({"key": "a", "value": 1}),
+            ..task(Schedule::IntervalSecs(60))
+        };
+        state.scheduler.add_task(first.clone()).unwrap();
+        state.scheduler.add_task(chained("second", "first", ChainCondition::Success, "b")).unwrap();
+        state.scheduler.add_task(chained("third", "second", ChainCondition::Always, "c")).unwrap();
+        state.scheduler.add_task(chained("fallback", "first", ChainCondition::Failure, "d")).unwrap();
+        assert_eq!(state.scheduler.get_task("second").unwrap().next_run, None);
+        assert_eq!(state.scheduler.get_task("second").unwrap().schedule.to_string(), "after first succeeds");
+
+        run_chain(state.clone(), first).await;
+        let value = |key: &'static str| {
+            let state = state.clone();
+            async move { state.memory_store.kv_get(key).await.unwrap().map(|kv| kv.value) }
+        };
+        assert_eq!(value("b").await, Some(serde_json::json!("first stored a")));
+        assert_eq!(value("c").await, Some(serde_json::json!("second stored b")));
+        assert_eq!(value("d").await, None);
+        assert!(state.scheduler.get_task("third").unwrap().last_result.unwrap().success);
+        let history = state.memory_store.list_task_runs("second", 10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].output.as_deref().unwrap().contains("\"key\":\"b\""));
+
+        // Chains must lead back to a task that runs on its own
+        assert!(state.scheduler.add_task(chained("first", "third", ChainCondition::Success, "a")).is_err());
+        assert!(state.scheduler.add_task(chained("loop", "loop", ChainCondition::Success, "a")).is_err());
+        assert!(state.scheduler.add_task(chained("orphan", "missing", ChainCondition::Success, "a")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_skips_or_catches_up() {
+        let store = crate::memory::SqliteStore::in_memory().unwrap();
+        let due = Utc::now() - Duration::seconds(150);
+        let persisted = |id: &str, schedule: Schedule, catch_up: bool| ScheduledTask {
+            id: id.to_string(),
+            next_run: Some(due),
+            catch_up,
+            ..task(schedule)
+        };
+        let scheduler = Scheduler::new();
+        for task in [
+            persisted("skip", Schedule::IntervalSecs(60), false),
+            persisted("catch-up", Schedule::IntervalSecs(60), true),
+            persisted("once", Schedule::At(due), false),
+            persisted("once-late", Schedule::At(due), true),
+        ] {
+            scheduler.tasks.write().insert(task.id.clone(), task);
+        }
+        scheduler.persist(&store).await.unwrap();
+
+        let restored = Scheduler::new();
+        restored.restore(&store).await;
+        let next_run = |id: &str| restored.get_task(id).unwrap().next_run;
+        // The missed runs at due, +60s and +120s are skipped
+        assert_eq!(next_run("skip"), Some(due + Duration::seconds(180)));
+        assert_eq!(next_run("catch-up"), Some(due));
+        assert_eq!(next_run("once"), None);
+        assert_eq!(next_run("once-late"), Some(due));
+
+        let caught_up: Vec<_> = restored.take_due(Utc::now()).into_iter().map(|task| task.id).collect();
+        assert_eq!(caught_up.len(), 2);
+        assert_eq!(next_run("catch-up"), Some(due + Duration::seconds(180)));
+    }
+
+    #[test]
+    fn test_concurrency_policies() {
+        let scheduler = Scheduler::new();
+        let with = |concurrency: Concurrency| ScheduledTask { concurrency, ..task(Schedule::IntervalSecs(60)) };
+
+        let skip = with(Concurrency::Skip);
+        assert!(scheduler.begin_run(&skip, None));
+        assert!(!scheduler.begin_run(&skip, None));
+        assert_eq!(scheduler.finish_run("t"), None);
+        assert!(scheduler.begin_run(&skip, None));
+        assert_eq!(scheduler.finish_run("t"), None);
+
+        // Runs due meanwhile are merged into one, started when the current one ends
+        let queue = with(Concurrency::Queue);
+        assert!(scheduler.begin_run(&queue, None));
+        assert!(!scheduler.begin_run(&queue, Some(&serde_json::json!(1))));
+        assert!(!scheduler.begin_run(&queue, Some(&serde_json::json!(2))));
+        assert_eq!(scheduler.finish_run("t"), Some(Some(serde_json::json!(2))));
+        assert_eq!(scheduler.finish_run("t"), None);
+
+        let parallel = with(Concurrency::Parallel);
+        assert!(scheduler.begin_run(&parallel, None));
+        assert!(scheduler.begin_run(&parallel, None));
+        assert_eq!(scheduler.finish_run("t"), None);
+        assert_eq!(scheduler.finish_run("t"), None);
+        assert!(scheduler.runs.lock().is_empty());
+    }
+
     #[test]
     fn test_validate_cron() {
         assert!(Scheduler::validate_cron("* * * * *").is_ok());
@@ -282,5 +1042,54 @@ mod tests {
         assert!(Scheduler::validate_cron("*/5 * * * *").is_ok());
         assert!(Scheduler::validate_cron("bad").is_err());
     }
-}
 
+    #[tokio::test]
+    async fn test_created_tasks_run_as_their_creator() {
+        use crate::tools::caller;
+        use crate::tools::extras::SchedulerCreateTool;
+        use crate::tools::Tool;
+
+        let mut config = crate::core::Config {
+            database_path: Some(":memory:".to_string()),
+            ..Default::default()
+        };
+        config.auth.keys = vec![serde_json::from_value(serde_json::json!({
+            "name": "agent",
+            "key_hash": "aaaa",
+            "scopes": ["scheduler.*", "memory.*"]
+        }))
+        .unwrap()];
+        let state = Arc::new(RuntimeState::new(config));
+        crate::tools::register_core_tools(&mut state.tool_registry.write(), &state.config);
+        let identity = crate::transport::middleware::ApiKeys::from_config(&state.config.auth).authenticate("aaaa");
+        let create = |tool: &str| {
+            let args = serde_json::json!({"name": "t", "tool": tool, "args": {"key": "seen", "value": 1}, "interval_secs": 60});
+            caller::with_context(
+                RequestContext::new("s1".to_string()).with_identity(identity.clone()),
+                SchedulerCreateTool.execute(args, state.clone()),
+            )
+        };
+
+        // Only tools the key is scoped for can be scheduled
+        let err = create("cmd.exec").await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+        let err = create("approval.approve").await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+        create("memory.store").await.unwrap();
+        let mut owned = state.scheduler.list_tasks().pop().unwrap();
+        assert_eq!(owned.owner.as_ref().unwrap().api_key_hash.as_deref(), Some("aaaa"));
+
+        // Runs land in the key's namespace and are held to its scopes
+        assert!(run_task(&state, &owned, None).await.success);
+        assert!(state.memory_store.kv_get("ns:agent:seen").await.unwrap().is_some());
+        owned.tool = "echo".to_string();
+        let result = run_task(&state, &owned, None).await;
+        assert!(!result.success);
+        assert!(result.output.contains("not scoped"));
+
+        owned.owner.as_mut().unwrap().api_key_hash = Some("revoked".to_string());
+        owned.tool = "memory.store".to_string();
+        assert!(!run_task(&state, &owned, None).await.success);
+    }
+}
@@ -2,13 +2,27 @@
 //!
 //! Provides encrypted storage for API keys, tokens, and other secrets.
 //! Secrets can be referenced in tool configurations as `${secrets.KEY_NAME}`.
+//!
+//! Every change of a secret's value starts a new version; the previous
+//! [`SECRET_HISTORY_LIMIT`] values are kept so a bad rotation can be rolled
+//! back. Secrets may carry an expiry date, and those expiring within
+//! [`EXPIRY_WARNING_DAYS`] are reported (see [`SecretsManager::expiring`]).
+
+pub mod scrub;
 
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::Path;
 
+/// Previous values kept per secret.
+pub const SECRET_HISTORY_LIMIT: usize = 10;
+
+/// Days before its expiry that a secret is reported as expiring.
+pub const EXPIRY_WARNING_DAYS: i64 = 14;
+
 /// A secret value with metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Secret {
@@ -20,6 +34,78 @@ pub struct Secret {
     pub updated_at: String,
     /// Optional description.
     pub description: Option<String>,
+    /// Version of the current value, starting at 1.
+    #[serde(default = "first_version")]
+    pub version: u32,
+    /// When the secret should have been rotated by (RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Previous values, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<SecretVersion>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// A previous value of a secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretVersion {
+    pub version: u32,
+    /// The value (encrypted like [`Secret::value`]).
+    pub value: String,
+    /// When this version was set.
+    pub created_at: String,
+}
+
+/// A version of a secret, without its value.
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionInfo {
+    pub version: u32,
+    pub created_at: String,
+    /// Whether this is the value in use.
+    pub current: bool,
+}
+
+/// A secret that expires soon or has expired.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiryWarning {
+    pub key: String,
+    pub expires_at: String,
+    /// Whole days until expiry; negative once expired.
+    pub days_left: i64,
+    pub expired: bool,
+}
+
+/// The file secrets are kept in next to a database (`aegis.db` keeps them
+/// in `aegis.secrets`). In-memory databases keep secrets in memory too.
+pub fn file_for_database(database_path: Option<&str>) -> Option<String> {
+    crate::memory::companion_file(database_path, "secrets")
+}
+
+/// Parses an expiry: a date (`2025-06-30`), an RFC 3339 timestamp, or a
+/// period from `now` (`90d`, `12w`, `24h`).
+pub fn parse_expiry(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let invalid = || format!("invalid expiry '{}' (use e.g. 90d, 12w, or 2025-06-30)", value);
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let period = match unit {
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(now + period)
 }
 
 /// Secrets manager for storing and retrieving secrets.
@@ -60,18 +146,141 @@ impl SecretsManager {
         manager
     }
 
-    /// Sets a secret.
+    /// Sets a secret. Setting an existing secret starts a new version and
+    /// keeps its description unless a new one is given.
     pub fn set(&self, key: &str, value: &str, description: Option<&str>) {
-        let now = chrono::Utc::now().to_rfc3339();
-        let secret = Secret {
-            value: self.encrypt(value),
-            created_at: now.clone(),
-            updated_at: now,
-            description: description.map(|s| s.to_string()),
+        let now = Utc::now().to_rfc3339();
+        let mut secrets = self.secrets.write();
+        match secrets.get_mut(key) {
+            Some(secret) => {
+                self.change_value(secret, value, &now);
+                if let Some(description) = description {
+                    secret.description = Some(description.to_string());
+                }
+            }
+            None => {
+                secrets.insert(
+                    key.to_string(),
+                    Secret {
+                        value: self.encrypt(value),
+                        created_at: now.clone(),
+                        updated_at: now,
+                        description: description.map(|s| s.to_string()),
+                        version: first_version(),
+                        expires_at: None,
+                        history: Vec::new(),
+                    },
+                );
+            }
+        }
+        drop(secrets);
+        self.save();
+    }
+
+    /// Rotates an existing secret to a new value and replaces its expiry
+    /// (`None` clears it). Returns the new version, or `None` if there is
+    /// no such secret.
+    pub fn rotate(&self, key: &str, value: &str, expires_at: Option<DateTime<Utc>>) -> Option<u32> {
+        let now = Utc::now().to_rfc3339();
+        let version = {
+            let mut secrets = self.secrets.write();
+            let secret = secrets.get_mut(key)?;
+            self.change_value(secret, value, &now);
+            secret.expires_at = expires_at.map(|ts| ts.to_rfc3339());
+            secret.version
         };
+        self.save();
+        Some(version)
+    }
 
-        self.secrets.write().insert(key.to_string(), secret);
+    /// Restores a previous value (the one before the current, unless
+    /// `version` names another) as a new version, which is returned.
+    pub fn rollback(&self, key: &str, version: Option<u32>) -> Result<u32, String> {
+        let now = Utc::now().to_rfc3339();
+        let version = {
+            let mut secrets = self.secrets.write();
+            let secret = secrets.get_mut(key).ok_or_else(|| format!("Secret '{}' not found", key))?;
+            let previous = match version {
+                Some(version) => secret.history.iter().find(|previous| previous.version == version),
+                None => secret.history.last(),
+            };
+            let previous = previous.ok_or_else(|| match version {
+                Some(version) => format!("Secret '{}' has no previous version {}", key, version),
+                None => format!("Secret '{}' has no previous version", key),
+            })?;
+            let value = self.decrypt(&previous.value);
+            self.change_value(secret, &value, &now);
+            secret.version
+        };
         self.save();
+        Ok(version)
+    }
+
+    /// Sets or clears a secret's expiry. Returns whether the secret exists.
+    pub fn set_expiry(&self, key: &str, expires_at: Option<DateTime<Utc>>) -> bool {
+        let found = match self.secrets.write().get_mut(key) {
+            Some(secret) => {
+                secret.expires_at = expires_at.map(|ts| ts.to_rfc3339());
+                true
+            }
+            None => false,
+        };
+        if found {
+            self.save();
+        }
+        found
+    }
+
+    /// A secret's versions, newest (the current one) first.
+    pub fn versions(&self, key: &str) -> Option<Vec<VersionInfo>> {
+        let secrets = self.secrets.read();
+        let secret = secrets.get(key)?;
+        let current = VersionInfo {
+            version: secret.version,
+            created_at: secret.updated_at.clone(),
+            current: true,
+        };
+        let previous = secret.history.iter().rev().map(|previous| VersionInfo {
+            version: previous.version,
+            created_at: previous.created_at.clone(),
+            current: false,
+        });
+        Some(std::iter::once(current).chain(previous).collect())
+    }
+
+    /// Secrets expiring within [`EXPIRY_WARNING_DAYS`] of `now` or already
+    /// expired, soonest first.
+    pub fn expiring(&self, now: DateTime<Utc>) -> Vec<ExpiryWarning> {
+        let horizon = now + Duration::days(EXPIRY_WARNING_DAYS);
+        let mut warnings: Vec<_> = self
+            .secrets
+            .read()
+            .iter()
+            .filter_map(|(key, secret)| {
+                let expires_at = DateTime::parse_from_rfc3339(secret.expires_at.as_deref()?).ok()?.with_timezone(&Utc);
+                (expires_at <= horizon).then(|| ExpiryWarning {
+                    key: key.clone(),
+                    expires_at: expires_at.to_rfc3339(),
+                    days_left: (expires_at - now).num_days(),
+                    expired: expires_at <= now,
+                })
+            })
+            .collect();
+        warnings.sort_by(|a, b| a.expires_at.cmp(&b.expires_at));
+        warnings
+    }
+
+    /// Replaces a secret's value, keeping the current one in its history.
+    fn change_value(&self, secret: &mut Secret, value: &str, now: &str) {
+        secret.history.push(SecretVersion {
+            version: secret.version,
+            value: std::mem::replace(&mut secret.value, self.encrypt(value)),
+            created_at: std::mem::replace(&mut secret.updated_at, now.to_string()),
+        });
+        if secret.history.len() > SECRET_HISTORY_LIMIT {
+            secret.history.remove(0);
+        }
+        secret.version += 1;
     }
 
     /// Gets a secret value.
@@ -128,6 +337,20 @@ impl SecretsManager {
         result
     }
 
+    /// Names and values of the secrets worth masking in output, longest
+    /// value first so a secret containing another is masked whole.
+    pub fn values(&self) -> Vec<(String, String)> {
+        let mut values: Vec<_> = self
+            .secrets
+            .read()
+            .iter()
+            .map(|(key, secret)| (key.clone(), self.decrypt(&secret.value)))
+            .filter(|(_, value)| value.len() >= scrub::MIN_SECRET_LEN)
+            .collect();
+        values.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+        values
+    }
+
     /// Simple XOR encryption (for demo - use proper encryption in production).
     fn encrypt(&self, plaintext: &str) -> String {
         let encrypted: Vec<u8> = plaintext
@@ -202,6 +425,66 @@ mod tests {
         assert_eq!(result, "Bearer abc123");
     }
 
+    #[test]
+    fn test_rotate_and_rollback() {
+        let manager = SecretsManager::new(None, None);
+        manager.set("TOKEN", "first", Some("CI token"));
+        assert_eq!(manager.rotate("TOKEN", "second", None), Some(2));
+        manager.set("TOKEN", "third", None);
+        assert_eq!(manager.get("TOKEN"), Some("third".to_string()));
+        assert_eq!(manager.get_metadata("TOKEN").unwrap().2, Some("CI token".to_string()));
+
+        let versions = manager.versions("TOKEN").unwrap();
+        assert_eq!(versions.iter().map(|v| (v.version, v.current)).collect::<Vec<_>>(), [(3, true), (2, false), (1, false)]);
+
+        // Rolling back restores the previous value as a new version
+        assert_eq!(manager.rollback("TOKEN", None), Ok(4));
+        assert_eq!(manager.get("TOKEN"), Some("second".to_string()));
+        assert_eq!(manager.rollback("TOKEN", Some(1)), Ok(5));
+        assert_eq!(manager.get("TOKEN"), Some("first".to_string()));
+        assert!(manager.rollback("TOKEN", Some(9)).is_err());
+        assert_eq!(manager.rotate("MISSING", "x", None), None);
+
+        for i in 0..20 {
+            manager.set("TOKEN", &format!("value{}", i), None);
+        }
+        assert_eq!(manager.versions("TOKEN").unwrap().len(), SECRET_HISTORY_LIMIT + 1);
+    }
+
+    #[test]
+    fn test_expiring() {
+        let now = Utc::now();
+        let manager = SecretsManager::new(None, None);
+        manager.set("OLD", "v", None);
+        manager.set("SOON", "v", None);
+        manager.set("LATER", "v", None);
+        manager.set("NEVER", "v", None);
+        manager.set_expiry("OLD", Some(now - Duration::days(1)));
+        manager.set_expiry("SOON", Some(parse_expiry("3d", now).unwrap()));
+        manager.set_expiry("LATER", Some(parse_expiry("2030-01-01", now).unwrap().max(now + Duration::days(90))));
+
+        let warnings = manager.expiring(now);
+        assert_eq!(warnings.iter().map(|w| (w.key.as_str(), w.expired)).collect::<Vec<_>>(), [("OLD", true), ("SOON", false)]);
+        assert_eq!(warnings[1].days_left, 3);
+
+        // Rotating replaces the expiry
+        manager.rotate("OLD", "new", Some(now + Duration::days(90)));
+        assert_eq!(manager.expiring(now).len(), 1);
+        assert!(parse_expiry("soon", now).is_err());
+    }
+
+    #[test]
+    fn test_file_for_database() {
+        assert_eq!(file_for_database(Some("data/aegis.db")).as_deref(), Some("data/aegis.secrets"));
+        assert_eq!(file_for_database(Some("aegis.sqlite")).as_deref(), Some("aegis.secrets"));
+        assert_eq!(file_for_database(Some("my.db.d/store")).as_deref(), Some("my.db.d/store.secrets"));
+        assert_eq!(file_for_database(Some("x.secrets")).as_deref(), Some("x.secrets.secrets"));
+        for in_memory in [":memory:", "", "file:shared?mode=memory&cache=shared"] {
+            assert_eq!(file_for_database(Some(in_memory)), None, "{}", in_memory);
+        }
+        assert_eq!(file_for_database(None), None);
+    }
+
     #[test]
     fn test_list() {
         let manager = SecretsManager::new(None, None);
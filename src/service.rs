@@ -0,0 +1,262 @@
+//! Running Aegis as a system service.
+//!
+//! `aegis service install` registers the running binary as a daemon that
+//! starts `aegis serve` at boot and restarts it when it fails:
+//!
+//! - on Linux, a systemd unit in `/etc/systemd/system`, enabled and started
+//!   with `systemctl`
+//! - on Windows, a [WinSW](https://github.com/winsw/winsw) wrapper
+//!   configuration next to the binary, registered with the wrapper
+//!   (`aegis-service.exe`, the WinSW executable renamed), since Aegis
+//!   doesn't speak the service control protocol itself
+//!
+//! The service runs in the directory it was installed from, so relative
+//! paths in the configuration (the database, plugins) keep working, and
+//! with the config file given to the install command.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Directory systemd units are installed to.
+pub const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Seconds a failed service waits before it is restarted.
+const RESTART_DELAY_SECS: u32 = 5;
+
+/// Errors from managing the service.
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("{command} failed: {message}")]
+    Command { command: String, message: String },
+
+    #[error("Invalid service name '{0}' (use letters, digits, '-', '_' and '.')")]
+    InvalidName(String),
+
+    #[error("Services are only supported on Linux (systemd) and Windows")]
+    Unsupported,
+
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What a service runs, and as whom.
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    /// Service name.
+    pub name: String,
+    /// The aegis binary.
+    pub binary: PathBuf,
+    /// Config file passed with `--config`.
+    pub config: Option<PathBuf>,
+    /// Directory the service runs in.
+    pub working_dir: PathBuf,
+    /// Account the service runs as; root (Linux) or LocalSystem (Windows)
+    /// when unset.
+    pub user: Option<String>,
+}
+
+impl ServiceSpec {
+    /// Arguments the service starts aegis with.
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = Vec::new();
+        if let Some(config) = &self.config {
+            arguments.push("--config".to_string());
+            arguments.push(config.display().to_string());
+        }
+        arguments.push("serve".to_string());
+        arguments
+    }
+
+    /// The systemd unit for the service.
+    pub fn systemd_unit(&self) -> String {
+        let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%"));
+        let command: Vec<String> = std::iter::once(self.binary.display().to_string())
+            .chain(self.arguments())
+            .map(|part| quote(&part))
+            .collect();
+        let mut unit = format!(
+            "[Unit]\n\
+             Description=Aegis MCP tool server\n\
+             After=network-online.target\n\
+             Wants=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={}\n\
+             WorkingDirectory={}\n\
+             Restart=on-failure\n\
+             RestartSec={}\n\
+             KillSignal=SIGTERM\n",
+            command.join(" "),
+            // Taken as is, without quotes, but with specifiers expanded
+            self.working_dir.display().to_string().replace('%', "%%"),
+            RESTART_DELAY_SECS,
+        );
+        if let Some(user) = &self.user {
+            unit.push_str(&format!("User={}\n", user));
+        }
+        unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+        unit
+    }
+
+    /// The WinSW configuration for the service.
+    pub fn winsw_config(&self) -> String {
+        let arguments: Vec<String> = self
+            .arguments()
+            .iter()
+            .map(|argument| if argument.contains(' ') { format!("\"{}\"", argument) } else { argument.clone() })
+            .collect();
+        let account = match &self.user {
+            Some(user) => format!(
+                "  <serviceaccount>\n    <username>{}</username>\n    <prompt>console</prompt>\n    <allowservicelogon>true</allowservicelogon>\n  </serviceaccount>\n",
+                xml_escape(user)
+            ),
+            None => String::new(),
+        };
+        format!(
+            "<service>\n\
+             \x20 <id>{name}</id>\n\
+             \x20 <name>{name}</name>\n\
+             \x20 <description>Aegis MCP tool server</description>\n\
+             \x20 <executable>{binary}</executable>\n\
+             \x20 <arguments>{arguments}</arguments>\n\
+             \x20 <workingdirectory>{dir}</workingdirectory>\n\
+             \x20 <startmode>Automatic</startmode>\n\
+             \x20 <onfailure action=\"restart\" delay=\"{delay} sec\"/>\n\
+             \x20 <stoptimeout>60 sec</stoptimeout>\n\
+             \x20 <log mode=\"roll\"/>\n\
+             {account}</service>\n",
+            name = xml_escape(&self.name),
+            binary = xml_escape(&self.binary.display().to_string()),
+            arguments = xml_escape(&arguments.join(" ")),
+            dir = xml_escape(&self.working_dir.display().to_string()),
+            delay = RESTART_DELAY_SECS,
+            account = account,
+        )
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Path of the systemd unit of a service.
+pub fn unit_path(name: &str) -> PathBuf {
+    Path::new(SYSTEMD_UNIT_DIR).join(format!("{}.service", name))
+}
+
+/// The WinSW wrapper executable and its configuration, next to `binary`.
+pub fn winsw_paths(binary: &Path) -> (PathBuf, PathBuf) {
+    (binary.with_file_name("aegis-service.exe"), binary.with_file_name("aegis-service.xml"))
+}
+
+/// Runs a command and returns its output, failing if it fails.
+fn run(program: impl AsRef<std::ffi::OsStr>, args: &[&str]) -> Result<String, ServiceError> {
+    let program = program.as_ref();
+    let command = format!("{} {}", program.to_string_lossy(), args.join(" "));
+    let output = Command::new(program).args(args).output().map_err(|e| ServiceError::Command {
+        command: command.clone(),
+        message: e.to_string(),
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ServiceError::Command { command, message: stderr });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Installs, enables and starts the service. Returns the file written.
+pub fn install(spec: &ServiceSpec) -> Result<PathBuf, ServiceError> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if spec.name.is_empty() || spec.name.starts_with('.') || !spec.name.chars().all(valid) {
+        return Err(ServiceError::InvalidName(spec.name.clone()));
+    }
+    if cfg!(target_os = "linux") {
+        let path = unit_path(&spec.name);
+        std::fs::write(&path, spec.systemd_unit())?;
+        run("systemctl", &["daemon-reload"])?;
+        run("systemctl", &["enable", "--now", &format!("{}.service", spec.name)])?;
+        Ok(path)
+    } else if cfg!(windows) {
+        let (wrapper, path) = winsw_paths(&spec.binary);
+        if !wrapper.exists() {
+            return Err(ServiceError::Command {
+                command: wrapper.display().to_string(),
+                message: "not found; download WinSW and save it under this name".to_string(),
+            });
+        }
+        std::fs::write(&path, spec.winsw_config())?;
+        run(&wrapper, &["install"])?;
+        run(&wrapper, &["start"])?;
+        Ok(path)
+    } else {
+        Err(ServiceError::Unsupported)
+    }
+}
+
+/// Stops and removes the service. `binary` locates the WinSW wrapper.
+pub fn uninstall(name: &str, binary: &Path) -> Result<(), ServiceError> {
+    if cfg!(target_os = "linux") {
+        run("systemctl", &["disable", "--now", &format!("{}.service", name)])?;
+        std::fs::remove_file(unit_path(name))?;
+        run("systemctl", &["daemon-reload"])?;
+        Ok(())
+    } else if cfg!(windows) {
+        let (wrapper, path) = winsw_paths(binary);
+        // Stopping fails for a service that isn't running
+        let _ = run(&wrapper, &["stop"]);
+        run(&wrapper, &["uninstall"])?;
+        std::fs::remove_file(path)?;
+        Ok(())
+    } else {
+        Err(ServiceError::Unsupported)
+    }
+}
+
+/// The state of the service, as reported by the service manager.
+pub fn status(name: &str) -> Result<String, ServiceError> {
+    if cfg!(target_os = "linux") {
+        let unit = format!("{}.service", name);
+        run("systemctl", &["show", &unit, "--no-pager", "--property=LoadState,UnitFileState,ActiveState,SubState,MainPID"])
+    } else if cfg!(windows) {
+        run("sc.exe", &["query", name])
+    } else {
+        Err(ServiceError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_files() {
+        let spec = ServiceSpec {
+            name: "aegis".to_string(),
+            binary: PathBuf::from("/opt/aegis/bin/aegis"),
+            config: Some(PathBuf::from("/etc/aegis/my aegis.toml")),
+            working_dir: PathBuf::from("/var/lib/aegis"),
+            user: Some("aegis".to_string()),
+        };
+        let unit = spec.systemd_unit();
+        assert!(unit.contains("ExecStart=\"/opt/aegis/bin/aegis\" \"--config\" \"/etc/aegis/my aegis.toml\" \"serve\"\n"));
+        assert!(unit.contains("WorkingDirectory=/var/lib/aegis\n"));
+        assert!(unit.contains("Restart=on-failure\n") && unit.contains("User=aegis\n"));
+        assert!(unit.ends_with("WantedBy=multi-user.target\n"));
+
+        let config = spec.winsw_config();
+        assert!(config.contains("<arguments>--config &quot;/etc/aegis/my aegis.toml&quot; serve</arguments>"));
+        assert!(config.contains("<onfailure action=\"restart\" delay=\"5 sec\"/>"));
+        assert!(config.contains("<username>aegis</username>"));
+
+        let system = ServiceSpec { user: None, config: None, ..spec };
+        assert!(!system.systemd_unit().contains("User="));
+        assert!(!system.winsw_config().contains("serviceaccount"));
+        assert!(system.systemd_unit().contains("ExecStart=\"/opt/aegis/bin/aegis\" \"serve\"\n"));
+    }
+}
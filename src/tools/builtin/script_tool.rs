@@ -89,6 +89,7 @@ impl Tool for ScriptTool {
             name: self.config.name.clone(),
             description: self.config.description.clone(),
             input_schema: self.config.input_schema.clone(),
+            output_schema: self.config.output_schema.clone(),
         }
     }
 
@@ -182,9 +183,11 @@ impl Tool for ScriptTool {
             "json" => {
                 // Validate it's valid JSON
                 match serde_json::from_str::<Value>(&stdout) {
-                    Ok(json) => Ok(ToolOutput::text(
-                        serde_json::to_string_pretty(&json).unwrap_or(stdout),
-                    )),
+                    Ok(json) => Ok(ToolOutput {
+                        content: ToolOutput::text(serde_json::to_string_pretty(&json).unwrap_or(stdout)).content,
+                        structured_content: Some(json),
+                        is_error: false,
+                    }),
                     Err(_) => Ok(ToolOutput::text(stdout.trim())),
                 }
             }
@@ -211,6 +214,7 @@ mod tests {
             input_schema: json!({ "type": "object" }),
             input_mode: String::new(),
             output_mode: String::new(),
+            output_schema: None,
         };
 
         let tool = ScriptTool::new(config);
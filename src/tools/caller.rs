@@ -0,0 +1,99 @@
+//! The caller of the current tool call.
+//!
+//! `tools/call` runs each tool with the request's [`RequestContext`]
+//! installed via [`with_context`], so tools can scope what they do to the
+//! connection that called them (e.g. memory namespaces).
+
+use std::future::Future;
+
+use std::sync::Arc;
+
+use crate::core::{RequestContext, Session};
+
+tokio::task_local! {
+    static CONTEXT: RequestContext;
+}
+
+/// Runs `fut` with `context` as the current call's caller.
+pub async fn with_context<F: Future>(context: RequestContext, fut: F) -> F::Output {
+    CONTEXT.scope(context, fut).await
+}
+
+/// The current call's caller, if the call came from a client.
+pub fn current() -> Option<RequestContext> {
+    CONTEXT.try_with(|context| context.clone()).ok()
+}
+
+/// The current call's session, if the call came from a client.
+pub fn session() -> Option<Arc<Session>> {
+    CONTEXT.try_with(|context| context.session.clone()).ok().flatten()
+}
+
+/// The caller's default memory namespace: the name of the API key it
+/// authenticated with, otherwise the namespace its session asked for at
+/// initialize.
+pub fn namespace() -> Option<String> {
+    CONTEXT
+        .try_with(|context| {
+            context
+                .key_name()
+                .map(|name| name.to_string())
+                .or_else(|| context.session.as_ref().and_then(|session| session.namespace()))
+        })
+        .ok()
+        .flatten()
+}
+
+/// The namespace of the caller's API key. A caller that authenticated
+/// with a key works in that namespace only.
+pub fn key_namespace() -> Option<String> {
+    CONTEXT.try_with(|context| context.key_name().map(str::to_string)).ok().flatten()
+}
+
+/// The namespace a call works in: the caller's key namespace, which an
+/// explicit `namespace` may name but not leave, otherwise `explicit`.
+pub fn scoped_namespace(explicit: Option<String>) -> Result<Option<String>, String> {
+    match (key_namespace(), explicit) {
+        (Some(own), Some(other)) if other != own => {
+            Err(format!("Namespace '{}' belongs to another API key", other))
+        }
+        (Some(own), _) => Ok(Some(own)),
+        (None, explicit) => Ok(explicit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::KeyIdentity;
+
+    #[tokio::test]
+    async fn test_namespace_follows_api_key() {
+        assert!(current().is_none());
+        assert_eq!(namespace(), None);
+
+        with_context(RequestContext::new("s1"), async {
+            assert_eq!(current().unwrap().session_id, "s1");
+            assert_eq!(namespace(), None);
+        })
+        .await;
+
+        let identity = Arc::new(KeyIdentity {
+            name: "agent-a".to_string(),
+            key_hash: "hash".to_string(),
+            scopes: vec!["*".to_string()],
+        });
+        let context = RequestContext::new("s1").with_identity(Some(identity));
+        with_context(context, async {
+            assert_eq!(namespace().as_deref(), Some("agent-a"));
+        })
+        .await;
+
+        let session = Arc::new(Session::new("s2"));
+        session.set_namespace(Some("team-b".to_string()));
+        with_context(RequestContext::new("s2").with_session(session), async {
+            assert_eq!(namespace().as_deref(), Some("team-b"));
+        })
+        .await;
+    }
+}
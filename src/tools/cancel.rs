@@ -0,0 +1,59 @@
+//! Cancellation of in-flight tool calls.
+//!
+//! The router runs each `tools/call` with a [`CancellationToken`] installed
+//! via [`with_token`] and stops waiting on (drops) the call when a client's
+//! `notifications/cancelled` fires the token. Dropping aborts whatever the
+//! tool is awaiting; tools that hold work outside their future (threads,
+//! detached tasks) or want to stop at a clean point check [`is_cancelled`]
+//! or await [`cancelled`].
+
+use std::future::Future;
+use tokio_util::sync::CancellationToken;
+
+tokio::task_local! {
+    static TOKEN: CancellationToken;
+}
+
+/// Runs `fut` with `token` as the current call's cancellation token.
+pub async fn with_token<F: Future>(token: CancellationToken, fut: F) -> F::Output {
+    TOKEN.scope(token, fut).await
+}
+
+/// The current call's cancellation token, if it can be cancelled.
+pub fn current() -> Option<CancellationToken> {
+    TOKEN.try_with(|token| token.clone()).ok()
+}
+
+/// Whether the client has cancelled the current call.
+pub fn is_cancelled() -> bool {
+    TOKEN.try_with(|token| token.is_cancelled()).unwrap_or(false)
+}
+
+/// Resolves when the current call is cancelled; never, if it can't be.
+pub async fn cancelled() {
+    match current() {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_is_scoped_to_call() {
+        assert!(current().is_none());
+        assert!(!is_cancelled());
+
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        with_token(token, async {
+            assert!(!is_cancelled());
+            trigger.cancel();
+            assert!(is_cancelled());
+            cancelled().await;
+        })
+        .await;
+    }
+}
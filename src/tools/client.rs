@@ -0,0 +1,254 @@
+//! Requests from the server to the connected client.
+//!
+//! MCP lets a server ask its client for things, most notably
+//! `sampling/createMessage` (generate text with the client's model). A
+//! transport creates a [`ClientPeer`] per connection, attaches the channel
+//! it writes outgoing messages from, routes the client's responses to
+//! [`ClientPeer::handle_response`], and installs the peer for the duration
+//! of each call with [`with_peer`]. Tools then use [`current`] or
+//! [`create_message`].
+
+use parking_lot::{Mutex, RwLock};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+
+use crate::protocol::ClientCapabilities;
+
+/// Default time to wait for the client to answer a request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+tokio::task_local! {
+    static PEER: Arc<ClientPeer>;
+}
+
+/// Errors from a request to the client.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The call is not running on behalf of a connected client.
+    #[error("no client connection to send the request to")]
+    NotConnected,
+
+    /// The client did not declare the capability at initialize.
+    #[error("client does not support {0}")]
+    Unsupported(String),
+
+    /// The client answered with a JSON-RPC error.
+    #[error("client returned error {code}: {message}")]
+    Rejected { code: i64, message: String },
+
+    /// The client did not answer in time.
+    #[error("client did not respond within {0}s")]
+    Timeout(u64),
+
+    /// The connection closed before the client answered.
+    #[error("client connection closed")]
+    Closed,
+}
+
+type Pending = oneshot::Sender<Result<Value, ClientError>>;
+
+/// One client connection that server-initiated requests can be sent over.
+#[derive(Debug, Default)]
+pub struct ClientPeer {
+    outbound: Mutex<Option<UnboundedSender<Value>>>,
+    pending: Mutex<HashMap<String, Pending>>,
+    next_id: AtomicU64,
+    capabilities: RwLock<ClientCapabilities>,
+}
+
+impl ClientPeer {
+    /// Creates a peer with no outgoing channel attached yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches a new outgoing channel, replacing any previous one, and
+    /// returns the receiver the transport writes messages from.
+    pub fn attach(&self) -> UnboundedReceiver<Value> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.outbound.lock() = Some(tx);
+        rx
+    }
+
+    /// Whether an outgoing channel is attached and still open.
+    pub fn is_connected(&self) -> bool {
+        self.outbound.lock().as_ref().is_some_and(|tx| !tx.is_closed())
+    }
+
+    /// Records the capabilities the client declared at initialize.
+    pub fn set_capabilities(&self, capabilities: ClientCapabilities) {
+        *self.capabilities.write() = capabilities;
+    }
+
+    /// The capabilities the client declared at initialize.
+    pub fn capabilities(&self) -> ClientCapabilities {
+        self.capabilities.read().clone()
+    }
+
+    /// Whether the client declared the sampling capability.
+    pub fn supports_sampling(&self) -> bool {
+        self.capabilities.read().sampling.is_some()
+    }
+
+    /// Whether the client declared the roots capability.
+    pub fn supports_roots(&self) -> bool {
+        self.capabilities.read().roots.is_some()
+    }
+
+    /// Sends a request to the client and waits for its result.
+    pub async fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value, ClientError> {
+        let id = format!("aegis-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(id.clone(), tx);
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        let sent = self
+            .outbound
+            .lock()
+            .as_ref()
+            .is_some_and(|outbound| outbound.send(message).is_ok());
+        if !sent {
+            self.pending.lock().remove(&id);
+            return Err(ClientError::NotConnected);
+        }
+
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(ClientError::Closed),
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                Err(ClientError::Timeout(timeout.as_secs()))
+            }
+        }
+    }
+
+    /// Routes a response from the client to the request waiting for it.
+    /// Returns `false` if no request is waiting for the message's id.
+    pub fn handle_response(&self, message: &Value) -> bool {
+        let id = match message.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => return false,
+        };
+        let Some(waiter) = self.pending.lock().remove(&id) else {
+            return false;
+        };
+
+        let result = match message.get("error") {
+            Some(error) => Err(ClientError::Rejected {
+                code: error.get("code").and_then(|c| c.as_i64()).unwrap_or(0),
+                message: error.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+            }),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        let _ = waiter.send(result);
+        true
+    }
+
+    /// Fails all outstanding requests, e.g. when the connection closes.
+    pub fn close(&self) {
+        self.outbound.lock().take();
+        for (_, waiter) in self.pending.lock().drain() {
+            let _ = waiter.send(Err(ClientError::Closed));
+        }
+    }
+}
+
+/// Whether a JSON-RPC message is a response (to a server-initiated request)
+/// rather than a request or notification.
+pub fn is_response(message: &Value) -> bool {
+    message.get("method").is_none()
+        && message.get("id").is_some()
+        && (message.get("result").is_some() || message.get("error").is_some())
+}
+
+/// Runs `fut` with requests made inside it sent to `peer`.
+pub async fn with_peer<F: Future>(peer: Arc<ClientPeer>, fut: F) -> F::Output {
+    PEER.scope(peer, fut).await
+}
+
+/// The client the current call is running on behalf of, if any.
+pub fn current() -> Option<Arc<ClientPeer>> {
+    PEER.try_with(|peer| peer.clone()).ok()
+}
+
+/// Whether the current call's client can sample (generate text) for us.
+pub fn can_sample() -> bool {
+    current().is_some_and(|peer| peer.supports_sampling() && peer.is_connected())
+}
+
+/// Asks the current call's client to generate a message with its model
+/// (`sampling/createMessage`).
+pub async fn create_message(params: Value, timeout: Duration) -> Result<Value, ClientError> {
+    let peer = current().ok_or(ClientError::NotConnected)?;
+    if !peer.supports_sampling() {
+        return Err(ClientError::Unsupported("sampling".to_string()));
+    }
+    peer.request("sampling/createMessage", params, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_is_correlated_with_response() {
+        let peer = Arc::new(ClientPeer::new());
+        let mut outbound = peer.attach();
+
+        let client = peer.clone();
+        tokio::spawn(async move {
+            let request = outbound.recv().await.unwrap();
+            assert_eq!(request["method"], "ping");
+            assert!(!client.handle_response(&json!({"id": "other", "result": {}})));
+            assert!(client.handle_response(&json!({"jsonrpc": "2.0", "id": request["id"], "result": {"ok": true}})));
+
+            let request = outbound.recv().await.unwrap();
+            client.handle_response(&json!({"id": request["id"], "error": {"code": -1, "message": "User rejected"}}));
+        });
+
+        let result = peer.request("ping", json!({}), DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(result, json!({"ok": true}));
+
+        let err = peer.request("ping", json!({}), DEFAULT_TIMEOUT).await.unwrap_err();
+        assert!(matches!(err, ClientError::Rejected { code: -1, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_create_message_requires_peer_and_capability() {
+        assert!(matches!(create_message(json!({}), DEFAULT_TIMEOUT).await, Err(ClientError::NotConnected)));
+
+        let peer = Arc::new(ClientPeer::new());
+        let _outbound = peer.attach();
+        with_peer(peer.clone(), async {
+            assert!(!can_sample());
+            let err = create_message(json!({}), DEFAULT_TIMEOUT).await.unwrap_err();
+            assert!(matches!(err, ClientError::Unsupported(_)));
+
+            peer.set_capabilities(ClientCapabilities { sampling: Some(json!({})), ..Default::default() });
+            assert!(can_sample());
+            let err = create_message(json!({}), Duration::from_millis(10)).await.unwrap_err();
+            assert!(matches!(err, ClientError::Timeout(_)));
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_is_response() {
+        assert!(is_response(&json!({"jsonrpc": "2.0", "id": 1, "result": {}})));
+        assert!(is_response(&json!({"jsonrpc": "2.0", "id": "a", "error": {"code": 1, "message": "x"}})));
+        assert!(!is_response(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"})));
+        assert!(!is_response(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"})));
+    }
+}
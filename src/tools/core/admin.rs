@@ -0,0 +1,212 @@
+//! Admin tools: runtime management of the server itself.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::core::logs::{self, LogQuery, LOG_BUFFER_CAPACITY};
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+// ============================================================================
+// Admin Tools Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminToolsAction {
+    List,
+    Enable,
+    Disable,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AdminToolsArgs {
+    /// What to do: list all tools with their state, or enable/disable one
+    action: AdminToolsAction,
+    /// Tool to enable or disable
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct AdminToolsTool;
+
+#[async_trait]
+impl TypedTool for AdminToolsTool {
+    type Args = AdminToolsArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "admin.tools";
+    const DESCRIPTION: &'static str =
+        "Lists tools with their enabled state, or enables/disables a tool at runtime without a restart. Changes persist and connected clients are told to refresh their tool list.";
+
+    async fn run(&self, args: AdminToolsArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let enabled = match args.action {
+            AdminToolsAction::List => {
+                let registry = state.tool_registry.read();
+                let tools: Vec<_> = registry
+                    .names()
+                    .into_iter()
+                    .map(|name| json!({ "name": name, "enabled": !registry.is_disabled(&name) }))
+                    .collect();
+                return Ok(ToolOutput::structured(json!({ "tools": tools })));
+            }
+            AdminToolsAction::Enable => true,
+            AdminToolsAction::Disable => false,
+        };
+
+        let name = args
+            .name
+            .ok_or_else(|| ToolError::InvalidInput("'name' is required to enable or disable a tool".to_string()))?;
+        if !state.tool_registry.read().tools.contains_key(&name) {
+            return Err(ToolError::NotFound(name));
+        }
+        if name == Self::NAME && !enabled {
+            return Err(ToolError::InvalidInput(format!("{} can't disable itself", Self::NAME)));
+        }
+
+        let changed = state
+            .set_tool_enabled(&name, enabled)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to persist tool state: {}", e)))?;
+        Ok(ToolOutput::structured(json!({
+            "name": name,
+            "enabled": enabled,
+            "changed": changed
+        })))
+    }
+}
+
+// ============================================================================
+// Logs Tail Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LogsTailArgs {
+    /// Number of most recent entries to return (default 100)
+    #[serde(default = "default_tail_lines")]
+    lines: usize,
+    /// Least severe level to include: error, warn, info, debug or trace
+    #[serde(default)]
+    level: Option<String>,
+    /// Case-insensitive text the message, module or fields must contain
+    #[serde(default)]
+    filter: Option<String>,
+    /// Only entries after this sequence number, to poll for new ones
+    #[serde(default)]
+    after: Option<u64>,
+}
+
+fn default_tail_lines() -> usize { 100 }
+
+#[derive(Debug)]
+pub struct LogsTailTool;
+
+#[async_trait]
+impl TypedTool for LogsTailTool {
+    type Args = LogsTailArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "logs.tail";
+    const DESCRIPTION: &'static str =
+        "Returns recent server log entries, optionally filtered by level and text. Pass the returned 'last_seq' as 'after' to fetch only newer entries.";
+
+    async fn run(&self, args: LogsTailArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let level = match args.level {
+            Some(level) => Some(
+                level
+                    .parse()
+                    .map_err(|_| ToolError::InvalidInput(format!("Unknown log level: {}", level)))?,
+            ),
+            None => None,
+        };
+        let entries = logs::buffer().query(&LogQuery {
+            level,
+            filter: args.filter,
+            after: args.after,
+            limit: args.lines.min(LOG_BUFFER_CAPACITY),
+        });
+        let last_seq = entries.last().map(|entry| entry.seq).or(args.after);
+        Ok(ToolOutput::structured(json!({
+            "entries": entries,
+            "count": entries.len(),
+            "last_seq": last_seq
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::Tool;
+
+    #[tokio::test]
+    async fn test_disable_and_enable_tool() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let mut changes = state.subscribe_tools_changed();
+
+        let output = AdminToolsTool
+            .execute(json!({"action": "disable", "name": "echo"}), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(output.structured_content.unwrap()["changed"], true);
+        assert!(changes.has_changed().unwrap());
+        changes.mark_unchanged();
+
+        {
+            let registry = state.tool_registry.read();
+            assert!(registry.get("echo").is_none());
+            assert!(!registry.list_definitions().iter().any(|def| def.name == "echo"));
+        }
+        let listed = AdminToolsTool.execute(json!({"action": "list"}), state.clone()).await.unwrap();
+        let tools = listed.structured_content.unwrap()["tools"].clone();
+        assert!(tools.as_array().unwrap().contains(&json!({"name": "echo", "enabled": false})));
+
+        // The choice survives a restart on the same store
+        state.tool_registry.write().enable("echo");
+        state.restore_disabled_tools().await;
+        assert!(state.tool_registry.read().is_disabled("echo"));
+
+        let output = AdminToolsTool
+            .execute(json!({"action": "enable", "name": "echo"}), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(output.structured_content.unwrap()["changed"], true);
+        assert!(changes.has_changed().unwrap());
+        assert!(state.tool_registry.read().get("echo").is_some());
+
+        let err = AdminToolsTool
+            .execute(json!({"action": "disable", "name": "admin.tools"}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+        let err = AdminToolsTool
+            .execute(json!({"action": "disable", "name": "nope"}), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_logs_tail_arguments() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        let output = LogsTailTool
+            .execute(json!({"level": "warn", "lines": 5, "after": 7}), state.clone())
+            .await
+            .unwrap();
+        let result = output.structured_content.unwrap();
+        assert!(result["entries"].is_array());
+        assert!(result["count"].as_u64().unwrap() <= 5);
+        assert!(result["last_seq"].as_u64().unwrap() >= 7);
+
+        let err = LogsTailTool.execute(json!({"level": "loud"}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+    }
+}
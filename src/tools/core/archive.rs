@@ -0,0 +1,725 @@
+//! Archive tools - create, list and extract zip and tar(.gz) archives.
+//!
+//! Sources and archives to read must be inside `allowed_read_paths`;
+//! archives and extraction destinations must be inside
+//! `allowed_write_paths`. Both directions are capped by
+//! `max_archive_entries` and `max_archive_bytes` (uncompressed), and
+//! extraction checks the whole archive before writing anything, so a
+//! zip bomb or an entry escaping the destination fails up front.
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::core::config::SecurityConfig;
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+use super::fs_read::is_path_allowed;
+use super::fs_write::is_write_allowed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar")]
+    Tar,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Infers the format from a file name.
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Infers the format from the first bytes of a file.
+    fn sniff(path: &Path) -> Option<Self> {
+        let mut magic = [0u8; 512];
+        let read = File::open(path).and_then(|mut f| f.read(&mut magic)).ok()?;
+        match &magic[..read] {
+            [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(Self::Zip),
+            [0x1f, 0x8b, ..] => Some(Self::TarGz),
+            m if m.len() >= 262 && &m[257..262] == b"ustar" => Some(Self::Tar),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// Size caps applied to an archive's uncompressed contents.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    max_entries: usize,
+    max_bytes: u64,
+}
+
+impl Limits {
+    fn from_config(security: &SecurityConfig) -> Self {
+        Self { max_entries: security.max_archive_entries, max_bytes: security.max_archive_bytes }
+    }
+
+    fn check(&self, entries: usize, bytes: u64) -> Result<(), ToolError> {
+        if entries > self.max_entries {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Archive has more than {} entries",
+                self.max_entries
+            )));
+        }
+        if bytes > self.max_bytes {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Archive contents exceed {} bytes",
+                self.max_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// An entry of an archive.
+#[derive(Debug, Serialize)]
+struct Entry {
+    path: String,
+    size: u64,
+    /// "file", "dir" or "link" (links are listed but never extracted).
+    kind: &'static str,
+}
+
+fn io_error(context: &str) -> impl Fn(io::Error) -> ToolError + '_ {
+    move |e| ToolError::ExecutionFailed(format!("{}: {}", context, e))
+}
+
+fn zip_error(e: zip::result::ZipError) -> ToolError {
+    ToolError::ExecutionFailed(format!("Invalid zip archive: {}", e))
+}
+
+/// Validates an entry name: relative, and no `..` or root components.
+fn safe_entry_path(name: &Path) -> Result<PathBuf, ToolError> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Refusing archive entry outside the destination: {}",
+                    name.display()
+                )))
+            }
+        }
+    }
+    Ok(path)
+}
+
+// ============================================================================
+// Creating
+// ============================================================================
+
+/// Starts an archive from files and directories.
+#[derive(Debug)]
+pub struct FsArchiveTool {
+    read_paths: Vec<PathBuf>,
+    write_paths: Vec<PathBuf>,
+    limits: Limits,
+}
+
+#[derive(Deserialize)]
+struct FsArchiveArgs {
+    sources: Vec<String>,
+    output: String,
+    #[serde(default)]
+    format: Option<ArchiveFormat>,
+}
+
+impl FsArchiveTool {
+    /// Creates the tool with the filesystem allowlists and archive limits.
+    pub fn new(security: &SecurityConfig) -> Self {
+        Self {
+            read_paths: security.allowed_read_paths.clone(),
+            write_paths: security.allowed_write_paths.clone(),
+            limits: Limits::from_config(security),
+        }
+    }
+}
+
+/// A file or directory to add, with its name in the archive.
+struct Source {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+    size: u64,
+}
+
+/// Expands sources into the files and directories to add. Each source is
+/// stored under its own name, e.g. `dist/` as `dist/index.html`. Symlinks
+/// are skipped so a link can't pull in files outside the allowlist.
+fn collect_sources(sources: &[PathBuf], limits: Limits) -> Result<Vec<Source>, ToolError> {
+    fn walk(path: &Path, name: String, out: &mut Vec<Source>, limits: Limits, bytes: &mut u64) -> Result<(), ToolError> {
+        let metadata = std::fs::symlink_metadata(path).map_err(io_error("Failed to read source"))?;
+        if metadata.file_type().is_symlink() {
+            return Ok(());
+        }
+        let is_dir = metadata.is_dir();
+        let size = if is_dir { 0 } else { metadata.len() };
+        *bytes += size;
+        out.push(Source { path: path.to_path_buf(), name: name.clone(), is_dir, size });
+        limits.check(out.len(), *bytes)?;
+
+        if is_dir {
+            let mut children: Vec<_> = std::fs::read_dir(path)
+                .map_err(io_error("Failed to read directory"))?
+                .filter_map(Result::ok)
+                .collect();
+            children.sort_by_key(|child| child.file_name());
+            for child in children {
+                let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+                walk(&child.path(), child_name, out, limits, bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    let mut bytes = 0;
+    for source in sources {
+        let canonical = source.canonicalize().map_err(io_error("Failed to read source"))?;
+        let name = canonical
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| ToolError::InvalidInput(format!("Cannot archive {}", source.display())))?;
+        walk(&canonical, name, &mut out, limits, &mut bytes)?;
+    }
+    Ok(out)
+}
+
+fn write_zip(output: &Path, sources: &[Source]) -> Result<(), ToolError> {
+    let file = File::create(output).map_err(io_error("Failed to create archive"))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    for source in sources {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(source.size >= u32::MAX as u64);
+        if source.is_dir {
+            zip.add_directory(source.name.as_str(), options).map_err(zip_error)?;
+        } else {
+            zip.start_file(source.name.as_str(), options).map_err(zip_error)?;
+            let mut input = File::open(&source.path).map_err(io_error("Failed to read source"))?;
+            io::copy(&mut input, &mut zip).map_err(io_error("Failed to write archive"))?;
+        }
+    }
+    zip.finish().map_err(zip_error)?.flush().map_err(io_error("Failed to write archive"))
+}
+
+fn write_tar<W: Write>(writer: W, sources: &[Source]) -> Result<W, ToolError> {
+    let mut tar = tar::Builder::new(writer);
+    tar.follow_symlinks(false);
+    for source in sources {
+        if source.is_dir {
+            tar.append_dir(&source.name, &source.path)
+        } else {
+            tar.append_path_with_name(&source.path, &source.name)
+        }
+        .map_err(io_error("Failed to write archive"))?;
+    }
+    tar.into_inner().map_err(io_error("Failed to write archive"))
+}
+
+fn create_archive(output: &Path, format: ArchiveFormat, sources: &[Source]) -> Result<(), ToolError> {
+    match format {
+        ArchiveFormat::Zip => write_zip(output, sources),
+        ArchiveFormat::Tar => {
+            let file = File::create(output).map_err(io_error("Failed to create archive"))?;
+            write_tar(BufWriter::new(file), sources)?.flush().map_err(io_error("Failed to write archive"))
+        }
+        ArchiveFormat::TarGz => {
+            let file = File::create(output).map_err(io_error("Failed to create archive"))?;
+            let gz = GzEncoder::new(BufWriter::new(file), Compression::default());
+            write_tar(gz, sources)?
+                .finish()
+                .and_then(|mut file| file.flush())
+                .map_err(io_error("Failed to write archive"))
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FsArchiveTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fs.archive".to_string(),
+            description: Some("Creates a zip, tar or tar.gz archive from files and directories. Directories are added recursively under their own name; symlinks are skipped.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "sources": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Files and directories to add"
+                    },
+                    "output": {
+                        "type": "string",
+                        "description": "Archive file to create (overwritten if it exists)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["zip", "tar", "tar.gz"],
+                        "description": "Archive format (default: from the output extension)"
+                    }
+                },
+                "required": ["sources", "output"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: FsArchiveArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+
+        if args.sources.is_empty() {
+            return Err(ToolError::InvalidInput("No sources given".to_string()));
+        }
+        let output = PathBuf::from(&args.output);
+        let format = args.format.or_else(|| ArchiveFormat::from_path(&output)).ok_or_else(|| {
+            ToolError::InvalidInput("Cannot tell the format from the output name; pass 'format'".to_string())
+        })?;
+
+        let sources: Vec<PathBuf> = args.sources.iter().map(PathBuf::from).collect();
+        for (source, name) in sources.iter().zip(&args.sources) {
+            if !is_path_allowed(&self.read_paths, source) {
+                return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", name)));
+            }
+        }
+        if !is_write_allowed(&self.write_paths, &output) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Path not in allowed directories: {}",
+                args.output
+            )));
+        }
+
+        let limits = self.limits;
+        tokio::task::spawn_blocking(move || {
+            let sources = collect_sources(&sources, limits)?;
+            create_archive(&output, format, &sources)?;
+            let size = std::fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+            Ok(ToolOutput::structured(json!({
+                "output": output.to_string_lossy(),
+                "format": format.as_str(),
+                "files": sources.iter().filter(|s| !s.is_dir).count(),
+                "directories": sources.iter().filter(|s| s.is_dir).count(),
+                "bytes": sources.iter().map(|s| s.size).sum::<u64>(),
+                "size": size,
+            })))
+        })
+        .await
+        .map_err(|e| ToolError::Internal(e.to_string()))?
+    }
+}
+
+// ============================================================================
+// Listing and extracting
+// ============================================================================
+
+/// Lists or extracts an archive.
+#[derive(Debug)]
+pub struct FsUnarchiveTool {
+    read_paths: Vec<PathBuf>,
+    write_paths: Vec<PathBuf>,
+    limits: Limits,
+}
+
+#[derive(Deserialize)]
+struct FsUnarchiveArgs {
+    path: String,
+    #[serde(default)]
+    destination: Option<String>,
+    #[serde(default)]
+    list: bool,
+    #[serde(default)]
+    format: Option<ArchiveFormat>,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+impl FsUnarchiveTool {
+    /// Creates the tool with the filesystem allowlists and archive limits.
+    pub fn new(security: &SecurityConfig) -> Self {
+        Self {
+            read_paths: security.allowed_read_paths.clone(),
+            write_paths: security.allowed_write_paths.clone(),
+            limits: Limits::from_config(security),
+        }
+    }
+}
+
+fn open_tar(path: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, ToolError> {
+    let file = BufReader::new(File::open(path).map_err(io_error("Failed to open archive"))?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn tar_kind(entry_type: tar::EntryType) -> &'static str {
+    match entry_type {
+        tar::EntryType::Regular | tar::EntryType::Continuous => "file",
+        tar::EntryType::Directory => "dir",
+        _ => "link",
+    }
+}
+
+/// Reads the table of contents, validating names and limits.
+fn list_entries(path: &Path, format: ArchiveFormat, limits: Limits) -> Result<Vec<Entry>, ToolError> {
+    let mut entries = Vec::new();
+    let mut bytes = 0;
+    let mut push = |entry: Entry| {
+        bytes += entry.size;
+        entries.push(entry);
+        limits.check(entries.len(), bytes)
+    };
+
+    match format {
+        ArchiveFormat::Zip => {
+            let file = File::open(path).map_err(io_error("Failed to open archive"))?;
+            let mut zip = ZipArchive::new(BufReader::new(file)).map_err(zip_error)?;
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i).map_err(zip_error)?;
+                let name = safe_entry_path(Path::new(file.name()))?;
+                let kind = if file.is_dir() { "dir" } else if file.is_symlink() { "link" } else { "file" };
+                push(Entry { path: name.to_string_lossy().into_owned(), size: file.size(), kind })?;
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries().map_err(io_error("Invalid tar archive"))? {
+                let entry = entry.map_err(io_error("Invalid tar archive"))?;
+                let name = safe_entry_path(&entry.path().map_err(io_error("Invalid tar archive"))?)?;
+                let kind = tar_kind(entry.header().entry_type());
+                push(Entry { path: name.to_string_lossy().into_owned(), size: entry.size(), kind })?;
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Copies at most `remaining` bytes, failing if the entry holds more than
+/// its header claimed.
+fn copy_limited(reader: &mut impl Read, target: &Path, remaining: &mut u64) -> Result<(), ToolError> {
+    let mut out = BufWriter::new(File::create(target).map_err(io_error("Failed to write file"))?);
+    let copied = io::copy(&mut reader.take(*remaining + 1), &mut out).map_err(io_error("Failed to extract"))?;
+    if copied > *remaining {
+        return Err(ToolError::ExecutionFailed(
+            "Archive contents exceed the size limit (entry larger than declared)".to_string(),
+        ));
+    }
+    *remaining -= copied;
+    out.flush().map_err(io_error("Failed to write file"))
+}
+
+/// Returns where an entry goes below `destination`, creating the
+/// directories leading to it. Symlinks already in the destination are
+/// never followed, so an entry can't be written outside it through one.
+fn entry_target(destination: &Path, name: &Path) -> Result<PathBuf, ToolError> {
+    let relative = safe_entry_path(name)?;
+    let mut target = destination.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        target.push(component);
+        match std::fs::symlink_metadata(&target) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Refusing archive entry through a symlink in the destination: {}",
+                    name.display()
+                )))
+            }
+            Ok(_) => {}
+            Err(_) if components.peek().is_some() => {
+                std::fs::create_dir(&target).map_err(io_error("Failed to create directory"))?;
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(target)
+}
+
+/// Extracts files and directories (never links) below `destination`.
+fn extract(path: &Path, format: ArchiveFormat, destination: &Path, limits: Limits) -> Result<usize, ToolError> {
+    let mut remaining = limits.max_bytes;
+    let mut extracted = 0;
+    let mut prepare = |name: &Path| -> Result<PathBuf, ToolError> {
+        let target = entry_target(destination, name)?;
+        extracted += 1;
+        Ok(target)
+    };
+
+    match format {
+        ArchiveFormat::Zip => {
+            let file = File::open(path).map_err(io_error("Failed to open archive"))?;
+            let mut zip = ZipArchive::new(BufReader::new(file)).map_err(zip_error)?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i).map_err(zip_error)?;
+                if file.is_symlink() {
+                    continue;
+                }
+                let target = prepare(Path::new(file.name()))?;
+                if file.is_dir() {
+                    std::fs::create_dir_all(&target).map_err(io_error("Failed to create directory"))?;
+                } else {
+                    copy_limited(&mut file, &target, &mut remaining)?;
+                }
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries().map_err(io_error("Invalid tar archive"))? {
+                let mut entry = entry.map_err(io_error("Invalid tar archive"))?;
+                let kind = tar_kind(entry.header().entry_type());
+                if kind == "link" {
+                    continue;
+                }
+                let target = prepare(&entry.path().map_err(io_error("Invalid tar archive"))?)?;
+                if kind == "dir" {
+                    std::fs::create_dir_all(&target).map_err(io_error("Failed to create directory"))?;
+                } else {
+                    copy_limited(&mut entry, &target, &mut remaining)?;
+                }
+            }
+        }
+    }
+    Ok(extracted)
+}
+
+#[async_trait]
+impl Tool for FsUnarchiveTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fs.unarchive".to_string(),
+            description: Some("Extracts a zip, tar or tar.gz archive into a directory, or lists its entries with list: true. Entries that would land outside the destination are refused; symlinks are not extracted.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Archive to read"
+                    },
+                    "destination": {
+                        "type": "string",
+                        "description": "Directory to extract into (created if missing; required unless list is true)"
+                    },
+                    "list": {
+                        "type": "boolean",
+                        "description": "Only list the entries (default: false)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["zip", "tar", "tar.gz"],
+                        "description": "Archive format (default: from the file name or contents)"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace existing files (default: false, fail if any exist)"
+                    }
+                },
+                "required": ["path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: FsUnarchiveArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+
+        let path = PathBuf::from(&args.path);
+        if !is_path_allowed(&self.read_paths, &path) {
+            return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", args.path)));
+        }
+        let destination = match (&args.destination, args.list) {
+            (_, true) => None,
+            (Some(destination), false) => {
+                let destination = PathBuf::from(destination);
+                if !is_write_allowed(&self.write_paths, &destination) {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "Path not in allowed directories: {}",
+                        destination.display()
+                    )));
+                }
+                Some(destination)
+            }
+            (None, false) => {
+                return Err(ToolError::InvalidInput("'destination' is required unless list is true".to_string()))
+            }
+        };
+
+        let limits = self.limits;
+        let overwrite = args.overwrite;
+        tokio::task::spawn_blocking(move || {
+            let format = args
+                .format
+                .or_else(|| ArchiveFormat::from_path(&path))
+                .or_else(|| ArchiveFormat::sniff(&path))
+                .ok_or_else(|| ToolError::InvalidInput("Unrecognized archive format; pass 'format'".to_string()))?;
+            let entries = list_entries(&path, format, limits)?;
+            let files = entries.iter().filter(|e| e.kind == "file").count();
+            let bytes: u64 = entries.iter().map(|e| e.size).sum();
+
+            let Some(destination) = destination else {
+                return Ok(ToolOutput::structured(json!({
+                    "format": format.as_str(),
+                    "entries": entries,
+                    "files": files,
+                    "bytes": bytes,
+                })));
+            };
+
+            if !overwrite {
+                if let Some(existing) = entries
+                    .iter()
+                    .filter(|e| e.kind == "file")
+                    .find(|e| destination.join(&e.path).exists())
+                {
+                    return Err(ToolError::ExecutionFailed(format!(
+                        "{} already exists (pass overwrite: true to replace)",
+                        destination.join(&existing.path).display()
+                    )));
+                }
+            }
+
+            std::fs::create_dir_all(&destination).map_err(io_error("Failed to create destination"))?;
+            let extracted = extract(&path, format, &destination, limits)?;
+            Ok(ToolOutput::structured(json!({
+                "destination": destination.to_string_lossy(),
+                "format": format.as_str(),
+                "extracted": extracted,
+                "skipped_links": entries.iter().filter(|e| e.kind == "link").count(),
+                "files": files,
+                "bytes": bytes,
+            })))
+        })
+        .await
+        .map_err(|e| ToolError::Internal(e.to_string()))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn tools(dir: &Path, limits: Limits) -> (FsArchiveTool, FsUnarchiveTool) {
+        let paths = vec![dir.to_path_buf()];
+        let archive = FsArchiveTool { read_paths: paths.clone(), write_paths: paths.clone(), limits };
+        let unarchive = FsUnarchiveTool { read_paths: paths.clone(), write_paths: paths, limits };
+        (archive, unarchive)
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".into()), ..Config::default() }));
+        let (archive, unarchive) = tools(dir.path(), Limits { max_entries: 100, max_bytes: 1 << 20 });
+
+        std::fs::create_dir_all(dir.path().join("dist/assets")).unwrap();
+        std::fs::write(dir.path().join("dist/index.html"), "<h1>hi</h1>").unwrap();
+        std::fs::write(dir.path().join("dist/assets/app.js"), "console.log(1)").unwrap();
+
+        for name in ["out.zip", "out.tar.gz"] {
+            let output = dir.path().join(name);
+            let sources = json!([dir.path().join("dist")]);
+            let result = archive
+                .execute(json!({ "sources": sources, "output": output }), state.clone())
+                .await
+                .unwrap();
+            assert_eq!(result.structured_content.as_ref().unwrap()["files"], 2);
+
+            let listed = unarchive.execute(json!({ "path": output, "list": true }), state.clone()).await.unwrap();
+            let listed = listed.structured_content.unwrap();
+            assert!(listed["entries"].as_array().unwrap().iter().any(|e| e["path"] == "dist/assets/app.js"));
+
+            let destination = dir.path().join(format!("unpacked-{}", name));
+            unarchive
+                .execute(json!({ "path": output, "destination": destination }), state.clone())
+                .await
+                .unwrap();
+            let js = std::fs::read_to_string(destination.join("dist/assets/app.js")).unwrap();
+            assert_eq!(js, "console.log(1)");
+
+            // Extracting again would overwrite files
+            let again = unarchive.execute(json!({ "path": output, "destination": destination }), state.clone()).await;
+            assert!(again.is_err());
+        }
+
+        // Over the limits
+        let (archive, _) = tools(dir.path(), Limits { max_entries: 100, max_bytes: 10 });
+        let output = dir.path().join("big.zip");
+        let result = archive.execute(json!({ "sources": [dir.path().join("dist")], "output": output }), state).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rejects_escaping_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("evil.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("../evil.txt", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"pwned").unwrap();
+        zip.finish().unwrap();
+
+        let limits = Limits { max_entries: 10, max_bytes: 1024 };
+        assert!(list_entries(&path, ArchiveFormat::Zip, limits).is_err());
+        assert!(safe_entry_path(Path::new("/etc/passwd")).is_err());
+        assert_eq!(safe_entry_path(Path::new("./a/b")).unwrap(), PathBuf::from("a/b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_does_not_follow_symlinks_in_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        let destination = dir.path().join("dest");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::create_dir_all(&destination).unwrap();
+        std::os::unix::fs::symlink(&outside, destination.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("file.txt"), destination.join("file.txt")).unwrap();
+
+        let limits = Limits { max_entries: 10, max_bytes: 1024 };
+        for name in ["link/evil.txt", "link/sub/evil.txt", "file.txt"] {
+            let path = dir.path().join("planted.zip");
+            let mut zip = ZipWriter::new(File::create(&path).unwrap());
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"pwned").unwrap();
+            zip.finish().unwrap();
+
+            assert!(extract(&path, ArchiveFormat::Zip, &destination, limits).is_err(), "{}", name);
+        }
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+    }
+}
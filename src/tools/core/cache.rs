@@ -0,0 +1,64 @@
+//! HTTP response cache tools.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+// ============================================================================
+// Cache Clear Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CacheClearArgs {
+    /// Only remove responses for URLs starting with this prefix (e.g. 'https://api.github.com/'); default: everything
+    #[serde(default)]
+    url_prefix: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct CacheClearTool;
+
+#[async_trait]
+impl TypedTool for CacheClearTool {
+    type Args = CacheClearArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "cache.clear";
+    const DESCRIPTION: &'static str =
+        "Removes cached HTTP responses used by http.request, web.extract and web.search, so the next call fetches fresh data.";
+
+    async fn run(&self, args: CacheClearArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let removed = state.http_cache.clear(args.url_prefix.as_deref());
+        Ok(ToolOutput::structured(json!({ "removed": removed })))
+    }
+}
+
+// ============================================================================
+// Cache Stats Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CacheStatsArgs {}
+
+#[derive(Debug)]
+pub struct CacheStatsTool;
+
+#[async_trait]
+impl TypedTool for CacheStatsTool {
+    type Args = CacheStatsArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "cache.stats";
+    const DESCRIPTION: &'static str =
+        "Shows the HTTP response cache: stored entries and size, and hits, misses and revalidations since startup.";
+
+    async fn run(&self, _args: CacheStatsArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let mut stats = json!(state.http_cache.stats());
+        stats["enabled"] = json!(state.config.http_client.cache.enabled);
+        Ok(ToolOutput::structured(stats))
+    }
+}
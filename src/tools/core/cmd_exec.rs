@@ -3,17 +3,24 @@
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, trace};
 
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::{Tool, ToolError, ToolOutput, ToolContent, ProcessManager};
+use crate::tools::process_manager::DEFAULT_MAX_OUTPUT_BYTES;
+use crate::tools::{stream, OutputStream, Tool, ToolError, ToolOutput, ProcessManager, ProcessRequest};
+
+use super::env::matches_any;
 
 /// Command execution tool - runs allowed commands.
 #[derive(Debug)]
 pub struct CmdExecTool {
     allowed_commands: Vec<String>,
+    allowed_working_dirs: Vec<PathBuf>,
+    max_output_bytes: usize,
 }
 
 #[derive(Deserialize)]
@@ -23,6 +30,12 @@ struct CmdExecArgs {
     args: Vec<String>,
     #[serde(default = "default_timeout")]
     timeout_secs: u64,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    stdin: Option<String>,
 }
 
 fn default_timeout() -> u64 {
@@ -32,31 +45,93 @@ fn default_timeout() -> u64 {
 impl CmdExecTool {
     /// Creates a new CmdExecTool with the given allowed commands.
     pub fn new(allowed_commands: Vec<String>) -> Self {
-        Self { allowed_commands }
+        Self {
+            allowed_commands,
+            allowed_working_dirs: vec![PathBuf::from(".")],
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
     }
 
-    /// Checks if a command is in the allowed list.
-    fn is_command_allowed(&self, command: &str) -> bool {
-        if self.allowed_commands.is_empty() {
-            return false;
-        }
+    /// Sets the directories commands may run in.
+    pub fn with_working_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.allowed_working_dirs = dirs;
+        self
+    }
 
-        // Check exact match or wildcard
-        for allowed in &self.allowed_commands {
-            if allowed == "*" || allowed == command {
+    /// Caps the stdout/stderr bytes kept per stream.
+    pub fn with_max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = max;
+        self
+    }
+}
+
+/// Checks if a command is in the allowed list.
+pub(crate) fn is_command_allowed(allowed_commands: &[String], command: &str) -> bool {
+    if allowed_commands.is_empty() {
+        return false;
+    }
+
+    // Check exact match or wildcard
+    for allowed in allowed_commands {
+        if allowed == "*" || allowed == command {
+            return true;
+        }
+        // Support prefix matching (e.g., "git*" matches "git", "git-log")
+        if allowed.ends_with('*') {
+            let prefix = &allowed[..allowed.len() - 1];
+            if command.starts_with(prefix) {
                 return true;
             }
-            // Support prefix matching (e.g., "git*" matches "git", "git-log")
-            if allowed.ends_with('*') {
-                let prefix = &allowed[..allowed.len() - 1];
-                if command.starts_with(prefix) {
-                    return true;
-                }
-            }
         }
+    }
+
+    false
+}
+
+/// Resolves a working directory, which must lie within an allowed one.
+pub(crate) fn resolve_working_dir(allowed_dirs: &[PathBuf], cwd: &str) -> Result<PathBuf, ToolError> {
+    let canonical = Path::new(cwd)
+        .canonicalize()
+        .map_err(|e| ToolError::InvalidInput(format!("Invalid working directory '{}': {}", cwd, e)))?;
+
+    if !canonical.is_dir() {
+        return Err(ToolError::InvalidInput(format!("Not a directory: {}", cwd)));
+    }
 
-        false
+    let allowed = allowed_dirs.iter().any(|dir| {
+        dir.canonicalize()
+            .map(|dir| canonical.starts_with(dir))
+            .unwrap_or(false)
+    });
+    if !allowed {
+        return Err(ToolError::PermissionDenied(format!(
+            "Working directory not in allowed list: {}",
+            cwd
+        )));
     }
+
+    Ok(canonical)
+}
+
+/// Validates extra environment variables and substitutes secrets in their
+/// values. Variables in `security.denied_command_env` are refused.
+pub(crate) fn resolve_env(env: HashMap<String, String>, state: &RuntimeState) -> Result<Vec<(String, String)>, ToolError> {
+    if let Some(name) = env.keys().find(|k| k.is_empty() || k.contains(['=', '\0'])) {
+        return Err(ToolError::InvalidInput(format!("Invalid environment variable name: {:?}", name)));
+    }
+    // Windows treats names case-insensitively
+    let denied = &state.config.security.denied_command_env;
+    if let Some(name) = env.keys().find(|k| matches_any(denied, k) || matches_any(denied, &k.to_ascii_uppercase())) {
+        return Err(ToolError::PermissionDenied(format!(
+            "Environment variable {} may not be set (security.denied_command_env)",
+            name
+        )));
+    }
+    // Substitute secrets only after validation, and never log the values
+    Ok(env
+        .into_iter()
+        .map(|(name, value)| (name, state.secrets.substitute(&value)))
+        .collect())
 }
 
 #[async_trait]
@@ -64,7 +139,11 @@ impl Tool for CmdExecTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "cmd.exec".to_string(),
-            description: Some("Executes a shell command. Only allowed commands can be run.".to_string()),
+            description: Some(
+                "Executes a shell command. Only allowed commands can be run. Output is streamed as it is produced \
+                 when the client asks for progress."
+                    .to_string(),
+            ),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -82,17 +161,41 @@ impl Tool for CmdExecTool {
                         "type": "integer",
                         "description": "Timeout in seconds (default: 30, max: 300)",
                         "default": 30
+                    },
+                    "cwd": {
+                        "type": "string",
+                        "description": "Working directory (must be within an allowed directory)"
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Extra environment variables; values may reference ${secrets.NAME}. PATH, LD_* and other variables that change what gets loaded are refused"
+                    },
+                    "stdin": {
+                        "type": "string",
+                        "description": "Input written to the command's stdin"
                     }
                 },
                 "required": ["command"]
             }),
+            output_schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "exit_code": {"type": "integer", "description": "Exit code; -1 if killed by a signal"},
+                    "success": {"type": "boolean"},
+                    "stdout": {"type": "string"},
+                    "stderr": {"type": "string"},
+                    "truncated": {"type": "boolean", "description": "Whether output was cut at the size limit"}
+                },
+                "required": ["exit_code", "success", "stdout", "stderr", "truncated"]
+            })),
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let args: CmdExecArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
@@ -100,13 +203,20 @@ impl Tool for CmdExecTool {
         debug!("Executing command: {} {:?}", args.command, args.args);
 
         // Check if command is allowed
-        if !self.is_command_allowed(&args.command) {
+        if !is_command_allowed(&self.allowed_commands, &args.command) {
             return Err(ToolError::PermissionDenied(format!(
                 "Command not in allowed list: {}",
                 args.command
             )));
         }
 
+        let cwd = args
+            .cwd
+            .as_deref()
+            .map(|cwd| resolve_working_dir(&self.allowed_working_dirs, cwd))
+            .transpose()?;
+        let env = resolve_env(args.env, &state)?;
+
         // Limit timeout to 5 minutes max
         let timeout_secs = args.timeout_secs.min(300);
         let pm = ProcessManager::with_timeout(timeout_secs);
@@ -114,25 +224,131 @@ impl Tool for CmdExecTool {
         // Convert args to &str slice
         let arg_refs: Vec<&str> = args.args.iter().map(|s| s.as_str()).collect();
 
-        // Execute the command
-        let output = pm.execute(&args.command, &arg_refs).await?;
+        let mut request = ProcessRequest::new(&args.command, &arg_refs)
+            .envs(env)
+            .max_output_bytes(self.max_output_bytes);
+        if let Some(cwd) = cwd {
+            request = request.cwd(cwd);
+        }
+        if let Some(stdin) = args.stdin {
+            request = request.stdin(stdin);
+        }
+
+        // Execute the command, streaming output to the client as it is
+        // produced, up to the same cap as the result
+        let command = args.command.as_str();
+        let streaming = stream::is_active();
+        let mut streamed = [0usize; 2];
+        let max_output_bytes = self.max_output_bytes;
+        let output = pm
+            .run(request, |output_stream, chunk| {
+                trace!("{} {:?}: {}", command, output_stream, String::from_utf8_lossy(chunk));
+                let (name, sent) = match output_stream {
+                    OutputStream::Stdout => ("stdout", &mut streamed[0]),
+                    OutputStream::Stderr => ("stderr", &mut streamed[1]),
+                };
+                let chunk = &chunk[..chunk.len().min(max_output_bytes.saturating_sub(*sent))];
+                if streaming && !chunk.is_empty() {
+                    *sent += chunk.len();
+                    stream::emit(serde_json::json!({
+                        "type": name,
+                        "text": String::from_utf8_lossy(chunk)
+                    }));
+                }
+            })
+            .await?;
 
         // Format the output
         let result = serde_json::json!({
             "exit_code": output.exit_code,
             "success": output.success,
             "stdout": output.stdout,
-            "stderr": output.stderr
+            "stderr": output.stderr,
+            "truncated": output.truncated
         });
 
-        if output.success {
-            Ok(ToolOutput::text(result.to_string()))
-        } else {
-            Ok(ToolOutput {
-                content: vec![ToolContent::Text { text: result.to_string() }],
-                is_error: true,
-            })
-        }
+        Ok(ToolOutput {
+            is_error: !output.success,
+            ..ToolOutput::structured(result)
+        })
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn output_json(output: &ToolOutput) -> Value {
+        output.structured_content.clone().expect("structured output")
+    }
+
+    #[tokio::test]
+    async fn test_cwd_env_and_stdin() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.secrets.set("TOKEN", "s3cret", None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = CmdExecTool::new(vec!["sh".to_string()])
+            .with_working_dirs(vec![dir.path().to_path_buf()]);
+
+        let output = tool
+            .execute(serde_json::json!({
+                "command": "sh",
+                "args": ["-c", "pwd; echo \"$TOKEN\"; cat"],
+                "cwd": dir.path(),
+                "env": { "TOKEN": "${secrets.TOKEN}" },
+                "stdin": "piped"
+            }), state.clone())
+            .await
+            .unwrap();
+        let result = output_json(&output);
+        let stdout = result["stdout"].as_str().unwrap();
+        assert!(stdout.contains("s3cret\npiped"), "{}", stdout);
+        assert_eq!(result["truncated"], false);
+
+        let err = tool
+            .execute(serde_json::json!({ "command": "sh", "args": ["-c", "true"], "cwd": "/" }), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_denied_env_and_streaming() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let tool = CmdExecTool::new(vec!["sh".to_string()]).with_max_output_bytes(6);
+
+        for name in [
+            "PATH", "LD_PRELOAD", "ld_library_path", "DYLD_INSERT_LIBRARIES", "GIT_EXTERNAL_DIFF", "GIT_PAGER", "PAGER",
+            "GIT_ASKPASS", "SSH_ASKPASS", "GIT_PROXY_COMMAND", "EDITOR", "VISUAL", "BASH_FUNC_ls%%",
+        ] {
+            let err = tool
+                .execute(serde_json::json!({ "command": "sh", "args": ["-c", "true"], "env": { name: "/tmp" } }), state.clone())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ToolError::PermissionDenied(_)), "{}: {}", name, err);
+        }
+
+        // Chunks reach the sink, capped like the result
+        let (sink, mut chunks) = tokio::sync::mpsc::unbounded_channel();
+        let call = tool.execute(
+            serde_json::json!({ "command": "sh", "args": ["-c", "echo hello world; echo oops >&2"] }),
+            state,
+        );
+        let output = stream::with_sink(sink, stream::SinkFilter::default(), call).await.unwrap();
+        assert_eq!(output_json(&output)["truncated"], true);
+        let mut streamed = HashMap::<String, String>::new();
+        while let Ok(stream::ProgressUpdate::Chunk(chunk)) = chunks.try_recv() {
+            streamed.entry(chunk["type"].as_str().unwrap().to_string()).or_default().push_str(chunk["text"].as_str().unwrap());
+        }
+        assert_eq!(streamed["stdout"], "hello ");
+        assert_eq!(streamed["stderr"], "oops\n");
+    }
+}
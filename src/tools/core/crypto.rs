@@ -0,0 +1,467 @@
+//! Crypto tools: hashes, HMAC signatures, random bytes and JWT inspection.
+//!
+//! Meant for webhook and API signature work: compute or check an HMAC over
+//! a payload, generate secrets, and look inside tokens.
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::RngCore;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Most bytes random.bytes generates at once.
+const MAX_RANDOM_BYTES: usize = 1024;
+
+/// How binary output is written out.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Hex,
+    Base64,
+    Base64url,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "hex",
+            Self::Base64 => "base64",
+            Self::Base64url => "base64url",
+        }
+    }
+
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Hex => hex::encode(bytes),
+            Self::Base64 => BASE64.encode(bytes),
+            Self::Base64url => BASE64_URL.encode(bytes),
+        }
+    }
+
+    fn decode(self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Hex => hex::decode(text).ok(),
+            Self::Base64 => BASE64.decode(text).ok(),
+            Self::Base64url => BASE64_URL.decode(text.trim_end_matches('=')).ok(),
+        }
+    }
+}
+
+// ============================================================================
+// Hash Tools
+// ============================================================================
+
+/// Algorithms of the hash.* tools.
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Md5 => Md5::digest(data).to_vec(),
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+            Self::Blake3 => blake3::hash(data).as_bytes().to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HashDigestArgs {
+    text: String,
+    #[serde(default)]
+    encoding: Encoding,
+}
+
+/// hash.md5, hash.sha1, hash.sha512 and hash.blake3 (hash.sha256 is
+/// `HashTool`).
+#[derive(Debug)]
+pub struct HashDigestTool {
+    algorithm: HashAlgorithm,
+}
+
+impl HashDigestTool {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self { algorithm }
+    }
+}
+
+#[async_trait]
+impl Tool for HashDigestTool {
+    fn definition(&self) -> ToolDefinition {
+        let (label, note) = match self.algorithm {
+            HashAlgorithm::Md5 => ("MD5", " Not collision resistant; use only for checksums and legacy APIs."),
+            HashAlgorithm::Sha1 => ("SHA-1", " Not collision resistant; use only for checksums and legacy APIs."),
+            HashAlgorithm::Sha512 => ("SHA-512", ""),
+            HashAlgorithm::Blake3 => ("BLAKE3", ""),
+        };
+        ToolDefinition {
+            name: format!("hash.{}", self.algorithm.name()),
+            description: Some(format!("Computes the {} hash of text.{}", label, note)),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Text to hash"
+                    },
+                    "encoding": {
+                        "type": "string",
+                        "enum": ["hex", "base64", "base64url"],
+                        "description": "Output encoding (default: hex)"
+                    }
+                },
+                "required": ["text"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: HashDigestArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+        Ok(ToolOutput::text(args.encoding.encode(&self.algorithm.digest(args.text.as_bytes()))))
+    }
+}
+
+// ============================================================================
+// HMAC Tools
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HmacAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HmacAlgorithm {
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1=",
+            Self::Sha256 => "sha256=",
+            Self::Sha384 => "sha384=",
+            Self::Sha512 => "sha512=",
+        }
+    }
+
+    fn sign(self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+            let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        match self {
+            Self::Sha1 => mac::<Hmac<Sha1>>(key, message),
+            Self::Sha256 => mac::<Hmac<Sha256>>(key, message),
+            Self::Sha384 => mac::<Hmac<Sha384>>(key, message),
+            Self::Sha512 => mac::<Hmac<Sha512>>(key, message),
+        }
+    }
+
+    /// Checks `signature` in constant time.
+    fn verify(self, key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let expected = self.sign(key, message);
+        expected.len() == signature.len()
+            && expected.iter().zip(signature).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct HmacSignArgs {
+    /// Message to sign, e.g. a webhook body exactly as received
+    message: String,
+    /// Shared secret
+    secret: String,
+    /// Hash function (default: sha256)
+    #[serde(default)]
+    algorithm: HmacAlgorithm,
+    /// Signature encoding (default: hex)
+    #[serde(default)]
+    encoding: Encoding,
+}
+
+#[derive(Debug)]
+pub struct HmacSignTool;
+
+#[async_trait]
+impl TypedTool for HmacSignTool {
+    type Args = HmacSignArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "hmac.sign";
+    const DESCRIPTION: &'static str =
+        "Computes an HMAC signature of a message with a secret (SHA-1, SHA-256, SHA-384 or SHA-512), e.g. to sign outgoing webhooks.";
+
+    async fn run(&self, args: HmacSignArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let signature = args.algorithm.sign(args.secret.as_bytes(), args.message.as_bytes());
+        Ok(ToolOutput::text(args.encoding.encode(&signature)))
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct HmacVerifyArgs {
+    /// Message that was signed
+    message: String,
+    /// Shared secret
+    secret: String,
+    /// Signature to check; a prefix naming the algorithm, like GitHub's 'sha256=', is ignored
+    signature: String,
+    /// Hash function (default: sha256)
+    #[serde(default)]
+    algorithm: HmacAlgorithm,
+    /// Signature encoding (default: hex)
+    #[serde(default)]
+    encoding: Encoding,
+}
+
+#[derive(Debug)]
+pub struct HmacVerifyTool;
+
+#[async_trait]
+impl TypedTool for HmacVerifyTool {
+    type Args = HmacVerifyArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "hmac.verify";
+    const DESCRIPTION: &'static str =
+        "Checks an HMAC signature of a message, e.g. an incoming webhook's signature header. The comparison takes constant time.";
+
+    async fn run(&self, args: HmacVerifyArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let signature = args.signature.trim();
+        let signature = signature.strip_prefix(args.algorithm.prefix()).unwrap_or(signature);
+        let Some(signature) = args.encoding.decode(signature) else {
+            return Err(ToolError::InvalidInput(format!("Signature is not valid {}", args.encoding.name())));
+        };
+        let valid = args.algorithm.verify(args.secret.as_bytes(), args.message.as_bytes(), &signature);
+        Ok(ToolOutput::structured(json!({ "valid": valid })))
+    }
+}
+
+// ============================================================================
+// Random Bytes Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RandomBytesArgs {
+    /// Number of bytes (default: 32, max: 1024)
+    #[serde(default)]
+    length: Option<usize>,
+    /// Output encoding (default: hex)
+    #[serde(default)]
+    encoding: Encoding,
+}
+
+#[derive(Debug)]
+pub struct RandomBytesTool;
+
+#[async_trait]
+impl TypedTool for RandomBytesTool {
+    type Args = RandomBytesArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "random.bytes";
+    const DESCRIPTION: &'static str =
+        "Generates cryptographically secure random bytes from the operating system, e.g. for secrets, tokens and nonces.";
+
+    async fn run(&self, args: RandomBytesArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let length = args.length.unwrap_or(32);
+        if length == 0 || length > MAX_RANDOM_BYTES {
+            return Err(ToolError::InvalidInput(format!("'length' must be between 1 and {}", MAX_RANDOM_BYTES)));
+        }
+        let mut bytes = vec![0u8; length];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        Ok(ToolOutput::text(args.encoding.encode(&bytes)))
+    }
+}
+
+// ============================================================================
+// JWT Decode Tool
+// ============================================================================
+
+/// Decodes one base64url JWT segment as JSON.
+fn jwt_segment(segment: &str, what: &str) -> Result<Value, ToolError> {
+    let bytes = BASE64_URL
+        .decode(segment.trim_end_matches('='))
+        .map_err(|e| ToolError::InvalidInput(format!("JWT {} is not base64url: {}", what, e)))?;
+    serde_json::from_slice(&bytes).map_err(|e| ToolError::InvalidInput(format!("JWT {} is not JSON: {}", what, e)))
+}
+
+/// RFC 3339 form of a NumericDate claim.
+fn jwt_time(claims: &Value, claim: &str) -> Option<(i64, String)> {
+    let seconds = claims.get(claim)?.as_f64()? as i64;
+    let time = chrono::DateTime::from_timestamp(seconds, 0)?;
+    Some((seconds, time.to_rfc3339()))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct JwtDecodeArgs {
+    /// The token (a leading 'Bearer ' is ignored)
+    token: String,
+    /// Secret to verify an HS256, HS384 or HS512 signature with. Without it the token is only decoded.
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct JwtDecodeTool;
+
+#[async_trait]
+impl TypedTool for JwtDecodeTool {
+    type Args = JwtDecodeArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "jwt.decode";
+    const DESCRIPTION: &'static str =
+        "Decodes a JWT's header and claims and reports when it expires. With a secret, also verifies an HS256/HS384/HS512 signature; without one the token is NOT verified.";
+
+    async fn run(&self, args: JwtDecodeArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let token = args.token.trim();
+        let token = token.strip_prefix("Bearer ").unwrap_or(token).trim();
+        let parts: Vec<&str> = token.split('.').collect();
+        let [header, claims, signature] = parts.as_slice() else {
+            return Err(ToolError::InvalidInput(format!(
+                "A JWT has 3 dot-separated parts, this has {}",
+                parts.len()
+            )));
+        };
+        let header_json = jwt_segment(header, "header")?;
+        let claims_json = jwt_segment(claims, "claims")?;
+        let alg = header_json.get("alg").and_then(Value::as_str).unwrap_or("none");
+
+        let mut result = json!({
+            "header": header_json,
+            "claims": claims_json,
+            "verified": Value::Null,
+        });
+
+        if let Some(secret) = &args.secret {
+            let algorithm = match alg {
+                "HS256" => HmacAlgorithm::Sha256,
+                "HS384" => HmacAlgorithm::Sha384,
+                "HS512" => HmacAlgorithm::Sha512,
+                other => {
+                    return Err(ToolError::InvalidInput(format!(
+                        "Only HS256, HS384 and HS512 tokens can be verified with a secret; this one uses {}",
+                        other
+                    )))
+                }
+            };
+            let signature = Encoding::Base64url
+                .decode(signature)
+                .ok_or_else(|| ToolError::InvalidInput("JWT signature is not base64url".to_string()))?;
+            let signed = format!("{}.{}", header, claims);
+            result["verified"] = json!(algorithm.verify(secret.as_bytes(), signed.as_bytes(), &signature));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if let Some((exp, at)) = jwt_time(&claims_json, "exp") {
+            result["expires_at"] = json!(at);
+            result["expired"] = json!(exp <= now);
+        }
+        if let Some((nbf, at)) = jwt_time(&claims_json, "nbf") {
+            result["not_before"] = json!(at);
+            result["active"] = json!(nbf <= now);
+        }
+        if let Some((_, at)) = jwt_time(&claims_json, "iat") {
+            result["issued_at"] = json!(at);
+        }
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn state() -> Arc<RuntimeState> {
+        Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".into()), ..Config::default() }))
+    }
+
+    #[test]
+    fn test_hashes_and_hmac() {
+        assert_eq!(hex::encode(HashAlgorithm::Md5.digest(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hex::encode(HashAlgorithm::Sha1.digest(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert!(hex::encode(HashAlgorithm::Sha512.digest(b"abc")).starts_with("ddaf35a193617aba"));
+        assert_eq!(
+            hex::encode(HashAlgorithm::Blake3.digest(b"abc")),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+
+        // RFC 4231 test case 2
+        let signature = HmacAlgorithm::Sha256.sign(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(&signature),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(HmacAlgorithm::Sha256.verify(b"Jefe", b"what do ya want for nothing?", &signature));
+        assert!(!HmacAlgorithm::Sha256.verify(b"Jefe", b"what do ya want for something?", &signature));
+    }
+
+    #[tokio::test]
+    async fn test_verify_webhook_signature() {
+        let body = r#"{"action":"opened"}"#;
+        let signature = HmacAlgorithm::Sha256.sign(b"s3cret", body.as_bytes());
+        let args = json!({ "message": body, "secret": "s3cret", "signature": format!("sha256={}", hex::encode(signature)) });
+        let result = HmacVerifyTool.run(serde_json::from_value(args).unwrap(), state()).await.unwrap();
+        assert_eq!(result.structured_content.unwrap()["valid"], true);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_decode() {
+        let header = BASE64_URL.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let claims = BASE64_URL.encode(r#"{"sub":"42","exp":1000000000}"#);
+        let signature = HmacAlgorithm::Sha256.sign(b"key", format!("{}.{}", header, claims).as_bytes());
+        let token = format!("{}.{}.{}", header, claims, BASE64_URL.encode(signature));
+
+        let decoded = JwtDecodeTool
+            .run(serde_json::from_value(json!({ "token": format!("Bearer {}", token) })).unwrap(), state())
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(decoded["claims"]["sub"], "42");
+        assert_eq!(decoded["verified"], Value::Null);
+        assert_eq!(decoded["expired"], true);
+        assert_eq!(decoded["expires_at"], "2001-09-09T01:46:40+00:00");
+
+        for (secret, verified) in [("key", true), ("wrong", false)] {
+            let result = JwtDecodeTool
+                .run(serde_json::from_value(json!({ "token": token, "secret": secret })).unwrap(), state())
+                .await
+                .unwrap()
+                .structured_content
+                .unwrap();
+            assert_eq!(result["verified"], verified);
+        }
+
+        assert!(JwtDecodeTool.run(serde_json::from_value(json!({ "token": "a.b" })).unwrap(), state()).await.is_err());
+    }
+}
@@ -0,0 +1,630 @@
+//! CSV tools: parse CSV/TSV into typed JSON rows, and query tables.
+//!
+//! Column types are inferred from the values: a column whose non-empty
+//! cells are all integers, numbers or booleans becomes that type, anything
+//! else stays text. Empty cells are null.
+
+use ::csv::{ReaderBuilder, Trim};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+use super::fs_read::is_path_allowed;
+
+/// Rows returned without a `limit`.
+const DEFAULT_ROW_LIMIT: usize = 1000;
+
+/// Where the table comes from and how to read it.
+#[derive(Default, Deserialize, JsonSchema)]
+pub struct CsvSource {
+    /// CSV text (or use path)
+    #[serde(default)]
+    text: Option<String>,
+    /// CSV or TSV file inside the allowed read paths
+    #[serde(default)]
+    path: Option<String>,
+    /// Field delimiter, e.g. ',' ';' or '\t' (default: tab for .tsv files or tab-separated text, else comma)
+    #[serde(default)]
+    delimiter: Option<String>,
+    /// Whether the first row holds column names (default: true; otherwise columns are named column_1, column_2, ...)
+    #[serde(default)]
+    has_headers: Option<bool>,
+    /// Convert numbers, booleans and empty cells (default: true); false keeps every cell as text
+    #[serde(default)]
+    infer_types: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    /// Only empty cells.
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    Text,
+}
+
+impl ColumnType {
+    fn of(cell: &str) -> Self {
+        if cell.is_empty() {
+            Self::Null
+        } else if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+            Self::Boolean
+        } else if cell.parse::<i64>().is_ok() {
+            Self::Integer
+        } else if cell.parse::<f64>().is_ok_and(f64::is_finite) {
+            Self::Number
+        } else {
+            Self::Text
+        }
+    }
+
+    /// The narrowest type holding values of both types.
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Null, t) | (t, Self::Null) => t,
+            (Self::Integer, Self::Number) | (Self::Number, Self::Integer) => Self::Number,
+            _ => Self::Text,
+        }
+    }
+
+    fn convert(self, cell: &str) -> Value {
+        if cell.is_empty() && self != Self::Text {
+            return Value::Null;
+        }
+        match self {
+            Self::Boolean => json!(cell.eq_ignore_ascii_case("true")),
+            Self::Integer => cell.parse::<i64>().map(Value::from).unwrap_or(Value::Null),
+            Self::Number => cell.parse::<f64>().map(Value::from).unwrap_or(Value::Null),
+            Self::Null | Self::Text => json!(cell),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Text => "string",
+        }
+    }
+}
+
+/// A parsed table.
+struct Table {
+    columns: Vec<String>,
+    types: Vec<ColumnType>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    fn column(&self, name: &str) -> Result<usize, ToolError> {
+        self.columns
+            .iter()
+            .position(|c| c == name)
+            .ok_or_else(|| ToolError::InvalidInput(format!("Unknown column '{}' (columns: {})", name, self.columns.join(", "))))
+    }
+
+    fn schema(&self) -> Value {
+        json!(self
+            .columns
+            .iter()
+            .zip(&self.types)
+            .map(|(name, kind)| json!({ "name": name, "type": kind.as_str() }))
+            .collect::<Vec<_>>())
+    }
+}
+
+fn delimiter_byte(delimiter: &str) -> Result<u8, ToolError> {
+    match delimiter {
+        "\\t" | "tab" => Ok(b'\t'),
+        d if d.len() == 1 => Ok(d.as_bytes()[0]),
+        d => Err(ToolError::InvalidInput(format!("Delimiter must be a single character, got '{}'", d))),
+    }
+}
+
+/// Reads and parses the table `source` describes.
+async fn load(source: CsvSource, state: &RuntimeState) -> Result<Table, ToolError> {
+    let (text, tsv_file) = match (source.text, &source.path) {
+        (Some(text), None) => (text, false),
+        (None, Some(path)) => {
+            if !is_path_allowed(&state.config.security.allowed_read_paths, Path::new(path)) {
+                return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", path)));
+            }
+            let text = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read {}: {}", path, e)))?;
+            (text, path.to_lowercase().ends_with(".tsv"))
+        }
+        _ => return Err(ToolError::InvalidInput("Pass exactly one of 'text' and 'path'".to_string())),
+    };
+
+    let delimiter = match source.delimiter {
+        Some(d) => delimiter_byte(&d)?,
+        None => {
+            let first_line = text.lines().next().unwrap_or_default();
+            if tsv_file || (first_line.contains('\t') && !first_line.contains(',')) {
+                b'\t'
+            } else {
+                b','
+            }
+        }
+    };
+    parse(&text, delimiter, source.has_headers.unwrap_or(true), source.infer_types.unwrap_or(true))
+}
+
+fn parse(text: &str, delimiter: u8, has_headers: bool, infer_types: bool) -> Result<Table, ToolError> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_headers)
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(text.as_bytes());
+
+    let invalid = |e: ::csv::Error| ToolError::InvalidInput(format!("Invalid CSV: {}", e));
+    let mut columns: Vec<String> = if has_headers {
+        reader.headers().map_err(invalid)?.iter().map(str::to_string).collect()
+    } else {
+        Vec::new()
+    };
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+
+    // Short rows are padded with empty cells; long rows get extra columns
+    let width = records.iter().map(|r| r.len()).max().unwrap_or(0).max(columns.len());
+    for i in columns.len()..width {
+        columns.push(format!("column_{}", i + 1));
+    }
+
+    let mut types = vec![ColumnType::Null; width];
+    for record in &records {
+        for (i, kind) in types.iter_mut().enumerate() {
+            let cell = record.get(i).unwrap_or_default();
+            *kind = if infer_types { kind.merge(ColumnType::of(cell)) } else { ColumnType::Text };
+        }
+    }
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            types
+                .iter()
+                .enumerate()
+                .map(|(i, kind)| kind.convert(record.get(i).unwrap_or_default()))
+                .collect()
+        })
+        .collect();
+    Ok(Table { columns, types, rows })
+}
+
+fn to_objects(columns: &[String], rows: impl IntoIterator<Item = Vec<Value>>) -> Vec<Value> {
+    rows.into_iter()
+        .map(|row| Value::Object(columns.iter().cloned().zip(row).collect::<Map<_, _>>()))
+        .collect()
+}
+
+// ============================================================================
+// CSV Parse Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CsvParseArgs {
+    #[serde(flatten)]
+    source: CsvSource,
+    /// Rows to skip (default: 0)
+    #[serde(default)]
+    offset: usize,
+    /// Maximum rows to return (default: 1000)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct CsvParseTool;
+
+#[async_trait]
+impl TypedTool for CsvParseTool {
+    type Args = CsvParseArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "csv.parse";
+    const DESCRIPTION: &'static str =
+        "Parses CSV or TSV text or a file into JSON rows keyed by column name, inferring integer, number and boolean columns. Returns the columns with their types.";
+
+    async fn run(&self, args: CsvParseArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let table = load(args.source, &state).await?;
+        let total = table.rows.len();
+        let limit = args.limit.unwrap_or(DEFAULT_ROW_LIMIT);
+        let schema = table.schema();
+        let rows = to_objects(&table.columns, table.rows.into_iter().skip(args.offset).take(limit));
+        let has_more = args.offset + rows.len() < total;
+
+        Ok(ToolOutput::structured(json!({
+            "columns": schema,
+            "rows": rows,
+            "total_rows": total,
+            "has_more": has_more,
+        })))
+    }
+}
+
+// ============================================================================
+// CSV Query Tool
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains,
+    StartsWith,
+    IsNull,
+    NotNull,
+}
+
+/// A condition on a column.
+#[derive(Deserialize, JsonSchema)]
+pub struct Filter {
+    /// Column to test
+    column: String,
+    /// Comparison
+    op: FilterOp,
+    /// Value to compare with (not needed for is_null / not_null)
+    #[serde(default)]
+    value: Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFn {
+    Count,
+    CountDistinct,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// A value computed per group.
+#[derive(Deserialize, JsonSchema)]
+pub struct Aggregate {
+    /// Function to apply
+    #[serde(rename = "fn")]
+    function: AggregateFn,
+    /// Column to aggregate (optional for count, which then counts rows)
+    #[serde(default)]
+    column: Option<String>,
+    /// Output name (default: e.g. 'sum_price')
+    #[serde(default, rename = "as")]
+    alias: Option<String>,
+}
+
+/// A sort key.
+#[derive(Deserialize, JsonSchema)]
+pub struct OrderBy {
+    /// Column (or aggregate output name) to sort by
+    column: String,
+    /// Sort descending (default: false)
+    #[serde(default)]
+    desc: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CsvQueryArgs {
+    #[serde(flatten)]
+    source: CsvSource,
+    /// Columns to return (default: all); ignored when aggregating
+    #[serde(default)]
+    select: Vec<String>,
+    /// Conditions rows must all meet
+    #[serde(default, rename = "where")]
+    filters: Vec<Filter>,
+    /// Columns to group by; each group becomes one row
+    #[serde(default)]
+    group_by: Vec<String>,
+    /// Values computed per group (or over all rows without group_by)
+    #[serde(default)]
+    aggregates: Vec<Aggregate>,
+    /// Sort keys, applied in order
+    #[serde(default)]
+    order_by: Vec<OrderBy>,
+    /// Maximum rows to return (default: 1000)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Orders values: nulls first, then numbers, booleans and strings.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Number(_) => 1,
+            Value::Bool(_) => 2,
+            _ => 3,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64().unwrap_or(0.0).total_cmp(&y.as_f64().unwrap_or(0.0))
+        }
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn text_of(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Coerces the filter value to the cell's type, so `"10"` matches 10.
+fn coerce(value: &Value, like: &Value) -> Value {
+    match (like, value) {
+        (Value::Number(_), Value::String(s)) => s.parse::<f64>().map(Value::from).unwrap_or_else(|_| value.clone()),
+        (Value::Bool(_), Value::String(s)) => match s.to_ascii_lowercase().as_str() {
+            "true" => json!(true),
+            "false" => json!(false),
+            _ => value.clone(),
+        },
+        (Value::String(_), Value::Number(_) | Value::Bool(_)) => json!(value.to_string()),
+        _ => value.clone(),
+    }
+}
+
+fn matches(cell: &Value, op: FilterOp, value: &Value) -> bool {
+    let value = coerce(value, cell);
+    match op {
+        FilterOp::IsNull => cell.is_null(),
+        FilterOp::NotNull => !cell.is_null(),
+        FilterOp::Contains => text_of(cell).contains(&text_of(&value)),
+        FilterOp::StartsWith => text_of(cell).starts_with(&text_of(&value)),
+        FilterOp::Eq => compare(cell, &value) == Ordering::Equal,
+        FilterOp::Ne => compare(cell, &value) != Ordering::Equal,
+        // Ordered comparisons never match nulls
+        _ if cell.is_null() => false,
+        FilterOp::Gt => compare(cell, &value) == Ordering::Greater,
+        FilterOp::Gte => compare(cell, &value) != Ordering::Less,
+        FilterOp::Lt => compare(cell, &value) == Ordering::Less,
+        FilterOp::Lte => compare(cell, &value) != Ordering::Greater,
+    }
+}
+
+fn aggregate(function: AggregateFn, values: &[&Value]) -> Value {
+    let present: Vec<&Value> = values.iter().copied().filter(|v| !v.is_null()).collect();
+    let numbers = || present.iter().filter_map(|v| v.as_f64());
+    match function {
+        AggregateFn::Count => json!(present.len()),
+        AggregateFn::CountDistinct => {
+            json!(present.iter().map(|v| v.to_string()).collect::<HashSet<_>>().len())
+        }
+        AggregateFn::Sum => {
+            if present.iter().all(|v| v.is_i64()) {
+                json!(present.iter().filter_map(|v| v.as_i64()).sum::<i64>())
+            } else {
+                json!(numbers().sum::<f64>())
+            }
+        }
+        AggregateFn::Avg => {
+            let count = numbers().count();
+            if count == 0 {
+                Value::Null
+            } else {
+                json!(numbers().sum::<f64>() / count as f64)
+            }
+        }
+        AggregateFn::Min => present.iter().min_by(|a, b| compare(a, b)).map(|v| (*v).clone()).unwrap_or(Value::Null),
+        AggregateFn::Max => present.iter().max_by(|a, b| compare(a, b)).map(|v| (*v).clone()).unwrap_or(Value::Null),
+    }
+}
+
+fn aggregate_name(aggregate: &Aggregate) -> String {
+    if let Some(alias) = &aggregate.alias {
+        return alias.clone();
+    }
+    let function = match aggregate.function {
+        AggregateFn::Count => "count",
+        AggregateFn::CountDistinct => "count_distinct",
+        AggregateFn::Sum => "sum",
+        AggregateFn::Avg => "avg",
+        AggregateFn::Min => "min",
+        AggregateFn::Max => "max",
+    };
+    match &aggregate.column {
+        Some(column) => format!("{}_{}", function, column),
+        None => function.to_string(),
+    }
+}
+
+/// Runs a query over a parsed table, returning the output columns and rows.
+fn query(table: Table, args: &CsvQueryArgs) -> Result<(Vec<String>, Vec<Vec<Value>>), ToolError> {
+    let filters = args
+        .filters
+        .iter()
+        .map(|f| Ok((table.column(&f.column)?, f)))
+        .collect::<Result<Vec<_>, ToolError>>()?;
+    let rows: Vec<Vec<Value>> = table
+        .rows
+        .iter()
+        .filter(|row| filters.iter().all(|(i, f)| matches(&row[*i], f.op, &f.value)))
+        .cloned()
+        .collect();
+
+    let (columns, mut rows) = if args.group_by.is_empty() && args.aggregates.is_empty() {
+        if args.select.is_empty() {
+            (table.columns.clone(), rows)
+        } else {
+            let picked = args.select.iter().map(|c| table.column(c)).collect::<Result<Vec<_>, _>>()?;
+            let rows = rows.into_iter().map(|row| picked.iter().map(|&i| row[i].clone()).collect()).collect();
+            (args.select.clone(), rows)
+        }
+    } else {
+        let keys = args.group_by.iter().map(|c| table.column(c)).collect::<Result<Vec<_>, _>>()?;
+        let aggregated = args
+            .aggregates
+            .iter()
+            .map(|a| Ok((a.column.as_deref().map(|c| table.column(c)).transpose()?, a)))
+            .collect::<Result<Vec<_>, ToolError>>()?;
+
+        // Groups keep the order they first appear in
+        let mut order: Vec<String> = Vec::new();
+        let mut groups: BTreeMap<String, Vec<&Vec<Value>>> = BTreeMap::new();
+        for row in &rows {
+            let key = serde_json::to_string(&keys.iter().map(|&i| &row[i]).collect::<Vec<_>>()).unwrap_or_default();
+            groups.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                Vec::new()
+            }).push(row);
+        }
+        if keys.is_empty() && groups.is_empty() {
+            // Aggregates over no rows still produce one row (e.g. count 0)
+            groups.insert(String::new(), Vec::new());
+            order.push(String::new());
+        }
+
+        let out_rows = order
+            .iter()
+            .map(|key| {
+                let members = &groups[key];
+                let mut out: Vec<Value> = keys
+                    .iter()
+                    .map(|&i| members.first().map(|row| row[i].clone()).unwrap_or(Value::Null))
+                    .collect();
+                for (column, aggregate_def) in &aggregated {
+                    out.push(match column {
+                        Some(i) => aggregate(aggregate_def.function, &members.iter().map(|row| &row[*i]).collect::<Vec<_>>()),
+                        None => json!(members.len()),
+                    });
+                }
+                out
+            })
+            .collect();
+        let mut columns = args.group_by.clone();
+        columns.extend(args.aggregates.iter().map(aggregate_name));
+        (columns, out_rows)
+    };
+
+    let sort_keys = args
+        .order_by
+        .iter()
+        .map(|o| {
+            columns
+                .iter()
+                .position(|c| *c == o.column)
+                .map(|i| (i, o.desc))
+                .ok_or_else(|| ToolError::InvalidInput(format!("Cannot order by '{}': not in the result", o.column)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !sort_keys.is_empty() {
+        rows.sort_by(|a, b| {
+            sort_keys.iter().fold(Ordering::Equal, |ordering, &(i, desc)| {
+                ordering.then_with(|| {
+                    let ordering = compare(&a[i], &b[i]);
+                    if desc { ordering.reverse() } else { ordering }
+                })
+            })
+        });
+    }
+    Ok((columns, rows))
+}
+
+#[derive(Debug)]
+pub struct CsvQueryTool;
+
+#[async_trait]
+impl TypedTool for CsvQueryTool {
+    type Args = CsvQueryArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "csv.query";
+    const DESCRIPTION: &'static str =
+        "Queries CSV/TSV text or a file: filter rows (where), pick columns (select), group and aggregate (count, count_distinct, sum, avg, min, max), and sort (order_by).";
+
+    async fn run(&self, mut args: CsvQueryArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let table = load(std::mem::take(&mut args.source), &state).await?;
+        let (columns, rows) = query(table, &args)?;
+        let total = rows.len();
+        let limit = args.limit.unwrap_or(DEFAULT_ROW_LIMIT);
+
+        Ok(ToolOutput::structured(json!({
+            "columns": columns,
+            "rows": to_objects(&columns, rows.into_iter().take(limit)),
+            "total_rows": total,
+            "has_more": total > limit,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = "region,product,units,price,shipped\n\
+                         east,apple,10,1.5,true\n\
+                         west,apple,4,1.5,false\n\
+                         east,pear,7,2,true\n\
+                         west,pear,,2.25,true\n";
+
+    #[test]
+    fn test_parse_infers_types() {
+        let table = parse(SALES, b',', true, true).unwrap();
+        let types: Vec<_> = table.types.iter().map(|t| t.as_str()).collect();
+        assert_eq!(types, ["string", "string", "integer", "number", "boolean"]);
+        assert_eq!(table.rows[0][2], json!(10));
+        assert_eq!(table.rows[3][2], Value::Null);
+
+        let tsv = parse("a\tb\n1\tx\n", b'\t', false, true).unwrap();
+        assert_eq!(tsv.columns, ["column_1", "column_2"]);
+        assert_eq!(tsv.types[0], ColumnType::Text);
+    }
+
+    #[test]
+    fn test_query_filters_groups_and_sorts() {
+        let args: CsvQueryArgs = serde_json::from_value(json!({
+            "text": SALES,
+            "where": [{ "column": "shipped", "op": "eq", "value": "true" }],
+            "group_by": ["product"],
+            "aggregates": [
+                { "fn": "sum", "column": "units" },
+                { "fn": "count", "as": "orders" },
+                { "fn": "avg", "column": "price" }
+            ],
+            "order_by": [{ "column": "orders", "desc": true }, { "column": "product" }]
+        }))
+        .unwrap();
+        let table = parse(SALES, b',', true, true).unwrap();
+        let (columns, rows) = query(table, &args).unwrap();
+        assert_eq!(columns, ["product", "sum_units", "orders", "avg_price"]);
+        assert_eq!(rows[0], vec![json!("pear"), json!(7), json!(2), json!(2.125)]);
+        assert_eq!(rows[1], vec![json!("apple"), json!(10), json!(1), json!(1.5)]);
+
+        let args: CsvQueryArgs = serde_json::from_value(json!({
+            "text": SALES,
+            "select": ["region", "units"],
+            "where": [{ "column": "units", "op": "gte", "value": 7 }]
+        }))
+        .unwrap();
+        let table = parse(SALES, b',', true, true).unwrap();
+        let (_, rows) = query(table, &args).unwrap();
+        assert_eq!(rows, vec![vec![json!("east"), json!(10)], vec![json!("east"), json!(7)]]);
+    }
+}
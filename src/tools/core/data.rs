@@ -0,0 +1,322 @@
+//! Data conversion between JSON, YAML, TOML and XML.
+//!
+//! XML maps to JSON by convention: an element becomes an object whose
+//! attributes are `@name` keys and whose child elements are keys (arrays
+//! when repeated); an element with only text becomes a string, and text
+//! next to attributes or children is kept under `#text`. Converting back
+//! follows the same rules.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+    Xml,
+}
+
+impl DataFormat {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+            Self::Xml => "XML",
+        }
+    }
+}
+
+fn invalid(format: DataFormat, location: Option<(usize, usize)>, message: impl std::fmt::Display) -> ToolError {
+    match location {
+        Some((line, column)) => ToolError::InvalidInput(format!(
+            "Invalid {} at line {}, column {}: {}",
+            format.name(),
+            line,
+            column,
+            message
+        )),
+        None => ToolError::InvalidInput(format!("Invalid {}: {}", format.name(), message)),
+    }
+}
+
+/// 1-based line and column of a byte offset.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+fn parse(input: &str, format: DataFormat) -> Result<Value, ToolError> {
+    match format {
+        // serde_json's and serde_yaml's messages already include the position
+        DataFormat::Json => serde_json::from_str(input).map_err(|e| invalid(format, None, e)),
+        DataFormat::Yaml => serde_yaml::from_str(input).map_err(|e| invalid(format, None, e)),
+        DataFormat::Toml => {
+            let table: toml::Table = input.parse().map_err(|e: toml::de::Error| {
+                let location = e.span().map(|span| line_column(input, span.start));
+                invalid(format, location, e.message())
+            })?;
+            serde_json::to_value(table).map_err(|e| ToolError::Internal(e.to_string()))
+        }
+        DataFormat::Xml => {
+            // roxmltree's messages already end with the position
+            let document = roxmltree::Document::parse(input).map_err(|e| invalid(format, None, e))?;
+            let root = document.root_element();
+            Ok(json!({ root.tag_name().name(): xml_element(root) }))
+        }
+    }
+}
+
+fn serialize(value: &Value, format: DataFormat, pretty: bool, root: &str) -> Result<String, ToolError> {
+    let failed = |e: &dyn std::fmt::Display| {
+        ToolError::ExecutionFailed(format!("Cannot write as {}: {}", format.name(), e))
+    };
+    match format {
+        DataFormat::Json if pretty => serde_json::to_string_pretty(value).map_err(|e| failed(&e)),
+        DataFormat::Json => serde_json::to_string(value).map_err(|e| failed(&e)),
+        DataFormat::Yaml => serde_yaml::to_string(value).map_err(|e| failed(&e)),
+        DataFormat::Toml => {
+            if !value.is_object() {
+                return Err(failed(&"the top level must be an object"));
+            }
+            if pretty {
+                toml::to_string_pretty(value).map_err(|e| failed(&e))
+            } else {
+                toml::to_string(value).map_err(|e| failed(&e))
+            }
+        }
+        DataFormat::Xml => {
+            // A single-key object names its own root element
+            let (name, content) = match value.as_object() {
+                Some(object) if object.len() == 1 => object.iter().next().map(|(k, v)| (k.as_str(), v)).unwrap(),
+                _ => (root, value),
+            };
+            if content.is_array() {
+                return Err(failed(&"the root element can't be an array; wrap it in an object"));
+            }
+            let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            write_xml(&mut out, name, content, 0, pretty).map_err(|e| failed(&e))?;
+            Ok(out)
+        }
+    }
+}
+
+// ============================================================================
+// XML mapping
+// ============================================================================
+
+fn xml_element(node: roxmltree::Node) -> Value {
+    let mut object = Map::new();
+    for attribute in node.attributes() {
+        object.insert(format!("@{}", attribute.name()), json!(attribute.value()));
+    }
+
+    let mut text = String::new();
+    for child in node.children() {
+        if child.is_element() {
+            let name = child.tag_name().name().to_string();
+            let value = xml_element(child);
+            match object.get_mut(&name) {
+                Some(Value::Array(items)) => items.push(value),
+                Some(existing) => *existing = json!([existing.take(), value]),
+                None => {
+                    object.insert(name, value);
+                }
+            }
+        } else if let Some(t) = child.text() {
+            text.push_str(t);
+        }
+    }
+
+    let text = text.trim();
+    if object.is_empty() {
+        return if text.is_empty() { Value::Null } else { json!(text) };
+    }
+    if !text.is_empty() {
+        object.insert("#text".to_string(), json!(text));
+    }
+    Value::Object(object)
+}
+
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+}
+
+fn escape_xml(text: &str, attribute: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn write_xml(out: &mut String, name: &str, value: &Value, depth: usize, pretty: bool) -> Result<(), String> {
+    if let Value::Array(items) = value {
+        for item in items {
+            write_xml(out, name, item, depth, pretty)?;
+        }
+        return Ok(());
+    }
+    if !is_xml_name(name) {
+        return Err(format!("'{}' is not a valid element name", name));
+    }
+
+    let indent = if pretty { "  ".repeat(depth) } else { String::new() };
+    let newline = if pretty { "\n" } else { "" };
+    let _ = write!(out, "{}<{}", indent, name);
+
+    let Value::Object(object) = value else {
+        let text = scalar_text(value);
+        if text.is_empty() {
+            let _ = write!(out, "/>{}", newline);
+        } else {
+            let _ = write!(out, ">{}</{}>{}", escape_xml(&text, false), name, newline);
+        }
+        return Ok(());
+    };
+
+    for (key, attribute) in object.iter().filter(|(k, _)| k.starts_with('@')) {
+        let attribute_name = &key[1..];
+        if !is_xml_name(attribute_name) {
+            return Err(format!("'{}' is not a valid attribute name", attribute_name));
+        }
+        let _ = write!(out, " {}=\"{}\"", attribute_name, escape_xml(&scalar_text(attribute), true));
+    }
+
+    let text = object.get("#text").map(scalar_text).unwrap_or_default();
+    let children: Vec<_> = object.iter().filter(|(k, _)| !k.starts_with('@') && *k != "#text").collect();
+    match (children.is_empty(), text.is_empty()) {
+        (true, true) => {
+            let _ = write!(out, "/>{}", newline);
+        }
+        (true, false) => {
+            let _ = write!(out, ">{}</{}>{}", escape_xml(&text, false), name, newline);
+        }
+        (false, _) => {
+            let _ = write!(out, ">{}", newline);
+            if !text.is_empty() {
+                let _ = write!(out, "{}  {}{}", indent, escape_xml(&text, false), newline);
+            }
+            for (key, child) in children {
+                write_xml(out, key, child, depth + 1, pretty)?;
+            }
+            let _ = write!(out, "{}</{}>{}", indent, name, newline);
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Data Convert Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DataConvertArgs {
+    /// Text to convert
+    input: String,
+    /// Format of the input
+    from: DataFormat,
+    /// Format to produce (default: json)
+    #[serde(default)]
+    to: Option<DataFormat>,
+    /// Indent the output (default: true; YAML is always indented)
+    #[serde(default)]
+    pretty: Option<bool>,
+    /// Root element name when writing XML from anything but a single-key object (default: 'root')
+    #[serde(default)]
+    root: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct DataConvertTool;
+
+#[async_trait]
+impl TypedTool for DataConvertTool {
+    type Args = DataConvertArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "data.convert";
+    const DESCRIPTION: &'static str =
+        "Converts between JSON, YAML, TOML and XML. Invalid input is reported with its line and column. Converting to JSON also returns the parsed value as 'data'.";
+
+    async fn run(&self, args: DataConvertArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let to = args.to.unwrap_or(DataFormat::Json);
+        let value = parse(&args.input, args.from)?;
+        let output = serialize(&value, to, args.pretty.unwrap_or(true), args.root.as_deref().unwrap_or("root"))?;
+
+        let mut result = json!({ "output": output });
+        if to == DataFormat::Json {
+            result["data"] = value;
+        }
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_between_formats() {
+        let toml_text = "[server]\nport = 8080\nhosts = [\"a\", \"b\"]\n";
+        let value = parse(toml_text, DataFormat::Toml).unwrap();
+        assert_eq!(value, json!({ "server": { "port": 8080, "hosts": ["a", "b"] } }));
+
+        let yaml = serialize(&value, DataFormat::Yaml, true, "root").unwrap();
+        assert_eq!(parse(&yaml, DataFormat::Yaml).unwrap(), value);
+
+        let xml = serialize(&value, DataFormat::Xml, true, "root").unwrap();
+        assert!(xml.contains("<hosts>a</hosts>"));
+        assert_eq!(
+            parse(&xml, DataFormat::Xml).unwrap(),
+            json!({ "server": { "port": "8080", "hosts": ["a", "b"] } })
+        );
+
+        let feed = parse(r#"<feed lang="en"><item id="1">First &amp; best</item><empty/></feed>"#, DataFormat::Xml).unwrap();
+        assert_eq!(
+            feed,
+            json!({ "feed": { "@lang": "en", "item": { "@id": "1", "#text": "First & best" }, "empty": null } })
+        );
+    }
+
+    #[test]
+    fn test_errors_have_locations() {
+        let yaml_error = parse("a: 1\nb: [unclosed\n", DataFormat::Yaml).unwrap_err().to_string();
+        assert!(yaml_error.contains("line"), "{}", yaml_error);
+
+        let toml_error = parse("a = 1\nb = \n", DataFormat::Toml).unwrap_err().to_string();
+        assert!(toml_error.contains("line 2"), "{}", toml_error);
+
+        let json_error = parse("{\n  \"a\": 1,\n  \"b\" 2\n}", DataFormat::Json).unwrap_err().to_string();
+        assert!(json_error.contains("line 3"), "{}", json_error);
+
+        assert!(serialize(&json!([1, 2]), DataFormat::Toml, true, "root").is_err());
+    }
+}
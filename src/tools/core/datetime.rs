@@ -0,0 +1,652 @@
+//! Date and time tools: parsing, formatting, arithmetic and timezone
+//! conversion.
+//!
+//! Times without an offset are read in the `timezone` argument, or the
+//! server's `default_timezone`. Adding days, months or years keeps the wall
+//! clock time across DST changes; hours, minutes and seconds are exact.
+
+use async_trait::async_trait;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, Datelike, Days, Duration, FixedOffset, Locale, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Formats tried for times with a date, most specific first.
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+    "%B %d, %Y %H:%M:%S",
+    "%B %d, %Y %H:%M",
+    "%B %d %Y %H:%M",
+    "%d %B %Y %H:%M:%S",
+    "%d %B %Y %H:%M",
+    "%a %b %e %H:%M:%S %Y",
+];
+
+/// Formats tried for dates alone (midnight).
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%B %d, %Y", "%B %d %Y", "%d %B %Y", "%A, %B %d, %Y", "%Y%m%d",
+];
+
+/// Formats with an offset, beyond RFC 3339 and RFC 2822.
+const OFFSET_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S%.f %z", "%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M:%S%.f%z"];
+
+fn parse_timezone(name: Option<&str>, state: &RuntimeState) -> Result<Tz, ToolError> {
+    match name {
+        Some(name) => name.parse().map_err(|_| ToolError::InvalidInput(format!("Unknown timezone: {}", name))),
+        None => Ok(state.config.timezone()),
+    }
+}
+
+/// Rejects strftime formats chrono would panic on.
+fn check_format(format: &str) -> Result<(), ToolError> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(ToolError::InvalidInput(format!("Invalid format string: {}", format)));
+    }
+    Ok(())
+}
+
+/// Places a wall clock time in `tz`. Ambiguous times (clocks going back)
+/// take the earlier instant; times skipped by clocks going forward move
+/// forward by the gap.
+fn localize(naive: NaiveDateTime, tz: Tz) -> Result<DateTime<Tz>, ToolError> {
+    if let Some(time) = tz.from_local_datetime(&naive).earliest() {
+        return Ok(time);
+    }
+    tz.from_local_datetime(&(naive + Duration::hours(1)))
+        .earliest()
+        .ok_or_else(|| ToolError::InvalidInput(format!("{} does not exist in {}", naive, tz.name())))
+}
+
+/// Parses a time without an offset. `%B` also accepts abbreviated month
+/// names ("Jan 5, 2024").
+fn parse_naive(input: &str, day_first: bool) -> Option<NaiveDateTime> {
+    let slash_datetime = if day_first { "%d/%m/%Y %H:%M" } else { "%m/%d/%Y %H:%M" };
+    let slash_datetime_seconds = if day_first { "%d/%m/%Y %H:%M:%S" } else { "%m/%d/%Y %H:%M:%S" };
+    let slash_date = if day_first { "%d/%m/%Y" } else { "%m/%d/%Y" };
+
+    DATETIME_FORMATS
+        .iter()
+        .chain(&[slash_datetime_seconds, slash_datetime])
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .chain(&[slash_date])
+                .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+}
+
+/// Parses a time in any supported form. `format` is a strftime format the
+/// input must match instead.
+fn parse_time(input: &str, tz: Tz, format: Option<&str>, day_first: bool) -> Result<DateTime<Tz>, ToolError> {
+    let input = input.trim();
+    if let Some(format) = format {
+        check_format(format)?;
+        if let Ok(time) = DateTime::parse_from_str(input, format) {
+            return Ok(time.with_timezone(&tz));
+        }
+        let naive = NaiveDateTime::parse_from_str(input, format)
+            .or_else(|_| NaiveDate::parse_from_str(input, format).map(|date| date.and_time(NaiveTime::MIN)))
+            .map_err(|e| ToolError::InvalidInput(format!("'{}' does not match '{}': {}", input, format, e)))?;
+        return localize(naive, tz);
+    }
+
+    let now = Utc::now().with_timezone(&tz);
+    let today = now.date_naive().and_time(NaiveTime::MIN);
+    match input.to_lowercase().as_str() {
+        "now" => return Ok(now),
+        "today" => return localize(today, tz),
+        "tomorrow" => return localize(today + Days::new(1), tz),
+        "yesterday" => return localize(today - Days::new(1), tz),
+        _ => {}
+    }
+
+    // Unix timestamps, in milliseconds when too large for seconds
+    if let Ok(number) = input.parse::<i64>() {
+        let time = if number.abs() >= 100_000_000_000 {
+            DateTime::from_timestamp_millis(number)
+        } else {
+            DateTime::from_timestamp(number, 0)
+        };
+        return time
+            .map(|t| t.with_timezone(&tz))
+            .ok_or_else(|| ToolError::InvalidInput(format!("Timestamp out of range: {}", input)));
+    }
+
+    let with_offset = DateTime::parse_from_rfc3339(input)
+        .or_else(|_| DateTime::parse_from_rfc2822(input))
+        .ok()
+        .or_else(|| OFFSET_FORMATS.iter().find_map(|format| DateTime::<FixedOffset>::parse_from_str(input, format).ok()));
+    if let Some(time) = with_offset {
+        return Ok(time.with_timezone(&tz));
+    }
+    // A trailing 'UTC' or 'Z' that RFC 3339 parsing didn't accept
+    if let Some(rest) = input.strip_suffix(" UTC").or_else(|| input.strip_suffix('Z')) {
+        if let Some(naive) = parse_naive(rest.trim(), day_first) {
+            return Ok(Utc.from_utc_datetime(&naive).with_timezone(&tz));
+        }
+    }
+
+    match parse_naive(input, day_first) {
+        Some(naive) => localize(naive, tz),
+        None => Err(ToolError::InvalidInput(format!(
+            "Cannot parse '{}' as a time; pass 'format' to give its layout",
+            input
+        ))),
+    }
+}
+
+/// The fields every time tool returns about a time.
+fn describe(time: DateTime<Tz>) -> Value {
+    json!({
+        "time": time.to_rfc3339(),
+        "utc": time.with_timezone(&Utc).to_rfc3339(),
+        "timestamp": time.timestamp(),
+        "timestamp_millis": time.timestamp_millis(),
+        "timezone": time.timezone().name(),
+        "utc_offset": time.format("%:z").to_string(),
+        "date": time.format("%Y-%m-%d").to_string(),
+        "weekday": time.format("%A").to_string(),
+    })
+}
+
+// ============================================================================
+// Durations
+// ============================================================================
+
+/// A span to add: calendar months and days, then exact milliseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Span {
+    months: i64,
+    days: i64,
+    millis: i64,
+}
+
+impl Span {
+    fn add_unit(&mut self, amount: f64, unit: &str) -> Result<(), ToolError> {
+        let whole = |amount: f64| {
+            if amount.fract() == 0.0 {
+                Ok(amount as i64)
+            } else {
+                Err(ToolError::InvalidInput(format!("{} must be a whole number of {}", amount, unit)))
+            }
+        };
+        match unit {
+            "y" | "yr" | "yrs" | "year" | "years" => self.months += 12 * whole(amount)?,
+            "mo" | "mon" | "month" | "months" => self.months += whole(amount)?,
+            "w" | "wk" | "week" | "weeks" => self.days += 7 * whole(amount)?,
+            "d" | "day" | "days" => self.days += whole(amount)?,
+            "h" | "hr" | "hrs" | "hour" | "hours" => self.millis += (amount * 3_600_000.0) as i64,
+            "m" | "min" | "mins" | "minute" | "minutes" => self.millis += (amount * 60_000.0) as i64,
+            "s" | "sec" | "secs" | "second" | "seconds" => self.millis += (amount * 1000.0) as i64,
+            "ms" | "millisecond" | "milliseconds" => self.millis += amount as i64,
+            _ => return Err(ToolError::InvalidInput(format!("Unknown duration unit: {}", unit))),
+        }
+        Ok(())
+    }
+
+    fn negated(self) -> Self {
+        Self { months: -self.months, days: -self.days, millis: -self.millis }
+    }
+
+    /// Parses "1d 2h 30m", "-3 weeks", "1.5 hours" or ISO 8601 ("P1Y2M3DT4H5M6S").
+    fn parse(input: &str) -> Result<Self, ToolError> {
+        let input = input.trim();
+        let (negative, body) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        let invalid = || ToolError::InvalidInput(format!("Invalid duration: {}", input));
+
+        let mut span = Span::default();
+        if let Some(iso) = body.strip_prefix('P').or_else(|| body.strip_prefix('p')) {
+            let mut in_time = false;
+            let mut number = String::new();
+            for c in iso.chars() {
+                match c.to_ascii_uppercase() {
+                    'T' => in_time = true,
+                    c if c.is_ascii_digit() || c == '.' || c == ',' => number.push(if c == ',' { '.' } else { c }),
+                    unit => {
+                        let amount: f64 = number.parse().map_err(|_| invalid())?;
+                        number.clear();
+                        let unit = match (unit, in_time) {
+                            ('Y', false) => "y",
+                            ('M', false) => "mo",
+                            ('W', false) => "w",
+                            ('D', false) => "d",
+                            ('H', true) => "h",
+                            ('M', true) => "m",
+                            ('S', true) => "s",
+                            _ => return Err(invalid()),
+                        };
+                        span.add_unit(amount, unit)?;
+                    }
+                }
+            }
+            if !number.is_empty() || iso.is_empty() {
+                return Err(invalid());
+            }
+        } else {
+            // Split "1d2h" and "1 d 2 h" alike into (number, unit) pairs
+            let mut rest = body;
+            while !rest.trim_start().is_empty() {
+                rest = rest.trim_start().trim_start_matches(',').trim_start();
+                let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+                let amount: f64 = rest[..number_end].parse().map_err(|_| invalid())?;
+                rest = rest[number_end..].trim_start();
+                let unit_end = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+                if unit_end == 0 {
+                    return Err(invalid());
+                }
+                span.add_unit(amount, &rest[..unit_end].to_lowercase())?;
+                rest = &rest[unit_end..];
+            }
+            if body.is_empty() {
+                return Err(invalid());
+            }
+        }
+        Ok(if negative { span.negated() } else { span })
+    }
+
+    fn apply(self, time: DateTime<Tz>) -> Result<DateTime<Tz>, ToolError> {
+        let overflow = || ToolError::InvalidInput("Result is out of range".to_string());
+        let mut local = time.naive_local();
+        if self.months != 0 {
+            let months = Months::new(u32::try_from(self.months.unsigned_abs()).map_err(|_| overflow())?);
+            local = if self.months > 0 { local.checked_add_months(months) } else { local.checked_sub_months(months) }
+                .ok_or_else(overflow)?;
+        }
+        if self.days != 0 {
+            let days = Days::new(self.days.unsigned_abs());
+            local = if self.days > 0 { local.checked_add_days(days) } else { local.checked_sub_days(days) }
+                .ok_or_else(overflow)?;
+        }
+        let calendar = if self.months == 0 && self.days == 0 { time } else { localize(local, time.timezone())? };
+        calendar.checked_add_signed(Duration::milliseconds(self.millis)).ok_or_else(overflow)
+    }
+}
+
+/// Whole calendar months from `start` to a later `end`.
+fn months_between(start: DateTime<Tz>, end: DateTime<Tz>) -> u32 {
+    let (from, to) = (start.naive_local(), end.naive_local());
+    let mut months = ((to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32).max(0) as u32;
+    while months > 0 && from.checked_add_months(Months::new(months)).is_none_or(|t| t > to) {
+        months -= 1;
+    }
+    months
+}
+
+/// "1 day, 2 hours and 5 minutes".
+fn humanize(total_seconds: i64) -> String {
+    let seconds = total_seconds.unsigned_abs();
+    let parts: Vec<String> = [(86_400, "day"), (3_600, "hour"), (60, "minute"), (1, "second")]
+        .iter()
+        .scan(seconds, |left, (size, name)| {
+            let count = *left / size;
+            *left %= size;
+            Some((count, *name))
+        })
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{} {}{}", count, name, if count == 1 { "" } else { "s" }))
+        .collect();
+    match parts.as_slice() {
+        [] => "0 seconds".to_string(),
+        [one] => one.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+// ============================================================================
+// Time Parse Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimeParseArgs {
+    /// Time to parse: ISO 8601/RFC 3339, RFC 2822, '2024-03-01 14:30', 'March 1, 2024', '01.03.2024', unix seconds or milliseconds, 'now', 'today', 'tomorrow', 'yesterday'
+    time: String,
+    /// strftime layout the input must match, e.g. '%d/%m/%y %Hh%M'
+    #[serde(default)]
+    format: Option<String>,
+    /// IANA timezone for times without an offset, and of the result (default: server default_timezone)
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Read 01/02/2024 as 1 February rather than January 2 (default: false)
+    #[serde(default)]
+    day_first: bool,
+}
+
+#[derive(Debug)]
+pub struct TimeParseTool;
+
+#[async_trait]
+impl TypedTool for TimeParseTool {
+    type Args = TimeParseArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "time.parse";
+    const DESCRIPTION: &'static str =
+        "Parses a date or time in common formats (or an explicit strftime format) into RFC 3339, unix timestamp, weekday and offset.";
+
+    async fn run(&self, args: TimeParseArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let tz = parse_timezone(args.timezone.as_deref(), &state)?;
+        let time = parse_time(&args.time, tz, args.format.as_deref(), args.day_first)?;
+        Ok(ToolOutput::structured(describe(time)))
+    }
+}
+
+// ============================================================================
+// Time Format Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimeFormatArgs {
+    /// Time in any form time.parse accepts
+    time: String,
+    /// strftime format (e.g. '%A %d %B %Y, %H:%M'), or 'rfc3339', 'rfc2822', 'unix', 'unix_millis'
+    format: String,
+    /// IANA timezone to show the time in (default: server default_timezone)
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Locale for month and day names, e.g. 'fr_FR'
+    #[serde(default)]
+    locale: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TimeFormatTool;
+
+#[async_trait]
+impl TypedTool for TimeFormatTool {
+    type Args = TimeFormatArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "time.format";
+    const DESCRIPTION: &'static str =
+        "Formats a time with a strftime format (optionally localized) or as RFC 3339, RFC 2822 or a unix timestamp.";
+
+    async fn run(&self, args: TimeFormatArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let tz = parse_timezone(args.timezone.as_deref(), &state)?;
+        let time = parse_time(&args.time, tz, None, false)?;
+        let formatted = match args.format.as_str() {
+            "rfc3339" => time.to_rfc3339(),
+            "rfc2822" => time.to_rfc2822(),
+            "unix" => time.timestamp().to_string(),
+            "unix_millis" => time.timestamp_millis().to_string(),
+            format => {
+                check_format(format)?;
+                match args.locale.as_deref() {
+                    Some(name) => {
+                        let locale = Locale::try_from(name)
+                            .map_err(|_| ToolError::InvalidInput(format!("Unknown locale: {}", name)))?;
+                        time.format_localized(format, locale).to_string()
+                    }
+                    None => time.format(format).to_string(),
+                }
+            }
+        };
+        Ok(ToolOutput::text(formatted))
+    }
+}
+
+// ============================================================================
+// Time Add Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimeAddArgs {
+    /// Start time in any form time.parse accepts (default: now)
+    #[serde(default)]
+    time: Option<String>,
+    /// Duration such as '1d 2h 30m', '-3 weeks', '1.5 hours' or ISO 8601 'P1M2DT3H'
+    #[serde(default)]
+    duration: Option<String>,
+    /// Calendar years to add (combined with duration and the other amounts)
+    #[serde(default)]
+    years: Option<i64>,
+    /// Calendar months to add
+    #[serde(default)]
+    months: Option<i64>,
+    /// Weeks to add
+    #[serde(default)]
+    weeks: Option<i64>,
+    /// Calendar days to add
+    #[serde(default)]
+    days: Option<i64>,
+    /// Hours to add
+    #[serde(default)]
+    hours: Option<f64>,
+    /// Minutes to add
+    #[serde(default)]
+    minutes: Option<f64>,
+    /// Seconds to add
+    #[serde(default)]
+    seconds: Option<f64>,
+    /// IANA timezone the calendar arithmetic and result use (default: server default_timezone)
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TimeAddTool;
+
+#[async_trait]
+impl TypedTool for TimeAddTool {
+    type Args = TimeAddArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "time.add";
+    const DESCRIPTION: &'static str =
+        "Adds (or, with negative amounts, subtracts) a duration to a time. Months and years follow the calendar (Jan 31 + 1 month = Feb 29/28), days keep the wall clock time across DST changes.";
+
+    async fn run(&self, args: TimeAddArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let tz = parse_timezone(args.timezone.as_deref(), &state)?;
+        let start = parse_time(args.time.as_deref().unwrap_or("now"), tz, None, false)?;
+
+        let mut span = match &args.duration {
+            Some(duration) => Span::parse(duration)?,
+            None => Span::default(),
+        };
+        let whole = [(args.years, "y"), (args.months, "mo"), (args.weeks, "w"), (args.days, "d")];
+        let fractional = [(args.hours, "h"), (args.minutes, "m"), (args.seconds, "s")];
+        let given = whole.iter().filter(|(v, _)| v.is_some()).count() + fractional.iter().filter(|(v, _)| v.is_some()).count();
+        if args.duration.is_none() && given == 0 {
+            return Err(ToolError::InvalidInput("Pass 'duration' or at least one of years..seconds".to_string()));
+        }
+        for (amount, unit) in whole {
+            if let Some(amount) = amount {
+                span.add_unit(amount as f64, unit)?;
+            }
+        }
+        for (amount, unit) in fractional {
+            if let Some(amount) = amount {
+                span.add_unit(amount, unit)?;
+            }
+        }
+
+        let mut result = describe(span.apply(start)?);
+        result["start"] = json!(start.to_rfc3339());
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+// ============================================================================
+// Time Diff Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimeDiffArgs {
+    /// Start time in any form time.parse accepts
+    start: String,
+    /// End time (default: now)
+    #[serde(default)]
+    end: Option<String>,
+    /// IANA timezone for times without an offset and for counting calendar months (default: server default_timezone)
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TimeDiffTool;
+
+#[async_trait]
+impl TypedTool for TimeDiffTool {
+    type Args = TimeDiffArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "time.diff";
+    const DESCRIPTION: &'static str =
+        "Computes the time between two times: total seconds, minutes, hours and days, calendar years/months/days, and a readable form. Negative when end is before start.";
+
+    async fn run(&self, args: TimeDiffArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let tz = parse_timezone(args.timezone.as_deref(), &state)?;
+        let start = parse_time(&args.start, tz, None, false)?;
+        let end = parse_time(args.end.as_deref().unwrap_or("now"), tz, None, false)?;
+
+        let millis = end.signed_duration_since(start).num_milliseconds();
+        let seconds = millis / 1000;
+        let sign = if millis < 0 { -1 } else { 1 };
+        let (earlier, later) = if millis < 0 { (end, start) } else { (start, end) };
+
+        let months = months_between(earlier, later);
+        let after_months = localize(
+            earlier.naive_local().checked_add_months(Months::new(months)).unwrap_or(earlier.naive_local()),
+            tz,
+        )?;
+        let rest = later.signed_duration_since(after_months);
+
+        Ok(ToolOutput::structured(json!({
+            "start": start.to_rfc3339(),
+            "end": end.to_rfc3339(),
+            "seconds": seconds,
+            "minutes": millis as f64 / 60_000.0,
+            "hours": millis as f64 / 3_600_000.0,
+            "days": millis as f64 / 86_400_000.0,
+            "calendar": {
+                "years": sign * (months / 12) as i64,
+                "months": sign * (months % 12) as i64,
+                "days": sign * rest.num_days(),
+                "hours": sign * (rest.num_hours() % 24),
+                "minutes": sign * (rest.num_minutes() % 60),
+                "seconds": sign * (rest.num_seconds() % 60),
+            },
+            "human": format!("{}{}", if millis < 0 { "-" } else { "" }, humanize(seconds)),
+        })))
+    }
+}
+
+// ============================================================================
+// Time Convert Timezone Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimeConvertTzArgs {
+    /// Time in any form time.parse accepts (default: now)
+    #[serde(default)]
+    time: Option<String>,
+    /// IANA timezone to convert to, e.g. 'America/New_York'
+    to: String,
+    /// IANA timezone of a time without an offset (default: server default_timezone)
+    #[serde(default)]
+    from: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TimeConvertTzTool;
+
+#[async_trait]
+impl TypedTool for TimeConvertTzTool {
+    type Args = TimeConvertTzArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "time.convert_tz";
+    const DESCRIPTION: &'static str =
+        "Converts a time to another timezone, e.g. '2024-03-01 09:00' in Europe/Berlin to America/New_York.";
+
+    async fn run(&self, args: TimeConvertTzArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let from = parse_timezone(args.from.as_deref(), &state)?;
+        let to = parse_timezone(Some(&args.to), &state)?;
+        let time = parse_time(args.time.as_deref().unwrap_or("now"), from, None, false)?;
+
+        let mut result = describe(time.with_timezone(&to));
+        result["from"] = json!({
+            "time": time.to_rfc3339(),
+            "timezone": from.name(),
+        });
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn state() -> Arc<RuntimeState> {
+        Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".into()), ..Config::default() }))
+    }
+
+    async fn call<T: TypedTool<Output = ToolOutput>>(tool: T, args: Value) -> Result<Value, ToolError> {
+        let output = tool.run(serde_json::from_value(args).unwrap(), state()).await?;
+        Ok(output.structured_content.unwrap_or_default())
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let expected = "2024-03-01T14:30:00+01:00";
+        for input in ["2024-03-01T14:30:00+01:00", "2024-03-01 14:30", "01.03.2024 14:30", "March 1, 2024 14:30", "03/01/2024 14:30", "Fri, 01 Mar 2024 13:30:00 +0000", "2024-03-01 13:30:00 UTC", "1709299800"] {
+            assert_eq!(parse_time(input, berlin, None, false).unwrap().to_rfc3339(), expected, "{}", input);
+        }
+        assert_eq!(parse_time("01/03/2024", berlin, None, true).unwrap().date_naive().to_string(), "2024-03-01");
+        assert_eq!(parse_time("1/3/24 14h30", berlin, Some("%d/%m/%y %Hh%M"), false).unwrap().to_rfc3339(), expected);
+        assert!(parse_time("next thursday-ish", berlin, None, false).is_err());
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(Span::parse("1d 2h 30m").unwrap(), Span { months: 0, days: 1, millis: 9_000_000 });
+        assert_eq!(Span::parse("-3 weeks").unwrap(), Span { months: 0, days: -21, millis: 0 });
+        assert_eq!(Span::parse("P1Y2M3DT4H").unwrap(), Span { months: 14, days: 3, millis: 14_400_000 });
+        assert!(Span::parse("3 fortnights").is_err());
+        assert_eq!(humanize(93_784), "1 day, 2 hours, 3 minutes and 4 seconds");
+    }
+
+    #[tokio::test]
+    async fn test_add_across_month_ends_and_dst() {
+        let end_of_month = call(TimeAddTool, json!({ "time": "2024-01-31 10:00", "months": 1, "timezone": "UTC" })).await.unwrap();
+        assert_eq!(end_of_month["date"], "2024-02-29");
+
+        // Berlin moves clocks forward on 2024-03-31: a day later keeps 12:00, 24 hours later doesn't
+        let day = call(TimeAddTool, json!({ "time": "2024-03-30 12:00", "duration": "1d", "timezone": "Europe/Berlin" })).await.unwrap();
+        assert_eq!(day["time"], "2024-03-31T12:00:00+02:00");
+        let hours = call(TimeAddTool, json!({ "time": "2024-03-30 12:00", "hours": 24, "timezone": "Europe/Berlin" })).await.unwrap();
+        assert_eq!(hours["time"], "2024-03-31T13:00:00+02:00");
+    }
+
+    #[tokio::test]
+    async fn test_diff_and_convert() {
+        let diff = call(TimeDiffTool, json!({ "start": "2024-01-15 08:00", "end": "2024-03-16 10:30", "timezone": "UTC" })).await.unwrap();
+        assert_eq!(diff["calendar"], json!({ "years": 0, "months": 2, "days": 1, "hours": 2, "minutes": 30, "seconds": 0 }));
+        assert_eq!(diff["seconds"], 5_279_400);
+
+        let backwards = call(TimeDiffTool, json!({ "start": "2024-01-02", "end": "2024-01-01", "timezone": "UTC" })).await.unwrap();
+        assert_eq!(backwards["human"], "-1 day");
+
+        let converted = call(TimeConvertTzTool, json!({ "time": "2024-07-01 09:00", "from": "Europe/Berlin", "to": "America/New_York" })).await.unwrap();
+        assert_eq!(converted["time"], "2024-07-01T03:00:00-04:00");
+        assert!(call(TimeConvertTzTool, json!({ "to": "Mars/Olympus" })).await.is_err());
+    }
+}
@@ -34,6 +34,7 @@ impl Tool for EchoTool {
                 },
                 "required": ["text"]
             }),
+            output_schema: None,
         }
     }
 
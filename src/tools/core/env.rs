@@ -1,14 +1,60 @@
 //! Environment variable tools.
+//!
+//! `env.get` and `env.list` only see the variables matching
+//! `security.allowed_env_vars` and none of `security.denied_env_vars`.
+//! With `security.redact_env_values`, values of variables that look like
+//! credentials are masked.
 
 use async_trait::async_trait;
+use globset::Glob;
 use serde_json::{json, Value};
+use std::borrow::Cow;
 use std::env;
 use std::sync::Arc;
 
+use crate::core::config::SecurityConfig;
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::secrets::scrub::{scrub, REDACTED};
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
 
+/// Name parts of variables whose values are masked.
+const SECRET_NAME_PARTS: &[&str] = &[
+    "TOKEN", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL", "PRIVATE", "API_KEY", "APIKEY", "ACCESS_KEY", "AUTH",
+];
+
+/// Whether `name` matches one of the glob `patterns` ("*" matches all).
+pub(super) fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        pattern == "*"
+            || Glob::new(pattern)
+                .map(|glob| glob.compile_matcher())
+                .is_ok_and(|matcher| matcher.is_match(name))
+    })
+}
+
+/// Whether the env tools may read a variable.
+pub(crate) fn is_env_var_exposed(security: &SecurityConfig, name: &str) -> bool {
+    !matches_any(&security.denied_env_vars, name) && matches_any(&security.allowed_env_vars, name)
+}
+
+/// Whether a variable's name or value looks like a credential.
+fn looks_secret(name: &str, value: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+        || name.ends_with("_KEY")
+        || matches!(scrub(value, &[]), Cow::Owned(_))
+}
+
+/// A variable's value as the env tools show it.
+fn shown_value(security: &SecurityConfig, name: &str, value: String) -> String {
+    if security.redact_env_values && looks_secret(name, &value) {
+        REDACTED.to_string()
+    } else {
+        value
+    }
+}
+
 /// Tool to get an environment variable.
 #[derive(Debug)]
 pub struct EnvGetTool;
@@ -18,7 +64,10 @@ impl Tool for EnvGetTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "env.get".to_string(),
-            description: Some("Gets the value of an environment variable.".to_string()),
+            description: Some(
+                "Gets the value of an environment variable. Values that look like credentials are masked."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -33,27 +82,37 @@ impl Tool for EnvGetTool {
                 },
                 "required": ["key"]
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let key = arguments
             .get("key")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidInput("Missing 'key'".to_string()))?;
 
+        let security = &state.config.security;
+        if !is_env_var_exposed(security, key) {
+            return Err(ToolError::PermissionDenied(format!("Environment variable not allowed: {}", key)));
+        }
+
         let default = arguments.get("default").and_then(|v| v.as_str());
 
-        let value = env::var(key).ok().or_else(|| default.map(|s| s.to_string()));
+        let found = env::var(key).ok();
+        let value = match &found {
+            Some(value) => Some(shown_value(security, key, value.clone())),
+            None => default.map(|s| s.to_string()),
+        };
 
         let result = json!({
             "key": key,
             "value": value,
-            "found": env::var(key).is_ok()
+            "found": found.is_some()
         });
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
@@ -70,7 +129,7 @@ impl Tool for EnvListTool {
         ToolDefinition {
             name: "env.list".to_string(),
             description: Some(
-                "Lists environment variable names (not values for security). Use prefix to filter."
+                "Lists environment variable names (not values for security). Use prefix to filter. Credential-like values are masked when shown."
                     .to_string(),
             ),
             input_schema: json!({
@@ -86,21 +145,23 @@ impl Tool for EnvListTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
+        let security = &state.config.security;
         let prefix = arguments.get("prefix").and_then(|v| v.as_str());
         let show_values = arguments
             .get("show_values")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        let vars: Vec<Value> = env::vars()
+        let mut vars: Vec<(String, String)> = env::vars()
             .filter(|(k, _)| {
                 if let Some(p) = prefix {
                     k.starts_with(p)
@@ -108,8 +169,15 @@ impl Tool for EnvListTool {
                     true
                 }
             })
+            .filter(|(k, _)| is_env_var_exposed(security, k))
+            .collect();
+        vars.sort();
+
+        let vars: Vec<Value> = vars
+            .into_iter()
             .map(|(k, v)| {
                 if show_values {
+                    let v = shown_value(security, &k, v);
                     json!({"key": k, "value": v})
                 } else {
                     json!({"key": k})
@@ -140,6 +208,7 @@ impl Tool for SysInfoTool {
                 "type": "object",
                 "properties": {}
             }),
+            output_schema: None,
         }
     }
 
@@ -170,3 +239,41 @@ impl Tool for SysInfoTool {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    #[tokio::test]
+    async fn test_env_access_is_restricted() {
+        std::env::set_var("ENV_TOOL_TEST_REGION", "eu-west-1");
+        std::env::set_var("ENV_TOOL_TEST_API_TOKEN", "t0ken-value");
+        std::env::set_var("ENV_TOOL_TEST_HIDDEN", "hidden");
+        let mut config = Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        };
+        config.security.allowed_env_vars = vec!["ENV_TOOL_TEST_*".to_string()];
+        config.security.denied_env_vars = vec!["*_HIDDEN".to_string()];
+        let state = Arc::new(RuntimeState::new(config));
+
+        let get = |key: &str| EnvGetTool.execute(json!({"key": key}), state.clone());
+        let value = |output: ToolOutput| -> Value {
+            serde_json::from_str(&crate::tools::middleware::output_text(&output)).unwrap()
+        };
+        assert_eq!(value(get("ENV_TOOL_TEST_REGION").await.unwrap())["value"], "eu-west-1");
+        assert_eq!(value(get("ENV_TOOL_TEST_API_TOKEN").await.unwrap())["value"], REDACTED);
+        assert!(matches!(get("ENV_TOOL_TEST_HIDDEN").await, Err(ToolError::PermissionDenied(_))));
+        assert!(matches!(get("HOME").await, Err(ToolError::PermissionDenied(_))));
+
+        let listed = value(EnvListTool.execute(json!({"show_values": true}), state.clone()).await.unwrap());
+        assert_eq!(
+            listed["variables"],
+            json!([
+                {"key": "ENV_TOOL_TEST_API_TOKEN", "value": REDACTED},
+                {"key": "ENV_TOOL_TEST_REGION", "value": "eu-west-1"}
+            ])
+        );
+    }
+}
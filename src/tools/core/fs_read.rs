@@ -1,16 +1,34 @@
 //! File system read tool - reads file contents.
+//!
+//! Large files can be read a page at a time (`offset`/`limit` in lines or
+//! bytes) or from the end (`tail`). Binary files are returned as base64
+//! with an inferred MIME type; images read whole come back as image content,
+//! up to `output_limit.max_binary_bytes`. A second content block describes
+//! the read: file size, modification time and where the next page starts.
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::fs;
 use tracing::debug;
 
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::{Tool, ToolError, ToolOutput};
+use crate::tools::{roots, Tool, ToolContent, ToolError, ToolOutput};
+
+/// Lines returned by `tail` without a `limit`.
+const DEFAULT_TAIL_LINES: u64 = 100;
+
+/// Bytes returned by `tail` in byte mode without a `limit`.
+const DEFAULT_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Bytes inspected to tell text from binary.
+const SNIFF_BYTES: usize = 8192;
 
 /// File read tool - reads content from allowed paths.
 #[derive(Debug)]
@@ -21,6 +39,46 @@ pub struct FsReadTool {
 #[derive(Deserialize)]
 struct FsReadArgs {
     path: String,
+    #[serde(default)]
+    offset: u64,
+    #[serde(default)]
+    limit: Option<u64>,
+    #[serde(default)]
+    unit: Option<ReadUnit>,
+    #[serde(default)]
+    tail: bool,
+    #[serde(default)]
+    encoding: Encoding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ReadUnit {
+    Lines,
+    Bytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Encoding {
+    /// Text, or base64 for files that look binary.
+    #[default]
+    Auto,
+    Text,
+    Base64,
+}
+
+/// The part of a file that was read.
+struct Page {
+    data: Vec<u8>,
+    /// Index of the first line or byte returned.
+    start: u64,
+    /// Lines or bytes returned.
+    returned: u64,
+    /// Total lines, when the whole file was scanned.
+    total_lines: Option<u64>,
+    /// Whether there is more past the page (before it, for `tail`).
+    has_more: bool,
 }
 
 impl FsReadTool {
@@ -31,26 +89,63 @@ impl FsReadTool {
 
     /// Checks if a path is within the allowed directories.
     fn is_path_allowed(&self, path: &Path) -> bool {
-        if self.allowed_paths.is_empty() {
-            // If no paths configured, deny all
-            return false;
-        }
+        is_path_allowed(&self.allowed_paths, path)
+    }
+}
 
-        let canonical = match path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
+/// A whole file read to be sent as binary content.
+pub(crate) struct FileData {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+    /// Whether the content looks binary rather than text.
+    pub binary: bool,
+}
 
-        for allowed in &self.allowed_paths {
-            if let Ok(allowed_canonical) = allowed.canonicalize() {
-                if canonical.starts_with(&allowed_canonical) {
-                    return true;
-                }
-            }
-        }
+/// Reads a whole file from the allowed directories to send on as binary
+/// content (an image part, a resource blob, an upload), refusing files over
+/// `max_bytes`.
+pub(crate) fn read_allowed_file(allowed_paths: &[PathBuf], path: &str, max_bytes: usize) -> Result<FileData, ToolError> {
+    let file_path = Path::new(path);
+    if !file_path.exists() {
+        return Err(ToolError::ExecutionFailed(format!("File not found: {}", path)));
+    }
+    if !is_path_allowed(allowed_paths, file_path) {
+        return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", path)));
+    }
 
-        false
+    let io_err = |e: std::io::Error| ToolError::ExecutionFailed(format!("Failed to read file: {}", e));
+    let size = std::fs::metadata(file_path).map_err(io_err)?.len();
+    if size > max_bytes as u64 {
+        return Err(ToolError::InvalidInput(format!(
+            "{} is {} bytes; the limit is {}",
+            path, size, max_bytes
+        )));
     }
+    let data = std::fs::read(file_path).map_err(io_err)?;
+    let head = &data[..SNIFF_BYTES.min(data.len())];
+    let binary = looks_binary(head);
+    Ok(FileData { mime_type: mime_type(file_path, head, binary), binary, data })
+}
+
+/// Whether content of a MIME type is sent as image content. SVG stays text:
+/// few clients or models render it.
+pub(crate) fn is_image(mime_type: &str) -> bool {
+    mime_type.starts_with("image/") && mime_type != "image/svg+xml"
+}
+
+/// Checks if an existing path is within one of the allowed directories and
+/// the client's roots. An empty list allows nothing.
+pub(crate) fn is_path_allowed(allowed_paths: &[PathBuf], path: &Path) -> bool {
+    let canonical = match path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+
+    allowed_paths.iter().any(|allowed| {
+        allowed
+            .canonicalize()
+            .is_ok_and(|allowed_canonical| canonical.starts_with(&allowed_canonical))
+    }) && roots::allows(&canonical)
 }
 
 #[async_trait]
@@ -58,24 +153,52 @@ impl Tool for FsReadTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "fs.read_file".to_string(),
-            description: Some("Reads the contents of a file. Only allowed paths can be accessed.".to_string()),
+            description: Some(
+                "Reads the contents of a file. Only allowed paths can be accessed. Large files can be read in pages \
+                 (offset/limit in lines or bytes) or from the end (tail); binary files are returned as base64, \
+                 and images as image content."
+                    .to_string(),
+            ),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
                     "path": {
                         "type": "string",
                         "description": "The path to the file to read"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Lines or bytes to skip (from the end with tail). Default: 0"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Max lines or bytes to return. Default: the rest of the file (100 lines or 64 KiB with tail)"
+                    },
+                    "unit": {
+                        "type": "string",
+                        "enum": ["lines", "bytes"],
+                        "description": "What offset and limit count. Default: lines for text, bytes for binary files"
+                    },
+                    "tail": {
+                        "type": "boolean",
+                        "description": "Read from the end of the file, e.g. the last lines of a log"
+                    },
+                    "encoding": {
+                        "type": "string",
+                        "enum": ["auto", "text", "base64"],
+                        "description": "auto returns binary files as base64 and everything else as text (default: auto)"
                     }
                 },
                 "required": ["path"]
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let args: FsReadArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
@@ -100,12 +223,320 @@ impl Tool for FsReadTool {
             )));
         }
 
-        // Read the file
-        let content = fs::read_to_string(&path)
+        let max_image_bytes = state.config.output_limit.max_binary_bytes;
+        tokio::task::spawn_blocking(move || read_file(&path, &args, max_image_bytes))
             .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read file: {}", e)))?;
+            .map_err(|e| ToolError::Internal(e.to_string()))?
+    }
+}
+
+/// Reads the requested page of `path`. A whole image is returned as image
+/// content unless it is over `max_image_bytes`; pages of one are base64 text.
+fn read_file(path: &Path, args: &FsReadArgs, max_image_bytes: usize) -> Result<ToolOutput, ToolError> {
+    let io_err = |e: std::io::Error| ToolError::ExecutionFailed(format!("Failed to read file: {}", e));
+
+    let metadata = std::fs::metadata(path).map_err(io_err)?;
+    if !metadata.is_file() {
+        return Err(ToolError::ExecutionFailed(format!("Not a file: {}", args.path)));
+    }
+    let mut file = File::open(path).map_err(io_err)?;
+
+    let mut head = vec![0; SNIFF_BYTES.min(metadata.len() as usize)];
+    file.read_exact(&mut head).map_err(io_err)?;
+    file.rewind().map_err(io_err)?;
+
+    let binary = match args.encoding {
+        Encoding::Auto => looks_binary(&head),
+        Encoding::Text => false,
+        Encoding::Base64 => true,
+    };
+    let unit = match (args.unit, binary) {
+        (Some(ReadUnit::Lines), true) => {
+            return Err(ToolError::InvalidInput(
+                "Binary content can only be read in bytes; use unit 'bytes' or encoding 'text'".to_string(),
+            ));
+        }
+        (Some(unit), _) => unit,
+        (None, true) => ReadUnit::Bytes,
+        (None, false) => ReadUnit::Lines,
+    };
+
+    let page = match unit {
+        ReadUnit::Bytes => read_bytes(&mut file, metadata.len(), args.offset, args.limit, args.tail),
+        ReadUnit::Lines => read_lines(BufReader::new(file), args.offset, args.limit, args.tail),
+    }
+    .map_err(io_err)?;
+
+    let mime_type = mime_type(path, &head, binary);
+    let whole_image = binary && is_image(mime_type) && page.start == 0 && !page.has_more;
+    if whole_image && page.data.len() > max_image_bytes {
+        return Err(ToolError::InvalidInput(format!(
+            "{} is a {} byte image, over the {} byte limit for image content (output_limit.max_binary_bytes); \
+             read it in pages with offset/limit",
+            args.path,
+            page.data.len(),
+            max_image_bytes
+        )));
+    }
+    let content = if binary {
+        let data = BASE64.encode(&page.data);
+        if whole_image {
+            ToolContent::Image { data, mime_type: mime_type.to_string() }
+        } else {
+            ToolContent::Text { text: data }
+        }
+    } else {
+        ToolContent::Text { text: String::from_utf8_lossy(&page.data).into_owned() }
+    };
+
+    let mut info = json!({
+        "path": args.path,
+        "size": metadata.len(),
+        "modified": metadata.modified().ok().map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        "mime_type": mime_type,
+        "encoding": if binary { "base64" } else { "utf-8" },
+        "unit": if unit == ReadUnit::Lines { "lines" } else { "bytes" },
+        "offset": page.start,
+        "returned": page.returned,
+        "has_more": page.has_more
+    });
+    if let Some(total_lines) = page.total_lines {
+        info["total_lines"] = json!(total_lines);
+    }
+    if page.has_more && !args.tail {
+        info["next_offset"] = json!(page.start + page.returned);
+    }
+
+    Ok(ToolOutput {
+        content: vec![content, ToolContent::Text { text: info.to_string() }],
+        is_error: false,
+        structured_content: None,
+    })
+}
 
-        Ok(ToolOutput::text(content))
+/// Reads a byte range, counted from the end with `tail`.
+fn read_bytes(file: &mut File, size: u64, offset: u64, limit: Option<u64>, tail: bool) -> std::io::Result<Page> {
+    let (start, end) = if tail {
+        let end = size.saturating_sub(offset);
+        (end.saturating_sub(limit.unwrap_or(DEFAULT_TAIL_BYTES)), end)
+    } else {
+        let start = offset.min(size);
+        (start, limit.map_or(size, |limit| start.saturating_add(limit).min(size)))
+    };
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut data = Vec::with_capacity((end - start) as usize);
+    file.take(end - start).read_to_end(&mut data)?;
+    Ok(Page {
+        returned: data.len() as u64,
+        data,
+        start,
+        total_lines: None,
+        has_more: if tail { start > 0 } else { end < size },
+    })
+}
+
+/// Reads whole lines, counted from the end with `tail`. Only the lines
+/// kept are held in memory, so tailing a large log is cheap.
+fn read_lines(mut reader: impl BufRead, offset: u64, limit: Option<u64>, tail: bool) -> std::io::Result<Page> {
+    let mut line = Vec::new();
+    let mut next_line = |reader: &mut dyn BufRead| -> std::io::Result<Option<Vec<u8>>> {
+        line.clear();
+        Ok((reader.read_until(b'\n', &mut line)? > 0).then(|| line.clone()))
+    };
+
+    if tail {
+        let limit = limit.unwrap_or(DEFAULT_TAIL_LINES);
+        let keep = offset.saturating_add(limit) as usize;
+        let mut window = VecDeque::new();
+        let mut total = 0u64;
+        while let Some(line) = next_line(&mut reader)? {
+            total += 1;
+            if keep == 0 {
+                continue;
+            }
+            if window.len() == keep {
+                window.pop_front();
+            }
+            window.push_back(line);
+        }
+        // The window ends with the `offset` lines being skipped
+        window.truncate(window.len().saturating_sub(offset as usize));
+        let returned = window.len() as u64;
+        let start = total.saturating_sub(offset).saturating_sub(returned);
+        return Ok(Page {
+            data: window.into_iter().flatten().collect(),
+            start,
+            returned,
+            total_lines: Some(total),
+            has_more: start > 0,
+        });
+    }
+
+    let mut skipped = 0;
+    while skipped < offset {
+        if next_line(&mut reader)?.is_none() {
+            break;
+        }
+        skipped += 1;
+    }
+    let mut data = Vec::new();
+    let mut returned = 0;
+    while limit.is_none_or(|limit| returned < limit) {
+        match next_line(&mut reader)? {
+            Some(line) => data.extend_from_slice(&line),
+            None => {
+                return Ok(Page { data, start: skipped, returned, total_lines: Some(skipped + returned), has_more: false });
+            }
+        }
+        returned += 1;
+    }
+    let has_more = !reader.fill_buf()?.is_empty();
+    Ok(Page { data, start: skipped, returned, total_lines: None, has_more })
+}
+
+/// Whether the start of a file looks like binary data: NUL bytes, or
+/// bytes that aren't UTF-8 (a character cut off at the end is fine).
+fn looks_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
     }
 }
 
+/// MIME type from the file's magic bytes, then its extension.
+fn mime_type(path: &Path, head: &[u8], binary: bool) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"SQLite format 3\0", "application/vnd.sqlite3"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "mp3" | "mpga" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "m4a" => "audio/mp4",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "webm" => "audio/webm",
+        "mp4" => "video/mp4",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "wasm" => "application/wasm",
+        _ if binary => "application/octet-stream",
+        _ => "text/plain",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn text(output: &ToolOutput, index: usize) -> String {
+        match &output.content[index] {
+            ToolContent::Text { text } => text.clone(),
+            ToolContent::Image { data, .. } => data.clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_tail_and_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        let lines: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        std::fs::write(&log, &lines).unwrap();
+        std::fs::write(dir.path().join("pixel.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let tool = FsReadTool::new(vec![dir.path().to_path_buf()]);
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let read = |args: Value| {
+            let (tool, state) = (&tool, state.clone());
+            async move {
+                let output = tool.execute(args, state).await.unwrap();
+                let info: Value = serde_json::from_str(&text(&output, 1)).unwrap();
+                (output, info)
+            }
+        };
+        let path = log.to_str().unwrap();
+
+        // A plain read still returns the whole file first
+        let (output, info) = read(json!({"path": path})).await;
+        assert_eq!(text(&output, 0), lines);
+        assert_eq!((info["size"].as_u64(), info["total_lines"].as_u64()), (Some(lines.len() as u64), Some(10)));
+
+        let (output, info) = read(json!({"path": path, "offset": 2, "limit": 3})).await;
+        assert_eq!(text(&output, 0), "line 3\nline 4\nline 5\n");
+        assert_eq!((info["has_more"].as_bool(), info["next_offset"].as_u64()), (Some(true), Some(5)));
+
+        let (output, info) = read(json!({"path": path, "tail": true, "limit": 2})).await;
+        assert_eq!(text(&output, 0), "line 9\nline 10\n");
+        assert_eq!(info["offset"], 8);
+
+        let (output, _) = read(json!({"path": path, "tail": true, "offset": 1, "limit": 2})).await;
+        assert_eq!(text(&output, 0), "line 8\nline 9\n");
+
+        let (output, info) = read(json!({"path": path, "unit": "bytes", "offset": 5, "limit": 3})).await;
+        assert_eq!(text(&output, 0), "1\nl");
+        assert_eq!(info["next_offset"], 8);
+
+        let (output, info) = read(json!({"path": dir.path().join("pixel.png")})).await;
+        assert!(matches!(&output.content[0], ToolContent::Image { mime_type, .. } if mime_type == "image/png"));
+        assert_eq!((info["encoding"].as_str(), info["unit"].as_str()), (Some("base64"), Some("bytes")));
+
+        let err = tool
+            .execute(json!({"path": dir.path().join("pixel.png"), "unit": "lines"}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+
+        // Part of an image is base64 text; an image over the limit is refused whole
+        let (output, _) = read(json!({"path": dir.path().join("pixel.png"), "limit": 8})).await;
+        assert_eq!(text(&output, 0), BASE64.encode(b"\x89PNG\r\n\x1a\n"));
+        assert!(matches!(&output.content[0], ToolContent::Text { .. }));
+
+        let mut config = Config { database_path: Some(":memory:".to_string()), ..Config::default() };
+        config.output_limit.max_binary_bytes = 8;
+        let small_limit = Arc::new(RuntimeState::new(config));
+        let err = tool.execute(json!({"path": dir.path().join("pixel.png")}), small_limit).await.unwrap_err();
+        assert!(err.to_string().contains("max_binary_bytes"), "{}", err);
+
+        let allowed = [dir.path().to_path_buf()];
+        let file = read_allowed_file(&allowed, dir.path().join("pixel.png").to_str().unwrap(), 1024).unwrap();
+        assert_eq!((file.mime_type, file.binary, file.data.len()), ("image/png", true, 16));
+        assert!(read_allowed_file(&allowed, dir.path().join("pixel.png").to_str().unwrap(), 8).is_err());
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("other.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        let outside = outside.path().join("other.png");
+        assert!(matches!(read_allowed_file(&allowed, outside.to_str().unwrap(), 1024), Err(ToolError::PermissionDenied(_))));
+    }
+}
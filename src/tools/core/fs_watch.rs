@@ -0,0 +1,200 @@
+//! File watch tools - follow changes under a path.
+//!
+//! `fs.watch` starts recording created, modified and deleted files;
+//! `fs.watch_events` polls what was recorded (optionally waiting for the
+//! next change). Each watch is also the resource `nexus://watches/{id}`,
+//! so clients can subscribe and be notified instead of polling.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::file_watch::watch_uri;
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+use super::fs_read::is_path_allowed;
+
+/// Events returned by one poll without a `limit`.
+const DEFAULT_EVENT_LIMIT: usize = 100;
+
+/// Longest `fs.watch_events` may wait for a change.
+const MAX_WAIT_SECS: u64 = 60;
+
+/// Starts watching a path.
+#[derive(Debug)]
+pub struct FsWatchTool {
+    allowed_paths: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct FsWatchArgs {
+    path: String,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default = "default_recursive")]
+    recursive: bool,
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+impl FsWatchTool {
+    /// Creates the tool with the same allowed paths as fs.read.
+    pub fn new(allowed_paths: Vec<PathBuf>) -> Self {
+        Self { allowed_paths }
+    }
+}
+
+#[async_trait]
+impl Tool for FsWatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fs.watch".to_string(),
+            description: Some("Watches a file or directory for changes. Created, modified and deleted files are recorded; poll them with fs.watch_events or subscribe to the returned resource URI for update notifications.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to watch"
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "Only record paths matching this glob. Without '/' it matches file names (e.g. '*.rs'), otherwise paths relative to the watched directory (e.g. 'target/**/*.wasm')"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Include subdirectories (default: true)"
+                    }
+                },
+                "required": ["path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: FsWatchArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+
+        let path = PathBuf::from(&args.path);
+        if !is_path_allowed(&self.allowed_paths, &path) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Path not in allowed directories: {}",
+                args.path
+            )));
+        }
+
+        let updates = state.resource_subscriptions.sender();
+        let id = state.file_watches.watch(&path, args.pattern, args.recursive, updates)?;
+        Ok(ToolOutput::structured(json!({ "id": id, "uri": watch_uri(&id) })))
+    }
+}
+
+/// Polls the changes a watch recorded.
+#[derive(Debug)]
+pub struct FsWatchEventsTool;
+
+#[derive(Deserialize)]
+struct FsWatchEventsArgs {
+    id: String,
+    #[serde(default)]
+    since: u64,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    wait_secs: Option<u64>,
+}
+
+#[async_trait]
+impl Tool for FsWatchEventsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fs.watch_events".to_string(),
+            description: Some("Returns changes recorded by an fs.watch, oldest first. Pass the returned cursor as 'since' to get only newer changes; set wait_secs to wait for the next change.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Watch ID returned by fs.watch"
+                    },
+                    "since": {
+                        "type": "integer",
+                        "description": "Only return events at or after this cursor (default: 0, all kept events)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum events to return (default: 100)"
+                    },
+                    "wait_secs": {
+                        "type": "integer",
+                        "description": "If there are no events yet, wait up to this many seconds for one (max 60)"
+                    }
+                },
+                "required": ["id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: FsWatchEventsArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+
+        let limit = args.limit.unwrap_or(DEFAULT_EVENT_LIMIT).max(1);
+        let wait = args.wait_secs.map(|secs| Duration::from_secs(secs.min(MAX_WAIT_SECS)));
+        let events = state
+            .file_watches
+            .events(&args.id, args.since, limit, wait)
+            .await
+            .ok_or_else(|| ToolError::NotFound(format!("No file watch with id {}", args.id)))?;
+        Ok(ToolOutput::structured(json!(events)))
+    }
+}
+
+/// Stops a watch.
+#[derive(Debug)]
+pub struct FsUnwatchTool;
+
+#[derive(Deserialize)]
+struct FsUnwatchArgs {
+    id: String,
+}
+
+#[async_trait]
+impl Tool for FsUnwatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fs.unwatch".to_string(),
+            description: Some("Stops an fs.watch and discards its recorded events.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Watch ID returned by fs.watch"
+                    }
+                },
+                "required": ["id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(&self, args: Value, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let args: FsUnwatchArgs = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?;
+
+        if !state.file_watches.unwatch(&args.id) {
+            return Err(ToolError::NotFound(format!("No file watch with id {}", args.id)));
+        }
+        Ok(ToolOutput::structured(json!({ "id": args.id, "removed": true })))
+    }
+}
@@ -10,7 +10,7 @@ use tracing::debug;
 
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::{Tool, ToolError, ToolOutput};
+use crate::tools::{roots, Tool, ToolError, ToolOutput};
 
 /// File write tool - writes content to allowed paths.
 #[derive(Debug)]
@@ -34,36 +34,35 @@ impl FsWriteTool {
 
     /// Checks if a path is within the allowed directories.
     fn is_path_allowed(&self, path: &Path) -> bool {
-        if self.allowed_paths.is_empty() {
-            return false;
-        }
-
-        // For new files, check parent directory
-        let check_path = if path.exists() {
-            path.to_path_buf()
-        } else {
-            path.parent()
-                .map(|p| p.to_path_buf())
-                .unwrap_or_else(|| path.to_path_buf())
-        };
-
-        let canonical = match check_path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
-
-        for allowed in &self.allowed_paths {
-            if let Ok(allowed_canonical) = allowed.canonicalize() {
-                if canonical.starts_with(&allowed_canonical) {
-                    return true;
-                }
-            }
-        }
-
-        false
+        is_write_allowed(&self.allowed_paths, path)
     }
 }
 
+/// Checks if a path may be written: it (or, for a new file, its parent
+/// directory) must be within one of the allowed directories and the
+/// client's roots.
+pub(crate) fn is_write_allowed(allowed_paths: &[PathBuf], path: &Path) -> bool {
+    // For new files, check parent directory
+    let check_path = if path.exists() {
+        path.to_path_buf()
+    } else {
+        path.parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| path.to_path_buf())
+    };
+
+    let canonical = match check_path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+
+    allowed_paths.iter().any(|allowed| {
+        allowed
+            .canonicalize()
+            .is_ok_and(|allowed_canonical| canonical.starts_with(&allowed_canonical))
+    }) && roots::allows(&canonical)
+}
+
 #[async_trait]
 impl Tool for FsWriteTool {
     fn definition(&self) -> ToolDefinition {
@@ -89,6 +88,7 @@ impl Tool for FsWriteTool {
                 },
                 "required": ["path", "content"]
             }),
+            output_schema: None,
         }
     }
 
@@ -1,7 +1,10 @@
-//! Get time tool - returns the current server time.
+//! Time tools - current time in any timezone.
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Locale, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
 use serde_json::Value;
 use std::sync::Arc;
 
@@ -9,7 +12,53 @@ use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
 use crate::tools::{Tool, ToolError, ToolOutput};
 
-/// Get time tool - returns the current server time in ISO 8601 format.
+/// Default format used when only a locale is given.
+const DEFAULT_LOCALIZED_FORMAT: &str = "%A, %e %B %Y %X";
+
+#[derive(Debug, Deserialize, Default)]
+struct TimeNowArgs {
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    locale: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// Time tool - returns the current time in the requested (or default) timezone.
+#[derive(Debug)]
+pub struct TimeNowTool;
+
+#[async_trait]
+impl Tool for TimeNowTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "time.now".to_string(),
+            description: Some(
+                "Returns the current time in a timezone (default: server timezone) with ISO week, unix epoch and optional locale formatting.".to_string(),
+            ),
+            input_schema: time_now_schema(),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: TimeNowArgs = if arguments.is_null() {
+            TimeNowArgs::default()
+        } else {
+            serde_json::from_value(arguments)
+                .map_err(|e| ToolError::InvalidInput(format!("Invalid arguments: {}", e)))?
+        };
+
+        time_now(args, &state)
+    }
+}
+
+/// Get time tool - legacy name for `time.now`.
 #[derive(Debug)]
 pub struct GetTimeTool;
 
@@ -18,28 +67,128 @@ impl Tool for GetTimeTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "get_time".to_string(),
-            description: Some("Returns the current server time in ISO 8601 format.".to_string()),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {},
-                "required": []
-            }),
+            description: Some("Returns the current server time in ISO 8601 format. Alias of time.now.".to_string()),
+            input_schema: time_now_schema(),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
-        _arguments: Value,
-        _state: Arc<RuntimeState>,
+        arguments: Value,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let now = Utc::now();
-        let iso_time = now.to_rfc3339();
-
-        Ok(ToolOutput::text(serde_json::json!({
-            "time": iso_time,
-            "timestamp": now.timestamp(),
-            "timezone": "UTC"
-        }).to_string()))
+        TimeNowTool.execute(arguments, state).await
     }
 }
 
+fn time_now_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "timezone": {
+                "type": "string",
+                "description": "IANA timezone, e.g. 'Europe/Berlin' (default: server default_timezone)"
+            },
+            "locale": {
+                "type": "string",
+                "description": "Locale for the 'formatted' field, e.g. 'de_DE'"
+            },
+            "format": {
+                "type": "string",
+                "description": "strftime format for the 'formatted' field"
+            }
+        },
+        "required": []
+    })
+}
+
+fn time_now(args: TimeNowArgs, state: &RuntimeState) -> Result<ToolOutput, ToolError> {
+    let tz: Tz = match args.timezone.as_deref() {
+        Some(name) => name
+            .parse()
+            .map_err(|_| ToolError::InvalidInput(format!("Unknown timezone: {}", name)))?,
+        None => state.config.timezone(),
+    };
+
+    let utc = Utc::now();
+    let now = utc.with_timezone(&tz);
+    let iso_week = now.iso_week();
+
+    let mut result = serde_json::json!({
+        "time": now.to_rfc3339(),
+        "utc": utc.to_rfc3339(),
+        "timestamp": utc.timestamp(),
+        "timestamp_millis": utc.timestamp_millis(),
+        "timezone": tz.name(),
+        "utc_offset": now.format("%:z").to_string(),
+        "date": now.format("%Y-%m-%d").to_string(),
+        "weekday": now.format("%A").to_string(),
+        "day_of_year": now.ordinal(),
+        "iso_week": {
+            "year": iso_week.year(),
+            "week": iso_week.week()
+        }
+    });
+
+    if args.locale.is_some() || args.format.is_some() {
+        let fmt = args.format.as_deref().unwrap_or(DEFAULT_LOCALIZED_FORMAT);
+        // Formatting panics on invalid specifiers, so reject them up front
+        if StrftimeItems::new(fmt).any(|item| matches!(item, Item::Error)) {
+            return Err(ToolError::InvalidInput(format!("Invalid format string: {}", fmt)));
+        }
+        let formatted = match args.locale.as_deref() {
+            Some(name) => {
+                let locale = Locale::try_from(name)
+                    .map_err(|_| ToolError::InvalidInput(format!("Unknown locale: {}", name)))?;
+                now.format_localized(fmt, locale).to_string()
+            }
+            None => now.format(fmt).to_string(),
+        };
+        result["formatted"] = Value::String(formatted);
+        if let Some(locale) = args.locale {
+            result["locale"] = Value::String(locale);
+        }
+    }
+
+    Ok(ToolOutput::text(result.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+
+    #[tokio::test]
+    async fn test_time_now_with_timezone_and_locale() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let args = serde_json::json!({
+            "timezone": "Asia/Tokyo",
+            "locale": "de_DE",
+            "format": "%A"
+        });
+
+        let output = TimeNowTool.execute(args, state).await.unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        let value: Value = serde_json::from_str(text).unwrap();
+
+        assert_eq!(value["timezone"], "Asia/Tokyo");
+        assert_eq!(value["utc_offset"], "+09:00");
+        let weekdays = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"];
+        assert!(weekdays.contains(&value["formatted"].as_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_time_now_rejects_bad_input() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        let bad_tz = TimeNowTool.execute(serde_json::json!({"timezone": "Mars/Base"}), state.clone()).await;
+        assert!(matches!(bad_tz, Err(ToolError::InvalidInput(_))));
+
+        let bad_fmt = TimeNowTool.execute(serde_json::json!({"format": "%Q"}), state).await;
+        assert!(matches!(bad_fmt, Err(ToolError::InvalidInput(_))));
+    }
+}
@@ -1,69 +1,76 @@
 //! HTTP request tool for making web requests.
+//!
+//! Image responses come back as image content followed by the status and
+//! headers, up to `output_limit.max_binary_bytes`.
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use regex::Regex;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::core::config::HttpClientConfig;
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
+use crate::core::http_cache::{CacheMode, CacheStatus, HttpCache};
 use crate::core::{Config, RuntimeState};
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::registry::{Tool, ToolContent, ToolError, ToolOutput};
+
+use super::is_image;
+
+/// Checks `url` against `http_client.blocked_urls` and `allowed_urls`.
+pub(crate) fn check_url_patterns(http_client: &HttpClientConfig, url: &str) -> Result<(), ToolError> {
+    // Check blocked patterns first
+    for pattern in &http_client.blocked_urls {
+        if let Ok(re) = Regex::new(pattern) {
+            if re.is_match(url) {
+                return Err(ToolError::PermissionDenied(format!(
+                    "URL blocked by pattern: {}",
+                    pattern
+                )));
+            }
+        }
+    }
+
+    // If allowed_urls is empty, allow all (except blocked)
+    if http_client.allowed_urls.is_empty() {
+        return Ok(());
+    }
+
+    // Check allowed patterns
+    for pattern in &http_client.allowed_urls {
+        if let Ok(re) = Regex::new(pattern) {
+            if re.is_match(url) {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(ToolError::PermissionDenied(format!(
+        "URL not in allowed list: {}",
+        url
+    )))
+}
 
 /// Tool for making HTTP requests.
 #[derive(Debug)]
 pub struct HttpRequestTool {
-    client: reqwest::Client,
     config: Config,
 }
 
 impl HttpRequestTool {
     /// Creates a new HTTP request tool with the given configuration.
     pub fn new(config: &Config) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.http_client.timeout_secs))
-            .user_agent(&config.http_client.user_agent)
-            .build()
-            .unwrap_or_default();
-
         Self {
-            client,
             config: config.clone(),
         }
     }
 
     fn is_url_allowed(&self, url: &str) -> Result<(), ToolError> {
-        // Check blocked patterns first
-        for pattern in &self.config.http_client.blocked_urls {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(url) {
-                    return Err(ToolError::PermissionDenied(format!(
-                        "URL blocked by pattern: {}",
-                        pattern
-                    )));
-                }
-            }
-        }
-
-        // If allowed_urls is empty, allow all (except blocked)
-        if self.config.http_client.allowed_urls.is_empty() {
-            return Ok(());
-        }
-
-        // Check allowed patterns
-        for pattern in &self.config.http_client.allowed_urls {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(url) {
-                    return Ok(());
-                }
-            }
-        }
-
-        Err(ToolError::PermissionDenied(format!(
-            "URL not in allowed list: {}",
-            url
-        )))
+        check_url_patterns(&self.config.http_client, url)
     }
 }
 
@@ -73,7 +80,8 @@ impl Tool for HttpRequestTool {
         ToolDefinition {
             name: "http.request".to_string(),
             description: Some(
-                "Makes an HTTP request to a URL. Supports GET, POST, PUT, DELETE, PATCH methods."
+                "Makes an HTTP request to a URL. Supports GET, POST, PUT, DELETE, PATCH methods. \
+                 GET responses can be cached (cache / cache_ttl). Images are returned as image content."
                     .to_string(),
             ),
             input_schema: json!({
@@ -101,17 +109,26 @@ impl Tool for HttpRequestTool {
                     "json": {
                         "type": "object",
                         "description": "JSON body (alternative to body, sets Content-Type)"
+                    },
+                    "cache": {
+                        "type": "boolean",
+                        "description": "Serve GET requests from the response cache, honoring Cache-Control and ETag (default: the http_client.cache.enabled setting)"
+                    },
+                    "cache_ttl": {
+                        "type": "integer",
+                        "description": "Cache a GET response for this many seconds, ignoring Cache-Control"
                     }
                 },
                 "required": ["url"]
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let url = arguments
             .get("url")
@@ -120,6 +137,7 @@ impl Tool for HttpRequestTool {
 
         // Validate URL
         self.is_url_allowed(url)?;
+        state.egress.check_url(ToolCategory::Http, url).map_err(ToolError::PermissionDenied)?;
 
         let method = arguments
             .get("method")
@@ -128,13 +146,20 @@ impl Tool for HttpRequestTool {
             .to_uppercase();
 
         // Build request
+        let profile = ClientProfile::new(ToolCategory::Http)
+            .timeout(Duration::from_secs(self.config.http_client.timeout_secs))
+            .user_agent(&self.config.http_client.user_agent);
+        let client = state
+            .http_clients
+            .get(&profile)
+            .map_err(|e| ToolError::Internal(format!("HTTP client error: {}", e)))?;
         let mut request = match method.as_str() {
-            "GET" => self.client.get(url),
-            "POST" => self.client.post(url),
-            "PUT" => self.client.put(url),
-            "DELETE" => self.client.delete(url),
-            "PATCH" => self.client.patch(url),
-            "HEAD" => self.client.head(url),
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "DELETE" => client.delete(url),
+            "PATCH" => client.patch(url),
+            "HEAD" => client.head(url),
             _ => {
                 return Err(ToolError::InvalidInput(format!(
                     "Invalid HTTP method: {}",
@@ -144,10 +169,12 @@ impl Tool for HttpRequestTool {
         };
 
         // Add headers
+        let mut request_headers = Vec::new();
         if let Some(headers) = arguments.get("headers").and_then(|v| v.as_object()) {
             for (key, value) in headers {
                 if let Some(val) = value.as_str() {
                     request = request.header(key, val);
+                    request_headers.push((key.clone(), val.to_string()));
                 }
             }
         }
@@ -159,27 +186,36 @@ impl Tool for HttpRequestTool {
             request = request.body(body.to_string());
         }
 
+        // Only GET responses are cached
+        let cache_mode = match method.as_str() {
+            "GET" => CacheMode::from_args(&self.config.http_client.cache, &arguments),
+            _ => CacheMode::Off,
+        };
+        let cache_key = HttpCache::key(&method, url, &request_headers);
+
         // Execute request
-        let response = request.send().await.map_err(|e| {
-            ToolError::ExecutionFailed(format!("HTTP request failed: {}", e))
-        })?;
+        let (response, cache_status) = state
+            .http_cache
+            .send(cache_mode, &cache_key, url, request)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("HTTP request failed: {}", e)))?;
+
+        let status = response.status;
+        let status_text = reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("Unknown");
 
-        let status = response.status().as_u16();
-        let status_text = response.status().canonical_reason().unwrap_or("Unknown");
+        let mime_type = response
+            .header("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
 
         // Get headers
-        let mut response_headers: HashMap<String, String> = HashMap::new();
-        for (key, value) in response.headers() {
-            if let Ok(v) = value.to_str() {
-                response_headers.insert(key.to_string(), v.to_string());
-            }
-        }
+        let response_headers: HashMap<String, String> = response.headers.into_iter().collect();
 
         // Get body with size limit
-        let body_bytes = response
-            .bytes()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read response: {}", e)))?;
+        let body_bytes = response.body;
 
         if body_bytes.len() > self.config.http_client.max_response_bytes {
             return Err(ToolError::ExecutionFailed(format!(
@@ -189,21 +225,94 @@ impl Tool for HttpRequestTool {
             )));
         }
 
+        if let Some(mime_type) = mime_type.filter(|mime_type| is_image(mime_type) && !body_bytes.is_empty()) {
+            let max_image_bytes = state.config.output_limit.max_binary_bytes;
+            if body_bytes.len() > max_image_bytes {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Image too large: {} bytes (max: {}, output_limit.max_binary_bytes)",
+                    body_bytes.len(),
+                    max_image_bytes
+                )));
+            }
+            let mut info = json!({
+                "status": status,
+                "statusText": status_text,
+                "headers": response_headers,
+                "mime_type": mime_type,
+                "size": body_bytes.len()
+            });
+            if cache_status != CacheStatus::Bypass {
+                info["cache"] = json!(cache_status);
+            }
+            return Ok(ToolOutput {
+                content: vec![
+                    ToolContent::Image { data: BASE64.encode(&body_bytes), mime_type },
+                    ToolContent::Text { text: serde_json::to_string_pretty(&info).unwrap() },
+                ],
+                is_error: false,
+                structured_content: None,
+            });
+        }
+
         // Try to parse as text
         let body = String::from_utf8_lossy(&body_bytes).to_string();
 
         // Try to parse as JSON for pretty output
         let body_json: Option<Value> = serde_json::from_str(&body).ok();
 
-        let result = json!({
+        let mut result = json!({
             "status": status,
             "statusText": status_text,
             "headers": response_headers,
-            "body": body_json.unwrap_or_else(|| Value::String(body)),
+            "body": body_json.unwrap_or(Value::String(body)),
             "size": body_bytes.len()
         });
+        if cache_status != CacheStatus::Bypass {
+            result["cache"] = json!(cache_status);
+        }
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_image_response() {
+        use axum::{http::header, routing::get, Router};
+
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let image = png.clone();
+        let app = Router::new()
+            .route("/logo.png", get(move || async move { ([(header::CONTENT_TYPE, "image/png")], image) }))
+            .route("/text", get(|| async { "plain" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut config = Config {
+            database_path: Some(":memory:".to_string()),
+            ..Default::default()
+        };
+        config.http_client.blocked_urls.clear();
+        config.http_client.allowed_private = vec!["127.0.0.1".to_string()];
+        let tool = HttpRequestTool::new(&config);
+        let state = Arc::new(RuntimeState::new(config.clone()));
+
+        let output = tool.execute(json!({"url": format!("http://{}/logo.png", addr)}), state.clone()).await.unwrap();
+        let ToolContent::Image { data, mime_type } = &output.content[0] else { panic!("expected an image") };
+        assert_eq!((BASE64.decode(data).unwrap(), mime_type.as_str()), (png, "image/png"));
+        let ToolContent::Text { text } = &output.content[1] else { panic!("expected text") };
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap()["status"], 200);
+
+        let output = tool.execute(json!({"url": format!("http://{}/text", addr)}), state).await.unwrap();
+        assert!(matches!(&output.content[0], ToolContent::Text { text } if text.contains("plain")));
+
+        config.output_limit.max_binary_bytes = 4;
+        let state = Arc::new(RuntimeState::new(config));
+        let err = tool.execute(json!({"url": format!("http://{}/logo.png", addr)}), state).await.unwrap_err();
+        assert!(err.to_string().contains("Image too large"), "{}", err);
+    }
+}
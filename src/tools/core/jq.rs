@@ -0,0 +1,112 @@
+//! jq expressions for json.query, evaluated with jaq.
+
+use jaq_core::load::{self, Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+
+use crate::tools::registry::ToolError;
+
+/// Most results an expression may produce (`range(1e9)` would never end).
+const MAX_RESULTS: usize = 10_000;
+
+/// Whether a json.query path is a jq expression rather than a plain dot
+/// path like `data.items[0].name`: jq expressions start with `.` or use
+/// characters dot paths can't contain.
+pub(crate) fn is_expression(path: &str) -> bool {
+    path.starts_with('.')
+        || path.contains(|c: char| "|(){}$\"',?=<>!+*/%;: ".contains(c))
+        || path.contains("[]")
+        || path.split('[').skip(1).any(|index| index.split(']').next().is_none_or(|i| i.parse::<usize>().is_err()))
+}
+
+/// Character position of `found` (a slice of `expr`) for error messages.
+fn position(expr: &str, found: &str) -> usize {
+    let offset = (found.as_ptr() as usize).saturating_sub(expr.as_ptr() as usize).min(expr.len());
+    expr[..offset].chars().count() + 1
+}
+
+/// Evaluates a jq expression against `input`, returning every result.
+pub(crate) fn run(expr: &str, input: Value) -> Result<Vec<Value>, ToolError> {
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader.load(&arena, File { code: expr, path: () }).map_err(|errors| {
+        let messages: Vec<String> = errors
+            .into_iter()
+            .flat_map(|(_, error)| match error {
+                load::Error::Io(errors) => errors.into_iter().map(|(_, message)| message).collect::<Vec<_>>(),
+                load::Error::Lex(errors) => errors
+                    .into_iter()
+                    .map(|(expected, found)| format!("expected {} at position {}", expected.as_str(), position(expr, found)))
+                    .collect(),
+                load::Error::Parse(errors) => errors
+                    .into_iter()
+                    .map(|(expected, found)| format!("expected {} at position {}", expected.as_str(), position(expr, found)))
+                    .collect(),
+            })
+            .collect();
+        ToolError::InvalidInput(format!("Invalid jq expression: {}", messages.join("; ")))
+    })?;
+
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errors| {
+            let messages: Vec<String> = errors
+                .into_iter()
+                .flat_map(|(_, undefined)| undefined)
+                .map(|(name, kind)| format!("undefined {} '{}'", kind.as_str(), name))
+                .collect();
+            ToolError::InvalidInput(format!("Invalid jq expression: {}", messages.join("; ")))
+        })?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut results = Vec::new();
+    for output in filter.run((Ctx::new([], &inputs), Val::from(input))) {
+        if results.len() == MAX_RESULTS {
+            return Err(ToolError::ExecutionFailed(format!(
+                "jq expression produced more than {} results",
+                MAX_RESULTS
+            )));
+        }
+        let value = output.map_err(|e| ToolError::ExecutionFailed(format!("jq error: {}", e)))?;
+        results.push(Value::from(value));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expressions() {
+        let input = json!({ "items": [
+            { "name": "a", "price": 3 },
+            { "name": "b", "price": 12 },
+            { "name": "c", "price": 7 }
+        ]});
+
+        let expensive = run("[.items[] | select(.price > 5) | .name]", input.clone()).unwrap();
+        assert_eq!(expensive, vec![json!(["b", "c"])]);
+
+        let built = run(".items[1:] | map({(.name): .price}) | add", input.clone()).unwrap();
+        assert_eq!(built, vec![json!({ "b": 12, "c": 7 })]);
+
+        assert_eq!(run(".items[].name", input.clone()).unwrap().len(), 3);
+        assert!(run(".items | frobnicate", input.clone()).unwrap_err().to_string().contains("frobnicate"));
+        assert!(run(".items[", input).unwrap_err().to_string().contains("position"));
+    }
+
+    #[test]
+    fn test_dot_paths_are_not_expressions() {
+        assert!(!is_expression("data.items[0].name"));
+        assert!(!is_expression("content-type"));
+        assert!(!is_expression(""));
+        assert!(is_expression(".data"));
+        assert!(is_expression("items | length"));
+        assert!(is_expression("items[]"));
+        assert!(is_expression("items[1:]"));
+    }
+}
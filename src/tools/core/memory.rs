@@ -7,8 +7,80 @@ use std::sync::Arc;
 use tracing::debug;
 
 use crate::core::RuntimeState;
+use crate::memory::{check_namespace, is_reserved_key, namespaced_key, Collection, KvOp};
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::{Tool, ToolError, ToolOutput};
+use crate::tools::{caller, Tool, ToolError, ToolOutput};
+
+/// Resolves the namespace for a memory call.
+///
+/// A caller with an API key always uses its key's namespace, and an
+/// explicit `namespace` naming another one is refused. For other callers
+/// an explicit `namespace` argument wins; otherwise the pinned conversation
+/// (if any) scopes the call, and then the session's namespace.
+pub(crate) fn resolve_namespace(explicit: Option<String>, state: &RuntimeState) -> Result<Option<String>, ToolError> {
+    let namespace = caller::scoped_namespace(explicit)
+        .map_err(ToolError::PermissionDenied)?
+        .or_else(|| state.pinned_conversation())
+        .or_else(caller::namespace);
+    if let Some(namespace) = &namespace {
+        check_namespace(namespace).map_err(ToolError::InvalidInput)?;
+    }
+    Ok(namespace)
+}
+
+/// Looks up the collection named by a memory call, if any.
+fn resolve_collection<'a>(
+    name: Option<&str>,
+    state: &'a RuntimeState,
+) -> Result<Option<&'a Collection>, ToolError> {
+    match name {
+        Some(name) => state
+            .collections
+            .get(name)
+            .map(Some)
+            .ok_or_else(|| ToolError::InvalidInput(format!("Unknown collection: {}", name))),
+        None => Ok(None),
+    }
+}
+
+/// Builds the storage key for a key inside an optional collection.
+fn collection_key(collection: Option<&Collection>, key: &str) -> String {
+    match collection {
+        Some(c) => format!("{}{}", c.key_prefix(), key),
+        None => key.to_string(),
+    }
+}
+
+/// Builds the storage key of a memory call's key, refusing the keys the
+/// server keeps its own state under (scheduled tasks, event rules,
+/// idempotent results, ...).
+fn storage_key(namespace: Option<&str>, collection: Option<&Collection>, key: &str) -> Result<String, ToolError> {
+    let key = collection_key(collection, key);
+    if is_reserved_key(&key) {
+        return Err(ToolError::PermissionDenied(format!("Key '{}' is reserved for the server's own state", key)));
+    }
+    Ok(namespaced_key(namespace, &key))
+}
+
+/// Keys starting with `prefix` that memory.recall would find, for argument
+/// completion. `arguments` are the call's other arguments so far, which
+/// pick the namespace and collection.
+pub(crate) async fn complete_keys(state: &RuntimeState, arguments: &Value, prefix: &str) -> Result<Vec<String>, ToolError> {
+    let argument = |name: &str| arguments.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let collection = resolve_collection(argument("collection").as_deref(), state)?;
+    let namespace = resolve_namespace(argument("namespace"), state)?;
+    let base = namespaced_key(namespace.as_deref(), &collection_key(collection, ""));
+    let keys = state
+        .memory_store
+        .kv_list(Some(&format!("{}{}", base, prefix)))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+    Ok(keys
+        .into_iter()
+        .filter_map(|key| key.strip_prefix(&base).map(str::to_string))
+        .filter(|key| !is_reserved_key(key))
+        .collect())
+}
 
 // ============================================================================
 // Memory Store Tool
@@ -24,6 +96,10 @@ struct MemoryStoreArgs {
     value: Value,
     #[serde(default)]
     ttl_secs: Option<u64>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
 }
 
 #[async_trait]
@@ -45,10 +121,19 @@ impl Tool for MemoryStoreTool {
                     "ttl_secs": {
                         "type": "integer",
                         "description": "Optional time-to-live in seconds"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Optional namespace (defaults to the pinned conversation, then the caller's API key name)"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional configured collection; the value must match its schema"
                     }
                 },
                 "required": ["key", "value"]
             }),
+            output_schema: None,
         }
     }
 
@@ -60,16 +145,27 @@ impl Tool for MemoryStoreTool {
         let args: MemoryStoreArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-        debug!("Storing key: {}", args.key);
+        let collection = resolve_collection(args.collection.as_deref(), &state)?;
+        if let Some(collection) = collection {
+            collection.validate(&args.value).map_err(ToolError::InvalidInput)?;
+        }
+
+        let namespace = resolve_namespace(args.namespace, &state)?;
+        let key = storage_key(namespace.as_deref(), collection, &args.key)?;
+
+        debug!("Storing key: {}", key);
 
         state.memory_store
-            .kv_set(&args.key, args.value.clone(), args.ttl_secs)
+            .kv_set(&key, args.value.clone(), args.ttl_secs)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        state.events.publish("memory.set", &key, serde_json::json!({ "value": args.value }));
 
         Ok(ToolOutput::text(serde_json::json!({
             "success": true,
             "key": args.key,
+            "namespace": namespace,
+            "collection": args.collection,
             "stored": true
         }).to_string()))
     }
@@ -86,6 +182,10 @@ pub struct MemoryRecallTool;
 #[derive(Deserialize)]
 struct MemoryRecallArgs {
     key: String,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
 }
 
 #[async_trait]
@@ -100,10 +200,19 @@ impl Tool for MemoryRecallTool {
                     "key": {
                         "type": "string",
                         "description": "The key to recall"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Optional namespace (defaults to the pinned conversation, then the caller's API key name)"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional configured collection the key belongs to"
                     }
                 },
                 "required": ["key"]
             }),
+            output_schema: None,
         }
     }
 
@@ -115,10 +224,14 @@ impl Tool for MemoryRecallTool {
         let args: MemoryRecallArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-        debug!("Recalling key: {}", args.key);
+        let collection = resolve_collection(args.collection.as_deref(), &state)?;
+        let namespace = resolve_namespace(args.namespace, &state)?;
+        let key = storage_key(namespace.as_deref(), collection, &args.key)?;
+
+        debug!("Recalling key: {}", key);
 
         let result = state.memory_store
-            .kv_get(&args.key)
+            .kv_get(&key)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
@@ -149,6 +262,10 @@ pub struct MemoryDeleteTool;
 #[derive(Deserialize)]
 struct MemoryDeleteArgs {
     key: String,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
 }
 
 #[async_trait]
@@ -163,10 +280,19 @@ impl Tool for MemoryDeleteTool {
                     "key": {
                         "type": "string",
                         "description": "The key to delete"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Optional namespace (defaults to the pinned conversation, then the caller's API key name)"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional configured collection the key belongs to"
                     }
                 },
                 "required": ["key"]
             }),
+            output_schema: None,
         }
     }
 
@@ -178,12 +304,17 @@ impl Tool for MemoryDeleteTool {
         let args: MemoryDeleteArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-        debug!("Deleting key: {}", args.key);
+        let collection = resolve_collection(args.collection.as_deref(), &state)?;
+        let namespace = resolve_namespace(args.namespace, &state)?;
+        let key = storage_key(namespace.as_deref(), collection, &args.key)?;
+
+        debug!("Deleting key: {}", key);
 
         state.memory_store
-            .kv_delete(&args.key)
+            .kv_delete(&key)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        state.events.publish("memory.deleted", &key, serde_json::json!({}));
 
         Ok(ToolOutput::text(serde_json::json!({
             "success": true,
@@ -205,6 +336,18 @@ pub struct MemoryListTool;
 struct MemoryListArgs {
     #[serde(default)]
     prefix: Option<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    filter: Option<serde_json::Map<String, Value>>,
+    #[serde(default)]
+    sort_by: Option<String>,
+    #[serde(default)]
+    order: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
 }
 
 #[async_trait]
@@ -212,17 +355,43 @@ impl Tool for MemoryListTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "memory.list".to_string(),
-            description: Some("Lists all keys in the memory store, optionally filtered by prefix.".to_string()),
+            description: Some("Lists all keys in the memory store, optionally filtered by prefix. Within a collection, entries can be filtered and sorted by indexed fields.".to_string()),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
                     "prefix": {
                         "type": "string",
                         "description": "Optional prefix to filter keys"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Optional namespace (defaults to the pinned conversation, then the caller's API key name)"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional configured collection; returns entries with their values"
+                    },
+                    "filter": {
+                        "type": "object",
+                        "description": "Indexed field values entries must equal (collections only)"
+                    },
+                    "sort_by": {
+                        "type": "string",
+                        "description": "Indexed field to sort entries by (collections only)"
+                    },
+                    "order": {
+                        "type": "string",
+                        "enum": ["asc", "desc"],
+                        "description": "Sort order (default: asc)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of entries to return (collections only)"
                     }
                 },
                 "required": []
             }),
+            output_schema: None,
         }
     }
 
@@ -234,17 +403,406 @@ impl Tool for MemoryListTool {
         let args: MemoryListArgs = serde_json::from_value(arguments)
             .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-        debug!("Listing keys with prefix: {:?}", args.prefix);
+        let collection = resolve_collection(args.collection.as_deref(), &state)?;
+        if collection.is_none() && (args.filter.is_some() || args.sort_by.is_some()) {
+            return Err(ToolError::InvalidInput(
+                "filter and sort_by require a collection".to_string(),
+            ));
+        }
+
+        let namespace = resolve_namespace(args.namespace, &state)?;
+
+        debug!("Listing keys with prefix: {:?} (namespace: {:?})", args.prefix, namespace);
+
+        let base = namespaced_key(namespace.as_deref(), &collection_key(collection, ""));
+        let full_prefix = format!("{}{}", base, args.prefix.as_deref().unwrap_or(""));
+        let keys: Vec<String> = if base.is_empty() {
+            state.memory_store
+                .kv_list(args.prefix.as_deref())
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+        } else {
+            state.memory_store
+                .kv_list(Some(&full_prefix))
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+                .into_iter()
+                .filter_map(|k| k.strip_prefix(&base).map(|s| s.to_string()))
+                .collect()
+        };
+        let keys: Vec<String> = keys.into_iter().filter(|key| !is_reserved_key(key)).collect();
+
+        let Some(collection) = collection else {
+            return Ok(ToolOutput::text(serde_json::json!({
+                "keys": keys,
+                "namespace": namespace,
+                "count": keys.len()
+            }).to_string()));
+        };
+
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            let stored = state.memory_store
+                .kv_get(&format!("{}{}", base, key))
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            let Some(kv) = stored else { continue };
+
+            if let Some(filter) = &args.filter {
+                if !collection.matches(&kv.value, filter).map_err(ToolError::InvalidInput)? {
+                    continue;
+                }
+            }
+            entries.push((key, kv.value));
+        }
+
+        if let Some(sort_by) = &args.sort_by {
+            let descending = match args.order.as_deref() {
+                None | Some("asc") => false,
+                Some("desc") => true,
+                Some(other) => {
+                    return Err(ToolError::InvalidInput(format!("Invalid order: {}", other)));
+                }
+            };
+            collection.sort(&mut entries, sort_by, descending).map_err(ToolError::InvalidInput)?;
+        }
+        if let Some(limit) = args.limit {
+            entries.truncate(limit);
+        }
+
+        let items: Vec<Value> = entries
+            .into_iter()
+            .map(|(key, value)| serde_json::json!({ "key": key, "value": value }))
+            .collect();
+
+        Ok(ToolOutput::text(serde_json::json!({
+            "items": items,
+            "namespace": namespace,
+            "collection": collection.name,
+            "count": items.len()
+        }).to_string()))
+    }
+}
+
+
+// ============================================================================
+// Memory Transaction Tool
+// ============================================================================
+
+/// Tool for applying several memory operations atomically.
+#[derive(Debug)]
+pub struct MemoryTransactionTool;
+
+#[derive(Deserialize)]
+struct MemoryTransactionArgs {
+    ops: Vec<KvOp>,
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[async_trait]
+impl Tool for MemoryTransactionTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "memory.transaction".to_string(),
+            description: Some(
+                "Applies a batch of memory operations atomically: if any operation fails \
+                 (including a 'check'), none of them take effect."
+                    .to_string(),
+            ),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "ops": {
+                        "type": "array",
+                        "description": "Operations to apply in order",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "op": {
+                                    "type": "string",
+                                    "enum": ["get", "set", "delete", "incr", "check"],
+                                    "description": "get: read a key; set: write a key; delete: remove a key; incr: add 'by' (default 1) to an integer key; check: abort unless the key holds 'expected' (null = absent)"
+                                },
+                                "key": { "type": "string" },
+                                "value": { "description": "Value for 'set'" },
+                                "ttl_secs": { "type": "integer", "description": "Optional TTL for 'set'" },
+                                "by": { "type": "integer", "description": "Increment for 'incr'" },
+                                "expected": { "description": "Expected value for 'check'" }
+                            },
+                            "required": ["op", "key"]
+                        }
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Optional namespace (defaults to the pinned conversation, then the caller's API key name)"
+                    }
+                },
+                "required": ["ops"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: MemoryTransactionArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        if args.ops.is_empty() {
+            return Err(ToolError::InvalidInput("ops must not be empty".to_string()));
+        }
+
+        let namespace = resolve_namespace(args.namespace, &state)?;
+        let mut ops = args.ops;
+        for op in &mut ops {
+            let key = op.key_mut();
+            *key = storage_key(namespace.as_deref(), None, key)?;
+        }
+
+        debug!("Applying memory transaction with {} operations", ops.len());
 
-        let keys = state.memory_store
-            .kv_list(args.prefix.as_deref())
+        let results = state.memory_store
+            .kv_batch(ops.clone())
             .await
-            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            .map_err(|e| ToolError::ExecutionFailed(format!("Transaction rolled back: {}", e)))?;
+        for (op, result) in ops.iter().zip(&results) {
+            match op {
+                KvOp::Set { key, value, .. } => {
+                    state.events.publish("memory.set", key, serde_json::json!({ "value": value }))
+                }
+                KvOp::Incr { key, .. } => {
+                    state.events.publish("memory.set", key, serde_json::json!({ "value": result }))
+                }
+                KvOp::Delete { key } if result == &serde_json::Value::Bool(true) => {
+                    state.events.publish("memory.deleted", key, serde_json::json!({}))
+                }
+                _ => {}
+            }
+        }
 
         Ok(ToolOutput::text(serde_json::json!({
-            "keys": keys,
-            "count": keys.len()
+            "committed": true,
+            "namespace": namespace,
+            "results": results
         }).to_string()))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+    use serde_json::json;
+
+    fn text(output: &ToolOutput) -> Value {
+        match &output.content[0] {
+            ToolContent::Text { text } => serde_json::from_str(text).unwrap(),
+            _ => panic!("expected text output"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_state_keys_are_reserved() {
+        use crate::core::events::EVENT_RULES_KEY;
+        use crate::core::idempotency::IDEMPOTENCY_KEY_PREFIX;
+        use crate::core::state::DISABLED_TOOLS_KEY;
+        use crate::scheduler::heartbeat::HEARTBEAT_PREFIX;
+        use crate::scheduler::SCHEDULED_TASKS_KEY;
+        use crate::tools::middleware::OUTPUT_KEY_PREFIX;
+
+        for key in [
+            SCHEDULED_TASKS_KEY,
+            EVENT_RULES_KEY,
+            DISABLED_TOOLS_KEY,
+            IDEMPOTENCY_KEY_PREFIX,
+            OUTPUT_KEY_PREFIX,
+            &format!("{}worker", HEARTBEAT_PREFIX),
+            "workflow:deploy",
+            "vector:default:doc#0",
+            "vector_namespace:default",
+        ] {
+            assert!(is_reserved_key(key), "{}", key);
+        }
+        assert!(!is_reserved_key("workflow_log:deploy"));
+
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.memory_store.kv_set(EVENT_RULES_KEY, json!([]), None).await.unwrap();
+
+        let err = MemoryStoreTool
+            .execute(json!({"key": SCHEDULED_TASKS_KEY, "value": []}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+        assert!(MemoryRecallTool.execute(json!({"key": EVENT_RULES_KEY}), state.clone()).await.is_err());
+        assert!(MemoryDeleteTool.execute(json!({"key": EVENT_RULES_KEY}), state.clone()).await.is_err());
+        let err = MemoryTransactionTool
+            .execute(json!({"ops": [{"op": "set", "key": "idempotency:x", "value": 1}]}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+        let listed = MemoryListTool.execute(json!({}), state.clone()).await.unwrap();
+        assert_eq!(text(&listed)["count"], 0);
+        assert!(state.memory_store.kv_get(EVENT_RULES_KEY).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_keys_cannot_reach_other_namespaces() {
+        use crate::core::{KeyIdentity, RequestContext};
+
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let as_key = |name: &str| {
+            let identity = KeyIdentity { name: name.to_string(), key_hash: name.to_string(), scopes: vec!["*".to_string()] };
+            RequestContext::new(name).with_identity(Some(Arc::new(identity)))
+        };
+
+        caller::with_context(as_key("bob"), async {
+            MemoryStoreTool.execute(json!({"key": "plan", "value": "secret"}), state.clone()).await.unwrap();
+        })
+        .await;
+
+        caller::with_context(as_key("alice"), async {
+            let err = MemoryRecallTool.execute(json!({"key": "plan", "namespace": "bob"}), state.clone()).await.unwrap_err();
+            assert!(matches!(err, ToolError::PermissionDenied(_)), "{}", err);
+            let err = MemoryListTool.execute(json!({"namespace": "bob"}), state.clone()).await.unwrap_err();
+            assert!(matches!(err, ToolError::PermissionDenied(_)), "{}", err);
+            let err = MemoryStoreTool
+                .execute(json!({"key": "plan", "value": "mine", "namespace": "bob"}), state.clone())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+            // Naming its own namespace is fine, and doesn't reach bob's key
+            let own = MemoryRecallTool.execute(json!({"key": "plan", "namespace": "alice"}), state.clone()).await.unwrap();
+            assert_eq!(text(&own)["found"], false);
+        })
+        .await;
+
+        let bobs = state.memory_store.kv_get(&namespaced_key(Some("bob"), "plan")).await.unwrap().unwrap();
+        assert_eq!(bobs.value, "secret");
+    }
+
+    #[tokio::test]
+    async fn test_transaction_commits_or_rolls_back() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        let output = MemoryTransactionTool
+            .execute(json!({
+                "namespace": "jobs",
+                "ops": [
+                    {"op": "check", "key": "job:1", "expected": null},
+                    {"op": "set", "key": "job:1", "value": {"status": "queued"}},
+                    {"op": "incr", "key": "count"},
+                    {"op": "get", "key": "job:1"}
+                ]
+            }), state.clone())
+            .await
+            .unwrap();
+        let result = text(&output);
+        assert_eq!(result["results"][2], json!(1));
+        assert_eq!(result["results"][3], json!({"status": "queued"}));
+
+        // The failed check undoes the earlier increment
+        let err = MemoryTransactionTool
+            .execute(json!({
+                "namespace": "jobs",
+                "ops": [
+                    {"op": "incr", "key": "count"},
+                    {"op": "check", "key": "job:1", "expected": null}
+                ]
+            }), state.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("rolled back"));
+
+        let count = state.memory_store.kv_get("ns:jobs:count").await.unwrap().unwrap();
+        assert_eq!(count.value, json!(1));
+
+        // "jobs:count" in namespace "ns" would alias "count" in "jobs"
+        let err = MemoryTransactionTool
+            .execute(json!({"namespace": "ns:jobs", "ops": [{"op": "get", "key": "count"}]}), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+
+    #[tokio::test]
+    async fn test_collection_store_and_list() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            collections: vec![serde_json::from_value(json!({
+                "name": "tasks",
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "status": { "type": "string" },
+                        "priority": { "type": "integer" }
+                    },
+                    "required": ["status"]
+                },
+                "indexes": ["status", "priority"]
+            }))
+            .unwrap()],
+            ..Config::default()
+        }));
+
+        let err = MemoryStoreTool
+            .execute(json!({"collection": "tasks", "key": "t0", "value": {"priority": 1}}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+
+        for (key, status, priority) in [("t1", "open", 3), ("t2", "done", 1), ("t3", "open", 5)] {
+            MemoryStoreTool
+                .execute(json!({
+                    "collection": "tasks",
+                    "key": key,
+                    "value": {"status": status, "priority": priority}
+                }), state.clone())
+                .await
+                .unwrap();
+        }
+
+        let output = MemoryListTool
+            .execute(json!({
+                "collection": "tasks",
+                "filter": {"status": "open"},
+                "sort_by": "priority",
+                "order": "desc"
+            }), state.clone())
+            .await
+            .unwrap();
+        let result = text(&output);
+        assert_eq!(result["count"], json!(2));
+        assert_eq!(result["items"][0]["key"], json!("t3"));
+        assert_eq!(result["items"][1]["key"], json!("t1"));
+
+        // Collection entries live under their own prefix
+        let recalled = text(&MemoryRecallTool
+            .execute(json!({"collection": "tasks", "key": "t1"}), state.clone())
+            .await
+            .unwrap());
+        assert_eq!(recalled["value"]["priority"], json!(3));
+        assert!(state.memory_store.kv_get("t1").await.unwrap().is_none());
+
+        let err = MemoryListTool
+            .execute(json!({"collection": "tasks", "sort_by": "title"}), state)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not an indexed field"));
+    }
+}
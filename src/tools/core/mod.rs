@@ -5,26 +5,57 @@
 
 mod echo;
 mod get_time;
+mod datetime;
 mod fs_read;
 mod fs_write;
+mod fs_watch;
+mod archive;
+mod text;
+mod csv;
+mod data;
+mod template;
+mod crypto;
 mod cmd_exec;
+mod process;
 mod memory;
 mod http_request;
+mod cache;
 mod env;
+mod path;
 mod utils;
+mod jq;
+mod admin;
 
 use std::sync::Arc;
 use crate::tools::ToolRegistry;
 use crate::core::Config;
 
 pub use echo::EchoTool;
-pub use get_time::GetTimeTool;
+pub use get_time::{GetTimeTool, TimeNowTool};
+pub use datetime::{TimeParseTool, TimeFormatTool, TimeAddTool, TimeDiffTool, TimeConvertTzTool};
 pub use fs_read::FsReadTool;
+pub(crate) use fs_read::{is_image, read_allowed_file};
 pub use fs_write::FsWriteTool;
+pub use fs_watch::{FsWatchTool, FsWatchEventsTool, FsUnwatchTool};
+pub use archive::{FsArchiveTool, FsUnarchiveTool};
+pub use text::{TextDiffTool, TextPatchTool};
+pub use self::csv::{CsvParseTool, CsvQueryTool};
+pub use data::DataConvertTool;
+pub use template::TemplateRenderTool;
+pub use crypto::{HashAlgorithm, HashDigestTool, HmacSignTool, HmacVerifyTool, RandomBytesTool, JwtDecodeTool};
 pub use cmd_exec::CmdExecTool;
-pub use memory::{MemoryStoreTool, MemoryRecallTool, MemoryDeleteTool, MemoryListTool};
+pub(crate) use cmd_exec::resolve_working_dir;
+#[cfg(feature = "docker")]
+pub(crate) use cmd_exec::is_command_allowed;
+pub use process::{ProcessStartTool, ProcessListTool, ProcessLogsTool, ProcessStopTool};
+pub(crate) use memory::{complete_keys, resolve_namespace};
+pub use memory::{MemoryStoreTool, MemoryRecallTool, MemoryDeleteTool, MemoryListTool, MemoryTransactionTool};
 pub use http_request::HttpRequestTool;
+pub(crate) use http_request::check_url_patterns;
+pub use cache::{CacheClearTool, CacheStatsTool};
 pub use env::{EnvGetTool, EnvListTool, SysInfoTool};
+pub use admin::{AdminToolsTool, LogsTailTool};
+pub use path::{PathJoinTool, PathNormalizeTool, PathRelativeTool, PathBasenameTool};
 pub use utils::{
     Base64EncodeTool, Base64DecodeTool,
     JsonParseTool, JsonQueryTool,
@@ -32,29 +63,58 @@ pub use utils::{
     RegexMatchTool, RegexReplaceTool,
 };
 
-/// Registers all core tools with the registry.
-/// These are the essential tools that define Nexus as an MCP runtime.
-pub fn register_core_tools(registry: &mut ToolRegistry, config: &Config) {
+/// Registers all core tools with the registry and returns how many were
+/// registered. These are the essential tools that define Nexus as an MCP runtime.
+pub fn register_core_tools(registry: &mut ToolRegistry, config: &Config) -> usize {
+    let before = registry.tools.len();
+
     // Basic utilities (always available)
     registry.register(Arc::new(EchoTool));
     registry.register(Arc::new(GetTimeTool));
+    registry.register(Arc::new(TimeNowTool));
+    registry.register(Arc::new(TimeParseTool));
+    registry.register(Arc::new(TimeFormatTool));
+    registry.register(Arc::new(TimeAddTool));
+    registry.register(Arc::new(TimeDiffTool));
+    registry.register(Arc::new(TimeConvertTzTool));
     registry.register(Arc::new(UuidGenerateTool));
 
     // Filesystem tools (restricted by config)
     registry.register(Arc::new(FsReadTool::new(config.security.allowed_read_paths.clone())));
     registry.register(Arc::new(FsWriteTool::new(config.security.allowed_write_paths.clone())));
+    registry.register(Arc::new(FsWatchTool::new(config.security.allowed_read_paths.clone())));
+    registry.register(Arc::new(FsWatchEventsTool));
+    registry.register(Arc::new(FsUnwatchTool));
+    registry.register(Arc::new(FsArchiveTool::new(&config.security)));
+    registry.register(Arc::new(FsUnarchiveTool::new(&config.security)));
 
     // Command execution (restricted by config)
-    registry.register(Arc::new(CmdExecTool::new(config.security.allowed_commands.clone())));
+    registry.register(Arc::new(
+        CmdExecTool::new(config.security.allowed_commands.clone())
+            .with_working_dirs(config.security.allowed_working_dirs.clone())
+            .with_max_output_bytes(config.security.max_command_output_bytes),
+    ));
+
+    // Background processes (same command restrictions as cmd.exec)
+    registry.register(Arc::new(ProcessStartTool::new(
+        config.security.allowed_commands.clone(),
+        config.security.allowed_working_dirs.clone(),
+    )));
+    registry.register(Arc::new(ProcessListTool));
+    registry.register(Arc::new(ProcessLogsTool));
+    registry.register(Arc::new(ProcessStopTool));
 
     // Memory tools (always available)
     registry.register(Arc::new(MemoryStoreTool));
     registry.register(Arc::new(MemoryRecallTool));
     registry.register(Arc::new(MemoryDeleteTool));
     registry.register(Arc::new(MemoryListTool));
+    registry.register(Arc::new(MemoryTransactionTool));
 
     // HTTP request tool (restricted by config)
     registry.register(Arc::new(HttpRequestTool::new(config)));
+    registry.register(Arc::new(CacheClearTool));
+    registry.register(Arc::new(CacheStatsTool));
 
     // Environment/System tools
     registry.register(Arc::new(EnvGetTool));
@@ -66,17 +126,33 @@ pub fn register_core_tools(registry: &mut ToolRegistry, config: &Config) {
     registry.register(Arc::new(Base64DecodeTool));
     registry.register(Arc::new(JsonParseTool));
     registry.register(Arc::new(JsonQueryTool));
+    registry.register(Arc::new(CsvParseTool));
+    registry.register(Arc::new(CsvQueryTool));
+    registry.register(Arc::new(DataConvertTool));
+    registry.register(Arc::new(TemplateRenderTool));
     registry.register(Arc::new(HashTool));
+    registry.register(Arc::new(HashDigestTool::new(HashAlgorithm::Md5)));
+    registry.register(Arc::new(HashDigestTool::new(HashAlgorithm::Sha1)));
+    registry.register(Arc::new(HashDigestTool::new(HashAlgorithm::Sha512)));
+    registry.register(Arc::new(HashDigestTool::new(HashAlgorithm::Blake3)));
+    registry.register(Arc::new(HmacSignTool));
+    registry.register(Arc::new(HmacVerifyTool));
+    registry.register(Arc::new(RandomBytesTool));
+    registry.register(Arc::new(JwtDecodeTool));
     registry.register(Arc::new(RegexMatchTool));
     registry.register(Arc::new(RegexReplaceTool));
-}
+    registry.register(Arc::new(TextDiffTool));
+    registry.register(Arc::new(TextPatchTool));
 
-/// Returns the count of core tools.
-pub fn core_tool_count() -> usize {
-    18 // echo, get_time, uuid, fs.read, fs.write, cmd.exec, 
-       // memory.store/recall/delete/list, http.request,
-       // env.get/list, sys.info, base64.encode/decode,
-       // json.parse/query, hash.sha256, regex.match/replace
-}
+    // Path utilities
+    registry.register(Arc::new(PathJoinTool));
+    registry.register(Arc::new(PathNormalizeTool));
+    registry.register(Arc::new(PathRelativeTool));
+    registry.register(Arc::new(PathBasenameTool));
 
+    // Runtime administration
+    registry.register(Arc::new(AdminToolsTool));
+    registry.register(Arc::new(LogsTailTool));
 
+    registry.tools.len() - before
+}
@@ -0,0 +1,513 @@
+//! Path tools: join, normalize, relative, basename.
+//!
+//! Paths are handled lexically (the filesystem is never touched) in either
+//! POSIX or Windows style, so agents can build valid paths for the host they
+//! target rather than concatenating strings. Components are Unicode
+//! normalized (NFC by default) so visually identical names compare equal.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+/// Path syntax to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PathStyle {
+    Posix,
+    Windows,
+    /// The style of the host Aegis runs on.
+    #[default]
+    Native,
+}
+
+impl PathStyle {
+    fn resolve(self) -> PathStyle {
+        match self {
+            PathStyle::Native if cfg!(windows) => PathStyle::Windows,
+            PathStyle::Native => PathStyle::Posix,
+            style => style,
+        }
+    }
+
+    fn separator(self) -> char {
+        match self.resolve() {
+            PathStyle::Windows => '\\',
+            _ => '/',
+        }
+    }
+
+    fn is_separator(self, c: char) -> bool {
+        match self.resolve() {
+            PathStyle::Windows => c == '\\' || c == '/',
+            _ => c == '/',
+        }
+    }
+}
+
+/// Unicode normalization form applied to path components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnicodeForm {
+    #[default]
+    Nfc,
+    Nfd,
+    None,
+}
+
+impl UnicodeForm {
+    fn apply(self, s: &str) -> String {
+        match self {
+            UnicodeForm::Nfc => s.nfc().collect(),
+            UnicodeForm::Nfd => s.nfd().collect(),
+            UnicodeForm::None => s.to_string(),
+        }
+    }
+}
+
+/// A path split into its root and components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParsedPath {
+    /// Drive (`C:`) or UNC share (`\\server\share`) on Windows.
+    prefix: String,
+    /// Whether the path starts at a root separator.
+    absolute: bool,
+    components: Vec<String>,
+}
+
+impl ParsedPath {
+    fn parse(path: &str, style: PathStyle, form: UnicodeForm) -> Self {
+        let path = form.apply(path);
+        let mut rest = path.as_str();
+        let mut prefix = String::new();
+
+        if style.resolve() == PathStyle::Windows {
+            let bytes = rest.as_bytes();
+            if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+                prefix = rest[..2].to_ascii_uppercase();
+                rest = &rest[2..];
+            } else if rest.starts_with("\\\\") || rest.starts_with("//") {
+                // UNC: \\server\share is the prefix; what follows is absolute
+                let mut parts = rest[2..].splitn(3, |c| style.is_separator(c));
+                let server = parts.next().unwrap_or("");
+                let share = parts.next().unwrap_or("");
+                prefix = format!("\\\\{}\\{}", server, share);
+                let consumed = 2 + server.len() + share.len() + usize::from(!share.is_empty());
+                rest = &rest[consumed.min(rest.len())..];
+                let components = split_components(rest, style);
+                return Self { prefix, absolute: true, components };
+            }
+        }
+
+        let absolute = rest.starts_with(|c| style.is_separator(c));
+        Self { prefix, absolute, components: split_components(rest, style) }
+    }
+
+    /// Resolves `.` and `..` lexically. `..` above an absolute root is dropped.
+    fn normalized(mut self) -> Self {
+        let mut out: Vec<String> = Vec::new();
+        for component in self.components.drain(..) {
+            match component.as_str() {
+                "." => {}
+                ".." => match out.last() {
+                    Some(last) if last != ".." => {
+                        out.pop();
+                    }
+                    _ if self.absolute => {}
+                    _ => out.push(component),
+                },
+                _ => out.push(component),
+            }
+        }
+        self.components = out;
+        self
+    }
+
+    fn render(&self, style: PathStyle) -> String {
+        let sep = style.separator().to_string();
+        let body = self.components.join(&sep);
+        let root = if self.absolute { sep.as_str() } else { "" };
+
+        let rendered = format!("{}{}{}", self.prefix, root, body);
+        if rendered.is_empty() { ".".to_string() } else { rendered }
+    }
+}
+
+fn split_components(path: &str, style: PathStyle) -> Vec<String> {
+    path.split(|c| style.is_separator(c))
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// Compares components, case-insensitively on Windows.
+fn same_component(a: &str, b: &str, style: PathStyle) -> bool {
+    match style.resolve() {
+        PathStyle::Windows => a.to_lowercase() == b.to_lowercase(),
+        _ => a == b,
+    }
+}
+
+fn normalize(path: &str, style: PathStyle, form: UnicodeForm) -> String {
+    ParsedPath::parse(path, style, form).normalized().render(style)
+}
+
+fn join(parts: &[String], style: PathStyle, form: UnicodeForm) -> String {
+    let mut joined = ParsedPath { prefix: String::new(), absolute: false, components: vec![] };
+
+    for part in parts {
+        let parsed = ParsedPath::parse(part, style, form);
+        if parsed.absolute {
+            // An absolute part restarts the path, keeping the drive if it has none
+            let prefix = if parsed.prefix.is_empty() { joined.prefix.clone() } else { parsed.prefix };
+            joined = ParsedPath { prefix, ..parsed };
+        } else if !parsed.prefix.is_empty() && !same_component(&parsed.prefix, &joined.prefix, style) {
+            // Drive-relative path on another drive (C:foo)
+            joined = parsed;
+        } else {
+            joined.components.extend(parsed.components);
+        }
+    }
+
+    joined.normalized().render(style)
+}
+
+fn relative(from: &str, to: &str, style: PathStyle, form: UnicodeForm) -> Result<String, String> {
+    let from = ParsedPath::parse(from, style, form).normalized();
+    let to = ParsedPath::parse(to, style, form).normalized();
+
+    if from.absolute != to.absolute || !same_component(&from.prefix, &to.prefix, style) {
+        return Err(format!(
+            "cannot relate '{}' to '{}': paths must both be absolute or both relative, on the same drive",
+            to.render(style),
+            from.render(style)
+        ));
+    }
+    if from.components.iter().any(|c| c == "..") {
+        return Err("'from' must not climb above its starting point ('..')".to_string());
+    }
+
+    let common = from
+        .components
+        .iter()
+        .zip(&to.components)
+        .take_while(|(a, b)| same_component(a, b, style))
+        .count();
+
+    let mut components: Vec<String> = vec!["..".to_string(); from.components.len() - common];
+    components.extend(to.components[common..].iter().cloned());
+
+    Ok(ParsedPath { prefix: String::new(), absolute: false, components }.render(style))
+}
+
+fn basename(path: &str, style: PathStyle, form: UnicodeForm) -> Value {
+    let parsed = ParsedPath::parse(path, style, form).normalized();
+    let name = parsed.components.last().filter(|c| *c != "..").cloned().unwrap_or_default();
+
+    // A leading dot marks a hidden file, not an extension
+    let (stem, extension) = match name.rfind('.') {
+        Some(i) if i > 0 => (name[..i].to_string(), Some(name[i + 1..].to_string())),
+        _ => (name.clone(), None),
+    };
+
+    let mut parent = parsed.clone();
+    if !name.is_empty() {
+        parent.components.pop();
+    }
+
+    json!({
+        "basename": name,
+        "stem": stem,
+        "extension": extension,
+        "dirname": parent.render(style)
+    })
+}
+
+fn style_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["native", "posix", "windows"],
+        "description": "Path syntax (default: native to the server)"
+    })
+}
+
+fn unicode_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["nfc", "nfd", "none"],
+        "description": "Unicode normalization of components (default: nfc)"
+    })
+}
+
+#[derive(Deserialize)]
+struct PathArgs {
+    path: String,
+    #[serde(default)]
+    style: PathStyle,
+    #[serde(default)]
+    unicode: UnicodeForm,
+}
+
+fn parse_args<T: serde::de::DeserializeOwned>(arguments: Value) -> Result<T, ToolError> {
+    serde_json::from_value(arguments).map_err(|e| ToolError::InvalidInput(e.to_string()))
+}
+
+// ============================================================================
+// Path Join Tool
+// ============================================================================
+
+#[derive(Debug)]
+pub struct PathJoinTool;
+
+#[derive(Deserialize)]
+struct PathJoinArgs {
+    parts: Vec<String>,
+    #[serde(default)]
+    style: PathStyle,
+    #[serde(default)]
+    unicode: UnicodeForm,
+}
+
+#[async_trait]
+impl Tool for PathJoinTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "path.join".to_string(),
+            description: Some(
+                "Joins path segments with the right separator and normalizes the result. \
+                 An absolute segment restarts the path."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "parts": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Path segments to join"
+                    },
+                    "style": style_schema(),
+                    "unicode": unicode_schema()
+                },
+                "required": ["parts"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: PathJoinArgs = parse_args(arguments)?;
+        if args.parts.is_empty() {
+            return Err(ToolError::InvalidInput("parts must not be empty".to_string()));
+        }
+
+        Ok(ToolOutput::text(join(&args.parts, args.style, args.unicode)))
+    }
+}
+
+// ============================================================================
+// Path Normalize Tool
+// ============================================================================
+
+#[derive(Debug)]
+pub struct PathNormalizeTool;
+
+#[async_trait]
+impl Tool for PathNormalizeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "path.normalize".to_string(),
+            description: Some(
+                "Normalizes a path: unifies separators, resolves '.' and '..', and applies \
+                 Unicode normalization. Does not touch the filesystem."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to normalize"
+                    },
+                    "style": style_schema(),
+                    "unicode": unicode_schema()
+                },
+                "required": ["path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: PathArgs = parse_args(arguments)?;
+        Ok(ToolOutput::text(normalize(&args.path, args.style, args.unicode)))
+    }
+}
+
+// ============================================================================
+// Path Relative Tool
+// ============================================================================
+
+#[derive(Debug)]
+pub struct PathRelativeTool;
+
+#[derive(Deserialize)]
+struct PathRelativeArgs {
+    from: String,
+    to: String,
+    #[serde(default)]
+    style: PathStyle,
+    #[serde(default)]
+    unicode: UnicodeForm,
+}
+
+#[async_trait]
+impl Tool for PathRelativeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "path.relative".to_string(),
+            description: Some("Computes the relative path from one directory to another path.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "from": {
+                        "type": "string",
+                        "description": "Directory to start from"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Target path"
+                    },
+                    "style": style_schema(),
+                    "unicode": unicode_schema()
+                },
+                "required": ["from", "to"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: PathRelativeArgs = parse_args(arguments)?;
+        relative(&args.from, &args.to, args.style, args.unicode)
+            .map(ToolOutput::text)
+            .map_err(ToolError::InvalidInput)
+    }
+}
+
+// ============================================================================
+// Path Basename Tool
+// ============================================================================
+
+#[derive(Debug)]
+pub struct PathBasenameTool;
+
+#[async_trait]
+impl Tool for PathBasenameTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "path.basename".to_string(),
+            description: Some(
+                "Splits a path into its file name, stem, extension, and parent directory.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to split"
+                    },
+                    "style": style_schema(),
+                    "unicode": unicode_schema()
+                },
+                "required": ["path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: PathArgs = parse_args(arguments)?;
+        Ok(ToolOutput::text(basename(&args.path, args.style, args.unicode).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSIX: PathStyle = PathStyle::Posix;
+    const WINDOWS: PathStyle = PathStyle::Windows;
+    const NFC: UnicodeForm = UnicodeForm::Nfc;
+
+    fn parts(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/a//b/./c/../d/", POSIX, NFC), "/a/b/d");
+        assert_eq!(normalize("../a/../../b", POSIX, NFC), "../../b");
+        assert_eq!(normalize("/../a", POSIX, NFC), "/a");
+        assert_eq!(normalize("a/..", POSIX, NFC), ".");
+        assert_eq!(normalize("c:/Users\\me/../you", WINDOWS, NFC), "C:\\Users\\you");
+        assert_eq!(normalize("\\\\server\\share/dir/../f.txt", WINDOWS, NFC), "\\\\server\\share\\f.txt");
+
+        // "e" + combining acute accent becomes a single "é"
+        assert_eq!(normalize("cafe\u{301}/menu", POSIX, NFC), "caf\u{e9}/menu");
+        assert_eq!(normalize("caf\u{e9}", POSIX, UnicodeForm::Nfd), "cafe\u{301}");
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join(&parts(&["/srv", "app/", "../data", "f.txt"]), POSIX, NFC), "/srv/data/f.txt");
+        assert_eq!(join(&parts(&["a", "/etc", "hosts"]), POSIX, NFC), "/etc/hosts");
+        assert_eq!(join(&parts(&["C:\\Users", "me", "Documents"]), WINDOWS, NFC), "C:\\Users\\me\\Documents");
+        assert_eq!(join(&parts(&["C:\\Users", "\\Windows"]), WINDOWS, NFC), "C:\\Windows");
+        assert_eq!(join(&parts(&["C:\\Users", "D:\\data"]), WINDOWS, NFC), "D:\\data");
+    }
+
+    #[test]
+    fn test_relative() {
+        assert_eq!(relative("/a/b/c", "/a/d/e", POSIX, NFC).unwrap(), "../../d/e");
+        assert_eq!(relative("/a/b", "/a/b", POSIX, NFC).unwrap(), ".");
+        assert_eq!(relative("C:\\Users\\Me", "c:\\users\\me\\docs", WINDOWS, NFC).unwrap(), "docs");
+        assert!(relative("C:\\a", "D:\\a", WINDOWS, NFC).is_err());
+        assert!(relative("/a", "b", POSIX, NFC).is_err());
+    }
+
+    #[test]
+    fn test_basename() {
+        let parts = basename("/home/me/archive.tar.gz", POSIX, NFC);
+        assert_eq!(parts["basename"], "archive.tar.gz");
+        assert_eq!(parts["stem"], "archive.tar");
+        assert_eq!(parts["extension"], "gz");
+        assert_eq!(parts["dirname"], "/home/me");
+
+        let hidden = basename("C:\\Users\\me\\.gitconfig", WINDOWS, NFC);
+        assert_eq!(hidden["basename"], ".gitconfig");
+        assert_eq!(hidden["extension"], Value::Null);
+        assert_eq!(hidden["dirname"], "C:\\Users\\me");
+    }
+}
@@ -0,0 +1,389 @@
+//! Background process tools - start, list, tail, and stop long-running processes.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::process_manager::{BackgroundProcess, DEFAULT_LOG_LINES};
+use crate::tools::{OutputStream, ProcessRequest, StopSignal, Tool, ToolError, ToolOutput};
+
+use super::cmd_exec::{is_command_allowed, resolve_env, resolve_working_dir};
+
+/// Most output lines a single process may buffer.
+const MAX_LOG_LINES: usize = 10_000;
+
+fn lookup(state: &RuntimeState, id: u64) -> Result<Arc<BackgroundProcess>, ToolError> {
+    state
+        .processes
+        .get(id)
+        .ok_or_else(|| ToolError::NotFound(format!("No background process with id {}", id)))
+}
+
+/// Starts a process in the background.
+#[derive(Debug)]
+pub struct ProcessStartTool {
+    allowed_commands: Vec<String>,
+    allowed_working_dirs: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct ProcessStartArgs {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default = "default_log_lines")]
+    log_lines: usize,
+}
+
+fn default_log_lines() -> usize {
+    DEFAULT_LOG_LINES
+}
+
+impl ProcessStartTool {
+    /// Creates the tool with the same restrictions as cmd.exec.
+    pub fn new(allowed_commands: Vec<String>, allowed_working_dirs: Vec<PathBuf>) -> Self {
+        Self { allowed_commands, allowed_working_dirs }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessStartTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "process.start".to_string(),
+            description: Some(
+                "Starts a long-running process (e.g. a dev server) in the background and returns its id. \
+                 Only allowed commands can be run. Read its output with process.logs and end it with process.stop."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The command to run"
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Arguments to pass to the command",
+                        "default": []
+                    },
+                    "cwd": {
+                        "type": "string",
+                        "description": "Working directory (must be within an allowed directory)"
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Extra environment variables; values may reference ${secrets.NAME}"
+                    },
+                    "log_lines": {
+                        "type": "integer",
+                        "description": "Output lines to keep (default: 1000, max: 10000)",
+                        "default": 1000
+                    }
+                },
+                "required": ["command"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: ProcessStartArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        if !is_command_allowed(&self.allowed_commands, &args.command) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Command not in allowed list: {}",
+                args.command
+            )));
+        }
+
+        let cwd = args
+            .cwd
+            .as_deref()
+            .map(|cwd| resolve_working_dir(&self.allowed_working_dirs, cwd))
+            .transpose()?;
+        let env = resolve_env(args.env, &state)?;
+
+        let arg_refs: Vec<&str> = args.args.iter().map(|s| s.as_str()).collect();
+        let mut request = ProcessRequest::new(&args.command, &arg_refs).envs(env);
+        if let Some(cwd) = cwd {
+            request = request.cwd(cwd);
+        }
+
+        let process = state.processes.spawn(request, args.log_lines.clamp(1, MAX_LOG_LINES))?;
+        Ok(ToolOutput::text(process.summary().to_string()))
+    }
+}
+
+/// Lists background processes.
+#[derive(Debug)]
+pub struct ProcessListTool;
+
+#[async_trait]
+impl Tool for ProcessListTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "process.list".to_string(),
+            description: Some(
+                "Lists background processes started with process.start, including recently exited ones.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "running_only": {
+                        "type": "boolean",
+                        "description": "Only list processes that are still running (default: false)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let running_only = arguments
+            .get("running_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let processes: Vec<Value> = state
+            .processes
+            .list()
+            .iter()
+            .filter(|p| !running_only || p.is_running())
+            .map(|p| p.summary())
+            .collect();
+
+        Ok(ToolOutput::text(json!({
+            "processes": processes,
+            "count": processes.len()
+        }).to_string()))
+    }
+}
+
+/// Reads buffered output of a background process.
+#[derive(Debug)]
+pub struct ProcessLogsTool;
+
+#[derive(Deserialize)]
+struct ProcessLogsArgs {
+    id: u64,
+    #[serde(default)]
+    since: Option<u64>,
+    #[serde(default = "default_tail")]
+    tail: usize,
+    #[serde(default)]
+    stream: Option<OutputStream>,
+}
+
+fn default_tail() -> usize {
+    100
+}
+
+#[async_trait]
+impl Tool for ProcessLogsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "process.logs".to_string(),
+            description: Some(
+                "Reads recent output of a background process. Pass the returned next_seq as 'since' to follow new output."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Process id from process.start"
+                    },
+                    "since": {
+                        "type": "integer",
+                        "description": "Return lines from this sequence number on (from a previous next_seq)"
+                    },
+                    "tail": {
+                        "type": "integer",
+                        "description": "Maximum lines to return (default: 100); without 'since', the last lines",
+                        "default": 100
+                    },
+                    "stream": {
+                        "type": "string",
+                        "enum": ["stdout", "stderr"],
+                        "description": "Only lines from this stream"
+                    }
+                },
+                "required": ["id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: ProcessLogsArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        let process = lookup(&state, args.id)?;
+        let logs = process.logs(args.since, args.tail, args.stream);
+
+        let mut result = serde_json::to_value(&logs)
+            .map_err(|e| ToolError::Internal(e.to_string()))?;
+        result["process"] = process.summary();
+        Ok(ToolOutput::text(result.to_string()))
+    }
+}
+
+/// Stops a background process.
+#[derive(Debug)]
+pub struct ProcessStopTool;
+
+#[derive(Deserialize)]
+struct ProcessStopArgs {
+    id: u64,
+    #[serde(default)]
+    signal: StopSignal,
+    #[serde(default = "default_grace")]
+    grace_secs: u64,
+}
+
+fn default_grace() -> u64 {
+    5
+}
+
+#[async_trait]
+impl Tool for ProcessStopTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "process.stop".to_string(),
+            description: Some(
+                "Stops a background process with a signal, killing it if it has not exited after the grace period."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Process id from process.start"
+                    },
+                    "signal": {
+                        "type": "string",
+                        "enum": ["TERM", "INT", "KILL"],
+                        "description": "Signal to send (default: TERM)",
+                        "default": "TERM"
+                    },
+                    "grace_secs": {
+                        "type": "integer",
+                        "description": "Seconds to wait before killing (default: 5, max: 60)",
+                        "default": 5
+                    }
+                },
+                "required": ["id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: ProcessStopArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        let process = lookup(&state, args.id)?;
+        let was_running = process.is_running();
+        process
+            .stop(args.signal, Duration::from_secs(args.grace_secs.min(60)))
+            .await;
+
+        let mut result = process.summary();
+        result["stopped"] = json!(was_running);
+        Ok(ToolOutput::text(result.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+
+    fn output_json(output: &ToolOutput) -> Value {
+        match &output.content[0] {
+            ToolContent::Text { text } => serde_json::from_str(text).unwrap(),
+            _ => panic!("expected text output"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_logs_stop() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let start = ProcessStartTool::new(vec!["sh".to_string()], vec![PathBuf::from(".")]);
+
+        let err = start
+            .execute(json!({"command": "python3"}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+        let started = output_json(
+            &start
+                .execute(json!({"command": "sh", "args": ["-c", "echo ready; exec sleep 30"]}), state.clone())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(started["status"], "running");
+        let id = started["id"].as_u64().unwrap();
+
+        let mut logs = Value::Null;
+        for _ in 0..50 {
+            logs = output_json(&ProcessLogsTool.execute(json!({"id": id, "since": 0}), state.clone()).await.unwrap());
+            if logs["next_seq"] == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(logs["lines"][0]["text"], "ready");
+        assert_eq!(logs["lines"][0]["stream"], "stdout");
+
+        let list = output_json(&ProcessListTool.execute(json!({"running_only": true}), state.clone()).await.unwrap());
+        assert_eq!(list["count"], 1);
+
+        let stopped = output_json(&ProcessStopTool.execute(json!({"id": id}), state.clone()).await.unwrap());
+        assert_eq!(stopped["stopped"], true);
+        assert_eq!(stopped["status"], "exited");
+
+        let err = ProcessLogsTool.execute(json!({"id": 999}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+    }
+}
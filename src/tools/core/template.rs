@@ -0,0 +1,156 @@
+//! Template rendering with Jinja-style syntax (minijinja).
+//!
+//! Templates come inline or by name from the `templates` config section.
+//! Named templates can `{% include %}` and `{% extends %}` each other, and
+//! those whose names end in `.html` or `.xml` escape their output.
+
+use async_trait::async_trait;
+use minijinja::{Environment, UndefinedBehavior};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Name inline templates are reported under in errors.
+const INLINE_NAME: &str = "<template>";
+
+/// Message of a render error, with the template and line it came from.
+fn describe(error: &minijinja::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Renders a named template or an inline one with `context`.
+fn render(
+    templates: &std::collections::HashMap<String, String>,
+    name: Option<&str>,
+    inline: Option<&str>,
+    context: &Value,
+    strict: bool,
+) -> Result<String, ToolError> {
+    let mut environment = Environment::new();
+    if strict {
+        environment.set_undefined_behavior(UndefinedBehavior::Strict);
+    }
+    for (template_name, source) in templates {
+        environment
+            .add_template(template_name, source)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Template '{}' is invalid: {}", template_name, describe(&e))))?;
+    }
+
+    let template = match (name, inline) {
+        (Some(name), None) => environment
+            .get_template(name)
+            .map_err(|_| ToolError::NotFound(format!("Template not found: {}", name)))?,
+        (None, Some(source)) => {
+            environment
+                .add_template(INLINE_NAME, source)
+                .map_err(|e| ToolError::InvalidInput(format!("Invalid template: {}", describe(&e))))?;
+            environment.get_template(INLINE_NAME).map_err(|e| ToolError::Internal(e.to_string()))?
+        }
+        _ => return Err(ToolError::InvalidInput("Pass exactly one of 'template' and 'name'".to_string())),
+    };
+
+    template
+        .render(context)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Rendering failed: {}", describe(&e))))
+}
+
+// ============================================================================
+// Template Render Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TemplateRenderArgs {
+    /// Template source, e.g. 'Hello {{ user.name }}{% for i in items %} - {{ i }}{% endfor %}' (or use name)
+    #[serde(default)]
+    template: Option<String>,
+    /// Name of a template from the server's 'templates' config
+    #[serde(default)]
+    name: Option<String>,
+    /// Values the template can use (default: {})
+    #[serde(default)]
+    context: Option<Value>,
+    /// Fail on undefined variables instead of rendering them empty (default: false)
+    #[serde(default)]
+    strict: bool,
+}
+
+#[derive(Debug)]
+pub struct TemplateRenderTool;
+
+#[async_trait]
+impl TypedTool for TemplateRenderTool {
+    type Args = TemplateRenderArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "template.render";
+    const DESCRIPTION: &'static str =
+        "Renders a Jinja-style template (inline or a named one from config) with a JSON context. Supports {{ variables }}, filters, {% if %}, {% for %}, macros and includes.";
+
+    async fn run(&self, args: TemplateRenderArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let context = args.context.unwrap_or_else(|| Value::Object(Default::default()));
+        if !context.is_object() {
+            return Err(ToolError::InvalidInput("'context' must be an object".to_string()));
+        }
+        let rendered = render(
+            &state.config.templates,
+            args.name.as_deref(),
+            args.template.as_deref(),
+            &context,
+            args.strict,
+        )?;
+        Ok(ToolOutput::text(rendered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_render() {
+        let templates = HashMap::from([
+            ("signature".to_string(), "-- {{ team | default('The team') }}".to_string()),
+            (
+                "report.html".to_string(),
+                "<h1>{{ title }}</h1>{% for row in rows %}<p>{{ row }}</p>{% endfor %}".to_string(),
+            ),
+        ]);
+        let context = json!({
+            "user": { "name": "Ada" },
+            "items": [{ "name": "disk", "ok": true }, { "name": "cpu", "ok": false }],
+            "title": "A & B",
+            "rows": ["<b>x</b>"]
+        });
+
+        let inline = "Hi {{ user.name }},\n{% for item in items %}{{ loop.index }}. {{ item.name | upper }}{% if not item.ok %} (failing){% endif %}\n{% endfor %}{% include 'signature' %}";
+        assert_eq!(
+            render(&templates, None, Some(inline), &context, false).unwrap(),
+            "Hi Ada,\n1. DISK\n2. CPU (failing)\n-- The team"
+        );
+
+        // Names ending in .html escape their output
+        assert_eq!(
+            render(&templates, Some("report.html"), None, &context, false).unwrap(),
+            "<h1>A &amp; B</h1><p>&lt;b&gt;x&lt;&#x2f;b&gt;</p>"
+        );
+
+        assert_eq!(render(&templates, None, Some("[{{ missing }}]"), &context, false).unwrap(), "[]");
+        assert!(render(&templates, None, Some("{{ missing }}"), &context, true).is_err());
+        assert!(matches!(render(&templates, Some("nope"), None, &context, false), Err(ToolError::NotFound(_))));
+
+        let error = render(&templates, None, Some("line one\n{% if %}"), &context, false).unwrap_err().to_string();
+        assert!(error.contains("<template>:2"), "{}", error);
+    }
+}
@@ -0,0 +1,226 @@
+//! Text diff tools: unified diffs between texts or files, and applying them.
+//!
+//! An agent can propose an edit as a diff with text.diff, have it
+//! reviewed, then apply it with text.patch (first as a dry run).
+
+use async_trait::async_trait;
+use diffy::{DiffOptions, Line, Patch};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+use super::fs_read::is_path_allowed;
+use super::fs_write::is_write_allowed;
+
+/// Lines of unchanged context around each change by default.
+const DEFAULT_CONTEXT: usize = 3;
+
+/// Reads a file inside `allowed_read_paths`.
+async fn read_allowed(state: &RuntimeState, path: &str) -> Result<String, ToolError> {
+    if !is_path_allowed(&state.config.security.allowed_read_paths, Path::new(path)) {
+        return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", path)));
+    }
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read {}: {}", path, e)))
+}
+
+/// Added and removed line counts of a patch.
+fn line_counts(patch: &Patch<'_, str>) -> (usize, usize) {
+    patch.hunks().iter().flat_map(|hunk| hunk.lines()).fold((0, 0), |(added, removed), line| match line {
+        Line::Insert(_) => (added + 1, removed),
+        Line::Delete(_) => (added, removed + 1),
+        Line::Context(_) => (added, removed),
+    })
+}
+
+// ============================================================================
+// Text Diff Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TextDiffArgs {
+    /// Original text (or use old_path)
+    #[serde(default)]
+    old: Option<String>,
+    /// Modified text (or use new_path)
+    #[serde(default)]
+    new: Option<String>,
+    /// File holding the original text
+    #[serde(default)]
+    old_path: Option<String>,
+    /// File holding the modified text
+    #[serde(default)]
+    new_path: Option<String>,
+    /// Unchanged lines shown around each change (default: 3)
+    #[serde(default)]
+    context: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct TextDiffTool;
+
+#[async_trait]
+impl TypedTool for TextDiffTool {
+    type Args = TextDiffArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "text.diff";
+    const DESCRIPTION: &'static str =
+        "Computes a unified diff between two texts or files (old/new or old_path/new_path). The diff can be applied with text.patch.";
+
+    async fn run(&self, args: TextDiffArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let (old, old_label) = match (args.old, &args.old_path) {
+            (Some(text), None) => (text, "original".to_string()),
+            (None, Some(path)) => (read_allowed(&state, path).await?, format!("a/{}", path)),
+            _ => return Err(ToolError::InvalidInput("Pass exactly one of 'old' and 'old_path'".to_string())),
+        };
+        let (new, new_label) = match (args.new, &args.new_path) {
+            (Some(text), None) => (text, "modified".to_string()),
+            (None, Some(path)) => (read_allowed(&state, path).await?, format!("b/{}", path)),
+            _ => return Err(ToolError::InvalidInput("Pass exactly one of 'new' and 'new_path'".to_string())),
+        };
+
+        let patch = DiffOptions::new()
+            .set_context_len(args.context.unwrap_or(DEFAULT_CONTEXT))
+            .set_original_filename(old_label)
+            .set_modified_filename(new_label)
+            .create_patch(&old, &new);
+        let (additions, deletions) = line_counts(&patch);
+        let identical = patch.hunks().is_empty();
+
+        Ok(ToolOutput::structured(json!({
+            "diff": if identical { String::new() } else { patch.to_string() },
+            "identical": identical,
+            "hunks": patch.hunks().len(),
+            "additions": additions,
+            "deletions": deletions,
+        })))
+    }
+}
+
+// ============================================================================
+// Text Patch Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TextPatchArgs {
+    /// Unified diff to apply (one file's changes, as produced by text.diff or git diff)
+    patch: String,
+    /// File to patch in place (or use text)
+    #[serde(default)]
+    path: Option<String>,
+    /// Text to patch; the result is returned instead of written
+    #[serde(default)]
+    text: Option<String>,
+    /// Check that the patch applies and return the result without writing (default: false)
+    #[serde(default)]
+    dry_run: bool,
+    /// Undo the patch instead of applying it (default: false)
+    #[serde(default)]
+    reverse: bool,
+}
+
+#[derive(Debug)]
+pub struct TextPatchTool;
+
+#[async_trait]
+impl TypedTool for TextPatchTool {
+    type Args = TextPatchArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "text.patch";
+    const DESCRIPTION: &'static str =
+        "Applies a unified diff to a file or text. Hunks may have moved since the diff was made, but their context must match. Use dry_run to check the result before writing.";
+
+    async fn run(&self, args: TextPatchArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let original = match (args.text, &args.path) {
+            (Some(text), None) => text,
+            (None, Some(path)) => read_allowed(&state, path).await?,
+            _ => return Err(ToolError::InvalidInput("Pass exactly one of 'path' and 'text'".to_string())),
+        };
+
+        let parsed = Patch::from_str(&args.patch)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid patch: {}", e)))?;
+        let patch = if args.reverse { parsed.reverse() } else { parsed };
+        let (additions, deletions) = line_counts(&patch);
+        let patched = diffy::apply(&original, &patch)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Patch does not apply: {}", e)))?;
+
+        let mut result = json!({
+            "hunks": patch.hunks().len(),
+            "additions": additions,
+            "deletions": deletions,
+            "dry_run": args.dry_run,
+        });
+
+        match args.path {
+            Some(path) if !args.dry_run => {
+                if !is_write_allowed(&state.config.security.allowed_write_paths, &PathBuf::from(&path)) {
+                    return Err(ToolError::PermissionDenied(format!("Path not in allowed directories: {}", path)));
+                }
+                tokio::fs::write(&path, &patched)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(format!("Failed to write {}: {}", path, e)))?;
+                result["path"] = json!(path);
+                result["written"] = json!(true);
+            }
+            path => {
+                result["path"] = json!(path);
+                result["content"] = json!(patched);
+            }
+        }
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    #[tokio::test]
+    async fn test_diff_then_patch() {
+        let state = Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".into()), ..Config::default() }));
+        let old = "fn main() {\n    println!(\"hi\");\n}\n";
+        let new = "fn main() {\n    println!(\"hello\");\n}\n";
+
+        let diff = TextDiffTool
+            .run(serde_json::from_value(json!({ "old": old, "new": new })).unwrap(), state.clone())
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(diff["additions"], 1);
+        assert_eq!(diff["deletions"], 1);
+        let diff = diff["diff"].as_str().unwrap();
+        assert!(diff.contains("-    println!(\"hi\");"));
+
+        // The file moved on a little since the diff was made
+        let drifted = format!("// header\n{}", old);
+        let patched = TextPatchTool
+            .run(serde_json::from_value(json!({ "patch": diff, "text": drifted })).unwrap(), state.clone())
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(patched["content"], format!("// header\n{}", new));
+
+        let reverted = TextPatchTool
+            .run(serde_json::from_value(json!({ "patch": diff, "text": new, "reverse": true })).unwrap(), state.clone())
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(reverted["content"], old);
+
+        let conflict = TextPatchTool
+            .run(serde_json::from_value(json!({ "patch": diff, "text": "unrelated\n" })).unwrap(), state)
+            .await;
+        assert!(conflict.is_err());
+    }
+}
@@ -3,6 +3,8 @@
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
@@ -10,45 +12,30 @@ use std::sync::Arc;
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
 
 // ============================================================================
 // Base64 Encode Tool
 // ============================================================================
 
+#[derive(Deserialize, JsonSchema)]
+pub struct Base64EncodeArgs {
+    /// Text to encode
+    text: String,
+}
+
 #[derive(Debug)]
 pub struct Base64EncodeTool;
 
 #[async_trait]
-impl Tool for Base64EncodeTool {
-    fn definition(&self) -> ToolDefinition {
-        ToolDefinition {
-            name: "base64.encode".to_string(),
-            description: Some("Encodes text to Base64.".to_string()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "text": {
-                        "type": "string",
-                        "description": "Text to encode"
-                    }
-                },
-                "required": ["text"]
-            }),
-        }
-    }
-
-    async fn execute(
-        &self,
-        arguments: Value,
-        _state: Arc<RuntimeState>,
-    ) -> Result<ToolOutput, ToolError> {
-        let text = arguments
-            .get("text")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'text' parameter".to_string()))?;
-
-        let encoded = BASE64.encode(text.as_bytes());
-        Ok(ToolOutput::text(encoded))
+impl TypedTool for Base64EncodeTool {
+    type Args = Base64EncodeArgs;
+    type Output = String;
+    const NAME: &'static str = "base64.encode";
+    const DESCRIPTION: &'static str = "Encodes text to Base64.";
+
+    async fn run(&self, args: Base64EncodeArgs, _state: Arc<RuntimeState>) -> Result<String, ToolError> {
+        Ok(BASE64.encode(args.text.as_bytes()))
     }
 }
 
@@ -56,46 +43,29 @@ impl Tool for Base64EncodeTool {
 // Base64 Decode Tool
 // ============================================================================
 
+#[derive(Deserialize, JsonSchema)]
+pub struct Base64DecodeArgs {
+    /// Base64 encoded string
+    encoded: String,
+}
+
 #[derive(Debug)]
 pub struct Base64DecodeTool;
 
 #[async_trait]
-impl Tool for Base64DecodeTool {
-    fn definition(&self) -> ToolDefinition {
-        ToolDefinition {
-            name: "base64.decode".to_string(),
-            description: Some("Decodes Base64 to text.".to_string()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "encoded": {
-                        "type": "string",
-                        "description": "Base64 encoded string"
-                    }
-                },
-                "required": ["encoded"]
-            }),
-        }
-    }
-
-    async fn execute(
-        &self,
-        arguments: Value,
-        _state: Arc<RuntimeState>,
-    ) -> Result<ToolOutput, ToolError> {
-        let encoded = arguments
-            .get("encoded")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'encoded' parameter".to_string()))?;
+impl TypedTool for Base64DecodeTool {
+    type Args = Base64DecodeArgs;
+    type Output = String;
+    const NAME: &'static str = "base64.decode";
+    const DESCRIPTION: &'static str = "Decodes Base64 to text.";
 
+    async fn run(&self, args: Base64DecodeArgs, _state: Arc<RuntimeState>) -> Result<String, ToolError> {
         let decoded = BASE64
-            .decode(encoded)
+            .decode(&args.encoded)
             .map_err(|e| ToolError::ExecutionFailed(format!("Invalid Base64: {}", e)))?;
 
-        let text = String::from_utf8(decoded)
-            .map_err(|e| ToolError::ExecutionFailed(format!("Invalid UTF-8: {}", e)))?;
-
-        Ok(ToolOutput::text(text))
+        String::from_utf8(decoded)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Invalid UTF-8: {}", e)))
     }
 }
 
@@ -122,6 +92,7 @@ impl Tool for JsonParseTool {
                 },
                 "required": ["text"]
             }),
+            output_schema: None,
         }
     }
 
@@ -146,7 +117,7 @@ impl Tool for JsonParseTool {
 }
 
 // ============================================================================
-// JSON Query Tool (simple path-based access)
+// JSON Query Tool (dot paths or jq expressions)
 // ============================================================================
 
 #[derive(Debug)]
@@ -158,7 +129,7 @@ impl Tool for JsonQueryTool {
         ToolDefinition {
             name: "json.query".to_string(),
             description: Some(
-                "Queries a JSON object using a dot-notation path (e.g., 'data.items[0].name')."
+                "Queries JSON with a dot-notation path (e.g., 'data.items[0].name') or a jq expression (e.g., '[.items[] | select(.price > 5) | .name]'). An expression with several results returns them as an array."
                     .to_string(),
             ),
             input_schema: json!({
@@ -169,11 +140,12 @@ impl Tool for JsonQueryTool {
                     },
                     "path": {
                         "type": "string",
-                        "description": "Dot-notation path (e.g., 'data.items[0].name')"
+                        "description": "Dot-notation path (e.g., 'data.items[0].name') or jq expression starting with '.' (filters, slices, pipes, object construction)"
                     }
                 },
                 "required": ["json", "path"]
             }),
+            output_schema: None,
         }
     }
 
@@ -199,6 +171,17 @@ impl Tool for JsonQueryTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidInput("Missing 'path' parameter".to_string()))?;
 
+        if super::jq::is_expression(path) {
+            let expr = path.to_string();
+            let mut results = tokio::task::spawn_blocking(move || super::jq::run(&expr, json_value))
+                .await
+                .map_err(|e| ToolError::Internal(e.to_string()))??;
+            let output = if results.len() == 1 { results.remove(0) } else { Value::Array(results) };
+            let result = serde_json::to_string_pretty(&output)
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to format: {}", e)))?;
+            return Ok(ToolOutput::text(result));
+        }
+
         // Simple path parser
         let mut current = &json_value;
         for part in path.split('.') {
@@ -258,6 +241,7 @@ impl Tool for UuidGenerateTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -288,44 +272,26 @@ impl Tool for UuidGenerateTool {
 // Hash Tool
 // ============================================================================
 
+#[derive(Deserialize, JsonSchema)]
+pub struct HashArgs {
+    /// Text to hash
+    text: String,
+}
+
 #[derive(Debug)]
 pub struct HashTool;
 
 #[async_trait]
-impl Tool for HashTool {
-    fn definition(&self) -> ToolDefinition {
-        ToolDefinition {
-            name: "hash.sha256".to_string(),
-            description: Some("Computes SHA-256 hash of text.".to_string()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "text": {
-                        "type": "string",
-                        "description": "Text to hash"
-                    }
-                },
-                "required": ["text"]
-            }),
-        }
-    }
-
-    async fn execute(
-        &self,
-        arguments: Value,
-        _state: Arc<RuntimeState>,
-    ) -> Result<ToolOutput, ToolError> {
-        let text = arguments
-            .get("text")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'text' parameter".to_string()))?;
+impl TypedTool for HashTool {
+    type Args = HashArgs;
+    type Output = String;
+    const NAME: &'static str = "hash.sha256";
+    const DESCRIPTION: &'static str = "Computes SHA-256 hash of text.";
 
+    async fn run(&self, args: HashArgs, _state: Arc<RuntimeState>) -> Result<String, ToolError> {
         let mut hasher = Sha256::new();
-        hasher.update(text.as_bytes());
-        let result = hasher.finalize();
-        let hash = hex::encode(result);
-
-        Ok(ToolOutput::text(hash))
+        hasher.update(args.text.as_bytes());
+        Ok(hex::encode(hasher.finalize()))
     }
 }
 
@@ -361,6 +327,7 @@ impl Tool for RegexMatchTool {
                 },
                 "required": ["text", "pattern"]
             }),
+            output_schema: None,
         }
     }
 
@@ -470,6 +437,7 @@ impl Tool for RegexReplaceTool {
                 },
                 "required": ["text", "pattern", "replacement"]
             }),
+            output_schema: None,
         }
     }
 
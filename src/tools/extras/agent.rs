@@ -0,0 +1,228 @@
+//! Agent supervision tools: heartbeats and liveness status.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::scheduler::heartbeat::{self, Heartbeat, HeartbeatAlert};
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+/// Tool for an agent to report that it is alive.
+#[derive(Debug)]
+pub struct AgentHeartbeatTool;
+
+#[derive(Deserialize)]
+struct AgentHeartbeatArgs {
+    agent: String,
+    #[serde(default)]
+    status: Value,
+    #[serde(default = "default_interval")]
+    interval_secs: u64,
+    #[serde(default)]
+    grace_secs: Option<u64>,
+    #[serde(default)]
+    alert: Option<HeartbeatAlert>,
+}
+
+fn default_interval() -> u64 {
+    300
+}
+
+#[async_trait]
+impl Tool for AgentHeartbeatTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "agent.heartbeat".to_string(),
+            description: Some(
+                "Records that an agent is alive, with an optional status blob. If the next heartbeat \
+                 does not arrive within interval_secs (+ grace), the scheduler fires the alert tool."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent": {
+                        "type": "string",
+                        "description": "Agent name"
+                    },
+                    "status": {
+                        "description": "Free-form status (progress, current task, ...)"
+                    },
+                    "interval_secs": {
+                        "type": "integer",
+                        "description": "Seconds until the next heartbeat is expected (default: 300)"
+                    },
+                    "grace_secs": {
+                        "type": "integer",
+                        "description": "Extra slack before a late heartbeat counts as missed (default: interval / 2)"
+                    },
+                    "alert": {
+                        "type": "object",
+                        "description": "Tool call fired when the heartbeat is missed, e.g. {\"tool\": \"notify.slack\", \"args\": {\"webhook_url\": \"${secrets.SLACK}\", \"text\": \"{agent} missed its heartbeat (last seen {last_seen})\"}}. Omit to keep the previous alert.",
+                        "properties": {
+                            "tool": { "type": "string" },
+                            "args": { "type": "object" }
+                        },
+                        "required": ["tool"]
+                    }
+                },
+                "required": ["agent"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: AgentHeartbeatArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        if args.agent.trim().is_empty() {
+            return Err(ToolError::InvalidInput("'agent' must not be empty".to_string()));
+        }
+        if args.interval_secs == 0 {
+            return Err(ToolError::InvalidInput("'interval_secs' must be positive".to_string()));
+        }
+
+        let store = state.memory_store.as_ref();
+        let previous = heartbeat::load(store, &args.agent)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let recovered = previous.as_ref().is_some_and(|hb| hb.alerted);
+
+        let heartbeat = Heartbeat {
+            agent: args.agent,
+            status: args.status,
+            last_seen: chrono::Utc::now().to_rfc3339(),
+            interval_secs: args.interval_secs,
+            grace_secs: args.grace_secs.unwrap_or(args.interval_secs / 2),
+            alert: args.alert.or_else(|| previous.and_then(|hb| hb.alert)),
+            alerted: false,
+        };
+
+        heartbeat::save(store, &heartbeat)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        Ok(ToolOutput::text(json!({
+            "success": true,
+            "agent": heartbeat.agent,
+            "last_seen": heartbeat.last_seen,
+            "next_deadline": heartbeat.deadline().map(|d| d.to_rfc3339()),
+            "recovered": recovered
+        }).to_string()))
+    }
+}
+
+/// Tool for reporting agent liveness.
+#[derive(Debug)]
+pub struct AgentStatusTool;
+
+#[async_trait]
+impl Tool for AgentStatusTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "agent.status".to_string(),
+            description: Some(
+                "Shows the last heartbeat, status, and liveness of one agent or all agents.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent": {
+                        "type": "string",
+                        "description": "Agent name (omit for all agents)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let store = state.memory_store.as_ref();
+        let now = chrono::Utc::now();
+
+        if let Some(agent) = arguments.get("agent").and_then(|v| v.as_str()) {
+            let heartbeat = heartbeat::load(store, agent)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+                .ok_or_else(|| ToolError::NotFound(format!("No heartbeat from agent '{}'", agent)))?;
+            return Ok(ToolOutput::text(heartbeat.report(now).to_string()));
+        }
+
+        let heartbeats = heartbeat::load_all(store)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let agents: Vec<Value> = heartbeats.iter().map(|hb| hb.report(now)).collect();
+        let down = agents.iter().filter(|a| a["alive"] == false).count();
+
+        Ok(ToolOutput::text(json!({
+            "agents": agents,
+            "count": agents.len(),
+            "down": down
+        }).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+
+    fn json_output(output: &ToolOutput) -> Value {
+        match &output.content[0] {
+            ToolContent::Text { text } => serde_json::from_str(text).unwrap(),
+            _ => panic!("expected text output"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_and_status() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        AgentHeartbeatTool
+            .execute(json!({
+                "agent": "crawler",
+                "status": {"pages": 12},
+                "interval_secs": 60,
+                "alert": {"tool": "echo", "args": {"text": "{agent} down"}}
+            }), state.clone())
+            .await
+            .unwrap();
+
+        // A later heartbeat without an alert keeps the configured one
+        AgentHeartbeatTool
+            .execute(json!({"agent": "crawler", "status": {"pages": 40}, "interval_secs": 60}), state.clone())
+            .await
+            .unwrap();
+
+        let status = json_output(&AgentStatusTool.execute(json!({"agent": "crawler"}), state.clone()).await.unwrap());
+        assert_eq!(status["alive"], true);
+        assert_eq!(status["status"]["pages"], 40);
+        assert_eq!(status["grace_secs"], 30);
+        assert_eq!(status["alert"]["tool"], "echo");
+
+        let all = json_output(&AgentStatusTool.execute(json!({}), state.clone()).await.unwrap());
+        assert_eq!(all["count"], 1);
+        assert_eq!(all["down"], 0);
+
+        let err = AgentStatusTool.execute(json!({"agent": "ghost"}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+    }
+}
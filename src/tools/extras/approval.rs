@@ -0,0 +1,171 @@
+//! Approval tools: decide on tool calls waiting for a human.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::core::approvals::Decision;
+use crate::core::RuntimeState;
+use crate::tools::caller;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Decides on a pending call, unless it was made by the deciding session
+/// or an event rule triggered the decision: an agent must not approve its
+/// own calls.
+fn decide(state: &RuntimeState, id: &str, decision: Decision) -> Result<ToolOutput, ToolError> {
+    let request = state
+        .approvals
+        .get(id)
+        .ok_or_else(|| ToolError::NotFound(format!("pending approval {}", id)))?;
+    if crate::core::events::in_rule() {
+        return Err(ToolError::PermissionDenied("Event rules can't decide on approvals".to_string()));
+    }
+    if caller::current().is_some_and(|context| context.session_id == request.session_id) {
+        return Err(ToolError::PermissionDenied(
+            "A session can't decide on its own calls".to_string(),
+        ));
+    }
+    let approved = decision == Decision::Approved;
+    let request = state
+        .approvals
+        .decide(id, decision)
+        .ok_or_else(|| ToolError::NotFound(format!("pending approval {}", id)))?;
+    Ok(ToolOutput::structured(json!({
+        "id": request.id,
+        "tool": request.tool,
+        "approved": approved
+    })))
+}
+
+// ============================================================================
+// Approval List Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApprovalListArgs {}
+
+#[derive(Debug)]
+pub struct ApprovalListTool;
+
+#[async_trait]
+impl TypedTool for ApprovalListTool {
+    type Args = ApprovalListArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "approval.list";
+    const DESCRIPTION: &'static str =
+        "Lists tool calls waiting for approval, oldest first, with their tool, arguments and calling session.";
+
+    async fn run(&self, _args: ApprovalListArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let pending = state.approvals.list();
+        Ok(ToolOutput::structured(json!({
+            "count": pending.len(),
+            "pending": pending
+        })))
+    }
+}
+
+// ============================================================================
+// Approval Approve Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApprovalApproveArgs {
+    /// Approval ID from approval.list
+    id: String,
+}
+
+#[derive(Debug)]
+pub struct ApprovalApproveTool;
+
+#[async_trait]
+impl TypedTool for ApprovalApproveTool {
+    type Args = ApprovalApproveArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "approval.approve";
+    const DESCRIPTION: &'static str =
+        "Approves a pending tool call, which then runs. Calls can't be approved by the session that made them.";
+
+    async fn run(&self, args: ApprovalApproveArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        decide(&state, &args.id, Decision::Approved)
+    }
+}
+
+// ============================================================================
+// Approval Reject Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApprovalRejectArgs {
+    /// Approval ID from approval.list
+    id: String,
+    /// Reason passed on to the caller
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ApprovalRejectTool;
+
+#[async_trait]
+impl TypedTool for ApprovalRejectTool {
+    type Args = ApprovalRejectArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "approval.reject";
+    const DESCRIPTION: &'static str = "Rejects a pending tool call; the caller gets an error with the reason.";
+
+    async fn run(&self, args: ApprovalRejectArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        decide(&state, &args.id, Decision::Rejected(args.reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ApprovalConfig, Config, RequestContext};
+    use crate::tools::Tool;
+
+    #[tokio::test]
+    async fn test_approve_from_another_session() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            approvals: ApprovalConfig { tools: vec!["cmd.exec".to_string()], ..Default::default() },
+            ..Config::default()
+        }));
+
+        let waiting = state.clone();
+        let call = tokio::spawn(async move {
+            let context = RequestContext::new("agent");
+            waiting.approvals.check("cmd.exec", &json!({"command": "ls"}), &context, &waiting.events).await
+        });
+        let id = loop {
+            if let Some(request) = state.approvals.list().pop() {
+                break request.id;
+            }
+            tokio::task::yield_now().await;
+        };
+
+        let listed = ApprovalListTool.execute(json!({}), state.clone()).await.unwrap();
+        assert_eq!(listed.structured_content.unwrap()["pending"][0]["tool"], "cmd.exec");
+
+        let own = caller::with_context(
+            RequestContext::new("agent"),
+            ApprovalApproveTool.execute(json!({ "id": id }), state.clone()),
+        )
+        .await;
+        assert!(matches!(own, Err(ToolError::PermissionDenied(_))));
+
+        caller::with_context(
+            RequestContext::new("operator"),
+            ApprovalApproveTool.execute(json!({ "id": id }), state.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(call.await.unwrap().is_ok());
+
+        let err = ApprovalRejectTool.execute(json!({ "id": id }), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+    }
+}
@@ -0,0 +1,156 @@
+//! Batch tool: run several independent tool calls in one round trip.
+//!
+//! Each call goes through `tools/call` as if the client had made it, so
+//! API key scopes, the policy, approvals, middleware and the audit log
+//! apply to every call on its own.
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::core::RuntimeState;
+use crate::handlers::handle_tools_call_with_context;
+use crate::tools::caller;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::stream::{without_sink, ProgressReporter};
+use crate::tools::typed::TypedTool;
+
+/// Most calls in one batch.
+const MAX_BATCH_CALLS: usize = 50;
+
+/// Most calls of a batch running at the same time.
+const MAX_CONCURRENCY: usize = 16;
+
+fn default_concurrency() -> usize {
+    4
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BatchCall {
+    /// Tool to call
+    name: String,
+    /// Arguments of the call
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BatchRunArgs {
+    /// Independent calls to make, each {"name": ..., "arguments": {...}} (at most 50)
+    calls: Vec<BatchCall>,
+    /// Calls running at the same time (default: 4, at most 16)
+    #[serde(default = "default_concurrency")]
+    concurrency: usize,
+}
+
+#[derive(Debug)]
+pub struct BatchRunTool;
+
+#[async_trait]
+impl TypedTool for BatchRunTool {
+    type Args = BatchRunArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "batch.run";
+    const DESCRIPTION: &'static str = "Runs several independent tool calls concurrently and returns their results in \
+        the order of the calls, each with its index. A failed call doesn't stop the others. Use it instead of \
+        separate calls when no call needs another's result.";
+
+    async fn run(&self, args: BatchRunArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        if args.calls.is_empty() {
+            return Err(ToolError::InvalidInput("'calls' is empty".to_string()));
+        }
+        if args.calls.len() > MAX_BATCH_CALLS {
+            return Err(ToolError::InvalidInput(format!("At most {} calls per batch", MAX_BATCH_CALLS)));
+        }
+        {
+            let registry = state.tool_registry.read();
+            if args.calls.iter().any(|call| registry.resolve(&call.name) == Self::NAME) {
+                return Err(ToolError::InvalidInput("Batches can't contain batch.run".to_string()));
+            }
+        }
+
+        let context = caller::current().unwrap_or_default();
+        let reporter = ProgressReporter::current();
+        let total = args.calls.len() as u64;
+        let done = AtomicU64::new(0);
+        let results: Vec<Value> = stream::iter(args.calls.into_iter().enumerate())
+            .map(|(index, call)| {
+                let (state, context, reporter, done) = (state.clone(), context.clone(), &reporter, &done);
+                async move {
+                    let started = Instant::now();
+                    let params = json!({ "name": call.name, "arguments": call.arguments });
+                    // Nested calls report nothing; the batch reports each one that finishes
+                    let mut result = match without_sink(handle_tools_call_with_context(Some(params), state, context)).await {
+                        Ok(result) => result,
+                        Err(e) => json!({ "content": [{"type": "text", "text": e.to_string()}], "isError": true }),
+                    };
+                    result["index"] = json!(index);
+                    result["name"] = json!(call.name);
+                    result["duration_ms"] = json!(started.elapsed().as_millis() as u64);
+                    let finished = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    reporter.report(finished, Some(total), format!("Finished call {} ({})", index, call.name));
+                    result
+                }
+            })
+            .buffered(args.concurrency.clamp(1, MAX_CONCURRENCY))
+            .collect()
+            .await;
+
+        let failed = results.iter().filter(|result| result["isError"] == true).count();
+        Ok(ToolOutput::structured(json!({
+            "count": results.len(),
+            "succeeded": results.len() - failed,
+            "failed": failed,
+            "results": results
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, RequestContext};
+    use crate::tools::Tool;
+
+    #[tokio::test]
+    async fn test_batch_runs_calls_concurrently() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            policy: serde_json::from_value(json!({ "enabled": true, "rules": ["deny get_time"] })).unwrap(),
+            ..Config::default()
+        }));
+        let args = json!({
+            "calls": [
+                {"name": "echo", "arguments": {"text": "one"}},
+                {"name": "get_time"},
+                {"name": "no.such_tool"},
+                {"name": "echo", "arguments": {"text": "two"}}
+            ],
+            "concurrency": 2
+        });
+        let output = caller::with_context(RequestContext::new("s1"), BatchRunTool.execute(args, state.clone()))
+            .await
+            .unwrap();
+        let report = output.structured_content.unwrap();
+        assert_eq!((report["count"].as_u64(), report["failed"].as_u64()), (Some(4), Some(2)));
+        let results = report["results"].as_array().unwrap();
+        assert!(results.iter().enumerate().all(|(i, result)| result["index"] == i));
+        assert!(results[0]["content"][0]["text"].as_str().unwrap().contains("one"));
+        assert!(results[1]["content"][0]["text"].as_str().unwrap().starts_with("Permission denied"));
+        assert_eq!(results[2]["isError"], true);
+        assert!(results[3]["content"][0]["text"].as_str().unwrap().contains("two"));
+
+        // Every call is audited as the caller's own
+        let calls = state.memory_store.list_tool_calls(None).await.unwrap();
+        assert_eq!(calls.iter().filter(|call| call.session_id == "s1").count(), 3);
+
+        let nested = json!({ "calls": [{"name": "batch.run", "arguments": {"calls": []}}] });
+        let err = BatchRunTool.execute(nested, state).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+}
@@ -0,0 +1,130 @@
+//! Headless Chrome rendering for JavaScript-heavy pages.
+//!
+//! web.extract with `render: true` loads the page in a headless Chrome
+//! over the DevTools protocol, so content built by scripts (single-page
+//! apps, lazy-loaded lists) is present before extraction.
+
+use chromiumoxide::browser::{Browser, BrowserConfig as LaunchConfig};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::handler::viewport::Viewport;
+use chromiumoxide::page::{Page, ScreenshotParams};
+use futures::StreamExt;
+use std::time::{Duration, Instant};
+
+use crate::core::config::BrowserConfig;
+use crate::tools::registry::ToolError;
+
+/// How often `wait_for` checks for the selector.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to do once the page has loaded.
+#[derive(Debug, Default)]
+pub struct RenderOptions {
+    /// CSS selector that must be present before the page counts as rendered.
+    pub wait_for: Option<String>,
+    /// Extra time to let scripts settle after loading, in milliseconds.
+    pub wait_ms: u64,
+    /// Capture a full-page PNG screenshot.
+    pub screenshot: bool,
+}
+
+/// A page after scripts ran.
+#[derive(Debug)]
+pub struct RenderedPage {
+    /// The page URL after redirects and client-side navigation.
+    pub url: String,
+    /// Serialized DOM.
+    pub html: String,
+    /// PNG screenshot, if requested.
+    pub screenshot: Option<Vec<u8>>,
+}
+
+/// Loads `url` in a fresh headless browser and returns the rendered DOM.
+pub async fn render(config: &BrowserConfig, url: &str, options: &RenderOptions) -> Result<RenderedPage, ToolError> {
+    // A profile per launch, so concurrent renders don't share Chrome's lock
+    let profile = std::env::temp_dir().join(format!("aegis-chrome-{}", uuid::Uuid::new_v4()));
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let (width, height) = config.viewport;
+
+    let mut launch = LaunchConfig::builder()
+        .user_data_dir(&profile)
+        .request_timeout(timeout)
+        .window_size(width, height)
+        .viewport(Viewport { width, height, ..Viewport::default() });
+    if let Some(executable) = &config.executable {
+        launch = launch.chrome_executable(executable);
+    }
+    if config.no_sandbox {
+        launch = launch.no_sandbox();
+    }
+    let launch = launch.build().map_err(ToolError::ExecutionFailed)?;
+
+    let (mut browser, mut handler) = match Browser::launch(launch).await {
+        Ok(launched) => launched,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&profile);
+            return Err(ToolError::ExecutionFailed(format!("Failed to launch browser: {}", e)));
+        }
+    };
+    let events = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+    let result = tokio::time::timeout(timeout, load(&browser, url, options))
+        .await
+        .unwrap_or(Err(ToolError::Timeout(config.timeout_secs)));
+
+    let _ = browser.close().await;
+    let _ = browser.wait().await;
+    events.abort();
+    let _ = std::fs::remove_dir_all(&profile);
+    result
+}
+
+async fn load(browser: &Browser, url: &str, options: &RenderOptions) -> Result<RenderedPage, ToolError> {
+    let page = browser
+        .new_page(url)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to open {}: {}", url, e)))?;
+
+    if let Some(selector) = &options.wait_for {
+        wait_for_selector(&page, selector).await;
+    }
+    if options.wait_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(options.wait_ms)).await;
+    }
+
+    let html = page
+        .content()
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read page: {}", e)))?;
+    let final_url = page.url().await.ok().flatten().unwrap_or_else(|| url.to_string());
+
+    let screenshot = if options.screenshot {
+        let params = ScreenshotParams::builder()
+            .format(CaptureScreenshotFormat::Png)
+            .full_page(true)
+            .build();
+        Some(
+            page.screenshot(params)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Screenshot failed: {}", e)))?,
+        )
+    } else {
+        None
+    };
+
+    let _ = page.close().await;
+    Ok(RenderedPage { url: final_url, html, screenshot })
+}
+
+/// Polls until an element matches `selector`. The overall render timeout
+/// bounds the wait.
+async fn wait_for_selector(page: &Page, selector: &str) {
+    let started = Instant::now();
+    loop {
+        if page.find_element(selector).await.is_ok() {
+            tracing::debug!("'{}' appeared after {:?}", selector, started.elapsed());
+            return;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,345 @@
+//! Sandboxed code execution (code.run).
+//!
+//! Each snippet runs in a fresh temporary directory as a child process with
+//! a clean environment, in its own session, with rlimits on CPU time,
+//! memory, file size and open files. With `code_run.isolate_network` it
+//! also gets its own (empty) network namespace; that only exists on Linux,
+//! so elsewhere code.run refuses to run unless the setting is turned off.
+//! This keeps runaway or chatty snippets contained; it is not a filesystem
+//! jail, which is why code.run is off by default.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::core::config::CodeRunConfig;
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Largest file a snippet may write.
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Most files a snippet may have open.
+const MAX_OPEN_FILES: u64 = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Python,
+    #[serde(alias = "js", alias = "node")]
+    Javascript,
+}
+
+/// A temporary directory removed when dropped.
+struct Workdir(PathBuf);
+
+impl Workdir {
+    fn create() -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("aegis-code-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for Workdir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Reads a stream to the end, keeping the first `max` bytes.
+async fn read_limited(mut reader: impl AsyncRead + Unpin, max: usize) -> (String, bool) {
+    let mut kept = Vec::new();
+    let mut buffer = [0u8; 8192];
+    let mut truncated = false;
+    while let Ok(n) = reader.read(&mut buffer).await {
+        if n == 0 {
+            break;
+        }
+        let room = max.saturating_sub(kept.len());
+        kept.extend_from_slice(&buffer[..n.min(room)]);
+        truncated |= n > room;
+    }
+    (String::from_utf8_lossy(&kept).into_owned(), truncated)
+}
+
+/// Applies the sandbox limits in the child between fork and exec.
+#[cfg(unix)]
+fn confine(command: &mut Command, config: &CodeRunConfig, cpu_secs: u64) {
+    let memory = config.max_memory_mb * 1024 * 1024;
+    let isolate_network = config.isolate_network;
+    let limit = |resource, value: u64| {
+        let limit = libc::rlimit { rlim_cur: value as libc::rlim_t, rlim_max: value as libc::rlim_t };
+        // SAFETY: setrlimit only reads the struct
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    // SAFETY: the closure only makes async-signal-safe system calls
+    unsafe {
+        command.pre_exec(move || {
+            // Own session, so a timeout can kill everything the snippet started
+            libc::setsid();
+            limit(libc::RLIMIT_CPU, cpu_secs)?;
+            limit(libc::RLIMIT_DATA, memory)?;
+            limit(libc::RLIMIT_FSIZE, MAX_FILE_BYTES)?;
+            limit(libc::RLIMIT_NOFILE, MAX_OPEN_FILES)?;
+            limit(libc::RLIMIT_CORE, 0)?;
+            #[cfg(target_os = "linux")]
+            if isolate_network && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = isolate_network;
+}
+
+/// Kills the snippet's whole session.
+#[cfg(unix)]
+fn kill_session(pid: Option<u32>) {
+    if let Some(pid) = pid.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+        // SAFETY: signalling a process group has no memory effects
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+}
+
+/// Runs `code` and returns its output.
+#[cfg(unix)]
+async fn run_snippet(
+    config: &CodeRunConfig,
+    language: Language,
+    code: &str,
+    stdin: Option<&str>,
+    timeout: Duration,
+) -> Result<serde_json::Value, ToolError> {
+    #[cfg(not(target_os = "linux"))]
+    if config.isolate_network {
+        return Err(ToolError::ExecutionFailed(
+            "code.run can only cut snippets off the network on Linux; set code_run.isolate_network to false to run them with network access".to_string(),
+        ));
+    }
+    let workdir = Workdir::create().map_err(|e| ToolError::Internal(format!("Cannot create workdir: {}", e)))?;
+    let (file, interpreter, flags) = match language {
+        Language::Python => ("main.py", &config.python, vec!["-I".to_string(), "-B".to_string()]),
+        Language::Javascript => (
+            "main.js",
+            &config.node,
+            vec![format!("--max-old-space-size={}", config.max_memory_mb)],
+        ),
+    };
+    std::fs::write(workdir.0.join(file), code).map_err(|e| ToolError::Internal(format!("Cannot write snippet: {}", e)))?;
+
+    let mut command = Command::new(interpreter);
+    command
+        .args(&flags)
+        .arg(file)
+        .current_dir(&workdir.0)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", &workdir.0)
+        .env("TMPDIR", &workdir.0)
+        .env("LANG", "C.UTF-8")
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    confine(&mut command, config, timeout.as_secs() + 1);
+
+    let started = Instant::now();
+    let mut child = command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            ToolError::ExecutionFailed(format!("Interpreter '{}' not found", interpreter))
+        }
+        _ if config.isolate_network => ToolError::ExecutionFailed(format!(
+            "Cannot start the sandbox ({}); if user namespaces are unavailable, set code_run.isolate_network to false",
+            e
+        )),
+        _ => ToolError::ExecutionFailed(format!("Cannot start the sandbox: {}", e)),
+    })?;
+    let pid = child.id();
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        let input = input.to_string();
+        tokio::spawn(async move {
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
+    let max = config.max_output_bytes;
+    let stdout = tokio::spawn(read_limited(child.stdout.take().expect("piped stdout"), max));
+    let stderr = tokio::spawn(read_limited(child.stderr.take().expect("piped stderr"), max));
+
+    let (status, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => (Some(status.map_err(|e| ToolError::ExecutionFailed(e.to_string()))?), false),
+        Err(_) => {
+            kill_session(pid);
+            let _ = child.wait().await;
+            (None, true)
+        }
+    };
+    // Background processes the snippet left behind would hold the pipes open
+    kill_session(pid);
+    let (stdout, stdout_truncated) = stdout.await.unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.await.unwrap_or_default();
+
+    use std::os::unix::process::ExitStatusExt;
+    Ok(json!({
+        "language": language,
+        "network_isolated": config.isolate_network,
+        "exit_code": status.and_then(|s| s.code()),
+        "signal": status.and_then(|s| s.signal()),
+        "timed_out": timed_out,
+        "stdout": stdout,
+        "stderr": stderr,
+        "truncated": stdout_truncated || stderr_truncated,
+        "duration_ms": started.elapsed().as_millis() as u64,
+    }))
+}
+
+#[cfg(not(unix))]
+async fn run_snippet(
+    _config: &CodeRunConfig,
+    _language: Language,
+    _code: &str,
+    _stdin: Option<&str>,
+    _timeout: Duration,
+) -> Result<serde_json::Value, ToolError> {
+    Err(ToolError::ExecutionFailed("code.run needs a Unix host".to_string()))
+}
+
+// ============================================================================
+// Code Run Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CodeRunArgs {
+    /// 'python' or 'javascript'
+    language: Language,
+    /// Source code; print results to stdout
+    code: String,
+    /// Text passed on stdin
+    #[serde(default)]
+    stdin: Option<String>,
+    /// Seconds before the snippet is killed (default and max: code_run.timeout_secs)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+/// Tool to run short Python or JavaScript snippets in a sandbox.
+#[derive(Debug)]
+pub struct CodeRunTool {
+    config: CodeRunConfig,
+}
+
+impl CodeRunTool {
+    pub fn new(config: CodeRunConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TypedTool for CodeRunTool {
+    type Args = CodeRunArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "code.run";
+    const DESCRIPTION: &'static str =
+        "Runs a short Python or JavaScript snippet in a sandbox (temporary directory, CPU/memory/time limits, no network unless the server allows it; see network_isolated in the result) and returns stdout, stderr and the exit code. Use it for calculations and data processing.";
+
+    async fn run(&self, args: CodeRunArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        if !self.config.enabled {
+            return Err(ToolError::PermissionDenied("code.run is disabled; set code_run.enabled".to_string()));
+        }
+        let timeout = args.timeout_secs.unwrap_or(self.config.timeout_secs).clamp(1, self.config.timeout_secs.max(1));
+        let result = run_snippet(
+            &self.config,
+            args.language,
+            &args.code,
+            args.stdin.as_deref(),
+            Duration::from_secs(timeout),
+        )
+        .await?;
+        Ok(ToolOutput::structured(result))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn config() -> CodeRunConfig {
+        CodeRunConfig { enabled: true, timeout_secs: 5, max_output_bytes: 1000, ..CodeRunConfig::default() }
+    }
+
+    /// Runs a snippet, or returns None when the interpreter or user
+    /// namespaces aren't available here.
+    async fn run(language: Language, code: &str, timeout: u64) -> Option<serde_json::Value> {
+        match run_snippet(&config(), language, code, Some("21"), Duration::from_secs(timeout)).await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                eprintln!("skipping: {}", e);
+                None
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_javascript_snippet() {
+        let Some(result) = run(
+            Language::Javascript,
+            "let n = ''; process.stdin.on('data', d => n += d); process.stdin.on('end', () => console.log(Number(n) * 2));",
+            5,
+        )
+        .await
+        else {
+            return;
+        };
+        assert_eq!(result["exit_code"], 0, "{}", result);
+        assert_eq!(result["stdout"], "42\n");
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let Some(chatty) = run(Language::Javascript, "for (let i = 0; i < 10000; i++) console.log('line ' + i);", 5).await
+        else {
+            return;
+        };
+        assert_eq!(chatty["truncated"], true);
+        assert_eq!(chatty["stdout"].as_str().unwrap().len(), 1000);
+
+        let stuck = run(Language::Javascript, "while (true) {}", 1).await.unwrap();
+        assert_eq!(stuck["timed_out"], true);
+
+        let offline = run(
+            Language::Javascript,
+            "require('net').connect(80, '1.1.1.1').on('error', e => { console.log(e.code); });",
+            5,
+        )
+        .await
+        .unwrap();
+        assert_eq!(offline["stdout"], "ENETUNREACH\n", "{}", offline);
+        assert_eq!(offline["network_isolated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_by_default() {
+        let state = Arc::new(RuntimeState::new(crate::core::Config {
+            database_path: Some(":memory:".into()),
+            ..Default::default()
+        }));
+        let args = serde_json::from_value(json!({ "language": "python", "code": "print(1)" })).unwrap();
+        let result = CodeRunTool::new(CodeRunConfig::default()).run(args, state).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+}
@@ -4,9 +4,422 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::core::config::ConversationConfig;
 use crate::core::RuntimeState;
+use crate::memory::{parse_since, Conversation, ConversationFilter, ConversationUpdate, MemoryError, Message, MessageFilter};
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::caller;
+use crate::tools::middleware::estimate_tokens;
+use crate::tools::registry::{Tool, ToolContent, ToolError, ToolOutput};
+
+/// Limit that fetches every message of a conversation.
+const ALL_MESSAGES: usize = i64::MAX as usize;
+
+/// Resolves the target conversation: explicit argument, then the pinned one.
+fn resolve_conversation_id(arguments: &Value, state: &RuntimeState) -> Result<String, ToolError> {
+    arguments
+        .get("conversation_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| state.pinned_conversation())
+        .ok_or_else(|| {
+            ToolError::InvalidInput(
+                "Missing 'conversation_id' and no conversation is pinned".to_string(),
+            )
+        })
+}
+
+/// Resolves the namespace of a conversation call: the caller's API key,
+/// which an explicit argument may not leave, otherwise the explicit
+/// argument, then the session's namespace.
+fn resolve_namespace(arguments: &Value) -> Result<Option<String>, ToolError> {
+    let explicit = arguments.get("namespace").and_then(|v| v.as_str()).map(|s| s.to_string());
+    Ok(caller::scoped_namespace(explicit)
+        .map_err(ToolError::PermissionDenied)?
+        .or_else(caller::namespace))
+}
+
+/// IDs starting with `prefix` of the conversations in the namespace of a
+/// call with `arguments`, for argument completion. Only the `limit` most
+/// recently updated conversations are considered.
+pub(crate) async fn complete_conversation_ids(
+    state: &RuntimeState,
+    arguments: &Value,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<String>, ToolError> {
+    let namespace = resolve_namespace(arguments)?;
+    let conversations = state
+        .memory_store
+        .list_conversations(limit, &ConversationFilter::in_namespace(namespace.as_deref()))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+    Ok(conversations.into_iter().map(|c| c.id).filter(|id| id.starts_with(prefix)).collect())
+}
+
+/// Resolves the target conversation and checks that it belongs to the
+/// call's namespace; other namespaces' conversations look missing.
+async fn resolve_scoped_conversation(arguments: &Value, state: &RuntimeState) -> Result<String, ToolError> {
+    let conversation_id = resolve_conversation_id(arguments, state)?;
+    if let Some(namespace) = resolve_namespace(arguments)? {
+        let conversation = state
+            .memory_store
+            .get_conversation(&conversation_id)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        if conversation.namespace.as_deref() != Some(namespace.as_str()) {
+            let missing = MemoryError::NotFound(format!("Conversation not found: {}", conversation_id));
+            return Err(ToolError::ExecutionFailed(missing.to_string()));
+        }
+    }
+    Ok(conversation_id)
+}
+
+/// Longest tag `conversation.tag` accepts.
+const MAX_TAG_LEN: usize = 64;
+
+/// Normalizes a tag: trimmed and lowercase, without commas.
+fn normalize_tag(tag: &str) -> Result<String, ToolError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || tag.len() > MAX_TAG_LEN || tag.contains(',') {
+        return Err(ToolError::InvalidInput(format!(
+            "Invalid tag '{}': tags are 1-{} characters without commas",
+            tag, MAX_TAG_LEN
+        )));
+    }
+    Ok(tag)
+}
+
+/// Reads a tag list argument: a single tag or an array of tags.
+fn tags_argument(arguments: &Value, key: &str) -> Result<Vec<String>, ToolError> {
+    match arguments.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::String(tag)) => Ok(vec![normalize_tag(tag)?]),
+        Some(Value::Array(tags)) => tags
+            .iter()
+            .map(|tag| {
+                tag.as_str()
+                    .ok_or_else(|| ToolError::InvalidInput(format!("'{}' must contain strings", key)))
+                    .and_then(normalize_tag)
+            })
+            .collect(),
+        Some(_) => Err(ToolError::InvalidInput(format!("'{}' must be a tag or an array of tags", key))),
+    }
+}
+
+/// Reads a time bound argument: a relative window back from now (`24h`,
+/// `7d`), a date or an RFC 3339 timestamp.
+fn time_argument(arguments: &Value, key: &str) -> Result<Option<String>, ToolError> {
+    let Some(value) = arguments.get(key).and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    parse_since(value, chrono::Utc::now())
+        .map(|ts| Some(ts.to_rfc3339()))
+        .map_err(|_| ToolError::InvalidInput(format!("Invalid '{}': use e.g. 24h, 7d, 2024-01-31 or an RFC 3339 timestamp", key)))
+}
+
+/// Schema of the tag and time filter arguments of conversation.list and
+/// conversation.search.
+fn filter_properties(times_of: &str) -> Value {
+    json!({
+        "tags": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Only conversations with all of these tags"
+        },
+        "since": {
+            "type": "string",
+            "description": format!("Only {} at or after this time: a window back from now (24h, 7d), a date or an RFC 3339 timestamp", times_of)
+        },
+        "until": {
+            "type": "string",
+            "description": format!("Only {} before this time, in the same formats as since", times_of)
+        }
+    })
+}
+
+/// Schema of the `namespace` argument shared by the conversation tools.
+fn namespace_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Namespace the conversation belongs to (default: the caller's API key name)"
+    })
+}
+
+/// Splits a conversation into its latest summary and the messages after it.
+///
+/// Summaries are system messages whose metadata records the last message
+/// they cover (`{"summary": {"through": id, "messages": n}}`), so older
+/// messages are kept but hidden from the compacted view.
+fn compact(messages: Vec<Message>) -> (Option<Message>, Vec<Message>) {
+    let mut summary = None;
+    let mut live = Vec::new();
+    for message in messages {
+        match summary_info(&message) {
+            Some(info) => {
+                let through = info["through"].as_str().unwrap_or_default();
+                if let Some(pos) = live.iter().position(|m: &Message| m.id == through) {
+                    live.drain(..=pos);
+                }
+                summary = Some(message);
+            }
+            None => live.push(message),
+        }
+    }
+    (summary, live)
+}
+
+/// Summary metadata of a message, if it is a summary.
+fn summary_info(message: &Message) -> Option<Value> {
+    let metadata: Value = serde_json::from_str(message.metadata.as_deref()?).ok()?;
+    metadata.get("summary").cloned()
+}
+
+fn message_json(message: &Message) -> Value {
+    json!({
+        "id": message.id,
+        "role": message.role,
+        "content": message.content,
+        "created_at": message.created_at
+    })
+}
+
+/// Renders a conversation as a Markdown transcript.
+pub(crate) fn export_markdown(conversation: &Conversation, messages: &[Message]) -> String {
+    let title = conversation.title.clone().unwrap_or_else(|| format!("Conversation {}", conversation.id));
+    let mut out = format!("# {}\n\n- ID: `{}`\n- Created: {}\n", title, conversation.id, conversation.created_at);
+    if let Some(namespace) = &conversation.namespace {
+        out.push_str(&format!("- Namespace: {}\n", namespace));
+    }
+    if !conversation.tags.is_empty() {
+        out.push_str(&format!("- Tags: {}\n", conversation.tags.join(", ")));
+    }
+
+    for message in messages {
+        let mut role = capitalize(&message.role);
+        if summary_info(message).is_some() {
+            role.push_str(" (summary)");
+        }
+        out.push_str(&format!("\n## {}\n\n_{}_\n\n{}\n", role, message.created_at, message.content.trim_end()));
+    }
+    out
+}
+
+/// Renders messages as JSONL, one message object per line.
+pub(crate) fn export_jsonl(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|message| {
+            let mut line = message_json(message);
+            if let Some(metadata) = message.metadata.as_deref().and_then(|m| serde_json::from_str::<Value>(m).ok()) {
+                line["metadata"] = metadata;
+            }
+            format!("{}\n", line)
+        })
+        .collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Parses the messages of an import: a JSON array, an object with a
+/// `messages` array (e.g. a chat completion request), or JSONL.
+fn parse_import_data(data: &str) -> Result<Vec<Value>, ToolError> {
+    match serde_json::from_str::<Value>(data) {
+        Ok(Value::Array(messages)) => return Ok(messages),
+        Ok(Value::Object(mut object)) => match object.remove("messages") {
+            Some(Value::Array(messages)) => return Ok(messages),
+            // A single-line JSONL file
+            _ if object.contains_key("role") => return Ok(vec![Value::Object(object)]),
+            _ => return Err(ToolError::InvalidInput("Expected a 'messages' array".to_string())),
+        },
+        _ => {}
+    }
+
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| ToolError::InvalidInput(format!("Line {} is not JSON: {}", index + 1, e)))
+        })
+        .collect()
+}
+
+/// A message to store, converted from an imported one.
+struct ImportedMessage {
+    /// ID of the message in the source, if it had one (Aegis exports).
+    source_id: Option<String>,
+    role: String,
+    content: String,
+    metadata: serde_json::Map<String, Value>,
+}
+
+/// Converts an OpenAI-style message (`role`, `content` as a string or an
+/// array of parts, `name`, `tool_calls`, `tool_call_id`) or an exported
+/// Aegis message. Returns `None` for messages with nothing to keep.
+fn import_message(value: &Value) -> Result<Option<ImportedMessage>, ToolError> {
+    let role = value
+        .get("role")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidInput("Message without a 'role'".to_string()))?;
+    let role = match role {
+        "developer" => "system",
+        "function" => "tool",
+        role => role,
+    };
+
+    let mut metadata = match value.get("metadata") {
+        Some(Value::Object(map)) => map.clone(),
+        Some(Value::String(raw)) => serde_json::from_str(raw).unwrap_or_default(),
+        _ => serde_json::Map::new(),
+    };
+    for field in ["name", "tool_calls", "tool_call_id"] {
+        if let Some(v) = value.get(field).filter(|v| !v.is_null()) {
+            metadata.insert(field.to_string(), v.clone());
+        }
+    }
+
+    let content = match value.get("content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => {
+            let mut texts = Vec::new();
+            let mut dropped = 0;
+            for part in parts {
+                match part.get("text").and_then(|v| v.as_str()) {
+                    Some(text) => texts.push(text),
+                    None => dropped += 1,
+                }
+            }
+            if dropped > 0 {
+                metadata.insert("dropped_parts".to_string(), json!(dropped));
+            }
+            texts.join("\n")
+        }
+        _ => String::new(),
+    };
+
+    if content.is_empty() && metadata.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ImportedMessage {
+        source_id: value.get("id").and_then(|v| v.as_str()).map(String::from),
+        role: role.to_string(),
+        content,
+        metadata,
+    }))
+}
+
+/// Whether unsummarized messages exceed the configured window.
+fn needs_summary(live: &[Message], config: &ConversationConfig) -> bool {
+    live.len() > config.max_messages
+        || config.max_tokens.is_some_and(|max| {
+            live.iter().map(|m| estimate_tokens(&m.content)).sum::<usize>() > max
+        })
+}
+
+/// Summarizes all but the `keep_recent` latest unsummarized messages into a
+/// new summary message. Returns `None` when there is nothing to summarize.
+async fn summarize_conversation(
+    state: &Arc<RuntimeState>,
+    conversation_id: &str,
+    keep_recent: usize,
+) -> Result<Option<Value>, ToolError> {
+    let config = &state.config.conversation;
+    let messages = state
+        .memory_store
+        .get_messages(conversation_id, ALL_MESSAGES)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let (previous, live) = compact(messages);
+    if live.len() <= keep_recent {
+        return Ok(None);
+    }
+    let older = &live[..live.len() - keep_recent];
+
+    let mut transcript = String::new();
+    if let Some(previous) = &previous {
+        transcript.push_str(&format!("Summary of earlier messages:\n{}\n\n", previous.content));
+    }
+    for message in older {
+        transcript.push_str(&format!("{}: {}\n", message.role, message.content));
+    }
+
+    let tool = state
+        .tool_registry
+        .read()
+        .get(&config.tool)
+        .cloned()
+        .ok_or_else(|| ToolError::NotFound(config.tool.clone()))?;
+
+    let prompt = format!(
+        "Summarize this conversation so it can replace the messages in an agent's context. Keep \
+         decisions, facts, names, numbers and open questions; drop pleasantries.\n\n{}",
+        transcript
+    );
+    let mut args = json!({
+        "prompt": prompt,
+        "max_tokens": config.summary_tokens,
+        "temperature": 0.0
+    });
+    if let Some(model) = &config.model {
+        args["model"] = json!(model);
+    }
+
+    let output = tool.execute(args, state.clone()).await?;
+    let raw = output
+        .content
+        .iter()
+        .find_map(|c| match c {
+            ToolContent::Text { text } => Some(text.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    if output.is_error {
+        return Err(ToolError::ExecutionFailed(raw));
+    }
+
+    // LLM tools return {"content": "...", ...}; fall back to raw text.
+    let summary = serde_json::from_str::<Value>(&raw)
+        .ok()
+        .and_then(|v| v.get("content").and_then(|c| c.as_str()).map(|s| s.to_string()))
+        .unwrap_or(raw);
+
+    let previous_count = previous
+        .as_ref()
+        .and_then(summary_info)
+        .and_then(|info| info["messages"].as_u64())
+        .unwrap_or(0);
+    let info = json!({
+        "through": older.last().map(|m| m.id.clone()),
+        "messages": previous_count + older.len() as u64
+    });
+
+    let summary_id = state
+        .memory_store
+        .add_message(
+            conversation_id,
+            "system",
+            &summary,
+            Some(json!({ "summary": info }).to_string()),
+        )
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    Ok(Some(json!({
+        "summary_id": summary_id,
+        "summarized": older.len(),
+        "total_summarized": info["messages"],
+        "kept": keep_recent,
+        "summary": summary
+    })))
+}
 
 /// Tool to create a new conversation.
 #[derive(Debug)]
@@ -28,9 +441,15 @@ impl Tool for ConversationCreateTool {
                     "metadata": {
                         "type": "object",
                         "description": "Optional metadata"
-                    }
+                    },
+                    "pin": {
+                        "type": "boolean",
+                        "description": "Pin the new conversation as the default scope (default: false)"
+                    },
+                    "namespace": namespace_schema()
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -48,16 +467,24 @@ impl Tool for ConversationCreateTool {
             .get("metadata")
             .map(|v| serde_json::to_string(v).unwrap_or_default());
 
+        let namespace = resolve_namespace(&arguments)?;
         let id = state
             .memory_store
-            .create_conversation(title.clone(), metadata)
+            .create_conversation(title.clone(), metadata, namespace.as_deref())
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
+        let pin = arguments.get("pin").and_then(|v| v.as_bool()).unwrap_or(false);
+        if pin {
+            state.pin_conversation(Some(id.clone()));
+        }
+
         let result = json!({
             "success": true,
             "conversation_id": id,
-            "title": title
+            "title": title,
+            "namespace": namespace,
+            "pinned": pin
         });
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
@@ -79,7 +506,7 @@ impl Tool for ConversationAddTool {
                 "properties": {
                     "conversation_id": {
                         "type": "string",
-                        "description": "Conversation ID"
+                        "description": "Conversation ID (default: pinned conversation)"
                     },
                     "role": {
                         "type": "string",
@@ -89,10 +516,12 @@ impl Tool for ConversationAddTool {
                     "content": {
                         "type": "string",
                         "description": "Message content"
-                    }
+                    },
+                    "namespace": namespace_schema()
                 },
-                "required": ["conversation_id", "role", "content"]
+                "required": ["role", "content"]
             }),
+            output_schema: None,
         }
     }
 
@@ -101,10 +530,7 @@ impl Tool for ConversationAddTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let conversation_id = arguments
-            .get("conversation_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'conversation_id'".to_string()))?;
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
 
         let role = arguments
             .get("role")
@@ -118,16 +544,36 @@ impl Tool for ConversationAddTool {
 
         let message_id = state
             .memory_store
-            .add_message(conversation_id, role, content, None)
+            .add_message(&conversation_id, role, content, None)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
-        let result = json!({
+        let mut result = json!({
             "success": true,
             "message_id": message_id,
             "conversation_id": conversation_id
         });
 
+        let config = &state.config.conversation;
+        if config.auto_summarize {
+            let messages = state
+                .memory_store
+                .get_messages(&conversation_id, ALL_MESSAGES)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            let (_, live) = compact(messages);
+            if needs_summary(&live, config) {
+                // The message is stored either way; a failed summary is retried on the next add
+                match summarize_conversation(&state, &conversation_id, config.keep_recent).await {
+                    Ok(summary) => result["summarized"] = json!(summary.is_some()),
+                    Err(e) => {
+                        tracing::warn!("Failed to summarize conversation {}: {}", conversation_id, e);
+                        result["summarize_error"] = json!(e.to_string());
+                    }
+                }
+            }
+        }
+
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
 }
@@ -141,21 +587,30 @@ impl Tool for ConversationGetTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "conversation.get".to_string(),
-            description: Some("Gets messages from a conversation.".to_string()),
+            description: Some(
+                "Gets messages from a conversation. Once summarized, returns the latest summary followed by \
+                 the messages after it."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "conversation_id": {
                         "type": "string",
-                        "description": "Conversation ID"
+                        "description": "Conversation ID (default: pinned conversation)"
                     },
                     "limit": {
                         "type": "integer",
                         "description": "Max messages to return (default: 50)"
-                    }
-                },
-                "required": ["conversation_id"]
+                    },
+                    "compact": {
+                        "type": "boolean",
+                        "description": "Replace summarized messages with their summary and return the latest messages (default: true)"
+                    },
+                    "namespace": namespace_schema()
+                }
             }),
+            output_schema: None,
         }
     }
 
@@ -164,39 +619,48 @@ impl Tool for ConversationGetTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let conversation_id = arguments
-            .get("conversation_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'conversation_id'".to_string()))?;
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
 
         let limit = arguments
             .get("limit")
             .and_then(|v| v.as_u64())
             .unwrap_or(50) as usize;
 
+        let compact_view = arguments
+            .get("compact")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
         let messages = state
             .memory_store
-            .get_messages(conversation_id, limit)
+            .get_messages(&conversation_id, if compact_view { ALL_MESSAGES } else { limit })
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
-        let messages_json: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                json!({
-                    "id": m.id,
-                    "role": m.role,
-                    "content": m.content,
-                    "created_at": m.created_at
-                })
-            })
-            .collect();
+        let mut result = json!({ "conversation_id": conversation_id });
+        let messages = if !compact_view {
+            messages
+        } else {
+            match compact(messages) {
+                (Some(summary), live) => {
+                    result["summary"] = json!({
+                        "id": summary.id,
+                        "content": summary.content,
+                        "created_at": summary.created_at,
+                        "messages": summary_info(&summary).map(|info| info["messages"].clone())
+                    });
+                    let skip = live.len().saturating_sub(limit.saturating_sub(1));
+                    std::iter::once(summary).chain(live.into_iter().skip(skip)).collect()
+                }
+                (None, mut live) => {
+                    let skip = live.len().saturating_sub(limit);
+                    live.split_off(skip)
+                }
+            }
+        };
 
-        let result = json!({
-            "conversation_id": conversation_id,
-            "count": messages.len(),
-            "messages": messages_json
-        });
+        result["count"] = json!(messages.len());
+        result["messages"] = json!(messages.iter().map(message_json).collect::<Vec<_>>());
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
@@ -209,18 +673,31 @@ pub struct ConversationListTool;
 #[async_trait]
 impl Tool for ConversationListTool {
     fn definition(&self) -> ToolDefinition {
+        let mut properties = json!({
+            "limit": {
+                "type": "integer",
+                "description": "Max conversations to return (default: 20)"
+            },
+            "include_archived": {
+                "type": "boolean",
+                "description": "Also list archived conversations (default: false)"
+            },
+            "namespace": namespace_schema()
+        });
+        properties.as_object_mut().unwrap().extend(filter_properties("conversations updated").as_object().unwrap().clone());
+
         ToolDefinition {
             name: "conversation.list".to_string(),
-            description: Some("Lists all conversations.".to_string()),
+            description: Some(
+                "Lists conversations, most recently updated first, optionally only those with given tags or \
+                 updated within a time range."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
-                "properties": {
-                    "limit": {
-                        "type": "integer",
-                        "description": "Max conversations to return (default: 20)"
-                    }
-                }
+                "properties": properties
             }),
+            output_schema: None,
         }
     }
 
@@ -234,9 +711,22 @@ impl Tool for ConversationListTool {
             .and_then(|v| v.as_u64())
             .unwrap_or(20) as usize;
 
+        let include_archived = arguments
+            .get("include_archived")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let namespace = resolve_namespace(&arguments)?;
+        let filter = ConversationFilter {
+            namespace,
+            tags: tags_argument(&arguments, "tags")?,
+            since: time_argument(&arguments, "since")?,
+            until: time_argument(&arguments, "until")?,
+            archived: if include_archived { None } else { Some(false) },
+        };
         let conversations = state
             .memory_store
-            .list_conversations(limit)
+            .list_conversations(limit, &filter)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
@@ -246,6 +736,9 @@ impl Tool for ConversationListTool {
                 json!({
                     "id": c.id,
                     "title": c.title,
+                    "namespace": c.namespace,
+                    "tags": c.tags,
+                    "archived": c.archived,
                     "created_at": c.created_at,
                     "updated_at": c.updated_at
                 })
@@ -268,23 +761,36 @@ pub struct ConversationSearchTool;
 #[async_trait]
 impl Tool for ConversationSearchTool {
     fn definition(&self) -> ToolDefinition {
+        let mut properties = json!({
+            "query": {
+                "type": "string",
+                "description": "Search query (empty matches every message)"
+            },
+            "limit": {
+                "type": "integer",
+                "description": "Max results (default: 20)"
+            },
+            "role": {
+                "type": "string",
+                "description": "Only messages with this role (user, assistant, system, tool)"
+            },
+            "namespace": namespace_schema()
+        });
+        properties.as_object_mut().unwrap().extend(filter_properties("messages sent").as_object().unwrap().clone());
+
         ToolDefinition {
             name: "conversation.search".to_string(),
-            description: Some("Searches messages across all conversations.".to_string()),
+            description: Some(
+                "Searches messages across all conversations of a namespace, optionally only those of \
+                 conversations with given tags, with a given role or sent within a time range."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "Search query"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Max results (default: 20)"
-                    }
-                },
+                "properties": properties,
                 "required": ["query"]
             }),
+            output_schema: None,
         }
     }
 
@@ -303,9 +809,16 @@ impl Tool for ConversationSearchTool {
             .and_then(|v| v.as_u64())
             .unwrap_or(20) as usize;
 
+        let filter = MessageFilter {
+            namespace: resolve_namespace(&arguments)?,
+            tags: tags_argument(&arguments, "tags")?,
+            role: arguments.get("role").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            since: time_argument(&arguments, "since")?,
+            until: time_argument(&arguments, "until")?,
+        };
         let results = state
             .memory_store
-            .search_messages(query, limit)
+            .search_messages(query, limit, &filter)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
@@ -332,3 +845,792 @@ impl Tool for ConversationSearchTool {
     }
 }
 
+
+/// Tool to pin (or unpin) the default conversation scope.
+#[derive(Debug)]
+pub struct ConversationPinTool;
+
+#[async_trait]
+impl Tool for ConversationPinTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.pin".to_string(),
+            description: Some(
+                "Pins a conversation so memory.*, conversation.* and vector.* tools default to it. Omit conversation_id to unpin.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID to pin (omit to unpin)"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let conversation_id = arguments
+            .get("conversation_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let previous = state.pinned_conversation();
+        state.pin_conversation(conversation_id.clone());
+
+        let result = json!({
+            "success": true,
+            "pinned": conversation_id,
+            "previous": previous
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to summarize older messages of a conversation.
+#[derive(Debug)]
+pub struct ConversationSummarizeTool;
+
+#[async_trait]
+impl Tool for ConversationSummarizeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.summarize".to_string(),
+            description: Some(
+                "Summarizes all but the most recent messages of a conversation with the configured LLM and \
+                 stores the summary as a system message. conversation.get then returns the summary in place \
+                 of the summarized messages."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "keep_recent": {
+                        "type": "integer",
+                        "description": "Most recent messages to leave out of the summary (default: from config, 10)"
+                    },
+                    "namespace": namespace_schema()
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+
+        let keep_recent = arguments
+            .get("keep_recent")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(state.config.conversation.keep_recent);
+
+        let result = match summarize_conversation(&state, &conversation_id, keep_recent).await? {
+            Some(mut summary) => {
+                summary["success"] = json!(true);
+                summary["conversation_id"] = json!(conversation_id);
+                summary
+            }
+            None => json!({
+                "success": true,
+                "conversation_id": conversation_id,
+                "summarized": 0,
+                "message": "Nothing to summarize"
+            }),
+        };
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to add and remove tags of a conversation.
+#[derive(Debug)]
+pub struct ConversationTagTool;
+
+#[async_trait]
+impl Tool for ConversationTagTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.tag".to_string(),
+            description: Some(
+                "Adds and removes tags of a conversation. Tags are lowercased; conversation.list and \
+                 conversation.search can filter by them."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "add": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tags to add"
+                    },
+                    "remove": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tags to remove"
+                    },
+                    "namespace": namespace_schema()
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let add = tags_argument(&arguments, "add")?;
+        let remove = tags_argument(&arguments, "remove")?;
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+
+        let tags = state
+            .memory_store
+            .tag_conversation(&conversation_id, &add, &remove)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "success": true,
+            "conversation_id": conversation_id,
+            "tags": tags
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to rename, annotate or archive a conversation.
+#[derive(Debug)]
+pub struct ConversationUpdateTool;
+
+#[async_trait]
+impl Tool for ConversationUpdateTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.update".to_string(),
+            description: Some(
+                "Updates the title, metadata or archived flag of a conversation. Archived conversations are \
+                 left out of conversation.list unless include_archived is set."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "New title"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "description": "New metadata, replacing the old"
+                    },
+                    "archived": {
+                        "type": "boolean",
+                        "description": "Archive (true) or unarchive (false) the conversation"
+                    },
+                    "namespace": namespace_schema()
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let update = ConversationUpdate {
+            title: arguments.get("title").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            metadata: arguments.get("metadata").map(|v| v.to_string()),
+            archived: arguments.get("archived").and_then(|v| v.as_bool()),
+        };
+        if update.title.is_none() && update.metadata.is_none() && update.archived.is_none() {
+            return Err(ToolError::InvalidInput(
+                "Nothing to update: pass 'title', 'metadata' or 'archived'".to_string(),
+            ));
+        }
+
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+        let conversation = state
+            .memory_store
+            .update_conversation(&conversation_id, update)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "success": true,
+            "conversation": {
+                "id": conversation.id,
+                "title": conversation.title,
+                "metadata": conversation.metadata.as_deref().and_then(|m| serde_json::from_str::<Value>(m).ok()),
+                "namespace": conversation.namespace,
+                "tags": conversation.tags,
+                "archived": conversation.archived,
+                "created_at": conversation.created_at,
+                "updated_at": conversation.updated_at
+            }
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Resolves the `message_id` argument of the message tools.
+fn required_message_id(arguments: &Value) -> Result<&str, ToolError> {
+    arguments
+        .get("message_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidInput("Missing 'message_id'".to_string()))
+}
+
+/// Tool to correct the content or metadata of a stored message.
+#[derive(Debug)]
+pub struct MessageUpdateTool;
+
+#[async_trait]
+impl Tool for MessageUpdateTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "message.update".to_string(),
+            description: Some("Replaces the content and/or metadata of a message in a conversation.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "Message ID"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "New content"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "description": "New metadata, replacing the old"
+                    },
+                    "namespace": namespace_schema()
+                },
+                "required": ["message_id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let message_id = required_message_id(&arguments)?;
+        let content = arguments.get("content").and_then(|v| v.as_str());
+        let metadata = arguments.get("metadata").map(|v| v.to_string());
+        if content.is_none() && metadata.is_none() {
+            return Err(ToolError::InvalidInput("Nothing to update: pass 'content' or 'metadata'".to_string()));
+        }
+
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+        let message = state
+            .memory_store
+            .update_message(&conversation_id, message_id, content, metadata)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "success": true,
+            "conversation_id": conversation_id,
+            "message": message_json(&message)
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to delete a message from a conversation.
+#[derive(Debug)]
+pub struct MessageDeleteTool;
+
+#[async_trait]
+impl Tool for MessageDeleteTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "message.delete".to_string(),
+            description: Some("Deletes a message from a conversation.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "Message ID"
+                    },
+                    "namespace": namespace_schema()
+                },
+                "required": ["message_id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let message_id = required_message_id(&arguments)?;
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+        state
+            .memory_store
+            .delete_message(&conversation_id, message_id)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "success": true,
+            "conversation_id": conversation_id,
+            "message_id": message_id
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to export a conversation as a Markdown transcript or JSONL.
+#[derive(Debug)]
+pub struct ConversationExportTool;
+
+#[async_trait]
+impl Tool for ConversationExportTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.export".to_string(),
+            description: Some(
+                "Exports every message of a conversation as a Markdown transcript or as JSONL (one message \
+                 per line) for other agent frameworks."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation ID (default: pinned conversation)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["markdown", "jsonl"],
+                        "description": "Export format (default: markdown)"
+                    },
+                    "namespace": namespace_schema()
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let conversation_id = resolve_scoped_conversation(&arguments, &state).await?;
+        let format = arguments.get("format").and_then(|v| v.as_str()).unwrap_or("markdown");
+
+        let conversation = state
+            .memory_store
+            .get_conversation(&conversation_id)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let messages = state
+            .memory_store
+            .get_messages(&conversation_id, ALL_MESSAGES)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        match format {
+            "markdown" => Ok(ToolOutput::text(export_markdown(&conversation, &messages))),
+            "jsonl" => Ok(ToolOutput::text(export_jsonl(&messages))),
+            other => Err(ToolError::InvalidInput(format!(
+                "Unknown format '{}' (expected markdown or jsonl)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Tool to import messages from other agent frameworks.
+#[derive(Debug)]
+pub struct ConversationImportTool;
+
+#[async_trait]
+impl Tool for ConversationImportTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "conversation.import".to_string(),
+            description: Some(
+                "Imports OpenAI-style chat messages (or a conversation.export JSONL) into a new or existing \
+                 conversation."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "messages": {
+                        "type": "array",
+                        "description": "Messages with role and content (a string or an array of text parts)",
+                        "items": {"type": "object"}
+                    },
+                    "data": {
+                        "type": "string",
+                        "description": "Messages as a JSON array, an object with a 'messages' array, or JSONL (instead of messages)"
+                    },
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Conversation to append to (default: a new conversation)"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Title of the new conversation"
+                    },
+                    "namespace": namespace_schema()
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let messages = match (arguments.get("messages"), arguments.get("data").and_then(|v| v.as_str())) {
+            (Some(Value::Array(messages)), _) => messages.clone(),
+            (_, Some(data)) => parse_import_data(data)?,
+            _ => return Err(ToolError::InvalidInput("Missing 'messages' or 'data'".to_string())),
+        };
+        // Check everything before anything is stored
+        let messages = messages.iter().map(import_message).collect::<Result<Vec<_>, _>>()?;
+
+        let conversation_id = match arguments.get("conversation_id").and_then(|v| v.as_str()) {
+            Some(_) => resolve_scoped_conversation(&arguments, &state).await?,
+            None => {
+                let title = arguments.get("title").and_then(|v| v.as_str()).map(String::from);
+                let namespace = resolve_namespace(&arguments)?;
+                state
+                    .memory_store
+                    .create_conversation(title, None, namespace.as_deref())
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+            }
+        };
+
+        // Summaries refer to the last message they cover by ID, which changes
+        let mut ids = std::collections::HashMap::new();
+        let mut imported = 0;
+        let mut skipped = 0;
+        for message in messages {
+            let Some(mut message) = message else {
+                skipped += 1;
+                continue;
+            };
+            if let Some(Value::Object(summary)) = message.metadata.get_mut("summary") {
+                let through = summary.get("through").and_then(|v| v.as_str()).and_then(|id| ids.get(id));
+                if let Some(through) = through.cloned() {
+                    summary.insert("through".to_string(), json!(through));
+                }
+            }
+
+            let metadata = (!message.metadata.is_empty()).then(|| Value::Object(message.metadata).to_string());
+            let id = state
+                .memory_store
+                .add_message(&conversation_id, &message.role, &message.content, metadata)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            if let Some(source_id) = message.source_id {
+                ids.insert(source_id, id);
+            }
+            imported += 1;
+        }
+
+        let result = json!({
+            "success": true,
+            "conversation_id": conversation_id,
+            "imported": imported,
+            "skipped": skipped
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    /// Stands in for llm.chat: "summarizes" by counting prompt lines.
+    #[derive(Debug)]
+    struct FakeLlm;
+
+    #[async_trait]
+    impl Tool for FakeLlm {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "llm.chat".to_string(),
+                description: None,
+                input_schema: json!({"type": "object"}),
+                output_schema: None,
+            }
+        }
+
+        async fn execute(&self, arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            let prompt = arguments["prompt"].as_str().unwrap();
+            let lines = prompt.lines().filter(|l| l.starts_with("user: ")).count();
+            Ok(ToolOutput::text(json!({ "content": format!("{} user messages", lines) }).to_string()))
+        }
+    }
+
+    fn state(config: ConversationConfig) -> Arc<RuntimeState> {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            conversation: config,
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(FakeLlm));
+        state
+    }
+
+    async fn run(tool: &dyn Tool, args: Value, state: &Arc<RuntimeState>) -> Value {
+        let output = tool.execute(args, state.clone()).await.unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!() };
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_summarize_compacts_get() {
+        let state = state(ConversationConfig::default());
+        let id = state.memory_store.create_conversation(None, None, None).await.unwrap();
+        for i in 0..5 {
+            run(&ConversationAddTool, json!({"conversation_id": id, "role": "user", "content": format!("m{}", i)}), &state).await;
+        }
+
+        let summary = run(&ConversationSummarizeTool, json!({"conversation_id": id, "keep_recent": 2}), &state).await;
+        assert_eq!(summary["summarized"], 3);
+        assert_eq!(summary["summary"], "3 user messages");
+
+        let got = run(&ConversationGetTool, json!({"conversation_id": id}), &state).await;
+        let contents: Vec<&str> = got["messages"].as_array().unwrap().iter().map(|m| m["content"].as_str().unwrap()).collect();
+        assert_eq!(contents, vec!["3 user messages", "m3", "m4"]);
+        assert_eq!(got["summary"]["messages"], 3);
+
+        // The full history is still there
+        let raw = run(&ConversationGetTool, json!({"conversation_id": id, "compact": false}), &state).await;
+        assert_eq!(raw["count"], 6);
+
+        // A second summary folds in the first
+        run(&ConversationAddTool, json!({"conversation_id": id, "role": "user", "content": "m5"}), &state).await;
+        let summary = run(&ConversationSummarizeTool, json!({"conversation_id": id, "keep_recent": 1}), &state).await;
+        assert_eq!(summary["summarized"], 2);
+        assert_eq!(summary["total_summarized"], 5);
+        let got = run(&ConversationGetTool, json!({"conversation_id": id}), &state).await;
+        assert_eq!(got["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_auto_summarize_on_add() {
+        let state = state(ConversationConfig {
+            auto_summarize: true,
+            max_messages: 4,
+            keep_recent: 2,
+            ..ConversationConfig::default()
+        });
+        let id = state.memory_store.create_conversation(None, None, None).await.unwrap();
+        let mut summarized = vec![];
+        for i in 0..5 {
+            let added = run(&ConversationAddTool, json!({"conversation_id": id, "role": "user", "content": format!("m{}", i)}), &state).await;
+            summarized.push(added["summarized"].as_bool().unwrap_or(false));
+        }
+        assert_eq!(summarized, vec![false, false, false, false, true]);
+
+        let got = run(&ConversationGetTool, json!({"conversation_id": id}), &state).await;
+        assert_eq!(got["count"], 3);
+        assert_eq!(got["messages"][0]["role"], "system");
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_follow_api_key() {
+        use crate::core::{KeyIdentity, RequestContext};
+
+        let state = state(ConversationConfig::default());
+        let agent = |name: &str| {
+            RequestContext::new("s1").with_identity(Some(Arc::new(KeyIdentity {
+                name: name.to_string(),
+                key_hash: format!("{}-hash", name),
+                scopes: vec!["*".to_string()],
+            })))
+        };
+
+        let created = caller::with_context(agent("a"), run(&ConversationCreateTool, json!({}), &state)).await;
+        assert_eq!(created["namespace"], "a");
+        let id = created["conversation_id"].as_str().unwrap().to_string();
+
+        // Another agent neither lists nor reads it
+        let listed = caller::with_context(agent("b"), run(&ConversationListTool, json!({}), &state)).await;
+        assert_eq!(listed["count"], 0);
+        let err = caller::with_context(
+            agent("b"),
+            ConversationGetTool.execute(json!({"conversation_id": id}), state.clone()),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Conversation not found"), "{}", err);
+
+        // Naming the other namespace explicitly is refused
+        let err = caller::with_context(
+            agent("b"),
+            ConversationGetTool.execute(json!({"conversation_id": id, "namespace": "a"}), state.clone()),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)), "{}", err);
+        let listed = caller::with_context(agent("a"), run(&ConversationListTool, json!({}), &state)).await;
+        assert_eq!(listed["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_and_export() {
+        let state = state(ConversationConfig::default());
+        let messages = json!([
+            {"role": "developer", "content": "Be brief."},
+            {"role": "user", "content": [{"type": "text", "text": "Weather?"}, {"type": "image_url", "image_url": {"url": "x"}}]},
+            {"role": "assistant", "content": null, "tool_calls": [{"id": "c1", "type": "function", "function": {"name": "weather", "arguments": "{}"}}]},
+            {"role": "tool", "tool_call_id": "c1", "content": "Sunny"},
+            {"role": "assistant", "content": ""}
+        ]);
+        let imported = run(&ConversationImportTool, json!({"messages": messages, "title": "Weather"}), &state).await;
+        assert_eq!((imported["imported"].as_u64(), imported["skipped"].as_u64()), (Some(4), Some(1)));
+        let id = imported["conversation_id"].as_str().unwrap().to_string();
+
+        let markdown = ConversationExportTool.execute(json!({"conversation_id": id}), state.clone()).await.unwrap();
+        let ToolContent::Text { text } = &markdown.content[0] else { panic!() };
+        assert!(text.starts_with("# Weather\n"));
+        assert!(text.contains("## System\n") && text.contains("Weather?") && text.contains("## Tool\n"));
+
+        // A JSONL export imports back into an identical conversation
+        let jsonl = ConversationExportTool
+            .execute(json!({"conversation_id": id, "format": "jsonl"}), state.clone())
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &jsonl.content[0] else { panic!() };
+        assert_eq!(text.lines().count(), 4);
+        let line: Value = serde_json::from_str(text.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(line["metadata"]["dropped_parts"], 1);
+
+        let copy = run(&ConversationImportTool, json!({"data": text}), &state).await;
+        let copy_id = copy["conversation_id"].as_str().unwrap();
+        let copied = state.memory_store.get_messages(copy_id, ALL_MESSAGES).await.unwrap();
+        assert_eq!(copied.len(), 4);
+        assert_eq!(copied[2].metadata.as_deref().map(|m| m.contains("tool_calls")), Some(true));
+
+        let err = ConversationImportTool.execute(json!({"data": "{\"role\": 1}\nnot json"}), state).await.unwrap_err();
+        assert!(err.to_string().contains("Line 2"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let state = state(ConversationConfig::default());
+        let id = state.memory_store.create_conversation(Some("Draft".to_string()), None, None).await.unwrap();
+        let added = run(&ConversationAddTool, json!({"conversation_id": id, "role": "user", "content": "teh typo"}), &state).await;
+        let message_id = added["message_id"].as_str().unwrap();
+        run(&ConversationAddTool, json!({"conversation_id": id, "role": "user", "content": "oops"}), &state).await;
+
+        let updated = run(&MessageUpdateTool, json!({"conversation_id": id, "message_id": message_id, "content": "the typo"}), &state).await;
+        assert_eq!(updated["message"]["content"], "the typo");
+        let missing = MessageUpdateTool.execute(json!({"conversation_id": id, "message_id": "nope", "content": "x"}), state.clone()).await;
+        assert!(missing.unwrap_err().to_string().contains("Message not found"));
+
+        let messages = state.memory_store.get_messages(&id, 10).await.unwrap();
+        run(&MessageDeleteTool, json!({"conversation_id": id, "message_id": messages[1].id}), &state).await;
+        let messages = state.memory_store.get_messages(&id, 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "the typo");
+
+        let renamed = run(&ConversationUpdateTool, json!({"conversation_id": id, "title": "Final", "archived": true}), &state).await;
+        assert_eq!(renamed["conversation"]["title"], "Final");
+        assert!(ConversationUpdateTool.execute(json!({"conversation_id": id}), state.clone()).await.is_err());
+
+        let listed = run(&ConversationListTool, json!({}), &state).await;
+        assert_eq!(listed["count"], 0);
+        let listed = run(&ConversationListTool, json!({"include_archived": true}), &state).await;
+        assert_eq!(listed["conversations"][0]["archived"], true);
+    }
+
+    #[tokio::test]
+    async fn test_tags_and_filters() {
+        let state = state(ConversationConfig::default());
+        let work = state.memory_store.create_conversation(Some("Work".to_string()), None, None).await.unwrap();
+        let home = state.memory_store.create_conversation(Some("Home".to_string()), None, None).await.unwrap();
+        run(&ConversationAddTool, json!({"conversation_id": work, "role": "user", "content": "ship the release"}), &state).await;
+        run(&ConversationAddTool, json!({"conversation_id": work, "role": "assistant", "content": "release shipped"}), &state).await;
+        run(&ConversationAddTool, json!({"conversation_id": home, "role": "user", "content": "release the hounds"}), &state).await;
+
+        let tagged = run(&ConversationTagTool, json!({"conversation_id": work, "add": ["Project-X", "urgent"]}), &state).await;
+        assert_eq!(tagged["tags"], json!(["project-x", "urgent"]));
+        let tagged = run(&ConversationTagTool, json!({"conversation_id": work, "remove": ["urgent"]}), &state).await;
+        assert_eq!(tagged["tags"], json!(["project-x"]));
+        run(&ConversationTagTool, json!({"conversation_id": home, "add": ["personal"]}), &state).await;
+        assert!(ConversationTagTool.execute(json!({"conversation_id": work, "add": ["a,b"]}), state.clone()).await.is_err());
+
+        let listed = run(&ConversationListTool, json!({"tags": ["project-x"]}), &state).await;
+        assert_eq!(listed["count"], 1);
+        assert_eq!(listed["conversations"][0]["title"], "Work");
+        let listed = run(&ConversationListTool, json!({"tags": ["project-x", "personal"]}), &state).await;
+        assert_eq!(listed["count"], 0);
+        let listed = run(&ConversationListTool, json!({"since": "1h"}), &state).await;
+        assert_eq!(listed["count"], 2);
+        let listed = run(&ConversationListTool, json!({"until": "2000-01-01"}), &state).await;
+        assert_eq!(listed["count"], 0);
+
+        let found = run(&ConversationSearchTool, json!({"query": "release", "tags": "project-x"}), &state).await;
+        assert_eq!(found["count"], 2);
+        let found = run(&ConversationSearchTool, json!({"query": "release", "role": "user"}), &state).await;
+        assert_eq!(found["count"], 2);
+        let found = run(&ConversationSearchTool, json!({"query": "release", "role": "user", "tags": ["project-x"]}), &state).await;
+        assert_eq!(found["results"][0]["content"], "ship the release");
+        assert!(ConversationSearchTool.execute(json!({"query": "x", "since": "yesterday"}), state.clone()).await.is_err());
+    }
+}
@@ -0,0 +1,429 @@
+//! Docker container tools (`docker` feature).
+//!
+//! Talks to the local Docker daemon (the socket, or `DOCKER_HOST`). Listing
+//! containers and reading logs is always allowed; docker.run only starts
+//! images matching `security.allowed_docker_images`, with commands from
+//! `security.allowed_docker_commands`, and docker.stop only touches
+//! containers of allowed images. Containers run without network access
+//! unless asked for.
+
+use async_trait::async_trait;
+use bollard::container::{
+    Config as ContainerConfig, CreateContainerOptions, KillContainerOptions, ListContainersOptions, LogOutput,
+    LogsOptions, RemoveContainerOptions, RestartContainerOptions, StopContainerOptions, WaitContainerOptions,
+};
+use bollard::image::CreateImageOptions;
+use bollard::models::HostConfig;
+use bollard::Docker;
+use futures::StreamExt;
+use globset::Glob;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::RuntimeState;
+use crate::tools::core::is_command_allowed;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Label marking containers started by docker.run.
+const MANAGED_LABEL: &str = "aegis.managed";
+
+/// Default seconds docker.run waits for a container that isn't detached.
+const DEFAULT_RUN_TIMEOUT_SECS: u64 = 300;
+
+fn connect() -> Result<Docker, ToolError> {
+    Docker::connect_with_local_defaults()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Cannot connect to Docker: {}", e)))
+}
+
+fn docker_error(e: bollard::errors::Error) -> ToolError {
+    match e {
+        bollard::errors::Error::DockerResponseServerError { status_code: 404, message } => ToolError::NotFound(message),
+        e => ToolError::ExecutionFailed(format!("Docker: {}", e)),
+    }
+}
+
+/// Whether `image` matches one of the allowed patterns. Patterns are globs
+/// (`python:3.*`, `ghcr.io/acme/*`); an image without a tag also matches
+/// as `:latest`.
+pub(crate) fn is_image_allowed(allowed: &[String], image: &str) -> bool {
+    let tagged = if image.rsplit('/').next().is_some_and(|last| last.contains(':') || last.contains('@')) {
+        image.to_string()
+    } else {
+        format!("{}:latest", image)
+    };
+    allowed.iter().any(|pattern| {
+        pattern == "*"
+            || Glob::new(pattern)
+                .map(|glob| glob.compile_matcher())
+                .is_ok_and(|matcher| matcher.is_match(image) || matcher.is_match(&tagged))
+    })
+}
+
+/// Keeps the last `max` bytes of `text`, marking the cut.
+fn truncate_front(text: String, max: usize) -> String {
+    if text.len() <= max {
+        return text;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... {} bytes truncated ...]\n{}", start, &text[start..])
+}
+
+/// Collects a container's stdout and stderr.
+async fn collect_logs(docker: &Docker, id: &str, tail: Option<usize>, timestamps: bool) -> Result<(String, String), ToolError> {
+    let options = LogsOptions::<String> {
+        stdout: true,
+        stderr: true,
+        timestamps,
+        tail: tail.map_or_else(|| "all".to_string(), |n| n.to_string()),
+        ..Default::default()
+    };
+    let mut stream = docker.logs(id, Some(options));
+    let (mut stdout, mut stderr) = (String::new(), String::new());
+    while let Some(chunk) = stream.next().await {
+        match chunk.map_err(docker_error)? {
+            LogOutput::StdErr { message } => stderr.push_str(&String::from_utf8_lossy(&message)),
+            LogOutput::StdOut { message } | LogOutput::Console { message } => {
+                stdout.push_str(&String::from_utf8_lossy(&message))
+            }
+            LogOutput::StdIn { .. } => {}
+        }
+    }
+    Ok((stdout, stderr))
+}
+
+// ============================================================================
+// Docker PS Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DockerPsArgs {
+    /// Include stopped containers (default: false)
+    #[serde(default)]
+    all: bool,
+    /// Only containers whose name contains this
+    #[serde(default)]
+    name: Option<String>,
+    /// Only containers started by docker.run (default: false)
+    #[serde(default)]
+    managed: bool,
+}
+
+#[derive(Debug)]
+pub struct DockerPsTool;
+
+#[async_trait]
+impl TypedTool for DockerPsTool {
+    type Args = DockerPsArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "docker.ps";
+    const DESCRIPTION: &'static str = "Lists Docker containers with their image, state and status.";
+
+    async fn run(&self, args: DockerPsArgs, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let mut filters = HashMap::new();
+        if let Some(name) = args.name {
+            filters.insert("name".to_string(), vec![name]);
+        }
+        if args.managed {
+            filters.insert("label".to_string(), vec![format!("{}=true", MANAGED_LABEL)]);
+        }
+        let containers = connect()?
+            .list_containers(Some(ListContainersOptions { all: args.all, filters, ..Default::default() }))
+            .await
+            .map_err(docker_error)?;
+
+        let containers: Vec<Value> = containers
+            .into_iter()
+            .map(|c| {
+                json!({
+                    "id": c.id.as_deref().map(|id| &id[..id.len().min(12)]),
+                    "names": c.names.unwrap_or_default().iter().map(|n| n.trim_start_matches('/')).collect::<Vec<_>>(),
+                    "image": c.image,
+                    "state": c.state,
+                    "status": c.status,
+                    "created": c.created.and_then(|t| chrono::DateTime::from_timestamp(t, 0)).map(|t| t.to_rfc3339()),
+                    "managed": c.labels.is_some_and(|labels| labels.contains_key(MANAGED_LABEL)),
+                })
+            })
+            .collect();
+        Ok(ToolOutput::structured(json!({ "count": containers.len(), "containers": containers })))
+    }
+}
+
+// ============================================================================
+// Docker Logs Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DockerLogsArgs {
+    /// Container ID or name
+    container: String,
+    /// Lines from the end (default: 100)
+    #[serde(default)]
+    tail: Option<usize>,
+    /// Prefix lines with timestamps (default: false)
+    #[serde(default)]
+    timestamps: bool,
+}
+
+#[derive(Debug)]
+pub struct DockerLogsTool;
+
+#[async_trait]
+impl TypedTool for DockerLogsTool {
+    type Args = DockerLogsArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "docker.logs";
+    const DESCRIPTION: &'static str = "Returns the last lines of a container's stdout and stderr.";
+
+    async fn run(&self, args: DockerLogsArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let max = state.config.security.max_command_output_bytes;
+        let (stdout, stderr) =
+            collect_logs(&connect()?, &args.container, Some(args.tail.unwrap_or(100)), args.timestamps).await?;
+        Ok(ToolOutput::structured(json!({
+            "container": args.container,
+            "stdout": truncate_front(stdout, max),
+            "stderr": truncate_front(stderr, max),
+        })))
+    }
+}
+
+// ============================================================================
+// Docker Run Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DockerRunArgs {
+    /// Image to run; must match security.allowed_docker_images
+    image: String,
+    /// Command and arguments (default: the image's); the command must match security.allowed_docker_commands
+    #[serde(default)]
+    command: Option<Vec<String>>,
+    /// Environment variables
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Container name
+    #[serde(default)]
+    name: Option<String>,
+    /// Working directory inside the container
+    #[serde(default)]
+    workdir: Option<String>,
+    /// Return once started instead of waiting for it to exit (default: false)
+    #[serde(default)]
+    detach: bool,
+    /// Seconds to wait before killing a container that isn't detached (default: 300)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// Give the container network access (default: false)
+    #[serde(default)]
+    network: bool,
+    /// Memory limit in MiB
+    #[serde(default)]
+    memory_mb: Option<u64>,
+    /// CPU limit, e.g. 0.5 or 2
+    #[serde(default)]
+    cpus: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct DockerRunTool;
+
+#[async_trait]
+impl TypedTool for DockerRunTool {
+    type Args = DockerRunArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "docker.run";
+    const DESCRIPTION: &'static str =
+        "Runs a command in a new container of an allowed image, pulling it if needed. Waits for it to exit and returns its output and exit code (the container is then removed), or with detach returns once it has started. No network unless 'network' is set.";
+
+    async fn run(&self, args: DockerRunArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let security = &state.config.security;
+        if !is_image_allowed(&security.allowed_docker_images, &args.image) {
+            return Err(ToolError::PermissionDenied(format!("Image not allowed: {}", args.image)));
+        }
+        if let Some(program) = args.command.as_ref().and_then(|command| command.first()) {
+            if !is_command_allowed(&security.allowed_docker_commands, program) {
+                return Err(ToolError::PermissionDenied(format!("Command not allowed in containers: {}", program)));
+            }
+        }
+
+        let docker = connect()?;
+        if docker.inspect_image(&args.image).await.is_err() {
+            let mut pull = docker.create_image(
+                Some(CreateImageOptions { from_image: args.image.as_str(), ..Default::default() }),
+                None,
+                None,
+            );
+            while let Some(progress) = pull.next().await {
+                progress.map_err(|e| ToolError::ExecutionFailed(format!("Cannot pull {}: {}", args.image, e)))?;
+            }
+        }
+
+        let host_config = HostConfig {
+            network_mode: (!args.network).then(|| "none".to_string()),
+            memory: args.memory_mb.map(|mb| (mb * 1024 * 1024) as i64),
+            nano_cpus: args.cpus.map(|cpus| (cpus * 1e9) as i64),
+            ..Default::default()
+        };
+        let config = ContainerConfig {
+            image: Some(args.image.clone()),
+            cmd: args.command,
+            env: Some(args.env.iter().map(|(k, v)| format!("{}={}", k, v)).collect()),
+            working_dir: args.workdir,
+            labels: Some(HashMap::from([(MANAGED_LABEL.to_string(), "true".to_string())])),
+            host_config: Some(host_config),
+            ..Default::default()
+        };
+        let options = args.name.clone().map(|name| CreateContainerOptions { name, platform: None });
+        let id = docker.create_container(options, config).await.map_err(docker_error)?.id;
+        docker.start_container::<String>(&id, None).await.map_err(docker_error)?;
+
+        if args.detach {
+            return Ok(ToolOutput::structured(json!({
+                "container_id": &id[..id.len().min(12)],
+                "name": args.name,
+                "image": args.image,
+                "detached": true,
+            })));
+        }
+
+        let timeout = Duration::from_secs(args.timeout_secs.unwrap_or(DEFAULT_RUN_TIMEOUT_SECS));
+        let mut wait = docker.wait_container(&id, None::<WaitContainerOptions<String>>);
+        let (exit_code, timed_out) = match tokio::time::timeout(timeout, wait.next()).await {
+            Ok(Some(Ok(response))) => (Some(response.status_code), false),
+            // Non-zero exits come back as errors
+            Ok(Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. }))) => (Some(code), false),
+            Ok(Some(Err(e))) => return Err(docker_error(e)),
+            Ok(None) => (None, false),
+            Err(_) => {
+                let _ = docker.kill_container(&id, None::<KillContainerOptions<String>>).await;
+                (None, true)
+            }
+        };
+
+        let max = security.max_command_output_bytes;
+        let logs = collect_logs(&docker, &id, None, false).await;
+        let _ = docker
+            .remove_container(&id, Some(RemoveContainerOptions { force: true, ..Default::default() }))
+            .await;
+        let (stdout, stderr) = logs?;
+
+        Ok(ToolOutput::structured(json!({
+            "container_id": &id[..id.len().min(12)],
+            "image": args.image,
+            "exit_code": exit_code,
+            "timed_out": timed_out,
+            "stdout": truncate_front(stdout, max),
+            "stderr": truncate_front(stderr, max),
+        })))
+    }
+}
+
+// ============================================================================
+// Docker Stop Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DockerStopArgs {
+    /// Container ID or name
+    container: String,
+    /// Seconds to wait for a clean exit before killing it (default: 10)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// Start it again after stopping, e.g. to restart a service (default: false)
+    #[serde(default)]
+    restart: bool,
+    /// Remove the container after stopping it (default: false)
+    #[serde(default)]
+    remove: bool,
+}
+
+#[derive(Debug)]
+pub struct DockerStopTool;
+
+#[async_trait]
+impl TypedTool for DockerStopTool {
+    type Args = DockerStopArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "docker.stop";
+    const DESCRIPTION: &'static str =
+        "Stops, restarts or removes a container whose image matches security.allowed_docker_images.";
+
+    async fn run(&self, args: DockerStopArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        if args.restart && args.remove {
+            return Err(ToolError::InvalidInput("'restart' and 'remove' can't be combined".to_string()));
+        }
+        let docker = connect()?;
+        let container = docker.inspect_container(&args.container, None).await.map_err(docker_error)?;
+        let image = container.config.and_then(|config| config.image).unwrap_or_default();
+        if !is_image_allowed(&state.config.security.allowed_docker_images, &image) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Container {} runs an image that is not allowed: {}",
+                args.container, image
+            )));
+        }
+
+        let wait = args.timeout_secs.unwrap_or(10);
+        let action = if args.restart {
+            docker
+                .restart_container(&args.container, Some(RestartContainerOptions { t: wait as isize }))
+                .await
+                .map_err(docker_error)?;
+            "restarted"
+        } else {
+            match docker.stop_container(&args.container, Some(StopContainerOptions { t: wait as i64 })).await {
+                // 304: already stopped
+                Ok(()) | Err(bollard::errors::Error::DockerResponseServerError { status_code: 304, .. }) => {}
+                Err(e) => return Err(docker_error(e)),
+            }
+            if args.remove {
+                docker
+                    .remove_container(&args.container, Some(RemoveContainerOptions { force: true, ..Default::default() }))
+                    .await
+                    .map_err(docker_error)?;
+                "removed"
+            } else {
+                "stopped"
+            }
+        };
+
+        Ok(ToolOutput::structured(json!({
+            "container": args.container,
+            "image": image,
+            "action": action,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_allowlist() {
+        let allowed = vec!["python:3.*".to_string(), "alpine".to_string(), "ghcr.io/acme/*".to_string()];
+        assert!(is_image_allowed(&allowed, "python:3.12-slim"));
+        assert!(is_image_allowed(&allowed, "alpine"));
+        assert!(is_image_allowed(&allowed, "ghcr.io/acme/worker:1.4"));
+        assert!(!is_image_allowed(&allowed, "python:2.7"));
+        assert!(!is_image_allowed(&allowed, "alpine:3.19"));
+        assert!(!is_image_allowed(&allowed, "ghcr.io/evil/worker"));
+        assert!(!is_image_allowed(&[], "alpine"));
+
+        let latest = vec!["node:latest".to_string()];
+        assert!(is_image_allowed(&latest, "node"));
+    }
+
+    #[test]
+    fn test_truncate_front_keeps_the_end() {
+        assert_eq!(truncate_front("short".to_string(), 10), "short");
+        assert_eq!(truncate_front("0123456789".to_string(), 3), "[... 7 bytes truncated ...]\n789");
+    }
+}
@@ -0,0 +1,204 @@
+//! Event rule tools: react to tool calls, tasks and memory changes.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::core::config::{EventRule, RuleOwner};
+use crate::core::events::is_forbidden_action;
+use crate::core::RuntimeState;
+use crate::tools::caller;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+// ============================================================================
+// Events Subscribe Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct EventsSubscribeArgs {
+    /// Event type, with an optional trailing '*': tool.succeeded, tool.failed, task.succeeded, task.failed, memory.set, memory.deleted
+    on: String,
+    /// Only events from this source (tool name, task ID or memory key), with an optional trailing '*'
+    #[serde(default)]
+    source: Option<String>,
+    /// Tool to call when the event arrives
+    #[serde(default)]
+    tool: Option<String>,
+    /// Saved workflow to run instead of a tool (gets the event as the 'event' input)
+    #[serde(default)]
+    workflow: Option<String>,
+    /// Tool arguments or workflow inputs; strings may reference {{event.source}} or {{event.data.field}}
+    #[serde(default)]
+    args: Option<Value>,
+    /// Timeout in seconds (default: 30)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct EventsSubscribeTool;
+
+#[async_trait]
+impl TypedTool for EventsSubscribeTool {
+    type Args = EventsSubscribeArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "events.subscribe";
+    const DESCRIPTION: &'static str =
+        "Adds a rule that runs a tool or saved workflow whenever a matching event is published, e.g. when git.commit succeeds or a memory key changes. Rules run with the subscribing caller's permissions and persist across restarts.";
+
+    async fn run(&self, args: EventsSubscribeArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        if args.tool.is_some() == args.workflow.is_some() {
+            return Err(ToolError::InvalidInput("Pass exactly one of 'tool' or 'workflow'".to_string()));
+        }
+        let context = caller::current();
+        if let Some(tool) = &args.tool {
+            let registry = state.tool_registry.read();
+            let resolved = registry.resolve(tool);
+            if registry.get(resolved).is_none() {
+                return Err(ToolError::NotFound(tool.clone()));
+            }
+            if is_forbidden_action(resolved) {
+                return Err(ToolError::PermissionDenied(format!("Event rules can't run {}", resolved)));
+            }
+            if let Some(identity) = context.as_ref().and_then(|context| context.identity.as_ref()) {
+                if !identity.allows_tool(resolved) {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "API key '{}' is not scoped for tool '{}'",
+                        identity.name, resolved
+                    )));
+                }
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let rule = EventRule {
+            id: Some(id.clone()),
+            on: args.on,
+            source: args.source,
+            tool: args.tool,
+            workflow: args.workflow,
+            args: args.args.unwrap_or(Value::Null),
+            timeout_secs: args.timeout_secs.unwrap_or(30),
+            owner: context.map(RuleOwner::of),
+        };
+        state
+            .events
+            .add_subscription(state.memory_store.as_ref(), rule)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to save rule: {}", e)))?;
+
+        Ok(ToolOutput::structured(json!({ "id": id, "subscribed": true })))
+    }
+}
+
+// ============================================================================
+// Events Unsubscribe Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct EventsUnsubscribeArgs {
+    /// Rule ID returned by events.subscribe
+    id: String,
+}
+
+#[derive(Debug)]
+pub struct EventsUnsubscribeTool;
+
+#[async_trait]
+impl TypedTool for EventsUnsubscribeTool {
+    type Args = EventsUnsubscribeArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "events.unsubscribe";
+    const DESCRIPTION: &'static str = "Removes a rule added with events.subscribe.";
+
+    async fn run(&self, args: EventsUnsubscribeArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let removed = state
+            .events
+            .remove_subscription(state.memory_store.as_ref(), &args.id)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to save rules: {}", e)))?;
+        if !removed {
+            return Err(ToolError::NotFound(format!("event rule {}", args.id)));
+        }
+        Ok(ToolOutput::structured(json!({ "id": args.id, "removed": true })))
+    }
+}
+
+// ============================================================================
+// Events List Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct EventsListArgs {}
+
+#[derive(Debug)]
+pub struct EventsListTool;
+
+#[async_trait]
+impl TypedTool for EventsListTool {
+    type Args = EventsListArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "events.list";
+    const DESCRIPTION: &'static str =
+        "Lists event rules: those from the configuration and those added with events.subscribe.";
+
+    async fn run(&self, _args: EventsListArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        Ok(ToolOutput::structured(json!({
+            "configured": state.config.event_rules,
+            "subscribed": state.events.subscriptions()
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::Tool;
+
+    #[tokio::test]
+    async fn test_subscribe_and_unsubscribe() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        let output = EventsSubscribeTool
+            .execute(
+                json!({"on": "tool.succeeded", "source": "git.commit", "workflow": "deploy"}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let id = output.structured_content.unwrap()["id"].as_str().unwrap().to_string();
+
+        let listed = EventsListTool.execute(json!({}), state.clone()).await.unwrap();
+        let subscribed = listed.structured_content.unwrap()["subscribed"].clone();
+        assert_eq!(subscribed[0]["workflow"], "deploy");
+
+        // Subscriptions survive a restart on the same store
+        let restored = crate::core::events::EventBus::new();
+        restored.restore(state.memory_store.as_ref()).await;
+        assert_eq!(restored.subscriptions()[0].id.as_deref(), Some(id.as_str()));
+
+        EventsUnsubscribeTool.execute(json!({ "id": id }), state.clone()).await.unwrap();
+        assert!(state.events.subscriptions().is_empty());
+        let err = EventsUnsubscribeTool.execute(json!({ "id": id }), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+
+        let err = EventsSubscribeTool
+            .execute(json!({"on": "tool.*", "tool": "no.such.tool"}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::NotFound(_)));
+
+        let err = EventsSubscribeTool
+            .execute(json!({"on": "approval.requested", "tool": "approval.approve", "args": {"id": "{{event.source}}"}}), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+    }
+}
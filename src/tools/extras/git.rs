@@ -1,13 +1,29 @@
 //! Git integration tools.
 
 use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
 use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
 
-use crate::core::RuntimeState;
+use crate::core::config::GitConfig;
+use crate::core::{Config, RuntimeState};
 use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::core::resolve_working_dir;
+use crate::tools::process_manager::ProcessOutput;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::roots;
+use crate::tools::{ProcessManager, ProcessRequest};
+
+/// The repository directory a git tool runs in (`path`, default the
+/// current directory), which must lie within the client's roots.
+fn repo_path(arguments: &Value) -> Result<&str, ToolError> {
+    let path = arguments.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    roots::check(Path::new(path))?;
+    Ok(path)
+}
 
 /// Tool to get git status.
 #[derive(Debug)]
@@ -28,6 +44,7 @@ impl Tool for GitStatusTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -36,10 +53,7 @@ impl Tool for GitStatusTool {
         arguments: Value,
         _state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let path = arguments
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
+        let path = repo_path(&arguments)?;
 
         let output = Command::new("git")
             .args(["status", "--porcelain", "-b"])
@@ -113,6 +127,7 @@ impl Tool for GitLogTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -121,10 +136,7 @@ impl Tool for GitLogTool {
         arguments: Value,
         _state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let path = arguments
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
+        let path = repo_path(&arguments)?;
 
         let count = arguments
             .get("count")
@@ -184,7 +196,7 @@ impl Tool for GitDiffTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "git.diff".to_string(),
-            description: Some("Gets git diff for changes.".to_string()),
+            description: Some("Gets git diff for changes, as a summary, a unified diff, or parsed hunks.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -203,9 +215,19 @@ impl Tool for GitDiffTool {
                     "commit": {
                         "type": "string",
                         "description": "Commit to diff against (e.g., HEAD~1)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["stat", "patch", "structured"],
+                        "description": "'stat' (default) for a summary, 'patch' for the unified diff, 'structured' for per-file hunks as JSON"
+                    },
+                    "context": {
+                        "type": "integer",
+                        "description": "Context lines around changes for 'patch'/'structured' (default: 3)"
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -214,12 +236,29 @@ impl Tool for GitDiffTool {
         arguments: Value,
         _state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let path = arguments
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
+        let path = repo_path(&arguments)?;
+
+        let format = arguments.get("format").and_then(|v| v.as_str()).unwrap_or("stat");
+        let context = arguments
+            .get("context")
+            .and_then(|v| v.as_u64())
+            .map(|n| format!("--unified={}", n));
 
-        let mut args = vec!["diff", "--stat"];
+        let mut args = vec!["-c", "core.quotepath=false", "diff", "--no-color", "--no-ext-diff"];
+        match format {
+            "stat" => args.push("--stat"),
+            "patch" | "structured" => {
+                if let Some(context) = &context {
+                    args.push(context);
+                }
+            }
+            other => {
+                return Err(ToolError::InvalidInput(format!(
+                    "Unknown format '{}' (expected stat, patch, or structured)",
+                    other
+                )))
+            }
+        }
 
         if arguments.get("staged").and_then(|v| v.as_bool()).unwrap_or(false) {
             args.push("--cached");
@@ -248,11 +287,319 @@ impl Tool for GitDiffTool {
             return Err(ToolError::ExecutionFailed(format!("Git error: {}", stderr)));
         }
 
+        let result = if format == "structured" {
+            let files = parse_unified_diff(&stdout);
+            json!({
+                "files_changed": files.len(),
+                "additions": files.iter().map(|f| f.additions).sum::<usize>(),
+                "deletions": files.iter().map(|f| f.deletions).sum::<usize>(),
+                "files": files
+            })
+        } else {
+            json!({
+                "diff": stdout.to_string()
+            })
+        };
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// One file in a unified diff.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffFile {
+    /// Path before the change (`None` for added files).
+    pub old_path: Option<String>,
+    /// Path after the change (`None` for deleted files).
+    pub new_path: Option<String>,
+    /// "modified", "added", "deleted", or "renamed".
+    pub status: &'static str,
+    /// Whether git reported the file as binary (no hunks).
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// One `@@` hunk of a file diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffHunk {
+    pub old_start: u64,
+    pub old_lines: u64,
+    pub new_start: u64,
+    pub new_lines: u64,
+    /// Text after the closing `@@` (usually the enclosing function).
+    pub section: String,
+    pub lines: Vec<DiffLine>,
+}
+
+/// One line of a hunk, with its line numbers on either side.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    /// "context", "add", or "remove".
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_line: Option<u64>,
+    pub content: String,
+    /// Set when the line has no trailing newline.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_newline: bool,
+}
+
+/// Parses unified diff output (as produced by `git diff`) into files and hunks.
+pub(crate) fn parse_unified_diff(diff: &str) -> Vec<DiffFile> {
+    let hunk_re = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@ ?(.*)$").unwrap();
+    let mut files: Vec<DiffFile> = Vec::new();
+    let (mut old_line, mut new_line) = (0u64, 0u64);
+    // Lines left in the current hunk, so content like "--- x" is not taken for a header
+    let (mut old_left, mut new_left) = (0u64, 0u64);
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) else {
+                break;
+            };
+            let (kind, old, new) = match line.chars().next() {
+                Some('-') => ("remove", Some(old_line), None),
+                Some('+') => ("add", None, Some(new_line)),
+                Some('\\') => {
+                    if let Some(last) = hunk.lines.last_mut() {
+                        last.no_newline = true;
+                    }
+                    continue;
+                }
+                // Some tools strip the space from empty context lines
+                _ => ("context", Some(old_line), Some(new_line)),
+            };
+            if old.is_some() {
+                old_line += 1;
+                old_left = old_left.saturating_sub(1);
+            }
+            if new.is_some() {
+                new_line += 1;
+                new_left = new_left.saturating_sub(1);
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                old_line: old,
+                new_line: new,
+                content: line.get(1..).unwrap_or("").to_string(),
+                no_newline: false,
+            });
+            continue;
+        }
+
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // Refined by the ---/+++ headers when the diff has content
+            let (old_path, new_path) = match paths.split_once(" b/") {
+                Some((old, new)) => (diff_path(old, "a/"), Some(new.to_string())),
+                None => (None, None),
+            };
+            files.push(DiffFile { old_path, new_path, status: "modified", ..DiffFile::default() });
+            continue;
+        }
+        // Plain `diff -u` output has no "diff --git" header
+        if line.starts_with("--- ") && files.last().is_none_or(|f| !f.hunks.is_empty()) {
+            files.push(DiffFile { status: "modified", ..DiffFile::default() });
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if let Some(caps) = hunk_re.captures(line) {
+            let number = |i: usize, default: u64| {
+                caps.get(i).and_then(|m| m.as_str().parse().ok()).unwrap_or(default)
+            };
+            old_line = number(1, 0);
+            new_line = number(3, 0);
+            old_left = number(2, 1);
+            new_left = number(4, 1);
+            file.hunks.push(DiffHunk {
+                old_start: old_line,
+                old_lines: old_left,
+                new_start: new_line,
+                new_lines: new_left,
+                section: caps[5].to_string(),
+                lines: Vec::new(),
+            });
+        } else if line.starts_with('\\') {
+            // "\ No newline at end of file" after the last line of a hunk
+            if let Some(last) = file.hunks.last_mut().and_then(|h| h.lines.last_mut()) {
+                last.no_newline = true;
+            }
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = diff_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = diff_path(path, "b/");
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = Some(path.to_string());
+            file.status = "renamed";
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path.to_string());
+            file.status = "renamed";
+        } else if line.starts_with("new file mode") {
+            file.status = "added";
+        } else if line.starts_with("deleted file mode") {
+            file.status = "deleted";
+        } else if let Some(rest) = line.strip_prefix("Binary files ") {
+            file.binary = true;
+            if let Some((old, new)) = rest.trim_end_matches(" differ").split_once(" and ") {
+                file.old_path = diff_path(old, "a/");
+                file.new_path = diff_path(new, "b/");
+            }
+        }
+    }
+
+    for file in &mut files {
+        file.additions = file.hunks.iter().flat_map(|h| &h.lines).filter(|l| l.kind == "add").count();
+        file.deletions = file.hunks.iter().flat_map(|h| &h.lines).filter(|l| l.kind == "remove").count();
+        if file.status == "modified" {
+            file.status = match (&file.old_path, &file.new_path) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "deleted",
+                _ => "modified",
+            };
+        }
+    }
+    files
+}
+
+/// Path from a `---`/`+++` header; `None` for /dev/null.
+fn diff_path(raw: &str, prefix: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim_matches('"');
+    if raw == "/dev/null" {
+        return None;
+    }
+    Some(raw.strip_prefix(prefix).unwrap_or(raw).to_string())
+}
+
+/// Tool to apply a unified diff.
+#[derive(Debug)]
+pub struct GitApplyPatchTool;
+
+#[async_trait]
+impl Tool for GitApplyPatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git.apply_patch".to_string(),
+            description: Some(
+                "Applies a unified diff to a repository's working tree (git apply). Use dry_run to check it applies cleanly first. Paths outside the repository are refused."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Repository path"
+                    },
+                    "patch": {
+                        "type": "string",
+                        "description": "Unified diff to apply"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only check whether the patch applies"
+                    },
+                    "stage": {
+                        "type": "boolean",
+                        "description": "Also stage the changes in the index"
+                    },
+                    "reverse": {
+                        "type": "boolean",
+                        "description": "Apply the patch in reverse (undo it)"
+                    },
+                    "three_way": {
+                        "type": "boolean",
+                        "description": "Fall back to a 3-way merge, leaving conflict markers"
+                    }
+                },
+                "required": ["patch"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        _state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let path = Path::new(repo_path(&arguments)?);
+        let patch = arguments
+            .get("patch")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'patch'".to_string()))?;
+        let flag = |name: &str| arguments.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let files = parse_unified_diff(patch);
+        if files.is_empty() {
+            return Err(ToolError::InvalidInput("Patch contains no file diffs".to_string()));
+        }
+
+        let dry_run = flag("dry_run");
+        let mut args = vec!["apply", "--verbose"];
+        if dry_run {
+            args.push("--check");
+        }
+        if flag("stage") {
+            args.push("--index");
+        }
+        if flag("reverse") {
+            args.push("--reverse");
+        }
+        if flag("three_way") {
+            args.push("--3way");
+        }
+
+        // A trailing newline is required for git to read the last line
+        let mut input = patch.to_string();
+        if !input.ends_with('\n') {
+            input.push('\n');
+        }
+
+        let output = ProcessManager::new()
+            .run(ProcessRequest::new("git", &args).cwd(path).stdin(input), |_, _| {})
+            .await?;
+
+        let errors: Vec<&str> = output
+            .stderr
+            .lines()
+            .filter(|l| l.starts_with("error:"))
+            .map(|l| l.trim_start_matches("error:").trim())
+            .collect();
+        let conflicts = if flag("three_way") { conflicted_files(path).await } else { Vec::new() };
+
+        let summary: Vec<Value> = files
+            .iter()
+            .map(|f| {
+                json!({
+                    "path": f.new_path.as_ref().or(f.old_path.as_ref()),
+                    "status": f.status,
+                    "additions": f.additions,
+                    "deletions": f.deletions
+                })
+            })
+            .collect();
+
         let result = json!({
-            "diff": stdout.to_string()
+            "success": output.success,
+            "dry_run": dry_run,
+            "applied": output.success && !dry_run,
+            "files": summary,
+            "errors": errors,
+            "conflicts": conflicts
         });
 
-        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+        let text = serde_json::to_string_pretty(&result).unwrap();
+        if output.success {
+            Ok(ToolOutput::text(text))
+        } else {
+            Ok(ToolOutput::error(text))
+        }
     }
 }
 
@@ -284,6 +631,7 @@ impl Tool for GitCommitTool {
                 },
                 "required": ["message"]
             }),
+            output_schema: None,
         }
     }
 
@@ -292,10 +640,7 @@ impl Tool for GitCommitTool {
         arguments: Value,
         _state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let path = arguments
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
+        let path = repo_path(&arguments)?;
 
         let message = arguments
             .get("message")
@@ -380,6 +725,7 @@ impl Tool for GitBranchTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -388,10 +734,7 @@ impl Tool for GitBranchTool {
         arguments: Value,
         _state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let path = arguments
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
+        let path = repo_path(&arguments)?;
 
         // Create new branch
         if let Some(name) = arguments.get("create").and_then(|v| v.as_str()) {
@@ -471,3 +814,808 @@ impl Tool for GitBranchTool {
     }
 }
 
+
+/// Credential helper that answers `get` from environment variables, so the
+/// token never appears on the command line or in the remote URL.
+const CREDENTIAL_HELPER: &str =
+    "credential.helper=!f() { test \"$1\" = get && echo \"username=$AEGIS_GIT_USERNAME\" && echo \"password=$AEGIS_GIT_PASSWORD\"; }; f";
+
+/// Remote allowlist, credentials, and timeout shared by the network git tools.
+#[derive(Debug)]
+pub struct GitRemotes {
+    config: GitConfig,
+    allowed_clone_dirs: Vec<PathBuf>,
+}
+
+impl GitRemotes {
+    /// Creates the shared remote policy from the server configuration.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.git.clone(),
+            allowed_clone_dirs: config.security.allowed_working_dirs.clone(),
+        }
+    }
+
+    /// Checks a remote URL against the allowlist (ignoring any userinfo).
+    fn check(&self, url: &str) -> Result<(), ToolError> {
+        let normalized = strip_userinfo(url);
+        let allowed = self.config.allowed_remotes.iter().any(|pattern| {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => normalized.starts_with(prefix),
+                None => normalized == *pattern,
+            }
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(ToolError::PermissionDenied(format!("Remote not in allowed list: {}", normalized)))
+        }
+    }
+
+    /// Looks up HTTPS credentials for a remote URL.
+    fn credentials(&self, url: &str, state: &RuntimeState) -> Result<Option<(String, String)>, ToolError> {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return Ok(None); // scp-style and local remotes use ssh keys / the filesystem
+        };
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Ok(None);
+        }
+        let Some(cred) = self
+            .config
+            .credentials
+            .iter()
+            .find(|c| parsed.host_str() == Some(c.host.as_str()))
+        else {
+            return Ok(None);
+        };
+        let token = state.secrets.get(&cred.secret).ok_or_else(|| {
+            ToolError::ExecutionFailed(format!("Secret '{}' for {} is not set", cred.secret, cred.host))
+        })?;
+        Ok(Some((cred.username.clone(), token)))
+    }
+
+    /// Runs a git command that talks to `url`, with credentials and timeout.
+    async fn run(
+        &self,
+        cwd: &Path,
+        args: &[&str],
+        url: &str,
+        state: &RuntimeState,
+    ) -> Result<ProcessOutput, ToolError> {
+        self.check(url)?;
+
+        let mut env = vec![("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())];
+        let mut full_args: Vec<&str> = Vec::new();
+        let credentials = self.credentials(url, state)?;
+        if let Some((username, token)) = &credentials {
+            full_args.extend(["-c", "credential.helper=", "-c", CREDENTIAL_HELPER]);
+            env.push(("AEGIS_GIT_USERNAME".to_string(), username.clone()));
+            env.push(("AEGIS_GIT_PASSWORD".to_string(), token.clone()));
+        }
+        full_args.extend(args);
+
+        let mut output = ProcessManager::with_timeout(self.config.timeout_secs)
+            .run(ProcessRequest::new("git", &full_args).cwd(cwd).envs(env), |_, _| {})
+            .await?;
+
+        // Never echo the token back, even if git or a hook prints it
+        if let Some((_, token)) = credentials.filter(|(_, token)| !token.is_empty()) {
+            output.stdout = output.stdout.replace(&token, "***");
+            output.stderr = output.stderr.replace(&token, "***");
+        }
+        Ok(output)
+    }
+}
+
+/// Removes `user:password@` from a URL.
+fn strip_userinfo(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) if !parsed.username().is_empty() || parsed.password().is_some() => {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Runs a local (non-network) git command and returns trimmed stdout.
+async fn git_local(cwd: &Path, args: &[&str]) -> Result<String, ToolError> {
+    let output = ProcessManager::new()
+        .run(ProcessRequest::new("git", args).cwd(cwd), |_, _| {})
+        .await?;
+    if !output.success {
+        return Err(ToolError::ExecutionFailed(format!("Git error: {}", output.stderr.trim())));
+    }
+    Ok(output.stdout.trim().to_string())
+}
+
+/// URL configured for a remote.
+async fn remote_url(cwd: &Path, remote: &str, push: bool) -> Result<String, ToolError> {
+    let args: &[&str] = if push {
+        &["remote", "get-url", "--push", remote]
+    } else {
+        &["remote", "get-url", remote]
+    };
+    git_local(cwd, args)
+        .await
+        .map_err(|_| ToolError::InvalidInput(format!("Unknown remote: {}", remote)))
+}
+
+/// Commits the current branch is ahead of / behind its upstream, if it has one.
+async fn ahead_behind(cwd: &Path) -> Value {
+    let Ok(counts) = git_local(cwd, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"]).await else {
+        return json!({ "upstream": null, "ahead": null, "behind": null });
+    };
+    let upstream = git_local(cwd, &["rev-parse", "--abbrev-ref", "@{upstream}"]).await.ok();
+    let mut parts = counts.split_whitespace().map(|n| n.parse::<u64>().ok());
+    json!({
+        "upstream": upstream,
+        "ahead": parts.next().flatten(),
+        "behind": parts.next().flatten()
+    })
+}
+
+/// Files with unresolved merge conflicts.
+async fn conflicted_files(cwd: &Path) -> Vec<String> {
+    git_local(cwd, &["diff", "--name-only", "--diff-filter=U"])
+        .await
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Parses ref updates from `git fetch`/`git pull` progress output.
+fn parse_fetch_updates(stderr: &str) -> Vec<Value> {
+    let re = Regex::new(r"^\s*([ +\-*!=t])\s+(\[[^\]]+\]|\S+)\s+(\S+)\s+->\s+(\S+)").unwrap();
+    stderr
+        .lines()
+        .filter_map(|line| re.captures(line))
+        .map(|caps| {
+            json!({
+                "status": ref_status(caps[1].chars().next().unwrap_or(' ')),
+                "summary": caps[2].trim_matches(['[', ']']),
+                "from": &caps[3],
+                "to": &caps[4]
+            })
+        })
+        .collect()
+}
+
+/// Parses `git push --porcelain` output.
+fn parse_push_refs(stdout: &str) -> Vec<Value> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let flag = fields.next()?;
+            let refs = fields.next()?;
+            let summary = fields.next().unwrap_or("");
+            let (from, to) = refs.split_once(':')?;
+            Some(json!({
+                "status": ref_status(flag.chars().next().unwrap_or(' ')),
+                "from": from,
+                "to": to,
+                "summary": summary
+            }))
+        })
+        .collect()
+}
+
+fn ref_status(flag: char) -> &'static str {
+    match flag {
+        ' ' => "fast-forward",
+        '+' => "forced",
+        '-' => "deleted",
+        '*' => "new",
+        '!' => "rejected",
+        '=' => "up-to-date",
+        't' => "tag-updated",
+        _ => "unknown",
+    }
+}
+
+/// Tool to fetch from a remote.
+#[derive(Debug)]
+pub struct GitFetchTool {
+    remotes: Arc<GitRemotes>,
+}
+
+impl GitFetchTool {
+    /// Creates the tool with the shared remote policy.
+    pub fn new(remotes: Arc<GitRemotes>) -> Self {
+        Self { remotes }
+    }
+}
+
+#[async_trait]
+impl Tool for GitFetchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git.fetch".to_string(),
+            description: Some(
+                "Fetches from an allowed remote and reports updated refs and ahead/behind counts.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Repository path"
+                    },
+                    "remote": {
+                        "type": "string",
+                        "description": "Remote name (default: origin)"
+                    },
+                    "prune": {
+                        "type": "boolean",
+                        "description": "Remove remote-tracking refs that no longer exist"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let path = Path::new(repo_path(&arguments)?);
+        let remote = arguments.get("remote").and_then(|v| v.as_str()).unwrap_or("origin");
+
+        let url = remote_url(path, remote, false).await?;
+        let mut args = vec!["fetch", remote];
+        if arguments.get("prune").and_then(|v| v.as_bool()).unwrap_or(false) {
+            args.push("--prune");
+        }
+
+        let output = self.remotes.run(path, &args, &url, &state).await?;
+        if !output.success {
+            return Err(ToolError::ExecutionFailed(format!("Git fetch error: {}", output.stderr.trim())));
+        }
+
+        let mut result = ahead_behind(path).await;
+        result["success"] = json!(true);
+        result["remote"] = json!(remote);
+        result["updated_refs"] = json!(parse_fetch_updates(&output.stderr));
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to pull from a remote.
+#[derive(Debug)]
+pub struct GitPullTool {
+    remotes: Arc<GitRemotes>,
+}
+
+impl GitPullTool {
+    /// Creates the tool with the shared remote policy.
+    pub fn new(remotes: Arc<GitRemotes>) -> Self {
+        Self { remotes }
+    }
+}
+
+#[async_trait]
+impl Tool for GitPullTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git.pull".to_string(),
+            description: Some(
+                "Pulls from an allowed remote. Reports whether it fast-forwarded, and lists conflicted files if the merge stopped."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Repository path"
+                    },
+                    "remote": {
+                        "type": "string",
+                        "description": "Remote name (default: origin)"
+                    },
+                    "branch": {
+                        "type": "string",
+                        "description": "Remote branch (default: the upstream of the current branch)"
+                    },
+                    "rebase": {
+                        "type": "boolean",
+                        "description": "Rebase instead of merging"
+                    },
+                    "ff_only": {
+                        "type": "boolean",
+                        "description": "Only fast-forward; fail if the branches diverged"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let path = Path::new(repo_path(&arguments)?);
+        let remote = arguments.get("remote").and_then(|v| v.as_str()).unwrap_or("origin");
+
+        let url = remote_url(path, remote, false).await?;
+        let before = git_local(path, &["rev-parse", "HEAD"]).await.ok();
+
+        let mut args = vec!["pull"];
+        if arguments.get("ff_only").and_then(|v| v.as_bool()).unwrap_or(false) {
+            args.push("--ff-only");
+        } else if arguments.get("rebase").and_then(|v| v.as_bool()).unwrap_or(false) {
+            args.push("--rebase");
+        } else {
+            args.push("--no-rebase");
+        }
+        args.push(remote);
+        if let Some(branch) = arguments.get("branch").and_then(|v| v.as_str()) {
+            args.push(branch);
+        }
+
+        let output = self.remotes.run(path, &args, &url, &state).await?;
+        let conflicts = conflicted_files(path).await;
+        if !output.success && conflicts.is_empty() {
+            return Err(ToolError::ExecutionFailed(format!("Git pull error: {}", output.stderr.trim())));
+        }
+
+        let after = git_local(path, &["rev-parse", "HEAD"]).await.ok();
+        let mut result = ahead_behind(path).await;
+        result["success"] = json!(output.success);
+        result["remote"] = json!(remote);
+        result["updated"] = json!(before != after);
+        result["fast_forward"] = json!(output.stdout.contains("Fast-forward"));
+        result["before"] = json!(before);
+        result["after"] = json!(after);
+        result["conflicts"] = json!(conflicts);
+        result["updated_refs"] = json!(parse_fetch_updates(&output.stderr));
+
+        let text = serde_json::to_string_pretty(&result).unwrap();
+        if output.success {
+            Ok(ToolOutput::text(text))
+        } else {
+            // Stopped on conflicts: report them so the agent can resolve and commit
+            Ok(ToolOutput::error(text))
+        }
+    }
+}
+
+/// Tool to push to a remote.
+#[derive(Debug)]
+pub struct GitPushTool {
+    remotes: Arc<GitRemotes>,
+}
+
+impl GitPushTool {
+    /// Creates the tool with the shared remote policy.
+    pub fn new(remotes: Arc<GitRemotes>) -> Self {
+        Self { remotes }
+    }
+}
+
+#[async_trait]
+impl Tool for GitPushTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git.push".to_string(),
+            description: Some(
+                "Pushes a branch to an allowed remote and reports the status of each ref (new, fast-forward, rejected, ...)."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Repository path"
+                    },
+                    "remote": {
+                        "type": "string",
+                        "description": "Remote name (default: origin)"
+                    },
+                    "branch": {
+                        "type": "string",
+                        "description": "Branch to push (default: current branch)"
+                    },
+                    "set_upstream": {
+                        "type": "boolean",
+                        "description": "Set the pushed branch as upstream"
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "description": "Force push (uses --force-with-lease)"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let path = Path::new(repo_path(&arguments)?);
+        let remote = arguments.get("remote").and_then(|v| v.as_str()).unwrap_or("origin");
+
+        let url = remote_url(path, remote, true).await?;
+        let branch = match arguments.get("branch").and_then(|v| v.as_str()) {
+            Some(branch) => branch.to_string(),
+            None => git_local(path, &["rev-parse", "--abbrev-ref", "HEAD"]).await?,
+        };
+
+        let mut args = vec!["push", "--porcelain"];
+        if arguments.get("set_upstream").and_then(|v| v.as_bool()).unwrap_or(false) {
+            args.push("--set-upstream");
+        }
+        if arguments.get("force").and_then(|v| v.as_bool()).unwrap_or(false) {
+            args.push("--force-with-lease");
+        }
+        args.push(remote);
+        args.push(&branch);
+
+        let output = self.remotes.run(path, &args, &url, &state).await?;
+        let refs = parse_push_refs(&output.stdout);
+        if !output.success && refs.is_empty() {
+            return Err(ToolError::ExecutionFailed(format!("Git push error: {}", output.stderr.trim())));
+        }
+
+        let rejected = refs.iter().any(|r| r["status"] == "rejected");
+        let mut result = ahead_behind(path).await;
+        result["success"] = json!(output.success);
+        result["remote"] = json!(remote);
+        result["branch"] = json!(branch);
+        result["rejected"] = json!(rejected);
+        result["refs"] = json!(refs);
+        if rejected {
+            result["hint"] = json!(output.stderr.trim());
+        }
+
+        let text = serde_json::to_string_pretty(&result).unwrap();
+        if output.success {
+            Ok(ToolOutput::text(text))
+        } else {
+            Ok(ToolOutput::error(text))
+        }
+    }
+}
+
+/// Tool to clone a repository.
+#[derive(Debug)]
+pub struct GitCloneTool {
+    remotes: Arc<GitRemotes>,
+}
+
+impl GitCloneTool {
+    /// Creates the tool with the shared remote policy.
+    pub fn new(remotes: Arc<GitRemotes>) -> Self {
+        Self { remotes }
+    }
+}
+
+#[async_trait]
+impl Tool for GitCloneTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git.clone".to_string(),
+            description: Some(
+                "Clones a repository from an allowed remote into a new directory within an allowed working directory."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "Repository URL"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Destination directory (must not exist yet)"
+                    },
+                    "branch": {
+                        "type": "string",
+                        "description": "Branch to check out"
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "Create a shallow clone with this many commits"
+                    }
+                },
+                "required": ["url", "path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let url = arguments
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'url'".to_string()))?;
+        let dest = arguments
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'path'".to_string()))?;
+
+        let dest = Path::new(dest);
+        if dest.exists() {
+            return Err(ToolError::InvalidInput(format!("Destination already exists: {}", dest.display())));
+        }
+        let name = dest
+            .file_name()
+            .ok_or_else(|| ToolError::InvalidInput(format!("Invalid destination: {}", dest.display())))?;
+        let parent = match dest.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let parent = resolve_working_dir(&self.remotes.allowed_clone_dirs, &parent.to_string_lossy())?;
+        roots::check(&parent)?;
+        let dest = parent.join(name);
+
+        let depth = arguments.get("depth").and_then(|v| v.as_u64()).map(|d| d.to_string());
+        let mut args = vec!["clone"];
+        if let Some(branch) = arguments.get("branch").and_then(|v| v.as_str()) {
+            args.extend(["--branch", branch]);
+        }
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        let dest_str = dest.to_string_lossy();
+        args.extend(["--", url, &dest_str]);
+
+        let output = self.remotes.run(&parent, &args, url, &state).await?;
+        if !output.success {
+            return Err(ToolError::ExecutionFailed(format!("Git clone error: {}", output.stderr.trim())));
+        }
+
+        let branch = git_local(&dest, &["rev-parse", "--abbrev-ref", "HEAD"]).await.ok();
+        let commit = git_local(&dest, &["rev-parse", "HEAD"]).await.ok();
+
+        let result = json!({
+            "success": true,
+            "url": strip_userinfo(url),
+            "path": dest,
+            "branch": branch,
+            "commit": commit,
+            "empty": commit.is_none()
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::GitCredentialConfig;
+    use crate::tools::ToolContent;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        assert!(status.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&status.stderr));
+    }
+
+    fn commit_file(repo: &Path, file: &str, content: &str) {
+        std::fs::write(repo.join(file), content).unwrap();
+        git(repo, &["add", file]);
+        git(repo, &["-c", "user.name=Test", "-c", "user.email=test@example.com", "-c", "commit.gpgsign=false",
+            "commit", "-q", "-m", file]);
+    }
+
+    fn output_json(output: &ToolOutput) -> Value {
+        match &output.content[0] {
+            ToolContent::Text { text } => serde_json::from_str(text).unwrap(),
+            _ => panic!("expected text output"),
+        }
+    }
+
+    #[test]
+    fn test_remote_allowlist_and_credentials() {
+        let mut config = Config::default();
+        config.git.allowed_remotes = vec!["https://github.com/acme/*".to_string()];
+        config.git.credentials = vec![GitCredentialConfig {
+            host: "github.com".to_string(),
+            secret: "GH_TOKEN".to_string(),
+            username: "x-access-token".to_string(),
+        }];
+        let remotes = GitRemotes::new(&config);
+
+        assert!(remotes.check("https://github.com/acme/app.git").is_ok());
+        assert!(remotes.check("https://bot:pw@github.com/acme/app.git").is_ok());
+        assert!(matches!(remotes.check("https://github.com/evil/app.git"), Err(ToolError::PermissionDenied(_))));
+
+        let state = RuntimeState::new(Config { database_path: Some(":memory:".to_string()), ..Config::default() });
+        assert!(remotes.credentials("https://github.com/acme/app.git", &state).is_err());
+        state.secrets.set("GH_TOKEN", "ghp_123", None);
+        let (user, token) = remotes.credentials("https://github.com/acme/app.git", &state).unwrap().unwrap();
+        assert_eq!((user.as_str(), token.as_str()), ("x-access-token", "ghp_123"));
+        assert!(remotes.credentials("git@github.com:acme/app.git", &state).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_ref_updates() {
+        let fetch = "From /tmp/origin\n   1a2b3c4..5d6e7f8  main       -> origin/main\n * [new branch]      feature    -> origin/feature\n";
+        let updates = parse_fetch_updates(fetch);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0]["status"], "fast-forward");
+        assert_eq!(updates[1]["status"], "new");
+        assert_eq!(updates[1]["to"], "origin/feature");
+
+        let push = "To /tmp/origin\n!\trefs/heads/main:refs/heads/main\t[rejected] (fetch first)\nDone\n";
+        let refs = parse_push_refs(push);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0]["status"], "rejected");
+        assert_eq!(refs[0]["summary"], "[rejected] (fetch first)");
+    }
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@ fn main() {
+ keep
+--- removed dashes
++new line
+ tail
+\\ No newline at end of file
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/old.rs b/renamed.rs
+similarity index 100%
+rename from old.rs
+rename to renamed.rs
+";
+        let files = parse_unified_diff(diff);
+        assert_eq!(files.len(), 3);
+
+        let lib = &files[0];
+        assert_eq!((lib.additions, lib.deletions), (1, 1));
+        assert_eq!(lib.hunks[0].section, "fn main() {");
+        let lines = &lib.hunks[0].lines;
+        assert_eq!(lines[1].kind, "remove");
+        assert_eq!(lines[1].content, "-- removed dashes");
+        assert_eq!((lines[2].old_line, lines[2].new_line), (None, Some(2)));
+        assert_eq!((lines[3].old_line, lines[3].new_line), (Some(3), Some(3)));
+        assert!(lines[3].no_newline);
+
+        assert_eq!(files[1].status, "added");
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].hunks[0].new_lines, 1);
+
+        assert_eq!(files[2].status, "renamed");
+        assert_eq!(files[2].new_path.as_deref(), Some("renamed.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_and_apply_patch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path();
+        git(repo, &["init", "-q"]);
+        commit_file(repo, "notes.txt", "one\ntwo\nthree\n");
+        std::fs::write(repo.join("notes.txt"), "one\n2\nthree\n").unwrap();
+
+        let state = Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".to_string()), ..Config::default() }));
+        let structured = output_json(
+            &GitDiffTool.execute(json!({"path": repo, "format": "structured"}), state.clone()).await.unwrap(),
+        );
+        assert_eq!(structured["files"][0]["new_path"], "notes.txt");
+        assert_eq!(structured["additions"], 1);
+
+        let patch = output_json(
+            &GitDiffTool.execute(json!({"path": repo, "format": "patch"}), state.clone()).await.unwrap(),
+        )["diff"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        git(repo, &["checkout", "-q", "--", "notes.txt"]);
+
+        let checked = output_json(
+            &GitApplyPatchTool
+                .execute(json!({"path": repo, "patch": patch, "dry_run": true}), state.clone())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(checked["success"], true);
+        assert_eq!(checked["applied"], false);
+        assert_eq!(std::fs::read_to_string(repo.join("notes.txt")).unwrap(), "one\ntwo\nthree\n");
+
+        GitApplyPatchTool.execute(json!({"path": repo, "patch": patch}), state.clone()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(repo.join("notes.txt")).unwrap(), "one\n2\nthree\n");
+
+        // Applying twice fails with a structured error
+        let again = GitApplyPatchTool.execute(json!({"path": repo, "patch": patch}), state).await.unwrap();
+        assert!(again.is_error);
+        assert!(!output_json(&again)["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clone_push_fetch_pull() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let origin = root.join("origin.git");
+        std::fs::create_dir(&origin).unwrap();
+        git(&origin, &["init", "-q", "--bare"]);
+
+        let mut config = Config { database_path: Some(":memory:".to_string()), ..Config::default() };
+        config.security.allowed_working_dirs = vec![root.clone()];
+        config.git.allowed_remotes = vec![format!("{}*", origin.display())];
+        let remotes = Arc::new(GitRemotes::new(&config));
+        let state = Arc::new(RuntimeState::new(config));
+        let origin_url = origin.to_string_lossy().to_string();
+
+        let denied = GitCloneTool::new(remotes.clone())
+            .execute(json!({"url": "https://example.com/x.git", "path": root.join("x")}), state.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(denied, ToolError::PermissionDenied(_)));
+
+        // First clone is empty; commit and push with upstream
+        let a = root.join("a");
+        let cloned = output_json(
+            &GitCloneTool::new(remotes.clone())
+                .execute(json!({"url": origin_url, "path": a}), state.clone())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(cloned["empty"], true);
+        commit_file(&a, "one.txt", "1");
+
+        let pushed = output_json(
+            &GitPushTool::new(remotes.clone())
+                .execute(json!({"path": a, "set_upstream": true}), state.clone())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(pushed["refs"][0]["status"], "new");
+        assert_eq!(pushed["ahead"], 0);
+
+        // Second clone falls behind after another push from the first
+        let b = root.join("b");
+        GitCloneTool::new(remotes.clone())
+            .execute(json!({"url": origin_url, "path": b}), state.clone())
+            .await
+            .unwrap();
+        commit_file(&a, "two.txt", "2");
+        GitPushTool::new(remotes.clone()).execute(json!({"path": a}), state.clone()).await.unwrap();
+
+        let fetched = output_json(
+            &GitFetchTool::new(remotes.clone()).execute(json!({"path": b}), state.clone()).await.unwrap(),
+        );
+        assert_eq!(fetched["behind"], 1);
+        assert_eq!(fetched["updated_refs"][0]["status"], "fast-forward");
+
+        let pulled = output_json(
+            &GitPullTool::new(remotes.clone()).execute(json!({"path": b}), state.clone()).await.unwrap(),
+        );
+        assert_eq!(pulled["fast_forward"], true);
+        assert_eq!(pulled["updated"], true);
+        assert_eq!(pulled["behind"], 0);
+        assert!(b.join("two.txt").exists());
+
+        // Diverged push is rejected with a structured result
+        commit_file(&b, "three.txt", "3");
+        commit_file(&a, "four.txt", "4");
+        GitPushTool::new(remotes.clone()).execute(json!({"path": a}), state.clone()).await.unwrap();
+        let rejected = GitPushTool::new(remotes).execute(json!({"path": b}), state).await.unwrap();
+        assert!(rejected.is_error);
+        assert_eq!(output_json(&rejected)["rejected"], true);
+    }
+}
@@ -0,0 +1,705 @@
+//! GitHub API tools: issues, pull requests and releases.
+//!
+//! Requests are authenticated with the token in the secret named by
+//! `github.token_secret` and limited to the repositories in
+//! `github.allowed_repos`.
+
+use async_trait::async_trait;
+use globset::GlobBuilder;
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::config::GithubConfig;
+use crate::core::egress::{EgressGuard, ToolCategory};
+use crate::core::{Config, RuntimeState};
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Most items a list call returns (GitHub's page size limit).
+const MAX_PER_PAGE: u32 = 100;
+
+/// Shared GitHub client and repository policy.
+#[derive(Debug)]
+pub struct GithubApi {
+    config: GithubConfig,
+    client: reqwest::Client,
+}
+
+impl GithubApi {
+    /// Creates the shared client from the server configuration.
+    pub fn new(config: &Config) -> Self {
+        let client = EgressGuard::from_config(config)
+            .client_builder(ToolCategory::Github)
+            .user_agent(&config.http_client.user_agent)
+            .timeout(Duration::from_secs(config.github.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default();
+        Self { config: config.github.clone(), client }
+    }
+
+    /// Checks that `repo` is an "owner/name" in the allowlist.
+    fn check(&self, repo: &str) -> Result<(), ToolError> {
+        let valid = repo.split_once('/').is_some_and(|(owner, name)| {
+            let part = |s: &str| {
+                !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            };
+            part(owner) && part(name) && name != "." && name != ".."
+        });
+        if !valid {
+            return Err(ToolError::InvalidInput(format!("Repository must be 'owner/name', got '{}'", repo)));
+        }
+        if is_repo_allowed(&self.config.allowed_repos, repo) {
+            Ok(())
+        } else {
+            Err(ToolError::PermissionDenied(format!("Repository not in github.allowed_repos: {}", repo)))
+        }
+    }
+
+    /// Sends a request to `path` (relative to the API URL) and returns the
+    /// JSON response.
+    async fn request(
+        &self,
+        state: &RuntimeState,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<Value>,
+    ) -> Result<Value, ToolError> {
+        let token = state.secrets.get(&self.config.token_secret).ok_or_else(|| {
+            ToolError::InvalidInput(format!("{} secret not set", self.config.token_secret))
+        })?;
+        let url = format!("{}{}", self.config.api_url.trim_end_matches('/'), path);
+        let mut request = self
+            .client
+            .request(method, &url)
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .query(query);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("GitHub request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(api_error(status.as_u16(), &body))
+        }
+    }
+}
+
+/// Whether `repo` matches one of the allowed "owner/name" globs. GitHub
+/// names are case-insensitive, and so is the match.
+pub(crate) fn is_repo_allowed(allowed: &[String], repo: &str) -> bool {
+    allowed.iter().any(|pattern| {
+        pattern == "*"
+            || GlobBuilder::new(pattern)
+                .case_insensitive(true)
+                .literal_separator(true)
+                .build()
+                .is_ok_and(|glob| glob.compile_matcher().is_match(repo))
+    })
+}
+
+/// Maps an error response to a ToolError, keeping GitHub's message and
+/// validation details.
+fn api_error(status: u16, body: &Value) -> ToolError {
+    let mut message = body.get("message").and_then(Value::as_str).unwrap_or("no message").to_string();
+    let details: Vec<String> = body
+        .get("errors")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|e| match e.get("message").and_then(Value::as_str) {
+            Some(text) => text.to_string(),
+            None => format!(
+                "{} {} {}",
+                e.get("resource").and_then(Value::as_str).unwrap_or(""),
+                e.get("field").and_then(Value::as_str).unwrap_or(""),
+                e.get("code").and_then(Value::as_str).unwrap_or("")
+            )
+            .trim()
+            .to_string(),
+        })
+        .collect();
+    if !details.is_empty() {
+        message = format!("{} ({})", message, details.join("; "));
+    }
+    match status {
+        401 => ToolError::PermissionDenied(format!("GitHub rejected the token: {}", message)),
+        403 => ToolError::PermissionDenied(format!("GitHub: {}", message)),
+        404 => ToolError::NotFound(format!("GitHub: {}", message)),
+        422 => ToolError::InvalidInput(format!("GitHub: {}", message)),
+        _ => ToolError::ExecutionFailed(format!("GitHub returned {}: {}", status, message)),
+    }
+}
+
+fn labels(item: &Value) -> Vec<&str> {
+    item["labels"].as_array().into_iter().flatten().filter_map(|l| l["name"].as_str()).collect()
+}
+
+fn issue_summary(issue: &Value) -> Value {
+    json!({
+        "number": issue["number"],
+        "title": issue["title"],
+        "state": issue["state"],
+        "author": issue["user"]["login"],
+        "labels": labels(issue),
+        "assignees": issue["assignees"].as_array().into_iter().flatten().filter_map(|a| a["login"].as_str()).collect::<Vec<_>>(),
+        "comments": issue["comments"],
+        "url": issue["html_url"],
+        "created_at": issue["created_at"],
+        "updated_at": issue["updated_at"],
+    })
+}
+
+fn pr_summary(pr: &Value) -> Value {
+    json!({
+        "number": pr["number"],
+        "title": pr["title"],
+        "state": pr["state"],
+        "draft": pr["draft"],
+        "merged": !pr["merged_at"].is_null(),
+        "author": pr["user"]["login"],
+        "head": pr["head"]["ref"],
+        "base": pr["base"]["ref"],
+        "labels": labels(pr),
+        "url": pr["html_url"],
+        "created_at": pr["created_at"],
+        "updated_at": pr["updated_at"],
+    })
+}
+
+/// One of "open", "closed" or "all".
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemState {
+    #[default]
+    Open,
+    Closed,
+    All,
+}
+
+impl ItemState {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+            Self::All => "all",
+        }
+    }
+}
+
+// ============================================================================
+// Issue Create Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IssueCreateArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// Issue title
+    title: String,
+    /// Issue body (Markdown)
+    #[serde(default)]
+    body: Option<String>,
+    /// Label names
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Logins to assign
+    #[serde(default)]
+    assignees: Vec<String>,
+}
+
+/// Tool to open an issue.
+#[derive(Debug)]
+pub struct GithubIssueCreateTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubIssueCreateTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubIssueCreateTool {
+    type Args = IssueCreateArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.issue_create";
+    const DESCRIPTION: &'static str = "Opens an issue in a GitHub repository and returns its number and URL.";
+
+    async fn run(&self, args: IssueCreateArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        let issue = self
+            .github
+            .request(
+                &state,
+                Method::POST,
+                &format!("/repos/{}/issues", args.repo),
+                &[],
+                Some(json!({
+                    "title": args.title,
+                    "body": args.body,
+                    "labels": args.labels,
+                    "assignees": args.assignees,
+                })),
+            )
+            .await?;
+        Ok(ToolOutput::structured(issue_summary(&issue)))
+    }
+}
+
+// ============================================================================
+// Issue List Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IssueListArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// "open" (default), "closed" or "all"
+    #[serde(default)]
+    state: ItemState,
+    /// Only issues with all of these labels
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Only issues assigned to this login ("none" for unassigned)
+    #[serde(default)]
+    assignee: Option<String>,
+    /// Most issues to return (default: 30, max: 100)
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+/// Tool to list issues (pull requests excluded).
+#[derive(Debug)]
+pub struct GithubIssueListTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubIssueListTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubIssueListTool {
+    type Args = IssueListArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.issue_list";
+    const DESCRIPTION: &'static str =
+        "Lists issues of a GitHub repository, most recently updated first, filtered by state, labels or assignee.";
+
+    async fn run(&self, args: IssueListArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        let mut query = vec![
+            ("state", args.state.as_str().to_string()),
+            ("sort", "updated".to_string()),
+            ("per_page", args.limit.unwrap_or(30).clamp(1, MAX_PER_PAGE).to_string()),
+        ];
+        if !args.labels.is_empty() {
+            query.push(("labels", args.labels.join(",")));
+        }
+        if let Some(assignee) = args.assignee {
+            query.push(("assignee", assignee));
+        }
+        let items = self
+            .github
+            .request(&state, Method::GET, &format!("/repos/{}/issues", args.repo), &query, None)
+            .await?;
+        // The issues endpoint also returns pull requests
+        let issues: Vec<Value> = items
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|item| item.get("pull_request").is_none())
+            .map(issue_summary)
+            .collect();
+        Ok(ToolOutput::structured(json!({
+            "repo": args.repo,
+            "count": issues.len(),
+            "issues": issues,
+        })))
+    }
+}
+
+// ============================================================================
+// PR Create Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PrCreateArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// Pull request title
+    title: String,
+    /// Branch with the changes ("owner:branch" for a fork)
+    head: String,
+    /// Branch to merge into
+    base: String,
+    /// Description (Markdown)
+    #[serde(default)]
+    body: Option<String>,
+    /// Open as a draft (default: false)
+    #[serde(default)]
+    draft: bool,
+}
+
+/// Tool to open a pull request.
+#[derive(Debug)]
+pub struct GithubPrCreateTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubPrCreateTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubPrCreateTool {
+    type Args = PrCreateArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.pr_create";
+    const DESCRIPTION: &'static str =
+        "Opens a pull request from a pushed branch (see git.push) and returns its number and URL.";
+
+    async fn run(&self, args: PrCreateArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        let pr = self
+            .github
+            .request(
+                &state,
+                Method::POST,
+                &format!("/repos/{}/pulls", args.repo),
+                &[],
+                Some(json!({
+                    "title": args.title,
+                    "head": args.head,
+                    "base": args.base,
+                    "body": args.body,
+                    "draft": args.draft,
+                })),
+            )
+            .await?;
+        Ok(ToolOutput::structured(pr_summary(&pr)))
+    }
+}
+
+// ============================================================================
+// PR List Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PrListArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// "open" (default), "closed" or "all"
+    #[serde(default)]
+    state: ItemState,
+    /// Only pull requests into this branch
+    #[serde(default)]
+    base: Option<String>,
+    /// Only pull requests from this branch, as "owner:branch"
+    #[serde(default)]
+    head: Option<String>,
+    /// Most pull requests to return (default: 30, max: 100)
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+/// Tool to list pull requests.
+#[derive(Debug)]
+pub struct GithubPrListTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubPrListTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubPrListTool {
+    type Args = PrListArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.pr_list";
+    const DESCRIPTION: &'static str =
+        "Lists pull requests of a GitHub repository, most recently updated first, filtered by state or branch.";
+
+    async fn run(&self, args: PrListArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        let mut query = vec![
+            ("state", args.state.as_str().to_string()),
+            ("sort", "updated".to_string()),
+            ("direction", "desc".to_string()),
+            ("per_page", args.limit.unwrap_or(30).clamp(1, MAX_PER_PAGE).to_string()),
+        ];
+        if let Some(base) = args.base {
+            query.push(("base", base));
+        }
+        if let Some(head) = args.head {
+            query.push(("head", head));
+        }
+        let items = self
+            .github
+            .request(&state, Method::GET, &format!("/repos/{}/pulls", args.repo), &query, None)
+            .await?;
+        let prs: Vec<Value> = items.as_array().into_iter().flatten().map(pr_summary).collect();
+        Ok(ToolOutput::structured(json!({
+            "repo": args.repo,
+            "count": prs.len(),
+            "pull_requests": prs,
+        })))
+    }
+}
+
+// ============================================================================
+// PR Review Comment Tool
+// ============================================================================
+
+/// What the review says about the pull request.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewEvent {
+    #[default]
+    Comment,
+    Approve,
+    RequestChanges,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LineComment {
+    /// File path in the repository
+    path: String,
+    /// Line in the file's new version (or old version with side "LEFT")
+    line: u64,
+    /// Comment text (Markdown)
+    body: String,
+    /// "RIGHT" (default, new version) or "LEFT" (old version)
+    #[serde(default)]
+    side: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PrReviewCommentArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// Pull request number
+    number: u64,
+    /// Review summary (Markdown)
+    #[serde(default)]
+    body: Option<String>,
+    /// "comment" (default), "approve" or "request_changes"
+    #[serde(default)]
+    event: ReviewEvent,
+    /// Comments on lines of the diff
+    #[serde(default)]
+    comments: Vec<LineComment>,
+}
+
+/// Tool to review a pull request.
+#[derive(Debug)]
+pub struct GithubPrReviewCommentTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubPrReviewCommentTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubPrReviewCommentTool {
+    type Args = PrReviewCommentArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.pr_review_comment";
+    const DESCRIPTION: &'static str =
+        "Submits a review on a pull request: a summary comment, optional comments on diff lines, and optionally an approval or change request.";
+
+    async fn run(&self, args: PrReviewCommentArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        if args.body.as_deref().is_none_or(str::is_empty) && args.comments.is_empty() {
+            return Err(ToolError::InvalidInput("A review needs a body or line comments".to_string()));
+        }
+        let event = match args.event {
+            ReviewEvent::Comment => "COMMENT",
+            ReviewEvent::Approve => "APPROVE",
+            ReviewEvent::RequestChanges => "REQUEST_CHANGES",
+        };
+        let comments: Vec<Value> = args
+            .comments
+            .iter()
+            .map(|c| {
+                json!({
+                    "path": c.path,
+                    "line": c.line,
+                    "side": c.side.as_deref().unwrap_or("RIGHT").to_ascii_uppercase(),
+                    "body": c.body,
+                })
+            })
+            .collect();
+        let mut payload = json!({ "event": event, "comments": comments });
+        if let Some(body) = &args.body {
+            payload["body"] = json!(body);
+        }
+        let review = self
+            .github
+            .request(
+                &state,
+                Method::POST,
+                &format!("/repos/{}/pulls/{}/reviews", args.repo, args.number),
+                &[],
+                Some(payload),
+            )
+            .await?;
+        Ok(ToolOutput::structured(json!({
+            "id": review["id"],
+            "state": review["state"],
+            "comments": comments.len(),
+            "url": review["html_url"],
+        })))
+    }
+}
+
+// ============================================================================
+// Release Create Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReleaseCreateArgs {
+    /// Repository as "owner/name"
+    repo: String,
+    /// Tag to release; created from `target` if it doesn't exist
+    tag: String,
+    /// Release title (default: the tag)
+    #[serde(default)]
+    name: Option<String>,
+    /// Release notes (Markdown)
+    #[serde(default)]
+    body: Option<String>,
+    /// Branch or commit SHA for a new tag (default: the default branch)
+    #[serde(default)]
+    target: Option<String>,
+    /// Save as a draft (default: false)
+    #[serde(default)]
+    draft: bool,
+    /// Mark as a pre-release (default: false)
+    #[serde(default)]
+    prerelease: bool,
+    /// Let GitHub generate notes from merged pull requests (default: false)
+    #[serde(default)]
+    generate_notes: bool,
+}
+
+/// Tool to publish a release.
+#[derive(Debug)]
+pub struct GithubReleaseCreateTool {
+    github: Arc<GithubApi>,
+}
+
+impl GithubReleaseCreateTool {
+    pub fn new(github: Arc<GithubApi>) -> Self {
+        Self { github }
+    }
+}
+
+#[async_trait]
+impl TypedTool for GithubReleaseCreateTool {
+    type Args = ReleaseCreateArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "github.release_create";
+    const DESCRIPTION: &'static str =
+        "Creates a GitHub release for a tag, optionally as a draft or pre-release with generated notes.";
+
+    async fn run(&self, args: ReleaseCreateArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        self.github.check(&args.repo)?;
+        let mut payload = json!({
+            "tag_name": args.tag,
+            "name": args.name.as_deref().unwrap_or(&args.tag),
+            "draft": args.draft,
+            "prerelease": args.prerelease,
+            "generate_release_notes": args.generate_notes,
+        });
+        if let Some(body) = args.body {
+            payload["body"] = json!(body);
+        }
+        if let Some(target) = args.target {
+            payload["target_commitish"] = json!(target);
+        }
+        let release = self
+            .github
+            .request(&state, Method::POST, &format!("/repos/{}/releases", args.repo), &[], Some(payload))
+            .await?;
+        Ok(ToolOutput::structured(json!({
+            "id": release["id"],
+            "tag": release["tag_name"],
+            "name": release["name"],
+            "draft": release["draft"],
+            "prerelease": release["prerelease"],
+            "url": release["html_url"],
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_check() {
+        let mut config = Config::default();
+        config.github.allowed_repos = vec!["acme/*".to_string(), "octo/hello-world".to_string()];
+        let github = GithubApi::new(&config);
+        assert!(github.check("acme/api").is_ok());
+        assert!(github.check("Octo/Hello-World").is_ok());
+        assert!(matches!(github.check("octo/other"), Err(ToolError::PermissionDenied(_))));
+        assert!(matches!(github.check("acme/api/../x"), Err(ToolError::InvalidInput(_))));
+        assert!(matches!(github.check("acme"), Err(ToolError::InvalidInput(_))));
+        assert!(matches!(github.check("acme/.."), Err(ToolError::InvalidInput(_))));
+        assert!(!is_repo_allowed(&[], "acme/api"));
+    }
+
+    #[test]
+    fn test_api_error() {
+        let body = json!({
+            "message": "Validation Failed",
+            "errors": [{"resource": "PullRequest", "code": "custom", "message": "No commits between main and fix"}]
+        });
+        match api_error(422, &body) {
+            ToolError::InvalidInput(message) => assert!(message.contains("No commits between main and fix")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(api_error(404, &json!({"message": "Not Found"})), ToolError::NotFound(_)));
+        assert!(matches!(api_error(401, &Value::Null), ToolError::PermissionDenied(_)));
+    }
+
+    #[test]
+    fn test_summaries() {
+        let pr = json!({
+            "number": 7, "title": "Fix", "state": "open", "draft": false, "merged_at": null,
+            "user": {"login": "octocat"}, "head": {"ref": "fix"}, "base": {"ref": "main"},
+            "labels": [{"name": "bug"}], "html_url": "https://github.com/acme/api/pull/7"
+        });
+        let summary = pr_summary(&pr);
+        assert_eq!(summary["head"], "fix");
+        assert_eq!(summary["merged"], false);
+        assert_eq!(summary["labels"], json!(["bug"]));
+        assert_eq!(issue_summary(&pr)["author"], "octocat");
+    }
+}
@@ -0,0 +1,467 @@
+//! Kubernetes tools (`k8s` feature).
+//!
+//! Credentials come from the kubeconfig stored in the secret named by
+//! `kubernetes.kubeconfig_secret`, or from the usual kubeconfig / in-cluster
+//! service account when none is configured. Every call is checked against
+//! `kubernetes.allowed_namespaces`; cluster-scoped resources need "*".
+//! Secrets can't be read through k8s.get.
+
+use async_trait::async_trait;
+use globset::Glob;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ApiResource, DynamicObject, ListParams, LogParams, Patch, PatchParams};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::core::GroupVersionKind;
+use kube::discovery::{self, Scope};
+use kube::Client;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::config::KubernetesConfig;
+use crate::core::RuntimeState;
+use crate::tools::registry::{ToolError, ToolOutput};
+use crate::tools::typed::TypedTool;
+
+/// Field manager recorded on objects applied by k8s.apply.
+const FIELD_MANAGER: &str = "aegis";
+
+/// Most log bytes k8s.logs asks the API server for.
+const MAX_LOG_BYTES: i64 = 1024 * 1024;
+
+/// A resource kind k8s.get knows by name.
+struct KnownKind {
+    names: &'static [&'static str],
+    group: &'static str,
+    version: &'static str,
+    kind: &'static str,
+    plural: &'static str,
+    namespaced: bool,
+}
+
+const fn known(
+    names: &'static [&'static str],
+    group: &'static str,
+    version: &'static str,
+    kind: &'static str,
+    plural: &'static str,
+    namespaced: bool,
+) -> KnownKind {
+    KnownKind { names, group, version, kind, plural, namespaced }
+}
+
+const KNOWN_KINDS: &[KnownKind] = &[
+    known(&["pod", "pods", "po"], "", "v1", "Pod", "pods", true),
+    known(&["service", "services", "svc"], "", "v1", "Service", "services", true),
+    known(&["configmap", "configmaps", "cm"], "", "v1", "ConfigMap", "configmaps", true),
+    known(&["persistentvolumeclaim", "persistentvolumeclaims", "pvc"], "", "v1", "PersistentVolumeClaim", "persistentvolumeclaims", true),
+    known(&["event", "events", "ev"], "", "v1", "Event", "events", true),
+    known(&["serviceaccount", "serviceaccounts", "sa"], "", "v1", "ServiceAccount", "serviceaccounts", true),
+    known(&["namespace", "namespaces", "ns"], "", "v1", "Namespace", "namespaces", false),
+    known(&["node", "nodes", "no"], "", "v1", "Node", "nodes", false),
+    known(&["deployment", "deployments", "deploy"], "apps", "v1", "Deployment", "deployments", true),
+    known(&["statefulset", "statefulsets", "sts"], "apps", "v1", "StatefulSet", "statefulsets", true),
+    known(&["daemonset", "daemonsets", "ds"], "apps", "v1", "DaemonSet", "daemonsets", true),
+    known(&["replicaset", "replicasets", "rs"], "apps", "v1", "ReplicaSet", "replicasets", true),
+    known(&["job", "jobs"], "batch", "v1", "Job", "jobs", true),
+    known(&["cronjob", "cronjobs", "cj"], "batch", "v1", "CronJob", "cronjobs", true),
+    known(&["ingress", "ingresses", "ing"], "networking.k8s.io", "v1", "Ingress", "ingresses", true),
+    known(&["horizontalpodautoscaler", "horizontalpodautoscalers", "hpa"], "autoscaling", "v2", "HorizontalPodAutoscaler", "horizontalpodautoscalers", true),
+];
+
+/// Looks up a kind by name, plural or short name (case-insensitive).
+fn resolve_kind(name: &str) -> Result<&'static KnownKind, ToolError> {
+    let name = name.to_ascii_lowercase();
+    if matches!(name.as_str(), "secret" | "secrets") {
+        return Err(ToolError::PermissionDenied("Secrets can't be read through k8s.get".to_string()));
+    }
+    KNOWN_KINDS.iter().find(|k| k.names.contains(&name.as_str())).ok_or_else(|| {
+        let supported: Vec<&str> = KNOWN_KINDS.iter().map(|k| k.names[1]).collect();
+        ToolError::InvalidInput(format!("Unknown kind '{}'; supported: {}", name, supported.join(", ")))
+    })
+}
+
+/// Whether `namespace` matches one of the allowed patterns. Patterns are
+/// globs (`team-*`); `None` stands for cluster-scoped resources, which
+/// only "*" allows.
+pub(crate) fn is_namespace_allowed(allowed: &[String], namespace: Option<&str>) -> bool {
+    allowed.iter().any(|pattern| {
+        pattern == "*"
+            || namespace.is_some_and(|ns| {
+                Glob::new(pattern).map(|glob| glob.compile_matcher()).is_ok_and(|matcher| matcher.is_match(ns))
+            })
+    })
+}
+
+fn check_namespace(config: &KubernetesConfig, namespace: Option<&str>) -> Result<(), ToolError> {
+    if is_namespace_allowed(&config.allowed_namespaces, namespace) {
+        return Ok(());
+    }
+    Err(ToolError::PermissionDenied(match namespace {
+        Some(ns) => format!("Namespace '{}' is not in kubernetes.allowed_namespaces", ns),
+        None => "Cluster-scoped resources need \"*\" in kubernetes.allowed_namespaces".to_string(),
+    }))
+}
+
+/// Splits a multi-document YAML manifest into objects, skipping empty
+/// documents and expanding `kind: List`.
+fn parse_manifest(manifest: &str) -> Result<Vec<DynamicObject>, ToolError> {
+    let mut objects = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(manifest) {
+        let value = Value::deserialize(document)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid manifest YAML: {}", e)))?;
+        let items = match value {
+            Value::Null => continue,
+            Value::Object(ref map) if map.get("kind").and_then(Value::as_str) == Some("List") => {
+                map.get("items").and_then(Value::as_array).cloned().unwrap_or_default()
+            }
+            value => vec![value],
+        };
+        for item in items {
+            let object: DynamicObject = serde_json::from_value(item)
+                .map_err(|e| ToolError::InvalidInput(format!("Invalid object in manifest: {}", e)))?;
+            if object.types.is_none() {
+                return Err(ToolError::InvalidInput("Every object needs apiVersion and kind".to_string()));
+            }
+            if object.metadata.name.is_none() {
+                return Err(ToolError::InvalidInput("Every object needs metadata.name".to_string()));
+            }
+            objects.push(object);
+        }
+    }
+    if objects.is_empty() {
+        return Err(ToolError::InvalidInput("Manifest contains no objects".to_string()));
+    }
+    Ok(objects)
+}
+
+/// Builds a client from the configured kubeconfig.
+async fn connect(config: &KubernetesConfig, state: &RuntimeState) -> Result<Client, ToolError> {
+    let options = KubeConfigOptions { context: config.context.clone(), ..Default::default() };
+    let mut kube_config = match &config.kubeconfig_secret {
+        Some(secret) => {
+            let yaml = state.secrets.get(secret).ok_or_else(|| {
+                ToolError::ExecutionFailed(format!("Kubeconfig secret '{}' is not set", secret))
+            })?;
+            let kubeconfig = Kubeconfig::from_yaml(&yaml)
+                .map_err(|e| ToolError::ExecutionFailed(format!("Invalid kubeconfig in '{}': {}", secret, e)))?;
+            kube::Config::from_custom_kubeconfig(kubeconfig, &options).await.map_err(|e| e.to_string())
+        }
+        None if config.context.is_some() => kube::Config::from_kubeconfig(&options).await.map_err(|e| e.to_string()),
+        None => kube::Config::infer().await.map_err(|e| e.to_string()),
+    }
+    .map_err(|e| ToolError::ExecutionFailed(format!("Cannot load kubeconfig: {}", e)))?;
+    let timeout = Some(Duration::from_secs(config.timeout_secs.max(1)));
+    kube_config.connect_timeout = timeout;
+    kube_config.read_timeout = timeout;
+    kube_config.write_timeout = timeout;
+    Client::try_from(kube_config).map_err(|e| ToolError::ExecutionFailed(format!("Cannot create Kubernetes client: {}", e)))
+}
+
+fn kube_error(e: kube::Error) -> ToolError {
+    match e {
+        kube::Error::Api(response) if response.code == 404 => ToolError::NotFound(response.message),
+        kube::Error::Api(response) if response.code == 403 => ToolError::PermissionDenied(response.message),
+        kube::Error::Api(response) if response.code == 400 || response.code == 422 => {
+            ToolError::InvalidInput(response.message)
+        }
+        e => ToolError::ExecutionFailed(format!("Kubernetes: {}", e)),
+    }
+}
+
+/// Serializes an object without its managedFields, which are long and of
+/// no use to an agent.
+fn object_json(mut object: DynamicObject) -> Value {
+    object.metadata.managed_fields = None;
+    serde_json::to_value(object).unwrap_or(Value::Null)
+}
+
+// ============================================================================
+// K8s Get Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct K8sGetArgs {
+    /// Resource kind: pods, deployments, services, configmaps, statefulsets, daemonsets, jobs, cronjobs, ingresses, nodes, namespaces, events, ...
+    kind: String,
+    /// Object name; omit to list
+    #[serde(default)]
+    name: Option<String>,
+    /// Namespace (default: kubernetes.default_namespace; ignored for cluster-scoped kinds)
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Label selector when listing, e.g. "app=web,tier!=cache"
+    #[serde(default)]
+    label_selector: Option<String>,
+    /// Most objects to list (default: 100)
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+/// Tool to read Kubernetes objects.
+#[derive(Debug)]
+pub struct K8sGetTool {
+    config: KubernetesConfig,
+}
+
+impl K8sGetTool {
+    pub fn new(config: KubernetesConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TypedTool for K8sGetTool {
+    type Args = K8sGetArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "k8s.get";
+    const DESCRIPTION: &'static str =
+        "Gets one Kubernetes object or lists objects of a kind (pods, deployments, services, ...) as JSON. Namespaces are limited to kubernetes.allowed_namespaces.";
+
+    async fn run(&self, args: K8sGetArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let known = resolve_kind(&args.kind)?;
+        let namespace = known
+            .namespaced
+            .then(|| args.namespace.unwrap_or_else(|| self.config.default_namespace.clone()));
+        check_namespace(&self.config, namespace.as_deref())?;
+
+        let resource = ApiResource::from_gvk_with_plural(
+            &GroupVersionKind::gvk(known.group, known.version, known.kind),
+            known.plural,
+        );
+        let client = connect(&self.config, &state).await?;
+        let api: Api<DynamicObject> = match &namespace {
+            Some(ns) => Api::namespaced_with(client, ns, &resource),
+            None => Api::all_with(client, &resource),
+        };
+
+        if let Some(name) = args.name {
+            let object = api.get(&name).await.map_err(kube_error)?;
+            return Ok(ToolOutput::structured(object_json(object)));
+        }
+        let mut params = ListParams::default().limit(args.limit.unwrap_or(100).max(1));
+        if let Some(selector) = &args.label_selector {
+            params = params.labels(selector);
+        }
+        let list = api.list(&params).await.map_err(kube_error)?;
+        let more = list.metadata.continue_.as_deref().is_some_and(|token| !token.is_empty());
+        let items: Vec<Value> = list.items.into_iter().map(object_json).collect();
+        Ok(ToolOutput::structured(json!({
+            "kind": known.kind,
+            "namespace": namespace,
+            "count": items.len(),
+            "more": more,
+            "items": items,
+        })))
+    }
+}
+
+// ============================================================================
+// K8s Logs Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct K8sLogsArgs {
+    /// Pod name
+    pod: String,
+    /// Namespace (default: kubernetes.default_namespace)
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Container (needed when the pod has several)
+    #[serde(default)]
+    container: Option<String>,
+    /// Lines from the end of the log (default: 100)
+    #[serde(default)]
+    tail_lines: Option<i64>,
+    /// Only lines from the last N seconds
+    #[serde(default)]
+    since_seconds: Option<i64>,
+    /// Logs of the previous (crashed) container instance (default: false)
+    #[serde(default)]
+    previous: bool,
+    /// Prefix lines with timestamps (default: false)
+    #[serde(default)]
+    timestamps: bool,
+}
+
+/// Tool to read a pod's logs.
+#[derive(Debug)]
+pub struct K8sLogsTool {
+    config: KubernetesConfig,
+}
+
+impl K8sLogsTool {
+    pub fn new(config: KubernetesConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TypedTool for K8sLogsTool {
+    type Args = K8sLogsArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "k8s.logs";
+    const DESCRIPTION: &'static str =
+        "Returns the logs of a pod's container, optionally of the previous (crashed) instance.";
+
+    async fn run(&self, args: K8sLogsArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let namespace = args.namespace.unwrap_or_else(|| self.config.default_namespace.clone());
+        check_namespace(&self.config, Some(&namespace))?;
+
+        let api: Api<Pod> = Api::namespaced(connect(&self.config, &state).await?, &namespace);
+        let params = LogParams {
+            container: args.container.clone(),
+            tail_lines: Some(args.tail_lines.unwrap_or(100).max(1)),
+            since_seconds: args.since_seconds,
+            previous: args.previous,
+            timestamps: args.timestamps,
+            limit_bytes: Some(MAX_LOG_BYTES),
+            ..Default::default()
+        };
+        let logs = api.logs(&args.pod, &params).await.map_err(kube_error)?;
+        Ok(ToolOutput::structured(json!({
+            "pod": args.pod,
+            "namespace": namespace,
+            "container": args.container,
+            "lines": logs.lines().count(),
+            "logs": logs,
+        })))
+    }
+}
+
+// ============================================================================
+// K8s Apply Tool
+// ============================================================================
+
+#[derive(Deserialize, JsonSchema)]
+pub struct K8sApplyArgs {
+    /// YAML manifest; several documents separated by '---' are applied in order
+    manifest: String,
+    /// Namespace for objects that don't set one (default: kubernetes.default_namespace)
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Validate on the server without persisting anything (default: false)
+    #[serde(default)]
+    dry_run: bool,
+    /// Take over fields owned by other managers (default: false)
+    #[serde(default)]
+    force: bool,
+}
+
+/// Tool to apply manifests with server-side apply.
+#[derive(Debug)]
+pub struct K8sApplyTool {
+    config: KubernetesConfig,
+}
+
+impl K8sApplyTool {
+    pub fn new(config: KubernetesConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TypedTool for K8sApplyTool {
+    type Args = K8sApplyArgs;
+    type Output = ToolOutput;
+    const NAME: &'static str = "k8s.apply";
+    const DESCRIPTION: &'static str =
+        "Applies a YAML manifest with server-side apply, like 'kubectl apply --server-side'. Use dry_run to validate first. Every object must be in an allowed namespace.";
+
+    async fn run(&self, args: K8sApplyArgs, state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+        let objects = parse_manifest(&args.manifest)?;
+        let default_namespace = args.namespace.unwrap_or_else(|| self.config.default_namespace.clone());
+        let client = connect(&self.config, &state).await?;
+
+        // Resolve and check every object before changing anything
+        let mut planned = Vec::with_capacity(objects.len());
+        for mut object in objects {
+            let types = object.types.clone().unwrap_or_default();
+            let (group, version) = types.api_version.rsplit_once('/').unwrap_or(("", &types.api_version));
+            let gvk = GroupVersionKind::gvk(group, version, &types.kind);
+            let (resource, capabilities) = discovery::pinned_kind(&client, &gvk).await.map_err(|e| match e {
+                kube::Error::Discovery(_) => {
+                    ToolError::InvalidInput(format!("Unknown kind {} {}", types.api_version, types.kind))
+                }
+                e => kube_error(e),
+            })?;
+            let namespace = match capabilities.scope {
+                Scope::Namespaced => {
+                    Some(object.metadata.namespace.get_or_insert_with(|| default_namespace.clone()).clone())
+                }
+                Scope::Cluster => None,
+            };
+            check_namespace(&self.config, namespace.as_deref())?;
+            planned.push((object, resource, namespace));
+        }
+
+        let mut params = PatchParams::apply(FIELD_MANAGER);
+        params.dry_run = args.dry_run;
+        params.force = args.force;
+        let mut applied = Vec::with_capacity(planned.len());
+        for (object, resource, namespace) in planned {
+            let api: Api<DynamicObject> = match &namespace {
+                Some(ns) => Api::namespaced_with(client.clone(), ns, &resource),
+                None => Api::all_with(client.clone(), &resource),
+            };
+            let name = object.metadata.name.clone().unwrap_or_default();
+            let result = api.patch(&name, &params, &Patch::Apply(&object)).await.map_err(kube_error)?;
+            applied.push(json!({
+                "kind": resource.kind,
+                "name": name,
+                "namespace": namespace,
+                "uid": result.metadata.uid,
+                "resource_version": result.metadata.resource_version,
+                "generation": result.metadata.generation,
+            }));
+        }
+        Ok(ToolOutput::structured(json!({
+            "dry_run": args.dry_run,
+            "count": applied.len(),
+            "applied": applied,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_kind() {
+        assert_eq!(resolve_kind("Deploy").unwrap().kind, "Deployment");
+        assert_eq!(resolve_kind("pods").unwrap().plural, "pods");
+        assert!(!resolve_kind("nodes").unwrap().namespaced);
+        assert!(matches!(resolve_kind("secrets"), Err(ToolError::PermissionDenied(_))));
+        assert!(matches!(resolve_kind("widgets"), Err(ToolError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_namespace_allowlist() {
+        let allowed = vec!["staging".to_string(), "team-*".to_string()];
+        assert!(is_namespace_allowed(&allowed, Some("staging")));
+        assert!(is_namespace_allowed(&allowed, Some("team-web")));
+        assert!(!is_namespace_allowed(&allowed, Some("production")));
+        assert!(!is_namespace_allowed(&allowed, None));
+        assert!(!is_namespace_allowed(&[], Some("default")));
+        assert!(is_namespace_allowed(&["*".to_string()], None));
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let objects = parse_manifest(
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: settings\ndata:\n  a: '1'\n---\n---\napiVersion: v1\nkind: List\nitems:\n- apiVersion: apps/v1\n  kind: Deployment\n  metadata:\n    name: web\n    namespace: staging\n",
+        )
+        .unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].data["data"]["a"], "1");
+        assert_eq!(objects[1].types.as_ref().unwrap().api_version, "apps/v1");
+        assert_eq!(objects[1].metadata.namespace.as_deref(), Some("staging"));
+
+        assert!(parse_manifest("kind: ConfigMap\nmetadata:\n  name: x\n").is_err());
+        assert!(parse_manifest("apiVersion: v1\nkind: ConfigMap\n").is_err());
+        assert!(parse_manifest("---\n").is_err());
+    }
+}
@@ -1,85 +1,91 @@
 //! LLM integration tools for AI providers.
+//!
+//! Providers implement [`LlmProvider`]. `llm.openai` and `llm.anthropic` call
+//! a single provider; `llm.chat` walks the providers configured under
+//! `llm.providers` and falls back to the next one when a provider fails or is
+//! rate limited. OpenAI-compatible servers (vLLM, LM Studio, llama.cpp, ...)
+//! are plain OpenAI providers with a `base_url`; Ollama has its own provider
+//! and tools in [`super::ollama`]. `llm.embed` calls OpenAI, or runs a local
+//! model from [`crate::embeddings`] when `llm.embeddings.backend` is "local".
+//!
+//! Chat tools can stream (chunks are reported through
+//! [`crate::tools::stream`]) and pass tool definitions to the model, returning
+//! any tool calls it makes in a structured `tool_calls` field. Aegis tools can
+//! be offered by name; their calls are mapped back to the Aegis tool name.
+//!
+//! OpenAI messages can carry images as `image` content parts, read from an
+//! allowed file, given inline as base64, or fetched by the API from a URL.
 
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
+use crate::core::config::{Config, EmbeddingBackend, EmbeddingsConfig, LlmProviderConfig, LlmProviderKind};
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
 use crate::core::RuntimeState;
+use crate::embeddings::{EmbeddingError, LocalEmbedder};
 use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::core::{is_image, read_allowed_file};
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::stream;
 
-/// Tool to call OpenAI API.
-#[derive(Debug)]
-pub struct OpenAiChatTool;
+use super::ollama::{ollama_provider_config, OllamaProvider};
+use super::sampling::{sampling_provider_config, SamplingProvider};
 
-#[async_trait]
-impl Tool for OpenAiChatTool {
-    fn definition(&self) -> ToolDefinition {
-        ToolDefinition {
-            name: "llm.openai".to_string(),
-            description: Some(
-                "Calls OpenAI Chat API. Requires OPENAI_KEY secret or api_key parameter."
-                    .to_string(),
-            ),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "messages": {
-                        "type": "array",
-                        "description": "Array of message objects with 'role' and 'content'",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "role": {"type": "string", "enum": ["system", "user", "assistant"]},
-                                "content": {"type": "string"}
-                            }
-                        }
-                    },
-                    "prompt": {
-                        "type": "string",
-                        "description": "Simple prompt (alternative to messages array)"
-                    },
-                    "model": {
-                        "type": "string",
-                        "description": "Model to use (default: gpt-4o-mini)"
-                    },
-                    "temperature": {
-                        "type": "number",
-                        "description": "Temperature (0-2, default: 0.7)"
-                    },
-                    "max_tokens": {
-                        "type": "integer",
-                        "description": "Max tokens to generate"
-                    },
-                    "api_key": {
-                        "type": "string",
-                        "description": "API key (optional, uses OPENAI_KEY secret if not provided)"
-                    }
-                }
-            }),
-        }
-    }
+/// A tool call requested by the model.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCall {
+    /// Provider-assigned call id, echoed back with the tool result.
+    pub id: String,
+    /// Function name as the model saw it.
+    pub name: String,
+    /// Parsed arguments (the raw string if the model produced invalid JSON).
+    pub arguments: Value,
+    /// Aegis tool to run, when the function was offered by Aegis tool name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
 
-    async fn execute(
-        &self,
-        arguments: Value,
-        state: Arc<RuntimeState>,
-    ) -> Result<ToolOutput, ToolError> {
-        // Get API key from arguments or secrets
-        let api_key = arguments
-            .get("api_key")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| state.secrets.get("OPENAI_KEY"))
-            .ok_or_else(|| {
-                ToolError::InvalidInput(
-                    "No API key provided. Set OPENAI_KEY secret or pass api_key parameter."
-                        .to_string(),
-                )
-            })?;
+/// Provider-independent result of a chat completion.
+#[derive(Debug, Default, Serialize)]
+pub struct Completion {
+    pub content: String,
+    pub provider: String,
+    pub model: String,
+    pub usage: Value,
+    pub finish_reason: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    pub streamed: bool,
+}
+
+/// A provider-independent chat request.
+#[derive(Debug, Clone, Default)]
+pub struct ChatRequest {
+    /// Conversation so far, in the provider's message format.
+    pub messages: Vec<Value>,
+    pub system: Option<String>,
+    /// Model override; `None` uses the provider's default model.
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u64>,
+    /// Aegis tool names and/or provider-native tool definitions.
+    pub tools: Option<Value>,
+    pub tool_choice: Option<Value>,
+    pub stream: bool,
+    /// Explicit API key, taking precedence over the provider's secret.
+    pub api_key: Option<String>,
+}
 
-        // Build messages
-        let messages: Vec<Value> = if let Some(msgs) = arguments.get("messages") {
+impl ChatRequest {
+    /// Builds a request from chat tool arguments.
+    fn from_arguments(arguments: &Value) -> Result<Self, ToolError> {
+        let messages = if let Some(msgs) = arguments.get("messages") {
             msgs.as_array()
                 .ok_or_else(|| ToolError::InvalidInput("messages must be an array".to_string()))?
                 .clone()
@@ -91,128 +97,893 @@ impl Tool for OpenAiChatTool {
             ));
         };
 
-        let model = arguments
-            .get("model")
-            .and_then(|v| v.as_str())
-            .unwrap_or("gpt-4o-mini");
+        let string = |key: &str| arguments.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Ok(Self {
+            messages,
+            system: string("system"),
+            model: string("model"),
+            temperature: arguments.get("temperature").and_then(|v| v.as_f64()),
+            max_tokens: arguments.get("max_tokens").and_then(|v| v.as_u64()),
+            tools: arguments.get("tools").cloned(),
+            tool_choice: arguments.get("tool_choice").cloned(),
+            stream: arguments
+                .get("stream")
+                .and_then(|v| v.as_bool())
+                .unwrap_or_else(stream::is_active),
+            api_key: string("api_key"),
+        })
+    }
+}
+
+/// Errors from an LLM provider.
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    /// The request itself is invalid; other providers would reject it too.
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// The provider is missing an API key or similar.
+    #[error("not configured: {0}")]
+    NotConfigured(String),
 
-        let temperature = arguments
-            .get("temperature")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.7);
+    /// The provider returned HTTP 429.
+    #[error("rate limited: {0}")]
+    RateLimited(String),
 
-        let max_tokens = arguments.get("max_tokens").and_then(|v| v.as_u64());
+    /// Network failure or error response.
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl From<LlmError> for ToolError {
+    fn from(e: LlmError) -> Self {
+        match e {
+            LlmError::InvalidRequest(_) | LlmError::NotConfigured(_) => ToolError::InvalidInput(e.to_string()),
+            LlmError::RateLimited(_) | LlmError::Failed(_) => ToolError::ExecutionFailed(e.to_string()),
+        }
+    }
+}
+
+/// A chat completion backend.
+#[async_trait]
+pub trait LlmProvider: Send + Sync + std::fmt::Debug {
+    /// Name used to select the provider.
+    fn name(&self) -> &str;
+
+    /// Whether the provider has what it needs (e.g. an API key) to be tried.
+    fn is_available(&self, state: &RuntimeState) -> bool;
+
+    /// Runs a chat completion.
+    async fn chat(&self, request: &ChatRequest, state: &RuntimeState) -> Result<Completion, LlmError>;
+}
+
+/// Creates the provider described by a config entry.
+pub fn provider_from_config(config: &LlmProviderConfig) -> Arc<dyn LlmProvider> {
+    match config.kind {
+        LlmProviderKind::OpenAi => Arc::new(OpenAiProvider::new(config.clone())),
+        LlmProviderKind::Anthropic => Arc::new(AnthropicProvider::new(config.clone())),
+        LlmProviderKind::Ollama => Arc::new(OllamaProvider::new(config.clone())),
+        LlmProviderKind::Sampling => Arc::new(SamplingProvider::new(config.clone())),
+    }
+}
+
+/// Providers used when `llm.providers` is empty.
+fn default_provider_configs(config: &Config) -> Vec<LlmProviderConfig> {
+    vec![
+        openai_config(),
+        anthropic_config(),
+        ollama_provider_config(&config.llm.ollama),
+        sampling_provider_config(),
+    ]
+}
+
+fn openai_config() -> LlmProviderConfig {
+    LlmProviderConfig {
+        name: "openai".to_string(),
+        kind: LlmProviderKind::OpenAi,
+        base_url: None,
+        api_key_secret: Some("OPENAI_KEY".to_string()),
+        model: None,
+        timeout_secs: 120,
+    }
+}
+
+fn anthropic_config() -> LlmProviderConfig {
+    LlmProviderConfig {
+        name: "anthropic".to_string(),
+        kind: LlmProviderKind::Anthropic,
+        base_url: None,
+        api_key_secret: Some("ANTHROPIC_KEY".to_string()),
+        model: None,
+        timeout_secs: 120,
+    }
+}
+
+/// Resolves the API key: the request's own key, else the provider's secret.
+/// `Ok(None)` means the provider needs no key.
+fn api_key(config: &LlmProviderConfig, request: &ChatRequest, state: &RuntimeState) -> Result<Option<String>, LlmError> {
+    if let Some(key) = &request.api_key {
+        return Ok(Some(key.clone()));
+    }
+    match &config.api_key_secret {
+        Some(secret) => state.secrets.get(secret).map(Some).ok_or_else(|| {
+            LlmError::NotConfigured(format!(
+                "No API key provided. Set {} secret or pass api_key parameter.",
+                secret
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// A client for requests to `base_url`, which must pass the egress guard
+/// (callers may override a provider's URL).
+pub(super) fn http_client(
+    config: &LlmProviderConfig,
+    state: &RuntimeState,
+    base_url: &str,
+) -> Result<reqwest::Client, LlmError> {
+    state.egress.check_url(ToolCategory::Llm, base_url).map_err(LlmError::InvalidRequest)?;
+    state
+        .http_clients
+        .get(&ClientProfile::new(ToolCategory::Llm).timeout(Duration::from_secs(config.timeout_secs)))
+        .map_err(|e| LlmError::Failed(format!("HTTP client error: {}", e)))
+}
+
+/// Sends a request, classifying failures for the fallback chain.
+pub(super) async fn send(request: reqwest::RequestBuilder, provider: &str) -> Result<reqwest::Response, LlmError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| LlmError::Failed(format!("HTTP error: {}", e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    let error_msg = body
+        .get("error")
+        .and_then(|e| e.get("message").or(Some(e)))
+        .and_then(|m| m.as_str())
+        .unwrap_or("Unknown error");
+    let message = format!("{} API error ({}): {}", provider, status.as_u16(), error_msg);
+    Err(if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LlmError::RateLimited(message)
+    } else {
+        LlmError::Failed(message)
+    })
+}
+
+/// Parses tool call arguments, keeping the raw string if it is not JSON.
+fn parse_arguments(raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return json!({});
+    }
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Function names providers accept: `[a-zA-Z0-9_-]`, so `fs.read_file`
+/// becomes `fs__read_file`.
+fn function_name(tool: &str) -> String {
+    tool.replace('.', "__")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Builds the provider `tools` array. Strings name Aegis tools and are
+/// converted from their definitions; objects are passed through as-is.
+/// Returns the tools and a map from function name back to Aegis tool name.
+pub(super) fn resolve_tools(
+    tools: Option<&Value>,
+    state: &RuntimeState,
+    kind: LlmProviderKind,
+) -> Result<(Vec<Value>, HashMap<String, String>), LlmError> {
+    let Some(tools) = tools else {
+        return Ok((Vec::new(), HashMap::new()));
+    };
+    let tools = tools
+        .as_array()
+        .ok_or_else(|| LlmError::InvalidRequest("tools must be an array".to_string()))?;
+
+    let mut resolved = Vec::with_capacity(tools.len());
+    let mut names = HashMap::new();
+    for tool in tools {
+        let Some(tool_name) = tool.as_str() else {
+            resolved.push(tool.clone());
+            continue;
+        };
+        let definition = state
+            .tool_registry
+            .read()
+            .get(tool_name)
+            .map(|t| t.definition())
+            .ok_or_else(|| LlmError::InvalidRequest(format!("Unknown tool in 'tools': {}", tool_name)))?;
+
+        let name = function_name(tool_name);
+        let description = definition.description.unwrap_or_default();
+        resolved.push(match kind {
+            // Sampling rejects tools before resolving them
+            LlmProviderKind::OpenAi | LlmProviderKind::Ollama | LlmProviderKind::Sampling => json!({
+                "type": "function",
+                "function": {
+                    "name": name,
+                    "description": description,
+                    "parameters": definition.input_schema
+                }
+            }),
+            LlmProviderKind::Anthropic => json!({
+                "name": name,
+                "description": description,
+                "input_schema": definition.input_schema
+            }),
+        });
+        names.insert(name, tool_name.to_string());
+    }
+    Ok((resolved, names))
+}
+
+impl Completion {
+    /// Fills in the fields the response parsers leave to the provider.
+    pub(super) fn finish(mut self, provider: &str, model: &str, streamed: bool, tool_names: &HashMap<String, String>) -> Self {
+        self.provider = provider.to_string();
+        self.model = model.to_string();
+        self.streamed = streamed;
+        if self.usage.is_null() {
+            self.usage = json!({});
+        }
+        for call in &mut self.tool_calls {
+            call.tool = tool_names.get(&call.name).cloned();
+        }
+        self
+    }
+}
+
+/// Extracts the completion from a non-streaming OpenAI response.
+fn parse_openai_response(body: &Value) -> Completion {
+    let choice = body.get("choices").and_then(|c| c.get(0));
+    let message = choice.and_then(|c| c.get("message"));
+    let tool_calls = message
+        .and_then(|m| m.get("tool_calls"))
+        .and_then(|t| t.as_array())
+        .map(|calls| {
+            calls
+                .iter()
+                .map(|call| ToolCall {
+                    id: call.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                    name: call
+                        .pointer("/function/name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: parse_arguments(
+                        call.pointer("/function/arguments").and_then(|v| v.as_str()).unwrap_or(""),
+                    ),
+                    tool: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Completion {
+        content: message
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_str())
+            .unwrap_or("")
+            .to_string(),
+        tool_calls,
+        finish_reason: choice
+            .and_then(|c| c.get("finish_reason"))
+            .and_then(|f| f.as_str())
+            .map(str::to_string),
+        usage: body.get("usage").cloned().unwrap_or(Value::Null),
+        ..Completion::default()
+    }
+}
+
+/// Assembles a streamed OpenAI completion from its `data:` events.
+#[derive(Debug, Default)]
+struct OpenAiStream {
+    completion: Completion,
+    /// (id, name, argument fragments) per tool call index.
+    calls: Vec<(String, String, String)>,
+}
+
+impl OpenAiStream {
+    fn on_data(&mut self, data: &str) {
+        if data == "[DONE]" {
+            return;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        if let Some(usage) = event.get("usage").filter(|u| !u.is_null()) {
+            self.completion.usage = usage.clone();
+        }
+        let Some(choice) = event.get("choices").and_then(|c| c.get(0)) else {
+            return;
+        };
+
+        if let Some(text) = choice.pointer("/delta/content").and_then(|c| c.as_str()) {
+            if !text.is_empty() {
+                self.completion.content.push_str(text);
+                stream::emit(json!({ "type": "text", "text": text }));
+            }
+        }
+        for delta in choice
+            .pointer("/delta/tool_calls")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let index = delta.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+            if self.calls.len() <= index {
+                self.calls.resize(index + 1, Default::default());
+            }
+            let call = &mut self.calls[index];
+            if let Some(id) = delta.get("id").and_then(|v| v.as_str()) {
+                call.0 = id.to_string();
+            }
+            if let Some(name) = delta.pointer("/function/name").and_then(|v| v.as_str()) {
+                call.1.push_str(name);
+            }
+            if let Some(args) = delta.pointer("/function/arguments").and_then(|v| v.as_str()) {
+                call.2.push_str(args);
+            }
+        }
+        if let Some(reason) = choice.get("finish_reason").and_then(|f| f.as_str()) {
+            self.completion.finish_reason = Some(reason.to_string());
+        }
+    }
+
+    fn finish(mut self) -> Completion {
+        self.completion.tool_calls = self
+            .calls
+            .into_iter()
+            .map(|(id, name, args)| ToolCall { id, name, arguments: parse_arguments(&args), tool: None })
+            .collect();
+        self.completion
+    }
+}
+
+/// Extracts the completion from a non-streaming Anthropic response.
+fn parse_anthropic_response(body: &Value) -> Completion {
+    let mut completion = Completion {
+        finish_reason: body.get("stop_reason").and_then(|s| s.as_str()).map(str::to_string),
+        usage: body.get("usage").cloned().unwrap_or(Value::Null),
+        ..Completion::default()
+    };
+    for block in body.get("content").and_then(|c| c.as_array()).into_iter().flatten() {
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => {
+                completion
+                    .content
+                    .push_str(block.get("text").and_then(|t| t.as_str()).unwrap_or(""));
+            }
+            Some("tool_use") => completion.tool_calls.push(ToolCall {
+                id: block.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                name: block.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                arguments: block.get("input").cloned().unwrap_or(json!({})),
+                tool: None,
+            }),
+            _ => {}
+        }
+    }
+    completion
+}
+
+/// Assembles a streamed Anthropic message from its events.
+#[derive(Debug, Default)]
+struct AnthropicStream {
+    completion: Completion,
+    /// (id, name, partial JSON) per tool_use content block index.
+    tool_blocks: HashMap<u64, (String, String, String)>,
+    usage: Map<String, Value>,
+}
+
+impl AnthropicStream {
+    fn on_data(&mut self, data: &str) {
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => {
+                if let Some(usage) = event.pointer("/message/usage").and_then(|u| u.as_object()) {
+                    self.usage.extend(usage.clone());
+                }
+            }
+            Some("content_block_start") => {
+                let block = event.get("content_block");
+                if block.and_then(|b| b.get("type")).and_then(|t| t.as_str()) == Some("tool_use") {
+                    let field = |name: &str| {
+                        block.and_then(|b| b.get(name)).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+                    };
+                    self.tool_blocks.insert(index, (field("id"), field("name"), String::new()));
+                }
+            }
+            Some("content_block_delta") => {
+                let delta = event.get("delta");
+                match delta.and_then(|d| d.get("type")).and_then(|t| t.as_str()) {
+                    Some("text_delta") => {
+                        let text = delta.and_then(|d| d.get("text")).and_then(|t| t.as_str()).unwrap_or("");
+                        self.completion.content.push_str(text);
+                        stream::emit(json!({ "type": "text", "text": text }));
+                    }
+                    Some("input_json_delta") => {
+                        let partial = delta
+                            .and_then(|d| d.get("partial_json"))
+                            .and_then(|p| p.as_str())
+                            .unwrap_or("");
+                        if let Some(block) = self.tool_blocks.get_mut(&index) {
+                            block.2.push_str(partial);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Some("message_delta") => {
+                if let Some(reason) = event.pointer("/delta/stop_reason").and_then(|s| s.as_str()) {
+                    self.completion.finish_reason = Some(reason.to_string());
+                }
+                if let Some(usage) = event.get("usage").and_then(|u| u.as_object()) {
+                    self.usage.extend(usage.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Completion {
+        let mut blocks: Vec<_> = self.tool_blocks.into_iter().collect();
+        blocks.sort_by_key(|(index, _)| *index);
+        self.completion.tool_calls = blocks
+            .into_iter()
+            .map(|(_, (id, name, json))| ToolCall { id, name, arguments: parse_arguments(&json), tool: None })
+            .collect();
+        self.completion.usage = Value::Object(self.usage);
+        self.completion
+    }
+}
+
+/// Feeds each line of a streamed response body to `on_line`.
+pub(super) async fn read_lines(mut response: reqwest::Response, mut on_line: impl FnMut(&str)) -> Result<(), LlmError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut handle_line = |line: &[u8]| on_line(String::from_utf8_lossy(line).trim_end());
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| LlmError::Failed(format!("Stream error: {}", e)))?
+    {
+        buffer.extend_from_slice(&chunk);
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            handle_line(&line);
+        }
+    }
+    if !buffer.is_empty() {
+        handle_line(&buffer);
+    }
+    Ok(())
+}
+
+/// Feeds the `data:` payloads of a server-sent event stream to `on_data`.
+async fn read_sse(response: reqwest::Response, mut on_data: impl FnMut(&str)) -> Result<(), LlmError> {
+    read_lines(response, |line| {
+        if let Some(data) = line.strip_prefix("data:") {
+            on_data(data.trim_start());
+        }
+    })
+    .await
+}
+
+/// OpenAI Chat Completions, or any server compatible with it.
+#[derive(Debug)]
+pub struct OpenAiProvider {
+    config: LlmProviderConfig,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: LlmProviderConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn is_available(&self, state: &RuntimeState) -> bool {
+        self.config
+            .api_key_secret
+            .as_ref()
+            .is_none_or(|secret| state.secrets.get(secret).is_some())
+    }
+
+    async fn chat(&self, request: &ChatRequest, state: &RuntimeState) -> Result<Completion, LlmError> {
+        let api_key = api_key(&self.config, request, state)?;
+        let model = request
+            .model
+            .as_deref()
+            .or(self.config.model.as_deref())
+            .unwrap_or("gpt-4o-mini");
+        let (tools, tool_names) = resolve_tools(request.tools.as_ref(), state, LlmProviderKind::OpenAi)?;
+
+        let mut messages = Vec::with_capacity(request.messages.len() + 1);
+        if let Some(system) = &request.system {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        for message in &request.messages {
+            messages.push(openai_image_parts(message, state)?);
+        }
 
         // Build request
         let mut request_body = json!({
             "model": model,
             "messages": messages,
-            "temperature": temperature
+            "temperature": request.temperature.unwrap_or(0.7)
         });
 
-        if let Some(mt) = max_tokens {
+        if let Some(mt) = request.max_tokens {
             request_body["max_tokens"] = json!(mt);
         }
+        if !tools.is_empty() {
+            request_body["tools"] = json!(tools);
+        }
+        if let Some(choice) = &request.tool_choice {
+            request_body["tool_choice"] = choice.clone();
+        }
+        if request.stream {
+            request_body["stream"] = json!(true);
+            request_body["stream_options"] = json!({ "include_usage": true });
+        }
 
         // Make request
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
+        let base_url = self
+            .config
+            .base_url
+            .as_deref()
+            .unwrap_or("https://api.openai.com/v1")
+            .trim_end_matches('/');
+        let mut http_request = http_client(&self.config, state, base_url)?
+            .post(format!("{}/chat/completions", base_url))
             .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("HTTP error: {}", e)))?;
+            .json(&request_body);
+        if let Some(key) = api_key {
+            http_request = http_request.header("Authorization", format!("Bearer {}", key));
+        }
+        let response = send(http_request, "OpenAI").await?;
 
-        let status = response.status();
-        let body: Value = response
-            .json()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to parse response: {}", e)))?;
+        let completion = if request.stream {
+            let mut assembled = OpenAiStream::default();
+            read_sse(response, |data| assembled.on_data(data)).await?;
+            assembled.finish()
+        } else {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| LlmError::Failed(format!("Failed to parse response: {}", e)))?;
+            parse_openai_response(&body)
+        };
 
-        if !status.is_success() {
-            let error_msg = body
-                .get("error")
-                .and_then(|e| e.get("message"))
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error");
-            return Err(ToolError::ExecutionFailed(format!(
-                "OpenAI API error: {}",
-                error_msg
-            )));
-        }
+        Ok(completion.finish(self.name(), model, request.stream, &tool_names))
+    }
+}
 
-        // Extract response
-        let content = body
-            .get("choices")
-            .and_then(|c| c.get(0))
-            .and_then(|c| c.get("message"))
-            .and_then(|m| m.get("content"))
-            .and_then(|c| c.as_str())
-            .unwrap_or("");
+/// Anthropic Messages API.
+#[derive(Debug)]
+pub struct AnthropicProvider {
+    config: LlmProviderConfig,
+}
 
-        let usage = body.get("usage").cloned().unwrap_or(json!({}));
+impl AnthropicProvider {
+    pub fn new(config: LlmProviderConfig) -> Self {
+        Self { config }
+    }
+}
 
-        let result = json!({
-            "content": content,
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn is_available(&self, state: &RuntimeState) -> bool {
+        self.config
+            .api_key_secret
+            .as_ref()
+            .is_none_or(|secret| state.secrets.get(secret).is_some())
+    }
+
+    async fn chat(&self, request: &ChatRequest, state: &RuntimeState) -> Result<Completion, LlmError> {
+        let api_key = api_key(&self.config, request, state)?;
+        let model = request
+            .model
+            .as_deref()
+            .or(self.config.model.as_deref())
+            .unwrap_or("claude-3-haiku-20240307");
+        let (tools, tool_names) = resolve_tools(request.tools.as_ref(), state, LlmProviderKind::Anthropic)?;
+
+        // Anthropic takes the system prompt separately; lift OpenAI-style
+        // system messages so the same messages work with either provider.
+        let mut system: Vec<String> = request.system.iter().cloned().collect();
+        let messages: Vec<Value> = request
+            .messages
+            .iter()
+            .filter(|m| {
+                if m.get("role").and_then(|r| r.as_str()) != Some("system") {
+                    return true;
+                }
+                if let Some(content) = m.get("content").and_then(|c| c.as_str()) {
+                    system.push(content.to_string());
+                }
+                false
+            })
+            .cloned()
+            .collect();
+
+        // Build request
+        let mut request_body = json!({
             "model": model,
-            "usage": usage
+            "messages": messages,
+            "max_tokens": request.max_tokens.unwrap_or(1024)
         });
 
-        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+        if !system.is_empty() {
+            request_body["system"] = json!(system.join("\n\n"));
+        }
+        if let Some(temperature) = request.temperature {
+            request_body["temperature"] = json!(temperature);
+        }
+        if !tools.is_empty() {
+            request_body["tools"] = json!(tools);
+        }
+        if let Some(choice) = &request.tool_choice {
+            request_body["tool_choice"] = choice.clone();
+        }
+        if request.stream {
+            request_body["stream"] = json!(true);
+        }
+
+        // Make request
+        let base_url = self
+            .config
+            .base_url
+            .as_deref()
+            .unwrap_or("https://api.anthropic.com/v1")
+            .trim_end_matches('/');
+        let mut http_request = http_client(&self.config, state, base_url)?
+            .post(format!("{}/messages", base_url))
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(&request_body);
+        if let Some(key) = api_key {
+            http_request = http_request.header("x-api-key", key);
+        }
+        let response = send(http_request, "Anthropic").await?;
+
+        let completion = if request.stream {
+            let mut assembled = AnthropicStream::default();
+            read_sse(response, |data| assembled.on_data(data)).await?;
+            assembled.finish()
+        } else {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| LlmError::Failed(format!("Failed to parse response: {}", e)))?;
+            parse_anthropic_response(&body)
+        };
+
+        Ok(completion.finish(self.name(), model, request.stream, &tool_names))
     }
 }
 
-/// Tool to call Anthropic Claude API.
+/// Rewrites the `image` content parts of a message into OpenAI
+/// `image_url` parts; other messages and parts pass through unchanged.
+fn openai_image_parts(message: &Value, state: &RuntimeState) -> Result<Value, LlmError> {
+    let Some(parts) = message.get("content").and_then(|c| c.as_array()) else {
+        return Ok(message.clone());
+    };
+    let parts = parts
+        .iter()
+        .map(|part| match part.get("type").and_then(|t| t.as_str()) {
+            Some("image") => image_url_part(part, state),
+            _ => Ok(part.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut message = message.clone();
+    message["content"] = Value::Array(parts);
+    Ok(message)
+}
+
+/// Builds an `image_url` part from an image part with a `path` (read from
+/// `security.allowed_read_paths`), base64 `data` and its `mime_type` (as in
+/// MCP image content), or a `url` the API fetches itself. Inline images are
+/// limited to `output_limit.max_binary_bytes`.
+fn image_url_part(part: &Value, state: &RuntimeState) -> Result<Value, LlmError> {
+    let max_bytes = state.config.output_limit.max_binary_bytes;
+    let url = if let Some(url) = part.get("url").and_then(|v| v.as_str()) {
+        url.to_string()
+    } else if let Some(path) = part.get("path").and_then(|v| v.as_str()) {
+        let file = read_allowed_file(&state.config.security.allowed_read_paths, path, max_bytes)
+            .map_err(|e| LlmError::InvalidRequest(e.to_string()))?;
+        if !is_image(file.mime_type) {
+            return Err(LlmError::InvalidRequest(format!("{} is not an image ({})", path, file.mime_type)));
+        }
+        format!("data:{};base64,{}", file.mime_type, BASE64.encode(&file.data))
+    } else if let Some(data) = part.get("data").and_then(|v| v.as_str()) {
+        let mime_type = part
+            .get("mime_type")
+            .or_else(|| part.get("mimeType"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| LlmError::InvalidRequest("An image part with 'data' needs its 'mime_type'".to_string()))?;
+        let size = BASE64
+            .decode(data)
+            .map_err(|e| LlmError::InvalidRequest(format!("Invalid base64 image data: {}", e)))?
+            .len();
+        if size > max_bytes {
+            return Err(LlmError::InvalidRequest(format!(
+                "Image is {} bytes; the limit is {} (output_limit.max_binary_bytes)",
+                size, max_bytes
+            )));
+        }
+        format!("data:{};base64,{}", mime_type, data)
+    } else {
+        return Err(LlmError::InvalidRequest("An image part needs a 'path', 'data' or 'url'".to_string()));
+    };
+
+    let mut image_url = json!({ "url": url });
+    if let Some(detail) = part.get("detail") {
+        image_url["detail"] = detail.clone();
+    }
+    Ok(json!({ "type": "image_url", "image_url": image_url }))
+}
+
+/// Runs a chat tool call against one provider.
+pub(super) async fn run_chat(provider: &dyn LlmProvider, arguments: &Value, state: &RuntimeState) -> Result<ToolOutput, ToolError> {
+    let request = ChatRequest::from_arguments(arguments)?;
+    let completion = provider.chat(&request, state).await?;
+    Ok(ToolOutput::text(serde_json::to_string_pretty(&completion).unwrap()))
+}
+
+/// Schema properties shared by the chat tools.
+pub(super) fn chat_schema_properties() -> Map<String, Value> {
+    let properties = json!({
+        "prompt": {
+            "type": "string",
+            "description": "Simple prompt (alternative to messages array)"
+        },
+        "system": {
+            "type": "string",
+            "description": "System prompt"
+        },
+        "temperature": {
+            "type": "number",
+            "description": "Temperature (0-2, default: 0.7 for OpenAI, provider default otherwise)"
+        },
+        "max_tokens": {
+            "type": "integer",
+            "description": "Max tokens to generate (default: 1024 for Anthropic)"
+        },
+        "stream": {
+            "type": "boolean",
+            "description": "Stream the completion; chunks are forwarded to clients that asked for progress (default: true when the client did)"
+        },
+        "tools": {
+            "type": "array",
+            "description": "Tools the model may call: Aegis tool names (e.g. \"fs.read_file\") or provider-native tool definitions",
+            "items": {}
+        },
+        "tool_choice": {
+            "description": "Provider-native tool_choice (e.g. \"auto\", \"required\")"
+        }
+    });
+    match properties {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// Tool to chat with any configured provider, falling back across them.
 #[derive(Debug)]
-pub struct AnthropicChatTool;
+pub struct LlmChatTool {
+    providers: Vec<Arc<dyn LlmProvider>>,
+}
+
+impl LlmChatTool {
+    /// Creates the tool with providers in fallback order.
+    pub fn new(providers: Vec<Arc<dyn LlmProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Creates the tool from `llm.providers`, or the default OpenAI,
+    /// Anthropic, and Ollama providers if none are configured.
+    pub fn from_config(config: &Config) -> Self {
+        let configs = if config.llm.providers.is_empty() {
+            default_provider_configs(config)
+        } else {
+            config.llm.providers.clone()
+        };
+        Self::new(configs.iter().map(provider_from_config).collect())
+    }
+
+    /// Providers to try, in order, for the `provider` argument.
+    fn chain(&self, selection: Option<&Value>) -> Result<Vec<Arc<dyn LlmProvider>>, ToolError> {
+        let lookup = |name: &str| {
+            self.providers
+                .iter()
+                .find(|p| p.name() == name)
+                .cloned()
+                .ok_or_else(|| {
+                    let known: Vec<&str> = self.providers.iter().map(|p| p.name()).collect();
+                    ToolError::InvalidInput(format!(
+                        "Unknown provider '{}' (configured: {})",
+                        name,
+                        known.join(", ")
+                    ))
+                })
+        };
+
+        match selection {
+            None => Ok(self.providers.clone()),
+            Some(Value::String(name)) if name == "auto" => Ok(self.providers.clone()),
+            Some(Value::String(name)) => Ok(vec![lookup(name)?]),
+            Some(Value::Array(names)) => names
+                .iter()
+                .map(|n| {
+                    n.as_str()
+                        .ok_or_else(|| ToolError::InvalidInput("provider names must be strings".to_string()))
+                        .and_then(lookup)
+                })
+                .collect(),
+            Some(_) => Err(ToolError::InvalidInput(
+                "provider must be \"auto\", a provider name, or an array of names".to_string(),
+            )),
+        }
+    }
+}
 
 #[async_trait]
-impl Tool for AnthropicChatTool {
+impl Tool for LlmChatTool {
     fn definition(&self) -> ToolDefinition {
+        let names: Vec<&str> = self.providers.iter().map(|p| p.name()).collect();
+        let mut properties = Map::new();
+        properties.insert("provider".to_string(), json!({
+            "description": format!(
+                "\"auto\" (default) tries the configured providers in order, falling back on errors and rate limits; \
+                 or a provider name, or an array of names to try in order. Configured: {}",
+                names.join(", ")
+            )
+        }));
+        properties.insert("messages".to_string(), json!({
+            "type": "array",
+            "description": "Array of message objects with 'role' and 'content'",
+            "items": {"type": "object"}
+        }));
+        properties.insert("model".to_string(), json!({
+            "type": "string",
+            "description": "Model override for the first provider tried; fallbacks use their configured model"
+        }));
+        properties.extend(chat_schema_properties());
+
         ToolDefinition {
-            name: "llm.anthropic".to_string(),
+            name: "llm.chat".to_string(),
             description: Some(
-                "Calls Anthropic Claude API. Requires ANTHROPIC_KEY secret or api_key parameter."
+                "Chats with an LLM through the configured providers (OpenAI, Anthropic, OpenAI-compatible \
+                 endpoints), falling back to the next provider when one fails or is rate limited."
                     .to_string(),
             ),
             input_schema: json!({
                 "type": "object",
-                "properties": {
-                    "messages": {
-                        "type": "array",
-                        "description": "Array of message objects with 'role' and 'content'",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "role": {"type": "string", "enum": ["user", "assistant"]},
-                                "content": {"type": "string"}
-                            }
-                        }
-                    },
-                    "prompt": {
-                        "type": "string",
-                        "description": "Simple prompt (alternative to messages array)"
-                    },
-                    "system": {
-                        "type": "string",
-                        "description": "System prompt"
-                    },
-                    "model": {
-                        "type": "string",
-                        "description": "Model to use (default: claude-3-haiku-20240307)"
-                    },
-                    "max_tokens": {
-                        "type": "integer",
-                        "description": "Max tokens to generate (default: 1024)"
-                    },
-                    "api_key": {
-                        "type": "string",
-                        "description": "API key (optional, uses ANTHROPIC_KEY secret if not provided)"
-                    }
-                }
+                "properties": properties
             }),
+            output_schema: None,
         }
     }
 
@@ -221,115 +992,224 @@ impl Tool for AnthropicChatTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        // Get API key
-        let api_key = arguments
-            .get("api_key")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| state.secrets.get("ANTHROPIC_KEY"))
-            .ok_or_else(|| {
-                ToolError::InvalidInput(
-                    "No API key provided. Set ANTHROPIC_KEY secret or pass api_key parameter."
-                        .to_string(),
-                )
-            })?;
-
-        // Build messages
-        let messages: Vec<Value> = if let Some(msgs) = arguments.get("messages") {
-            msgs.as_array()
-                .ok_or_else(|| ToolError::InvalidInput("messages must be an array".to_string()))?
-                .clone()
-        } else if let Some(prompt) = arguments.get("prompt").and_then(|v| v.as_str()) {
-            vec![json!({"role": "user", "content": prompt})]
-        } else {
+        if arguments.get("api_key").is_some() {
             return Err(ToolError::InvalidInput(
-                "Either 'messages' or 'prompt' is required".to_string(),
+                "llm.chat reads API keys from the provider configuration; use llm.openai or llm.anthropic to pass api_key"
+                    .to_string(),
             ));
-        };
+        }
 
-        let model = arguments
-            .get("model")
-            .and_then(|v| v.as_str())
-            .unwrap_or("claude-3-haiku-20240307");
+        let chain = self.chain(arguments.get("provider"))?;
+        let explicit = chain.len() == 1;
+        let mut request = ChatRequest::from_arguments(&arguments)?;
+        let mut failures = Vec::new();
 
-        let max_tokens = arguments
-            .get("max_tokens")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(1024);
+        for provider in chain {
+            if !explicit && !provider.is_available(&state) {
+                continue;
+            }
+            match provider.chat(&request, &state).await {
+                Ok(completion) => {
+                    let mut result = serde_json::to_value(&completion)
+                        .map_err(|e| ToolError::Internal(e.to_string()))?;
+                    if !failures.is_empty() {
+                        result["fallbacks"] = json!(failures);
+                    }
+                    return Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()));
+                }
+                Err(e @ LlmError::InvalidRequest(_)) => return Err(e.into()),
+                Err(e) if explicit => return Err(e.into()),
+                Err(e) => {
+                    warn!("LLM provider '{}' failed, trying next: {}", provider.name(), e);
+                    failures.push(json!({ "provider": provider.name(), "error": e.to_string() }));
+                    // The model override names a model of the first provider
+                    request.model = None;
+                }
+            }
+        }
 
-        let system = arguments.get("system").and_then(|v| v.as_str());
+        if failures.is_empty() {
+            return Err(ToolError::InvalidInput(
+                "No LLM provider is available. Configure llm.providers or set OPENAI_KEY / ANTHROPIC_KEY."
+                    .to_string(),
+            ));
+        }
+        let summary: Vec<String> = failures
+            .iter()
+            .map(|f| format!("{}: {}", f["provider"].as_str().unwrap_or(""), f["error"].as_str().unwrap_or("")))
+            .collect();
+        Err(ToolError::ExecutionFailed(format!("All LLM providers failed. {}", summary.join("; "))))
+    }
+}
 
-        // Build request
-        let mut request_body = json!({
-            "model": model,
-            "messages": messages,
-            "max_tokens": max_tokens
-        });
+/// Tool to call OpenAI API.
+#[derive(Debug)]
+pub struct OpenAiChatTool;
 
-        if let Some(sys) = system {
-            request_body["system"] = json!(sys);
+#[async_trait]
+impl Tool for OpenAiChatTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut properties = Map::new();
+        properties.insert("messages".to_string(), json!({
+            "type": "array",
+            "description": "Array of message objects with 'role' and 'content' (tool results use role 'tool' with 'tool_call_id'). \
+                            Content can be a list of parts, including images: {\"type\": \"image\"} with a 'path' to an allowed file, \
+                            base64 'data' and 'mime_type', or a 'url'",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "role": {"type": "string", "enum": ["system", "user", "assistant", "tool"]},
+                    "content": {"type": ["string", "array", "null"]}
+                }
+            }
+        }));
+        properties.insert("model".to_string(), json!({
+            "type": "string",
+            "description": "Model to use (default: gpt-4o-mini)"
+        }));
+        properties.insert("api_key".to_string(), json!({
+            "type": "string",
+            "description": "API key (optional, uses OPENAI_KEY secret if not provided)"
+        }));
+        properties.insert("base_url".to_string(), json!({
+            "type": "string",
+            "description": "OpenAI-compatible API base URL (e.g. http://localhost:11434/v1). The OPENAI_KEY secret is not sent to it; pass api_key if needed."
+        }));
+        properties.extend(chat_schema_properties());
+
+        ToolDefinition {
+            name: "llm.openai".to_string(),
+            description: Some(
+                "Calls OpenAI Chat API. Requires OPENAI_KEY secret or api_key parameter. Supports streaming and tool calls."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties
+            }),
+            output_schema: None,
         }
+    }
 
-        // Make request
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("HTTP error: {}", e)))?;
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let mut config = openai_config();
+        if let Some(base_url) = arguments.get("base_url").and_then(|v| v.as_str()) {
+            // Never send the stored key to a caller-chosen endpoint
+            config.base_url = Some(base_url.to_string());
+            config.api_key_secret = None;
+        }
+        run_chat(&OpenAiProvider::new(config), &arguments, &state).await
+    }
+}
 
-        let status = response.status();
-        let body: Value = response
-            .json()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to parse response: {}", e)))?;
+/// Tool to call Anthropic Claude API.
+#[derive(Debug)]
+pub struct AnthropicChatTool;
 
-        if !status.is_success() {
-            let error_msg = body
-                .get("error")
-                .and_then(|e| e.get("message"))
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error");
-            return Err(ToolError::ExecutionFailed(format!(
-                "Anthropic API error: {}",
-                error_msg
-            )));
+#[async_trait]
+impl Tool for AnthropicChatTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut properties = Map::new();
+        properties.insert("messages".to_string(), json!({
+            "type": "array",
+            "description": "Array of message objects with 'role' and 'content' (text or content blocks, e.g. tool_result)",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "role": {"type": "string", "enum": ["user", "assistant"]},
+                    "content": {"type": ["string", "array"]}
+                }
+            }
+        }));
+        properties.insert("model".to_string(), json!({
+            "type": "string",
+            "description": "Model to use (default: claude-3-haiku-20240307)"
+        }));
+        properties.insert("api_key".to_string(), json!({
+            "type": "string",
+            "description": "API key (optional, uses ANTHROPIC_KEY secret if not provided)"
+        }));
+        properties.extend(chat_schema_properties());
+
+        ToolDefinition {
+            name: "llm.anthropic".to_string(),
+            description: Some(
+                "Calls Anthropic Claude API. Requires ANTHROPIC_KEY secret or api_key parameter. Supports streaming and tool use."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties
+            }),
+            output_schema: None,
         }
+    }
 
-        // Extract response
-        let content = body
-            .get("content")
-            .and_then(|c| c.get(0))
-            .and_then(|c| c.get("text"))
-            .and_then(|t| t.as_str())
-            .unwrap_or("");
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        run_chat(&AnthropicProvider::new(anthropic_config()), &arguments, &state).await
+    }
+}
 
-        let usage = body.get("usage").cloned().unwrap_or(json!({}));
+/// Tool to generate embeddings, with OpenAI or a local model.
+#[derive(Debug)]
+pub struct EmbeddingsTool {
+    backend: EmbeddingBackend,
+    local: LocalEmbedder,
+}
+
+impl EmbeddingsTool {
+    pub fn new(config: EmbeddingsConfig) -> Self {
+        Self { backend: config.backend, local: LocalEmbedder::new(config) }
+    }
+
+    /// Embeds texts with the local backend.
+    async fn embed_locally(&self, texts: Vec<String>, model: Option<&str>) -> Result<ToolOutput, ToolError> {
+        let embeddings = self.local.embed(model, texts).await.map_err(|e| match e {
+            EmbeddingError::UnknownModel(_) => ToolError::InvalidInput(e.to_string()),
+            _ => ToolError::ExecutionFailed(e.to_string()),
+        })?;
 
         let result = json!({
-            "content": content,
-            "model": model,
-            "usage": usage
+            "model": embeddings.model,
+            "backend": "local",
+            "embeddings": embeddings
+                .vectors
+                .iter()
+                .enumerate()
+                .map(|(index, embedding)| json!({
+                    "index": index,
+                    "embedding": embedding,
+                    "dimensions": embedding.len()
+                }))
+                .collect::<Vec<_>>()
         });
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
 }
 
-/// Tool to generate embeddings using OpenAI.
-#[derive(Debug)]
-pub struct EmbeddingsTool;
-
 #[async_trait]
 impl Tool for EmbeddingsTool {
     fn definition(&self) -> ToolDefinition {
+        let default_backend = match self.backend {
+            EmbeddingBackend::OpenAi => "openai",
+            EmbeddingBackend::Local => "local",
+        };
         ToolDefinition {
             name: "llm.embed".to_string(),
-            description: Some("Generates text embeddings using OpenAI. Useful for semantic search.".to_string()),
+            description: Some(
+                "Generates text embeddings using OpenAI, or a local model (no API key) with backend 'local'. \
+                 Useful for semantic search."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -342,9 +1222,17 @@ impl Tool for EmbeddingsTool {
                         "items": {"type": "string"},
                         "description": "Multiple texts to embed"
                     },
+                    "backend": {
+                        "type": "string",
+                        "enum": ["openai", "local"],
+                        "description": format!("Where to compute the embeddings (default: {})", default_backend)
+                    },
                     "model": {
                         "type": "string",
-                        "description": "Model to use (default: text-embedding-3-small)"
+                        "description": format!(
+                            "Model to use (default: text-embedding-3-small, or {} for the local backend)",
+                            self.local.default_model()
+                        )
                     },
                     "api_key": {
                         "type": "string",
@@ -352,6 +1240,7 @@ impl Tool for EmbeddingsTool {
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -360,15 +1249,6 @@ impl Tool for EmbeddingsTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let api_key = arguments
-            .get("api_key")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| state.secrets.get("OPENAI_KEY"))
-            .ok_or_else(|| {
-                ToolError::InvalidInput("No API key. Set OPENAI_KEY secret.".to_string())
-            })?;
-
         let texts: Vec<String> = if let Some(text) = arguments.get("text").and_then(|v| v.as_str()) {
             vec![text.to_string()]
         } else if let Some(arr) = arguments.get("texts").and_then(|v| v.as_array()) {
@@ -379,6 +1259,27 @@ impl Tool for EmbeddingsTool {
             return Err(ToolError::InvalidInput("Either 'text' or 'texts' is required".to_string()));
         };
 
+        let backend = match arguments.get("backend").and_then(|v| v.as_str()) {
+            Some("openai") => EmbeddingBackend::OpenAi,
+            Some("local") => EmbeddingBackend::Local,
+            Some(other) => {
+                return Err(ToolError::InvalidInput(format!("Unknown backend '{}' (openai, local)", other)));
+            }
+            None => self.backend,
+        };
+        if backend == EmbeddingBackend::Local {
+            return self.embed_locally(texts, arguments.get("model").and_then(|v| v.as_str())).await;
+        }
+
+        let api_key = arguments
+            .get("api_key")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| state.secrets.get("OPENAI_KEY"))
+            .ok_or_else(|| {
+                ToolError::InvalidInput("No API key. Set OPENAI_KEY secret, or use backend 'local'.".to_string())
+            })?;
+
         let model = arguments
             .get("model")
             .and_then(|v| v.as_str())
@@ -389,7 +1290,10 @@ impl Tool for EmbeddingsTool {
             "input": texts
         });
 
-        let client = reqwest::Client::new();
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Llm))
+            .map_err(|e| ToolError::Internal(format!("HTTP client error: {}", e)))?;
         let response = client
             .post("https://api.openai.com/v1/embeddings")
             .header("Authorization", format!("Bearer {}", api_key))
@@ -432,6 +1336,7 @@ impl Tool for EmbeddingsTool {
 
         let result = json!({
             "model": model,
+            "backend": "openai",
             "embeddings": embeddings,
             "usage": body.get("usage")
         });
@@ -440,3 +1345,192 @@ impl Tool for EmbeddingsTool {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    #[test]
+    fn test_openai_stream_assembles_text_and_tool_calls() {
+        let events = [
+            r#"{"choices":[{"delta":{"role":"assistant","content":"Hel"}}]}"#,
+            r#"{"choices":[{"delta":{"content":"lo"}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"fs__read_file","arguments":"{\"pa"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"th\": \"a.txt\"}"}}]}}]}"#,
+            r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":7}}"#,
+            "[DONE]",
+        ];
+        let mut assembled = OpenAiStream::default();
+        for event in events {
+            assembled.on_data(event);
+        }
+
+        let names = HashMap::from([("fs__read_file".to_string(), "fs.read_file".to_string())]);
+        let completion = assembled.finish().finish("openai", "gpt-4o-mini", true, &names);
+        let result = serde_json::to_value(&completion).unwrap();
+        assert_eq!(result["content"], "Hello");
+        assert_eq!(result["finish_reason"], "tool_calls");
+        assert_eq!(result["usage"]["completion_tokens"], 7);
+        assert_eq!(result["tool_calls"][0]["id"], "call_1");
+        assert_eq!(result["tool_calls"][0]["arguments"]["path"], "a.txt");
+        assert_eq!(result["tool_calls"][0]["tool"], "fs.read_file");
+    }
+
+    #[test]
+    fn test_anthropic_stream_and_response() {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":12}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Checking"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"get_time","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"timezone\":"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":" \"UTC\"}"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":20}}"#,
+        ];
+        let mut assembled = AnthropicStream::default();
+        for event in events {
+            assembled.on_data(event);
+        }
+        let streamed = assembled.finish();
+        assert_eq!(streamed.content, "Checking");
+        assert_eq!(streamed.finish_reason.as_deref(), Some("tool_use"));
+        assert_eq!(streamed.usage, json!({"input_tokens": 12, "output_tokens": 20}));
+        assert_eq!(streamed.tool_calls[0].arguments, json!({"timezone": "UTC"}));
+
+        let body = json!({
+            "content": [
+                {"type": "text", "text": "Checking"},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_time", "input": {"timezone": "UTC"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 12, "output_tokens": 20}
+        });
+        let parsed = parse_anthropic_response(&body);
+        assert_eq!(parsed.content, streamed.content);
+        assert_eq!(parsed.tool_calls[0].arguments, streamed.tool_calls[0].arguments);
+    }
+
+    #[test]
+    fn test_resolve_aegis_tools() {
+        let state = RuntimeState::new(Config { database_path: Some(":memory:".to_string()), ..Config::default() });
+        let tools = json!(["fs.read_file", {"type": "function", "function": {"name": "custom"}}]);
+
+        let (openai, names) = resolve_tools(Some(&tools), &state, LlmProviderKind::OpenAi).unwrap();
+        assert_eq!(openai[0]["function"]["name"], "fs__read_file");
+        assert_eq!(openai[0]["function"]["parameters"]["type"], "object");
+        assert_eq!(openai[1]["function"]["name"], "custom");
+        assert_eq!(names["fs__read_file"], "fs.read_file");
+
+        let (anthropic, _) = resolve_tools(Some(&tools), &state, LlmProviderKind::Anthropic).unwrap();
+        assert!(anthropic[0]["input_schema"].is_object());
+
+        let unknown = json!(["no.such.tool"]);
+        assert!(resolve_tools(Some(&unknown), &state, LlmProviderKind::OpenAi).is_err());
+    }
+
+    /// Fails with a fixed error, or answers with its name.
+    #[derive(Debug)]
+    struct FakeProvider {
+        name: &'static str,
+        available: bool,
+        error: Option<fn() -> LlmError>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FakeProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn is_available(&self, _state: &RuntimeState) -> bool {
+            self.available
+        }
+
+        async fn chat(&self, request: &ChatRequest, _state: &RuntimeState) -> Result<Completion, LlmError> {
+            if let Some(error) = self.error {
+                return Err(error());
+            }
+            let model = request.model.as_deref().unwrap_or("default");
+            let completion = Completion { content: format!("hi from {}", self.name), ..Completion::default() };
+            Ok(completion.finish(self.name, model, false, &HashMap::new()))
+        }
+    }
+
+    fn fake(name: &'static str, available: bool, error: Option<fn() -> LlmError>) -> Arc<dyn LlmProvider> {
+        Arc::new(FakeProvider { name, available, error })
+    }
+
+    #[tokio::test]
+    async fn test_chat_falls_back_across_providers() {
+        let state = Arc::new(RuntimeState::new(Config { database_path: Some(":memory:".to_string()), ..Config::default() }));
+        let tool = LlmChatTool::new(vec![
+            fake("nokey", false, None),
+            fake("busy", true, Some(|| LlmError::RateLimited("429".to_string()))),
+            fake("local", true, None),
+        ]);
+        let output_json = |output: ToolOutput| match &output.content[0] {
+            crate::tools::ToolContent::Text { text } => serde_json::from_str::<Value>(text).unwrap(),
+            _ => panic!("expected text output"),
+        };
+
+        let result = output_json(tool.execute(json!({"prompt": "hi", "model": "big"}), state.clone()).await.unwrap());
+        assert_eq!(result["provider"], "local");
+        assert_eq!(result["model"], "default");
+        assert_eq!(result["fallbacks"][0]["provider"], "busy");
+        assert_eq!(result["fallbacks"].as_array().unwrap().len(), 1);
+
+        // An explicit provider is used even without a key, and not retried elsewhere
+        let err = tool.execute(json!({"prompt": "hi", "provider": "busy"}), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(_)));
+
+        let result = output_json(
+            tool.execute(json!({"prompt": "hi", "provider": ["local"], "model": "small"}), state.clone()).await.unwrap(),
+        );
+        assert_eq!(result["model"], "small");
+
+        let err = tool.execute(json!({"prompt": "hi", "provider": "nope"}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_default_chain_from_config() {
+        let tool = LlmChatTool::from_config(&Config::default());
+        let names: Vec<&str> = tool.providers.iter().map(|p| p.name()).collect();
+        assert_eq!(names, ["openai", "anthropic", "ollama", "client"]);
+    }
+
+    #[test]
+    fn test_openai_image_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("cat.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let mut config = Config::default();
+        config.security.allowed_read_paths = vec![dir.path().to_path_buf()];
+        config.output_limit.max_binary_bytes = 16;
+        let state = RuntimeState::new(config);
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+        let message = json!({"role": "user", "content": [
+            {"type": "text", "text": "What is this?"},
+            {"type": "image", "path": path("cat.png"), "detail": "low"},
+            {"type": "image", "data": "aGk=", "mimeType": "image/gif"},
+            {"type": "image", "url": "https://example.com/cat.jpg"}
+        ]});
+        let parts = openai_image_parts(&message, &state).unwrap()["content"].clone();
+        assert_eq!(parts[0]["type"], "text");
+        assert_eq!(parts[1]["image_url"]["url"], format!("data:image/png;base64,{}", BASE64.encode(b"\x89PNG\r\n\x1a\n")));
+        assert_eq!(parts[1]["image_url"]["detail"], "low");
+        assert_eq!(parts[2]["image_url"]["url"], "data:image/gif;base64,aGk=");
+        assert_eq!(parts[3], json!({"type": "image_url", "image_url": {"url": "https://example.com/cat.jpg"}}));
+
+        let plain = json!({"role": "user", "content": "hi"});
+        assert_eq!(openai_image_parts(&plain, &state).unwrap(), plain);
+
+        let image = |part: Value| openai_image_parts(&json!({"role": "user", "content": [part]}), &state);
+        assert!(image(json!({"type": "image", "path": path("notes.txt")})).is_err());
+        assert!(image(json!({"type": "image", "path": "/etc/passwd"})).is_err());
+        assert!(image(json!({"type": "image", "data": "aGk="})).is_err());
+        assert!(image(json!({"type": "image", "data": BASE64.encode([0u8; 32]), "mime_type": "image/png"})).is_err());
+    }
+}
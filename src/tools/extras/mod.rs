@@ -4,68 +4,162 @@
 //! They can be enabled via configuration or feature flags.
 //!
 //! Categories:
-//! - llm: LLM provider integrations (OpenAI, Anthropic)
+//! - llm: LLM provider integrations (OpenAI, Anthropic, OpenAI-compatible) with fallback
+//! - ollama: Local models via an Ollama server
+//! - sampling: The connected client's model via MCP sampling
+//! - usage: LLM token usage and cost reporting
+//! - transcribe: Audio transcription via Whisper-compatible APIs
 //! - vector: Vector storage and semantic search
+//! - rag: Document ingestion and retrieval over the vector store
 //! - git: Git repository operations
-//! - notify: Notifications (Slack, Discord, Email, Webhooks)
+//! - github: GitHub issues, pull requests and releases
+//! - notify: Notifications (Slack, Discord, Telegram, Teams, Email, Webhooks)
 //! - workflow: Workflow/pipeline orchestration
 //! - scheduler: Cron-like task scheduling
 //! - web: Web scraping and search
+//! - browser: Headless Chrome rendering for web.extract (`browser` feature)
+//! - docker: Docker containers (`docker` feature)
+//! - k8s: Kubernetes clusters (`k8s` feature)
+//! - code: Sandboxed Python/JavaScript snippets
 //! - conversation: Conversation history management
 //! - secrets: Secure credential storage
+//! - agent: Agent heartbeats and liveness
+//! - approval: Deciding on tool calls that wait for approval
+//! - batch: Several independent tool calls in one round trip
 
 mod llm;
+mod ollama;
+mod sampling;
+mod transcribe;
 mod vector;
+mod rag;
 mod git;
+mod github;
 mod notify;
 mod workflow;
 mod scheduler;
 mod web;
+#[cfg(feature = "browser")]
+mod browser;
+#[cfg(feature = "docker")]
+mod docker;
+#[cfg(feature = "k8s")]
+mod k8s;
+mod code;
 mod conversation;
 mod secrets;
+mod agent;
+mod usage;
+mod events;
+mod approval;
+mod batch;
 
 use std::sync::Arc;
 use tracing::info;
 use crate::tools::ToolRegistry;
 use crate::core::Config;
 
-pub use llm::{OpenAiChatTool, AnthropicChatTool, EmbeddingsTool};
-pub use vector::{VectorStoreTool, VectorSearchTool, VectorDeleteTool, VectorListTool};
-pub use git::{GitStatusTool, GitLogTool, GitDiffTool, GitCommitTool, GitBranchTool};
-pub use notify::{WebhookSendTool, SlackNotifyTool, DiscordNotifyTool, EmailNotifyTool};
-pub use workflow::{WorkflowRunTool, WorkflowDefineTool, WorkflowExecuteTool, WorkflowListTool};
-pub use scheduler::{SchedulerCreateTool, SchedulerListTool, SchedulerDeleteTool, SchedulerToggleTool, SchedulerRunTool};
-pub use web::{WebExtractTool, WebSearchTool};
-pub use conversation::{ConversationCreateTool, ConversationAddTool, ConversationGetTool, ConversationListTool, ConversationSearchTool};
-pub use secrets::{SecretsSetTool, SecretsGetTool, SecretsListTool, SecretsDeleteTool};
-
-/// Registers all extra tools with the registry.
-/// Call this only if extras are enabled in config.
-pub fn register_extra_tools(registry: &mut ToolRegistry, _config: &Config) {
+pub use llm::{
+    OpenAiChatTool, AnthropicChatTool, EmbeddingsTool, LlmChatTool, LlmProvider, LlmError, ChatRequest,
+    Completion, OpenAiProvider, AnthropicProvider, provider_from_config,
+};
+pub use ollama::{OllamaChatTool, OllamaEmbedTool, OllamaModelsTool, OllamaProvider};
+pub use sampling::{LlmSampleTool, SamplingProvider};
+pub use transcribe::TranscribeTool;
+pub use vector::{VectorStoreTool, VectorSearchTool, VectorDeleteTool, VectorListTool, VectorNamespaceCreateTool, VectorNamespaceDeleteTool, VectorNamespaceStatsTool};
+pub use rag::{RagIngestTool, RagQueryTool};
+pub use git::{
+    GitStatusTool, GitLogTool, GitDiffTool, GitApplyPatchTool, GitCommitTool, GitBranchTool,
+    GitRemotes, GitFetchTool, GitPullTool, GitPushTool, GitCloneTool,
+};
+pub use github::{
+    GithubApi, GithubIssueCreateTool, GithubIssueListTool, GithubPrCreateTool, GithubPrListTool,
+    GithubPrReviewCommentTool, GithubReleaseCreateTool,
+};
+pub use notify::{WebhookSendTool, SlackNotifyTool, DiscordNotifyTool, TelegramNotifyTool, TeamsNotifyTool, EmailNotifyTool};
+pub use workflow::{
+    WorkflowRunTool, WorkflowDefineTool, WorkflowExecuteTool, WorkflowListTool,
+    WorkflowHistoryTool, WorkflowRollbackTool, CompositeTool, mount_composite_tools, substitute_context,
+};
+pub use scheduler::{SchedulerCreateTool, SchedulerListTool, SchedulerDeleteTool, SchedulerToggleTool, SchedulerRunTool, SchedulerHistoryTool};
+pub use web::{WebExtractTool, WebCrawlTool, WebSearchTool};
+pub(crate) use conversation::{complete_conversation_ids, export_jsonl, export_markdown};
+pub use conversation::{ConversationCreateTool, ConversationAddTool, ConversationGetTool, ConversationListTool, ConversationSearchTool, ConversationPinTool, ConversationSummarizeTool, ConversationExportTool, ConversationImportTool, ConversationTagTool, ConversationUpdateTool, MessageUpdateTool, MessageDeleteTool};
+pub use secrets::{SecretsSetTool, SecretsGetTool, SecretsListTool, SecretsDeleteTool, SecretsRotateTool, SecretsVersionsTool, SecretsRollbackTool};
+pub use agent::{AgentHeartbeatTool, AgentStatusTool};
+pub use usage::LlmUsageTool;
+pub use events::{EventsSubscribeTool, EventsUnsubscribeTool, EventsListTool};
+pub use approval::{ApprovalListTool, ApprovalApproveTool, ApprovalRejectTool};
+pub use batch::BatchRunTool;
+pub use code::CodeRunTool;
+#[cfg(feature = "docker")]
+pub use docker::{DockerPsTool, DockerLogsTool, DockerRunTool, DockerStopTool};
+#[cfg(feature = "k8s")]
+pub use k8s::{K8sGetTool, K8sLogsTool, K8sApplyTool};
+
+/// Registers all extra tools with the registry and returns how many were
+/// registered. Call this only if extras are enabled in config.
+pub fn register_extra_tools(registry: &mut ToolRegistry, config: &Config) -> usize {
     info!("Loading extra tools...");
+    let before = registry.tools.len();
 
     // LLM integration tools
+    registry.register(Arc::new(LlmChatTool::from_config(config)));
     registry.register(Arc::new(OpenAiChatTool));
     registry.register(Arc::new(AnthropicChatTool));
-    registry.register(Arc::new(EmbeddingsTool));
+    registry.register(Arc::new(EmbeddingsTool::new(config.llm.embeddings.clone())));
+    registry.register(Arc::new(LlmUsageTool));
+    registry.register(Arc::new(TranscribeTool::new(config.llm.transcription.clone())));
+
+    // Local models via Ollama
+    registry.register(Arc::new(OllamaChatTool::new(config.llm.ollama.clone())));
+    registry.register(Arc::new(OllamaEmbedTool::new(config.llm.ollama.clone())));
+    registry.register(Arc::new(OllamaModelsTool::new(config.llm.ollama.clone())));
+    registry.register(Arc::new(LlmSampleTool));
 
     // Vector store tools
     registry.register(Arc::new(VectorStoreTool));
     registry.register(Arc::new(VectorSearchTool));
     registry.register(Arc::new(VectorDeleteTool));
     registry.register(Arc::new(VectorListTool));
+    registry.register(Arc::new(VectorNamespaceCreateTool));
+    registry.register(Arc::new(VectorNamespaceDeleteTool));
+    registry.register(Arc::new(VectorNamespaceStatsTool));
+
+    // RAG tools
+    registry.register(Arc::new(RagIngestTool));
+    registry.register(Arc::new(RagQueryTool));
 
     // Git tools
     registry.register(Arc::new(GitStatusTool));
     registry.register(Arc::new(GitLogTool));
     registry.register(Arc::new(GitDiffTool));
+    registry.register(Arc::new(GitApplyPatchTool));
     registry.register(Arc::new(GitCommitTool));
     registry.register(Arc::new(GitBranchTool));
 
+    // Git remote tools (restricted to git.allowed_remotes)
+    let git_remotes = Arc::new(GitRemotes::new(config));
+    registry.register(Arc::new(GitFetchTool::new(git_remotes.clone())));
+    registry.register(Arc::new(GitPullTool::new(git_remotes.clone())));
+    registry.register(Arc::new(GitPushTool::new(git_remotes.clone())));
+    registry.register(Arc::new(GitCloneTool::new(git_remotes)));
+
+    // GitHub API tools (restricted to github.allowed_repos)
+    let github = Arc::new(GithubApi::new(config));
+    registry.register(Arc::new(GithubIssueCreateTool::new(github.clone())));
+    registry.register(Arc::new(GithubIssueListTool::new(github.clone())));
+    registry.register(Arc::new(GithubPrCreateTool::new(github.clone())));
+    registry.register(Arc::new(GithubPrListTool::new(github.clone())));
+    registry.register(Arc::new(GithubPrReviewCommentTool::new(github.clone())));
+    registry.register(Arc::new(GithubReleaseCreateTool::new(github)));
+
     // Notification tools
     registry.register(Arc::new(WebhookSendTool));
     registry.register(Arc::new(SlackNotifyTool));
     registry.register(Arc::new(DiscordNotifyTool));
+    registry.register(Arc::new(TelegramNotifyTool));
+    registry.register(Arc::new(TeamsNotifyTool));
     registry.register(Arc::new(EmailNotifyTool));
 
     // Workflow tools
@@ -73,6 +167,8 @@ pub fn register_extra_tools(registry: &mut ToolRegistry, _config: &Config) {
     registry.register(Arc::new(WorkflowDefineTool));
     registry.register(Arc::new(WorkflowExecuteTool));
     registry.register(Arc::new(WorkflowListTool));
+    registry.register(Arc::new(WorkflowHistoryTool));
+    registry.register(Arc::new(WorkflowRollbackTool));
 
     // Scheduler tools
     registry.register(Arc::new(SchedulerCreateTool));
@@ -80,9 +176,11 @@ pub fn register_extra_tools(registry: &mut ToolRegistry, _config: &Config) {
     registry.register(Arc::new(SchedulerDeleteTool));
     registry.register(Arc::new(SchedulerToggleTool));
     registry.register(Arc::new(SchedulerRunTool));
+    registry.register(Arc::new(SchedulerHistoryTool));
 
     // Web tools
     registry.register(Arc::new(WebExtractTool));
+    registry.register(Arc::new(WebCrawlTool));
     registry.register(Arc::new(WebSearchTool));
 
     // Conversation tools
@@ -91,19 +189,64 @@ pub fn register_extra_tools(registry: &mut ToolRegistry, _config: &Config) {
     registry.register(Arc::new(ConversationGetTool));
     registry.register(Arc::new(ConversationListTool));
     registry.register(Arc::new(ConversationSearchTool));
+    registry.register(Arc::new(ConversationPinTool));
+    registry.register(Arc::new(ConversationSummarizeTool));
+    registry.register(Arc::new(ConversationTagTool));
+    registry.register(Arc::new(ConversationUpdateTool));
+    registry.register(Arc::new(MessageUpdateTool));
+    registry.register(Arc::new(MessageDeleteTool));
+    registry.register(Arc::new(ConversationExportTool));
+    registry.register(Arc::new(ConversationImportTool));
 
     // Secrets tools
     registry.register(Arc::new(SecretsSetTool));
     registry.register(Arc::new(SecretsGetTool));
     registry.register(Arc::new(SecretsListTool));
     registry.register(Arc::new(SecretsDeleteTool));
-
-    info!("Loaded {} extra tools", extra_tool_count());
-}
-
-/// Returns the count of extra tools.
-pub fn extra_tool_count() -> usize {
-    39 // 3 llm + 4 vector + 5 git + 4 notify + 4 workflow + 5 scheduler + 2 web + 5 conversation + 4 secrets + 3 (script plugins counted separately)
+    registry.register(Arc::new(SecretsRotateTool));
+    registry.register(Arc::new(SecretsVersionsTool));
+    registry.register(Arc::new(SecretsRollbackTool));
+
+    // Agent supervision tools
+    registry.register(Arc::new(AgentHeartbeatTool));
+    registry.register(Arc::new(AgentStatusTool));
+
+    // Event rule tools
+    registry.register(Arc::new(EventsSubscribeTool));
+    registry.register(Arc::new(EventsUnsubscribeTool));
+    registry.register(Arc::new(EventsListTool));
+
+    // Approval tools
+    registry.register(Arc::new(ApprovalListTool));
+    registry.register(Arc::new(ApprovalApproveTool));
+    registry.register(Arc::new(ApprovalRejectTool));
+
+    // Batch tool
+    registry.register(Arc::new(BatchRunTool));
+
+    // Sandboxed code execution (refuses to run unless code_run.enabled)
+    registry.register(Arc::new(CodeRunTool::new(config.code_run.clone())));
+
+    // Docker tools
+    #[cfg(feature = "docker")]
+    {
+        registry.register(Arc::new(DockerPsTool));
+        registry.register(Arc::new(DockerLogsTool));
+        registry.register(Arc::new(DockerRunTool));
+        registry.register(Arc::new(DockerStopTool));
+    }
+
+    // Kubernetes tools (restricted to kubernetes.allowed_namespaces)
+    #[cfg(feature = "k8s")]
+    {
+        registry.register(Arc::new(K8sGetTool::new(config.kubernetes.clone())));
+        registry.register(Arc::new(K8sLogsTool::new(config.kubernetes.clone())));
+        registry.register(Arc::new(K8sApplyTool::new(config.kubernetes.clone())));
+    }
+
+    let count = registry.tools.len() - before;
+    info!("Loaded {} extra tools", count);
+    count
 }
 
 
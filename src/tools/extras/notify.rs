@@ -4,10 +4,20 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
 
+/// The shared client for notification requests.
+fn client(state: &RuntimeState) -> Result<reqwest::Client, ToolError> {
+    state
+        .http_clients
+        .get(&ClientProfile::new(ToolCategory::Notify))
+        .map_err(|e| ToolError::Internal(format!("HTTP client error: {}", e)))
+}
+
 /// Tool to send a webhook notification.
 #[derive(Debug)]
 pub struct WebhookSendTool;
@@ -40,6 +50,7 @@ impl Tool for WebhookSendTool {
                 },
                 "required": ["url"]
             }),
+            output_schema: None,
         }
     }
 
@@ -69,7 +80,8 @@ impl Tool for WebhookSendTool {
             "data": data
         });
 
-        let client = reqwest::Client::new();
+        state.egress.check_url(ToolCategory::Notify, &url).map_err(ToolError::PermissionDenied)?;
+        let client = client(&state)?;
         let mut request = client
             .post(&url)
             .header("Content-Type", "application/json")
@@ -147,6 +159,7 @@ impl Tool for SlackNotifyTool {
                 },
                 "required": ["text"]
             }),
+            output_schema: None,
         }
     }
 
@@ -191,7 +204,8 @@ impl Tool for SlackNotifyTool {
             payload["blocks"] = blocks.clone();
         }
 
-        let client = reqwest::Client::new();
+        state.egress.check_url(ToolCategory::Notify, &webhook_url).map_err(ToolError::PermissionDenied)?;
+        let client = client(&state)?;
         let response = client
             .post(&webhook_url)
             .header("Content-Type", "application/json")
@@ -249,6 +263,7 @@ impl Tool for DiscordNotifyTool {
                 },
                 "required": ["content"]
             }),
+            output_schema: None,
         }
     }
 
@@ -289,7 +304,268 @@ impl Tool for DiscordNotifyTool {
             payload["embeds"] = embeds.clone();
         }
 
-        let client = reqwest::Client::new();
+        state.egress.check_url(ToolCategory::Notify, &webhook_url).map_err(ToolError::PermissionDenied)?;
+        let client = client(&state)?;
+        let response = client
+            .post(&webhook_url)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("HTTP error: {}", e)))?;
+
+        let status = response.status();
+
+        let result = json!({
+            "success": status.is_success(),
+            "status_code": status.as_u16()
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to send Telegram messages through a bot.
+#[derive(Debug)]
+pub struct TelegramNotifyTool;
+
+#[async_trait]
+impl Tool for TelegramNotifyTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "notify.telegram".to_string(),
+            description: Some(
+                "Sends a Telegram message through a bot. Requires TELEGRAM_BOT_TOKEN secret.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Message text"
+                    },
+                    "chat_id": {
+                        "type": "string",
+                        "description": "Chat ID or @channelusername (optional, uses TELEGRAM_CHAT_ID secret)"
+                    },
+                    "parse_mode": {
+                        "type": "string",
+                        "enum": ["MarkdownV2", "Markdown", "HTML"],
+                        "description": "How Telegram formats the text (default: plain text)"
+                    },
+                    "disable_notification": {
+                        "type": "boolean",
+                        "description": "Deliver silently"
+                    }
+                },
+                "required": ["text"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let token = state.secrets.get("TELEGRAM_BOT_TOKEN").ok_or_else(|| {
+            ToolError::InvalidInput("TELEGRAM_BOT_TOKEN secret not set".to_string())
+        })?;
+
+        let chat_id = arguments
+            .get("chat_id")
+            .and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .or_else(|| state.secrets.get("TELEGRAM_CHAT_ID"))
+            .ok_or_else(|| {
+                ToolError::InvalidInput(
+                    "No chat ID. Set TELEGRAM_CHAT_ID secret or pass chat_id.".to_string(),
+                )
+            })?;
+
+        let text = arguments
+            .get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'text'".to_string()))?;
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "text": text
+        });
+
+        if let Some(mode) = arguments.get("parse_mode").and_then(|v| v.as_str()) {
+            payload["parse_mode"] = json!(mode);
+        }
+
+        if let Some(silent) = arguments.get("disable_notification").and_then(|v| v.as_bool()) {
+            payload["disable_notification"] = json!(silent);
+        }
+
+        let client = client(&state)?;
+        let response = client
+            .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+            .json(&payload)
+            .send()
+            .await
+            // The URL carries the token, so keep it out of the error
+            .map_err(|e| ToolError::ExecutionFailed(format!("HTTP error: {}", e.without_url())))?;
+
+        let status = response.status();
+        let response_body: Value = response.json().await.unwrap_or(json!({}));
+
+        let result = json!({
+            "success": response_body.get("ok").and_then(|v| v.as_bool()).unwrap_or(status.is_success()),
+            "status_code": status.as_u16(),
+            "chat_id": chat_id,
+            "message_id": response_body.pointer("/result/message_id"),
+            "error": response_body.get("description")
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Builds a Microsoft Teams incoming webhook payload: a MessageCard for
+/// connector webhooks, or an Adaptive Card for Workflows webhooks.
+fn teams_payload(arguments: &Value, text: &str) -> Value {
+    let title = arguments.get("title").and_then(|v| v.as_str());
+    let facts: Vec<(String, String)> = arguments
+        .get("facts")
+        .and_then(|v| v.as_object())
+        .map(|facts| {
+            facts
+                .iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if arguments.get("format").and_then(|v| v.as_str()) == Some("adaptive_card") {
+        let mut body = vec![];
+        if let Some(title) = title {
+            body.push(json!({"type": "TextBlock", "text": title, "weight": "Bolder", "size": "Medium", "wrap": true}));
+        }
+        body.push(json!({"type": "TextBlock", "text": text, "wrap": true}));
+        if !facts.is_empty() {
+            let facts: Vec<Value> = facts.iter().map(|(name, value)| json!({"title": name, "value": value})).collect();
+            body.push(json!({"type": "FactSet", "facts": facts}));
+        }
+
+        return json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": body
+                }
+            }]
+        });
+    }
+
+    let mut payload = json!({
+        "@type": "MessageCard",
+        "@context": "https://schema.org/extensions",
+        "summary": title.unwrap_or(text),
+        "text": text
+    });
+    if let Some(title) = title {
+        payload["title"] = json!(title);
+    }
+    if let Some(color) = arguments.get("theme_color").and_then(|v| v.as_str()) {
+        payload["themeColor"] = json!(color.trim_start_matches('#'));
+    }
+    if !facts.is_empty() {
+        let facts: Vec<Value> = facts.iter().map(|(name, value)| json!({"name": name, "value": value})).collect();
+        payload["sections"] = json!([{ "facts": facts }]);
+    }
+    payload
+}
+
+/// Tool to send Microsoft Teams notifications.
+#[derive(Debug)]
+pub struct TeamsNotifyTool;
+
+#[async_trait]
+impl Tool for TeamsNotifyTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "notify.teams".to_string(),
+            description: Some(
+                "Sends a Microsoft Teams card through an incoming webhook. Requires TEAMS_WEBHOOK_URL secret.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Message text (Markdown)"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Card title"
+                    },
+                    "facts": {
+                        "type": "object",
+                        "description": "Name/value pairs shown as a table (e.g., {\"Status\": \"Failed\"})"
+                    },
+                    "theme_color": {
+                        "type": "string",
+                        "description": "Accent color as hex (e.g., 'FF0000'; message_card only)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["message_card", "adaptive_card"],
+                        "description": "Card format: message_card for connector webhooks, adaptive_card for Workflows webhooks (default: message_card)"
+                    },
+                    "webhook_url": {
+                        "type": "string",
+                        "description": "Webhook URL (optional, uses TEAMS_WEBHOOK_URL secret)"
+                    }
+                },
+                "required": ["text"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let webhook_url = arguments
+            .get("webhook_url")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| state.secrets.get("TEAMS_WEBHOOK_URL"))
+            .ok_or_else(|| {
+                ToolError::InvalidInput(
+                    "No webhook URL. Set TEAMS_WEBHOOK_URL secret or pass webhook_url.".to_string(),
+                )
+            })?;
+
+        let text = arguments
+            .get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'text'".to_string()))?;
+
+        let payload = teams_payload(&arguments, text);
+
+        state.egress.check_url(ToolCategory::Notify, &webhook_url).map_err(ToolError::PermissionDenied)?;
+        let client = client(&state)?;
         let response = client
             .post(&webhook_url)
             .header("Content-Type", "application/json")
@@ -353,6 +629,7 @@ impl Tool for EmailNotifyTool {
                 },
                 "required": ["to", "subject", "body"]
             }),
+            output_schema: None,
         }
     }
 
@@ -411,7 +688,7 @@ impl Tool for EmailNotifyTool {
                     payload["html"] = json!(h);
                 }
 
-                let client = reqwest::Client::new();
+                let client = client(&state)?;
                 let response = client
                     .post("https://api.resend.com/emails")
                     .header("Authorization", format!("Bearer {}", api_key))
@@ -453,7 +730,7 @@ impl Tool for EmailNotifyTool {
                     "content": content
                 });
 
-                let client = reqwest::Client::new();
+                let client = client(&state)?;
                 let response = client
                     .post("https://api.sendgrid.com/v3/mail/send")
                     .header("Authorization", format!("Bearer {}", api_key))
@@ -482,3 +759,25 @@ impl Tool for EmailNotifyTool {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teams_payload() {
+        let arguments = json!({"title": "Deploy", "facts": {"Env": "prod", "Attempt": 2}, "theme_color": "#FF0000"});
+        let card = teams_payload(&arguments, "Deploy failed");
+        assert_eq!(card["@type"], "MessageCard");
+        assert_eq!(card["summary"], "Deploy");
+        assert_eq!(card["themeColor"], "FF0000");
+        assert_eq!(card["sections"][0]["facts"][0], json!({"name": "Attempt", "value": "2"}));
+
+        let arguments = json!({"format": "adaptive_card", "facts": {"Env": "prod"}});
+        let card = teams_payload(&arguments, "Deploy failed");
+        let content = &card["attachments"][0]["content"];
+        assert_eq!(content["type"], "AdaptiveCard");
+        assert_eq!(content["body"][0]["text"], "Deploy failed");
+        assert_eq!(content["body"][1]["facts"][0], json!({"title": "Env", "value": "prod"}));
+    }
+}
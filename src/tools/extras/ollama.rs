@@ -0,0 +1,475 @@
+//! Ollama integration: chat, embeddings, and model listing against a local
+//! Ollama server, so Aegis can use LLMs without any cloud API key.
+//!
+//! The server URL and default models come from `llm.ollama`; every tool also
+//! accepts a `base_url` override.
+
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::config::{LlmProviderConfig, LlmProviderKind, OllamaConfig};
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::stream;
+
+use super::llm::{
+    chat_schema_properties, http_client, read_lines, resolve_tools, run_chat, send, ChatRequest, Completion,
+    LlmError, LlmProvider, ToolCall,
+};
+
+/// The provider entry for the configured Ollama server.
+pub(super) fn ollama_provider_config(ollama: &OllamaConfig) -> LlmProviderConfig {
+    LlmProviderConfig {
+        name: "ollama".to_string(),
+        kind: LlmProviderKind::Ollama,
+        base_url: Some(ollama.base_url.clone()),
+        api_key_secret: None,
+        model: Some(ollama.model.clone()),
+        timeout_secs: ollama.timeout_secs,
+    }
+}
+
+fn base_url(arguments: &Value, ollama: &OllamaConfig) -> String {
+    arguments
+        .get("base_url")
+        .and_then(|v| v.as_str())
+        .unwrap_or(&ollama.base_url)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Assembles a chat completion from Ollama's response objects: one for a
+/// plain request, or one per line when streaming.
+#[derive(Debug, Default)]
+struct OllamaResponse {
+    completion: Completion,
+}
+
+impl OllamaResponse {
+    fn on_message(&mut self, message: &Value, emit: bool) {
+        if let Some(text) = message.pointer("/message/content").and_then(|c| c.as_str()) {
+            if !text.is_empty() {
+                self.completion.content.push_str(text);
+                if emit {
+                    stream::emit(json!({ "type": "text", "text": text }));
+                }
+            }
+        }
+        for call in message
+            .pointer("/message/tool_calls")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+        {
+            // Ollama returns arguments as an object and assigns no call ids
+            let index = self.completion.tool_calls.len();
+            self.completion.tool_calls.push(ToolCall {
+                id: format!("call_{}", index),
+                name: call
+                    .pointer("/function/name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                arguments: call.pointer("/function/arguments").cloned().unwrap_or(json!({})),
+                tool: None,
+            });
+        }
+        if message.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            self.completion.finish_reason = message
+                .get("done_reason")
+                .and_then(|r| r.as_str())
+                .map(str::to_string);
+            self.completion.usage = json!({
+                "prompt_tokens": message.get("prompt_eval_count"),
+                "completion_tokens": message.get("eval_count")
+            });
+        }
+    }
+}
+
+/// Ollama's native chat API.
+#[derive(Debug)]
+pub struct OllamaProvider {
+    config: LlmProviderConfig,
+}
+
+impl OllamaProvider {
+    pub fn new(config: LlmProviderConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn is_available(&self, _state: &RuntimeState) -> bool {
+        true
+    }
+
+    async fn chat(&self, request: &ChatRequest, state: &RuntimeState) -> Result<Completion, LlmError> {
+        let model = request
+            .model
+            .as_deref()
+            .or(self.config.model.as_deref())
+            .unwrap_or("llama3.2");
+        let (tools, tool_names) = resolve_tools(request.tools.as_ref(), state, LlmProviderKind::Ollama)?;
+
+        let mut messages = Vec::with_capacity(request.messages.len() + 1);
+        if let Some(system) = &request.system {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        messages.extend(request.messages.iter().cloned());
+
+        let mut options = Map::new();
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+
+        let mut request_body = json!({
+            "model": model,
+            "messages": messages,
+            "stream": request.stream,
+            "options": options
+        });
+        if !tools.is_empty() {
+            request_body["tools"] = json!(tools);
+        }
+
+        let base_url = self
+            .config
+            .base_url
+            .as_deref()
+            .unwrap_or("http://localhost:11434")
+            .trim_end_matches('/');
+        let http_request = http_client(&self.config, state, base_url)?
+            .post(format!("{}/api/chat", base_url))
+            .json(&request_body);
+        let response = send(http_request, "Ollama").await?;
+
+        let mut assembled = OllamaResponse::default();
+        if request.stream {
+            // Streaming responses are newline-delimited JSON objects
+            read_lines(response, |line| {
+                if let Ok(message) = serde_json::from_str::<Value>(line) {
+                    assembled.on_message(&message, true);
+                }
+            })
+            .await?;
+        } else {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| LlmError::Failed(format!("Failed to parse response: {}", e)))?;
+            assembled.on_message(&body, false);
+        }
+
+        Ok(assembled.completion.finish(self.name(), model, request.stream, &tool_names))
+    }
+}
+
+/// Tool to chat with a local Ollama model.
+#[derive(Debug)]
+pub struct OllamaChatTool {
+    config: OllamaConfig,
+}
+
+impl OllamaChatTool {
+    pub fn new(config: OllamaConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for OllamaChatTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut properties = Map::new();
+        properties.insert("messages".to_string(), json!({
+            "type": "array",
+            "description": "Array of message objects with 'role' (system, user, assistant, tool) and 'content'",
+            "items": {"type": "object"}
+        }));
+        properties.insert("model".to_string(), json!({
+            "type": "string",
+            "description": format!("Model to use (default: {}); see llm.ollama_models", self.config.model)
+        }));
+        properties.insert("base_url".to_string(), json!({
+            "type": "string",
+            "description": format!("Ollama server URL (default: {})", self.config.base_url)
+        }));
+        properties.extend(chat_schema_properties());
+        properties.remove("tool_choice");
+
+        ToolDefinition {
+            name: "llm.ollama".to_string(),
+            description: Some(
+                "Chats with a model served by a local Ollama server. No API key needed. Supports streaming and tool calls."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let mut config = ollama_provider_config(&self.config);
+        config.base_url = Some(base_url(&arguments, &self.config));
+        run_chat(&OllamaProvider::new(config), &arguments, &state).await
+    }
+}
+
+/// Tool to generate embeddings with a local Ollama model.
+#[derive(Debug)]
+pub struct OllamaEmbedTool {
+    config: OllamaConfig,
+}
+
+impl OllamaEmbedTool {
+    pub fn new(config: OllamaConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for OllamaEmbedTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "llm.ollama_embed".to_string(),
+            description: Some(
+                "Generates text embeddings with a local Ollama model. Output matches llm.embed, for use with vector.*."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Text to embed"
+                    },
+                    "texts": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Multiple texts to embed"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": format!("Embedding model (default: {})", self.config.embed_model)
+                    },
+                    "base_url": {
+                        "type": "string",
+                        "description": format!("Ollama server URL (default: {})", self.config.base_url)
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let texts: Vec<String> = if let Some(text) = arguments.get("text").and_then(|v| v.as_str()) {
+            vec![text.to_string()]
+        } else if let Some(arr) = arguments.get("texts").and_then(|v| v.as_array()) {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        } else {
+            return Err(ToolError::InvalidInput("Either 'text' or 'texts' is required".to_string()));
+        };
+
+        let model = arguments
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.config.embed_model);
+
+        let base_url = base_url(&arguments, &self.config);
+        state.egress.check_url(ToolCategory::Llm, &base_url).map_err(ToolError::PermissionDenied)?;
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Llm).timeout(Duration::from_secs(self.config.timeout_secs)))
+            .map_err(|e| ToolError::Internal(e.to_string()))?;
+        let request = client
+            .post(format!("{}/api/embed", base_url))
+            .json(&json!({ "model": model, "input": texts }));
+        let body: Value = send(request, "Ollama")
+            .await?
+            .json()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Parse error: {}", e)))?;
+
+        let embeddings: Vec<Value> = body
+            .get("embeddings")
+            .and_then(|e| e.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .enumerate()
+                    .map(|(index, embedding)| {
+                        json!({
+                            "index": index,
+                            "embedding": embedding,
+                            "dimensions": embedding.as_array().map(|a| a.len())
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let result = json!({
+            "model": model,
+            "embeddings": embeddings,
+            "usage": { "prompt_tokens": body.get("prompt_eval_count") }
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to list the models available on the Ollama server.
+#[derive(Debug)]
+pub struct OllamaModelsTool {
+    config: OllamaConfig,
+}
+
+impl OllamaModelsTool {
+    pub fn new(config: OllamaConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// Summarizes Ollama's `/api/tags` response.
+fn summarize_models(body: &Value) -> Vec<Value> {
+    body.get("models")
+        .and_then(|m| m.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .map(|m| {
+                    json!({
+                        "name": m.get("name"),
+                        "size_bytes": m.get("size"),
+                        "modified_at": m.get("modified_at"),
+                        "family": m.pointer("/details/family"),
+                        "parameter_size": m.pointer("/details/parameter_size"),
+                        "quantization": m.pointer("/details/quantization_level")
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl Tool for OllamaModelsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "llm.ollama_models".to_string(),
+            description: Some("Lists the models installed on the local Ollama server.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "base_url": {
+                        "type": "string",
+                        "description": format!("Ollama server URL (default: {})", self.config.base_url)
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let base_url = base_url(&arguments, &self.config);
+        state.egress.check_url(ToolCategory::Llm, &base_url).map_err(ToolError::PermissionDenied)?;
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Llm).timeout(Duration::from_secs(30)))
+            .map_err(|e| ToolError::Internal(e.to_string()))?;
+        let request = client.get(format!("{}/api/tags", base_url));
+        let body: Value = send(request, "Ollama")
+            .await?
+            .json()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Parse error: {}", e)))?;
+
+        let models = summarize_models(&body);
+        Ok(ToolOutput::text(json!({
+            "models": models,
+            "count": models.len()
+        }).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_and_plain_responses_agree() {
+        let lines = [
+            json!({"message": {"role": "assistant", "content": "It is "}, "done": false}),
+            json!({"message": {"role": "assistant", "content": "sunny"}, "done": false}),
+            json!({"message": {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "get_time", "arguments": {"timezone": "UTC"}}}
+            ]}, "done": false}),
+            json!({"message": {"role": "assistant", "content": ""}, "done": true,
+                   "done_reason": "stop", "prompt_eval_count": 9, "eval_count": 4}),
+        ];
+        let mut streamed = OllamaResponse::default();
+        for line in &lines {
+            streamed.on_message(line, true);
+        }
+
+        let mut plain = OllamaResponse::default();
+        plain.on_message(&json!({
+            "message": {"role": "assistant", "content": "It is sunny", "tool_calls": [
+                {"function": {"name": "get_time", "arguments": {"timezone": "UTC"}}}
+            ]},
+            "done": true, "done_reason": "stop", "prompt_eval_count": 9, "eval_count": 4
+        }), false);
+
+        for completion in [&streamed.completion, &plain.completion] {
+            assert_eq!(completion.content, "It is sunny");
+            assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+            assert_eq!(completion.usage["completion_tokens"], 4);
+            assert_eq!(completion.tool_calls[0].id, "call_0");
+            assert_eq!(completion.tool_calls[0].arguments["timezone"], "UTC");
+        }
+    }
+
+    #[test]
+    fn test_summarize_models() {
+        let body = json!({"models": [{
+            "name": "llama3.2:latest",
+            "size": 2019393189u64,
+            "modified_at": "2024-10-01T10:00:00Z",
+            "details": {"family": "llama", "parameter_size": "3.2B", "quantization_level": "Q4_K_M"}
+        }]});
+        let models = summarize_models(&body);
+        assert_eq!(models[0]["name"], "llama3.2:latest");
+        assert_eq!(models[0]["parameter_size"], "3.2B");
+    }
+}
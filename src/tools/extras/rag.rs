@@ -0,0 +1,617 @@
+//! Retrieval-augmented generation: ingest documents and query them.
+//!
+//! `rag.ingest` and `rag.query` compose existing tools rather than
+//! duplicating them: sources are read with `fs.read_file` (so the usual read
+//! restrictions apply) or `web.extract`, embedded with `llm.embed` (or any
+//! tool with the same output, e.g. `llm.ollama_embed`), and stored and
+//! searched with `vector.store` / `vector.search`. These calls go through
+//! `tools/call` as the caller, so its key scopes, the policy, approvals,
+//! budgets and the audit log apply to each of them.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::handlers::handle_tools_call_with_context;
+use crate::memory::{check_namespace, KvOp};
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::caller;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::stream::{self, ProgressReporter};
+
+/// Texts sent to the embedding tool per call.
+const EMBED_BATCH: usize = 64;
+
+/// Chunks stored per `vector.store` call.
+const STORE_BATCH: usize = 1000;
+
+/// Runs another tool as the caller and returns its text output.
+async fn call_tool(state: &Arc<RuntimeState>, name: &str, args: Value) -> Result<String, ToolError> {
+    let caller = caller::current().ok_or_else(|| {
+        ToolError::PermissionDenied("rag tools call other tools as the caller, and this call has none".to_string())
+    })?;
+
+    // Callers report their own progress
+    let params = json!({ "name": name, "arguments": args });
+    let result = stream::without_sink(handle_tools_call_with_context(Some(params), state.clone(), caller))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("{} failed: {}", name, e)))?;
+    let text = result["content"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|c| c["text"].as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    if result["isError"] == true {
+        return Err(ToolError::ExecutionFailed(format!("{} failed: {}", name, text)));
+    }
+    Ok(text)
+}
+
+/// Runs a tool whose output is JSON.
+async fn call_tool_json(state: &Arc<RuntimeState>, name: &str, args: Value) -> Result<Value, ToolError> {
+    let text = call_tool(state, name, args).await?;
+    serde_json::from_str(&text)
+        .map_err(|e| ToolError::ExecutionFailed(format!("{} returned invalid JSON: {}", name, e)))
+}
+
+/// Whether `name` is an embedding tool: `llm.embed` or another
+/// `llm.*_embed` tool with the same output, such as `llm.ollama_embed`.
+fn is_embed_tool(name: &str) -> bool {
+    name.strip_prefix("llm.").is_some_and(|rest| rest == "embed" || rest.ends_with("_embed"))
+}
+
+/// The vector namespace of a rag call: the caller's API key's namespace,
+/// which `namespace` may name but not leave, otherwise `namespace`.
+fn resolve_namespace(namespace: Option<String>) -> Result<String, ToolError> {
+    let namespace = caller::scoped_namespace(namespace)
+        .map_err(ToolError::PermissionDenied)?
+        .unwrap_or_else(|| "default".to_string());
+    check_namespace(&namespace).map_err(ToolError::InvalidInput)?;
+    Ok(namespace)
+}
+
+/// Embeds texts with an `llm.embed`-compatible tool, returning the
+/// embeddings and the model the tool reports.
+async fn embed(
+    state: &Arc<RuntimeState>,
+    embed_tool: &str,
+    model: Option<&str>,
+    texts: &[String],
+) -> Result<(Vec<Value>, Option<String>), ToolError> {
+    if !is_embed_tool(embed_tool) {
+        return Err(ToolError::InvalidInput(format!(
+            "'{}' is not an embedding tool; use llm.embed or another llm.*_embed tool",
+            embed_tool
+        )));
+    }
+    let mut embeddings = Vec::with_capacity(texts.len());
+    let mut used_model = None;
+    for batch in texts.chunks(EMBED_BATCH) {
+        let mut args = json!({ "texts": batch });
+        if let Some(model) = model {
+            args["model"] = json!(model);
+        }
+        let result = call_tool_json(state, embed_tool, args).await?;
+        let batch_embeddings = result
+            .get("embeddings")
+            .and_then(|e| e.as_array())
+            .filter(|e| e.len() == batch.len())
+            .ok_or_else(|| {
+                ToolError::ExecutionFailed(format!("{} returned no embeddings for the batch", embed_tool))
+            })?;
+        embeddings.extend(batch_embeddings.iter().map(|e| e["embedding"].clone()));
+        used_model = result["model"].as_str().map(|m| m.to_string());
+    }
+    Ok((embeddings, used_model))
+}
+
+/// Splits text into chunks of at most `size` characters, each starting
+/// `overlap` characters before the previous one ended. Chunks end at a
+/// paragraph, sentence, or word boundary when one falls in their last fifth.
+pub(crate) fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start..end];
+            let min_len = size - size / 5;
+            let boundary = |pattern: &dyn Fn(usize) -> bool| {
+                (min_len..window.len()).rev().find(|&i| pattern(i)).map(|i| start + i + 1)
+            };
+            end = boundary(&|i| window[i] == '\n' && i > 0 && window[i - 1] == '\n')
+                .or_else(|| boundary(&|i| matches!(window[i - 1], '.' | '!' | '?') && window[i].is_whitespace()))
+                .or_else(|| boundary(&|i| window[i].is_whitespace()))
+                .unwrap_or(end);
+        }
+
+        let chunk: String = chars[start..end].iter().collect();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end >= chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+/// Stable document id for a source.
+fn document_id(source: &str) -> String {
+    let digest = Sha256::digest(source.as_bytes());
+    format!("doc-{}", &hex::encode(digest)[..12])
+}
+
+/// Tool to ingest a document into the vector store.
+#[derive(Debug)]
+pub struct RagIngestTool;
+
+#[derive(Deserialize)]
+struct RagIngestArgs {
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default = "default_chunk_size")]
+    chunk_size: usize,
+    #[serde(default = "default_chunk_overlap")]
+    chunk_overlap: usize,
+    #[serde(default = "default_embed_tool")]
+    embed_tool: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    metadata: Value,
+}
+
+fn default_chunk_size() -> usize {
+    1000
+}
+
+fn default_chunk_overlap() -> usize {
+    200
+}
+
+fn default_embed_tool() -> String {
+    "llm.embed".to_string()
+}
+
+#[async_trait]
+impl Tool for RagIngestTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "rag.ingest".to_string(),
+            description: Some(
+                "Ingests a document for retrieval: reads a file path or URL (or takes text), splits it into \
+                 overlapping chunks, embeds them, and stores them in the vector store. Re-ingesting a source \
+                 replaces its chunks."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "source": {
+                        "type": "string",
+                        "description": "File path (read with fs.read_file) or http(s) URL (read with web.extract)"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Text to ingest instead of reading a source"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "Document id (default: derived from source)"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Vector namespace (default: default; always the API key name for authenticated keys)"
+                    },
+                    "chunk_size": {
+                        "type": "integer",
+                        "description": "Maximum characters per chunk (default: 1000)"
+                    },
+                    "chunk_overlap": {
+                        "type": "integer",
+                        "description": "Characters shared by consecutive chunks (default: 200)"
+                    },
+                    "embed_tool": {
+                        "type": "string",
+                        "description": "Embedding tool, llm.embed or another llm.*_embed tool such as llm.ollama_embed (default: llm.embed). Use the same one for rag.query."
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Embedding model passed to the embedding tool"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "description": "Metadata stored with every chunk"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: RagIngestArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        let namespace = resolve_namespace(args.namespace)?;
+        if args.chunk_size == 0 || args.chunk_overlap >= args.chunk_size {
+            return Err(ToolError::InvalidInput(
+                "chunk_size must be positive and larger than chunk_overlap".to_string(),
+            ));
+        }
+
+        // Steps: reading the source, embedding, then storing the chunks
+        let reporter = ProgressReporter::current();
+        if let Some(source) = &args.source {
+            reporter.report(0, None, format!("Reading {}", source));
+        }
+        let (source, text) = match (&args.source, args.text) {
+            (_, Some(text)) => (args.source.clone().unwrap_or_else(|| "text".to_string()), text),
+            (Some(source), None) if source.starts_with("http://") || source.starts_with("https://") => {
+                let page = call_tool_json(&state, "web.extract", json!({ "url": source, "format": "text" })).await?;
+                (source.clone(), page["content"].as_str().unwrap_or_default().to_string())
+            }
+            (Some(source), None) => {
+                let text = call_tool(&state, "fs.read_file", json!({ "path": source })).await?;
+                (source.clone(), text)
+            }
+            (None, None) => {
+                return Err(ToolError::InvalidInput("Either 'source' or 'text' is required".to_string()));
+            }
+        };
+
+        let chunks = chunk_text(&text, args.chunk_size, args.chunk_overlap);
+        if chunks.is_empty() {
+            return Err(ToolError::InvalidInput(format!("No text to ingest from {}", source)));
+        }
+        let doc_id = args.id.unwrap_or_else(|| document_id(&source));
+
+        let total = 3;
+        reporter.report(1, Some(total), format!("Embedding {} chunks", chunks.len()));
+        let (embeddings, model) = embed(&state, &args.embed_tool, args.model.as_deref(), &chunks).await?;
+
+        // Drop chunks from a previous ingest of this document
+        let prefix = format!("vector:{}:{}#", namespace, doc_id);
+        let stale = state
+            .memory_store
+            .kv_list(Some(&prefix))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        if !stale.is_empty() {
+            let ops = stale.iter().map(|key| KvOp::Delete { key: key.clone() }).collect();
+            state
+                .memory_store
+                .kv_batch(ops)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        }
+
+        reporter.report(2, Some(total), format!("Storing {} chunks", chunks.len()));
+        let mut vectors = Vec::with_capacity(chunks.len());
+        for (index, (chunk, embedding)) in chunks.iter().zip(embeddings).enumerate() {
+            let mut metadata = json!({
+                "source": source,
+                "doc_id": doc_id,
+                "chunk": index,
+                "embed_tool": args.embed_tool
+            });
+            if let (Some(meta), Some(extra)) = (metadata.as_object_mut(), args.metadata.as_object()) {
+                for (key, value) in extra {
+                    meta.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            vectors.push(json!({
+                "id": format!("{}#{}", doc_id, index),
+                "embedding": embedding,
+                "text": chunk,
+                "metadata": metadata
+            }));
+        }
+        for batch in vectors.chunks(STORE_BATCH) {
+            let store_args = json!({ "vectors": batch, "namespace": namespace, "model": model });
+            call_tool(&state, "vector.store", store_args).await?;
+        }
+        reporter.report(total, Some(total), format!("Ingested {} chunks from {}", chunks.len(), source));
+
+        Ok(ToolOutput::text(json!({
+            "success": true,
+            "doc_id": doc_id,
+            "source": source,
+            "namespace": namespace,
+            "chunks": chunks.len(),
+            "characters": text.chars().count(),
+            "replaced_chunks": stale.len(),
+            "embed_tool": args.embed_tool,
+            "model": model
+        }).to_string()))
+    }
+}
+
+/// Tool to retrieve context for a question.
+#[derive(Debug)]
+pub struct RagQueryTool;
+
+#[derive(Deserialize)]
+struct RagQueryArgs {
+    question: String,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default = "default_top_k")]
+    top_k: usize,
+    #[serde(default)]
+    threshold: Option<f64>,
+    #[serde(default = "default_embed_tool")]
+    embed_tool: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default = "default_max_context")]
+    max_context_chars: usize,
+}
+
+fn default_top_k() -> usize {
+    5
+}
+
+fn default_max_context() -> usize {
+    8000
+}
+
+#[async_trait]
+impl Tool for RagQueryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "rag.query".to_string(),
+            description: Some(
+                "Retrieves the document chunks most relevant to a question (ingested with rag.ingest) and \
+                 returns them assembled into a numbered, source-attributed context for a prompt."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "question": {
+                        "type": "string",
+                        "description": "Question to retrieve context for"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Vector namespace (default: default; always the API key name for authenticated keys)"
+                    },
+                    "top_k": {
+                        "type": "integer",
+                        "description": "Chunks to retrieve (default: 5)"
+                    },
+                    "threshold": {
+                        "type": "number",
+                        "description": "Minimum similarity score (0-1)"
+                    },
+                    "embed_tool": {
+                        "type": "string",
+                        "description": "Embedding tool, llm.embed or another llm.*_embed tool; must match the one used for rag.ingest (default: llm.embed)"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Embedding model passed to the embedding tool"
+                    },
+                    "max_context_chars": {
+                        "type": "integer",
+                        "description": "Maximum characters of assembled context (default: 8000)"
+                    }
+                },
+                "required": ["question"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: RagQueryArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+        let namespace = resolve_namespace(args.namespace)?;
+
+        let (mut embeddings, _) =
+            embed(&state, &args.embed_tool, args.model.as_deref(), std::slice::from_ref(&args.question)).await?;
+        let embedding = embeddings.remove(0);
+
+        let mut search = json!({
+            "embedding": embedding,
+            "limit": args.top_k,
+            "namespace": namespace
+        });
+        if let Some(threshold) = args.threshold {
+            search["threshold"] = json!(threshold);
+        }
+        let found = call_tool_json(&state, "vector.search", search).await?;
+
+        let mut context = String::new();
+        let mut chunks = Vec::new();
+        for result in found["results"].as_array().into_iter().flatten() {
+            let text = result["text"].as_str().unwrap_or_default();
+            let source = result["metadata"]["source"].as_str().unwrap_or("unknown");
+            let entry = format!("[{}] ({})\n{}\n\n", chunks.len() + 1, source, text);
+            if !context.is_empty() && context.len() + entry.len() > args.max_context_chars {
+                break;
+            }
+            context.push_str(&entry);
+            chunks.push(json!({
+                "id": result["id"],
+                "score": result["score"],
+                "source": source,
+                "chunk": result["metadata"]["chunk"],
+                "text": text
+            }));
+        }
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&json!({
+            "question": args.question,
+            "namespace": namespace,
+            "count": chunks.len(),
+            "context": context.trim_end(),
+            "chunks": chunks
+        })).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, KeyIdentity, RequestContext};
+    use crate::tools::ToolContent;
+
+    /// Embeds text as letter frequencies, so similar words score high.
+    #[derive(Debug)]
+    struct LetterEmbed;
+
+    #[async_trait]
+    impl Tool for LetterEmbed {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "llm.test_embed".to_string(),
+                description: None,
+                input_schema: json!({"type": "object"}),
+                output_schema: None,
+            }
+        }
+
+        async fn execute(&self, arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            let embeddings: Vec<Value> = arguments["texts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|text| {
+                    let mut counts = [0.0f64; 26];
+                    for c in text.as_str().unwrap().to_lowercase().chars().filter(|c| c.is_ascii_lowercase()) {
+                        counts[(c as u8 - b'a') as usize] += 1.0;
+                    }
+                    json!({ "embedding": counts.to_vec() })
+                })
+                .collect();
+            Ok(ToolOutput::text(json!({ "embeddings": embeddings }).to_string()))
+        }
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        let text = "First sentence here. Second sentence follows. Third one ends it.";
+        let chunks = chunk_text(text, 25, 5);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 25));
+        assert_eq!(chunks[0], "First sentence here.");
+
+        assert_eq!(chunk_text("short", 100, 10), vec!["short".to_string()]);
+        assert!(chunk_text("   ", 100, 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ingest_and_query() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(LetterEmbed));
+
+        let ingest = |text: &str| {
+            json!({
+                "text": text,
+                "id": "notes",
+                "namespace": "kb",
+                "chunk_size": 40,
+                "chunk_overlap": 0,
+                "embed_tool": "llm.test_embed"
+            })
+        };
+        let text = "zebras zigzag in the zoo.\n\nApples and bananas are fruit.";
+        let as_caller = |fut| caller::with_context(RequestContext::new("s1"), fut);
+        as_caller(RagIngestTool.execute(ingest(text), state.clone())).await.unwrap();
+
+        // Re-ingesting replaces the previous chunks
+        let output = as_caller(RagIngestTool.execute(ingest(text), state.clone())).await.unwrap();
+        let ToolContent::Text { text: summary } = &output.content[0] else { panic!() };
+        let summary: Value = serde_json::from_str(summary).unwrap();
+        assert_eq!(summary["chunks"], 2);
+        assert_eq!(summary["replaced_chunks"], 2);
+
+        let query = json!({
+            "question": "zoo zebra",
+            "namespace": "kb",
+            "top_k": 1,
+            "embed_tool": "llm.test_embed"
+        });
+        let output = as_caller(RagQueryTool.execute(query, state)).await.unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!() };
+        let result: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["chunks"][0]["id"], "notes#0");
+        assert!(result["context"].as_str().unwrap().starts_with("[1] (text)\nzebras"));
+    }
+
+    #[tokio::test]
+    async fn test_nested_calls_are_authorized_as_the_caller() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.tool_registry.write().register(Arc::new(LetterEmbed));
+        let ingest = |embed_tool: &str| json!({"text": "some notes", "embed_tool": embed_tool});
+        let as_key = |scopes: &[&str]| {
+            let identity = KeyIdentity {
+                name: "agent-a".to_string(),
+                key_hash: "hash".to_string(),
+                scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            };
+            RequestContext::new("s1").with_identity(Some(Arc::new(identity)))
+        };
+
+        // Without a caller nothing runs
+        let err = RagIngestTool.execute(ingest("llm.test_embed"), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+        // Only embedding tools embed
+        let err = caller::with_context(as_key(&["*"]), RagIngestTool.execute(ingest("fs.write"), state.clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+
+        // The nested calls need the key's scopes
+        let err = caller::with_context(as_key(&["rag.*"]), RagIngestTool.execute(ingest("llm.test_embed"), state.clone()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not scoped for tool 'llm.test_embed'"), "{}", err);
+
+        // A key stores in its own namespace and can't name another
+        let output = caller::with_context(as_key(&["*"]), RagIngestTool.execute(ingest("llm.test_embed"), state.clone()))
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!() };
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap()["namespace"], "agent-a");
+        let mut other = ingest("llm.test_embed");
+        other["namespace"] = json!("kb");
+        let err = caller::with_context(as_key(&["*"]), RagIngestTool.execute(other, state.clone())).await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+    }
+}
@@ -0,0 +1,245 @@
+//! MCP sampling: generate text with the connected client's model.
+//!
+//! Instead of calling a provider API with a key, the server sends the
+//! client a `sampling/createMessage` request and the client runs it on its
+//! own model (usually after asking the user). Only available while serving
+//! a client that declared the `sampling` capability.
+
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::config::{LlmProviderConfig, LlmProviderKind};
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::client::{self, ClientError};
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+use super::llm::{chat_schema_properties, run_chat, ChatRequest, Completion, LlmError, LlmProvider};
+
+/// `maxTokens` is required by sampling; used when the request sets none.
+const DEFAULT_MAX_TOKENS: u64 = 1024;
+
+/// The provider entry for the connected client.
+pub(super) fn sampling_provider_config() -> LlmProviderConfig {
+    LlmProviderConfig {
+        name: "client".to_string(),
+        kind: LlmProviderKind::Sampling,
+        base_url: None,
+        api_key_secret: None,
+        model: None,
+        timeout_secs: client::DEFAULT_TIMEOUT.as_secs(),
+    }
+}
+
+/// Provider that samples from the connected MCP client's model.
+#[derive(Debug)]
+pub struct SamplingProvider {
+    config: LlmProviderConfig,
+}
+
+impl SamplingProvider {
+    pub fn new(config: LlmProviderConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl From<ClientError> for LlmError {
+    fn from(e: ClientError) -> Self {
+        match e {
+            ClientError::NotConnected | ClientError::Unsupported(_) => LlmError::NotConfigured(e.to_string()),
+            _ => LlmError::Failed(e.to_string()),
+        }
+    }
+}
+
+/// Text of a chat message's content: a string, or OpenAI/Anthropic-style parts.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Builds `sampling/createMessage` params from a chat request.
+fn sampling_params(request: &ChatRequest) -> Value {
+    // Sampling messages are user/assistant only; system text goes in systemPrompt
+    let mut system: Vec<String> = request.system.iter().cloned().collect();
+    let mut messages = Vec::with_capacity(request.messages.len());
+    for message in &request.messages {
+        let text = content_text(message.get("content").unwrap_or(&Value::Null));
+        match message.get("role").and_then(|r| r.as_str()) {
+            Some("system") => system.push(text),
+            Some("assistant") => messages.push(json!({"role": "assistant", "content": {"type": "text", "text": text}})),
+            _ => messages.push(json!({"role": "user", "content": {"type": "text", "text": text}})),
+        }
+    }
+
+    let mut params = json!({
+        "messages": messages,
+        "maxTokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)
+    });
+    if !system.is_empty() {
+        params["systemPrompt"] = json!(system.join("\n\n"));
+    }
+    if let Some(temperature) = request.temperature {
+        params["temperature"] = json!(temperature);
+    }
+    if let Some(model) = &request.model {
+        params["modelPreferences"] = json!({ "hints": [{ "name": model }] });
+    }
+    params
+}
+
+#[async_trait]
+impl LlmProvider for SamplingProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn is_available(&self, _state: &RuntimeState) -> bool {
+        client::can_sample()
+    }
+
+    async fn chat(&self, request: &ChatRequest, _state: &RuntimeState) -> Result<Completion, LlmError> {
+        if request.tools.as_ref().and_then(|t| t.as_array()).is_some_and(|t| !t.is_empty()) {
+            return Err(LlmError::Failed("client sampling does not support tools".to_string()));
+        }
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let result = client::create_message(sampling_params(request), timeout).await?;
+
+        let model = result
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or("client")
+            .to_string();
+        let completion = Completion {
+            content: content_text(result.pointer("/content/text").unwrap_or(&Value::Null)),
+            finish_reason: result.get("stopReason").and_then(|s| s.as_str()).map(str::to_string),
+            ..Completion::default()
+        };
+        Ok(completion.finish(self.name(), &model, false, &HashMap::new()))
+    }
+}
+
+/// Tool to generate text with the connected client's model.
+#[derive(Debug)]
+pub struct LlmSampleTool;
+
+#[async_trait]
+impl Tool for LlmSampleTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut properties = Map::new();
+        properties.insert("messages".to_string(), json!({
+            "type": "array",
+            "description": "Array of message objects with 'role' (user, assistant, system) and 'content'",
+            "items": {"type": "object"}
+        }));
+        properties.insert("model".to_string(), json!({
+            "type": "string",
+            "description": "Preferred model, passed to the client as a hint"
+        }));
+        properties.extend(chat_schema_properties());
+        properties.remove("stream");
+        properties.remove("tools");
+        properties.remove("tool_choice");
+
+        ToolDefinition {
+            name: "llm.sample".to_string(),
+            description: Some(
+                "Asks the connected MCP client to generate a message with its own model (MCP sampling). \
+                 No API key needed; requires a client that supports sampling."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        run_chat(&SamplingProvider::new(sampling_provider_config()), &arguments, &state).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::protocol::ClientCapabilities;
+    use crate::tools::client::ClientPeer;
+    use crate::tools::ToolContent;
+
+    #[test]
+    fn test_sampling_params() {
+        let request = ChatRequest {
+            messages: vec![
+                json!({"role": "system", "content": "Be terse."}),
+                json!({"role": "user", "content": [{"type": "text", "text": "Hi"}]}),
+                json!({"role": "assistant", "content": "Hello"}),
+            ],
+            model: Some("claude-3-5-sonnet".to_string()),
+            ..ChatRequest::default()
+        };
+        let params = sampling_params(&request);
+        assert_eq!(params["systemPrompt"], "Be terse.");
+        assert_eq!(params["maxTokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(params["messages"][0], json!({"role": "user", "content": {"type": "text", "text": "Hi"}}));
+        assert_eq!(params["messages"][1]["role"], "assistant");
+        assert_eq!(params["modelPreferences"]["hints"][0]["name"], "claude-3-5-sonnet");
+    }
+
+    #[tokio::test]
+    async fn test_sample_round_trip() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let peer = Arc::new(ClientPeer::new());
+        peer.set_capabilities(ClientCapabilities { sampling: Some(json!({})), ..Default::default() });
+        let mut outbound = peer.attach();
+
+        // Plays the client: answers the sampling request
+        let client = peer.clone();
+        tokio::spawn(async move {
+            let request = outbound.recv().await.unwrap();
+            assert_eq!(request["method"], "sampling/createMessage");
+            let prompt = request["params"]["messages"][0]["content"]["text"].as_str().unwrap().to_string();
+            client.handle_response(&json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {
+                    "role": "assistant",
+                    "content": {"type": "text", "text": format!("echo: {}", prompt)},
+                    "model": "client-model",
+                    "stopReason": "endTurn"
+                }
+            }));
+        });
+
+        let output = client::with_peer(peer, LlmSampleTool.execute(json!({"prompt": "ping"}), state))
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!() };
+        let completion: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(completion["content"], "echo: ping");
+        assert_eq!(completion["provider"], "client");
+        assert_eq!(completion["model"], "client-model");
+        assert_eq!(completion["finish_reason"], "endTurn");
+    }
+}
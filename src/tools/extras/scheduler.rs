@@ -2,14 +2,27 @@
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use chrono_tz::Tz;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::core::config::RuleOwner;
+use crate::core::events::is_forbidden_action;
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::scheduler::ScheduledTask;
+use crate::scheduler::{parse_run_time, ChainCondition, Concurrency, Schedule, ScheduledTask, TASK_HISTORY_LIMIT, WORKFLOW_TOOL};
+use crate::tools::caller;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
 
+/// Persists the scheduler's tasks after a change.
+async fn save_tasks(state: &RuntimeState) -> Result<(), ToolError> {
+    state
+        .scheduler
+        .persist(state.memory_store.as_ref())
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to save tasks: {}", e)))
+}
+
 /// Tool to create a scheduled task.
 #[derive(Debug)]
 pub struct SchedulerCreateTool;
@@ -20,7 +33,11 @@ impl Tool for SchedulerCreateTool {
         ToolDefinition {
             name: "scheduler.create".to_string(),
             description: Some(
-                "Creates a new scheduled task with cron expression. Format: 'minute hour day month weekday'"
+                "Creates a scheduled task that runs a tool or a saved workflow on a cron expression \
+                 ('minute hour day month weekday'), every interval_secs seconds, once at a given time, or after \
+                 another task finishes. Give exactly one of cron, interval_secs, at and after, and one of tool and \
+                 workflow. A task run after another can use {{previous.output}}, {{previous.success}}, \
+                 {{previous.name}} and {{previous.task_id}} in its args."
                     .to_string(),
             ),
             input_schema: json!({
@@ -34,17 +51,57 @@ impl Tool for SchedulerCreateTool {
                         "type": "string",
                         "description": "Cron expression (e.g., '*/5 * * * *' for every 5 mins)"
                     },
+                    "interval_secs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Run every this many seconds, starting one interval from now"
+                    },
+                    "at": {
+                        "type": "string",
+                        "description": "Run once at this time: RFC 3339, or 'YYYY-MM-DD HH:MM' in the task's timezone"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "ID of a task this one runs after"
+                    },
+                    "on": {
+                        "type": "string",
+                        "enum": ["success", "failure", "always"],
+                        "description": "Which outcomes of the 'after' task trigger this one (default: success)"
+                    },
                     "tool": {
                         "type": "string",
                         "description": "Tool to execute"
                     },
+                    "workflow": {
+                        "type": "string",
+                        "description": "Saved workflow to run instead of a tool"
+                    },
+                    "version": {
+                        "type": "integer",
+                        "description": "Workflow version to run (default: latest at each run)"
+                    },
                     "args": {
                         "type": "object",
-                        "description": "Tool arguments"
+                        "description": "Tool arguments, or the workflow's inputs"
+                    },
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA timezone for cron and a local 'at' time (default: server default_timezone)"
+                    },
+                    "catch_up": {
+                        "type": "boolean",
+                        "description": "Run once on startup if runs were missed while the server was down (default: false, skip them)"
+                    },
+                    "concurrency": {
+                        "type": "string",
+                        "enum": ["skip", "queue", "parallel"],
+                        "description": "When the task falls due while still running: skip the run (default), queue it, or run in parallel"
                     }
                 },
-                "required": ["name", "cron", "tool"]
+                "required": ["name"]
             }),
+            output_schema: None,
         }
     }
 
@@ -58,44 +115,119 @@ impl Tool for SchedulerCreateTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidInput("Missing 'name'".to_string()))?;
 
-        let cron = arguments
-            .get("cron")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'cron'".to_string()))?;
-
-        let tool = arguments
-            .get("tool")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'tool'".to_string()))?;
-
         let args = arguments
             .get("args")
             .cloned()
             .unwrap_or(json!({}));
 
+        let workflow = arguments.get("workflow").and_then(|v| v.as_str());
+        let (tool, args) = match (arguments.get("tool").and_then(|v| v.as_str()), workflow) {
+            (Some(tool), None) => (tool.to_string(), args),
+            (None, Some(workflow)) => {
+                let mut call = json!({"name": workflow, "inputs": args});
+                if let Some(version) = arguments.get("version").and_then(|v| v.as_i64()) {
+                    call["version"] = json!(version);
+                }
+                (WORKFLOW_TOOL.to_string(), call)
+            }
+            _ => return Err(ToolError::InvalidInput("Give exactly one of 'tool' and 'workflow'".to_string())),
+        };
+
+        // The task runs as this caller later, so it may only call what the caller can
+        let context = caller::current();
+        {
+            let registry = state.tool_registry.read();
+            let resolved = registry.resolve(&tool);
+            if registry.get(resolved).is_none() {
+                return Err(ToolError::NotFound(tool.clone()));
+            }
+            if is_forbidden_action(resolved) {
+                return Err(ToolError::PermissionDenied(format!("Scheduled tasks can't run {}", resolved)));
+            }
+            if let Some(identity) = context.as_ref().and_then(|context| context.identity.as_ref()) {
+                if !identity.allows_tool(resolved) {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "API key '{}' is not scoped for tool '{}'",
+                        identity.name, resolved
+                    )));
+                }
+            }
+        }
+
+        let timezone = arguments
+            .get("timezone")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let tz = match &timezone {
+            Some(tz) => tz
+                .parse::<Tz>()
+                .map_err(|_| ToolError::InvalidInput(format!("Unknown timezone: {}", tz)))?,
+            None => state.scheduler.timezone(),
+        };
+
+        let cron = arguments.get("cron").and_then(|v| v.as_str());
+        let interval = arguments.get("interval_secs").and_then(|v| v.as_u64());
+        let at = arguments.get("at").and_then(|v| v.as_str());
+        let after = arguments.get("after").and_then(|v| v.as_str());
+        let schedule = match (cron, interval, at, after) {
+            (Some(cron), None, None, None) => Schedule::Cron(cron.to_string()),
+            (None, Some(secs), None, None) => Schedule::IntervalSecs(secs),
+            (None, None, Some(at), None) => Schedule::At(parse_run_time(at, tz).map_err(ToolError::InvalidInput)?),
+            (None, None, None, Some(after)) => Schedule::After {
+                task: after.to_string(),
+                on: arguments
+                    .get("on")
+                    .map(|on| serde_json::from_value::<ChainCondition>(on.clone()))
+                    .transpose()
+                    .map_err(|_| ToolError::InvalidInput("'on' must be success, failure or always".to_string()))?
+                    .unwrap_or_default(),
+            },
+            _ => {
+                return Err(ToolError::InvalidInput(
+                    "Give exactly one of 'cron', 'interval_secs', 'at' and 'after'".to_string(),
+                ))
+            }
+        };
+
+        let concurrency = arguments
+            .get("concurrency")
+            .map(|c| serde_json::from_value::<Concurrency>(c.clone()))
+            .transpose()
+            .map_err(|_| ToolError::InvalidInput("'concurrency' must be skip, queue or parallel".to_string()))?
+            .unwrap_or_default();
+
         let task = ScheduledTask {
             id: Uuid::new_v4().to_string(),
             name: name.to_string(),
-            cron: cron.to_string(),
-            tool: tool.to_string(),
+            schedule,
+            tool,
             args,
             enabled: true,
             created_at: chrono::Utc::now().to_rfc3339(),
             last_run: None,
             last_result: None,
+            timezone: timezone.clone(),
+            next_run: None,
+            catch_up: arguments.get("catch_up").and_then(|v| v.as_bool()).unwrap_or(false),
+            concurrency,
+            owner: context.map(RuleOwner::of),
         };
 
         let task_id = task.id.clone();
         state
             .scheduler
             .add_task(task)
-            .map_err(|e| ToolError::ExecutionFailed(e))?;
+            .map_err(ToolError::InvalidInput)?;
+        save_tasks(&state).await?;
+        let task = state.scheduler.get_task(&task_id);
 
         let result = json!({
             "success": true,
             "task_id": task_id,
             "name": name,
-            "cron": cron,
+            "schedule": task.as_ref().map(|t| t.schedule.to_string()),
+            "next_run": task.and_then(|t| t.next_run),
+            "timezone": tz.name(),
             "message": format!("Scheduled task '{}' created", name)
         });
 
@@ -117,6 +249,7 @@ impl Tool for SchedulerListTool {
                 "type": "object",
                 "properties": {}
             }),
+            output_schema: None,
         }
     }
 
@@ -133,8 +266,12 @@ impl Tool for SchedulerListTool {
                 json!({
                     "id": t.id,
                     "name": t.name,
-                    "cron": t.cron,
+                    "schedule": t.schedule.to_string(),
+                    "next_run": t.next_run,
                     "tool": t.tool,
+                    "workflow": t.workflow(),
+                    "catch_up": t.catch_up,
+                    "concurrency": t.concurrency,
                     "enabled": t.enabled,
                     "last_run": t.last_run,
                     "created_at": t.created_at
@@ -171,6 +308,7 @@ impl Tool for SchedulerDeleteTool {
                 },
                 "required": ["id"]
             }),
+            output_schema: None,
         }
     }
 
@@ -185,6 +323,9 @@ impl Tool for SchedulerDeleteTool {
             .ok_or_else(|| ToolError::InvalidInput("Missing 'id'".to_string()))?;
 
         let deleted = state.scheduler.remove_task(id);
+        if deleted {
+            save_tasks(&state).await?;
+        }
 
         let result = json!({
             "success": deleted,
@@ -220,6 +361,7 @@ impl Tool for SchedulerToggleTool {
                 },
                 "required": ["id", "enabled"]
             }),
+            output_schema: None,
         }
     }
 
@@ -239,6 +381,9 @@ impl Tool for SchedulerToggleTool {
             .ok_or_else(|| ToolError::InvalidInput("Missing 'enabled'".to_string()))?;
 
         let updated = state.scheduler.set_enabled(id, enabled);
+        if updated {
+            save_tasks(&state).await?;
+        }
 
         let result = json!({
             "success": updated,
@@ -275,6 +420,7 @@ impl Tool for SchedulerRunTool {
                 },
                 "required": ["id"]
             }),
+            output_schema: None,
         }
     }
 
@@ -321,3 +467,64 @@ impl Tool for SchedulerRunTool {
     }
 }
 
+
+/// Tool to list past runs of a scheduled task.
+#[derive(Debug)]
+pub struct SchedulerHistoryTool;
+
+#[async_trait]
+impl Tool for SchedulerHistoryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "scheduler.history".to_string(),
+            description: Some(format!(
+                "Lists past runs of a scheduled task (newest first) with their duration and an excerpt of the output \
+                 or error. The last {} runs of each task are kept.",
+                TASK_HISTORY_LIMIT
+            )),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Task ID"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum runs to return (default: 20)"
+                    }
+                },
+                "required": ["id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let id = arguments
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'id'".to_string()))?;
+        let limit = arguments.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+
+        let runs = state
+            .memory_store
+            .list_task_runs(id, limit.min(TASK_HISTORY_LIMIT))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "id": id,
+            "name": state.scheduler.get_task(id).map(|t| t.name),
+            "count": runs.len(),
+            "runs": runs
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
@@ -6,8 +6,18 @@ use std::sync::Arc;
 
 use crate::core::RuntimeState;
 use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::secrets::parse_expiry;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
 
+/// Parses the optional `expires` argument.
+fn expiry_argument(arguments: &Value) -> Result<Option<chrono::DateTime<chrono::Utc>>, ToolError> {
+    arguments
+        .get("expires")
+        .and_then(|v| v.as_str())
+        .map(|expires| parse_expiry(expires, chrono::Utc::now()).map_err(ToolError::InvalidInput))
+        .transpose()
+}
+
 /// Tool to store a secret.
 #[derive(Debug)]
 pub struct SecretsSetTool;
@@ -35,10 +45,15 @@ impl Tool for SecretsSetTool {
                     "description": {
                         "type": "string",
                         "description": "Optional description"
+                    },
+                    "expires": {
+                        "type": "string",
+                        "description": "When the secret should be rotated by: a date (2025-06-30), a timestamp, or a period (90d, 12w)"
                     }
                 },
                 "required": ["key", "value"]
             }),
+            output_schema: None,
         }
     }
 
@@ -58,8 +73,12 @@ impl Tool for SecretsSetTool {
             .ok_or_else(|| ToolError::InvalidInput("Missing 'value' parameter".to_string()))?;
 
         let description = arguments.get("description").and_then(|v| v.as_str());
+        let expires = expiry_argument(&arguments)?;
 
         state.secrets.set(key, value, description);
+        if let Some(expires) = expires {
+            state.secrets.set_expiry(key, Some(expires));
+        }
 
         let result = json!({
             "success": true,
@@ -91,6 +110,7 @@ impl Tool for SecretsGetTool {
                 },
                 "required": ["key"]
             }),
+            output_schema: None,
         }
     }
 
@@ -146,11 +166,15 @@ impl Tool for SecretsListTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "secrets.list".to_string(),
-            description: Some("Lists all stored secret keys (not values).".to_string()),
+            description: Some(
+                "Lists all stored secret keys (not values) and the secrets that expire soon or have expired."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {}
             }),
+            output_schema: None,
         }
     }
 
@@ -163,7 +187,8 @@ impl Tool for SecretsListTool {
 
         let result = json!({
             "count": keys.len(),
-            "keys": keys
+            "keys": keys,
+            "expiring": state.secrets.expiring(chrono::Utc::now())
         });
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
@@ -190,6 +215,7 @@ impl Tool for SecretsDeleteTool {
                 },
                 "required": ["key"]
             }),
+            output_schema: None,
         }
     }
 
@@ -215,3 +241,214 @@ impl Tool for SecretsDeleteTool {
     }
 }
 
+
+/// Tool to rotate a secret.
+#[derive(Debug)]
+pub struct SecretsRotateTool;
+
+#[async_trait]
+impl Tool for SecretsRotateTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "secrets.rotate".to_string(),
+            description: Some(
+                "Rotates an existing secret to a new value. The previous value is kept for secrets.rollback, and the expiry is replaced (cleared unless given)."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Secret name to rotate"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "New secret value"
+                    },
+                    "expires": {
+                        "type": "string",
+                        "description": "When the new value should be rotated by: a date (2025-06-30), a timestamp, or a period (90d, 12w)"
+                    }
+                },
+                "required": ["key", "value"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let key = arguments
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'key' parameter".to_string()))?;
+
+        let value = arguments
+            .get("value")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'value' parameter".to_string()))?;
+
+        let expires = expiry_argument(&arguments)?;
+
+        let version = state
+            .secrets
+            .rotate(key, value, expires)
+            .ok_or_else(|| ToolError::NotFound(format!("Secret '{}'", key)))?;
+
+        let result = json!({
+            "success": true,
+            "key": key,
+            "version": version,
+            "expires_at": expires.map(|ts| ts.to_rfc3339())
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to list the versions of a secret.
+#[derive(Debug)]
+pub struct SecretsVersionsTool;
+
+#[async_trait]
+impl Tool for SecretsVersionsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "secrets.versions".to_string(),
+            description: Some(
+                "Lists the versions of a secret (not their values), newest first.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Secret name"
+                    }
+                },
+                "required": ["key"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let key = arguments
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'key' parameter".to_string()))?;
+
+        let versions = state
+            .secrets
+            .versions(key)
+            .ok_or_else(|| ToolError::NotFound(format!("Secret '{}'", key)))?;
+
+        let result = json!({
+            "key": key,
+            "versions": versions
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to roll a secret back to a previous value.
+#[derive(Debug)]
+pub struct SecretsRollbackTool;
+
+#[async_trait]
+impl Tool for SecretsRollbackTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "secrets.rollback".to_string(),
+            description: Some(
+                "Restores a previous value of a secret as a new version: the one before the current value, or the given version."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Secret name"
+                    },
+                    "version": {
+                        "type": "integer",
+                        "description": "Version to restore (from secrets.versions)"
+                    }
+                },
+                "required": ["key"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let key = arguments
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'key' parameter".to_string()))?;
+
+        let version = arguments.get("version").and_then(|v| v.as_u64()).map(|v| v as u32);
+
+        let restored = state.secrets.rollback(key, version).map_err(ToolError::InvalidInput)?;
+
+        let result = json!({
+            "success": true,
+            "key": key,
+            "version": restored
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    #[tokio::test]
+    async fn test_rotate_and_roll_back() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.secrets.set("API_TOKEN", "old-token", None);
+
+        SecretsRotateTool
+            .execute(json!({"key": "API_TOKEN", "value": "new-token", "expires": "7d"}), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(state.secrets.get("API_TOKEN"), Some("new-token".to_string()));
+
+        let listed = SecretsListTool.execute(json!({}), state.clone()).await.unwrap();
+        let listed: Value = serde_json::from_str(&crate::tools::middleware::output_text(&listed)).unwrap();
+        assert_eq!(listed["expiring"][0]["key"], "API_TOKEN");
+
+        let versions = SecretsVersionsTool.execute(json!({"key": "API_TOKEN"}), state.clone()).await.unwrap();
+        assert!(crate::tools::middleware::output_text(&versions).contains("\"version\": 1"));
+
+        SecretsRollbackTool.execute(json!({"key": "API_TOKEN"}), state.clone()).await.unwrap();
+        assert_eq!(state.secrets.get("API_TOKEN"), Some("old-token".to_string()));
+
+        let missing = SecretsRotateTool.execute(json!({"key": "NOPE", "value": "x"}), state.clone()).await;
+        assert!(matches!(missing, Err(ToolError::NotFound(_))));
+        let bad_expiry = SecretsRotateTool
+            .execute(json!({"key": "API_TOKEN", "value": "x", "expires": "someday"}), state)
+            .await;
+        assert!(matches!(bad_expiry, Err(ToolError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,346 @@
+//! Audio transcription through Whisper-compatible APIs.
+//!
+//! `llm.transcribe` uploads an audio file from `security.allowed_read_paths`
+//! to the `/audio/transcriptions` endpoint of OpenAI, or of the server set in
+//! `llm.transcription.base_url` (faster-whisper-server, LocalAI, a
+//! whisper.cpp server, ...), and returns the text with segment and,
+//! optionally, word timestamps.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::config::TranscriptionConfig;
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::core::read_allowed_file;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+use super::llm::send;
+
+/// A `multipart/form-data` request body.
+struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    fn new() -> Self {
+        Self { boundary: format!("aegis-{}", uuid::Uuid::new_v4().simple()), body: Vec::new() }
+    }
+
+    fn part(&mut self, disposition: &str, content_type: Option<&str>, data: &[u8]) {
+        self.body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; {}\r\n", self.boundary, disposition).as_bytes());
+        if let Some(content_type) = content_type {
+            self.body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        self.body.extend_from_slice(b"\r\n");
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.part(&format!("name=\"{}\"", name), None, value.as_bytes());
+    }
+
+    fn file(&mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) {
+        // Quotes and line breaks would end the header early
+        let filename: String = filename
+            .chars()
+            .map(|c| if c == '"' || c.is_control() { '_' } else { c })
+            .collect();
+        self.part(&format!("name=\"{}\"; filename=\"{}\"", name, filename), Some(content_type), data);
+    }
+
+    /// Returns the Content-Type header and the finished body.
+    fn finish(mut self) -> (String, Vec<u8>) {
+        self.body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (format!("multipart/form-data; boundary={}", self.boundary), self.body)
+    }
+}
+
+/// Timestamps requested from the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Timestamps {
+    None,
+    Segment,
+    Word,
+}
+
+impl Timestamps {
+    fn parse(value: Option<&str>) -> Result<Self, ToolError> {
+        match value {
+            None | Some("segment") => Ok(Self::Segment),
+            Some("word") => Ok(Self::Word),
+            Some("none") => Ok(Self::None),
+            Some(other) => Err(ToolError::InvalidInput(format!(
+                "Unknown timestamps '{}' (segment, word, none)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Shapes an API response: the text, plus language, duration, and segments
+/// and words with their start and end in seconds, when the server sent them.
+fn summarize_transcription(body: &Value, model: &str) -> Value {
+    let mut result = json!({
+        "text": body.get("text").and_then(|t| t.as_str()).unwrap_or("").trim(),
+        "model": model
+    });
+    for key in ["language", "duration"] {
+        if let Some(value) = body.get(key) {
+            result[key] = value.clone();
+        }
+    }
+    if let Some(segments) = body.get("segments").and_then(|s| s.as_array()) {
+        result["segments"] = segments
+            .iter()
+            .map(|segment| json!({
+                "start": segment.get("start"),
+                "end": segment.get("end"),
+                "text": segment.get("text").and_then(|t| t.as_str()).unwrap_or("").trim()
+            }))
+            .collect();
+    }
+    if let Some(words) = body.get("words").and_then(|w| w.as_array()) {
+        result["words"] = words
+            .iter()
+            .map(|word| json!({
+                "start": word.get("start"),
+                "end": word.get("end"),
+                "word": word.get("word")
+            }))
+            .collect();
+    }
+    result
+}
+
+/// Tool to transcribe audio files.
+#[derive(Debug)]
+pub struct TranscribeTool {
+    config: TranscriptionConfig,
+}
+
+impl TranscribeTool {
+    pub fn new(config: TranscriptionConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for TranscribeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "llm.transcribe".to_string(),
+            description: Some(
+                "Transcribes an audio file (mp3, wav, m4a, ogg, webm, flac...) with a Whisper-compatible API, \
+                 returning the text with segment or word timestamps."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Audio file to transcribe (must be in an allowed read path)"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": format!("Model to use (default: {})", self.config.model)
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Spoken language as an ISO-639-1 code (e.g. 'en'); detected when omitted"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Text to guide spelling and style, e.g. names and jargon used in the audio"
+                    },
+                    "temperature": {
+                        "type": "number",
+                        "description": "Sampling temperature 0-1 (default: 0)"
+                    },
+                    "timestamps": {
+                        "type": "string",
+                        "enum": ["segment", "word", "none"],
+                        "description": "Timestamps to return: per segment, also per word, or none (default: segment)"
+                    },
+                    "api_key": {
+                        "type": "string",
+                        "description": "API key (optional, uses the configured secret if not provided)"
+                    }
+                },
+                "required": ["path"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let path = arguments
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'path' parameter".to_string()))?;
+        let timestamps = Timestamps::parse(arguments.get("timestamps").and_then(|v| v.as_str()))?;
+        let model = arguments
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.config.model);
+
+        let api_key = match arguments.get("api_key").and_then(|v| v.as_str()) {
+            Some(key) => Some(key.to_string()),
+            None => match &self.config.api_key_secret {
+                Some(secret) => Some(state.secrets.get(secret).ok_or_else(|| {
+                    ToolError::InvalidInput(format!("No API key provided. Set {} secret or pass api_key parameter.", secret))
+                })?),
+                None => None,
+            },
+        };
+
+        let file = read_allowed_file(&state.config.security.allowed_read_paths, path, self.config.max_file_bytes)?;
+        if !file.mime_type.starts_with("audio/") && !file.mime_type.starts_with("video/") {
+            return Err(ToolError::InvalidInput(format!("{} is not an audio file ({})", path, file.mime_type)));
+        }
+        let filename = std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "audio".to_string());
+
+        let mut form = Multipart::new();
+        form.file("file", &filename, file.mime_type, &file.data);
+        form.text("model", model);
+        match timestamps {
+            Timestamps::None => form.text("response_format", "json"),
+            Timestamps::Segment => {
+                form.text("response_format", "verbose_json");
+                form.text("timestamp_granularities[]", "segment");
+            }
+            Timestamps::Word => {
+                form.text("response_format", "verbose_json");
+                form.text("timestamp_granularities[]", "segment");
+                form.text("timestamp_granularities[]", "word");
+            }
+        }
+        for key in ["language", "prompt"] {
+            if let Some(value) = arguments.get(key).and_then(|v| v.as_str()) {
+                form.text(key, value);
+            }
+        }
+        if let Some(temperature) = arguments.get("temperature").and_then(|v| v.as_f64()) {
+            form.text("temperature", &temperature.to_string());
+        }
+        let (content_type, body) = form.finish();
+
+        let base_url = self.config.base_url.trim_end_matches('/');
+        state.egress.check_url(ToolCategory::Llm, base_url).map_err(ToolError::PermissionDenied)?;
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Llm).timeout(Duration::from_secs(self.config.timeout_secs)))
+            .map_err(|e| ToolError::Internal(format!("HTTP client error: {}", e)))?;
+        let mut request = client
+            .post(format!("{}/audio/transcriptions", base_url))
+            .header("Content-Type", content_type)
+            .body(body);
+        if let Some(key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+        let body: Value = send(request, "Transcription")
+            .await?
+            .json()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Parse error: {}", e)))?;
+
+        let result = summarize_transcription(&body, model);
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::registry::ToolContent;
+
+    #[test]
+    fn test_summarize_transcription() {
+        let body = json!({
+            "task": "transcribe",
+            "language": "english",
+            "duration": 3.2,
+            "text": " Buy milk. Call Sam. ",
+            "segments": [
+                {"id": 0, "start": 0.0, "end": 1.4, "text": " Buy milk.", "tokens": [1, 2], "avg_logprob": -0.2},
+                {"id": 1, "start": 1.6, "end": 3.2, "text": " Call Sam.", "tokens": [3], "avg_logprob": -0.3}
+            ],
+            "words": [{"word": "Buy", "start": 0.0, "end": 0.3}]
+        });
+        let result = summarize_transcription(&body, "whisper-1");
+        assert_eq!(result["text"], "Buy milk. Call Sam.");
+        assert_eq!(result["language"], "english");
+        assert_eq!(result["segments"][1], json!({"start": 1.6, "end": 3.2, "text": "Call Sam."}));
+        assert_eq!(result["words"][0]["word"], "Buy");
+
+        // Plain `json` responses only carry the text
+        let result = summarize_transcription(&json!({"text": "Hello"}), "whisper-1");
+        assert_eq!(result, json!({"text": "Hello", "model": "whisper-1"}));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_uploads_audio() {
+        use axum::{body::Bytes, http::HeaderMap, routing::post, Json, Router};
+
+        // Echoes what it received, as a transcription
+        let app = Router::new().route(
+            "/v1/audio/transcriptions",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                let content_type = headers["content-type"].to_str().unwrap().to_string();
+                Json(json!({
+                    "text": String::from_utf8_lossy(&body),
+                    "segments": [{"start": 0.0, "end": 1.0, "text": content_type}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("note.mp3"), b"ID3 audio bytes").unwrap();
+        std::fs::write(dir.path().join("note.txt"), "not audio").unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+        let mut config = Config::default();
+        config.security.allowed_read_paths = vec![dir.path().to_path_buf()];
+        config.llm.transcription.base_url = base_url;
+        config.llm.transcription.api_key_secret = None;
+        let tool = TranscribeTool::new(config.llm.transcription.clone());
+        let state = Arc::new(RuntimeState::new(config));
+
+        let output = tool
+            .execute(json!({"path": path("note.mp3"), "language": "en", "timestamps": "word"}), state.clone())
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!("expected text") };
+        let result: Value = serde_json::from_str(text).unwrap();
+        let form = result["text"].as_str().unwrap();
+        assert!(form.contains("filename=\"note.mp3\"\r\nContent-Type: audio/mpeg\r\n\r\nID3 audio bytes"), "{}", form);
+        assert!(form.contains("name=\"model\"\r\n\r\nwhisper-1"));
+        assert!(form.contains("name=\"response_format\"\r\n\r\nverbose_json"));
+        assert!(form.contains("name=\"timestamp_granularities[]\"\r\n\r\nword"));
+        assert!(form.contains("name=\"language\"\r\n\r\nen"));
+        assert!(result["segments"][0]["text"].as_str().unwrap().starts_with("multipart/form-data; boundary="));
+
+        let err = tool.execute(json!({"path": path("note.txt")}), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+        let err = tool.execute(json!({"path": path("note.mp3"), "timestamps": "frame"}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+    }
+}
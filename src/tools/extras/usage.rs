@@ -0,0 +1,88 @@
+//! LLM usage reporting.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::memory::parse_since;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::middleware::{month_start, usage_report, UsageGrouping};
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+/// Tool to report LLM token usage and estimated cost.
+#[derive(Debug)]
+pub struct LlmUsageTool;
+
+#[derive(Deserialize)]
+struct LlmUsageArgs {
+    #[serde(default)]
+    since: Option<String>,
+    #[serde(default)]
+    group_by: UsageGrouping,
+    #[serde(default)]
+    key: Option<String>,
+}
+
+#[async_trait]
+impl Tool for LlmUsageTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "llm.usage".to_string(),
+            description: Some(
+                "Reports tokens and estimated cost of llm.* calls, grouped by model, provider, API key, tool, \
+                 session or day, with the month-to-date spend against the monthly budget."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "since": {
+                        "type": "string",
+                        "description": "Start of the period: a duration (24h, 7d, 4w), a date (2024-01-31), or RFC 3339 (default: start of this month)"
+                    },
+                    "group_by": {
+                        "type": "string",
+                        "enum": ["model", "provider", "key", "tool", "session", "day"],
+                        "description": "How to group usage (default: model)"
+                    },
+                    "key": {
+                        "type": "string",
+                        "description": "Only calls made with this API key (name or hash)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: LlmUsageArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        let now = chrono::Utc::now();
+        let since = match &args.since {
+            Some(since) => parse_since(since, now).map_err(ToolError::InvalidInput)?,
+            None => month_start(now),
+        };
+
+        let report = usage_report(
+            state.memory_store.as_ref(),
+            &state.config.llm.usage,
+            Some(since),
+            args.group_by,
+            args.key.as_deref(),
+            now,
+        )
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&report).unwrap()))
+    }
+}
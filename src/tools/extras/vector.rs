@@ -5,9 +5,163 @@ use serde_json::{json, Value};
 use std::sync::Arc;
 
 use crate::core::RuntimeState;
+use crate::memory::KvOp;
 use crate::protocol::mcp::Tool as ToolDefinition;
 use crate::tools::registry::{Tool, ToolError, ToolOutput};
 
+/// Most vectors `vector.store` takes in one call.
+const MAX_BULK_VECTORS: usize = 10_000;
+
+/// Picks the vector namespace: explicit argument, then the pinned
+/// conversation, then `"default"`.
+fn resolve_namespace(arguments: &Value, state: &RuntimeState) -> String {
+    arguments
+        .get("namespace")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| state.pinned_conversation())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// KV prefix of a namespace's vectors.
+fn vector_prefix(namespace: &str) -> String {
+    format!("vector:{}:", namespace)
+}
+
+/// KV key of a namespace's settings, written by `vector.namespace_create`
+/// or the first `vector.store` into the namespace.
+fn settings_key(namespace: &str) -> String {
+    format!("vector_namespace:{}", namespace)
+}
+
+/// Settings of a namespace, if it has any.
+async fn namespace_settings(state: &RuntimeState, namespace: &str) -> Result<Option<Value>, ToolError> {
+    state
+        .memory_store
+        .kv_get(&settings_key(namespace))
+        .await
+        .map(|entry| entry.map(|entry| entry.value))
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+}
+
+/// Loads every vector of a namespace.
+async fn load_vectors(state: &RuntimeState, namespace: &str) -> Result<Vec<(String, Value)>, ToolError> {
+    let keys = state
+        .memory_store
+        .kv_list(Some(&vector_prefix(namespace)))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let mut vectors = Vec::with_capacity(keys.len());
+    for key in keys {
+        if let Ok(Some(kv)) = state.memory_store.kv_get(&key).await {
+            vectors.push((key, kv.value));
+        }
+    }
+    Ok(vectors)
+}
+
+/// Deletes keys in one atomic batch and returns how many existed.
+async fn delete_keys(state: &RuntimeState, keys: Vec<String>) -> Result<usize, ToolError> {
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let ops = keys.into_iter().map(|key| KvOp::Delete { key }).collect();
+    let results = state
+        .memory_store
+        .kv_batch(ops)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+    Ok(results.iter().filter(|existed| existed.as_bool() == Some(true)).count())
+}
+
+/// Builds the stored record of one vector of a `vector.store` call,
+/// checking its dimensions against the namespace's, if set.
+fn vector_record(item: &Value, namespace: &str, dimensions: Option<u64>) -> Result<(String, Value), ToolError> {
+    let id = item
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidInput("Missing 'id'".to_string()))?;
+
+    let embedding: Vec<f64> = item
+        .get("embedding")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| ToolError::InvalidInput(format!("Missing 'embedding' array for '{}'", id)))?
+        .iter()
+        .filter_map(|v| v.as_f64())
+        .collect();
+    if embedding.is_empty() {
+        return Err(ToolError::InvalidInput(format!("Empty embedding for '{}'", id)));
+    }
+    if let Some(expected) = dimensions.filter(|&d| d != embedding.len() as u64) {
+        return Err(ToolError::InvalidInput(format!(
+            "Embedding of '{}' has {} dimensions; namespace '{}' expects {}",
+            id,
+            embedding.len(),
+            namespace,
+            expected
+        )));
+    }
+
+    let value = json!({
+        "id": id,
+        "text": item.get("text").and_then(|v| v.as_str()).unwrap_or(""),
+        "dimensions": embedding.len(),
+        "embedding": embedding,
+        "metadata": item.get("metadata").cloned().unwrap_or(json!({})),
+        "namespace": namespace
+    });
+    Ok((format!("{}{}", vector_prefix(namespace), id), value))
+}
+
+/// Settings to record after storing embeddings of `dimensions` from `model`
+/// into a namespace, if they add to what it has: a namespace without
+/// settings gets them, and one created without a size or model gets those
+/// filled in. Fails if the namespace holds embeddings of another model.
+fn recorded_settings(
+    settings: Option<&Value>,
+    namespace: &str,
+    dimensions: usize,
+    model: Option<&str>,
+) -> Result<Option<Value>, ToolError> {
+    let Some(settings) = settings else {
+        return Ok(Some(json!({
+            "namespace": namespace,
+            "dimensions": dimensions,
+            "model": model,
+            "description": null,
+            "created_at": chrono::Utc::now().to_rfc3339()
+        })));
+    };
+
+    let recorded_model = settings["model"].as_str();
+    if let (Some(recorded), Some(model)) = (recorded_model, model) {
+        if recorded != model {
+            return Err(ToolError::InvalidInput(format!(
+                "Namespace '{}' holds embeddings from model '{}', not '{}'",
+                namespace, recorded, model
+            )));
+        }
+    }
+    if settings["dimensions"].is_u64() && (recorded_model.is_some() || model.is_none()) {
+        return Ok(None);
+    }
+
+    let mut settings = settings.clone();
+    if !settings["dimensions"].is_u64() {
+        settings["dimensions"] = json!(dimensions);
+    }
+    if recorded_model.is_none() && model.is_some() {
+        settings["model"] = json!(model);
+    }
+    Ok(Some(settings))
+}
+
+/// Whether a stored vector's metadata has every field of `filter`.
+fn metadata_matches(vector: &Value, filter: &serde_json::Map<String, Value>) -> bool {
+    filter.iter().all(|(key, expected)| vector["metadata"].get(key) == Some(expected))
+}
+
 /// Tool to store a vector embedding.
 #[derive(Debug)]
 pub struct VectorStoreTool;
@@ -18,7 +172,9 @@ impl Tool for VectorStoreTool {
         ToolDefinition {
             name: "vector.store".to_string(),
             description: Some(
-                "Stores a text with its vector embedding for semantic search. Use with llm.embed to generate embeddings."
+                "Stores a text with its vector embedding for semantic search, or many at once with 'vectors'. \
+                 Use with llm.embed to generate embeddings. The first store into a namespace records its \
+                 embedding size (and model), and later embeddings must match."
                     .to_string(),
             ),
             input_schema: json!({
@@ -41,13 +197,32 @@ impl Tool for VectorStoreTool {
                         "type": "object",
                         "description": "Optional metadata"
                     },
+                    "vectors": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "id": {"type": "string"},
+                                "text": {"type": "string"},
+                                "embedding": {"type": "array", "items": {"type": "number"}},
+                                "metadata": {"type": "object"}
+                            },
+                            "required": ["id", "embedding"]
+                        },
+                        "description": "Vectors to store in one atomic write, instead of id/text/embedding/metadata"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Embedding model (the 'model' llm.embed returned); recorded for the namespace, \
+                                        which then refuses other models"
+                    },
                     "namespace": {
                         "type": "string",
-                        "description": "Namespace/collection (default: 'default')"
+                        "description": "Namespace/collection (default: pinned conversation or 'default')"
                     }
-                },
-                "required": ["id", "embedding"]
+                }
             }),
+            output_schema: None,
         }
     }
 
@@ -56,61 +231,64 @@ impl Tool for VectorStoreTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let id = arguments
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'id'".to_string()))?;
-
-        let embedding = arguments
-            .get("embedding")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'embedding' array".to_string()))?;
-
-        let text = arguments
-            .get("text")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-
-        let metadata = arguments.get("metadata").cloned().unwrap_or(json!({}));
-
-        let namespace = arguments
-            .get("namespace")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default");
-
-        // Convert embedding to f64 vec
-        let embedding_vec: Vec<f64> = embedding
-            .iter()
-            .filter_map(|v| v.as_f64())
-            .collect();
-
-        if embedding_vec.is_empty() {
-            return Err(ToolError::InvalidInput("Empty embedding".to_string()));
+        let namespace = resolve_namespace(&arguments, &state);
+        let settings = namespace_settings(&state, &namespace).await?;
+        let model = arguments.get("model").and_then(|v| v.as_str());
+        let mut dimensions = settings.as_ref().and_then(|settings| settings["dimensions"].as_u64());
+
+        let single = arguments.get("vectors").is_none();
+        let items = match arguments.get("vectors") {
+            None => std::slice::from_ref(&arguments),
+            Some(items) => items
+                .as_array()
+                .ok_or_else(|| ToolError::InvalidInput("'vectors' must be an array".to_string()))?,
+        };
+        if items.is_empty() {
+            return Err(ToolError::InvalidInput("'vectors' is empty".to_string()));
+        }
+        if items.len() > MAX_BULK_VECTORS {
+            return Err(ToolError::InvalidInput(format!("At most {} vectors per call", MAX_BULK_VECTORS)));
         }
 
-        // Store in memory store
-        let key = format!("vector:{}:{}", namespace, id);
-        let value = json!({
-            "id": id,
-            "text": text,
-            "embedding": embedding_vec,
-            "metadata": metadata,
-            "namespace": namespace,
-            "dimensions": embedding_vec.len()
-        });
+        // Validate everything first, so a bad vector stores nothing. Without
+        // recorded dimensions, the first vector sets them for the rest.
+        let mut ops = Vec::with_capacity(items.len() + 1);
+        for (index, item) in items.iter().enumerate() {
+            let (key, value) = vector_record(item, &namespace, dimensions).map_err(|e| match e {
+                ToolError::InvalidInput(message) if !single => {
+                    ToolError::InvalidInput(format!("vectors[{}]: {}", index, message))
+                }
+                e => e,
+            })?;
+            dimensions = value["dimensions"].as_u64();
+            ops.push(KvOp::Set { key, value, ttl_secs: None });
+        }
+        let stored_dimensions = dimensions.unwrap_or_default() as usize;
+        if let Some(value) = recorded_settings(settings.as_ref(), &namespace, stored_dimensions, model)? {
+            ops.push(KvOp::Set { key: settings_key(&namespace), value, ttl_secs: None });
+        }
 
         state
             .memory_store
-            .kv_set(&key, value, None)
+            .kv_batch(ops)
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
-        let result = json!({
-            "success": true,
-            "id": id,
-            "namespace": namespace,
-            "dimensions": embedding_vec.len()
-        });
+        let result = if single {
+            json!({
+                "success": true,
+                "id": arguments["id"],
+                "namespace": namespace,
+                "dimensions": stored_dimensions
+            })
+        } else {
+            json!({
+                "success": true,
+                "stored": items.len(),
+                "namespace": namespace,
+                "dimensions": stored_dimensions
+            })
+        };
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
@@ -143,7 +321,7 @@ impl Tool for VectorSearchTool {
                     },
                     "namespace": {
                         "type": "string",
-                        "description": "Namespace to search (default: 'default')"
+                        "description": "Namespace to search (default: pinned conversation or 'default')"
                     },
                     "threshold": {
                         "type": "number",
@@ -152,6 +330,7 @@ impl Tool for VectorSearchTool {
                 },
                 "required": ["embedding"]
             }),
+            output_schema: None,
         }
     }
 
@@ -179,10 +358,7 @@ impl Tool for VectorSearchTool {
             .and_then(|v| v.as_u64())
             .unwrap_or(5) as usize;
 
-        let namespace = arguments
-            .get("namespace")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default");
+        let namespace = resolve_namespace(&arguments, &state);
 
         let threshold = arguments
             .get("threshold")
@@ -190,10 +366,9 @@ impl Tool for VectorSearchTool {
             .unwrap_or(0.0);
 
         // Get all vectors in namespace
-        let prefix = format!("vector:{}:", namespace);
         let keys = state
             .memory_store
-            .kv_list(Some(&prefix))
+            .kv_list(Some(&vector_prefix(&namespace)))
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
@@ -253,7 +428,10 @@ impl Tool for VectorDeleteTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "vector.delete".to_string(),
-            description: Some("Deletes a stored vector.".to_string()),
+            description: Some(
+                "Deletes stored vectors: one by id, several by ids, or every vector whose metadata matches a filter."
+                    .to_string(),
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -261,13 +439,22 @@ impl Tool for VectorDeleteTool {
                         "type": "string",
                         "description": "Vector ID to delete"
                     },
+                    "ids": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Vector IDs to delete"
+                    },
+                    "filter": {
+                        "type": "object",
+                        "description": "Delete the vectors whose metadata has all of these fields, e.g. {\"source\": \"notes.md\"}"
+                    },
                     "namespace": {
                         "type": "string",
-                        "description": "Namespace (default: 'default')"
+                        "description": "Namespace (default: pinned conversation or 'default')"
                     }
-                },
-                "required": ["id"]
+                }
             }),
+            output_schema: None,
         }
     }
 
@@ -276,27 +463,50 @@ impl Tool for VectorDeleteTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let id = arguments
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'id'".to_string()))?;
-
-        let namespace = arguments
-            .get("namespace")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default");
-
-        let key = format!("vector:{}:{}", namespace, id);
+        let namespace = resolve_namespace(&arguments, &state);
+        let prefix = vector_prefix(&namespace);
+
+        if let Some(id) = arguments.get("id").and_then(|v| v.as_str()) {
+            state
+                .memory_store
+                .kv_delete(&format!("{}{}", prefix, id))
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+            let result = json!({
+                "success": true,
+                "id": id,
+                "namespace": namespace
+            });
+            return Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()));
+        }
 
-        state
-            .memory_store
-            .kv_delete(&key)
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let keys: Vec<String> = if let Some(ids) = arguments.get("ids").and_then(|v| v.as_array()) {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .map(|id| format!("{}{}", prefix, id))
+                .collect()
+        } else if let Some(filter) = arguments.get("filter").and_then(|v| v.as_object()) {
+            if filter.is_empty() {
+                return Err(ToolError::InvalidInput(
+                    "Empty 'filter'; use vector.namespace_delete to delete a whole namespace".to_string(),
+                ));
+            }
+            load_vectors(&state, &namespace)
+                .await?
+                .into_iter()
+                .filter(|(_, vector)| metadata_matches(vector, filter))
+                .map(|(key, _)| key)
+                .collect()
+        } else {
+            return Err(ToolError::InvalidInput("One of 'id', 'ids' or 'filter' is required".to_string()));
+        };
+
+        let deleted = delete_keys(&state, keys).await?;
 
         let result = json!({
             "success": true,
-            "id": id,
+            "deleted": deleted,
             "namespace": namespace
         });
 
@@ -319,10 +529,11 @@ impl Tool for VectorListTool {
                 "properties": {
                     "namespace": {
                         "type": "string",
-                        "description": "Namespace (default: 'default')"
+                        "description": "Namespace (default: pinned conversation or 'default')"
                     }
                 }
             }),
+            output_schema: None,
         }
     }
 
@@ -331,12 +542,9 @@ impl Tool for VectorListTool {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let namespace = arguments
-            .get("namespace")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default");
+        let namespace = resolve_namespace(&arguments, &state);
 
-        let prefix = format!("vector:{}:", namespace);
+        let prefix = vector_prefix(&namespace);
         let keys = state
             .memory_store
             .kv_list(Some(&prefix))
@@ -359,6 +567,206 @@ impl Tool for VectorListTool {
     }
 }
 
+/// Tool to create a vector namespace with fixed settings.
+#[derive(Debug)]
+pub struct VectorNamespaceCreateTool;
+
+#[async_trait]
+impl Tool for VectorNamespaceCreateTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "vector.namespace_create".to_string(),
+            description: Some(
+                "Creates a vector namespace. With 'dimensions' or 'model', vector.store rejects embeddings of any \
+                 other size or model, so vectors from different embedding models don't mix. Namespaces are also \
+                 created implicitly by vector.store, which records the size (and model) of the first embeddings."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "Namespace name"
+                    },
+                    "dimensions": {
+                        "type": "integer",
+                        "description": "Required embedding size"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Required embedding model, as llm.embed reports it"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "What the namespace holds"
+                    }
+                },
+                "required": ["namespace"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let namespace = arguments
+            .get("namespace")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'namespace'".to_string()))?;
+        let dimensions = arguments.get("dimensions").and_then(|v| v.as_u64());
+        if dimensions == Some(0) {
+            return Err(ToolError::InvalidInput("'dimensions' must be positive".to_string()));
+        }
+
+        if namespace_settings(&state, namespace).await?.is_some() {
+            return Err(ToolError::InvalidInput(format!("Namespace '{}' already exists", namespace)));
+        }
+
+        let settings = json!({
+            "namespace": namespace,
+            "dimensions": dimensions,
+            "model": arguments.get("model").and_then(|v| v.as_str()),
+            "description": arguments.get("description").and_then(|v| v.as_str()),
+            "created_at": chrono::Utc::now().to_rfc3339()
+        });
+        state
+            .memory_store
+            .kv_set(&settings_key(namespace), settings.clone(), None)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let result = json!({
+            "success": true,
+            "namespace": settings
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to delete a vector namespace with all its vectors.
+#[derive(Debug)]
+pub struct VectorNamespaceDeleteTool;
+
+#[async_trait]
+impl Tool for VectorNamespaceDeleteTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "vector.namespace_delete".to_string(),
+            description: Some("Deletes a vector namespace with all its vectors and settings.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "Namespace to delete"
+                    }
+                },
+                "required": ["namespace"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        // No default here: deleting the pinned or default namespace by accident loses data
+        let namespace = arguments
+            .get("namespace")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'namespace'".to_string()))?;
+
+        let mut keys = state
+            .memory_store
+            .kv_list(Some(&vector_prefix(namespace)))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let vectors = keys.len();
+        keys.push(settings_key(namespace));
+        let existed = delete_keys(&state, keys).await?;
+
+        let result = json!({
+            "success": true,
+            "namespace": namespace,
+            "existed": existed > 0,
+            "deleted_vectors": vectors
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to report the size and settings of a vector namespace.
+#[derive(Debug)]
+pub struct VectorNamespaceStatsTool;
+
+#[async_trait]
+impl Tool for VectorNamespaceStatsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "vector.namespace_stats".to_string(),
+            description: Some(
+                "Reports a vector namespace's settings, vector count, the embedding sizes in use and the size \
+                 of the stored texts."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "Namespace (default: pinned conversation or 'default')"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let namespace = resolve_namespace(&arguments, &state);
+        let settings = namespace_settings(&state, &namespace).await?;
+        let vectors = load_vectors(&state, &namespace).await?;
+
+        let mut dimensions = std::collections::BTreeMap::<u64, usize>::new();
+        let mut text_chars = 0;
+        for (_, vector) in &vectors {
+            if let Some(size) = vector["dimensions"].as_u64() {
+                *dimensions.entry(size).or_default() += 1;
+            }
+            text_chars += vector["text"].as_str().map_or(0, |text| text.chars().count());
+        }
+
+        let result = json!({
+            "namespace": namespace,
+            "created": settings.is_some(),
+            "dimensions": settings.as_ref().and_then(|s| s["dimensions"].as_u64()),
+            "model": settings.as_ref().and_then(|s| s["model"].as_str()),
+            "description": settings.as_ref().and_then(|s| s["description"].as_str()),
+            "created_at": settings.as_ref().and_then(|s| s["created_at"].as_str()),
+            "vectors": vectors.len(),
+            "embedding_sizes": dimensions
+                .iter()
+                .map(|(size, count)| json!({ "dimensions": size, "vectors": count }))
+                .collect::<Vec<_>>(),
+            "text_chars": text_chars
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
 /// Compute cosine similarity between two vectors.
 fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
     if a.len() != b.len() || a.is_empty() {
@@ -376,3 +784,84 @@ fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
     dot_product / (norm_a * norm_b)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+
+    async fn run(tool: &dyn Tool, args: Value, state: &Arc<RuntimeState>) -> Result<Value, ToolError> {
+        let output = tool.execute(args, state.clone()).await?;
+        let ToolContent::Text { text } = &output.content[0] else { panic!() };
+        Ok(serde_json::from_str(text).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_and_bulk_operations() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        run(&VectorNamespaceCreateTool, json!({"namespace": "kb", "dimensions": 2}), &state).await.unwrap();
+        assert!(run(&VectorNamespaceCreateTool, json!({"namespace": "kb"}), &state).await.is_err());
+
+        let vectors: Vec<Value> = (0..4)
+            .map(|i| json!({"id": format!("v{}", i), "embedding": [1.0, i as f64], "metadata": {"source": if i < 3 { "a.md" } else { "b.md" }}}))
+            .collect();
+        let stored = run(&VectorStoreTool, json!({"namespace": "kb", "vectors": vectors}), &state).await.unwrap();
+        assert_eq!(stored["stored"], 4);
+
+        // A wrongly sized embedding fails the whole batch
+        let bad = json!({"namespace": "kb", "vectors": [{"id": "x", "embedding": [1.0, 2.0]}, {"id": "y", "embedding": [1.0]}]});
+        let err = run(&VectorStoreTool, bad, &state).await.unwrap_err();
+        assert!(err.to_string().contains("vectors[1]"), "{}", err);
+
+        let stats = run(&VectorNamespaceStatsTool, json!({"namespace": "kb"}), &state).await.unwrap();
+        assert_eq!((stats["vectors"].as_u64(), stats["dimensions"].as_u64()), (Some(4), Some(2)));
+
+        let deleted = run(&VectorDeleteTool, json!({"namespace": "kb", "filter": {"source": "a.md"}}), &state).await.unwrap();
+        assert_eq!(deleted["deleted"], 3);
+        let deleted = run(&VectorDeleteTool, json!({"namespace": "kb", "ids": ["v3", "missing"]}), &state).await.unwrap();
+        assert_eq!(deleted["deleted"], 1);
+
+        run(&VectorStoreTool, json!({"namespace": "kb", "id": "v9", "embedding": [0.5, 0.5]}), &state).await.unwrap();
+        let dropped = run(&VectorNamespaceDeleteTool, json!({"namespace": "kb"}), &state).await.unwrap();
+        assert_eq!(dropped["deleted_vectors"], 1);
+        let stats = run(&VectorNamespaceStatsTool, json!({"namespace": "kb"}), &state).await.unwrap();
+        assert_eq!((stats["vectors"].as_u64(), stats["created"].as_bool()), (Some(0), Some(false)));
+    }
+
+    #[tokio::test]
+    async fn test_store_records_namespace_settings() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        let args = json!({"namespace": "notes", "model": "AllMiniLML6V2", "vectors": [
+            {"id": "a", "embedding": [1.0, 0.0, 0.0]},
+            {"id": "b", "embedding": [0.0, 1.0]}
+        ]});
+        let err = run(&VectorStoreTool, args, &state).await.unwrap_err();
+        assert!(err.to_string().contains("vectors[1]"), "{}", err);
+
+        let args = json!({"namespace": "notes", "model": "AllMiniLML6V2", "id": "a", "embedding": [1.0, 0.0, 0.0]});
+        run(&VectorStoreTool, args, &state).await.unwrap();
+        let stats = run(&VectorNamespaceStatsTool, json!({"namespace": "notes"}), &state).await.unwrap();
+        assert_eq!(stats["dimensions"], 3);
+        assert_eq!(stats["model"], "AllMiniLML6V2");
+
+        let other_model = json!({"namespace": "notes", "model": "text-embedding-3-small", "id": "b", "embedding": [0.0, 1.0, 0.0]});
+        assert!(run(&VectorStoreTool, other_model, &state).await.is_err());
+        let other_size = json!({"namespace": "notes", "id": "b", "embedding": [0.0, 1.0]});
+        assert!(run(&VectorStoreTool, other_size, &state).await.is_err());
+
+        // A namespace created without settings gets them on the first store
+        run(&VectorNamespaceCreateTool, json!({"namespace": "docs"}), &state).await.unwrap();
+        run(&VectorStoreTool, json!({"namespace": "docs", "id": "a", "embedding": [0.5, 0.5]}), &state).await.unwrap();
+        let stats = run(&VectorNamespaceStatsTool, json!({"namespace": "docs"}), &state).await.unwrap();
+        assert_eq!((stats["dimensions"].as_u64(), stats["model"].as_str()), (Some(2), None));
+    }
+}
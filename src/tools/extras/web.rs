@@ -2,12 +2,23 @@
 
 use async_trait::async_trait;
 use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
 
+use crate::core::egress::ToolCategory;
+use crate::core::http_clients::ClientProfile;
+use crate::core::http_cache::{CacheMode, CacheStatus, HttpCache};
 use crate::core::RuntimeState;
+use crate::handlers::handle_tools_call_with_context;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::registry::{Tool, ToolError, ToolOutput};
+use crate::tools::caller;
+use crate::tools::registry::{Tool, ToolContent, ToolError, ToolOutput};
+use crate::tools::stream::{self, ProgressReporter};
 
 /// Tool to fetch and extract content from web pages.
 #[derive(Debug)]
@@ -19,7 +30,9 @@ impl Tool for WebExtractTool {
         ToolDefinition {
             name: "web.extract".to_string(),
             description: Some(
-                "Fetches a web page and extracts clean text content. Removes HTML tags, scripts, and styles."
+                "Fetches a web page and extracts clean text, markdown, HTML, links, attribute values or tables (as JSON). \
+                 Scripts and styles are removed; a CSS selector narrows extraction to matching elements. \
+                 With render: true the page is loaded in headless Chrome first, for JavaScript-heavy sites."
                     .to_string(),
             ),
             input_schema: json!({
@@ -31,27 +44,56 @@ impl Tool for WebExtractTool {
                     },
                     "selector": {
                         "type": "string",
-                        "description": "CSS-like selector hint (e.g., 'article', 'main', 'body'). Not full CSS - just element name."
+                        "description": "CSS selector (e.g., 'article', '.post-body', '#main table', 'a[href$=\".pdf\"]'). Default: the page's main content (article, main or body)"
+                    },
+                    "attribute": {
+                        "type": "string",
+                        "description": "Return this attribute of each matching element instead of content (e.g., 'href', 'src', 'data-id')"
                     },
                     "format": {
                         "type": "string",
-                        "enum": ["text", "html", "markdown", "links"],
-                        "description": "Output format (default: text)"
+                        "enum": ["text", "html", "markdown", "links", "table"],
+                        "description": "Output format (default: text). 'table' converts tables to JSON rows keyed by header"
                     },
                     "max_length": {
                         "type": "integer",
                         "description": "Max characters to return (default: 50000)"
+                    },
+                    "cache": {
+                        "type": "boolean",
+                        "description": "Serve the page from the response cache when fresh (default: the http_client.cache.enabled setting)"
+                    },
+                    "cache_ttl": {
+                        "type": "integer",
+                        "description": "Cache the page for this many seconds, ignoring Cache-Control"
+                    },
+                    "render": {
+                        "type": "boolean",
+                        "description": "Render the page in headless Chrome so JavaScript runs first (needs the 'browser' feature; default: false)"
+                    },
+                    "wait_for": {
+                        "type": "string",
+                        "description": "With render: CSS selector to wait for before extracting"
+                    },
+                    "wait_ms": {
+                        "type": "integer",
+                        "description": "With render: extra milliseconds to let scripts settle (default: 0)"
+                    },
+                    "screenshot": {
+                        "type": "boolean",
+                        "description": "With render: also return a full-page PNG screenshot as image content"
                     }
                 },
                 "required": ["url"]
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let url = arguments
             .get("url")
@@ -70,242 +112,852 @@ impl Tool for WebExtractTool {
 
         let selector = arguments
             .get("selector")
+            .and_then(|v| v.as_str())
+            .map(|s| parse_selector(s).map(|parsed| (s, parsed)))
+            .transpose()?;
+
+        let attribute = arguments
+            .get("attribute")
             .and_then(|v| v.as_str());
 
-        // Fetch the page
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (compatible; NexusBot/1.0)")
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| ToolError::ExecutionFailed(format!("Client error: {}", e)))?;
+        let render = arguments.get("render").and_then(|v| v.as_bool()).unwrap_or(false);
+        let (base_url, content_type, html, screenshot, cache_status) = if render {
+            let (page_url, html, screenshot) = render_page(&state, url, &arguments).await?;
+            let base_url = Url::parse(&page_url)
+                .map_err(|e| ToolError::ExecutionFailed(format!("Invalid page URL: {}", e)))?;
+            (base_url, "text/html".to_string(), html, screenshot, CacheStatus::Bypass)
+        } else {
+            let mode = CacheMode::from_args(&state.config.http_client.cache, &arguments);
+            let (base_url, content_type, html, cache_status) = fetch_page(&state, url, mode).await?;
+            (base_url, content_type, html, None, cache_status)
+        };
 
-        let response = client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Fetch error: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            return Err(ToolError::ExecutionFailed(format!(
-                "HTTP {}: {}",
-                status.as_u16(),
-                status.canonical_reason().unwrap_or("Unknown")
-            )));
+        let document = Html::parse_document(&html);
+        let mut result = json!({
+            "url": url,
+            "content_type": content_type,
+            "selector": selector.as_ref().map(|(raw, _)| raw)
+        });
+        if cache_status != CacheStatus::Bypass {
+            result["cache"] = json!(cache_status);
+        }
+        let extracted = extract(&document, &base_url, selector.as_ref().map(|(_, parsed)| parsed), attribute, format, max_length);
+        if let (Value::Object(result), Value::Object(extracted)) = (&mut result, extracted) {
+            result.extend(extracted);
+        }
+
+        let mut output = ToolOutput::text(serde_json::to_string_pretty(&result).unwrap());
+        if let Some(png) = screenshot {
+            output.content.push(ToolContent::Image {
+                data: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png),
+                mime_type: "image/png".to_string(),
+            });
         }
+        Ok(output)
+    }
+}
 
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("")
-            .to_string();
+/// Fetches raw HTML, through the response cache when `mode` allows.
+/// Returns the final URL (after redirects), content type, body and where
+/// the response came from.
+async fn fetch_page(
+    state: &RuntimeState,
+    url: &str,
+    mode: CacheMode,
+) -> Result<(Url, String, String, CacheStatus), ToolError> {
+    state.egress.check_url(ToolCategory::Web, url).map_err(ToolError::PermissionDenied)?;
+    let client = state
+        .http_clients
+        .get(&ClientProfile::new(ToolCategory::Web).user_agent(USER_AGENT).timeout(Duration::from_secs(30)))
+        .map_err(|e| ToolError::ExecutionFailed(format!("Client error: {}", e)))?;
+
+    let (response, cache_status) = state
+        .http_cache
+        .send(mode, &HttpCache::key("GET", url, &[]), url, client.get(url))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Fetch error: {}", e)))?;
+
+    let status = reqwest::StatusCode::from_u16(response.status)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Fetch error: {}", e)))?;
+    if !status.is_success() {
+        return Err(ToolError::ExecutionFailed(format!(
+            "HTTP {}: {}",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("Unknown")
+        )));
+    }
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Read error: {}", e)))?;
+    // Redirects may have moved the page; resolve links against where it ended up
+    let base_url = Url::parse(&response.url)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Invalid page URL: {}", e)))?;
+
+    let content_type = response.header("content-type").unwrap_or("").to_string();
+    let html = String::from_utf8_lossy(&response.body).into_owned();
+
+    Ok((base_url, content_type, html, cache_status))
+}
 
-        // Extract based on selector
-        let html_to_parse = if let Some(sel) = selector {
-            extract_by_tag(&html, sel).unwrap_or_else(|| html.clone())
+/// Renders the page in headless Chrome. Returns the final URL, the
+/// rendered DOM and the screenshot, if one was asked for.
+#[cfg(feature = "browser")]
+async fn render_page(
+    state: &RuntimeState,
+    url: &str,
+    arguments: &Value,
+) -> Result<(String, String, Option<Vec<u8>>), ToolError> {
+    let options = super::browser::RenderOptions {
+        wait_for: arguments.get("wait_for").and_then(|v| v.as_str()).map(String::from),
+        wait_ms: arguments.get("wait_ms").and_then(|v| v.as_u64()).unwrap_or(0),
+        screenshot: arguments.get("screenshot").and_then(|v| v.as_bool()).unwrap_or(false),
+    };
+    state.egress.check_url(ToolCategory::Web, url).map_err(ToolError::PermissionDenied)?;
+    let page = super::browser::render(&state.config.browser, url, &options).await?;
+    Ok((page.url, page.html, page.screenshot))
+}
+
+#[cfg(not(feature = "browser"))]
+async fn render_page(
+    _state: &RuntimeState,
+    _url: &str,
+    _arguments: &Value,
+) -> Result<(String, String, Option<Vec<u8>>), ToolError> {
+    Err(ToolError::InvalidInput(
+        "render: true needs Aegis built with the 'browser' feature".to_string(),
+    ))
+}
+
+/// User-Agent header of page requests.
+const USER_AGENT: &str = "Mozilla/5.0 (compatible; NexusBot/1.0)";
+
+/// User-agent token matched against robots.txt groups.
+const ROBOTS_AGENT: &str = "nexusbot";
+
+/// Upper bound on pages per crawl.
+const MAX_CRAWL_PAGES: usize = 200;
+
+/// Longest robots.txt Crawl-delay honored, in milliseconds.
+const MAX_CRAWL_DELAY_MS: u64 = 10_000;
+
+/// Tool to crawl a site from a start URL.
+#[derive(Debug)]
+pub struct WebCrawlTool;
+
+#[derive(Deserialize)]
+struct WebCrawlArgs {
+    url: String,
+    #[serde(default = "default_crawl_depth")]
+    max_depth: usize,
+    #[serde(default = "default_crawl_pages")]
+    max_pages: usize,
+    #[serde(default)]
+    allowed_domains: Vec<String>,
+    #[serde(default)]
+    path_prefix: Option<String>,
+    #[serde(default)]
+    selector: Option<String>,
+    #[serde(default = "default_page_chars")]
+    max_chars_per_page: usize,
+    #[serde(default = "default_true")]
+    respect_robots: bool,
+    #[serde(default = "default_crawl_delay")]
+    delay_ms: u64,
+    #[serde(default)]
+    ingest: bool,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    embed_tool: Option<String>,
+}
+
+fn default_crawl_depth() -> usize { 2 }
+fn default_crawl_pages() -> usize { 20 }
+fn default_page_chars() -> usize { 5000 }
+fn default_crawl_delay() -> u64 { 250 }
+fn default_true() -> bool { true }
+
+#[async_trait]
+impl Tool for WebCrawlTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "web.crawl".to_string(),
+            description: Some(
+                "Crawls a site from a start URL, following links breadth-first up to a depth and page limit within \
+                 allowed domains. Honors robots.txt, skips duplicate URLs, and returns each page's text or ingests it \
+                 into the vector store (via rag.ingest)."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "Start URL"
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "description": "Link hops to follow from the start page (default: 2)"
+                    },
+                    "max_pages": {
+                        "type": "integer",
+                        "description": "Max pages to fetch (default: 20, max: 200)"
+                    },
+                    "allowed_domains": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Domains to stay within, subdomains included (default: the start URL's host)"
+                    },
+                    "path_prefix": {
+                        "type": "string",
+                        "description": "Only follow URLs whose path starts with this (e.g., '/docs/')"
+                    },
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for each page's content (default: article, main or body)"
+                    },
+                    "max_chars_per_page": {
+                        "type": "integer",
+                        "description": "Max characters of text per page (default: 5000)"
+                    },
+                    "respect_robots": {
+                        "type": "boolean",
+                        "description": "Skip URLs disallowed by robots.txt and honor Crawl-delay (default: true)"
+                    },
+                    "delay_ms": {
+                        "type": "integer",
+                        "description": "Pause between requests in milliseconds (default: 250)"
+                    },
+                    "ingest": {
+                        "type": "boolean",
+                        "description": "Ingest each page with rag.ingest instead of returning its text"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Vector namespace for ingested pages (default: default)"
+                    },
+                    "embed_tool": {
+                        "type": "string",
+                        "description": "Embedding tool for ingestion (default: llm.embed)"
+                    }
+                },
+                "required": ["url"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let args: WebCrawlArgs = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+        let start = Url::parse(&args.url)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid url: {}", e)))?;
+        if !matches!(start.scheme(), "http" | "https") {
+            return Err(ToolError::InvalidInput("url must be http(s)".to_string()));
+        }
+        if let Some(selector) = &args.selector {
+            parse_selector(selector)?;
+        }
+        let allowed_domains: Vec<String> = if args.allowed_domains.is_empty() {
+            start.host_str().map(|host| vec![host.to_string()]).unwrap_or_default()
         } else {
-            // Try to find main content
-            extract_by_tag(&html, "article")
-                .or_else(|| extract_by_tag(&html, "main"))
-                .or_else(|| extract_by_tag(&html, "body"))
-                .unwrap_or_else(|| html.clone())
+            args.allowed_domains.iter().map(|d| d.trim_start_matches("*.").to_lowercase()).collect()
         };
+        let max_pages = args.max_pages.clamp(1, MAX_CRAWL_PAGES);
+        state.egress.check_url(ToolCategory::Web, start.as_str()).map_err(ToolError::PermissionDenied)?;
+
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Web).user_agent(USER_AGENT).timeout(Duration::from_secs(30)))
+            .map_err(|e| ToolError::ExecutionFailed(format!("Client error: {}", e)))?;
+
+        let mut queue = VecDeque::from([(normalize_url(start.clone()), 0)]);
+        let mut seen = HashSet::from([normalize_url(start).to_string()]);
+        let mut robots: HashMap<String, Robots> = HashMap::new();
+        let mut pages = Vec::new();
+        let mut errors = Vec::new();
+        let mut blocked_by_robots = 0;
+        let mut ingested = 0;
+        let mut fetched = 0;
+        let reporter = ProgressReporter::current();
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if fetched >= max_pages {
+                break;
+            }
+
+            // Links may point anywhere, including private addresses
+            if let Err(e) = state.egress.check_url(ToolCategory::Web, url.as_str()) {
+                errors.push(json!({ "url": url.as_str(), "error": e }));
+                continue;
+            }
 
-        let result = match format {
-            "html" => {
-                let mut output = html_to_parse;
-                if output.len() > max_length {
-                    output.truncate(max_length);
-                    output.push_str("...[truncated]");
+            let mut delay_ms = args.delay_ms;
+            if args.respect_robots {
+                let origin = url.origin().ascii_serialization();
+                if !robots.contains_key(&origin) {
+                    let rules = fetch_robots(&client, &url).await;
+                    robots.insert(origin.clone(), rules);
+                }
+                let rules = &robots[&origin];
+                if !rules.allows(url.path()) {
+                    blocked_by_robots += 1;
+                    continue;
+                }
+                if let Some(crawl_delay) = rules.crawl_delay_ms {
+                    delay_ms = delay_ms.max(crawl_delay.min(MAX_CRAWL_DELAY_MS));
                 }
-                json!({
-                    "url": url,
-                    "content_type": content_type,
-                    "format": "html",
-                    "content": output
-                })
             }
-            "links" => {
-                let links = extract_links(&html, url);
-                json!({
-                    "url": url,
-                    "format": "links",
-                    "count": links.len(),
-                    "links": links
-                })
+
+            if fetched > 0 && delay_ms > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
             }
-            "markdown" => {
-                let text = html_to_text(&html_to_parse);
-                let markdown = text_to_markdown(&text);
-                let mut output = markdown;
-                if output.len() > max_length {
-                    output.truncate(max_length);
-                    output.push_str("\n\n...[truncated]");
+            reporter.report(fetched as u64, Some(max_pages as u64), format!("Fetching {}", url));
+            fetched += 1;
+
+            let response = match client.get(url.clone()).send().await {
+                Ok(response) if response.status().is_success() => response,
+                Ok(response) => {
+                    errors.push(json!({ "url": url.as_str(), "error": format!("HTTP {}", response.status().as_u16()) }));
+                    continue;
                 }
-                json!({
-                    "url": url,
-                    "format": "markdown",
-                    "length": output.len(),
-                    "content": output
-                })
+                Err(e) => {
+                    errors.push(json!({ "url": url.as_str(), "error": e.to_string() }));
+                    continue;
+                }
+            };
+            let is_html = response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .is_none_or(|content_type| content_type.contains("html"));
+            if !is_html {
+                continue;
             }
-            _ => {
-                // Default: text
-                let mut text = html_to_text(&html_to_parse);
-                if text.len() > max_length {
-                    text.truncate(max_length);
-                    text.push_str("...[truncated]");
+            let final_url = response.url().clone();
+            let html = match response.text().await {
+                Ok(html) => html,
+                Err(e) => {
+                    errors.push(json!({ "url": url.as_str(), "error": e.to_string() }));
+                    continue;
+                }
+            };
+
+            let page = parse_page(&html, &final_url, args.selector.as_deref(), args.max_chars_per_page);
+
+            if depth < args.max_depth {
+                for link in page.links {
+                    let Ok(link) = Url::parse(&link) else { continue };
+                    let link = normalize_url(link);
+                    if !matches!(link.scheme(), "http" | "https")
+                        || !domain_allowed(&link, &allowed_domains)
+                        || args.path_prefix.as_deref().is_some_and(|prefix| !link.path().starts_with(prefix))
+                    {
+                        continue;
+                    }
+                    if seen.insert(link.to_string()) {
+                        queue.push_back((link, depth + 1));
+                    }
                 }
-                json!({
-                    "url": url,
-                    "format": "text",
-                    "length": text.len(),
-                    "content": text
-                })
             }
-        };
+
+            let mut entry = json!({
+                "url": url.as_str(),
+                "depth": depth,
+                "title": page.title,
+                "length": page.text.len()
+            });
+            if args.ingest {
+                if page.text.is_empty() {
+                    continue;
+                }
+                let mut ingest_args = json!({
+                    "source": url.as_str(),
+                    "text": page.text,
+                    "metadata": { "url": url.as_str(), "title": page.title }
+                });
+                if let Some(namespace) = &args.namespace {
+                    ingest_args["namespace"] = json!(namespace);
+                }
+                if let Some(embed_tool) = &args.embed_tool {
+                    ingest_args["embed_tool"] = json!(embed_tool);
+                }
+                match stream::without_sink(ingest(&state, ingest_args)).await {
+                    Ok(result) => {
+                        ingested += 1;
+                        entry["chunks"] = result["chunks"].clone();
+                    }
+                    Err(e) => {
+                        errors.push(json!({ "url": url.as_str(), "error": format!("Ingest failed: {}", e) }));
+                        continue;
+                    }
+                }
+            } else {
+                entry["text"] = json!(page.text);
+            }
+            pages.push(entry);
+        }
+        reporter.report(fetched as u64, Some(fetched as u64), format!("Crawled {} pages", pages.len()));
+
+        let mut result = json!({
+            "start_url": args.url,
+            "pages_crawled": pages.len(),
+            "pages": pages,
+            "queued_remaining": queue.len(),
+            "blocked_by_robots": blocked_by_robots,
+            "errors": errors
+        });
+        if args.ingest {
+            result["ingested"] = json!(ingested);
+        }
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
 }
 
-/// Extract content within a specific HTML tag.
-fn extract_by_tag(html: &str, tag: &str) -> Option<String> {
-    let open_pattern = format!("<{}[^>]*>", regex::escape(tag));
-    let close_pattern = format!("</{}>", regex::escape(tag));
+/// What a crawl needs from a page.
+struct CrawledPage {
+    title: Option<String>,
+    text: String,
+    links: Vec<String>,
+}
 
-    let open_re = Regex::new(&open_pattern).ok()?;
-    let close_re = Regex::new(&close_pattern).ok()?;
+fn parse_page(html: &str, url: &Url, selector: Option<&str>, max_chars: usize) -> CrawledPage {
+    let document = Html::parse_document(html);
+    let selector = selector.and_then(|s| Selector::parse(s).ok());
+    let extracted = extract(&document, url, selector.as_ref(), None, "text", max_chars);
+    let title = document
+        .select(&Selector::parse("title").unwrap())
+        .next()
+        .map(|title| element_text(title, false))
+        .filter(|title| !title.is_empty());
+    let links = extract_links(document.root_element(), url)
+        .into_iter()
+        .filter_map(|link| link["href"].as_str().map(str::to_string))
+        .collect();
+
+    CrawledPage {
+        title,
+        text: extracted["content"].as_str().unwrap_or_default().to_string(),
+        links,
+    }
+}
 
-    let start_match = open_re.find(html)?;
-    let start = start_match.end();
+/// Drops the fragment, which never names a different page.
+fn normalize_url(mut url: Url) -> Url {
+    url.set_fragment(None);
+    url
+}
 
-    let search_area = &html[start..];
-    let end_match = close_re.find(search_area)?;
-    let end = start + end_match.start();
+/// Whether the URL's host is one of the domains or a subdomain of one.
+fn domain_allowed(url: &Url, domains: &[String]) -> bool {
+    let Some(host) = url.host_str() else { return false };
+    let host = host.to_lowercase();
+    domains
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+}
 
-    Some(html[start..end].to_string())
+async fn ingest(state: &Arc<RuntimeState>, args: Value) -> Result<Value, ToolError> {
+    let caller = caller::current().ok_or_else(|| {
+        ToolError::PermissionDenied("web.crawl ingests pages as the caller, and this call has none".to_string())
+    })?;
+    let params = json!({ "name": "rag.ingest", "arguments": args });
+    let result = handle_tools_call_with_context(Some(params), state.clone(), caller)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+    let text = result["content"][0]["text"].as_str().unwrap_or_default();
+    if result["isError"] == true {
+        return Err(ToolError::ExecutionFailed(text.to_string()));
+    }
+    Ok(serde_json::from_str(text).unwrap_or(Value::Null))
 }
 
-/// Convert HTML to plain text.
-fn html_to_text(html: &str) -> String {
-    let mut text = html.to_string();
-
-    // Remove script and style tags with content
-    let script_re = Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap();
-    text = script_re.replace_all(&text, "").to_string();
-
-    let style_re = Regex::new(r"(?is)<style[^>]*>.*?</style>").unwrap();
-    text = style_re.replace_all(&text, "").to_string();
-
-    // Remove comments
-    let comment_re = Regex::new(r"<!--.*?-->").unwrap();
-    text = comment_re.replace_all(&text, "").to_string();
-
-    // Convert common elements to newlines
-    let block_re = Regex::new(r"(?i)</(p|div|h[1-6]|li|tr|br|hr)[^>]*>").unwrap();
-    text = block_re.replace_all(&text, "\n").to_string();
-
-    let br_re = Regex::new(r"(?i)<br[^>]*/?>").unwrap();
-    text = br_re.replace_all(&text, "\n").to_string();
-
-    // Remove all HTML tags
-    let tag_re = Regex::new(r"<[^>]+>").unwrap();
-    text = tag_re.replace_all(&text, "").to_string();
-
-    // Decode common HTML entities
-    text = text
-        .replace("&nbsp;", " ")
-        .replace("&amp;", "&")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&quot;", "\"")
-        .replace("&#39;", "'")
-        .replace("&apos;", "'");
-
-    // Decode numeric entities
-    let numeric_re = Regex::new(r"&#(\d+);").unwrap();
-    text = numeric_re
-        .replace_all(&text, |caps: &regex::Captures| {
-            caps.get(1)
-                .and_then(|m| m.as_str().parse::<u32>().ok())
-                .and_then(char::from_u32)
-                .map(|c| c.to_string())
-                .unwrap_or_default()
-        })
-        .to_string();
+/// The robots.txt rules that apply to this crawler.
+#[derive(Debug, Default)]
+struct Robots {
+    /// (allow, path pattern)
+    rules: Vec<(bool, String)>,
+    crawl_delay_ms: Option<u64>,
+}
 
-    // Collapse whitespace
-    let ws_re = Regex::new(r"[ \t]+").unwrap();
-    text = ws_re.replace_all(&text, " ").to_string();
+impl Robots {
+    /// Parses robots.txt, keeping the group for this crawler's token, or
+    /// the `*` group when there is none.
+    fn parse(text: &str) -> Self {
+        let mut specific = Robots::default();
+        let mut wildcard = Robots::default();
+        let mut has_specific = false;
+
+        // Agents of the group being read, and whether its rules started
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else { continue };
+            let field = field.trim().to_lowercase();
+            let value = value.trim();
+
+            if field == "user-agent" {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                agents.push(value.to_lowercase());
+                continue;
+            }
+            in_rules = true;
+
+            let mine = agents.iter().any(|agent| agent == ROBOTS_AGENT);
+            let target = if mine {
+                has_specific = true;
+                &mut specific
+            } else if agents.iter().any(|agent| agent == "*") {
+                &mut wildcard
+            } else {
+                continue;
+            };
+            match field.as_str() {
+                "allow" if !value.is_empty() => target.rules.push((true, value.to_string())),
+                "disallow" if !value.is_empty() => target.rules.push((false, value.to_string())),
+                "crawl-delay" => {
+                    target.crawl_delay_ms = value.parse::<f64>().ok().map(|secs| (secs * 1000.0) as u64);
+                }
+                _ => {}
+            }
+        }
 
-    // Collapse multiple newlines
-    let nl_re = Regex::new(r"\n\s*\n+").unwrap();
-    text = nl_re.replace_all(&text, "\n\n").to_string();
+        if has_specific { specific } else { wildcard }
+    }
 
-    text.trim().to_string()
+    /// Whether a path may be fetched: the longest matching rule wins, and
+    /// Allow wins a tie.
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_match(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
 }
 
-/// Convert text to basic markdown format.
-fn text_to_markdown(text: &str) -> String {
-    let lines: Vec<&str> = text.lines().collect();
-    let mut result = Vec::new();
+/// Matches a robots.txt path pattern (`*` wildcards, `$` end anchor).
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else { return false };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part must end the path when anchored
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
 
-    for line in lines {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            result.push(String::new());
-        } else {
-            result.push(trimmed.to_string());
+async fn fetch_robots(client: &reqwest::Client, url: &Url) -> Robots {
+    let Ok(robots_url) = url.join("/robots.txt") else { return Robots::default() };
+    match client.get(robots_url).send().await {
+        Ok(response) if response.status().is_success() => {
+            Robots::parse(&response.text().await.unwrap_or_default())
         }
+        // A missing robots.txt allows everything
+        _ => Robots::default(),
     }
+}
 
-    result.join("\n")
+/// Elements whose content is never page text.
+const SKIPPED_TAGS: &[&str] = &["script", "style", "noscript", "template", "svg", "head", "iframe"];
+
+/// Elements rendered on lines of their own.
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure",
+    "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol",
+    "p", "pre", "section", "table", "tr", "ul",
+];
+
+fn parse_selector(selector: &str) -> Result<Selector, ToolError> {
+    Selector::parse(selector)
+        .map_err(|e| ToolError::InvalidInput(format!("Invalid selector '{}': {}", selector, e)))
 }
 
-/// Extract all links from HTML.
-fn extract_links(html: &str, base_url: &str) -> Vec<Value> {
-    let link_re = Regex::new(r#"<a[^>]+href=["']([^"']+)["'][^>]*>([^<]*)</a>"#).unwrap();
-    let mut links = Vec::new();
-
-    for cap in link_re.captures_iter(html) {
-        let href = cap.get(1).map(|m| m.as_str()).unwrap_or("");
-        let text = cap.get(2).map(|m| m.as_str()).unwrap_or("").trim();
-
-        // Resolve relative URLs
-        let full_url = if href.starts_with("http://") || href.starts_with("https://") {
-            href.to_string()
-        } else if href.starts_with("//") {
-            format!("https:{}", href)
-        } else if href.starts_with('/') {
-            // Get base domain
-            if let Some(domain_end) = base_url
-                .find("://")
-                .and_then(|i| base_url[i + 3..].find('/').map(|j| i + 3 + j))
-            {
-                format!("{}{}", &base_url[..domain_end], href)
-            } else {
-                format!("{}{}", base_url.trim_end_matches('/'), href)
+/// Extracts from a parsed page: the elements matching `selector`, or the
+/// page's main content (article, main or body) without one.
+fn extract(
+    document: &Html,
+    base_url: &Url,
+    selector: Option<&Selector>,
+    attribute: Option<&str>,
+    format: &str,
+    max_length: usize,
+) -> Value {
+    let scope: Vec<ElementRef> = match selector {
+        Some(selector) => document.select(selector).collect(),
+        None => ["article", "main", "body"]
+            .iter()
+            .find_map(|tag| document.select(&Selector::parse(tag).unwrap()).next())
+            .into_iter()
+            .collect(),
+    };
+    let scope = if scope.is_empty() && selector.is_none() { vec![document.root_element()] } else { scope };
+
+    if let Some(attribute) = attribute {
+        let values: Vec<String> = scope
+            .iter()
+            .filter_map(|element| element.value().attr(attribute))
+            .map(|value| match attribute {
+                "href" | "src" | "action" => resolve_url(base_url, value),
+                _ => value.to_string(),
+            })
+            .collect();
+        return json!({
+            "attribute": attribute,
+            "matches": scope.len(),
+            "count": values.len(),
+            "values": values
+        });
+    }
+
+    match format {
+        "html" => {
+            let html: Vec<String> = scope
+                .iter()
+                .map(|element| if selector.is_some() { element.html() } else { element.inner_html() })
+                .collect();
+            let output = truncate(html.join("\n"), max_length, "...[truncated]");
+            json!({ "format": "html", "matches": scope.len(), "content": output })
+        }
+        "links" => {
+            // Without a selector, links anywhere on the page count
+            let links = match selector {
+                Some(_) => scope.iter().flat_map(|element| extract_links(*element, base_url)).collect(),
+                None => extract_links(document.root_element(), base_url),
+            };
+            json!({ "format": "links", "count": links.len(), "links": links })
+        }
+        "table" => {
+            let table_selector = Selector::parse("table").unwrap();
+            let tables: Vec<Value> = scope
+                .iter()
+                .flat_map(|element| {
+                    if element.value().name() == "table" {
+                        vec![*element]
+                    } else {
+                        element.select(&table_selector).collect()
+                    }
+                })
+                .map(table_to_json)
+                .collect();
+            json!({ "format": "table", "count": tables.len(), "tables": tables })
+        }
+        _ => {
+            let markdown = format == "markdown";
+            let text: Vec<String> = scope.iter().map(|element| element_text(*element, markdown)).collect();
+            let suffix = if markdown { "\n\n...[truncated]" } else { "...[truncated]" };
+            let output = truncate(text.join("\n\n"), max_length, suffix);
+            json!({
+                "format": if markdown { "markdown" } else { "text" },
+                "matches": scope.len(),
+                "length": output.len(),
+                "content": output
+            })
+        }
+    }
+}
+
+/// Cuts `text` to at most `max_length` bytes on a character boundary.
+fn truncate(mut text: String, max_length: usize, suffix: &str) -> String {
+    if text.len() > max_length {
+        let mut end = max_length;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(suffix);
+    }
+    text
+}
+
+/// Readable text of an element, with blocks on their own lines. As
+/// markdown, headings, list items and links keep their markup.
+fn element_text(element: ElementRef, markdown: bool) -> String {
+    let mut out = String::new();
+    write_text(element, markdown, &mut out);
+
+    // Collapse whitespace within lines and runs of blank lines
+    let mut lines: Vec<String> = Vec::new();
+    for line in out.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn write_text(element: ElementRef, markdown: bool, out: &mut String) {
+    let tag = element.value().name();
+    if SKIPPED_TAGS.contains(&tag) {
+        return;
+    }
+    if tag == "br" {
+        out.push('\n');
+        return;
+    }
+
+    let block = BLOCK_TAGS.contains(&tag);
+    if block {
+        out.push_str(if matches!(tag, "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6") { "\n\n" } else { "\n" });
+    }
+    if markdown {
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = tag[1..].parse().unwrap_or(1);
+                out.push_str(&"#".repeat(level));
+                out.push(' ');
             }
-        } else {
-            format!("{}/{}", base_url.trim_end_matches('/'), href)
-        };
+            "li" => out.push_str("- "),
+            "hr" => out.push_str("---"),
+            _ => {}
+        }
+    }
+
+    if markdown && tag == "a" {
+        let mut text = String::new();
+        write_children(element, markdown, &mut text);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        match element.value().attr("href").filter(|href| !text.is_empty() && !href.starts_with('#')) {
+            Some(href) => out.push_str(&format!("[{}]({})", text, href)),
+            None => out.push_str(&text),
+        }
+    } else {
+        write_children(element, markdown, out);
+    }
 
-        if !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:") {
-            links.push(json!({
-                "href": full_url,
-                "text": text
-            }));
+    if block {
+        out.push('\n');
+    } else if matches!(tag, "td" | "th") {
+        // Keep cells of a row apart
+        out.push(' ');
+    }
+}
+
+fn write_children(element: ElementRef, markdown: bool, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    write_text(child, markdown, out);
+                }
+            }
+            _ => {}
         }
     }
+}
+
+/// Converts an HTML fragment to plain text.
+fn html_to_text(html: &str) -> String {
+    element_text(Html::parse_fragment(html).root_element(), false)
+}
+
+/// Resolves a link against the page URL.
+fn resolve_url(base_url: &Url, href: &str) -> String {
+    base_url
+        .join(href)
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| href.to_string())
+}
+
+/// Extracts the links under an element.
+fn extract_links(element: ElementRef, base_url: &Url) -> Vec<Value> {
+    let link_selector = Selector::parse("a[href]").unwrap();
+    let anchors = element.select(&link_selector);
+    let own = (element.value().name() == "a").then_some(element);
+
+    own.into_iter()
+        .chain(anchors)
+        .filter_map(|anchor| {
+            let href = anchor.value().attr("href")?.trim();
+            if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+                return None;
+            }
+            Some(json!({
+                "href": resolve_url(base_url, href),
+                "text": element_text(anchor, false)
+            }))
+        })
+        .collect()
+}
 
-    links
+/// Converts a table to JSON. With a header row, each row becomes an
+/// object keyed by header; otherwise rows are arrays of cell text.
+fn table_to_json(table: ElementRef) -> Value {
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+
+    // Rows of nested tables belong to those tables
+    let rows: Vec<(bool, Vec<String>)> = table
+        .select(&row_selector)
+        .filter(|row| row.ancestors().filter_map(ElementRef::wrap).find(|e| e.value().name() == "table") == Some(table))
+        .map(|row| {
+            let cells: Vec<ElementRef> = row
+                .select(&cell_selector)
+                .filter(|cell| cell.parent().and_then(ElementRef::wrap) == Some(row))
+                .collect();
+            let header = !cells.is_empty() && cells.iter().all(|cell| cell.value().name() == "th");
+            (header, cells.into_iter().map(|cell| element_text(cell, false)).collect::<Vec<_>>())
+        })
+        .filter(|(_, cells)| !cells.is_empty())
+        .collect();
+
+    let caption = table
+        .select(&Selector::parse("caption").unwrap())
+        .next()
+        .map(|caption| element_text(caption, false));
+
+    match rows.first() {
+        Some((true, headers)) => {
+            let headers = headers.clone();
+            let records: Vec<Value> = rows[1..]
+                .iter()
+                .map(|(_, cells)| {
+                    let record: serde_json::Map<String, Value> = cells
+                        .iter()
+                        .enumerate()
+                        .map(|(i, cell)| {
+                            let key = headers
+                                .get(i)
+                                .filter(|header| !header.is_empty())
+                                .cloned()
+                                .unwrap_or_else(|| format!("column_{}", i + 1));
+                            (key, json!(cell))
+                        })
+                        .collect();
+                    Value::Object(record)
+                })
+                .collect();
+            json!({ "caption": caption, "headers": headers, "rows": records })
+        }
+        _ => {
+            let records: Vec<&Vec<String>> = rows.iter().map(|(_, cells)| cells).collect();
+            json!({ "caption": caption, "headers": [], "rows": records })
+        }
+    }
 }
 
 /// Tool to search the web using a search engine.
@@ -331,17 +983,26 @@ impl Tool for WebSearchTool {
                     "limit": {
                         "type": "integer",
                         "description": "Max results (default: 10)"
+                    },
+                    "cache": {
+                        "type": "boolean",
+                        "description": "Reuse cached results for the same query (default: the http_client.cache.enabled setting)"
+                    },
+                    "cache_ttl": {
+                        "type": "integer",
+                        "description": "Cache the results for this many seconds"
                     }
                 },
                 "required": ["query"]
             }),
+            output_schema: None,
         }
     }
 
     async fn execute(
         &self,
         arguments: Value,
-        _state: Arc<RuntimeState>,
+        state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
         let query = arguments
             .get("query")
@@ -357,31 +1018,35 @@ impl Tool for WebSearchTool {
         let encoded_query = urlencoding::encode(query);
         let url = format!("https://html.duckduckgo.com/html/?q={}", encoded_query);
 
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (compatible; NexusBot/1.0)")
-            .timeout(std::time::Duration::from_secs(15))
-            .build()
+        let client = state
+            .http_clients
+            .get(&ClientProfile::new(ToolCategory::Web).user_agent(USER_AGENT).timeout(Duration::from_secs(15)))
             .map_err(|e| ToolError::ExecutionFailed(format!("Client error: {}", e)))?;
 
-        let response = client
-            .get(&url)
-            .send()
+        // Result pages say nothing useful about freshness, so use a plain TTL
+        let mode = match CacheMode::from_args(&state.config.http_client.cache, &arguments) {
+            CacheMode::Headers => CacheMode::Ttl(state.config.http_client.cache.default_ttl_secs),
+            mode => mode,
+        };
+        let (response, cache_status) = state
+            .http_cache
+            .send(mode, &HttpCache::key("GET", &url, &[]), &url, client.get(&url))
             .await
             .map_err(|e| ToolError::ExecutionFailed(format!("Search error: {}", e)))?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Read error: {}", e)))?;
+        let html = String::from_utf8_lossy(&response.body);
 
         // Parse DuckDuckGo results
         let results = parse_ddg_results(&html, limit);
 
-        let output = json!({
+        let mut output = json!({
             "query": query,
             "count": results.len(),
             "results": results
         });
+        if cache_status != CacheStatus::Bypass {
+            output["cache"] = json!(cache_status);
+        }
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&output).unwrap()))
     }
@@ -467,3 +1132,150 @@ fn decode_ddg_url(url: &str) -> String {
     url.to_string()
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><title>T</title><style>p { color: red }</style></head><body>
+        <nav><a href="/home">Home</a></nav>
+        <article>
+          <h1>Release <em>notes</em></h1>
+          <div class="post"><p>First <b>nested</b> para.</p><script>alert(1)</script>
+            <div class="post"><p>Inner &amp; deeper</p></div>
+          </div>
+          <ul><li><a href="docs/guide.pdf">Guide</a></li><li>Two</li></ul>
+          <table id="prices"><caption>Prices</caption>
+            <tr><th>Plan</th><th>USD</th></tr>
+            <tr><td>Free</td><td>0</td></tr>
+            <tr><td>Pro</td><td>20</td></tr>
+          </table>
+        </article>
+    </body></html>"#;
+
+    fn run(selector: Option<&str>, attribute: Option<&str>, format: &str) -> Value {
+        let document = Html::parse_document(PAGE);
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let selector = selector.map(|s| parse_selector(s).unwrap());
+        extract(&document, &base, selector.as_ref(), attribute, format, 50000)
+    }
+
+    #[test]
+    fn test_main_content_text() {
+        let text = run(None, None, "text")["content"].as_str().unwrap().to_string();
+        assert!(text.starts_with("Release notes\n\nFirst nested para."), "{}", text);
+        assert!(text.contains("Inner & deeper"));
+        assert!(!text.contains("alert") && !text.contains("Home"));
+
+        let markdown = run(None, None, "markdown")["content"].as_str().unwrap().to_string();
+        assert!(markdown.contains("# Release notes"));
+        assert!(markdown.contains("- [Guide](docs/guide.pdf)"));
+    }
+
+    #[test]
+    fn test_css_selectors_and_attributes() {
+        // Nested matches are found, not cut off at the first closing tag
+        let result = run(Some("div.post > p"), None, "text");
+        assert_eq!(result["matches"], 2);
+        assert_eq!(result["content"], "First nested para.\n\nInner & deeper");
+
+        let result = run(Some("a[href$='.pdf']"), Some("href"), "text");
+        assert_eq!(result["values"], json!(["https://example.com/blog/docs/guide.pdf"]));
+
+        let links = run(None, None, "links");
+        assert_eq!(links["links"][0], json!({"href": "https://example.com/home", "text": "Home"}));
+
+        assert!(parse_selector("div[").is_err());
+    }
+
+    #[test]
+    fn test_tables() {
+        let result = run(Some("#prices"), None, "table");
+        assert_eq!(
+            result["tables"][0],
+            json!({
+                "caption": "Prices",
+                "headers": ["Plan", "USD"],
+                "rows": [{"Plan": "Free", "USD": "0"}, {"Plan": "Pro", "USD": "20"}]
+            })
+        );
+        assert_eq!(html_to_text("<b>Top</b> result &amp; more"), "Top result & more");
+        assert_eq!(truncate("héllo".to_string(), 2, "…"), "h…");
+    }
+
+    #[test]
+    fn test_robots() {
+        let robots = Robots::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: Googlebot\nUser-agent: NexusBot\n\
+             Disallow: /private/\nAllow: /private/public\nDisallow: /*.json$\nCrawl-delay: 1.5\n",
+        );
+        assert!(robots.allows("/docs"));
+        assert!(!robots.allows("/private/keys"));
+        assert!(robots.allows("/private/public/page"));
+        assert!(!robots.allows("/api/data.json"));
+        assert!(robots.allows("/api/data.json?raw=1"));
+        assert_eq!(robots.crawl_delay_ms, Some(1500));
+
+        let robots = Robots::parse("User-agent: *\nDisallow: /tmp # scratch\nDisallow:\n");
+        assert!(!robots.allows("/tmp/x") && robots.allows("/"));
+        assert!(Robots::parse("").allows("/anything"));
+
+        let url = Url::parse("https://docs.example.com/a").unwrap();
+        assert!(domain_allowed(&url, &["example.com".to_string()]));
+        assert!(!domain_allowed(&url, &["ample.com".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_crawl() {
+        use axum::{response::Html as Page, routing::get, Router};
+
+        let app = Router::new()
+            .route("/robots.txt", get(|| async { "User-agent: *\nDisallow: /private" }))
+            .route("/", get(|| async {
+                Page(r#"<title>Home</title><a href="/a#top">A</a> <a href="/private/x">P</a>
+                        <a href="https://elsewhere.test/">Out</a> <a href="/a">A again</a>"#)
+            }))
+            .route("/a", get(|| async { Page(r#"<main>Page A</main><a href="/b">B</a>"#) }))
+            .route("/b", get(|| async { Page(r#"<main>Page B</main><a href="/c">C</a>"#) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut config = crate::core::Config {
+            database_path: Some(":memory:".to_string()),
+            ..Default::default()
+        };
+        config.http_client.allowed_private = vec!["127.0.0.1".to_string()];
+        let state = Arc::new(RuntimeState::new(config));
+        let output = WebCrawlTool
+            .execute(json!({"url": format!("http://{}/", addr), "max_depth": 2, "delay_ms": 0}), state)
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else { panic!("expected text") };
+        let result: Value = serde_json::from_str(text).unwrap();
+
+        let urls: Vec<&str> = result["pages"].as_array().unwrap().iter().map(|p| p["url"].as_str().unwrap()).collect();
+        let base = format!("http://{}", addr);
+        assert_eq!(urls, vec![format!("{}/", base), format!("{}/a", base), format!("{}/b", base)]);
+        assert_eq!(result["pages"][0]["title"], "Home");
+        assert_eq!(result["pages"][2]["text"], "Page B");
+        assert_eq!(result["blocked_by_robots"], 1);
+        assert_eq!(result["queued_remaining"], 0);
+    }
+
+    #[cfg(not(feature = "browser"))]
+    #[tokio::test]
+    async fn test_render_needs_browser_feature() {
+        use crate::core::Config;
+
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let err = WebExtractTool
+            .execute(json!({"url": "http://127.0.0.1:9/", "render": true}), state)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+}
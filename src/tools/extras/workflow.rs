@@ -3,11 +3,19 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
 
-use crate::core::RuntimeState;
+use crate::core::{CompositeToolConfig, RequestContext, RuntimeState};
+use crate::handlers::handle_tools_call_with_context;
+use crate::memory::WorkflowRun;
 use crate::protocol::mcp::Tool as ToolDefinition;
-use crate::tools::registry::{Tool, ToolContent, ToolError, ToolOutput};
+use crate::tools::caller;
+use crate::tools::registry::{self, Tool, ToolError, ToolOutput};
+use crate::tools::stream::{self, ProgressReporter};
 
 /// Tool to execute a workflow (chain of tools).
 #[derive(Debug)]
@@ -19,7 +27,7 @@ impl Tool for WorkflowRunTool {
         ToolDefinition {
             name: "workflow.run".to_string(),
             description: Some(
-                "Executes a workflow - a sequence of tool calls. Each step can reference previous outputs."
+                "Executes a workflow - a sequence of tool calls. Each step can reference previous outputs. Foreach steps iterate sub-steps over an array."
                     .to_string(),
             ),
             input_schema: json!({
@@ -36,11 +44,14 @@ impl Tool for WorkflowRunTool {
                             "type": "object",
                             "properties": {
                                 "id": {"type": "string", "description": "Step ID for referencing"},
-                                "tool": {"type": "string", "description": "Tool to call"},
+                                "tool": {"type": "string", "description": "Tool to call (not needed for foreach steps)"},
                                 "args": {"type": "object", "description": "Tool arguments"},
-                                "condition": {"type": "string", "description": "Condition to check (optional)"}
-                            },
-                            "required": ["tool"]
+                                "condition": {"type": "string", "description": "Condition to check (optional)"},
+                                "foreach": {"description": "Array, or context reference like 'search.results', to iterate over"},
+                                "as": {"type": "string", "description": "Variable name for the current item (default: item)"},
+                                "steps": {"type": "array", "description": "Sub-steps run for each item (foreach only)"},
+                                "continue_on_error": {"type": "boolean", "description": "Keep iterating when an item fails (foreach only)"}
+                            }
                         }
                     },
                     "context": {
@@ -50,6 +61,7 @@ impl Tool for WorkflowRunTool {
                 },
                 "required": ["steps"]
             }),
+            output_schema: None,
         }
     }
 
@@ -73,16 +85,96 @@ impl Tool for WorkflowRunTool {
             .cloned()
             .unwrap_or(json!({}));
 
-        // Context to store step outputs
-        let mut context: HashMap<String, Value> = HashMap::new();
-        
-        // Add initial context
-        if let Some(obj) = initial_context.as_object() {
-            for (k, v) in obj {
-                context.insert(k.clone(), v.clone());
-            }
+        let result = run_workflow(workflow_name, None, steps, &initial_context, &state).await?;
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Runs a workflow's steps and records the run in the workflow history.
+async fn run_workflow(
+    workflow_name: &str,
+    version: Option<i64>,
+    steps: &[Value],
+    initial_context: &Value,
+    state: &Arc<RuntimeState>,
+) -> Result<Value, ToolError> {
+    // Steps are authorized like direct calls of the caller; without one
+    // there is nothing to authorize them against
+    let caller = caller::current().ok_or_else(|| {
+        ToolError::PermissionDenied("Workflow steps run as the caller, and this call has none".to_string())
+    })?;
+
+    // Context to store step outputs
+    let mut context: HashMap<String, Value> = HashMap::new();
+
+    // Add initial context
+    if let Some(obj) = initial_context.as_object() {
+        for (k, v) in obj {
+            context.insert(k.clone(), v.clone());
         }
+    }
 
+    let started_at = chrono::Utc::now();
+    let start = Instant::now();
+    // The workflow reports its own progress per step, so the tools it calls
+    // run without a sink of their own
+    let reporter = ProgressReporter::current();
+    let (results, success) = stream::without_sink(run_steps(steps, &mut context, state, &caller, &reporter)).await?;
+    reporter.report(
+        steps.len() as u64,
+        Some(steps.len() as u64),
+        if success { "Workflow finished" } else { "Workflow failed" },
+    );
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let run = WorkflowRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        workflow: workflow_name.to_string(),
+        version,
+        success,
+        started_at: started_at.to_rfc3339(),
+        finished_at: chrono::Utc::now().to_rfc3339(),
+        duration_ms,
+        steps: json!(results),
+    };
+
+    // History is best-effort; a failed insert should not fail the run
+    if let Err(e) = state.memory_store.record_workflow_run(&run).await {
+        warn!("Failed to record workflow run {}: {}", run.id, e);
+    }
+
+    Ok(json!({
+        "workflow": workflow_name,
+        "version": version,
+        "run_id": run.id,
+        "success": success,
+        "duration_ms": duration_ms,
+        "steps_executed": results.len(),
+        "steps_total": steps.len(),
+        "results": results,
+        "final_context": context
+    }))
+}
+
+/// Maximum number of iterations a single foreach step may run.
+const MAX_FOREACH_ITERATIONS: usize = 1000;
+
+/// Boxed future returned by [`run_steps`] (boxed so foreach can recurse).
+type StepsFuture<'a> = Pin<Box<dyn Future<Output = Result<(Vec<Value>, bool), ToolError>> + Send + 'a>>;
+
+/// Runs a list of steps against a context, returning per-step results and
+/// whether every executed step succeeded. Execution stops at the first failure.
+/// Each step is announced to `reporter` before it runs, and goes through
+/// `tools/call` as `caller`, so its scopes, the policy and approvals apply.
+fn run_steps<'a>(
+    steps: &'a [Value],
+    context: &'a mut HashMap<String, Value>,
+    state: &'a Arc<RuntimeState>,
+    caller: &'a RequestContext,
+    reporter: &'a ProgressReporter,
+) -> StepsFuture<'a> {
+    Box::pin(async move {
         let mut results: Vec<Value> = Vec::new();
         let mut success = true;
 
@@ -93,16 +185,21 @@ impl Tool for WorkflowRunTool {
                 .and_then(|v| v.as_str())
                 .unwrap_or(&default_id);
 
-            let tool_name = step
-                .get("tool")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| {
-                    ToolError::InvalidInput(format!("Step {} missing 'tool'", step_id))
-                })?;
+            let is_foreach = step.get("foreach").is_some();
+            let tool_name = match step.get("tool").and_then(|v| v.as_str()) {
+                Some(name) => name,
+                None if is_foreach => "foreach",
+                None => {
+                    return Err(ToolError::InvalidInput(format!(
+                        "Step {} missing 'tool'",
+                        step_id
+                    )))
+                }
+            };
 
             // Check condition if present
             if let Some(condition) = step.get("condition").and_then(|v| v.as_str()) {
-                if !evaluate_condition(condition, &context) {
+                if !evaluate_condition(condition, context) {
                     results.push(json!({
                         "step_id": step_id,
                         "tool": tool_name,
@@ -113,76 +210,197 @@ impl Tool for WorkflowRunTool {
                 }
             }
 
-            // Substitute context variables in arguments
-            let raw_args = step.get("args").cloned().unwrap_or(json!({}));
-            let args = substitute_context(&raw_args, &context);
+            reporter.report(
+                index as u64,
+                Some(steps.len() as u64),
+                format!("Running step {} ({})", step_id, tool_name),
+            );
+            let step_start = Instant::now();
 
-            // Execute tool
-            let tool = {
-                let registry = state.tool_registry.read();
-                registry.get(tool_name).cloned()
-            };
-
-            let tool = match tool {
-                Some(t) => t,
-                None => {
+            if is_foreach {
+                let (mut result, ok) = run_foreach(step, step_id, context, state, caller).await?;
+                result["duration_ms"] = json!(step_start.elapsed().as_millis() as u64);
+                results.push(result);
+                if !ok {
                     success = false;
-                    results.push(json!({
-                        "step_id": step_id,
-                        "tool": tool_name,
-                        "error": format!("Tool not found: {}", tool_name)
-                    }));
                     break;
                 }
+                continue;
+            }
+
+            // Substitute context variables in arguments
+            let raw_args = step.get("args").cloned().unwrap_or(json!({}));
+            let args = substitute_context(&raw_args, context);
+
+            // Execute the tool as the caller would call it directly
+            let params = json!({ "name": tool_name, "arguments": args });
+            let result = match handle_tools_call_with_context(Some(params), state.clone(), caller.clone()).await {
+                Ok(result) => result,
+                Err(e) => json!({ "content": [{"type": "text", "text": e.to_string()}], "isError": true }),
             };
+            let output_text = result["content"][0]["text"].as_str().unwrap_or_default().to_string();
+
+            if result["isError"] == true {
+                success = false;
+                results.push(json!({
+                    "step_id": step_id,
+                    "tool": tool_name,
+                    "duration_ms": step_start.elapsed().as_millis() as u64,
+                    "error": output_text
+                }));
+                // Stop on error
+                break;
+            }
 
-            match tool.execute(args, state.clone()).await {
-                Ok(output) => {
-                    // Extract text content
-                    let output_text = match output.content.first() {
-                        Some(ToolContent::Text { text }) => text.clone(),
-                        _ => String::new(),
-                    };
+            // Try to parse as JSON for context
+            let output_value: Value = serde_json::from_str(&output_text)
+                .unwrap_or(json!(output_text));
 
-                    // Try to parse as JSON for context
-                    let output_value: Value = serde_json::from_str(&output_text)
-                        .unwrap_or(json!(output_text));
+            // Store in context
+            context.insert(step_id.to_string(), output_value.clone());
+            context.insert("_last".to_string(), output_value.clone());
 
-                    // Store in context
-                    context.insert(step_id.to_string(), output_value.clone());
-                    context.insert("_last".to_string(), output_value.clone());
+            results.push(json!({
+                "step_id": step_id,
+                "tool": tool_name,
+                "success": true,
+                "duration_ms": step_start.elapsed().as_millis() as u64,
+                "output": output_value
+            }));
+        }
 
-                    results.push(json!({
-                        "step_id": step_id,
-                        "tool": tool_name,
-                        "success": true,
-                        "output": output_value
-                    }));
-                }
-                Err(e) => {
-                    success = false;
-                    results.push(json!({
-                        "step_id": step_id,
-                        "tool": tool_name,
-                        "error": e.to_string()
-                    }));
-                    // Stop on error
-                    break;
-                }
+        Ok((results, success))
+    })
+}
+
+/// Runs a foreach step: executes its sub-steps once per item of an array.
+///
+/// Each iteration sees the parent context plus the current item (under `as`,
+/// default `item`) and `_index`. The output of the last sub-step of every
+/// iteration is collected into an array stored under the step ID.
+async fn run_foreach(
+    step: &Value,
+    step_id: &str,
+    context: &mut HashMap<String, Value>,
+    state: &Arc<RuntimeState>,
+    caller: &RequestContext,
+) -> Result<(Value, bool), ToolError> {
+    let items = resolve_foreach_items(&step["foreach"], context).ok_or_else(|| {
+        ToolError::InvalidInput(format!(
+            "Step {}: 'foreach' must be an array or reference one in the context",
+            step_id
+        ))
+    })?;
+
+    if items.len() > MAX_FOREACH_ITERATIONS {
+        return Err(ToolError::InvalidInput(format!(
+            "Step {}: foreach over {} items exceeds the limit of {}",
+            step_id,
+            items.len(),
+            MAX_FOREACH_ITERATIONS
+        )));
+    }
+
+    let sub_steps = step
+        .get("steps")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| ToolError::InvalidInput(format!("Step {} missing 'steps' array", step_id)))?;
+
+    let item_var = step.get("as").and_then(|v| v.as_str()).unwrap_or("item");
+    let continue_on_error = step
+        .get("continue_on_error")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let mut outputs = Vec::with_capacity(items.len());
+    let mut iterations = Vec::with_capacity(items.len());
+    let mut failed = 0;
+
+    for (index, item) in items.iter().enumerate() {
+        let mut iteration_context = context.clone();
+        iteration_context.insert(item_var.to_string(), item.clone());
+        iteration_context.insert("_index".to_string(), json!(index));
+        iteration_context.remove("_last");
+
+        let (results, ok) = run_steps(sub_steps, &mut iteration_context, state, caller, &ProgressReporter::default()).await?;
+        let output = iteration_context.get("_last").cloned().unwrap_or(Value::Null);
+
+        outputs.push(output);
+        iterations.push(json!({
+            "index": index,
+            "success": ok,
+            "results": results
+        }));
+
+        if !ok {
+            failed += 1;
+            if !continue_on_error {
+                break;
             }
         }
+    }
 
-        let result = json!({
-            "workflow": workflow_name,
-            "success": success,
-            "steps_executed": results.len(),
-            "steps_total": steps.len(),
-            "results": results,
-            "final_context": context
-        });
+    let aggregated = Value::Array(outputs);
+    context.insert(step_id.to_string(), aggregated.clone());
+    context.insert("_last".to_string(), aggregated.clone());
 
-        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    let ok = failed == 0;
+    Ok((
+        json!({
+            "step_id": step_id,
+            "tool": "foreach",
+            "success": ok,
+            "items": items.len(),
+            "failed": failed,
+            "output": aggregated,
+            "iterations": iterations
+        }),
+        ok || continue_on_error,
+    ))
+}
+
+/// Resolves the array a foreach step iterates over.
+///
+/// Accepts an inline array, or a context reference such as
+/// `"search.results"` or `"{{search.results}}"`.
+fn resolve_foreach_items(source: &Value, context: &HashMap<String, Value>) -> Option<Vec<Value>> {
+    match source {
+        Value::Array(items) => Some(
+            items.iter().map(|v| substitute_context(v, context)).collect(),
+        ),
+        Value::String(s) => {
+            let path = s.trim();
+            let path = path
+                .strip_prefix("{{")
+                .and_then(|p| p.strip_suffix("}}"))
+                .unwrap_or(path)
+                .trim();
+            match lookup_path(path, context)? {
+                Value::Array(items) => Some(items.clone()),
+                // Tool outputs that were plain text may still hold a JSON array
+                Value::String(text) => match serde_json::from_str(text).ok()? {
+                    Value::Array(items) => Some(items),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Looks up a dotted path (e.g. `step.field.0`) in the context.
+/// Numeric segments index into arrays.
+fn lookup_path<'a>(path: &str, context: &'a HashMap<String, Value>) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut current = context.get(parts.next()?)?;
+    for part in parts {
+        current = match part.parse::<usize>() {
+            Ok(i) if current.is_array() => current.get(i)?,
+            _ => current.get(part)?,
+        };
     }
+    Some(current)
 }
 
 /// Evaluates a simple condition against context.
@@ -193,8 +411,8 @@ fn evaluate_condition(condition: &str, context: &HashMap<String, Value>) -> bool
     match parts.len() {
         2 if parts[1] == "exists" => context.contains_key(parts[0]),
         2 if parts[1] == "empty" => {
-            context.get(parts[0]).map_or(true, |v| {
-                v.is_null() || v.as_str().map_or(false, |s| s.is_empty())
+            context.get(parts[0]).is_none_or(|v| {
+                v.is_null() || v.as_str().is_some_and(|s| s.is_empty())
             })
         }
         3 => {
@@ -205,12 +423,12 @@ fn evaluate_condition(condition: &str, context: &HashMap<String, Value>) -> bool
             let ctx_value = context.get(key);
 
             match op {
-                "==" | "=" => ctx_value.map_or(false, |v| {
-                    v.as_str().map_or(false, |s| s == value)
+                "==" | "=" => ctx_value.is_some_and(|v| {
+                    (v.as_str() == Some(value))
                         || v.to_string().trim_matches('"') == value
                 }),
-                "!=" => ctx_value.map_or(true, |v| {
-                    v.as_str().map_or(true, |s| s != value)
+                "!=" => ctx_value.is_none_or(|v| {
+                    (v.as_str() != Some(value))
                         && v.to_string().trim_matches('"') != value
                 }),
                 ">" | ">=" | "<" | "<=" => {
@@ -237,7 +455,7 @@ fn evaluate_condition(condition: &str, context: &HashMap<String, Value>) -> bool
 
 /// Substitutes context variables in a JSON value.
 /// Variables are referenced as {{variable_name}} or {{step_id.field}}
-fn substitute_context(value: &Value, context: &HashMap<String, Value>) -> Value {
+pub fn substitute_context(value: &Value, context: &HashMap<String, Value>) -> Value {
     match value {
         Value::String(s) => {
             let mut result = s.clone();
@@ -249,17 +467,7 @@ fn substitute_context(value: &Value, context: &HashMap<String, Value>) -> Value
                 let var_path = &cap[1];
 
                 // Handle dot notation
-                let parts: Vec<&str> = var_path.split('.').collect();
-                let replacement = if parts.len() == 1 {
-                    context.get(parts[0]).map(|v| value_to_string(v))
-                } else {
-                    // Navigate nested path
-                    let mut current = context.get(parts[0]);
-                    for part in &parts[1..] {
-                        current = current.and_then(|v| v.get(*part));
-                    }
-                    current.map(|v| value_to_string(v))
-                };
+                let replacement = lookup_path(var_path, context).map(value_to_string);
 
                 if let Some(repl) = replacement {
                     result = result.replace(full_match, &repl);
@@ -291,6 +499,9 @@ fn value_to_string(v: &Value) -> String {
     }
 }
 
+/// KV prefix used by workflows saved before versioned storage existed.
+const LEGACY_WORKFLOW_PREFIX: &str = "workflow:";
+
 /// Tool to define a reusable workflow template.
 #[derive(Debug)]
 pub struct WorkflowDefineTool;
@@ -301,7 +512,7 @@ impl Tool for WorkflowDefineTool {
         ToolDefinition {
             name: "workflow.define".to_string(),
             description: Some(
-                "Saves a workflow definition for later use.".to_string(),
+                "Saves a workflow definition for later use. Each save creates a new version.".to_string(),
             ),
             input_schema: json!({
                 "type": "object",
@@ -320,12 +531,13 @@ impl Tool for WorkflowDefineTool {
                     },
                     "inputs": {
                         "type": "array",
-                        "description": "Required input parameters",
-                        "items": {"type": "string"}
+                        "description": "Input parameters: names, or objects with name, type, description, default and required",
+                        "items": {"type": ["string", "object"]}
                     }
                 },
                 "required": ["name", "steps"]
             }),
+            output_schema: None,
         }
     }
 
@@ -339,19 +551,23 @@ impl Tool for WorkflowDefineTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidInput("Missing 'name'".to_string()))?;
 
-        // Store workflow in memory
-        let key = format!("workflow:{}", name);
-        
-        state
+        if !arguments.get("steps").is_some_and(|v| v.is_array()) {
+            return Err(ToolError::InvalidInput("Missing 'steps' array".to_string()));
+        }
+
+        let version = state
             .memory_store
-            .kv_set(&key, arguments.clone(), None)
+            .save_workflow(name, arguments.clone())
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
+        remount_composite_tools(&state, name).await;
+
         let result = json!({
             "success": true,
             "workflow": name,
-            "message": format!("Workflow '{}' saved. Run with workflow.execute", name)
+            "version": version,
+            "message": format!("Workflow '{}' saved as version {}. Run with workflow.execute", name, version)
         });
 
         Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
@@ -375,6 +591,10 @@ impl Tool for WorkflowExecuteTool {
                         "type": "string",
                         "description": "Workflow name to execute"
                     },
+                    "version": {
+                        "type": "integer",
+                        "description": "Version to execute (default: latest)"
+                    },
                     "inputs": {
                         "type": "object",
                         "description": "Input parameters for the workflow"
@@ -382,6 +602,7 @@ impl Tool for WorkflowExecuteTool {
                 },
                 "required": ["name"]
             }),
+            output_schema: None,
         }
     }
 
@@ -395,25 +616,46 @@ impl Tool for WorkflowExecuteTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidInput("Missing 'name'".to_string()))?;
 
-        let key = format!("workflow:{}", name);
-        
-        let workflow = state
+        let requested_version = arguments.get("version").and_then(|v| v.as_i64());
+
+        let stored = state
             .memory_store
-            .kv_get(&key)
+            .get_workflow(name, requested_version)
             .await
-            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
-            .ok_or_else(|| ToolError::ExecutionFailed(format!("Workflow '{}' not found", name)))?;
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
-        let mut workflow_args = workflow.value.clone();
-        
-        // Merge inputs into context
-        if let Some(inputs) = arguments.get("inputs") {
-            workflow_args["context"] = inputs.clone();
-        }
+        let (definition, version) = match stored {
+            Some(wf) => (wf.definition, Some(wf.version)),
+            None if requested_version.is_none() => {
+                // Fall back to workflows saved before versioning
+                let legacy = state
+                    .memory_store
+                    .kv_get(&format!("{}{}", LEGACY_WORKFLOW_PREFIX, name))
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+                    .ok_or_else(|| ToolError::ExecutionFailed(format!("Workflow '{}' not found", name)))?;
+                (legacy.value, None)
+            }
+            None => {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Workflow '{}' version {} not found",
+                    name,
+                    requested_version.unwrap_or_default()
+                )))
+            }
+        };
+
+        let steps = definition
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ToolError::ExecutionFailed(format!("Workflow '{}' has no steps", name)))?;
+
+        // Inputs become the initial context
+        let context = arguments.get("inputs").cloned().unwrap_or(json!({}));
 
-        // Execute using workflow.run
-        let run_tool = WorkflowRunTool;
-        run_tool.execute(workflow_args, state).await
+        let result = run_workflow(name, version, steps, &context, &state).await?;
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
     }
 }
 
@@ -426,11 +668,12 @@ impl Tool for WorkflowListTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "workflow.list".to_string(),
-            description: Some("Lists all saved workflows.".to_string()),
+            description: Some("Lists all saved workflows with their latest version.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {}
             }),
+            output_schema: None,
         }
     }
 
@@ -439,17 +682,39 @@ impl Tool for WorkflowListTool {
         _arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        let keys = state
+        let stored = state
             .memory_store
-            .kv_list(Some("workflow:"))
+            .list_workflows()
             .await
             .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
 
-        let workflows: Vec<String> = keys
+        let mut workflows: Vec<Value> = stored
             .iter()
-            .filter_map(|k| k.strip_prefix("workflow:").map(|s| s.to_string()))
+            .map(|wf| {
+                json!({
+                    "name": wf.name,
+                    "version": wf.version,
+                    "description": wf.definition.get("description"),
+                    "updated_at": wf.created_at
+                })
+            })
             .collect();
 
+        // Include workflows saved before versioning
+        let legacy_keys = state
+            .memory_store
+            .kv_list(Some(LEGACY_WORKFLOW_PREFIX))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        for name in legacy_keys
+            .iter()
+            .filter_map(|k| k.strip_prefix(LEGACY_WORKFLOW_PREFIX))
+            .filter(|name| !stored.iter().any(|wf| wf.name == *name))
+        {
+            workflows.push(json!({ "name": name, "version": null, "legacy": true }));
+        }
+
         let result = json!({
             "count": workflows.len(),
             "workflows": workflows
@@ -459,3 +724,510 @@ impl Tool for WorkflowListTool {
     }
 }
 
+/// Tool to inspect past workflow runs and stored versions.
+#[derive(Debug)]
+pub struct WorkflowHistoryTool;
+
+#[async_trait]
+impl Tool for WorkflowHistoryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "workflow.history".to_string(),
+            description: Some(
+                "Lists past workflow runs with per-step timings and outputs. With a name, also lists stored versions.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Only show runs of this workflow"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Max runs to return (default: 20)"
+                    },
+                    "include_steps": {
+                        "type": "boolean",
+                        "description": "Include per-step results (default: true)"
+                    }
+                }
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let name = arguments.get("name").and_then(|v| v.as_str());
+
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(20) as usize;
+
+        let include_steps = arguments
+            .get("include_steps")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let runs = state
+            .memory_store
+            .list_workflow_runs(name, limit)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let runs_json: Vec<Value> = runs
+            .into_iter()
+            .map(|run| {
+                let mut value = json!(run);
+                if !include_steps {
+                    if let Some(obj) = value.as_object_mut() {
+                        obj.remove("steps");
+                    }
+                }
+                value
+            })
+            .collect();
+
+        let mut result = json!({
+            "count": runs_json.len(),
+            "runs": runs_json
+        });
+
+        if let Some(name) = name {
+            let versions = state
+                .memory_store
+                .list_workflow_versions(name)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+            result["workflow"] = json!(name);
+            result["versions"] = versions
+                .iter()
+                .map(|v| json!({ "version": v.version, "created_at": v.created_at }))
+                .collect();
+        }
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Tool to restore an older workflow definition.
+#[derive(Debug)]
+pub struct WorkflowRollbackTool;
+
+#[async_trait]
+impl Tool for WorkflowRollbackTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "workflow.rollback".to_string(),
+            description: Some(
+                "Restores an older version of a workflow by saving it as the new latest version.".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Workflow name"
+                    },
+                    "version": {
+                        "type": "integer",
+                        "description": "Version to restore"
+                    }
+                },
+                "required": ["name", "version"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let name = arguments
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'name'".to_string()))?;
+
+        let version = arguments
+            .get("version")
+            .and_then(|v| v.as_i64())
+            .ok_or_else(|| ToolError::InvalidInput("Missing 'version'".to_string()))?;
+
+        let old = state
+            .memory_store
+            .get_workflow(name, Some(version))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+            .ok_or_else(|| {
+                ToolError::ExecutionFailed(format!("Workflow '{}' version {} not found", name, version))
+            })?;
+
+        let new_version = state
+            .memory_store
+            .save_workflow(name, old.definition)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        remount_composite_tools(&state, name).await;
+
+        let result = json!({
+            "success": true,
+            "workflow": name,
+            "restored_from": version,
+            "version": new_version,
+            "message": format!("Workflow '{}' version {} restored as version {}", name, version, new_version)
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// A tool from `composite_tools`: runs a saved workflow with the call's
+/// arguments as the workflow's inputs.
+#[derive(Debug)]
+pub struct CompositeTool {
+    config: CompositeToolConfig,
+    definition: ToolDefinition,
+}
+
+impl CompositeTool {
+    /// Builds the tool for a saved workflow definition. The description
+    /// and input schema come from the workflow unless the config sets them.
+    pub fn new(config: CompositeToolConfig, workflow: &Value) -> Self {
+        let definition = ToolDefinition {
+            name: config.name.clone(),
+            description: config
+                .description
+                .clone()
+                .or_else(|| workflow.get("description").and_then(|v| v.as_str()).map(String::from)),
+            input_schema: config
+                .input_schema
+                .clone()
+                .unwrap_or_else(|| inputs_schema(workflow.get("inputs"))),
+            output_schema: None,
+        };
+        Self { config, definition }
+    }
+}
+
+/// A workflow input: a bare name or an object with `name` and schema fields.
+fn input_name(input: &Value) -> Option<&str> {
+    input.as_str().or_else(|| input.get("name").and_then(|v| v.as_str()))
+}
+
+/// Whether a call must provide an input: bare names are required, objects
+/// unless they have a default or set `required: false`.
+fn input_required(input: &Value) -> bool {
+    input.is_string()
+        || (input.get("default").is_none() && input.get("required").and_then(|v| v.as_bool()) != Some(false))
+}
+
+/// Derives an input schema from a workflow's `inputs`.
+fn inputs_schema(inputs: Option<&Value>) -> Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+
+    for input in inputs.and_then(|v| v.as_array()).into_iter().flatten() {
+        let Some(name) = input_name(input) else { continue };
+        let mut property = json!({ "type": input.get("type").cloned().unwrap_or(json!("string")) });
+        for field in ["description", "default", "enum", "items"] {
+            if let Some(value) = input.get(field) {
+                property[field] = value.clone();
+            }
+        }
+        properties.insert(name.to_string(), property);
+        if input_required(input) {
+            required.push(json!(name));
+        }
+    }
+
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+#[async_trait]
+impl Tool for CompositeTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let workflow = &self.config.workflow;
+        let stored = state
+            .memory_store
+            .get_workflow(workflow, self.config.version)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+            .ok_or_else(|| ToolError::ExecutionFailed(format!("Workflow '{}' not found", workflow)))?;
+
+        let steps = stored
+            .definition
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ToolError::ExecutionFailed(format!("Workflow '{}' has no steps", workflow)))?;
+
+        // Arguments become the initial context, with defaults filled in
+        let mut context = match arguments {
+            Value::Object(map) => map,
+            Value::Null => serde_json::Map::new(),
+            _ => return Err(ToolError::InvalidInput("Arguments must be an object".to_string())),
+        };
+        for input in stored.definition.get("inputs").and_then(|v| v.as_array()).into_iter().flatten() {
+            let Some(name) = input_name(input) else { continue };
+            if context.contains_key(name) {
+                continue;
+            }
+            match input.get("default") {
+                Some(default) => {
+                    context.insert(name.to_string(), default.clone());
+                }
+                None if input_required(input) => {
+                    return Err(ToolError::InvalidInput(format!("Missing input '{}'", name)));
+                }
+                None => {}
+            }
+        }
+
+        // The workflow may use tools hidden from clients
+        let context = Value::Object(context);
+        let run = run_workflow(workflow, Some(stored.version), steps, &context, &state);
+        let result = registry::with_internal_tools(run).await?;
+
+        let mut output = ToolOutput::text(serde_json::to_string_pretty(&result).unwrap());
+        output.is_error = result["success"] != true;
+        Ok(output)
+    }
+}
+
+/// Builds the composite tool for a config entry from its saved workflow.
+async fn load_composite_tool(config: &CompositeToolConfig, state: &RuntimeState) -> Result<CompositeTool, String> {
+    let stored = state
+        .memory_store
+        .get_workflow(&config.workflow, config.version)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("workflow '{}' is not saved", config.workflow))?;
+    Ok(CompositeTool::new(config.clone(), &stored.definition))
+}
+
+/// Registers the tools in `composite_tools`. Composites whose workflow is
+/// not saved yet are registered once `workflow.define` saves it. Returns the
+/// number of tools registered.
+pub async fn mount_composite_tools(state: &Arc<RuntimeState>) -> usize {
+    let mut mounted = 0;
+    for config in &state.config.composite_tools {
+        match load_composite_tool(config, state).await {
+            Ok(tool) => {
+                state.tool_registry.write().register(Arc::new(tool));
+                mounted += 1;
+            }
+            Err(e) => warn!("Composite tool '{}' not registered: {}", config.name, e),
+        }
+    }
+    if mounted > 0 {
+        info!("Registered {} composite tool(s)", mounted);
+    }
+    mounted
+}
+
+/// Re-registers the composite tools of a workflow after it was saved, so
+/// their schemas follow its inputs.
+async fn remount_composite_tools(state: &Arc<RuntimeState>, workflow: &str) {
+    let mut changed = false;
+    for config in state.config.composite_tools.iter().filter(|c| c.workflow == workflow) {
+        match load_composite_tool(config, state).await {
+            Ok(tool) => {
+                state.tool_registry.write().register(Arc::new(tool));
+                changed = true;
+            }
+            Err(e) => warn!("Composite tool '{}' not registered: {}", config.name, e),
+        }
+    }
+    if changed {
+        state.notify_tools_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::ToolContent;
+
+    #[tokio::test]
+    async fn test_foreach_aggregates_outputs() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let args = json!({
+            "context": {"names": ["a", "b", "c"]},
+            "steps": [{
+                "id": "greet",
+                "foreach": "{{names}}",
+                "as": "name",
+                "steps": [{"tool": "echo", "args": {"text": "hi {{name}} #{{_index}}"}}]
+            }]
+        });
+
+        let output = caller::with_context(RequestContext::new("s1"), WorkflowRunTool.execute(args, state))
+            .await
+            .unwrap();
+        let text = match &output.content[0] {
+            ToolContent::Text { text } => text.clone(),
+            _ => panic!("expected text output"),
+        };
+        let value: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(value["success"], true);
+        assert_eq!(
+            value["final_context"]["greet"],
+            json!(["hi a #0", "hi b #1", "hi c #2"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_define_execute_and_rollback() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+
+        for text in ["one", "two"] {
+            let args = json!({
+                "name": "greet",
+                "steps": [{"id": "say", "tool": "echo", "args": {"text": text}}]
+            });
+            WorkflowDefineTool.execute(args, state.clone()).await.unwrap();
+        }
+
+        WorkflowRollbackTool
+            .execute(json!({"name": "greet", "version": 1}), state.clone())
+            .await
+            .unwrap();
+
+        let output = caller::with_context(
+            RequestContext::new("s1"),
+            WorkflowExecuteTool.execute(json!({"name": "greet"}), state.clone()),
+        )
+        .await
+        .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        let value: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(value["version"], 3);
+        assert_eq!(value["final_context"]["say"], "one");
+
+        let runs = state.memory_store.list_workflow_runs(Some("greet"), 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].steps[0]["duration_ms"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn test_composite_tool_hides_building_blocks() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            composite_tools: vec![CompositeToolConfig {
+                name: "greet".to_string(),
+                description: None,
+                workflow: "greeting".to_string(),
+                version: None,
+                input_schema: None,
+                hide_tools: vec!["echo".to_string()],
+            }],
+            ..Config::default()
+        }));
+        assert_eq!(mount_composite_tools(&state).await, 0);
+
+        let args = json!({
+            "name": "greeting",
+            "description": "Greets someone",
+            "inputs": ["who", {"name": "punctuation", "default": "!"}],
+            "steps": [{"id": "say", "tool": "echo", "args": {"text": "hi {{who}}{{punctuation}}"}}]
+        });
+        WorkflowDefineTool.execute(args, state.clone()).await.unwrap();
+
+        let definitions = state.tool_registry.read().list_definitions();
+        assert!(!definitions.iter().any(|d| d.name == "echo"));
+        let greet = definitions.iter().find(|d| d.name == "greet").unwrap();
+        assert_eq!(greet.description.as_deref(), Some("Greets someone"));
+        assert_eq!(greet.input_schema["required"], json!(["who"]));
+
+        let tool = state.tool_registry.read().get("greet").cloned().unwrap();
+        let run = |args| caller::with_context(RequestContext::new("s1"), tool.execute(args, state.clone()));
+        let output = run(json!({"who": "bob"})).await.unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        let value: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(value["final_context"]["say"], "hi bob!");
+        assert!(run(json!({})).await.is_err());
+
+        // Hidden tools are out of reach of workflows clients run themselves
+        let args = json!({"steps": [{"tool": "echo", "args": {"text": "hi"}}]});
+        let output = caller::with_context(RequestContext::new("s1"), WorkflowRunTool.execute(args, state))
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        assert!(text.contains("Tool not found: echo"));
+    }
+
+    #[tokio::test]
+    async fn test_steps_are_authorized_as_the_caller() {
+        let state = Arc::new(RuntimeState::new(Config {
+            policy: serde_json::from_value(json!({ "enabled": true, "rules": ["deny get_time", "allow *"] })).unwrap(),
+            ..Config::default()
+        }));
+        let steps = json!({"steps": [{"id": "say", "tool": "echo", "args": {"text": "hi"}}, {"tool": "get_time"}]});
+
+        // Without a caller no step runs
+        let err = WorkflowRunTool.execute(steps.clone(), state.clone()).await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+
+        // The policy applies to steps like to direct calls
+        let output = caller::with_context(RequestContext::new("s1"), WorkflowRunTool.execute(steps, state.clone()))
+            .await
+            .unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        let value: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(value["success"], false);
+        assert_eq!(value["final_context"]["say"], "hi");
+        assert!(value["results"][1]["error"].as_str().unwrap().contains("Permission denied"));
+
+        // So do the scopes of the caller's API key
+        let identity = Arc::new(crate::core::KeyIdentity {
+            name: "agent-a".to_string(),
+            key_hash: "hash".to_string(),
+            scopes: vec!["workflow.*".to_string()],
+        });
+        let context = RequestContext::new("s1").with_identity(Some(identity));
+        let steps = json!({"steps": [{"tool": "echo", "args": {"text": "hi"}}]});
+        let output = caller::with_context(context, WorkflowRunTool.execute(steps, state)).await.unwrap();
+        let ToolContent::Text { text } = &output.content[0] else {
+            panic!("expected text output");
+        };
+        assert!(text.contains("not scoped for tool 'echo'"));
+    }
+
+    #[test]
+    fn test_lookup_path_indexes_arrays() {
+        let mut context = HashMap::new();
+        context.insert("search".to_string(), json!({"results": [{"url": "x"}]}));
+
+        assert_eq!(lookup_path("search.results.0.url", &context), Some(&json!("x")));
+        assert_eq!(lookup_path("search.missing", &context), None);
+    }
+}
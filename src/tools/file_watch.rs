@@ -0,0 +1,314 @@
+//! File watches for `fs.watch`.
+//!
+//! Each watch follows a file or directory with the platform's change
+//! notifications (inotify, FSEvents, ReadDirectoryChangesW) and records
+//! created, modified and deleted paths matching its pattern in a ring
+//! buffer. Clients poll the buffer with `fs.watch_events` or subscribe to
+//! the watch's resource to be told when it changes.
+
+use chrono::Utc;
+use globset::{Glob, GlobMatcher};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify};
+
+use crate::tools::ToolError;
+
+/// Events kept per watch before the oldest are dropped.
+pub const MAX_WATCH_EVENTS: usize = 1000;
+
+/// Repeats of the same change to the same path within this window are
+/// recorded once (editors and builds often write a file several times).
+const COALESCE_WINDOW: Duration = Duration::from_millis(200);
+
+/// URI of a watch's resource.
+pub fn watch_uri(id: &str) -> String {
+    format!("nexus://watches/{}", id)
+}
+
+/// A recorded change.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEvent {
+    /// Position in the watch's event sequence, for polling with `since`.
+    pub seq: u64,
+    /// "created", "modified" or "deleted".
+    pub kind: &'static str,
+    pub path: String,
+    /// When the change was seen (RFC 3339).
+    pub timestamp: String,
+}
+
+/// Events of one watch, shared with the watcher thread.
+#[derive(Debug)]
+struct Buffer {
+    events: VecDeque<FileEvent>,
+    next_seq: u64,
+    /// Last (kind, path) recorded and when, for coalescing.
+    last: Option<(&'static str, String, Instant)>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    id: String,
+    root: PathBuf,
+    matcher: Option<GlobMatcher>,
+    /// Match the pattern against file names rather than relative paths.
+    match_name: bool,
+    buffer: Mutex<Buffer>,
+    arrived: Notify,
+    updates: broadcast::Sender<String>,
+}
+
+impl Shared {
+    fn matches(&self, path: &Path) -> bool {
+        let Some(matcher) = &self.matcher else { return true };
+        if self.match_name {
+            return path.file_name().is_some_and(|name| matcher.is_match(name));
+        }
+        path.strip_prefix(&self.root).is_ok_and(|relative| matcher.is_match(relative))
+    }
+
+    fn record(&self, kind: &'static str, path: &Path) {
+        if !self.matches(path) {
+            return;
+        }
+        let path = path.to_string_lossy().into_owned();
+        {
+            let mut buffer = self.buffer.lock();
+            let now = Instant::now();
+            if let Some((last_kind, last_path, at)) = &buffer.last {
+                if *last_kind == kind && *last_path == path && now.duration_since(*at) < COALESCE_WINDOW {
+                    return;
+                }
+            }
+            buffer.last = Some((kind, path.clone(), now));
+            let seq = buffer.next_seq;
+            buffer.next_seq += 1;
+            if buffer.events.len() == MAX_WATCH_EVENTS {
+                buffer.events.pop_front();
+            }
+            buffer.events.push_back(FileEvent { seq, kind, path, timestamp: Utc::now().to_rfc3339() });
+        }
+        self.arrived.notify_waiters();
+        let _ = self.updates.send(watch_uri(&self.id));
+    }
+
+    fn handle(&self, event: notify::Event) {
+        let kind = match event.kind {
+            EventKind::Create(_) => "created",
+            EventKind::Remove(_) => "deleted",
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => "deleted",
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => "created",
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = event.paths.as_slice() {
+                    self.record("deleted", from);
+                    self.record("created", to);
+                }
+                return;
+            }
+            // Platforms that can't tell which side of a rename a path is on
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in &event.paths {
+                    self.record(if path.exists() { "created" } else { "deleted" }, path);
+                }
+                return;
+            }
+            EventKind::Modify(ModifyKind::Metadata(_)) | EventKind::Access(_) => return,
+            EventKind::Modify(_) | EventKind::Any | EventKind::Other => "modified",
+        };
+        for path in &event.paths {
+            self.record(kind, path);
+        }
+    }
+}
+
+/// A file watch and its recorded events.
+#[derive(Debug)]
+struct FileWatch {
+    shared: Arc<Shared>,
+    pattern: Option<String>,
+    recursive: bool,
+    created_at: String,
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatch {
+    fn info(&self) -> Value {
+        let buffer = self.shared.buffer.lock();
+        json!({
+            "id": self.shared.id,
+            "uri": watch_uri(&self.shared.id),
+            "path": self.shared.root.to_string_lossy(),
+            "pattern": self.pattern,
+            "recursive": self.recursive,
+            "created_at": self.created_at,
+            "events": buffer.next_seq,
+        })
+    }
+}
+
+/// Events returned by a poll.
+#[derive(Debug, Serialize)]
+pub struct WatchEvents {
+    pub events: Vec<FileEvent>,
+    /// Pass as `since` to get only later events.
+    pub cursor: u64,
+    /// Whether older events than those asked for were already dropped.
+    pub dropped: bool,
+}
+
+/// Active file watches by ID.
+#[derive(Debug)]
+pub struct FileWatches {
+    watches: Mutex<BTreeMap<String, FileWatch>>,
+    max_watches: usize,
+}
+
+impl FileWatches {
+    /// Creates an empty table allowing `max_watches` watches at a time.
+    pub fn new(max_watches: usize) -> Self {
+        Self { watches: Mutex::new(BTreeMap::new()), max_watches }
+    }
+
+    /// Starts watching `path`. Changes are announced on `updates` as the
+    /// watch's resource URI. Returns the watch ID.
+    pub fn watch(
+        &self,
+        path: &Path,
+        pattern: Option<String>,
+        recursive: bool,
+        updates: broadcast::Sender<String>,
+    ) -> Result<String, ToolError> {
+        if self.watches.lock().len() >= self.max_watches {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Too many file watches (max {}); remove one with fs.unwatch",
+                self.max_watches
+            )));
+        }
+        let matcher = pattern
+            .as_deref()
+            .map(|pattern| Glob::new(pattern).map(|glob| glob.compile_matcher()))
+            .transpose()
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid pattern: {}", e)))?;
+        let root = path
+            .canonicalize()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Cannot watch {}: {}", path.display(), e)))?;
+
+        let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        let shared = Arc::new(Shared {
+            id: id.clone(),
+            root: root.clone(),
+            match_name: pattern.as_deref().is_some_and(|p| !p.contains('/')),
+            matcher,
+            buffer: Mutex::new(Buffer { events: VecDeque::new(), next_seq: 0, last: None }),
+            arrived: Notify::new(),
+            updates,
+        });
+
+        let handler = shared.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => handler.handle(event),
+            Err(e) => tracing::warn!("File watch {} error: {}", handler.id, e),
+        })
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to start watcher: {}", e)))?;
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher
+            .watch(&root, mode)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Cannot watch {}: {}", root.display(), e)))?;
+
+        self.watches.lock().insert(
+            id.clone(),
+            FileWatch { shared, pattern, recursive, created_at: Utc::now().to_rfc3339(), _watcher: watcher },
+        );
+        Ok(id)
+    }
+
+    /// Stops a watch. Returns whether it existed.
+    pub fn unwatch(&self, id: &str) -> bool {
+        self.watches.lock().remove(id).is_some()
+    }
+
+    /// Descriptions of all watches.
+    pub fn list(&self) -> Vec<Value> {
+        self.watches.lock().values().map(FileWatch::info).collect()
+    }
+
+    /// Description of one watch.
+    pub fn info(&self, id: &str) -> Option<Value> {
+        self.watches.lock().get(id).map(FileWatch::info)
+    }
+
+    /// Events with `seq >= since`, at most `limit`. With a `wait`, waits
+    /// that long for the first event if there are none yet.
+    pub async fn events(&self, id: &str, since: u64, limit: usize, wait: Option<Duration>) -> Option<WatchEvents> {
+        let shared = self.watches.lock().get(id)?.shared.clone();
+        let collect = || {
+            let buffer = shared.buffer.lock();
+            let events: Vec<FileEvent> = buffer.events.iter().filter(|e| e.seq >= since).take(limit).cloned().collect();
+            let cursor = events.last().map_or(since, |e| e.seq + 1);
+            let dropped = buffer.events.front().is_some_and(|oldest| oldest.seq > since);
+            WatchEvents { events, cursor, dropped }
+        };
+
+        let Some(wait) = wait else { return Some(collect()) };
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            let arrived = shared.arrived.notified();
+            let events = collect();
+            if !events.events.is_empty() {
+                return Some(events);
+            }
+            if tokio::time::timeout_at(deadline, arrived).await.is_err() {
+                return Some(collect());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watch_records_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let watches = FileWatches::new(4);
+        let (updates, mut announced) = broadcast::channel(16);
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let id = watches.watch(dir.path(), Some("*.rs".to_string()), true, updates).unwrap();
+
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+
+        let polled = watches.events(&id, 0, 10, Some(Duration::from_secs(5))).await.unwrap();
+        let first = &polled.events[0];
+        assert_eq!(first.kind, "created");
+        assert!(first.path.ends_with("main.rs"));
+        assert!(polled.events.iter().all(|e| e.path.ends_with(".rs")));
+        assert_eq!(announced.recv().await.unwrap(), watch_uri(&id));
+
+        std::fs::remove_file(dir.path().join("src/main.rs")).unwrap();
+        let mut deleted = false;
+        let mut cursor = polled.cursor;
+        for _ in 0..50 {
+            let polled = watches.events(&id, cursor, 10, Some(Duration::from_millis(100))).await.unwrap();
+            cursor = polled.cursor;
+            if polled.events.iter().any(|e| e.kind == "deleted") {
+                deleted = true;
+                break;
+            }
+        }
+        assert!(deleted);
+
+        assert!(watches.unwatch(&id));
+        assert!(watches.events(&id, 0, 10, None).await.is_none());
+    }
+}
@@ -0,0 +1,599 @@
+//! Machine-readable descriptions of the tool surface.
+//!
+//! `aegis tools export` writes the registered tool definitions as a JSON
+//! manifest or an OpenAPI document, and `aegis tools stubs` turns them into
+//! typed client code, so the exposed surface can be reviewed, documented
+//! and called without hand-written wrappers.
+
+use serde_json::{json, Map, Value};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use crate::protocol::mcp::Tool as ToolDefinition;
+
+/// Format of `aegis tools export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// The `tools/list` definitions with the server name and version.
+    Json,
+    /// An OpenAPI 3.1 document with one operation per tool.
+    Openapi,
+}
+
+impl FromStr for ManifestFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "openapi" => Ok(Self::Openapi),
+            other => Err(format!("unknown format '{}' (expected json or openapi)", other)),
+        }
+    }
+}
+
+/// Language of `aegis tools stubs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubLanguage {
+    Typescript,
+    Python,
+}
+
+impl FromStr for StubLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "typescript" | "ts" => Ok(Self::Typescript),
+            "python" | "py" => Ok(Self::Python),
+            other => Err(format!("unknown language '{}' (expected typescript or python)", other)),
+        }
+    }
+}
+
+/// Renders the definitions in the given format.
+pub fn export(tools: &[ToolDefinition], format: ManifestFormat) -> Value {
+    match format {
+        ManifestFormat::Json => manifest(tools),
+        ManifestFormat::Openapi => openapi(tools),
+    }
+}
+
+/// The JSON manifest: server name and version, and the definitions sorted by
+/// name.
+pub fn manifest(tools: &[ToolDefinition]) -> Value {
+    json!({
+        "server": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+        "tools": sorted(tools),
+    })
+}
+
+/// An OpenAPI 3.1 document describing each tool as `POST /tools/{name}`.
+///
+/// The paths name the tools rather than real endpoints: calls go through a
+/// JSON-RPC `tools/call` request to `/mcp`, which the document says in its
+/// description and in each operation's `x-mcp-tool` extension.
+pub fn openapi(tools: &[ToolDefinition]) -> Value {
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+
+    for tool in sorted(tools) {
+        let id = identifier(&tool.name, Case::Pascal);
+        let input = hoist_defs(&tool.input_schema, &id, &mut schemas);
+        schemas.insert(format!("{}Input", id), input);
+
+        let result = match &tool.output_schema {
+            Some(output) => {
+                let output = hoist_defs(output, &id, &mut schemas);
+                schemas.insert(format!("{}Output", id), output);
+                json!({
+                    "allOf": [{ "$ref": "#/components/schemas/CallToolResult" }],
+                    "properties": {
+                        "structuredContent": { "$ref": format!("#/components/schemas/{}Output", id) }
+                    }
+                })
+            }
+            None => json!({ "$ref": "#/components/schemas/CallToolResult" }),
+        };
+
+        let mut operation = json!({
+            "operationId": identifier(&tool.name, Case::Camel),
+            "summary": tool.name,
+            "x-mcp-tool": tool.name,
+            "requestBody": {
+                "required": true,
+                "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}Input", id) } } }
+            },
+            "responses": {
+                "200": {
+                    "description": "Tool result",
+                    "content": { "application/json": { "schema": result } }
+                }
+            }
+        });
+        if let Some(description) = &tool.description {
+            operation["description"] = json!(description);
+        }
+        paths.insert(format!("/tools/{}", tool.name), json!({ "post": operation }));
+    }
+
+    schemas.insert(
+        "CallToolResult".to_string(),
+        json!({
+            "type": "object",
+            "required": ["content"],
+            "properties": {
+                "content": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["type"],
+                        "properties": {
+                            "type": { "type": "string", "enum": ["text", "image", "resource"] },
+                            "text": { "type": "string" },
+                            "data": { "type": "string" },
+                            "mimeType": { "type": "string" }
+                        }
+                    }
+                },
+                "structuredContent": {},
+                "isError": { "type": "boolean" }
+            }
+        }),
+    );
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": format!("{} tools", env!("CARGO_PKG_NAME")),
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Each operation is an MCP tool. Call it with a JSON-RPC `tools/call` request to `POST /mcp`, with the tool name (`x-mcp-tool`) as `name` and the request body as `arguments`."
+        },
+        "paths": paths,
+        "components": { "schemas": schemas }
+    })
+}
+
+/// Moves a schema's `$defs` into `schemas` under names prefixed with
+/// `prefix`, and points its references there.
+fn hoist_defs(schema: &Value, prefix: &str, schemas: &mut Map<String, Value>) -> Value {
+    let mut schema = schema.clone();
+    let defs = schema.as_object_mut().and_then(|o| o.remove("$defs"));
+    if let Some(Value::Object(defs)) = defs {
+        for (name, mut def) in defs {
+            rewrite_refs(&mut def, prefix);
+            schemas.insert(format!("{}{}", prefix, name), def);
+        }
+        rewrite_refs(&mut schema, prefix);
+    }
+    schema
+}
+
+fn rewrite_refs(value: &mut Value, prefix: &str) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => {
+                        if let Some(name) = reference.strip_prefix("#/$defs/") {
+                            *reference = format!("#/components/schemas/{}{}", prefix, name);
+                        }
+                    }
+                    (_, value) => rewrite_refs(value, prefix),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rewrite_refs(item, prefix)),
+        _ => {}
+    }
+}
+
+/// Client code with an argument type and a method per tool. The generated
+/// class takes a function that performs `tools/call`, so it works with any
+/// MCP client library.
+pub fn stubs(tools: &[ToolDefinition], language: StubLanguage) -> String {
+    match language {
+        StubLanguage::Typescript => typescript_stubs(&sorted(tools)),
+        StubLanguage::Python => python_stubs(&sorted(tools)),
+    }
+}
+
+fn typescript_stubs(tools: &[&ToolDefinition]) -> String {
+    let mut out = format!(
+        "// Generated by `aegis tools stubs` from {} {}. Do not edit.\n\n\
+         export interface CallToolResult {{\n  \
+         content: Array<{{ type: string; text?: string; data?: string; mimeType?: string }}>;\n  \
+         structuredContent?: unknown;\n  \
+         isError?: boolean;\n\
+         }}\n\n\
+         /** Sends a `tools/call` request and returns its result. */\n\
+         export type CallTool = (name: string, args: Record<string, unknown>) => Promise<CallToolResult>;\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    for tool in tools {
+        let _ = write!(out, "\nexport interface {}Args {{\n", identifier(&tool.name, Case::Pascal));
+        for (name, schema, required) in properties(&tool.input_schema) {
+            if let Some(description) = schema.get("description").and_then(Value::as_str) {
+                let _ = writeln!(out, "  /** {} */", doc_line(description));
+            }
+            let optional = if required { "" } else { "?" };
+            let _ = writeln!(out, "  {}{}: {};", quoted_key(&name), optional, typescript_type(schema, &tool.input_schema));
+        }
+        out.push_str("}\n");
+    }
+
+    out.push_str("\nexport class AegisTools {\n  constructor(private readonly callTool: CallTool) {}\n");
+    for tool in tools {
+        let args = format!("{}Args", identifier(&tool.name, Case::Pascal));
+        // Tools whose arguments are all optional can be called without any
+        let param = if properties(&tool.input_schema).iter().any(|(_, _, required)| *required) {
+            format!("args: {}", args)
+        } else {
+            format!("args: {} = {{}}", args)
+        };
+        out.push('\n');
+        if let Some(description) = &tool.description {
+            let _ = writeln!(out, "  /** {} */", doc_line(description));
+        }
+        let _ = writeln!(
+            out,
+            "  {}({}): Promise<CallToolResult> {{\n    return this.callTool({}, args as Record<string, unknown>);\n  }}",
+            identifier(&tool.name, Case::Camel),
+            param,
+            json!(tool.name)
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn python_stubs(tools: &[&ToolDefinition]) -> String {
+    let mut out = format!(
+        "# Generated by `aegis tools stubs` from {} {}. Do not edit.\n\n\
+         from typing import Any, Awaitable, Callable, Literal, NotRequired, TypedDict\n\n\
+         # Sends a `tools/call` request and returns its result.\n\
+         CallTool = Callable[[str, dict[str, Any]], Awaitable[dict[str, Any]]]\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    for tool in tools {
+        let class = format!("{}Args", identifier(&tool.name, Case::Pascal));
+        let properties = properties(&tool.input_schema);
+        let fields: Vec<_> = properties
+            .iter()
+            .map(|(name, schema, required)| {
+                let ty = python_type(schema, &tool.input_schema);
+                (name, *schema, if *required { ty } else { format!("NotRequired[{}]", ty) })
+            })
+            .collect();
+
+        // Keys that aren't identifiers need the functional syntax
+        if !fields.iter().all(|(name, _, _)| is_python_identifier(name)) {
+            let fields: Vec<_> = fields.iter().map(|(name, _, ty)| format!("{}: {}", json!(name), ty)).collect();
+            let _ = write!(out, "\n\n{} = TypedDict({}, {{{}}})\n", class, json!(class), fields.join(", "));
+            continue;
+        }
+        let _ = write!(out, "\n\nclass {}(TypedDict):\n", class);
+        if fields.is_empty() {
+            out.push_str("    pass\n");
+        }
+        for (name, schema, ty) in fields {
+            let _ = writeln!(out, "    {}: {}", name, ty);
+            if let Some(description) = schema.get("description").and_then(Value::as_str) {
+                let _ = writeln!(out, "    \"\"\"{}\"\"\"", python_doc(description));
+            }
+        }
+    }
+
+    out.push_str("\n\nclass AegisTools:\n    def __init__(self, call_tool: CallTool) -> None:\n        self._call_tool = call_tool\n");
+    for tool in tools {
+        let mut method = identifier(&tool.name, Case::Snake);
+        if !is_python_identifier(&method) {
+            method.push('_');
+        }
+        let args = format!("{}Args", identifier(&tool.name, Case::Pascal));
+        // Tools whose arguments are all optional can be called without any
+        let (param, value) = if properties(&tool.input_schema).iter().any(|(_, _, required)| *required) {
+            (format!("args: {}", args), "dict(args)")
+        } else {
+            (format!("args: {} | None = None", args), "dict(args or {})")
+        };
+        let _ = write!(out, "\n    async def {}(self, {}) -> dict[str, Any]:\n", method, param);
+        if let Some(description) = &tool.description {
+            let _ = writeln!(out, "        \"\"\"{}\"\"\"", python_doc(description));
+        }
+        let _ = writeln!(out, "        return await self._call_tool({}, {})", json!(tool.name), value);
+    }
+    out
+}
+
+/// The definitions sorted by name.
+fn sorted(tools: &[ToolDefinition]) -> Vec<&ToolDefinition> {
+    let mut tools: Vec<_> = tools.iter().collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    tools
+}
+
+/// An object schema's properties with whether each is required.
+fn properties(schema: &Value) -> Vec<(String, &Value, bool)> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, schema)| (name.clone(), schema, required.contains(&name.as_str())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Follows a `#/$defs/...` reference within `root`.
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .and_then(|name| root.get("$defs")?.get(name))
+        .unwrap_or(schema)
+}
+
+/// The variants of a union schema (`anyOf`, `oneOf`, or a list of types
+/// without an `enum`), if it is one.
+fn variants(schema: &Value) -> Option<Vec<Value>> {
+    if let Some(variants) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(Value::as_array) {
+        return Some(variants.clone());
+    }
+    // An enum already lists the values its types allow
+    if schema.get("enum").is_some() || schema.get("const").is_some() {
+        return None;
+    }
+    let types = schema.get("type")?.as_array()?;
+    Some(
+        types
+            .iter()
+            .map(|ty| {
+                let mut variant = schema.clone();
+                variant["type"] = ty.clone();
+                variant
+            })
+            .collect(),
+    )
+}
+
+fn typescript_type(schema: &Value, root: &Value) -> String {
+    let schema = resolve(schema, root);
+    if let Some(variants) = variants(schema) {
+        let types: Vec<_> = variants.iter().map(|v| typescript_type(v, root)).collect();
+        return types.join(" | ");
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ");
+    }
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => "string".to_string(),
+        Some("integer") | Some("number") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("null") => "null".to_string(),
+        Some("array") => match schema.get("items") {
+            Some(items) => format!("Array<{}>", typescript_type(items, root)),
+            None => "unknown[]".to_string(),
+        },
+        Some("object") if schema.get("properties").is_some() => {
+            let fields: Vec<_> = properties(schema)
+                .into_iter()
+                .map(|(name, property, required)| {
+                    format!("{}{}: {}", quoted_key(&name), if required { "" } else { "?" }, typescript_type(property, root))
+                })
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        }
+        Some("object") => match schema.get("additionalProperties") {
+            Some(values) if values.is_object() => format!("Record<string, {}>", typescript_type(values, root)),
+            _ => "Record<string, unknown>".to_string(),
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+fn python_type(schema: &Value, root: &Value) -> String {
+    let schema = resolve(schema, root);
+    if let Some(variants) = variants(schema) {
+        let types: Vec<_> = variants.iter().map(|v| python_type(v, root)).collect();
+        return types.join(" | ");
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return format!("Literal[{}]", values.iter().map(python_literal).collect::<Vec<_>>().join(", "));
+    }
+    if let Some(value) = schema.get("const") {
+        return format!("Literal[{}]", python_literal(value));
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => "str".to_string(),
+        Some("integer") => "int".to_string(),
+        Some("number") => "float".to_string(),
+        Some("boolean") => "bool".to_string(),
+        Some("null") => "None".to_string(),
+        Some("array") => match schema.get("items") {
+            Some(items) => format!("list[{}]", python_type(items, root)),
+            None => "list[Any]".to_string(),
+        },
+        Some("object") => match schema.get("additionalProperties") {
+            Some(values) if values.is_object() && schema.get("properties").is_none() => {
+                format!("dict[str, {}]", python_type(values, root))
+            }
+            _ => "dict[str, Any]".to_string(),
+        },
+        _ => "Any".to_string(),
+    }
+}
+
+fn python_literal(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        Value::Null => "None".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A property name as a TypeScript key, quoted unless it's an identifier.
+fn quoted_key(name: &str) -> String {
+    let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        name.to_string()
+    } else {
+        json!(name).to_string()
+    }
+}
+
+/// Whether `name` can be used as a Python attribute name.
+fn is_python_identifier(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+        "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ];
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// A description on one line, safe inside a Python docstring.
+fn python_doc(text: &str) -> String {
+    doc_line(text).replace('\\', "\\\\").replace('"', "'")
+}
+
+/// A description on one line, safe inside a doc comment.
+fn doc_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").replace("*/", "* /")
+}
+
+#[derive(Clone, Copy)]
+enum Case {
+    Pascal,
+    Camel,
+    Snake,
+}
+
+/// A tool name as an identifier: `fs.read_file` becomes `FsReadFile`,
+/// `fsReadFile` or `fs_read_file`.
+fn identifier(name: &str, case: Case) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let capitalize = |word: &String| {
+        let mut chars = word.chars();
+        chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+    };
+    let mut identifier = match case {
+        Case::Pascal => words.iter().map(capitalize).collect(),
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| if i == 0 { word.clone() } else { capitalize(word) })
+            .collect(),
+        Case::Snake => words.join("_"),
+    };
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tools() -> Vec<ToolDefinition> {
+        vec![
+            ToolDefinition {
+                name: "fs.read_file".to_string(),
+                description: Some("Reads a file.".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "File to read" },
+                        "encoding": { "anyOf": [{ "$ref": "#/$defs/Encoding" }, { "type": "null" }] },
+                        "max-bytes": { "type": ["integer", "null"] }
+                    },
+                    "required": ["path"],
+                    "$defs": { "Encoding": { "type": "string", "enum": ["utf8", "base64"] } }
+                }),
+                output_schema: Some(json!({ "type": "object", "properties": { "content": { "type": "string" } } })),
+            },
+            ToolDefinition {
+                name: "echo".to_string(),
+                description: None,
+                input_schema: json!({ "type": "object", "properties": { "text": { "type": "string" } } }),
+                output_schema: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let document = openapi(&tools());
+        let operation = &document["paths"]["/tools/fs.read_file"]["post"];
+        assert_eq!(operation["operationId"], "fsReadFile");
+        assert_eq!(operation["x-mcp-tool"], "fs.read_file");
+
+        let schemas = &document["components"]["schemas"];
+        let input = &schemas["FsReadFileInput"];
+        assert!(input.get("$defs").is_none());
+        assert_eq!(input["properties"]["encoding"]["anyOf"][0]["$ref"], "#/components/schemas/FsReadFileEncoding");
+        assert_eq!(schemas["FsReadFileEncoding"]["enum"], json!(["utf8", "base64"]));
+        assert_eq!(schemas["FsReadFileOutput"]["properties"]["content"]["type"], "string");
+        assert_eq!(
+            document["paths"]["/tools/echo"]["post"]["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/CallToolResult"
+        );
+
+        let manifest = manifest(&tools());
+        assert_eq!(manifest["tools"][0]["name"], "echo");
+        assert_eq!(manifest["tools"][1]["inputSchema"]["required"], json!(["path"]));
+    }
+
+    #[test]
+    fn test_stubs() {
+        let typescript = stubs(&tools(), StubLanguage::Typescript);
+        assert!(typescript.contains("export interface FsReadFileArgs {\n"));
+        assert!(typescript.contains("  /** File to read */\n  path: string;"));
+        assert!(typescript.contains("  encoding?: \"utf8\" | \"base64\" | null;"));
+        assert!(typescript.contains("  \"max-bytes\"?: number | null;"));
+        assert!(typescript.contains("  fsReadFile(args: FsReadFileArgs): Promise<CallToolResult> {"));
+        assert!(typescript.contains("  echo(args: EchoArgs = {}): Promise<CallToolResult> {"));
+
+        let python = stubs(&tools(), StubLanguage::Python);
+        assert!(python.contains("class EchoArgs(TypedDict):\n    text: NotRequired[str]\n"));
+        // "max-bytes" isn't an identifier
+        assert!(python.contains(
+            "FsReadFileArgs = TypedDict(\"FsReadFileArgs\", {\"encoding\": NotRequired[Literal[\"utf8\", \"base64\"] | None], \
+             \"max-bytes\": NotRequired[int | None], \"path\": str})"
+        ));
+        assert!(python.contains("    async def fs_read_file(self, args: FsReadFileArgs) -> dict[str, Any]:"));
+        assert!(python.contains("return await self._call_tool(\"fs.read_file\", dict(args))"));
+        assert!(python.contains("    async def echo(self, args: EchoArgs | None = None) -> dict[str, Any]:"));
+
+        let mut keyword = tools().remove(1);
+        keyword.name = "import".to_string();
+        keyword.input_schema = json!({ "type": "object", "properties": { "from": { "type": "string" } } });
+        let python = stubs(&[keyword], StubLanguage::Python);
+        assert!(python.contains("ImportArgs = TypedDict(\"ImportArgs\", {\"from\": NotRequired[str]})"));
+        assert!(python.contains("    async def import_(self, args: ImportArgs | None = None)"));
+    }
+}
@@ -0,0 +1,194 @@
+//! Per-session call budgets for expensive tools.
+//!
+//! Counts LLM, outbound HTTP and `cmd.exec` calls per session over a sliding
+//! window and rejects calls once a limit is reached, so a runaway agent loop
+//! cannot burn through API credits or hammer external services.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::{BudgetConfig, BudgetLimits};
+use crate::core::RuntimeState;
+use crate::tools::{ToolError, ToolOutput};
+
+/// Budgeted tool categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BudgetCategory {
+    Llm,
+    Http,
+    Cmd,
+}
+
+impl BudgetCategory {
+    /// Classifies a tool by name. Returns `None` for unbudgeted tools.
+    pub fn of(tool_name: &str) -> Option<Self> {
+        if super::is_metered(tool_name) {
+            Some(Self::Llm)
+        } else if tool_name == "http.request" || tool_name.starts_with("web.") {
+            Some(Self::Http)
+        } else if tool_name == "cmd.exec" {
+            Some(Self::Cmd)
+        } else {
+            None
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Llm => "LLM calls",
+            Self::Http => "HTTP requests",
+            Self::Cmd => "cmd.exec invocations",
+        }
+    }
+
+    fn limit(self, limits: &BudgetLimits) -> Option<u32> {
+        match self {
+            Self::Llm => limits.max_llm_calls,
+            Self::Http => limits.max_http_requests,
+            Self::Cmd => limits.max_cmd_exec,
+        }
+    }
+}
+
+/// Middleware enforcing per-session budgets.
+#[derive(Debug)]
+pub struct BudgetMiddleware {
+    config: BudgetConfig,
+    /// Call timestamps per (session, category) within the window.
+    usage: DashMap<(String, BudgetCategory), VecDeque<Instant>>,
+}
+
+impl BudgetMiddleware {
+    /// Creates a new budget middleware with the given configuration.
+    pub fn new(config: BudgetConfig) -> Self {
+        Self {
+            config,
+            usage: DashMap::new(),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs)
+    }
+
+    /// Records a call if it fits within the budget.
+    fn try_consume(
+        &self,
+        session: &str,
+        category: BudgetCategory,
+        limit: u32,
+    ) -> Result<(), ToolError> {
+        let now = Instant::now();
+        let window = self.window();
+        let mut calls = self
+            .usage
+            .entry((session.to_string(), category))
+            .or_default();
+
+        while calls.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            calls.pop_front();
+        }
+
+        if calls.len() as u32 >= limit {
+            let retry_after = calls
+                .front()
+                .map(|t| window.saturating_sub(now.duration_since(*t)).as_secs() + 1)
+                .unwrap_or(1);
+            warn!("Session '{}' exceeded its budget of {} {}", session, limit, category.label());
+            return Err(ToolError::BudgetExceeded(format!(
+                "session '{}' used {}/{} {} in the last {}s; retry in {}s",
+                session,
+                calls.len(),
+                limit,
+                category.label(),
+                self.config.window_secs,
+                retry_after
+            )));
+        }
+
+        calls.push_back(now);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for BudgetMiddleware {
+    fn name(&self) -> &str {
+        "budget"
+    }
+
+    async fn before(
+        &self,
+        call: &mut ToolCall,
+        _state: &Arc<RuntimeState>,
+    ) -> Result<Option<ToolOutput>, ToolError> {
+        let Some(category) = BudgetCategory::of(&call.name) else {
+            return Ok(None);
+        };
+
+        let limits = call
+            .context
+            .api_key_hash
+            .as_ref()
+            .and_then(|hash| self.config.per_key.get(hash))
+            .unwrap_or(&self.config.default);
+
+        if let Some(limit) = category.limit(limits) {
+            self.try_consume(&call.context.session_id, category, limit)?;
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::RequestContext;
+    use serde_json::json;
+
+    fn call(name: &str, session: &str) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            arguments: json!({}),
+            context: RequestContext::new(session),
+        }
+    }
+
+    #[test]
+    fn test_category_classification() {
+        assert_eq!(BudgetCategory::of("llm.openai"), Some(BudgetCategory::Llm));
+        assert_eq!(BudgetCategory::of("web.search"), Some(BudgetCategory::Http));
+        assert_eq!(BudgetCategory::of("cmd.exec"), Some(BudgetCategory::Cmd));
+        assert_eq!(BudgetCategory::of("echo"), None);
+        assert_eq!(BudgetCategory::of("llm.usage"), None);
+    }
+
+    #[tokio::test]
+    async fn test_budget_is_per_session() {
+        let state = Arc::new(RuntimeState::new(crate::core::Config::default()));
+        let budget = BudgetMiddleware::new(BudgetConfig {
+            enabled: true,
+            default: BudgetLimits {
+                max_cmd_exec: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        for _ in 0..2 {
+            assert!(budget.before(&mut call("cmd.exec", "a"), &state).await.is_ok());
+        }
+        let err = budget.before(&mut call("cmd.exec", "a"), &state).await.unwrap_err();
+        assert!(matches!(err, ToolError::BudgetExceeded(_)));
+
+        // Other sessions and unbudgeted tools are unaffected
+        assert!(budget.before(&mut call("cmd.exec", "b"), &state).await.is_ok());
+        assert!(budget.before(&mut call("echo", "a"), &state).await.is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+//! Fault injection for testing agents against a misbehaving server.
+//!
+//! Rules from `chaos.rules` add latency and/or random failures to matching
+//! tools. Only active when `chaos.enabled` is set.
+
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::{ChaosConfig, ChaosRule};
+use crate::core::RuntimeState;
+use crate::tools::{ToolError, ToolOutput};
+
+/// Middleware that injects latency and failures.
+#[derive(Debug)]
+pub struct ChaosMiddleware {
+    rules: Vec<ChaosRule>,
+    /// xorshift64 state; good enough for fault injection.
+    rng: AtomicU64,
+}
+
+impl ChaosMiddleware {
+    /// Creates a new chaos middleware with the given configuration.
+    pub fn new(config: ChaosConfig) -> Self {
+        let seed = config.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0x9E37_79B9_7F4A_7C15)
+        });
+
+        Self {
+            rules: config.rules,
+            // xorshift must never be seeded with zero
+            rng: AtomicU64::new(seed.max(1)),
+        }
+    }
+
+    /// Returns the first rule matching the tool name.
+    fn rule_for(&self, tool_name: &str) -> Option<&ChaosRule> {
+        self.rules.iter().find(|rule| match rule.tool.strip_suffix('*') {
+            Some(prefix) => tool_name.starts_with(prefix),
+            None => rule.tool == tool_name,
+        })
+    }
+
+    /// Returns a pseudo-random number in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        let mut x = self.rng.load(Ordering::Relaxed);
+        loop {
+            let mut next = x;
+            next ^= next << 13;
+            next ^= next >> 7;
+            next ^= next << 17;
+            match self
+                .rng
+                .compare_exchange_weak(x, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return (next >> 11) as f64 / (1u64 << 53) as f64,
+                Err(current) => x = current,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for ChaosMiddleware {
+    fn name(&self) -> &str {
+        "chaos"
+    }
+
+    async fn before(
+        &self,
+        call: &mut ToolCall,
+        _state: &Arc<RuntimeState>,
+    ) -> Result<Option<ToolOutput>, ToolError> {
+        let Some(rule) = self.rule_for(&call.name) else {
+            return Ok(None);
+        };
+
+        let jitter = (self.next_f64() * rule.jitter_ms as f64) as u64;
+        let delay = rule.latency_ms + jitter;
+        if delay > 0 {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+
+        if rule.error_rate > 0.0 && self.next_f64() < rule.error_rate {
+            warn!("Chaos: injecting failure into {}", call.name);
+            let message = rule
+                .error_message
+                .clone()
+                .unwrap_or_else(|| "Injected fault".to_string());
+            return Err(ToolError::ExecutionFailed(message));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chaos(rules: Vec<ChaosRule>) -> ChaosMiddleware {
+        ChaosMiddleware::new(ChaosConfig {
+            enabled: true,
+            seed: Some(42),
+            rules,
+        })
+    }
+
+    fn rule(tool: &str, error_rate: f64) -> ChaosRule {
+        ChaosRule {
+            tool: tool.to_string(),
+            latency_ms: 0,
+            jitter_ms: 0,
+            error_rate,
+            error_message: None,
+        }
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let chaos = chaos(vec![rule("llm.*", 0.0), rule("echo", 0.0)]);
+        assert!(chaos.rule_for("llm.openai").is_some());
+        assert!(chaos.rule_for("echo").is_some());
+        assert!(chaos.rule_for("echo2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_rate() {
+        let state = Arc::new(RuntimeState::new(crate::core::Config::default()));
+        let chaos = chaos(vec![rule("always", 1.0), rule("never", 0.0)]);
+        let call = |name: &str| ToolCall {
+            name: name.to_string(),
+            arguments: json!({}),
+            context: Default::default(),
+        };
+
+        assert!(chaos.before(&mut call("always"), &state).await.is_err());
+        assert!(chaos.before(&mut call("never"), &state).await.is_ok());
+        assert!(chaos.before(&mut call("unmatched"), &state).await.is_ok());
+    }
+
+    #[test]
+    fn test_seeded_rng_is_deterministic() {
+        let a = chaos(vec![]);
+        let b = chaos(vec![]);
+        for _ in 0..10 {
+            let x = a.next_f64();
+            assert_eq!(x, b.next_f64());
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+}
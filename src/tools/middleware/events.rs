@@ -0,0 +1,65 @@
+//! Publishes `tool.succeeded` and `tool.failed` events for client tool
+//! calls, so event rules can chain work onto them.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::RuntimeState;
+use crate::tools::{ToolContent, ToolError, ToolOutput};
+
+/// Middleware that publishes the outcome of every tool call.
+#[derive(Debug, Default)]
+pub struct EventMiddleware;
+
+/// A tool's result as event data: its structured content, or its text
+/// (parsed when it holds JSON).
+pub fn output_value(output: &ToolOutput) -> Value {
+    if let Some(structured) = &output.structured_content {
+        return structured.clone();
+    }
+    let text: Vec<&str> = output
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ToolContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let text = text.join("\n");
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+#[async_trait]
+impl ToolMiddleware for EventMiddleware {
+    fn name(&self) -> &str {
+        "events"
+    }
+
+    async fn after(
+        &self,
+        call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        match &result {
+            Ok(output) if !output.is_error => state.events.publish(
+                "tool.succeeded",
+                &call.name,
+                json!({ "arguments": call.arguments, "output": output_value(output) }),
+            ),
+            Ok(output) => state.events.publish(
+                "tool.failed",
+                &call.name,
+                json!({ "arguments": call.arguments, "error": output_value(output) }),
+            ),
+            Err(e) => state.events.publish(
+                "tool.failed",
+                &call.name,
+                json!({ "arguments": call.arguments, "error": e.to_string() }),
+            ),
+        }
+        result
+    }
+}
@@ -0,0 +1,183 @@
+//! Tool middleware chain.
+//!
+//! Middleware wraps every client-initiated tool call (`tools/call`). Each
+//! middleware gets a `before` hook, which may rewrite the call or
+//! short-circuit it, and an `after` hook, which may rewrite the result.
+//! `after` hooks run in reverse order of registration.
+
+mod budget;
+mod chaos;
+mod events;
+mod output_limit;
+mod rate_limit;
+mod scrub;
+mod summarizer;
+mod usage;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::core::{Config, RequestContext, RuntimeState};
+use crate::tools::{Tool, ToolError, ToolOutput};
+
+pub use budget::{BudgetCategory, BudgetMiddleware};
+pub use chaos::ChaosMiddleware;
+pub use events::{output_value, EventMiddleware};
+pub use output_limit::{output_chunk, OutputLimitMiddleware};
+pub use rate_limit::RateLimitMiddleware;
+pub use scrub::{scrub_result, ScrubMiddleware};
+pub use summarizer::{estimate_tokens, SummarizerMiddleware, OUTPUT_KEY_PREFIX};
+pub(crate) use summarizer::{output_key, output_text};
+pub use usage::{estimate_cost, is_metered, month_start, usage_report, UsageGrouping, UsageMiddleware};
+
+/// A tool call as seen by middleware.
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    /// Name of the tool being called.
+    pub name: String,
+    /// Arguments passed to the tool.
+    pub arguments: Value,
+    /// Caller the call is made on behalf of.
+    pub context: RequestContext,
+}
+
+/// Hook points around tool execution.
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync + Debug {
+    /// Short name used in logs.
+    fn name(&self) -> &str;
+
+    /// Runs before the tool. Returning `Ok(Some(output))` skips the tool
+    /// (and any later middleware) and uses `output` as the result.
+    async fn before(
+        &self,
+        _call: &mut ToolCall,
+        _state: &Arc<RuntimeState>,
+    ) -> Result<Option<ToolOutput>, ToolError> {
+        Ok(None)
+    }
+
+    /// Runs after the tool (or after a short-circuit) with its result.
+    async fn after(
+        &self,
+        _call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        _state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        result
+    }
+}
+
+/// Ordered list of middleware applied to tool calls.
+#[derive(Debug, Default)]
+pub struct MiddlewareChain {
+    middleware: Vec<Arc<dyn ToolMiddleware>>,
+}
+
+impl MiddlewareChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the chain enabled by the given configuration.
+    pub fn from_config(config: &Config) -> Self {
+        let mut chain = Self::new();
+
+        // Outermost, so events carry the result the client gets
+        chain.push(Arc::new(EventMiddleware));
+
+        // Next, so the cap applies to whatever the rest of the chain returns
+        if config.output_limit.max_bytes > 0 {
+            chain.push(Arc::new(OutputLimitMiddleware::new(config.output_limit.clone())));
+        }
+
+        // Before budgets, so calls turned away here don't use them up
+        if !config.rate_limit.tools.is_empty() {
+            chain.push(Arc::new(RateLimitMiddleware::new(config)));
+        }
+
+        if config.chaos.enabled {
+            warn!("Chaos middleware is enabled: tool calls may be delayed or fail on purpose");
+            chain.push(Arc::new(ChaosMiddleware::new(config.chaos.clone())));
+        }
+
+        if config.budget.enabled {
+            chain.push(Arc::new(BudgetMiddleware::new(config.budget.clone())));
+        }
+
+        if config.summarizer.enabled {
+            chain.push(Arc::new(SummarizerMiddleware::new(config.summarizer.clone())));
+        }
+
+        // Inside the summarizer and output limit, so neither the stashed
+        // full output nor anything sent on carries a secret
+        if config.security.scrub_secrets {
+            chain.push(Arc::new(ScrubMiddleware));
+        }
+
+        // Innermost, so usage is read from the raw LLM result
+        if config.llm.usage.enabled {
+            chain.push(Arc::new(UsageMiddleware::new(config.llm.usage.clone())));
+        }
+
+        chain
+    }
+
+    /// Appends a middleware to the end of the chain.
+    pub fn push(&mut self, middleware: Arc<dyn ToolMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Returns the number of middleware in the chain.
+    pub fn len(&self) -> usize {
+        self.middleware.len()
+    }
+
+    /// Returns whether the chain is empty.
+    pub fn is_empty(&self) -> bool {
+        self.middleware.is_empty()
+    }
+
+    /// Executes a tool through the chain.
+    pub async fn execute(
+        &self,
+        tool: Arc<dyn Tool>,
+        mut call: ToolCall,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let mut ran = 0;
+        let mut result = None;
+
+        for middleware in &self.middleware {
+            ran += 1;
+            match middleware.before(&mut call, &state).await {
+                Ok(None) => {}
+                Ok(Some(output)) => {
+                    debug!("Middleware '{}' short-circuited {}", middleware.name(), call.name);
+                    result = Some(Ok(output));
+                    break;
+                }
+                Err(e) => {
+                    debug!("Middleware '{}' rejected {}: {}", middleware.name(), call.name, e);
+                    result = Some(Err(e));
+                    break;
+                }
+            }
+        }
+
+        let mut result = match result {
+            Some(result) => result,
+            None => tool.execute(call.arguments.clone(), state.clone()).await,
+        };
+
+        for middleware in self.middleware[..ran].iter().rev() {
+            result = middleware.after(&call, result, &state).await;
+        }
+
+        result
+    }
+}
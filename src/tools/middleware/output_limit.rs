@@ -0,0 +1,182 @@
+//! Caps the size of tool results.
+//!
+//! Text beyond `output_limit.max_bytes` is cut from the middle: the client
+//! gets the head and tail of the output around a marker naming the
+//! `nexus://outputs/{id}` resource that holds the full text, which it can
+//! read whole or in `?chunk=N` pieces.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::debug;
+
+use super::summarizer::{output_text, stash_output};
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::OutputLimitConfig;
+use crate::core::RuntimeState;
+use crate::tools::{ToolContent, ToolError, ToolOutput};
+
+/// Middleware that truncates oversized tool results.
+#[derive(Debug)]
+pub struct OutputLimitMiddleware {
+    config: OutputLimitConfig,
+}
+
+impl OutputLimitMiddleware {
+    /// Creates the middleware with the given limits.
+    pub fn new(config: OutputLimitConfig) -> Self {
+        Self { config }
+    }
+
+    /// The note put where the output was cut.
+    fn marker(&self, id: &str, total: usize, omitted: usize) -> String {
+        let chunks = total.div_ceil(self.config.chunk_bytes.max(1));
+        format!(
+            "\n\n[... {} of {} bytes omitted. Full output: nexus://outputs/{}, also readable in {} chunks as nexus://outputs/{}?chunk=0..{} ...]\n\n",
+            omitted,
+            total,
+            id,
+            chunks,
+            id,
+            chunks.saturating_sub(1)
+        )
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for OutputLimitMiddleware {
+    fn name(&self) -> &str {
+        "output_limit"
+    }
+
+    async fn after(
+        &self,
+        call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let mut output = result?;
+        let max = self.config.max_bytes;
+        let structured_len = output.structured_content.as_ref().map_or(0, |v| v.to_string().len());
+        let mut text = output_text(&output);
+        if max == 0 || (text.len() <= max && structured_len <= max) {
+            return Ok(output);
+        }
+        if text.is_empty() {
+            text = output.structured_content.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        }
+
+        debug!("Truncating {} output ({} bytes, limit {})", call.name, text.len(), max);
+        let id = stash_output(state, &text, self.config.retention_secs).await?;
+
+        // Size the cut for the longest marker, then write the real one
+        let budget = max.saturating_sub(self.marker(&id, text.len(), text.len()).len());
+        let (head, tail) = head_and_tail(&text, budget, self.config.head_ratio);
+        let marker = self.marker(&id, text.len(), text.len() - head.len() - tail.len());
+
+        // Structured content can't be cut without breaking it
+        output.structured_content = None;
+        let images = output.content.into_iter().filter(|c| !matches!(c, ToolContent::Text { .. }));
+        output.content = std::iter::once(ToolContent::Text { text: format!("{}{}{}", head, marker, tail) })
+            .chain(images)
+            .collect();
+        Ok(output)
+    }
+}
+
+/// Splits off up to `budget` bytes of `text`, `head_ratio` of them from the
+/// start and the rest from the end, preferring to cut at line breaks.
+fn head_and_tail(text: &str, budget: usize, head_ratio: f64) -> (&str, &str) {
+    let head_budget = (budget as f64 * head_ratio.clamp(0.0, 1.0)) as usize;
+    let tail_budget = budget - head_budget;
+
+    let mut head_end = floor_char_boundary(text, head_budget);
+    if let Some(newline) = text[..head_end].rfind('\n') {
+        if newline >= head_end - head_end / 4 {
+            head_end = newline + 1;
+        }
+    }
+
+    let mut tail_start = ceil_char_boundary(text, text.len().saturating_sub(tail_budget).max(head_end));
+    if let Some(newline) = text[tail_start..].find('\n') {
+        if newline < (text.len() - tail_start) / 4 {
+            tail_start += newline + 1;
+        }
+    }
+
+    (&text[..head_end], &text[tail_start..])
+}
+
+/// The `chunk`th piece of `text` when split into `chunk_bytes` pieces (cut
+/// at character boundaries), or `None` past the end.
+pub fn output_chunk(text: &str, chunk: usize, chunk_bytes: usize) -> Option<&str> {
+    let chunk_bytes = chunk_bytes.max(1);
+    let start = chunk.checked_mul(chunk_bytes).filter(|&start| start < text.len())?;
+    let end = start.saturating_add(chunk_bytes);
+    Some(&text[floor_char_boundary(text, start)..floor_char_boundary(text, end)])
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    (0..=index).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0)
+}
+
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    (index..text.len()).find(|&i| text.is_char_boundary(i)).unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::middleware::OUTPUT_KEY_PREFIX;
+    use serde_json::json;
+
+    fn call() -> ToolCall {
+        ToolCall {
+            name: "fs.read_file".to_string(),
+            arguments: json!({}),
+            context: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_output_keeps_head_and_tail() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let middleware = OutputLimitMiddleware::new(OutputLimitConfig {
+            max_bytes: 1000,
+            chunk_bytes: 4000,
+            ..Default::default()
+        });
+        let lines: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+
+        let small = middleware.after(&call(), Ok(ToolOutput::text("short")), &state).await.unwrap();
+        assert_eq!(output_text(&small), "short");
+
+        let output = middleware
+            .after(&call(), Ok(ToolOutput::structured(json!({ "content": lines }))), &state)
+            .await
+            .unwrap();
+        let text = output_text(&output);
+        assert!(text.len() <= 1000);
+        assert!(text.starts_with("{\"content\":\"line 0\\n"));
+        assert!(text.ends_with("line 999\\n\"}"));
+        assert!(output.structured_content.is_none());
+
+        let id = text.split("nexus://outputs/").nth(1).unwrap().split(',').next().unwrap();
+        let stored = state.memory_store.kv_get(&format!("{}{}", OUTPUT_KEY_PREFIX, id)).await.unwrap().unwrap();
+        let full = stored.value.as_str().unwrap();
+        assert_eq!(full, json!({ "content": lines }).to_string());
+        assert!(text.contains(&format!("in {} chunks", full.len().div_ceil(4000))));
+    }
+
+    #[test]
+    fn test_output_chunks() {
+        let text = "héllo wörld";
+        let chunks: Vec<_> = (0..).map_while(|i| output_chunk(text, i, 4)).collect();
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(chunks.len(), text.len().div_ceil(4));
+        assert_eq!(output_chunk(text, 99, 4), None);
+    }
+}
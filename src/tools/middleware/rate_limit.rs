@@ -0,0 +1,148 @@
+//! Per-tool rate limits.
+//!
+//! `rate_limit.tools` maps tool names or globs to token buckets, so e.g.
+//! `llm.*` can be held to a few calls a second while cheap tools stay
+//! unlimited. Every caller (API key, or session without one) gets its own
+//! bucket per pattern. Unlike the HTTP limiter this applies to all
+//! transports.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::RateLimitConfig;
+use crate::core::{Config, RuntimeState};
+use crate::tools::{ToolError, ToolOutput};
+use crate::transport::RateLimiter;
+
+/// A tool pattern and its bucket settings.
+struct ToolLimit {
+    pattern: String,
+    requests_per_second: u32,
+    limiter: RateLimiter,
+}
+
+/// Middleware enforcing `rate_limit.tools`.
+pub struct RateLimitMiddleware {
+    limits: Vec<ToolLimit>,
+}
+
+impl std::fmt::Debug for RateLimitMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("patterns", &self.limits.iter().map(|l| &l.pattern).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl RateLimitMiddleware {
+    /// Creates the middleware for the configured tool limits.
+    pub fn new(config: &Config) -> Self {
+        #[cfg(feature = "redis")]
+        let redis = crate::transport::middleware::shared_buckets(config);
+        let RateLimitConfig { tools, .. } = &config.rate_limit;
+
+        let limits = tools
+            .iter()
+            .map(|(pattern, limit)| {
+                let limiter = RateLimiter::with_limits(limit.requests_per_second as f64, limit.burst_size);
+                #[cfg(feature = "redis")]
+                let limiter = match &redis {
+                    Some(redis) => limiter.shared(redis.clone(), "tool"),
+                    None => limiter,
+                };
+                ToolLimit {
+                    pattern: pattern.clone(),
+                    requests_per_second: limit.requests_per_second,
+                    limiter,
+                }
+            })
+            .collect();
+        Self { limits }
+    }
+
+    /// The limit for a tool: an exact name beats a glob, a longer glob a
+    /// shorter one.
+    fn limit_for(&self, tool: &str) -> Option<&ToolLimit> {
+        self.limits
+            .iter()
+            .filter(|limit| match limit.pattern.strip_suffix('*') {
+                Some(prefix) => tool.starts_with(prefix),
+                None => limit.pattern == tool,
+            })
+            .max_by_key(|limit| (!limit.pattern.ends_with('*'), limit.pattern.len()))
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for RateLimitMiddleware {
+    fn name(&self) -> &str {
+        "rate_limit"
+    }
+
+    async fn before(
+        &self,
+        call: &mut ToolCall,
+        _state: &Arc<RuntimeState>,
+    ) -> Result<Option<ToolOutput>, ToolError> {
+        let Some(limit) = self.limit_for(&call.name) else {
+            return Ok(None);
+        };
+
+        let caller = match &call.context.identity {
+            Some(identity) => format!("key:{}", identity.name),
+            None => format!("session:{}", call.context.session_id),
+        };
+        if limit.limiter.check(&format!("{}:{}", limit.pattern, caller)).await {
+            return Ok(None);
+        }
+
+        warn!("Tool rate limit exceeded for {} by {}", call.name, caller);
+        Err(ToolError::RateLimited(format!(
+            "'{}' allows {} calls per second; retry shortly",
+            limit.pattern, limit.requests_per_second
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::KeyRateLimit;
+    use crate::core::RequestContext;
+    use serde_json::json;
+
+    fn call(name: &str, session: &str) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            arguments: json!({}),
+            context: RequestContext::new(session),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_limits() {
+        let mut config = Config::default();
+        config.rate_limit.tools = [
+            ("llm.*", KeyRateLimit { requests_per_second: 1, burst_size: 1 }),
+            ("llm.embed", KeyRateLimit { requests_per_second: 100, burst_size: 100 }),
+        ]
+        .into_iter()
+        .map(|(pattern, limit)| (pattern.to_string(), limit))
+        .collect();
+        let middleware = RateLimitMiddleware::new(&config);
+        let state = Arc::new(RuntimeState::new(Config::default()));
+
+        assert!(middleware.before(&mut call("llm.chat", "a"), &state).await.is_ok());
+        let err = middleware.before(&mut call("llm.openai", "a"), &state).await.unwrap_err();
+        assert!(matches!(err, ToolError::RateLimited(_)), "{}", err);
+
+        // Other callers and more specific patterns have their own buckets
+        assert!(middleware.before(&mut call("llm.chat", "b"), &state).await.is_ok());
+        assert!(middleware.before(&mut call("llm.embed", "a"), &state).await.is_ok());
+        for _ in 0..5 {
+            assert!(middleware.before(&mut call("echo", "a"), &state).await.is_ok());
+        }
+    }
+}
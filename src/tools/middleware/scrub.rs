@@ -0,0 +1,113 @@
+//! Masks secrets in tool results and errors before anything else sees
+//! them: the client, stashed full outputs, events and the audit log.
+
+use async_trait::async_trait;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::RuntimeState;
+use crate::secrets::scrub::{scrub, scrub_value};
+use crate::tools::{ToolContent, ToolError, ToolOutput};
+
+/// Masks secret values and common tokens in a tool's result. Scheduled
+/// tasks, which don't go through the middleware chain, use this directly.
+pub fn scrub_result(
+    result: Result<ToolOutput, ToolError>,
+    secrets: &[(String, String)],
+) -> Result<ToolOutput, ToolError> {
+    let mask = |text: &mut String| {
+        if let Cow::Owned(scrubbed) = scrub(text, secrets) {
+            *text = scrubbed;
+        }
+    };
+    match result {
+        Ok(mut output) => {
+            for content in &mut output.content {
+                if let ToolContent::Text { text } = content {
+                    mask(text);
+                }
+            }
+            if let Some(structured) = &mut output.structured_content {
+                scrub_value(structured, secrets);
+            }
+            Ok(output)
+        }
+        Err(mut error) => {
+            match &mut error {
+                ToolError::NotFound(message)
+                | ToolError::InvalidInput(message)
+                | ToolError::ExecutionFailed(message)
+                | ToolError::PermissionDenied(message)
+                | ToolError::BudgetExceeded(message)
+                | ToolError::RateLimited(message)
+                | ToolError::Internal(message) => mask(message),
+                ToolError::Timeout(_) => {}
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Middleware that masks secret values and common tokens in results.
+#[derive(Debug, Default)]
+pub struct ScrubMiddleware;
+
+#[async_trait]
+impl ToolMiddleware for ScrubMiddleware {
+    fn name(&self) -> &str {
+        "scrub"
+    }
+
+    async fn after(
+        &self,
+        _call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        scrub_result(result, &state.secrets.values())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::tools::middleware::output_text;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_secrets_are_masked_in_results() {
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        state.secrets.set("SLACK_WEBHOOK", "https://hooks.slack.test/services/T1/B2/c3d4e5", None);
+        let call = ToolCall {
+            name: "notify.slack".to_string(),
+            arguments: json!({}),
+            context: Default::default(),
+        };
+
+        let output = ScrubMiddleware
+            .after(
+                &call,
+                Ok(ToolOutput::structured(json!({"url": "https://hooks.slack.test/services/T1/B2/c3d4e5"}))),
+                &state,
+            )
+            .await
+            .unwrap();
+        assert!(!output_text(&output).contains("c3d4e5"));
+        assert_eq!(output.structured_content.unwrap(), json!({"url": "[REDACTED:SLACK_WEBHOOK]"}));
+
+        let error = ScrubMiddleware
+            .after(
+                &call,
+                Err(ToolError::ExecutionFailed("POST https://hooks.slack.test/services/T1/B2/c3d4e5: 404".to_string())),
+                &state,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Execution failed: POST [REDACTED:SLACK_WEBHOOK]: 404");
+    }
+}
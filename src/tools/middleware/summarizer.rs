@@ -0,0 +1,223 @@
+//! Summarizes oversized tool results.
+//!
+//! When a result exceeds the configured token threshold, the full output is
+//! parked in the KV store and the client receives an LLM-written summary
+//! plus a `nexus://outputs/{id}` resource URI for the original text.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::SummarizerConfig;
+use crate::core::RuntimeState;
+use crate::memory::namespaced_key;
+use crate::tools::{caller, ToolContent, ToolError, ToolOutput};
+
+/// KV key prefix under which full outputs are stored.
+pub const OUTPUT_KEY_PREFIX: &str = "tool_output:";
+
+/// Characters in the fallback preview when summarization fails.
+const PREVIEW_CHARS: usize = 2000;
+
+/// Middleware that replaces large tool results with a summary.
+#[derive(Debug)]
+pub struct SummarizerMiddleware {
+    config: SummarizerConfig,
+}
+
+impl SummarizerMiddleware {
+    /// Creates a new summarizer with the given configuration.
+    pub fn new(config: SummarizerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Asks the configured LLM tool for a summary of `text`.
+    async fn summarize(
+        &self,
+        tool_name: &str,
+        text: &str,
+        state: &Arc<RuntimeState>,
+    ) -> Result<String, ToolError> {
+        let tool = state
+            .tool_registry
+            .read()
+            .get(&self.config.tool)
+            .cloned()
+            .ok_or_else(|| ToolError::NotFound(self.config.tool.clone()))?;
+
+        let prompt = format!(
+            "Summarize the following output of the tool '{}'. Keep identifiers, numbers, \
+             errors and anything an agent would need to decide its next step.\n\n{}",
+            tool_name, text
+        );
+
+        let mut args = json!({
+            "prompt": prompt,
+            "max_tokens": self.config.summary_tokens,
+            "temperature": 0.0
+        });
+        if let Some(model) = &self.config.model {
+            args["model"] = json!(model);
+        }
+
+        let output = tool.execute(args, state.clone()).await?;
+        if output.is_error {
+            return Err(ToolError::ExecutionFailed(output_text(&output)));
+        }
+
+        // LLM tools return {"content": "...", ...}; fall back to raw text.
+        let raw = output_text(&output);
+        let summary = serde_json::from_str::<Value>(&raw)
+            .ok()
+            .and_then(|v| v.get("content").and_then(|c| c.as_str()).map(|s| s.to_string()))
+            .unwrap_or(raw);
+
+        Ok(summary)
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for SummarizerMiddleware {
+    fn name(&self) -> &str {
+        "summarizer"
+    }
+
+    async fn after(
+        &self,
+        call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        let output = match result {
+            Ok(output) if !output.is_error => output,
+            other => return other,
+        };
+
+        let text = output_text(&output);
+        let tokens = estimate_tokens(&text);
+        if tokens <= self.config.threshold_tokens {
+            return Ok(output);
+        }
+
+        debug!("Summarizing {} output (~{} tokens)", call.name, tokens);
+
+        let id = stash_output(state, &text, self.config.retention_secs).await?;
+
+        let (summary, summarized) = match self.summarize(&call.name, &text, state).await {
+            Ok(summary) => (summary, true),
+            Err(e) => {
+                warn!("Summarization of {} failed, returning preview: {}", call.name, e);
+                (text.chars().take(PREVIEW_CHARS).collect(), false)
+            }
+        };
+
+        let result = json!({
+            "summary": summary,
+            "summarized": summarized,
+            "original_tokens": tokens,
+            "full_output_uri": format!("nexus://outputs/{}", id)
+        });
+
+        Ok(ToolOutput::text(serde_json::to_string_pretty(&result).unwrap()))
+    }
+}
+
+/// Storage key of a stashed output, in the caller's namespace so only
+/// callers of that namespace can read it back.
+pub(crate) fn output_key(id: &str) -> String {
+    namespaced_key(caller::namespace().as_deref(), &format!("{}{}", OUTPUT_KEY_PREFIX, id))
+}
+
+/// Keeps the full text of a tool result for `retention_secs` and returns
+/// its ID, readable as `nexus://outputs/{id}`.
+pub(crate) async fn stash_output(
+    state: &RuntimeState,
+    text: &str,
+    retention_secs: u64,
+) -> Result<String, ToolError> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let key = output_key(&id);
+    state
+        .memory_store
+        .kv_set(&key, json!(text), Some(retention_secs))
+        .await
+        .map_err(|e| ToolError::Internal(format!("Failed to store full output: {}", e)))?;
+    Ok(id)
+}
+
+/// Concatenates the text content of a tool output.
+pub(crate) fn output_text(output: &ToolOutput) -> String {
+    output
+        .content
+        .iter()
+        .filter_map(|c| match c {
+            ToolContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rough token estimate (~4 characters per token).
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn middleware() -> SummarizerMiddleware {
+        SummarizerMiddleware::new(SummarizerConfig {
+            enabled: true,
+            threshold_tokens: 10,
+            tool: "missing.llm".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn call() -> ToolCall {
+        ToolCall {
+            name: "echo".to_string(),
+            arguments: json!({}),
+            context: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_output_passes_through() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let output = middleware()
+            .after(&call(), Ok(ToolOutput::text("short")), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(output_text(&output), "short");
+    }
+
+    #[tokio::test]
+    async fn test_large_output_is_stored_and_previewed() {
+        let state = Arc::new(RuntimeState::new(Config::default()));
+        let big = "x".repeat(500);
+        let output = middleware()
+            .after(&call(), Ok(ToolOutput::text(big.clone())), &state)
+            .await
+            .unwrap();
+
+        let value: Value = serde_json::from_str(&output_text(&output)).unwrap();
+        assert_eq!(value["summarized"], false);
+
+        let uri = value["full_output_uri"].as_str().unwrap();
+        let id = uri.strip_prefix("nexus://outputs/").unwrap();
+        let stored = state
+            .memory_store
+            .kv_get(&format!("{}{}", OUTPUT_KEY_PREFIX, id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.value, json!(big));
+    }
+}
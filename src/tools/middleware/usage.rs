@@ -0,0 +1,482 @@
+//! LLM token usage and cost accounting.
+//!
+//! Records the tokens and estimated cost of every successful `llm.*` call in
+//! the `llm_usage` table, attributed to provider, model, session and API key,
+//! and blocks further calls once a configured monthly budget is spent.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::summarizer::estimate_tokens;
+use super::{ToolCall, ToolMiddleware};
+use crate::core::config::{ModelPrice, UsageConfig};
+use crate::core::RuntimeState;
+use crate::memory::{LlmUsageRecord, MemoryError, MemoryStore};
+use crate::tools::{ToolContent, ToolError, ToolOutput};
+
+/// Built-in prices in USD per million tokens, matched by model name prefix.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("o1-mini", 1.10, 4.40),
+    ("o1", 15.00, 60.00),
+    ("o3-mini", 1.10, 4.40),
+    ("text-embedding-3-small", 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.0),
+    ("text-embedding-ada-002", 0.10, 0.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-sonnet", 3.00, 15.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-opus-4", 15.00, 75.00),
+];
+
+/// Whether a tool is an LLM call that consumes tokens. Read-only tools
+/// such as `llm.usage` share the prefix but are never metered.
+pub fn is_metered(tool_name: &str) -> bool {
+    tool_name.starts_with("llm.") && !matches!(tool_name, "llm.usage" | "llm.ollama_models")
+}
+
+/// Start of the calendar month (UTC) containing `now`.
+pub fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+/// Price of a model: configured overrides first, then the built-in table,
+/// each by longest matching prefix. Local Ollama models are free.
+fn price_for(config: &UsageConfig, provider: &str, model: &str) -> Option<ModelPrice> {
+    if provider == "ollama" {
+        return Some(ModelPrice { input_per_mtok: 0.0, output_per_mtok: 0.0 });
+    }
+    let configured = config
+        .prices
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price);
+    configured.or_else(|| {
+        PRICES
+            .iter()
+            .filter(|(prefix, _, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|(_, input, output)| ModelPrice { input_per_mtok: *input, output_per_mtok: *output })
+    })
+}
+
+/// Estimated cost in USD, or `None` if the model's price is unknown.
+pub fn estimate_cost(config: &UsageConfig, provider: &str, model: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+    price_for(config, provider, model).map(|price| {
+        (input_tokens as f64 * price.input_per_mtok + output_tokens as f64 * price.output_per_mtok) / 1_000_000.0
+    })
+}
+
+/// Provider implied by a tool name, for results that do not report one.
+fn provider_of(tool_name: &str) -> &str {
+    match tool_name {
+        "llm.openai" | "llm.embed" => "openai",
+        "llm.anthropic" => "anthropic",
+        name if name.starts_with("llm.ollama") => "ollama",
+        name => name.trim_start_matches("llm."),
+    }
+}
+
+/// Token counts reported in a result's `usage`, or estimated from the
+/// request and response text. The flag is true for estimates.
+fn token_counts(arguments: &Value, result: &Value) -> (u64, u64, bool) {
+    let usage = &result["usage"];
+    let field = |names: &[&str]| names.iter().find_map(|name| usage.get(*name).and_then(|v| v.as_u64()));
+    let input = field(&["prompt_tokens", "input_tokens"]);
+    let output = field(&["completion_tokens", "output_tokens"]);
+
+    if input.is_some() || output.is_some() {
+        return (input.unwrap_or(0), output.unwrap_or(0), false);
+    }
+
+    let input_text: String = ["system", "prompt", "messages", "text", "texts"]
+        .iter()
+        .filter_map(|key| arguments.get(*key))
+        .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let output_text = result["content"].as_str().unwrap_or("");
+    (estimate_tokens(&input_text) as u64, estimate_tokens(output_text) as u64, true)
+}
+
+/// Middleware that records LLM usage and enforces monthly budgets.
+#[derive(Debug)]
+pub struct UsageMiddleware {
+    config: UsageConfig,
+}
+
+impl UsageMiddleware {
+    /// Creates a new usage middleware with the given configuration.
+    pub fn new(config: UsageConfig) -> Self {
+        Self { config }
+    }
+
+    /// The caller's per-key monthly limit, looked up by key hash or name.
+    fn key_limit(&self, call: &ToolCall) -> Option<f64> {
+        let limits = &self.config.per_key_monthly_budget_usd;
+        call.context
+            .api_key_hash
+            .as_ref()
+            .and_then(|hash| limits.get(hash))
+            .or_else(|| call.context.key_name().and_then(|name| limits.get(name)))
+            .copied()
+    }
+
+    async fn check_budget(&self, call: &ToolCall, store: &dyn MemoryStore) -> Result<(), ToolError> {
+        let since = month_start(Utc::now());
+        let since_str = since.to_rfc3339();
+
+        if let Some(limit) = self.config.monthly_budget_usd {
+            let spent = store
+                .llm_cost_since(&since_str, None)
+                .await
+                .map_err(|e| ToolError::Internal(e.to_string()))?;
+            if spent >= limit {
+                warn!("Monthly LLM budget of ${:.2} reached (${:.4} spent)", limit, spent);
+                return Err(ToolError::BudgetExceeded(format!(
+                    "monthly LLM budget of ${:.2} reached (${:.4} spent since {})",
+                    limit,
+                    spent,
+                    since.format("%Y-%m-%d")
+                )));
+            }
+        }
+
+        if let (Some(limit), Some(hash)) = (self.key_limit(call), &call.context.api_key_hash) {
+            let spent = store
+                .llm_cost_since(&since_str, Some(hash))
+                .await
+                .map_err(|e| ToolError::Internal(e.to_string()))?;
+            if spent >= limit {
+                let key = call.context.key_name().unwrap_or(hash.as_str()).to_string();
+                warn!("API key '{}' reached its monthly LLM budget of ${:.2}", key, limit);
+                return Err(ToolError::BudgetExceeded(format!(
+                    "API key '{}' reached its monthly LLM budget of ${:.2} (${:.4} spent since {})",
+                    key,
+                    limit,
+                    spent,
+                    since.format("%Y-%m-%d")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the usage record for a successful call, if it reports a model.
+    fn record_for(&self, call: &ToolCall, output: &ToolOutput) -> Option<LlmUsageRecord> {
+        let text = output.content.iter().find_map(|c| match c {
+            ToolContent::Text { text } => Some(text),
+            _ => None,
+        })?;
+        let result: Value = serde_json::from_str(text).ok()?;
+        let model = result["model"].as_str()?.to_string();
+        let provider = result["provider"]
+            .as_str()
+            .unwrap_or_else(|| provider_of(&call.name))
+            .to_string();
+
+        let (input_tokens, output_tokens, estimated) = token_counts(&call.arguments, &result);
+        Some(LlmUsageRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: call.name.clone(),
+            cost_usd: estimate_cost(&self.config, &provider, &model, input_tokens, output_tokens),
+            provider,
+            model,
+            session_id: call.context.session_id.clone(),
+            api_key_hash: call.context.api_key_hash.clone(),
+            api_key_name: call.context.key_name().map(str::to_string),
+            input_tokens,
+            output_tokens,
+            estimated,
+            created_at: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for UsageMiddleware {
+    fn name(&self) -> &str {
+        "usage"
+    }
+
+    async fn before(
+        &self,
+        call: &mut ToolCall,
+        state: &Arc<RuntimeState>,
+    ) -> Result<Option<ToolOutput>, ToolError> {
+        if !is_metered(&call.name) {
+            return Ok(None);
+        }
+        match self.check_budget(call, state.memory_store.as_ref()).await {
+            Err(ToolError::Internal(e)) => {
+                // A broken usage table should not take LLM tools down with it
+                warn!("Failed to check LLM budget: {}", e);
+                Ok(None)
+            }
+            other => other.map(|_| None),
+        }
+    }
+
+    async fn after(
+        &self,
+        call: &ToolCall,
+        result: Result<ToolOutput, ToolError>,
+        state: &Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        if let Ok(output) = &result {
+            if is_metered(&call.name) && !output.is_error {
+                if let Some(record) = self.record_for(call, output) {
+                    if let Err(e) = state.memory_store.record_llm_usage(&record).await {
+                        warn!("Failed to record LLM usage: {}", e);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// How usage is grouped in reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGrouping {
+    #[default]
+    Model,
+    Provider,
+    Key,
+    Tool,
+    Session,
+    Day,
+}
+
+impl UsageGrouping {
+    fn key(self, record: &LlmUsageRecord) -> String {
+        match self {
+            Self::Model => format!("{}/{}", record.provider, record.model),
+            Self::Provider => record.provider.clone(),
+            Self::Key => record
+                .api_key_name
+                .clone()
+                .or_else(|| record.api_key_hash.clone())
+                .unwrap_or_else(|| "(none)".to_string()),
+            Self::Tool => record.tool.clone(),
+            Self::Session => record.session_id.clone(),
+            Self::Day => record.created_at.chars().take(10).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct UsageTotals {
+    calls: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: f64,
+    unpriced_calls: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &LlmUsageRecord) {
+        self.calls += 1;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        match record.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_calls += 1,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "calls": self.calls,
+            "input_tokens": self.input_tokens,
+            "output_tokens": self.output_tokens,
+            "cost_usd": round_usd(self.cost_usd),
+            "unpriced_calls": self.unpriced_calls
+        })
+    }
+}
+
+fn round_usd(value: f64) -> f64 {
+    (value * 1_000_000.0).round() / 1_000_000.0
+}
+
+/// Summarizes usage since `since` (all time if `None`), optionally for one
+/// API key (by name or hash), along with month-to-date budget status.
+pub async fn usage_report(
+    store: &dyn MemoryStore,
+    config: &UsageConfig,
+    since: Option<DateTime<Utc>>,
+    group_by: UsageGrouping,
+    key: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Value, MemoryError> {
+    let records = store.list_llm_usage(since.map(|ts| ts.to_rfc3339()).as_deref()).await?;
+    let matches_key = |record: &LlmUsageRecord| {
+        key.is_none_or(|key| record.api_key_name.as_deref() == Some(key) || record.api_key_hash.as_deref() == Some(key))
+    };
+
+    let mut totals = UsageTotals::default();
+    let mut groups: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    for record in records.iter().filter(|r| matches_key(r)) {
+        totals.add(record);
+        groups.entry(group_by.key(record)).or_default().add(record);
+    }
+
+    let mut groups: Vec<(String, UsageTotals)> = groups.into_iter().collect();
+    if group_by != UsageGrouping::Day {
+        groups.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd).then(b.1.calls.cmp(&a.1.calls)));
+    }
+    let groups: Vec<Value> = groups
+        .iter()
+        .map(|(name, totals)| {
+            let mut group = totals.to_json();
+            group["name"] = json!(name);
+            group
+        })
+        .collect();
+
+    let month = month_start(now);
+    let month_to_date = store.llm_cost_since(&month.to_rfc3339(), None).await?;
+    let budget = json!({
+        "month_start": month.format("%Y-%m-%d").to_string(),
+        "month_to_date_usd": round_usd(month_to_date),
+        "monthly_budget_usd": config.monthly_budget_usd,
+        "remaining_usd": config.monthly_budget_usd.map(|limit| round_usd((limit - month_to_date).max(0.0)))
+    });
+
+    Ok(json!({
+        "since": since.map(|ts| ts.to_rfc3339()),
+        "key": key,
+        "totals": totals.to_json(),
+        "group_by": format!("{:?}", group_by).to_lowercase(),
+        "groups": groups,
+        "budget": budget
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, KeyIdentity, RequestContext};
+
+    fn state() -> Arc<RuntimeState> {
+        Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }))
+    }
+
+    fn llm_call(key: Option<&str>) -> ToolCall {
+        let identity = key.map(|name| {
+            Arc::new(KeyIdentity {
+                name: name.to_string(),
+                key_hash: format!("hash-{}", name),
+                scopes: vec!["*".to_string()],
+            })
+        });
+        ToolCall {
+            name: "llm.chat".to_string(),
+            arguments: json!({"prompt": "hi"}),
+            context: RequestContext::new("s1").with_identity(identity),
+        }
+    }
+
+    fn output(model: &str, input: u64, output: u64) -> ToolOutput {
+        ToolOutput::text(
+            json!({
+                "content": "hello",
+                "provider": "openai",
+                "model": model,
+                "usage": {"prompt_tokens": input, "completion_tokens": output}
+            })
+            .to_string(),
+        )
+    }
+
+    #[test]
+    fn test_pricing() {
+        let mut config = UsageConfig::default();
+        // gpt-4o-mini must not be priced as gpt-4o
+        let cost = estimate_cost(&config, "openai", "gpt-4o-mini-2024-07-18", 1_000_000, 1_000_000).unwrap();
+        assert!((cost - 0.75).abs() < 1e-9);
+        assert_eq!(estimate_cost(&config, "ollama", "llama3.2", 5000, 5000), Some(0.0));
+        assert_eq!(estimate_cost(&config, "local", "qwen2.5", 10, 10), None);
+
+        config.prices.insert("qwen".to_string(), ModelPrice { input_per_mtok: 1.0, output_per_mtok: 2.0 });
+        assert_eq!(estimate_cost(&config, "local", "qwen2.5", 1_000_000, 1_000_000), Some(3.0));
+
+        assert!(is_metered("llm.anthropic"));
+        assert!(!is_metered("llm.usage"));
+    }
+
+    #[test]
+    fn test_token_estimate_without_usage() {
+        let (input, output, estimated) = token_counts(&json!({"prompt": "abcdefgh"}), &json!({"content": "abcd"}));
+        assert_eq!((input, output, estimated), (2, 1, true));
+    }
+
+    #[tokio::test]
+    async fn test_records_usage_and_enforces_budgets() {
+        let state = state();
+        let mut config = UsageConfig {
+            monthly_budget_usd: Some(10.0),
+            ..UsageConfig::default()
+        };
+        config.per_key_monthly_budget_usd.insert("ci".to_string(), 0.5);
+        let usage = UsageMiddleware::new(config.clone());
+
+        // 1M input tokens of gpt-4o = $2.50, charged to the "ci" key
+        let call = llm_call(Some("ci"));
+        let result = usage.after(&call, Ok(output("gpt-4o", 1_000_000, 0)), &state).await;
+        assert!(result.is_ok());
+
+        let err = usage.before(&mut llm_call(Some("ci")), &state).await.unwrap_err();
+        assert!(matches!(err, ToolError::BudgetExceeded(_)), "{}", err);
+        assert!(usage.before(&mut llm_call(Some("other")), &state).await.is_ok());
+        assert!(usage.before(&mut llm_call(None), &state).await.is_ok());
+
+        // Push the global spend over $10
+        for _ in 0..4 {
+            let _ = usage.after(&llm_call(None), Ok(output("gpt-4o", 1_000_000, 0)), &state).await;
+        }
+        let err = usage.before(&mut llm_call(None), &state).await.unwrap_err();
+        assert!(err.to_string().contains("monthly LLM budget"), "{}", err);
+
+        let report = usage_report(
+            state.memory_store.as_ref(),
+            &config,
+            None,
+            UsageGrouping::Key,
+            None,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report["totals"]["calls"], 5);
+        assert_eq!(report["totals"]["cost_usd"], 12.5);
+        assert_eq!(report["groups"][0]["name"], "(none)");
+        assert_eq!(report["groups"][1]["name"], "ci");
+        assert_eq!(report["budget"]["remaining_usd"], 0.0);
+    }
+}
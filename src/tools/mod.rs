@@ -5,7 +5,16 @@
 //! - **Extras**: Optional capabilities (loaded if enabled)
 
 pub mod registry;
+pub mod middleware;
 pub mod process_manager;
+pub mod file_watch;
+pub mod stream;
+pub mod client;
+pub mod cancel;
+pub mod caller;
+pub mod roots;
+pub mod typed;
+pub mod manifest;
 pub mod core;
 pub mod extras;
 
@@ -13,7 +22,9 @@ pub mod extras;
 mod builtin;
 
 pub use registry::{Tool, ToolRegistry, ToolError, ToolOutput, ToolContent, ToolInput};
-pub use process_manager::ProcessManager;
+pub use process_manager::{OutputStream, ProcessManager, ProcessRequest, ProcessStatus, ProcessTable, StopSignal};
+pub use middleware::{MiddlewareChain, ToolCall, ToolMiddleware};
+pub use typed::{IntoToolOutput, Structured, TypedTool};
 
 // Re-export for convenience
 pub use core::register_core_tools;
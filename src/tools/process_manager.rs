@@ -1,18 +1,33 @@
 //! Process manager for safe subprocess execution.
 //!
-//! Provides timeout-aware process spawning and monitoring.
+//! Provides timeout-aware process spawning and monitoring, plus a table of
+//! long-running background processes whose recent output is kept in
+//! per-process ring buffers.
 
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::{watch, Notify};
 use tokio::time::timeout;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use crate::tools::ToolError;
 
 /// Default timeout for tool execution (30 seconds).
 pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
+/// Default cap on captured output per stream (10 MiB).
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 10 * 1024 * 1024;
+
 /// Process manager for executing subprocesses safely.
 #[derive(Debug, Clone)]
 pub struct ProcessManager {
@@ -41,52 +56,98 @@ impl ProcessManager {
         program: &str,
         args: &[&str],
     ) -> Result<ProcessOutput, ToolError> {
-        debug!("Executing: {} {:?}", program, args);
+        self.run(ProcessRequest::new(program, args), |_, _| {}).await
+    }
 
-        let mut child = Command::new(program)
-            .args(args)
-            .stdin(Stdio::null())
+    /// Runs a process, reading stdout and stderr as they are produced.
+    ///
+    /// Every chunk is passed to `on_output` as it arrives; at most
+    /// `max_output_bytes` per stream are kept for the returned output. The
+    /// process is killed if it exceeds the configured timeout.
+    pub async fn run<F>(
+        &self,
+        request: ProcessRequest<'_>,
+        mut on_output: F,
+    ) -> Result<ProcessOutput, ToolError>
+    where
+        F: FnMut(OutputStream, &[u8]) + Send,
+    {
+        debug!("Executing: {} {:?}", request.program, request.args);
+
+        let mut command = Command::new(request.program);
+        command
+            .args(request.args)
+            .envs(request.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .stdin(if request.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .kill_on_drop(true) // Kill the process if the future is dropped
+            .kill_on_drop(true); // Kill the process if the future is dropped
+        if let Some(cwd) = &request.cwd {
+            command.current_dir(cwd);
+        }
+
+        let mut child = command
             .spawn()
             .map_err(|e| ToolError::ExecutionFailed(format!("Failed to spawn process: {}", e)))?;
 
+        // Feed stdin from a separate task so a process that writes before it
+        // finishes reading cannot deadlock us; dropping the handle closes it.
+        if let (Some(input), Some(mut stdin)) = (request.stdin, child.stdin.take()) {
+            tokio::spawn(async move {
+                let _ = stdin.write_all(input.as_bytes()).await;
+            });
+        }
+
+        let mut stdout = child.stdout.take();
+        let mut stderr = child.stderr.take();
+        let max_output_bytes = request.max_output_bytes;
+
+        let collect = async {
+            let mut out = Capture::new(max_output_bytes);
+            let mut err = Capture::new(max_output_bytes);
+            let mut out_buf = [0u8; 8192];
+            let mut err_buf = [0u8; 8192];
+
+            // Drain both pipes concurrently so neither can fill up and block the child
+            while stdout.is_some() || stderr.is_some() {
+                tokio::select! {
+                    read = read_some(&mut stdout, &mut out_buf), if stdout.is_some() => match read {
+                        Some(n) => {
+                            on_output(OutputStream::Stdout, &out_buf[..n]);
+                            out.push(&out_buf[..n]);
+                        }
+                        None => stdout = None,
+                    },
+                    read = read_some(&mut stderr, &mut err_buf), if stderr.is_some() => match read {
+                        Some(n) => {
+                            on_output(OutputStream::Stderr, &err_buf[..n]);
+                            err.push(&err_buf[..n]);
+                        }
+                        None => stderr = None,
+                    },
+                }
+            }
+
+            let status = child.wait().await;
+            (status, out, err)
+        };
+
         let timeout_duration = Duration::from_secs(self.timeout_secs);
 
-        // Wait for the process with timeout
-        match timeout(timeout_duration, child.wait()).await {
-            Ok(exit_result) => {
+        match timeout(timeout_duration, collect).await {
+            Ok((exit_result, out, err)) => {
                 let status = exit_result.map_err(|e| {
                     ToolError::ExecutionFailed(format!("Process error: {}", e))
                 })?;
 
-                // Read stdout and stderr after process exits
-                let stdout = if let Some(mut stdout) = child.stdout.take() {
-                    use tokio::io::AsyncReadExt;
-                    let mut buf = Vec::new();
-                    stdout.read_to_end(&mut buf).await.unwrap_or_default();
-                    String::from_utf8_lossy(&buf).to_string()
-                } else {
-                    String::new()
-                };
-
-                let stderr = if let Some(mut stderr) = child.stderr.take() {
-                    use tokio::io::AsyncReadExt;
-                    let mut buf = Vec::new();
-                    stderr.read_to_end(&mut buf).await.unwrap_or_default();
-                    String::from_utf8_lossy(&buf).to_string()
-                } else {
-                    String::new()
-                };
-
                 let exit_code = status.code().unwrap_or(-1);
 
                 debug!("Process completed with exit code: {}", exit_code);
 
                 Ok(ProcessOutput {
-                    stdout,
-                    stderr,
+                    truncated: out.truncated || err.truncated,
+                    stdout: out.into_string(),
+                    stderr: err.into_string(),
                     exit_code,
                     success: status.success(),
                 })
@@ -111,6 +172,96 @@ impl Default for ProcessManager {
     }
 }
 
+/// A process to run, with its environment and input.
+#[derive(Debug, Clone)]
+pub struct ProcessRequest<'a> {
+    program: &'a str,
+    args: &'a [&'a str],
+    cwd: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    stdin: Option<String>,
+    max_output_bytes: usize,
+}
+
+impl<'a> ProcessRequest<'a> {
+    /// Creates a request to run `program` with `args`.
+    pub fn new(program: &'a str, args: &'a [&'a str]) -> Self {
+        Self {
+            program,
+            args,
+            cwd: None,
+            env: Vec::new(),
+            stdin: None,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    /// Sets the working directory.
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Adds environment variables on top of the inherited environment.
+    pub fn envs(mut self, env: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(env);
+        self
+    }
+
+    /// Sets input written to the process's stdin.
+    pub fn stdin(mut self, input: impl Into<String>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// Caps the output kept per stream.
+    pub fn max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = max;
+        self
+    }
+}
+
+/// Which output stream a chunk came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Output kept from one stream, up to a byte limit.
+struct Capture {
+    buf: Vec<u8>,
+    max: usize,
+    truncated: bool,
+}
+
+impl Capture {
+    fn new(max: usize) -> Self {
+        Self { buf: Vec::new(), max, truncated: false }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        let room = self.max.saturating_sub(self.buf.len());
+        if chunk.len() > room {
+            self.truncated = true;
+        }
+        self.buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+
+    fn into_string(self) -> String {
+        String::from_utf8_lossy(&self.buf).to_string()
+    }
+}
+
+/// Reads the next chunk from an open pipe; `None` once it is closed.
+async fn read_some<R: AsyncRead + Unpin>(pipe: &mut Option<R>, buf: &mut [u8]) -> Option<usize> {
+    match pipe.as_mut()?.read(buf).await {
+        Ok(0) | Err(_) => None,
+        Ok(n) => Some(n),
+    }
+}
+
 /// Output from a process execution.
 #[derive(Debug, Clone)]
 pub struct ProcessOutput {
@@ -122,6 +273,348 @@ pub struct ProcessOutput {
     pub exit_code: i32,
     /// Whether the process exited successfully.
     pub success: bool,
+    /// Whether output beyond the size cap was dropped.
+    pub truncated: bool,
+}
+
+/// Default number of output lines kept per background process.
+pub const DEFAULT_LOG_LINES: usize = 1000;
+
+/// One line of background process output.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    /// Position in the process's output, counting from 0.
+    pub seq: u64,
+    /// Stream the line was written to.
+    pub stream: OutputStream,
+    /// Line text, without the trailing newline.
+    pub text: String,
+}
+
+/// Ring buffer of the most recent output lines.
+#[derive(Debug)]
+struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl LogBuffer {
+    fn new(capacity: usize) -> Self {
+        Self { lines: VecDeque::new(), capacity: capacity.max(1), next_seq: 0 }
+    }
+
+    fn push(&mut self, stream: OutputStream, text: String) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(LogLine { seq: self.next_seq, stream, text });
+        self.next_seq += 1;
+    }
+}
+
+/// Lines read from a background process's ring buffer.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSlice {
+    /// Matching lines, oldest first.
+    pub lines: Vec<LogLine>,
+    /// Pass as `since` to read only newer lines.
+    pub next_seq: u64,
+    /// Lines after `since` that were already evicted from the buffer.
+    pub dropped: u64,
+}
+
+/// Lifecycle state of a background process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    /// Exited with a code, or `None` if terminated by a signal.
+    Exited(Option<i32>),
+}
+
+/// Signal used to stop a background process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StopSignal {
+    #[default]
+    Term,
+    Int,
+    Kill,
+}
+
+/// A process running in the background, with its recent output.
+#[derive(Debug)]
+pub struct BackgroundProcess {
+    /// Table-assigned id.
+    pub id: u64,
+    /// Program that was started.
+    pub program: String,
+    /// Program arguments.
+    pub args: Vec<String>,
+    /// Working directory, if set.
+    pub cwd: Option<PathBuf>,
+    /// OS process id.
+    pub pid: Option<u32>,
+    /// When the process was started.
+    pub started_at: DateTime<Utc>,
+    status: watch::Sender<ProcessStatus>,
+    finished_at: Mutex<Option<DateTime<Utc>>>,
+    logs: Mutex<LogBuffer>,
+    kill: Notify,
+}
+
+impl BackgroundProcess {
+    /// Current lifecycle state.
+    pub fn status(&self) -> ProcessStatus {
+        *self.status.borrow()
+    }
+
+    /// Whether the process is still running.
+    pub fn is_running(&self) -> bool {
+        self.status() == ProcessStatus::Running
+    }
+
+    /// Summary used by `process.list` and friends.
+    pub fn summary(&self) -> Value {
+        let (state, exit_code) = match self.status() {
+            ProcessStatus::Running => ("running", None),
+            ProcessStatus::Exited(code) => ("exited", code),
+        };
+        json!({
+            "id": self.id,
+            "command": self.program,
+            "args": self.args,
+            "cwd": self.cwd,
+            "pid": self.pid,
+            "status": state,
+            "exit_code": exit_code,
+            "started_at": self.started_at.to_rfc3339(),
+            "finished_at": self.finished_at.lock().map(|t| t.to_rfc3339()),
+            "log_lines": self.logs.lock().next_seq
+        })
+    }
+
+    /// Reads buffered output: lines after `since` if given, else the last
+    /// `tail` lines, optionally from one stream only.
+    pub fn logs(&self, since: Option<u64>, tail: usize, stream: Option<OutputStream>) -> LogSlice {
+        let tail = tail.max(1);
+        let logs = self.logs.lock();
+        let oldest = logs.lines.front().map(|line| line.seq).unwrap_or(logs.next_seq);
+        let matching = logs
+            .lines
+            .iter()
+            .filter(|line| since.is_none_or(|since| line.seq >= since))
+            .filter(|line| stream.is_none_or(|stream| line.stream == stream));
+
+        match since {
+            Some(since) => {
+                let mut lines: Vec<LogLine> = matching.cloned().collect();
+                // Resume after the last returned line if the page was cut short
+                let next_seq = if lines.len() > tail {
+                    lines.truncate(tail);
+                    lines[tail - 1].seq + 1
+                } else {
+                    logs.next_seq
+                };
+                LogSlice { lines, next_seq, dropped: oldest.saturating_sub(since) }
+            }
+            None => {
+                let mut lines: Vec<LogLine> = matching.rev().take(tail).cloned().collect();
+                lines.reverse();
+                LogSlice { lines, next_seq: logs.next_seq, dropped: 0 }
+            }
+        }
+    }
+
+    /// Waits up to `limit` for the process to exit; returns whether it did.
+    pub async fn wait(&self, limit: Duration) -> bool {
+        let mut status = self.status.subscribe();
+        let exited = timeout(limit, status.wait_for(|s| *s != ProcessStatus::Running)).await;
+        exited.is_ok()
+    }
+
+    /// Stops the process with `signal`, escalating to a kill if it is still
+    /// running after `grace`.
+    pub async fn stop(&self, signal: StopSignal, grace: Duration) -> ProcessStatus {
+        if !self.is_running() {
+            return self.status();
+        }
+
+        if signal == StopSignal::Kill || !self.send_signal(signal) {
+            self.kill.notify_one();
+        }
+        if !self.wait(grace).await {
+            warn!("Process {} ignored {:?}; killing it", self.id, signal);
+            self.kill.notify_one();
+            self.wait(Duration::from_secs(5)).await;
+        }
+        self.status()
+    }
+
+    #[cfg(unix)]
+    fn send_signal(&self, signal: StopSignal) -> bool {
+        let Some(pid) = self.pid.and_then(|pid| libc::pid_t::try_from(pid).ok()) else {
+            return false;
+        };
+        let signal = match signal {
+            StopSignal::Term => libc::SIGTERM,
+            StopSignal::Int => libc::SIGINT,
+            StopSignal::Kill => libc::SIGKILL,
+        };
+        // SAFETY: kill(2) takes plain integers and has no memory-safety preconditions.
+        unsafe { libc::kill(pid, signal) == 0 }
+    }
+
+    #[cfg(not(unix))]
+    fn send_signal(&self, _signal: StopSignal) -> bool {
+        false
+    }
+}
+
+/// Table of background processes started via `process.start`.
+///
+/// Finished processes stay listed (so their logs can be read) until they are
+/// pushed out by newer ones.
+#[derive(Debug)]
+pub struct ProcessTable {
+    processes: RwLock<BTreeMap<u64, Arc<BackgroundProcess>>>,
+    next_id: AtomicU64,
+    max_processes: usize,
+}
+
+impl ProcessTable {
+    /// Creates a table allowing at most `max_processes` running at once.
+    pub fn new(max_processes: usize) -> Self {
+        Self {
+            processes: RwLock::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            max_processes,
+        }
+    }
+
+    /// Starts a process in the background, keeping its last `log_lines`
+    /// lines of output. Stdin and the timeout of `request` are not used.
+    pub fn spawn(&self, request: ProcessRequest<'_>, log_lines: usize) -> Result<Arc<BackgroundProcess>, ToolError> {
+        let mut processes = self.processes.write();
+
+        let running = processes.values().filter(|p| p.is_running()).count();
+        if running >= self.max_processes {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Too many background processes (max {})",
+                self.max_processes
+            )));
+        }
+
+        let mut command = Command::new(request.program);
+        command
+            .args(request.args)
+            .envs(request.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = &request.cwd {
+            command.current_dir(cwd);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to spawn process: {}", e)))?;
+
+        let process = Arc::new(BackgroundProcess {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            program: request.program.to_string(),
+            args: request.args.iter().map(|a| a.to_string()).collect(),
+            cwd: request.cwd,
+            pid: child.id(),
+            started_at: Utc::now(),
+            status: watch::channel(ProcessStatus::Running).0,
+            finished_at: Mutex::new(None),
+            logs: Mutex::new(LogBuffer::new(log_lines)),
+            kill: Notify::new(),
+        });
+
+        let readers = [
+            child.stdout.take().map(|pipe| tokio::spawn(read_lines(pipe, OutputStream::Stdout, process.clone()))),
+            child.stderr.take().map(|pipe| tokio::spawn(read_lines(pipe, OutputStream::Stderr, process.clone()))),
+        ];
+
+        let waiter = process.clone();
+        tokio::spawn(async move {
+            let exit_code = tokio::select! {
+                status = child.wait() => status.ok().and_then(|s| s.code()),
+                _ = waiter.kill.notified() => {
+                    let _ = child.kill().await;
+                    None
+                }
+            };
+
+            // Let the readers drain what is left in the pipes, unless a
+            // grandchild keeps them open
+            for reader in readers.into_iter().flatten() {
+                let _ = timeout(Duration::from_secs(1), reader).await;
+            }
+
+            *waiter.finished_at.lock() = Some(Utc::now());
+            waiter.status.send_replace(ProcessStatus::Exited(exit_code));
+            debug!("Background process {} exited with {:?}", waiter.id, exit_code);
+        });
+
+        // Drop the oldest finished entries beyond the running limit
+        let finished: Vec<u64> = processes
+            .iter()
+            .filter(|(_, p)| !p.is_running())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(self.max_processes)) {
+            processes.remove(id);
+        }
+
+        info!("Started background process {} ({}, pid {:?})", process.id, process.program, process.pid);
+        processes.insert(process.id, process.clone());
+        Ok(process)
+    }
+
+    /// Looks up a process by id.
+    pub fn get(&self, id: u64) -> Option<Arc<BackgroundProcess>> {
+        self.processes.read().get(&id).cloned()
+    }
+
+    /// All tracked processes, oldest first.
+    pub fn list(&self) -> Vec<Arc<BackgroundProcess>> {
+        self.processes.read().values().cloned().collect()
+    }
+
+    /// Stops every running process; called on server shutdown.
+    pub async fn shutdown(&self) {
+        let running: Vec<_> = self.list().into_iter().filter(|p| p.is_running()).collect();
+        if running.is_empty() {
+            return;
+        }
+        info!("Stopping {} background process(es)", running.len());
+        futures::future::join_all(
+            running.iter().map(|p| p.stop(StopSignal::Term, Duration::from_secs(3))),
+        )
+        .await;
+    }
+}
+
+/// Copies a pipe into a process's log buffer line by line.
+async fn read_lines<R: AsyncRead + Unpin>(pipe: R, stream: OutputStream, process: Arc<BackgroundProcess>) {
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.strip_suffix('\n').unwrap_or(&text);
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                process.logs.lock().push(stream, text.to_string());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +631,70 @@ mod tests {
         assert!(output.stdout.trim() == "hello");
     }
 
+    #[tokio::test]
+    async fn test_run_with_cwd_env_stdin_and_cap() {
+        let pm = ProcessManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let args = ["-c", "pwd; echo \"$GREETING\"; cat; echo oops >&2"];
+        let request = ProcessRequest::new("sh", &args)
+            .cwd(dir.path())
+            .envs([("GREETING".to_string(), "hi".to_string())])
+            .stdin("from stdin\n");
+
+        let mut chunks = Vec::new();
+        let output = pm.run(request, |stream, chunk| chunks.push((stream, chunk.len()))).await.unwrap();
+
+        let lines: Vec<&str> = output.stdout.lines().collect();
+        assert!(lines[0].ends_with(dir.path().file_name().unwrap().to_str().unwrap()));
+        assert_eq!(&lines[1..], ["hi", "from stdin"]);
+        assert_eq!(output.stderr, "oops\n");
+        assert!(!output.truncated);
+        assert!(chunks.iter().any(|(stream, _)| *stream == OutputStream::Stderr));
+
+        let args = ["-c", "yes | head -c 100000"];
+        let output = pm
+            .run(ProcessRequest::new("sh", &args).max_output_bytes(1000), |_, _| {})
+            .await
+            .unwrap();
+        assert!(output.success);
+        assert!(output.truncated);
+        assert_eq!(output.stdout.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_background_process_logs_and_stop() {
+        let table = ProcessTable::new(1);
+        let args = ["-c", "for i in 1 2 3 4 5; do echo line$i; done; echo err >&2; exec sleep 30"];
+        let process = table.spawn(ProcessRequest::new("sh", &args), 4).unwrap();
+
+        let err = table.spawn(ProcessRequest::new("sleep", &["1"]), 4).unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(_)));
+
+        // Wait for all output to land in the (4-line) ring buffer
+        for _ in 0..50 {
+            if process.logs(None, 10, None).next_seq == 6 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let tail = process.logs(None, 10, Some(OutputStream::Stdout));
+        let texts: Vec<&str> = tail.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["line3", "line4", "line5"]);
+
+        let since = process.logs(Some(0), 10, None);
+        assert_eq!(since.dropped, 2);
+        assert_eq!(since.next_seq, 6);
+        assert_eq!(since.lines.last().unwrap().stream, OutputStream::Stderr);
+
+        let status = process.stop(StopSignal::Term, Duration::from_secs(5)).await;
+        assert!(matches!(status, ProcessStatus::Exited(_)));
+        assert_eq!(table.list().len(), 1);
+
+        // A finished process frees its slot
+        table.spawn(ProcessRequest::new("true", &[]), 4).unwrap();
+        table.shutdown().await;
+    }
+
     #[tokio::test]
     async fn test_execute_timeout() {
         let pm = ProcessManager::with_timeout(1);
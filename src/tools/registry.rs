@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Debug;
 use std::sync::Arc;
 use thiserror::Error;
@@ -29,6 +29,12 @@ pub enum ToolError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error("Rate limit exceeded: {0}")]
+    RateLimited(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -50,6 +56,9 @@ pub struct ToolOutput {
     /// Whether the tool execution resulted in an error.
     #[serde(rename = "isError", default)]
     pub is_error: bool,
+    /// Structured result, conforming to the tool's output schema if it declares one.
+    #[serde(rename = "structuredContent", default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
 }
 
 /// Content item in tool output.
@@ -68,6 +77,17 @@ impl ToolOutput {
         Self {
             content: vec![ToolContent::Text { text: text.into() }],
             is_error: false,
+            structured_content: None,
+        }
+    }
+
+    /// Creates a successful structured output. The JSON is also sent as
+    /// text for clients that predate structured content.
+    pub fn structured(value: Value) -> Self {
+        Self {
+            content: vec![ToolContent::Text { text: value.to_string() }],
+            is_error: false,
+            structured_content: Some(value),
         }
     }
 
@@ -76,6 +96,7 @@ impl ToolOutput {
         Self {
             content: vec![ToolContent::Text { text: message.into() }],
             is_error: true,
+            structured_content: None,
         }
     }
 }
@@ -95,10 +116,43 @@ pub trait Tool: Send + Sync + Debug {
 }
 
 /// Registry for managing tools.
+///
+/// Tools can be disabled at runtime: a disabled tool stays registered but
+/// is hidden from `tools/list` and can't be looked up or executed.
+///
+/// Tools can also be exposed under aliases (e.g. `read_file` for
+/// `fs.read_file`). Lookups resolve aliases, but an alias never shadows a
+/// registered tool.
+///
+/// Internal tools are the building blocks of composite tools: they are
+/// hidden from `tools/list` and can only be looked up inside
+/// [`with_internal_tools`].
 #[derive(Debug)]
 pub struct ToolRegistry {
     /// All registered tools (public for iteration).
     pub tools: HashMap<String, Arc<dyn Tool>>,
+    /// Names of disabled tools (may include tools not registered yet).
+    disabled: BTreeSet<String>,
+    /// Alternative names, alias -> tool name.
+    aliases: HashMap<String, String>,
+    /// List aliased tools only under their aliases.
+    hide_aliased: bool,
+    /// Names of internal tools.
+    internal: BTreeSet<String>,
+}
+
+tokio::task_local! {
+    static INTERNAL_ACCESS: ();
+}
+
+/// Runs `fut` with internal tools resolvable, for composite tools running
+/// their workflow.
+pub async fn with_internal_tools<F: std::future::Future>(fut: F) -> F::Output {
+    INTERNAL_ACCESS.scope((), fut).await
+}
+
+fn internal_access() -> bool {
+    INTERNAL_ACCESS.try_with(|_| ()).is_ok()
 }
 
 impl ToolRegistry {
@@ -106,6 +160,10 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            disabled: BTreeSet::new(),
+            aliases: HashMap::new(),
+            hide_aliased: false,
+            internal: BTreeSet::new(),
         }
     }
 
@@ -116,11 +174,76 @@ impl ToolRegistry {
         self.tools.insert(name, tool);
     }
 
-    /// Gets a tool by name.
+    /// Removes a tool, returning it if it was registered.
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Tool>> {
+        tracing::debug!("Unregistering tool: {}", name);
+        self.tools.remove(name)
+    }
+
+    /// Sets the aliases tools are also exposed under (alias -> tool name).
+    /// With `hide_aliased`, aliased tools are listed only under their
+    /// aliases, though their own names still resolve.
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>, hide_aliased: bool) {
+        self.aliases = aliases;
+        self.hide_aliased = hide_aliased;
+    }
+
+    /// Sets the tools that are internal to composite tools.
+    pub fn set_internal(&mut self, names: impl IntoIterator<Item = String>) {
+        self.internal = names.into_iter().collect();
+    }
+
+    /// Whether a tool is internal to composite tools.
+    pub fn is_internal(&self, name: &str) -> bool {
+        self.internal.contains(name)
+    }
+
+    /// Resolves an alias to the name of the tool it stands for; other
+    /// names are returned unchanged.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.tools.contains_key(name) {
+            return name;
+        }
+        self.aliases.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// Gets an enabled tool by name or alias. Internal tools are only
+    /// found inside [`with_internal_tools`].
     pub fn get(&self, name: &str) -> Option<&Arc<dyn Tool>> {
+        let name = self.resolve(name);
+        if self.disabled.contains(name) || (self.internal.contains(name) && !internal_access()) {
+            return None;
+        }
         self.tools.get(name)
     }
 
+    /// Disables a tool. Returns whether it was enabled before.
+    pub fn disable(&mut self, name: &str) -> bool {
+        self.disabled.insert(name.to_string())
+    }
+
+    /// Re-enables a disabled tool. Returns whether it was disabled before.
+    pub fn enable(&mut self, name: &str) -> bool {
+        self.disabled.remove(name)
+    }
+
+    /// Whether a tool is disabled.
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.disabled.contains(name)
+    }
+
+    /// Returns the names of all disabled tools, sorted.
+    pub fn disabled(&self) -> Vec<String> {
+        self.disabled.iter().cloned().collect()
+    }
+
+    /// Returns the names of all registered tools (enabled or not), sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tools.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     /// Executes a tool by name.
     pub async fn execute(
         &self,
@@ -128,15 +251,38 @@ impl ToolRegistry {
         arguments: Value,
         state: Arc<RuntimeState>,
     ) -> Result<ToolOutput, ToolError> {
-        match self.tools.get(name) {
+        match self.get(name) {
             Some(tool) => tool.execute(arguments, state).await,
             None => Err(ToolError::NotFound(name.to_string())),
         }
     }
 
-    /// Returns all tool definitions for MCP.
+    /// Returns the definitions of all enabled tools for MCP, aliases
+    /// included under their own names.
     pub fn list_definitions(&self) -> Vec<ToolDefinition> {
-        self.tools.values().map(|t| t.definition()).collect()
+        let aliased: BTreeSet<&str> = if self.hide_aliased {
+            self.aliases.values().map(String::as_str).collect()
+        } else {
+            BTreeSet::new()
+        };
+        let tools = self
+            .tools
+            .iter()
+            .filter(|(name, _)| !self.disabled.contains(*name) && !self.internal.contains(*name))
+            .filter(|(name, _)| !aliased.contains(name.as_str()))
+            .map(|(_, t)| t.definition());
+
+        let aliases = self
+            .aliases
+            .iter()
+            .filter(|(alias, _)| !self.tools.contains_key(*alias))
+            .filter_map(|(alias, name)| {
+                let mut definition = self.get(name)?.definition();
+                definition.name = alias.clone();
+                Some(definition)
+            });
+
+        tools.chain(aliases).collect()
     }
 }
 
@@ -0,0 +1,131 @@
+//! Filesystem roots declared by the client.
+//!
+//! Editors and other MCP clients can declare the directories the user has
+//! open (`roots`). When the client supports them, the session's roots are
+//! fetched with `roots/list` before its first tool call and cached until the
+//! client sends `notifications/roots/list_changed`. File tools and git tools
+//! then only accept paths inside a root, on top of the static allowlists in
+//! `security`. Sessions whose client has no roots are limited by the
+//! allowlists alone.
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::core::Session;
+use crate::tools::{caller, client, ToolError};
+
+/// Time to wait for the client to list its roots.
+pub const ROOTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fetches the client's roots for `session` unless they are already known.
+/// A client that fails to answer leaves the session unscoped; the next call
+/// asks again.
+pub async fn refresh(session: &Session) {
+    if session.roots().is_some() {
+        return;
+    }
+    let Some(peer) = client::current().filter(|peer| peer.supports_roots() && peer.is_connected()) else {
+        return;
+    };
+    match peer.request("roots/list", json!({}), ROOTS_TIMEOUT).await {
+        Ok(result) => {
+            let roots = parse_roots(&result);
+            if roots.is_empty() {
+                warn!("Client declared no local roots; file and git tools will refuse every path");
+            } else {
+                debug!("Client roots for session {}: {:?}", session.id(), roots);
+            }
+            session.set_roots(Some(roots));
+        }
+        Err(e) => warn!("Failed to list client roots: {}", e),
+    }
+}
+
+/// The local directories of a `roots/list` result. Roots that are not
+/// `file://` URIs or don't exist on this machine are skipped.
+pub fn parse_roots(result: &Value) -> Vec<PathBuf> {
+    result
+        .get("roots")
+        .and_then(|roots| roots.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|root| root.get("uri")?.as_str())
+        .filter_map(|uri| Url::parse(uri).ok()?.to_file_path().ok())
+        .filter_map(|path| path.canonicalize().ok())
+        .collect()
+}
+
+/// Whether a canonical path lies within the current caller's roots. Calls
+/// without known roots are not restricted.
+pub fn allows(path: &Path) -> bool {
+    match caller::session().and_then(|session| session.roots()) {
+        Some(roots) => roots.iter().any(|root| path.starts_with(root)),
+        None => true,
+    }
+}
+
+/// Checks that an existing path lies within the current caller's roots.
+pub fn check(path: &Path) -> Result<(), ToolError> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| ToolError::InvalidInput(format!("Invalid path '{}': {}", path.display(), e)))?;
+    if allows(&canonical) {
+        Ok(())
+    } else {
+        Err(ToolError::PermissionDenied(format!("Path outside the client's roots: {}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::RequestContext;
+    use crate::protocol::ClientCapabilities;
+    use crate::tools::client::ClientPeer;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_roots_scope_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+
+        let peer = Arc::new(ClientPeer::new());
+        let mut outbound = peer.attach();
+        peer.set_capabilities(ClientCapabilities { roots: Some(json!({"listChanged": true})), ..Default::default() });
+        let client = peer.clone();
+        let uri = Url::from_file_path(&workspace).unwrap().to_string();
+        tokio::spawn(async move {
+            let request = outbound.recv().await.unwrap();
+            assert_eq!(request["method"], "roots/list");
+            client.handle_response(&json!({
+                "id": request["id"],
+                "result": {"roots": [
+                    {"uri": uri, "name": "workspace"},
+                    {"uri": "https://example.com/repo"},
+                    {"uri": "file:///does/not/exist"}
+                ]}
+            }));
+        });
+
+        let session = Arc::new(Session::new("s1"));
+        // Unknown roots restrict nothing
+        let context = RequestContext::new("s1").with_session(session.clone());
+        caller::with_context(context.clone(), async {
+            assert!(check(tmp.path()).is_ok());
+        })
+        .await;
+
+        client::with_peer(peer, refresh(&session)).await;
+        assert_eq!(session.roots(), Some(vec![workspace.canonicalize().unwrap()]));
+        caller::with_context(context, async {
+            assert!(check(&workspace).is_ok());
+            assert!(matches!(check(tmp.path()), Err(ToolError::PermissionDenied(_))));
+            assert!(matches!(check(&workspace.join("missing")), Err(ToolError::InvalidInput(_))));
+        })
+        .await;
+    }
+}
@@ -0,0 +1,300 @@
+//! Streaming tool output and progress.
+//!
+//! Tools that produce output incrementally (e.g. LLM completions) report
+//! chunks with [`emit`] as they arrive and still return the complete result.
+//! Long-running tools report how far along they are through a
+//! [`ProgressReporter`]. A transport that can deliver either to the client
+//! installs a sink for the duration of a call with [`with_sink`] and turns
+//! what arrives into `notifications/progress` with a [`Progress`]; without
+//! a sink, chunks and reports are dropped. What a sink passes on is held
+//! to the same secret masking and output limit as results, by a
+//! [`SinkFilter`].
+
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::core::RuntimeState;
+use crate::secrets::scrub::{scrub, scrub_value};
+
+/// Something a call reports while it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressUpdate {
+    /// A chunk of partial output.
+    Chunk(Value),
+    /// Steps done, out of `total` if known.
+    Step { progress: u64, total: Option<u64>, message: String },
+}
+
+/// What a sink lets through to the client. Secrets are masked in chunks
+/// and progress messages like in results, and chunks stop once they add up
+/// to `output_limit.max_bytes`; the result itself is still capped and its
+/// full text kept by the middleware.
+#[derive(Debug, Clone, Default)]
+pub struct SinkFilter {
+    /// Secrets to mask, when `security.scrub_secrets` is on.
+    secrets: Option<Arc<Vec<(String, String)>>>,
+    /// Bytes of chunks let through per call; 0 for no limit.
+    max_bytes: usize,
+    sent: Arc<AtomicUsize>,
+}
+
+impl SinkFilter {
+    /// The filter for a call made to this server.
+    pub fn from_state(state: &RuntimeState) -> Self {
+        Self {
+            secrets: state.config.security.scrub_secrets.then(|| Arc::new(state.secrets.values())),
+            max_bytes: state.config.output_limit.max_bytes,
+            sent: Arc::default(),
+        }
+    }
+
+    /// The chunk as the client may see it, or `None` past the limit.
+    fn chunk(&self, mut chunk: Value) -> Option<Value> {
+        if let Some(secrets) = &self.secrets {
+            scrub_value(&mut chunk, secrets);
+        }
+        if self.max_bytes > 0 {
+            let len = match &chunk {
+                Value::String(text) => text.len(),
+                other => other.to_string().len(),
+            };
+            if self.sent.fetch_add(len, Ordering::Relaxed) + len > self.max_bytes {
+                return None;
+            }
+        }
+        Some(chunk)
+    }
+
+    /// The progress message as the client may see it.
+    fn message(&self, message: String) -> String {
+        match &self.secrets {
+            Some(secrets) => scrub(&message, secrets).into_owned(),
+            None => message,
+        }
+    }
+}
+
+/// Where a call's reports go.
+#[derive(Debug, Clone)]
+struct Sink {
+    tx: UnboundedSender<ProgressUpdate>,
+    filter: SinkFilter,
+}
+
+impl Sink {
+    fn send(&self, update: ProgressUpdate) {
+        let update = match update {
+            ProgressUpdate::Chunk(chunk) => match self.filter.chunk(chunk) {
+                Some(chunk) => ProgressUpdate::Chunk(chunk),
+                None => return,
+            },
+            ProgressUpdate::Step { progress, total, message } => {
+                ProgressUpdate::Step { progress, total, message: self.filter.message(message) }
+            }
+        };
+        let _ = self.tx.send(update);
+    }
+}
+
+tokio::task_local! {
+    static SINK: Option<Sink>;
+}
+
+/// Runs `fut` with chunks and progress reported inside it sent to `sink`,
+/// after passing through `filter`.
+pub async fn with_sink<F: Future>(sink: UnboundedSender<ProgressUpdate>, filter: SinkFilter, fut: F) -> F::Output {
+    SINK.scope(Some(Sink { tx: sink, filter }), fut).await
+}
+
+/// Runs `fut` with nothing it reports reaching the client. Tools that
+/// report their own progress run the tools they call this way, so nested
+/// reports don't interleave with theirs.
+pub async fn without_sink<F: Future>(fut: F) -> F::Output {
+    SINK.scope(None, fut).await
+}
+
+fn sink() -> Option<Sink> {
+    SINK.try_with(|sink| sink.clone()).ok().flatten()
+}
+
+/// Sends a chunk of partial output to the current call's sink, if any.
+pub fn emit(chunk: Value) {
+    if let Some(sink) = sink() {
+        sink.send(ProgressUpdate::Chunk(chunk));
+    }
+}
+
+/// Whether the current call has a sink, i.e. streaming is worth doing.
+pub fn is_active() -> bool {
+    SINK.try_with(|sink| sink.is_some()).unwrap_or(false)
+}
+
+/// Handle a tool reports its progress through. It can be cloned into
+/// tasks the tool spawns.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressReporter {
+    sink: Option<Sink>,
+}
+
+impl ProgressReporter {
+    /// The reporter for the current call; reports go nowhere unless the
+    /// client asked for progress.
+    pub fn current() -> Self {
+        Self { sink: sink() }
+    }
+
+    /// Whether anyone receives the reports.
+    pub fn is_active(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Reports that `progress` of `total` (if known) steps are done.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: impl Into<String>) {
+        if let Some(sink) = &self.sink {
+            sink.send(ProgressUpdate::Step { progress, total, message: message.into() });
+        }
+    }
+}
+
+/// Params of the `notifications/progress` that carries the `progress`-th
+/// chunk of a call the client gave `token` for.
+pub fn progress_params(token: &Value, progress: u64, chunk: Value) -> Value {
+    let message = match &chunk {
+        Value::String(text) => text.clone(),
+        other => other.get("text").and_then(|t| t.as_str()).map(str::to_string).unwrap_or_else(|| other.to_string()),
+    };
+    json!({
+        "progressToken": token,
+        "progress": progress,
+        "message": message,
+        "chunk": chunk
+    })
+}
+
+/// Turns one call's updates into `notifications/progress` params.
+///
+/// MCP requires the progress value to grow with every notification. Chunks
+/// count up by one; steps use the tool's count, moved past the last value
+/// sent if chunks got ahead of it.
+#[derive(Debug)]
+pub struct Progress {
+    token: Value,
+    last: Option<u64>,
+}
+
+impl Progress {
+    /// Tracks the progress of a call the client gave `token` for.
+    pub fn new(token: Value) -> Self {
+        Self { token, last: None }
+    }
+
+    /// The params of the notification for `update`.
+    pub fn params(&mut self, update: ProgressUpdate) -> Value {
+        match update {
+            ProgressUpdate::Chunk(chunk) => {
+                let progress = self.last.map_or(1, |last| last + 1);
+                self.last = Some(progress);
+                progress_params(&self.token, progress, chunk)
+            }
+            ProgressUpdate::Step { progress, total, message } => {
+                let progress = self.last.map_or(progress, |last| progress.max(last + 1));
+                self.last = Some(progress);
+                let mut params = json!({
+                    "progressToken": self.token,
+                    "progress": progress,
+                    "message": message
+                });
+                if let Some(total) = total.filter(|&total| total >= progress) {
+                    params["total"] = json!(total);
+                }
+                params
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_emit_reaches_sink_only_in_scope() {
+        emit(json!("dropped"));
+        assert!(!is_active());
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        with_sink(tx, SinkFilter::default(), async {
+            assert!(is_active());
+            emit(json!({"text": "a"}));
+            emit(json!({"text": "b"}));
+        })
+        .await;
+
+        assert_eq!(rx.recv().await, Some(ProgressUpdate::Chunk(json!({"text": "a"}))));
+        assert_eq!(rx.recv().await, Some(ProgressUpdate::Chunk(json!({"text": "b"}))));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_progress_reports() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        with_sink(tx, SinkFilter::default(), async {
+            let reporter = ProgressReporter::current();
+            reporter.report(0, Some(2), "starting");
+            without_sink(async {
+                assert!(!is_active());
+                emit(json!("nested"));
+                ProgressReporter::current().report(5, None, "nested");
+            })
+            .await;
+            emit(json!("chunk"));
+            reporter.report(1, Some(2), "halfway");
+            reporter.report(2, Some(2), "done");
+        })
+        .await;
+
+        let mut progress = Progress::new(json!("t"));
+        let mut params = Vec::new();
+        while let Some(update) = rx.recv().await {
+            params.push(progress.params(update));
+        }
+        let values: Vec<_> = params.iter().map(|p| (p["progress"].clone(), p.get("total").cloned())).collect();
+        // The chunk pushed later steps up by one; past the total it is dropped
+        assert_eq!(
+            values,
+            [(json!(0), Some(json!(2))), (json!(1), None), (json!(2), Some(json!(2))), (json!(3), None)]
+        );
+        assert_eq!(params[0]["message"], "starting");
+        assert_eq!(params[1]["chunk"], "chunk");
+    }
+
+    #[tokio::test]
+    async fn test_sink_masks_secrets_and_caps_chunks() {
+        let filter = SinkFilter {
+            secrets: Some(Arc::new(vec![("API_TOKEN".to_string(), "s3cr3t-value-123".to_string())])),
+            max_bytes: 40,
+            sent: Arc::default(),
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        with_sink(tx, filter, async {
+            emit(json!({"text": "token s3cr3t-value-123"}));
+            ProgressReporter::current().report(1, None, "using s3cr3t-value-123");
+            emit(json!("more than the forty bytes the sink lets through"));
+        })
+        .await;
+
+        let Some(ProgressUpdate::Chunk(chunk)) = rx.recv().await else {
+            panic!("expected a chunk");
+        };
+        assert!(!chunk.to_string().contains("s3cr3t"));
+        let Some(ProgressUpdate::Step { message, .. }) = rx.recv().await else {
+            panic!("expected a step");
+        };
+        assert!(!message.contains("s3cr3t"));
+        assert_eq!(rx.recv().await, None);
+    }
+}
@@ -0,0 +1,208 @@
+//! Typed tools: tools declared with serde-typed arguments.
+//!
+//! Instead of a hand-written `json!()` schema and manual argument
+//! extraction, a typed tool names its arguments as a struct deriving
+//! `Deserialize` and `JsonSchema`; the input schema is generated from it
+//! and arguments arrive already parsed:
+//!
+//! ```ignore
+//! #[derive(Deserialize, JsonSchema)]
+//! struct GreetArgs {
+//!     /// Who to greet
+//!     name: String,
+//! }
+//!
+//! #[derive(Debug)]
+//! struct GreetTool;
+//!
+//! #[async_trait]
+//! impl TypedTool for GreetTool {
+//!     type Args = GreetArgs;
+//!     type Output = String;
+//!     const NAME: &'static str = "greet";
+//!     const DESCRIPTION: &'static str = "Greets someone.";
+//!
+//!     async fn run(&self, args: GreetArgs, _state: Arc<RuntimeState>) -> Result<String, ToolError> {
+//!         Ok(format!("Hello, {}!", args.name))
+//!     }
+//! }
+//! ```
+//!
+//! Every `TypedTool` is a [`Tool`] and registers like any other. Returning
+//! [`Structured`] also declares an output schema generated from the type.
+
+use async_trait::async_trait;
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::core::RuntimeState;
+use crate::protocol::mcp::Tool as ToolDefinition;
+use crate::tools::registry::{Tool, ToolError, ToolOutput};
+
+/// A tool whose arguments (and optionally result) are Rust types.
+#[async_trait]
+pub trait TypedTool: Send + Sync + Debug {
+    /// The tool's arguments; doc comments on fields become descriptions.
+    type Args: DeserializeOwned + JsonSchema + Send;
+    /// What the tool returns.
+    type Output: IntoToolOutput;
+
+    /// The tool name.
+    const NAME: &'static str;
+    /// The tool description.
+    const DESCRIPTION: &'static str;
+
+    /// Runs the tool with parsed arguments.
+    async fn run(&self, args: Self::Args, state: Arc<RuntimeState>) -> Result<Self::Output, ToolError>;
+}
+
+/// A value a typed tool can return.
+pub trait IntoToolOutput: Send {
+    /// The output schema to declare, if any.
+    fn output_schema() -> Option<Value> {
+        None
+    }
+
+    /// Converts the value into the tool result.
+    fn into_tool_output(self) -> Result<ToolOutput, ToolError>;
+}
+
+impl IntoToolOutput for ToolOutput {
+    fn into_tool_output(self) -> Result<ToolOutput, ToolError> {
+        Ok(self)
+    }
+}
+
+impl IntoToolOutput for String {
+    fn into_tool_output(self) -> Result<ToolOutput, ToolError> {
+        Ok(ToolOutput::text(self))
+    }
+}
+
+/// A structured result, returned as `structuredContent` with an output
+/// schema generated from `T` (which must serialize to a JSON object).
+#[derive(Debug, Clone)]
+pub struct Structured<T>(pub T);
+
+impl<T: Serialize + JsonSchema + Send> IntoToolOutput for Structured<T> {
+    fn output_schema() -> Option<Value> {
+        Some(schema_for::<T>(SchemaSettings::draft2020_12().for_serialize()))
+    }
+
+    fn into_tool_output(self) -> Result<ToolOutput, ToolError> {
+        let value = serde_json::to_value(&self.0)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to serialize result: {}", e)))?;
+        Ok(ToolOutput::structured(value))
+    }
+}
+
+/// Generates a self-contained schema for `T`, without the `$schema` and
+/// `title` keywords tool definitions don't use.
+fn schema_for<T: JsonSchema>(settings: SchemaSettings) -> Value {
+    let schema = settings
+        .with(|s| {
+            s.meta_schema = None;
+            s.inline_subschemas = true;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+
+    let mut value = schema.to_value();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("title");
+    }
+    value
+}
+
+/// The input schema generated for a typed tool's arguments.
+pub fn input_schema<T: JsonSchema>() -> Value {
+    schema_for::<T>(SchemaSettings::draft2020_12().for_deserialize())
+}
+
+#[async_trait]
+impl<T: TypedTool> Tool for T {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: T::NAME.to_string(),
+            description: Some(T::DESCRIPTION.to_string()),
+            input_schema: input_schema::<T::Args>(),
+            output_schema: T::Output::output_schema(),
+        }
+    }
+
+    async fn execute(
+        &self,
+        arguments: Value,
+        state: Arc<RuntimeState>,
+    ) -> Result<ToolOutput, ToolError> {
+        // Tools without arguments may be called with none at all
+        let arguments = if arguments.is_null() { Value::Object(Default::default()) } else { arguments };
+        let args: T::Args = serde_json::from_value(arguments)
+            .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+        self.run(args, state).await?.into_tool_output()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct AddArgs {
+        /// First operand
+        a: i64,
+        /// Second operand
+        #[serde(default)]
+        b: i64,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Sum {
+        sum: i64,
+    }
+
+    #[derive(Debug)]
+    struct AddTool;
+
+    #[async_trait]
+    impl TypedTool for AddTool {
+        type Args = AddArgs;
+        type Output = Structured<Sum>;
+        const NAME: &'static str = "math.add";
+        const DESCRIPTION: &'static str = "Adds two numbers.";
+
+        async fn run(&self, args: AddArgs, _state: Arc<RuntimeState>) -> Result<Structured<Sum>, ToolError> {
+            Ok(Structured(Sum { sum: args.a + args.b }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool() {
+        let definition = AddTool.definition();
+        assert_eq!(definition.name, "math.add");
+        let schema = &definition.input_schema;
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["a"]["description"], "First operand");
+        assert_eq!(schema["required"], json!(["a"]));
+        assert!(schema.get("$schema").is_none() && schema.get("title").is_none());
+        assert_eq!(definition.output_schema.unwrap()["required"], json!(["sum"]));
+
+        let state = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        let output = AddTool.execute(json!({"a": 2, "b": 3}), state.clone()).await.unwrap();
+        assert_eq!(output.structured_content, Some(json!({"sum": 5})));
+
+        let err = AddTool.execute(json!({"a": "two"}), state).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)), "{}", err);
+    }
+}
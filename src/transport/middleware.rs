@@ -2,7 +2,7 @@
 
 use axum::{
     body::Body,
-    extract::State,
+    extract::{ConnectInfo, State},
     http::{Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
@@ -10,27 +10,175 @@ use axum::{
 };
 use sha2::{Digest, Sha256};
 use serde_json::json;
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use dashmap::DashMap;
+use futures::StreamExt;
 use tracing::{info, warn};
 
-use crate::core::Config;
+use crate::core::config::{AuthConfig, KeyQuota};
+use crate::core::{Config, KeyIdentity};
+#[cfg(feature = "redis")]
+use crate::memory::{MemoryError, RedisConnection};
 
 // ============================================================================
 // Authentication Middleware
 // ============================================================================
 
+/// Identity of the API key a request authenticated with.
+///
+/// Inserted into request extensions by [`auth_middleware`].
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey(pub Arc<KeyIdentity>);
+
+/// A configured API key with its limits.
+struct KeyEntry {
+    identity: Arc<KeyIdentity>,
+    limiter: Option<RateLimiter>,
+    quota: Option<KeyQuota>,
+}
+
+/// Why an authenticated key was turned away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyRejection {
+    /// The key's rate limit was hit.
+    RateLimited,
+    /// The key's quota is used up until the window resets.
+    QuotaExceeded { retry_after_secs: u64 },
+}
+
+/// Configured API keys, keyed by hash, with per-key rate limits and quotas.
+#[derive(Default)]
+pub struct ApiKeys {
+    keys: HashMap<String, KeyEntry>,
+    /// Quota window start and request count per key hash.
+    quota_usage: DashMap<String, (Instant, u64)>,
+}
+
+impl ApiKeys {
+    /// Builds the key set from `auth.keys` plus the unnamed `auth.api_keys`.
+    pub fn from_config(auth: &AuthConfig) -> Self {
+        let mut keys = HashMap::new();
+
+        for hash in &auth.api_keys {
+            let identity = KeyIdentity {
+                name: hash[..hash.len().min(16)].to_string(),
+                key_hash: hash.clone(),
+                scopes: vec!["*".to_string()],
+            };
+            keys.insert(hash.clone(), KeyEntry {
+                identity: Arc::new(identity),
+                limiter: None,
+                quota: None,
+            });
+        }
+
+        for key in &auth.keys {
+            let identity = KeyIdentity {
+                name: key.name.clone(),
+                key_hash: key.key_hash.clone(),
+                scopes: key.scopes.clone(),
+            };
+            let limiter = key
+                .rate_limit
+                .as_ref()
+                .map(|limit| RateLimiter::with_limits(limit.requests_per_second as f64, limit.burst_size));
+            keys.insert(key.key_hash.clone(), KeyEntry {
+                identity: Arc::new(identity),
+                limiter,
+                quota: key.quota.clone(),
+            });
+        }
+
+        Self { keys, quota_usage: DashMap::new() }
+    }
+
+    /// Moves the per-key rate-limit buckets to Redis.
+    #[cfg(feature = "redis")]
+    fn share_limits(&mut self, redis: Arc<RedisConnection>) {
+        for entry in self.keys.values_mut() {
+            entry.limiter = entry.limiter.take().map(|limiter| limiter.shared(redis.clone(), "key"));
+        }
+    }
+
+    /// Returns the number of configured keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether no keys are configured.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Looks up the identity for a key hash.
+    pub fn authenticate(&self, hash: &str) -> Option<Arc<KeyIdentity>> {
+        self.keys.get(hash).map(|entry| entry.identity.clone())
+    }
+
+    /// Charges one request to a key, enforcing its rate limit and quota.
+    pub async fn admit(&self, hash: &str) -> Result<(), KeyRejection> {
+        let Some(entry) = self.keys.get(hash) else {
+            return Ok(());
+        };
+
+        if let Some(limiter) = &entry.limiter {
+            if !limiter.check(hash).await {
+                return Err(KeyRejection::RateLimited);
+            }
+        }
+
+        if let Some(quota) = &entry.quota {
+            let window = Duration::from_secs(quota.window_secs);
+            let now = Instant::now();
+            let mut usage = self.quota_usage.entry(hash.to_string()).or_insert((now, 0));
+            if now.duration_since(usage.0) >= window {
+                *usage = (now, 0);
+            }
+            if usage.1 >= quota.max_requests {
+                let reset = window.saturating_sub(now.duration_since(usage.0));
+                return Err(KeyRejection::QuotaExceeded {
+                    retry_after_secs: reset.as_secs().max(1),
+                });
+            }
+            usage.1 += 1;
+        }
+
+        Ok(())
+    }
+}
+
 /// State for authentication middleware.
 #[derive(Clone)]
 pub struct AuthState {
     pub config: Arc<Config>,
+    pub keys: Arc<ApiKeys>,
+}
+
+impl AuthState {
+    /// Creates the auth state for a configuration.
+    pub fn new(config: &Config) -> Self {
+        #[allow(unused_mut)]
+        let mut keys = ApiKeys::from_config(&config.auth);
+        #[cfg(feature = "redis")]
+        if let Some(redis) = shared_buckets(config) {
+            keys.share_limits(redis);
+        }
+
+        Self {
+            config: Arc::new(config.clone()),
+            keys: Arc::new(keys),
+        }
+    }
 }
 
 /// Authentication middleware that checks for valid API keys.
 pub async fn auth_middleware(
     State(state): State<AuthState>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Response {
     // Skip auth if disabled
@@ -44,6 +192,11 @@ pub async fn auth_middleware(
         return next.run(request).await;
     }
 
+    // Webhooks verify their own signatures
+    if path.starts_with("/hooks/") {
+        return next.run(request).await;
+    }
+
     // Get API key from header
     let api_key = request
         .headers()
@@ -58,17 +211,44 @@ pub async fn auth_middleware(
             let hash = hex::encode(hasher.finalize());
 
             // Check if hash matches any configured key
-            if state.config.auth.api_keys.contains(&hash) {
-                next.run(request).await
-            } else {
+            let Some(identity) = state.keys.authenticate(&hash) else {
                 warn!("Invalid API key attempted");
-                (
+                return (
                     StatusCode::UNAUTHORIZED,
                     Json(json!({
                         "error": "Invalid API key"
                     })),
                 )
-                    .into_response()
+                    .into_response();
+            };
+
+            match state.keys.admit(&hash).await {
+                Ok(()) => {
+                    request.extensions_mut().insert(AuthenticatedKey(identity));
+                    next.run(request).await
+                }
+                Err(KeyRejection::RateLimited) => {
+                    warn!("Rate limit exceeded for API key '{}'", identity.name);
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        Json(json!({
+                            "error": "Rate limit exceeded for API key",
+                            "retry_after": 1
+                        })),
+                    )
+                        .into_response()
+                }
+                Err(KeyRejection::QuotaExceeded { retry_after_secs }) => {
+                    warn!("Quota exceeded for API key '{}'", identity.name);
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        Json(json!({
+                            "error": "Quota exceeded for API key",
+                            "retry_after": retry_after_secs
+                        })),
+                    )
+                        .into_response()
+                }
             }
         }
         None => {
@@ -89,6 +269,9 @@ pub async fn auth_middleware(
 // ============================================================================
 
 /// Rate limiter using token bucket algorithm.
+///
+/// Buckets live in memory, or in Redis when `redis.url` is set so that all
+/// instances draw from the same buckets.
 #[derive(Clone)]
 pub struct RateLimiter {
     /// Tokens per client IP
@@ -99,6 +282,53 @@ pub struct RateLimiter {
     burst: u32,
     /// Whether enabled
     enabled: bool,
+    /// Redis connection and key scope of shared buckets
+    #[cfg(feature = "redis")]
+    shared: Option<(Arc<RedisConnection>, &'static str)>,
+}
+
+/// Refills and takes a token from a bucket hash in one step, on the Redis
+/// clock so instances agree on elapsed time. Returns 1 if a token was taken.
+#[cfg(feature = "redis")]
+static TOKEN_BUCKET: std::sync::LazyLock<redis::Script> = std::sync::LazyLock::new(|| {
+    redis::Script::new(
+        r"
+        redis.replicate_commands()
+        local rate = tonumber(ARGV[1])
+        local burst = tonumber(ARGV[2])
+        local time = redis.call('TIME')
+        local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+        local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+        local tokens = tonumber(bucket[1]) or burst
+        local ts = tonumber(bucket[2]) or now
+        tokens = math.min(burst, tokens + math.max(0, now - ts) * rate)
+        local allowed = 0
+        if tokens >= 1 then
+            tokens = tokens - 1
+            allowed = 1
+        end
+        redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(now))
+        local ttl = 3600
+        if rate > 0 then
+            ttl = math.ceil(burst / rate) + 1
+        end
+        redis.call('EXPIRE', KEYS[1], ttl)
+        return allowed
+        ",
+    )
+});
+
+/// The Redis connection rate-limit buckets are shared through, if any.
+#[cfg(feature = "redis")]
+pub(crate) fn shared_buckets(config: &Config) -> Option<Arc<RedisConnection>> {
+    let url = config.redis.url.as_ref().filter(|_| config.redis.rate_limit)?;
+    match RedisConnection::new(url, &config.redis.key_prefix) {
+        Ok(redis) => Some(Arc::new(redis)),
+        Err(e) => {
+            warn!("Rate limits stay local: {}", e);
+            None
+        }
+    }
 }
 
 struct TokenBucket {
@@ -108,19 +338,78 @@ struct TokenBucket {
 
 impl RateLimiter {
     pub fn new(config: &Config) -> Self {
-        Self {
+        let limiter = Self {
             buckets: Arc::new(DashMap::new()),
             rate: config.rate_limit.requests_per_second as f64,
             burst: config.rate_limit.burst_size,
             enabled: config.rate_limit.enabled,
+            #[cfg(feature = "redis")]
+            shared: None,
+        };
+
+        #[cfg(feature = "redis")]
+        if limiter.enabled {
+            if let Some(redis) = shared_buckets(config) {
+                return limiter.shared(redis, "client");
+            }
         }
+        limiter
     }
 
-    pub fn check(&self, client_id: &str) -> bool {
+    /// Creates an always-enabled limiter with explicit limits.
+    pub fn with_limits(rate: f64, burst: u32) -> Self {
+        Self {
+            buckets: Arc::new(DashMap::new()),
+            rate,
+            burst,
+            enabled: true,
+            #[cfg(feature = "redis")]
+            shared: None,
+        }
+    }
+
+    /// Keeps the buckets in Redis under `scope`, falling back to local
+    /// buckets while Redis is unreachable.
+    #[cfg(feature = "redis")]
+    pub fn shared(mut self, redis: Arc<RedisConnection>, scope: &'static str) -> Self {
+        self.shared = Some((redis, scope));
+        self
+    }
+
+    pub async fn check(&self, client_id: &str) -> bool {
         if !self.enabled {
             return true;
         }
 
+        #[cfg(feature = "redis")]
+        if let Some((redis, scope)) = &self.shared {
+            match self.check_shared(redis, scope, client_id).await {
+                Ok(allowed) => return allowed,
+                Err(e) => warn!("Shared rate limit unavailable, using local bucket: {}", e),
+            }
+        }
+
+        self.check_local(client_id)
+    }
+
+    #[cfg(feature = "redis")]
+    async fn check_shared(
+        &self,
+        redis: &RedisConnection,
+        scope: &str,
+        client_id: &str,
+    ) -> Result<bool, MemoryError> {
+        let key = redis.key(&format!("ratelimit:{}:{}", scope, client_id));
+        TOKEN_BUCKET
+            .key(key)
+            .arg(self.rate)
+            .arg(self.burst)
+            .invoke_async(&mut redis.manager().await?)
+            .await
+            .map_err(|e| MemoryError::Database(e.to_string()))
+    }
+
+    fn check_local(&self, client_id: &str) -> bool {
         let now = Instant::now();
         let mut bucket = self.buckets.entry(client_id.to_string()).or_insert_with(|| {
             TokenBucket {
@@ -156,6 +445,74 @@ impl RateLimiter {
 #[derive(Clone)]
 pub struct RateLimitState {
     pub limiter: RateLimiter,
+    /// Proxies whose `X-Forwarded-For` header is believed.
+    pub trusted_proxies: Arc<Vec<IpNet>>,
+}
+
+impl RateLimitState {
+    /// Creates the rate limit state for a configuration.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            limiter: RateLimiter::new(config),
+            trusted_proxies: Arc::new(parse_networks(&config.rate_limit.trusted_proxies)),
+        }
+    }
+}
+
+/// Parses address list entries, each an IP address or a CIDR range.
+/// Invalid entries are skipped (config validation reports them).
+fn parse_networks(proxies: &[String]) -> Vec<IpNet> {
+    proxies
+        .iter()
+        .filter_map(|proxy| {
+            proxy
+                .parse::<IpNet>()
+                .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from))
+                .ok()
+        })
+        .collect()
+}
+
+/// The address of the client behind `peer`. When the peer is a trusted
+/// proxy, that is the last `X-Forwarded-For` hop not added by a trusted
+/// proxy; otherwise it is the peer itself, whatever the header claims.
+pub fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted_proxies: &[IpNet]) -> IpAddr {
+    let trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+    let mut client = peer;
+    if !trusted(&peer) {
+        return client;
+    }
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !trusted(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+/// Identifies who a request is rate limited as: the API key it
+/// authenticated with, otherwise the client's IP address.
+fn client_id(request: &Request<Body>, trusted_proxies: &[IpNet]) -> String {
+    if let Some(AuthenticatedKey(identity)) = request.extensions().get::<AuthenticatedKey>() {
+        return format!("key:{}", identity.name);
+    }
+    client_addr(request, trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The client address of a request (see [`client_ip`]), if the server
+/// recorded the peer address.
+fn client_addr(request: &Request<Body>, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    let ConnectInfo(peer) = request.extensions().get::<ConnectInfo<SocketAddr>>()?;
+    let forwarded_for = request.headers().get("x-forwarded-for").and_then(|v| v.to_str().ok());
+    Some(client_ip(peer.ip(), forwarded_for, trusted_proxies))
 }
 
 /// Rate limiting middleware.
@@ -164,15 +521,9 @@ pub async fn rate_limit_middleware(
     request: Request<Body>,
     next: Next,
 ) -> Response {
-    // Get client identifier (IP or header)
-    let client_id = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or("unknown").trim().to_string())
-        .unwrap_or_else(|| "unknown".to_string());
+    let client_id = client_id(&request, &state.trusted_proxies);
 
-    if state.limiter.check(&client_id) {
+    if state.limiter.check(&client_id).await {
         next.run(request).await
     } else {
         warn!("Rate limit exceeded for client: {}", client_id);
@@ -187,6 +538,105 @@ pub async fn rate_limit_middleware(
     }
 }
 
+// ============================================================================
+// Network Access
+// ============================================================================
+
+/// Address allow/deny lists and per-address connection limits.
+#[derive(Clone)]
+pub struct NetworkGuard {
+    /// Allowed networks; `None` allows everyone.
+    allow: Option<Arc<Vec<IpNet>>>,
+    deny: Arc<Vec<IpNet>>,
+    trusted_proxies: Arc<Vec<IpNet>>,
+    max_connections_per_ip: usize,
+    /// Open requests per client address.
+    connections: Arc<DashMap<IpAddr, usize>>,
+}
+
+/// Counts an open request until dropped.
+struct ConnectionSlot {
+    ip: IpAddr,
+    connections: Arc<DashMap<IpAddr, usize>>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.connections.remove_if_mut(&self.ip, |_, open| {
+            *open -= 1;
+            *open == 0
+        });
+    }
+}
+
+impl NetworkGuard {
+    /// Creates the guard for a configuration.
+    pub fn new(config: &Config) -> Self {
+        let network = &config.network;
+        Self {
+            // A list of only invalid entries still admits no one
+            allow: (!network.allow.is_empty()).then(|| Arc::new(parse_networks(&network.allow))),
+            deny: Arc::new(parse_networks(&network.deny)),
+            trusted_proxies: Arc::new(parse_networks(&config.rate_limit.trusted_proxies)),
+            max_connections_per_ip: network.max_connections_per_ip,
+            connections: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Whether the lists let an address in.
+    pub fn admits(&self, ip: &IpAddr) -> bool {
+        let allowed = self.allow.as_ref().is_none_or(|allow| allow.iter().any(|net| net.contains(ip)));
+        allowed && !self.deny.iter().any(|net| net.contains(ip))
+    }
+
+    /// Takes a connection slot for an address, if it has one free.
+    fn open(&self, ip: IpAddr) -> Option<ConnectionSlot> {
+        let mut open = self.connections.entry(ip).or_insert(0);
+        if self.max_connections_per_ip > 0 && *open >= self.max_connections_per_ip {
+            return None;
+        }
+        *open += 1;
+        Some(ConnectionSlot { ip, connections: self.connections.clone() })
+    }
+}
+
+/// Refuses requests from addresses outside the allow list or on the deny
+/// list (403), and from addresses with too many open requests (429).
+pub async fn network_guard_middleware(
+    State(guard): State<NetworkGuard>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(ip) = client_addr(&request, &guard.trusted_proxies) else {
+        return next.run(request).await;
+    };
+
+    if !guard.admits(&ip) {
+        warn!("Refused request from {}", ip);
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Forbidden" }))).into_response();
+    }
+
+    let Some(slot) = guard.open(ip) else {
+        warn!("Too many open connections from {}", ip);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": "Too many open connections",
+                "max_connections_per_ip": guard.max_connections_per_ip
+            })),
+        )
+            .into_response();
+    };
+
+    // Hold the slot until the body is sent, so SSE streams count while open
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _slot = &slot;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
 // ============================================================================
 // Request Logging Middleware
 // ============================================================================
@@ -265,3 +715,97 @@ impl Metrics {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::{ApiKeyConfig, KeyRateLimit};
+
+    fn key(name: &str, hash: &str) -> ApiKeyConfig {
+        ApiKeyConfig {
+            name: name.to_string(),
+            key_hash: hash.to_string(),
+            scopes: vec!["memory.*".to_string()],
+            rate_limit: None,
+            quota: None,
+        }
+    }
+
+    #[test]
+    fn test_named_and_legacy_keys() {
+        let auth = AuthConfig {
+            api_keys: vec!["0123456789abcdef0123".to_string()],
+            keys: vec![key("reader", "aaaa")],
+            ..AuthConfig::default()
+        };
+        let keys = ApiKeys::from_config(&auth);
+        assert_eq!(keys.len(), 2);
+
+        let reader = keys.authenticate("aaaa").unwrap();
+        assert_eq!(reader.name, "reader");
+        assert!(reader.allows_tool("memory.store"));
+        assert!(!reader.allows_tool("cmd.exec"));
+
+        let legacy = keys.authenticate("0123456789abcdef0123").unwrap();
+        assert_eq!(legacy.name, "0123456789abcdef");
+        assert!(legacy.allows_tool("cmd.exec"));
+
+        assert!(keys.authenticate("bbbb").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_key_rate_limit_and_quota() {
+        let auth = AuthConfig {
+            keys: vec![
+                ApiKeyConfig {
+                    rate_limit: Some(KeyRateLimit { requests_per_second: 1, burst_size: 2 }),
+                    ..key("bursty", "aaaa")
+                },
+                ApiKeyConfig {
+                    quota: Some(KeyQuota { max_requests: 3, window_secs: 3600 }),
+                    ..key("metered", "bbbb")
+                },
+            ],
+            ..AuthConfig::default()
+        };
+        let keys = ApiKeys::from_config(&auth);
+
+        assert!(keys.admit("aaaa").await.is_ok());
+        assert!(keys.admit("aaaa").await.is_ok());
+        assert_eq!(keys.admit("aaaa").await, Err(KeyRejection::RateLimited));
+
+        for _ in 0..3 {
+            assert!(keys.admit("bbbb").await.is_ok());
+        }
+        match keys.admit("bbbb").await {
+            Err(KeyRejection::QuotaExceeded { retry_after_secs }) => assert!(retry_after_secs > 3500),
+            other => panic!("expected quota rejection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_client_ip_trusts_only_configured_proxies() {
+        let proxies = parse_networks(&["10.0.0.0/8".to_string(), "192.168.1.1".to_string(), "junk".to_string()]);
+        assert_eq!(proxies.len(), 2);
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        // Untrusted peers can't pick their own address
+        assert_eq!(client_ip(ip("203.0.113.9"), Some("1.2.3.4"), &proxies), ip("203.0.113.9"));
+        // Through trusted proxies, the last untrusted hop is the client
+        assert_eq!(
+            client_ip(ip("10.0.0.2"), Some("1.2.3.4, 198.51.100.7, 192.168.1.1"), &proxies),
+            ip("198.51.100.7")
+        );
+        assert_eq!(client_ip(ip("10.0.0.2"), None, &proxies), ip("10.0.0.2"));
+        assert_eq!(client_ip(ip("10.0.0.2"), Some("garbage"), &proxies), ip("10.0.0.2"));
+
+        let mut request = Request::new(Body::empty());
+        request.extensions_mut().insert(ConnectInfo("10.0.0.2:4000".parse::<SocketAddr>().unwrap()));
+        request.headers_mut().insert("x-forwarded-for", "198.51.100.7".parse().unwrap());
+        assert_eq!(client_id(&request, &proxies), "198.51.100.7");
+        let identity = ApiKeys::from_config(&AuthConfig { keys: vec![key("ci", "aaaa")], ..AuthConfig::default() })
+            .authenticate("aaaa")
+            .unwrap();
+        request.extensions_mut().insert(AuthenticatedKey(identity));
+        assert_eq!(client_id(&request, &proxies), "key:ci");
+    }
+}
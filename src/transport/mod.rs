@@ -4,9 +4,12 @@
 //! - Transport trait defining the interface for message exchange
 //! - Stdio transport for CLI/pipe-based communication
 //! - SSE transport for HTTP-based communication
+//! - Streamable HTTP transport (MCP 2025-03-26) on the same endpoint
 //! - Middleware for auth, rate limiting, and observability
+//! - Inbound webhooks at `/hooks/{name}`
 
 /// Transport trait definition.
+#[allow(clippy::module_inception)]
 mod transport;
 
 /// Stdio transport implementation.
@@ -15,11 +18,17 @@ pub mod stdio;
 /// SSE (Server-Sent Events) transport via Axum.
 pub mod sse;
 
+/// Streamable HTTP transport, negotiated by protocol version.
+pub mod streamable_http;
+
 /// HTTP middleware (auth, rate limiting, metrics).
 pub mod middleware;
 
+/// Inbound webhooks that trigger tools and workflows.
+pub mod webhooks;
+
 // Re-exports
 pub use transport::Transport;
-pub use stdio::StdioTransport;
-pub use middleware::{AuthState, RateLimiter, RateLimitState, Metrics};
+pub use stdio::{Incoming, StdioTransport};
+pub use middleware::{ApiKeys, AuthState, NetworkGuard, RateLimiter, RateLimitState, Metrics};
 
@@ -4,26 +4,34 @@
 //! Server-Sent Events for streaming responses to clients.
 
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, Query, State},
+    http::{HeaderMap, StatusCode},
     middleware as axum_mw,
-    response::Sse,
+    response::{IntoResponse, Response as HttpResponse, Sse},
     routing::{get, post},
     Json, Router,
 };
-use futures::stream;
+use dashmap::DashMap;
+use futures::{stream, StreamExt};
 use serde_json::Value;
 use std::convert::Infallible;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::{debug, error, info};
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::{debug, error, info, warn};
 
-use crate::core::{Config, AegisError, AegisResult, RuntimeState};
+use crate::core::shutdown;
+use crate::core::{Config, AegisError, AegisResult, KeyIdentity, RequestContext, RuntimeState};
 use crate::dashboard::dashboard_routes;
 use crate::handlers::Router as McpRouter;
 use crate::protocol::{Request, Response, RequestId, ErrorObject};
+use crate::tools::client::{self, ClientPeer};
+use crate::transport::streamable_http;
+use crate::transport::webhooks::webhook_routes;
 use crate::transport::middleware::{
-    AuthState, RateLimiter, RateLimitState, Metrics,
-    auth_middleware, rate_limit_middleware, logging_middleware,
+    AuthState, AuthenticatedKey, NetworkGuard, RateLimitState, Metrics,
+    auth_middleware, network_guard_middleware, rate_limit_middleware, logging_middleware,
 };
 
 /// Shared state for the SSE server.
@@ -35,6 +43,17 @@ pub struct SseState {
     pub router: Arc<McpRouter>,
     /// Metrics collector.
     pub metrics: Metrics,
+    /// Client connections by session, for requests to the client (sampling).
+    pub peers: Arc<DashMap<String, Arc<ClientPeer>>>,
+    /// Sessions of the streamable HTTP transport, by session ID.
+    pub sessions: Arc<DashMap<String, Arc<streamable_http::Session>>>,
+}
+
+impl SseState {
+    /// The client connection for a session, created on first use.
+    fn peer(&self, session: &str) -> Arc<ClientPeer> {
+        self.peers.entry(session.to_string()).or_default().clone()
+    }
 }
 
 /// Creates the Axum router for SSE transport.
@@ -45,26 +64,27 @@ pub fn create_router(state: SseState, config: &Config) -> Router {
         .allow_headers(Any);
 
     // Create auth state
-    let auth_state = AuthState {
-        config: Arc::new(config.clone()),
-    };
+    let auth_state = AuthState::new(config);
 
     // Create rate limiter state
-    let rate_limit_state = RateLimitState {
-        limiter: RateLimiter::new(config),
-    };
+    let rate_limit_state = RateLimitState::new(config);
 
     // Build dashboard routes separately (has its own state)
     let dashboard = dashboard_routes(state.runtime.clone());
-    
+    let webhooks = webhook_routes(state.runtime.clone());
+
     // Build main router with middleware layers
     let mut router = Router::new()
         .route("/health", get(health_handler))
-        .route("/mcp", post(mcp_handler))
+        .route("/mcp", post(mcp_handler).delete(delete_handler))
         .route("/sse", get(sse_handler))
         .route("/metrics", get(metrics_handler))
         .with_state(state)
-        .nest("/dashboard", dashboard);
+        .nest("/dashboard", dashboard)
+        .nest("/hooks", webhooks)
+        // Oversized bodies get 413 before any handler reads them
+        .layer(DefaultBodyLimit::max(config.network.max_body_bytes))
+        .layer(RequestBodyLimitLayer::new(config.network.max_body_bytes));
 
     // Add rate limiting (if enabled)
     if config.rate_limit.enabled {
@@ -82,6 +102,15 @@ pub fn create_router(state: SseState, config: &Config) -> Router {
         ));
     }
 
+    // Refuse unwanted addresses before anything else runs
+    let network = &config.network;
+    if !network.allow.is_empty() || !network.deny.is_empty() || network.max_connections_per_ip > 0 {
+        router = router.layer(axum_mw::from_fn_with_state(
+            NetworkGuard::new(config),
+            network_guard_middleware,
+        ));
+    }
+
     // Add logging and CORS
     router = router
         .layer(axum_mw::from_fn(logging_middleware))
@@ -100,22 +129,56 @@ async fn health_handler() -> Json<Value> {
     }))
 }
 
-/// Metrics endpoint.
+/// Metrics endpoint, including database maintenance totals and secret
+/// expiry counts.
 #[axum::debug_handler]
 async fn metrics_handler(
     State(state): State<SseState>,
 ) -> Json<Value> {
-    Json(state.metrics.snapshot())
+    let mut snapshot = state.metrics.snapshot();
+    snapshot["maintenance"] = state.runtime.scheduler.maintenance().snapshot();
+    let expiring = state.runtime.secrets.expiring(chrono::Utc::now());
+    snapshot["secrets"] = serde_json::json!({
+        "total": state.runtime.secrets.list().len(),
+        "expiring": expiring.iter().filter(|warning| !warning.expired).count(),
+        "expired": expiring.iter().filter(|warning| warning.expired).count()
+    });
+    Json(snapshot)
 }
 
 /// Main MCP endpoint - handles JSON-RPC requests.
+///
+/// Clients of MCP 2025-03-26 and later get the streamable HTTP transport
+/// (see [`streamable_http`]); the rest of this handler is the legacy one.
+/// Notifications (e.g. `notifications/cancelled`, which aborts a running
+/// call of the same session) and the client's responses to requests sent
+/// over the `/sse` stream (e.g. sampling) are answered with 202 Accepted.
 #[axum::debug_handler]
 async fn mcp_handler(
     State(state): State<SseState>,
+    auth: Option<axum::Extension<AuthenticatedKey>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
+) -> HttpResponse {
     debug!("Received MCP request: {:?}", body);
 
+    let identity = auth.map(|axum::Extension(key)| key.0);
+    if streamable_http::is_streamable(&state, &headers, &body) {
+        return streamable_http::handle_post(state, headers, identity, body).await;
+    }
+    let context = request_context(&headers, identity);
+
+    if client::is_response(&body) {
+        let routed = state
+            .peers
+            .get(&context.session_id)
+            .is_some_and(|peer| peer.handle_response(&body));
+        if !routed {
+            warn!("Ignoring response to unknown request: {}", body["id"]);
+        }
+        return StatusCode::ACCEPTED.into_response();
+    }
+
     // Parse the request
     let request: Request = match serde_json::from_value(body) {
         Ok(req) => req,
@@ -125,7 +188,7 @@ async fn mcp_handler(
                 RequestId::Null,
                 ErrorObject::parse_error(e.to_string()),
             );
-            return Json(serde_json::to_value(error_response).unwrap_or_default());
+            return Json(serde_json::to_value(error_response).unwrap_or_default()).into_response();
         }
     };
 
@@ -133,20 +196,121 @@ async fn mcp_handler(
     if let Err(e) = request.validate() {
         error!("Invalid request: {}", e);
         let error_response = Response::from_error(request.id.clone(), &e);
-        return Json(serde_json::to_value(error_response).unwrap_or_default());
+        return Json(serde_json::to_value(error_response).unwrap_or_default()).into_response();
     }
 
     // Route and handle the request
-    let response = state.router.handle(request, state.runtime.clone()).await;
-    Json(serde_json::to_value(response).unwrap_or_default())
+    let notification = request.is_notification();
+    let peer = state.peer(&context.session_id);
+    let response = client::with_peer(
+        peer,
+        state.router.handle_with_context(request, state.runtime.clone(), context),
+    )
+    .await;
+    if notification {
+        return StatusCode::ACCEPTED.into_response();
+    }
+    Json(serde_json::to_value(response).unwrap_or_default()).into_response()
+}
+
+/// Ends a streamable HTTP session.
+async fn delete_handler(
+    State(state): State<SseState>,
+    auth: Option<axum::Extension<AuthenticatedKey>>,
+    headers: HeaderMap,
+) -> HttpResponse {
+    let identity = auth.map(|axum::Extension(key)| key.0);
+    streamable_http::handle_delete(state, headers, identity).await
 }
 
-/// SSE endpoint for streaming (placeholder for future implementation).
+/// Header clients may use to identify their session.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Builds the caller context for an HTTP request.
+///
+/// The session is the `Mcp-Session-Id` header if present, otherwise the
+/// authenticated API key's name, otherwise the default session.
+fn request_context(headers: &HeaderMap, identity: Option<Arc<KeyIdentity>>) -> RequestContext {
+    let session = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| identity.as_ref().map(|identity| format!("key:{}", identity.name)));
+
+    match session {
+        Some(session) => RequestContext::new(session),
+        None => RequestContext::default(),
+    }
+    .with_identity(identity)
+}
+
+/// SSE endpoint carrying requests from the server to the client (e.g.
+/// `sampling/createMessage`) and `notifications/tools/list_changed` as
+/// `message` events, plus a ping every 30s.
+/// On shutdown the stream ends with a `shutdown` event.
+///
+/// The stream belongs to the session given by the `Mcp-Session-Id` header
+/// or `session` query parameter (browsers' EventSource can't set headers);
+/// the client posts its responses to `/mcp` with the same session.
 async fn sse_handler(
-    State(_state): State<SseState>,
+    State(state): State<SseState>,
+    auth: Option<axum::Extension<AuthenticatedKey>>,
+    Query(query): Query<std::collections::HashMap<String, String>>,
+    mut headers: HeaderMap,
 ) -> Sse<impl futures::Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
-    // For MVP, we return a simple stream that sends a ping every 30 seconds
-    let stream = stream::unfold(0u64, |counter| async move {
+    if let Some(session) = query.get("session").and_then(|s| s.parse().ok()) {
+        headers.entry(SESSION_HEADER).or_insert(session);
+    }
+    let context = request_context(&headers, auth.map(|axum::Extension(key)| key.0));
+    let outbound = state.peer(&context.session_id).attach();
+    debug!("Client stream opened for session {}", context.session_id);
+
+    let messages = stream::unfold(outbound, |mut outbound| async move {
+        let message = outbound.recv().await?;
+        let event = axum::response::sse::Event::default()
+            .event("message")
+            .data(message.to_string());
+        Some((Ok::<_, Infallible>(event), outbound))
+    });
+
+    let changes = stream::unfold(state.runtime.subscribe_tools_changed(), |mut changes| async move {
+        changes.changed().await.ok()?;
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/tools/list_changed",
+            "params": {}
+        });
+        let event = axum::response::sse::Event::default()
+            .event("message")
+            .data(notification.to_string());
+        Some((Ok::<_, Infallible>(event), changes))
+    });
+
+    // Changes to resources this session subscribed to
+    let subscribed = (state.runtime.resource_subscriptions.updates(), state.runtime.clone(), context.session_id.clone());
+    let updates = stream::unfold(subscribed, |(mut updates, runtime, session_id)| async move {
+        loop {
+            let uri = match updates.recv().await {
+                Ok(uri) => uri,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            if !runtime.resource_subscriptions.is_subscribed(&session_id, &uri) {
+                continue;
+            }
+            let notification = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/resources/updated",
+                "params": { "uri": uri }
+            });
+            let event = axum::response::sse::Event::default()
+                .event("message")
+                .data(notification.to_string());
+            return Some((Ok::<_, Infallible>(event), (updates, runtime, session_id)));
+        }
+    });
+
+    let pings = stream::unfold(0u64, |counter| async move {
         tokio::time::sleep(std::time::Duration::from_secs(30)).await;
         let event = axum::response::sse::Event::default()
             .event("ping")
@@ -154,13 +318,27 @@ async fn sse_handler(
         Some((Ok::<_, Infallible>(event), counter + 1))
     });
 
-    Sse::new(stream)
+    // End the stream on shutdown so the server can drain, telling the client why
+    let closing = state.runtime.shutdown.token();
+    let farewell = stream::once(async {
+        let event = axum::response::sse::Event::default()
+            .event("shutdown")
+            .data("{\"reason\": \"server shutting down\"}");
+        Ok::<_, Infallible>(event)
+    });
+
+    Sse::new(
+        stream::select(stream::select(messages, stream::select(changes, updates)), pings)
+            .take_until(closing.cancelled_owned())
+            .chain(farewell),
+    )
 }
 
 /// Starts the SSE server.
 pub async fn start_server(state: SseState, config: &Config, addr: std::net::SocketAddr) -> AegisResult<()> {
     info!("Starting SSE server on http://{}", addr);
 
+    let state_runtime = state.runtime.clone();
     let router = create_router(state, config);
 
     let listener = tokio::net::TcpListener::bind(addr)
@@ -169,7 +347,7 @@ pub async fn start_server(state: SseState, config: &Config, addr: std::net::Sock
 
     // Log security status
     if config.auth.enabled {
-        info!("🔐 Authentication enabled ({} API keys configured)", config.auth.api_keys.len());
+        info!("🔐 Authentication enabled ({} API keys configured)", config.auth.api_keys.len() + config.auth.keys.len());
     } else {
         info!("⚠️  Authentication disabled - server is open");
     }
@@ -182,10 +360,106 @@ pub async fn start_server(state: SseState, config: &Config, addr: std::net::Sock
 
     info!("🟢 Aegis SSE server listening on http://{}", addr);
     info!("📊 Dashboard available at http://{}/dashboard", addr);
+    for hook in &config.webhooks {
+        info!("🪝 Webhook {} at http://{}/hooks/{}", hook.name, addr, hook.name);
+    }
 
-    axum::serve(listener, router)
-        .await
-        .map_err(|e| AegisError::Transport(format!("Server error: {}", e)))?;
+    // Stop accepting connections on a shutdown signal, then give running
+    // requests until the deadline to finish
+    let runtime = state_runtime.clone();
+    tokio::spawn(async move {
+        shutdown::signal().await;
+        runtime.shutdown.trigger();
+    });
+
+    let drained = state_runtime.clone();
+    // Peer addresses identify clients for rate limiting
+    let service = router.into_make_service_with_connect_info::<std::net::SocketAddr>();
+    let server = axum::serve(listener, service).with_graceful_shutdown(async move {
+        drained.shutdown.triggered().await;
+    });
+
+    let deadline = std::time::Duration::from_secs(config.shutdown_timeout_secs);
+    tokio::select! {
+        result = server => {
+            result.map_err(|e| AegisError::Transport(format!("Server error: {}", e)))?;
+        }
+        _ = async {
+            state_runtime.shutdown.triggered().await;
+            info!(
+                "Waiting up to {}s for {} running tool call(s)",
+                deadline.as_secs(),
+                state_runtime.shutdown.in_flight()
+            );
+            tokio::time::sleep(deadline).await;
+        } => {
+            warn!(
+                "{} tool call(s) still running after {}s, shutting down anyway",
+                state_runtime.shutdown.in_flight(),
+                deadline.as_secs()
+            );
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Metrics;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::Request as HttpRequest;
+    use tower::Service;
+
+    fn request(from: &str, method: &str, path: &str, body: &str) -> HttpRequest<Body> {
+        let mut request = HttpRequest::builder()
+            .method(method)
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(format!("{}:5000", from).parse::<std::net::SocketAddr>().unwrap()));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_network_limits() {
+        let mut config = Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        };
+        config.network.deny = vec!["10.0.0.0/8".to_string()];
+        config.network.max_body_bytes = 100;
+        config.network.max_connections_per_ip = 1;
+        let state = SseState {
+            runtime: Arc::new(RuntimeState::new(config.clone())),
+            router: Arc::new(McpRouter::new()),
+            metrics: Metrics::new(),
+            peers: Default::default(),
+            sessions: Default::default(),
+        };
+        let router = create_router(state, &config);
+        // Router is always ready, so it can be called directly
+        let send = |request| router.clone().call(request);
+
+        let denied = send(request("10.1.2.3", "GET", "/health", "")).await.unwrap();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let too_big = send(request("192.0.2.1", "POST", "/mcp", &"x".repeat(1000))).await.unwrap();
+        assert_eq!(too_big.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        drop(too_big);
+
+        // The first response holds the address's only slot until its body is dropped
+        let open = send(request("192.0.2.1", "GET", "/health", "")).await.unwrap();
+        assert_eq!(open.status(), StatusCode::OK);
+        let busy = send(request("192.0.2.1", "GET", "/health", "")).await.unwrap();
+        assert_eq!(busy.status(), StatusCode::TOO_MANY_REQUESTS);
+        let other = send(request("192.0.2.2", "GET", "/health", "")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+        drop(open);
+        let again = send(request("192.0.2.1", "GET", "/health", "")).await.unwrap();
+        assert_eq!(again.status(), StatusCode::OK);
+    }
+}
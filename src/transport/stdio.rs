@@ -32,6 +32,86 @@ impl StdioTransport {
             buffer: String::with_capacity(4096),
         }
     }
+
+    /// Writes a JSON-RPC notification (e.g. progress) to stdout.
+    pub async fn write_notification(&mut self, method: &str, params: serde_json::Value) -> NexusResult<()> {
+        self.write_message(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        }))
+        .await
+    }
+
+    /// Writes any JSON-RPC message (e.g. a request to the client) to stdout.
+    pub async fn write_message(&mut self, message: &serde_json::Value) -> NexusResult<()> {
+        let json = serde_json::to_string(message)?;
+
+        trace!("Sending message: {}", json);
+
+        self.writer.write_all(json.as_bytes()).await?;
+        self.writer.write_all(b"\n").await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    /// Reads the next message, which may also be the client's response to
+    /// a server-initiated request (e.g. sampling).
+    ///
+    /// Returns `None` on EOF.
+    pub async fn read_message(&mut self) -> NexusResult<Option<Incoming>> {
+        let Some(line) = self.read_line().await? else {
+            return Ok(None);
+        };
+
+        let message: serde_json::Value = serde_json::from_str(&line).map_err(NexusError::JsonParse)?;
+        if crate::tools::client::is_response(&message) {
+            debug!("Received client response: id={}", message["id"]);
+            return Ok(Some(Incoming::Response(message)));
+        }
+
+        let request: Request = serde_json::from_value(message).map_err(NexusError::JsonParse)?;
+        request.validate()?;
+
+        debug!("Parsed request: method={}, id={:?}", request.method, request.id);
+
+        Ok(Some(Incoming::Request(request)))
+    }
+
+    /// Reads the next non-empty line, or `None` on EOF.
+    async fn read_line(&mut self) -> NexusResult<Option<String>> {
+        loop {
+            self.buffer.clear();
+
+            // Read a line from stdin
+            let bytes_read = self.reader.read_line(&mut self.buffer).await?;
+
+            // EOF check
+            if bytes_read == 0 {
+                debug!("EOF reached on stdin");
+                return Ok(None);
+            }
+
+            // Skip empty lines
+            let line = self.buffer.trim();
+            if line.is_empty() {
+                trace!("Skipping empty line");
+                continue;
+            }
+
+            trace!("Received line: {}", line);
+            return Ok(Some(line.to_string()));
+        }
+    }
+}
+
+/// A message read from the client.
+#[derive(Debug)]
+pub enum Incoming {
+    /// A request or notification to handle.
+    Request(Request),
+    /// A response to a request the server sent the client.
+    Response(serde_json::Value),
 }
 
 impl Default for StdioTransport {
@@ -43,30 +123,12 @@ impl Default for StdioTransport {
 #[async_trait]
 impl Transport for StdioTransport {
     async fn read_request(&mut self) -> NexusResult<Option<Request>> {
-        self.buffer.clear();
-
-        // Read a line from stdin
-        let bytes_read = self.reader.read_line(&mut self.buffer).await?;
-
-        // EOF check
-        if bytes_read == 0 {
-            debug!("EOF reached on stdin");
+        let Some(line) = self.read_line().await? else {
             return Ok(None);
-        }
-
-        // Trim whitespace
-        let line = self.buffer.trim();
-
-        // Skip empty lines
-        if line.is_empty() {
-            trace!("Skipping empty line");
-            return self.read_request().await;
-        }
-
-        trace!("Received line: {}", line);
+        };
 
         // Parse JSON-RPC request
-        let request: Request = serde_json::from_str(line).map_err(|e| {
+        let request: Request = serde_json::from_str(&line).map_err(|e| {
             NexusError::JsonParse(e)
         })?;
 
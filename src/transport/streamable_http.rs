@@ -0,0 +1,517 @@
+//! Streamable HTTP transport (MCP 2025-03-26).
+//!
+//! Everything goes through the single `/mcp` endpoint:
+//! - `initialize` creates a session whose ID is returned in the
+//!   `Mcp-Session-Id` header and must be sent with every later request.
+//!   The session belongs to the API key that created it; requests with
+//!   another key (or none) are answered as if it didn't exist.
+//! - A POST carries one JSON-RPC message or a batch. Responses and
+//!   notifications are answered with 202 Accepted; requests with their
+//!   responses, as JSON or, once a call sends progress or a request to the
+//!   client (e.g. sampling) and the client accepts it, as an SSE stream
+//!   that ends with the responses.
+//! - `DELETE` ends the session.
+//!
+//! Clients that initialize with an older protocol version keep the legacy
+//! transport (`POST /mcp` returning JSON, plus `GET /sse`).
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{sse::Event, IntoResponse, Response as HttpResponse, Sse},
+    Json,
+};
+use futures::{stream, StreamExt};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, info, warn};
+
+use crate::core::{KeyIdentity, RequestContext};
+use crate::protocol::mcp::STREAMABLE_HTTP_VERSION;
+use crate::protocol::{ClientCapabilities, ErrorObject, Request, RequestId, Response};
+use crate::tools::client::{self, ClientPeer};
+use crate::tools::stream::{self as tool_stream, Progress};
+use crate::transport::sse::{SseState, SESSION_HEADER};
+
+/// Header carrying the negotiated protocol version on requests after
+/// `initialize` (required by MCP 2025-06-18, optional before).
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+pub use crate::core::session::SESSION_IDLE_TIMEOUT;
+
+/// A client session created by `initialize`.
+#[derive(Debug)]
+pub struct Session {
+    protocol_version: String,
+    /// Hash of the API key that created the session.
+    key_hash: Option<String>,
+    capabilities: ClientCapabilities,
+    /// Client connections of POSTs still streaming, for routing responses.
+    streams: dashmap::DashMap<u64, Arc<ClientPeer>>,
+    next_stream: AtomicU64,
+    last_seen: Mutex<Instant>,
+}
+
+impl Session {
+    fn new(protocol_version: String, key_hash: Option<String>, capabilities: ClientCapabilities) -> Self {
+        Self {
+            protocol_version,
+            key_hash,
+            capabilities,
+            streams: dashmap::DashMap::new(),
+            next_stream: AtomicU64::new(0),
+            last_seen: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// The protocol version negotiated at `initialize`.
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// Whether a request with `identity` may use the session.
+    fn belongs_to(&self, identity: Option<&KeyIdentity>) -> bool {
+        self.key_hash.as_deref() == identity.map(|key| key.key_hash.as_str())
+    }
+
+    fn is_expired(&self) -> bool {
+        self.last_seen.lock().elapsed() > SESSION_IDLE_TIMEOUT
+    }
+
+    /// Routes a client response to the call waiting for it.
+    fn handle_response(&self, message: &Value) -> bool {
+        self.streams.iter().any(|peer| peer.handle_response(message))
+    }
+}
+
+/// Whether a POST to `/mcp` belongs to the streamable transport: it
+/// continues a streamable session, states a streamable protocol version,
+/// is a batch, or is an `initialize` asking for a streamable version.
+pub fn is_streamable(state: &SseState, headers: &HeaderMap, body: &Value) -> bool {
+    if header_str(headers, SESSION_HEADER).is_some_and(|id| state.sessions.contains_key(id)) {
+        return true;
+    }
+    if header_str(headers, PROTOCOL_VERSION_HEADER).is_some_and(|version| version >= STREAMABLE_HTTP_VERSION) {
+        return true;
+    }
+    if body.is_array() {
+        return true;
+    }
+    body.get("method").and_then(|m| m.as_str()) == Some("initialize")
+        && body
+            .pointer("/params/protocolVersion")
+            .and_then(|v| v.as_str())
+            .is_some_and(|version| version >= STREAMABLE_HTTP_VERSION)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn error_response(status: StatusCode, error: ErrorObject) -> HttpResponse {
+    let body = serde_json::to_value(Response::error(RequestId::Null, error)).unwrap_or_default();
+    (status, Json(body)).into_response()
+}
+
+/// Something to send the client while answering a POST.
+enum Outgoing {
+    /// A notification or request to the client, sent before the responses.
+    Message(Value),
+    /// A response to one of the POST's requests.
+    Response(Value),
+}
+
+/// Handles a POST of the streamable transport.
+pub async fn handle_post(
+    state: SseState,
+    headers: HeaderMap,
+    identity: Option<Arc<KeyIdentity>>,
+    body: Value,
+) -> HttpResponse {
+    let batch = body.is_array();
+    let messages = match body {
+        Value::Array(messages) if messages.is_empty() => {
+            return error_response(StatusCode::BAD_REQUEST, ErrorObject::invalid_request("Empty batch"));
+        }
+        Value::Array(messages) => messages,
+        message => vec![message],
+    };
+
+    let is_initialize = |m: &Value| m.get("method").and_then(|m| m.as_str()) == Some("initialize");
+    if messages.iter().any(is_initialize) {
+        if messages.len() > 1 {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                ErrorObject::invalid_request("initialize must not be part of a batch"),
+            );
+        }
+        return initialize(state, identity, messages.into_iter().next().unwrap_or_default()).await;
+    }
+
+    state.sessions.retain(|id, session| {
+        let expired = session.is_expired();
+        if expired {
+            state.runtime.sessions.remove(id);
+            state.runtime.resource_subscriptions.remove_session(id);
+        }
+        !expired
+    });
+    let Some(session_id) = header_str(&headers, SESSION_HEADER).map(str::to_string) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorObject::invalid_request("Missing Mcp-Session-Id header; send initialize first"),
+        );
+    };
+    let session = state.sessions.get(&session_id).map(|s| s.clone());
+    let Some(session) = session.filter(|session| session.belongs_to(identity.as_deref())) else {
+        return error_response(StatusCode::NOT_FOUND, ErrorObject::invalid_request("Unknown or expired session"));
+    };
+    *session.last_seen.lock() = Instant::now();
+    let context = RequestContext::new(session_id).with_identity(identity);
+
+    // Responses and notifications are handled here; requests get answered
+    let mut requests = Vec::new();
+    for message in messages {
+        if client::is_response(&message) {
+            if !session.handle_response(&message) {
+                warn!("Ignoring response to unknown request: {}", message["id"]);
+            }
+            continue;
+        }
+        let request = match serde_json::from_value::<Request>(message) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, ErrorObject::parse_error(e.to_string()));
+            }
+        };
+        if let Err(e) = request.validate() {
+            return error_response(StatusCode::BAD_REQUEST, ErrorObject::invalid_request(e.to_string()));
+        }
+        if request.is_notification() {
+            state.router.handle_with_context(request, state.runtime.clone(), context.clone()).await;
+        } else {
+            requests.push(request);
+        }
+    }
+    if requests.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    // Only a client that reads SSE can get progress and requests of its own
+    let streaming = header_str(&headers, header::ACCEPT.as_str()).is_some_and(|a| a.contains("text/event-stream"));
+    let peer = Arc::new(ClientPeer::new());
+    peer.set_capabilities(session.capabilities.clone());
+    let outbound = streaming.then(|| peer.attach());
+    let stream_id = session.next_stream.fetch_add(1, Ordering::Relaxed);
+    session.streams.insert(stream_id, peer.clone());
+
+    let (events, mut received) = mpsc::unbounded_channel();
+    let driver_session = session.clone();
+    tokio::spawn(async move {
+        run_requests(&state, context, requests, peer.clone(), outbound, streaming, &events).await;
+        peer.close();
+        driver_session.streams.remove(&stream_id);
+    });
+
+    // Answer with plain JSON unless something has to go out before the responses
+    let mut responses = Vec::new();
+    loop {
+        match received.recv().await {
+            Some(Outgoing::Response(response)) => responses.push(response),
+            Some(Outgoing::Message(message)) => {
+                debug!("Upgrading response to an SSE stream");
+                let sent = stream::iter(responses.into_iter().chain(std::iter::once(message)));
+                let rest = stream::unfold(received, |mut received| async move {
+                    let message = match received.recv().await? {
+                        Outgoing::Message(message) | Outgoing::Response(message) => message,
+                    };
+                    Some((message, received))
+                });
+                let events = sent
+                    .chain(rest)
+                    .map(|message| Ok::<_, Infallible>(Event::default().event("message").data(message.to_string())));
+                return Sse::new(events).into_response();
+            }
+            None => break,
+        }
+    }
+
+    if batch {
+        Json(Value::Array(responses)).into_response()
+    } else {
+        Json(responses.into_iter().next().unwrap_or_default()).into_response()
+    }
+}
+
+/// Runs a POST's requests in order, sending progress, requests to the
+/// client and finally each response to `events`.
+async fn run_requests(
+    state: &SseState,
+    context: RequestContext,
+    requests: Vec<Request>,
+    peer: Arc<ClientPeer>,
+    mut outbound: Option<UnboundedReceiver<Value>>,
+    streaming: bool,
+    events: &UnboundedSender<Outgoing>,
+) {
+    for request in requests {
+        let progress_token = request
+            .params
+            .as_ref()
+            .and_then(|p| p.pointer("/_meta/progressToken"))
+            .cloned()
+            .filter(|_| streaming);
+
+        let (tx, mut chunks) = mpsc::unbounded_channel();
+        let call = client::with_peer(
+            peer.clone(),
+            state.router.handle_with_context(request, state.runtime.clone(), context.clone()),
+        );
+        let handle = async {
+            if progress_token.is_some() {
+                tool_stream::with_sink(tx, tool_stream::SinkFilter::from_state(&state.runtime), call).await
+            } else {
+                call.await
+            }
+        };
+        tokio::pin!(handle);
+
+        let mut progress = Progress::new(progress_token.clone().unwrap_or_default());
+        let mut progress_message = |update| {
+            Outgoing::Message(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": progress.params(update)
+            }))
+        };
+        let response = loop {
+            tokio::select! {
+                response = &mut handle => {
+                    while let Ok(chunk) = chunks.try_recv() {
+                        let _ = events.send(progress_message(chunk));
+                    }
+                    break response;
+                }
+                Some(chunk) = chunks.recv() => {
+                    let _ = events.send(progress_message(chunk));
+                }
+                Some(message) = async { outbound.as_mut()?.recv().await } => {
+                    let _ = events.send(Outgoing::Message(message));
+                }
+            }
+        };
+        let _ = events.send(Outgoing::Response(serde_json::to_value(response).unwrap_or_default()));
+    }
+}
+
+/// Handles `initialize`, creating a session if it succeeds.
+async fn initialize(state: SseState, identity: Option<Arc<KeyIdentity>>, message: Value) -> HttpResponse {
+    let request = match serde_json::from_value::<Request>(message) {
+        Ok(request) => request,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ErrorObject::parse_error(e.to_string())),
+    };
+
+    let session_id = uuid::Uuid::new_v4().simple().to_string();
+    let key_hash = identity.as_ref().map(|key| key.key_hash.clone());
+    let context = RequestContext::new(session_id.clone()).with_identity(identity);
+    let peer = Arc::new(ClientPeer::new());
+    let response = client::with_peer(
+        peer.clone(),
+        state.router.handle_with_context(request, state.runtime.clone(), context),
+    )
+    .await;
+
+    let Some(result) = &response.result else {
+        return Json(serde_json::to_value(response).unwrap_or_default()).into_response();
+    };
+    let protocol_version = result
+        .get("protocolVersion")
+        .and_then(|v| v.as_str())
+        .unwrap_or(STREAMABLE_HTTP_VERSION)
+        .to_string();
+    info!("Streamable HTTP session {} started (protocol {})", session_id, protocol_version);
+    state
+        .sessions
+        .insert(session_id.clone(), Arc::new(Session::new(protocol_version, key_hash, peer.capabilities())));
+
+    let mut http = Json(serde_json::to_value(response).unwrap_or_default()).into_response();
+    if let Ok(value) = HeaderValue::from_str(&session_id) {
+        http.headers_mut().insert(SESSION_HEADER, value);
+    }
+    http
+}
+
+/// Ends a session (`DELETE /mcp`), failing requests still waiting on the client.
+pub async fn handle_delete(state: SseState, headers: HeaderMap, identity: Option<Arc<KeyIdentity>>) -> HttpResponse {
+    let Some(session_id) = header_str(&headers, SESSION_HEADER) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    match state.sessions.remove_if(session_id, |_, session| session.belongs_to(identity.as_deref())) {
+        Some((_, session)) => {
+            for peer in session.streams.iter() {
+                peer.close();
+            }
+            state.runtime.resource_subscriptions.remove_session(session_id);
+            state.runtime.sessions.remove(session_id);
+            info!("Streamable HTTP session {} ended by client", session_id);
+            StatusCode::OK.into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, RuntimeState};
+    use crate::handlers::Router as McpRouter;
+    use crate::protocol::mcp::Tool as ToolDefinition;
+    use crate::tools::registry::{Tool, ToolError, ToolOutput};
+    use crate::transport::Metrics;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// Streams two chunks before returning.
+    #[derive(Debug)]
+    struct ChunkTool;
+
+    #[async_trait]
+    impl Tool for ChunkTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "test.chunks".to_string(),
+                description: None,
+                input_schema: json!({"type": "object"}),
+                output_schema: None,
+            }
+        }
+
+        async fn execute(&self, _arguments: Value, _state: Arc<RuntimeState>) -> Result<ToolOutput, ToolError> {
+            tool_stream::emit(json!("one"));
+            tool_stream::emit(json!("two"));
+            Ok(ToolOutput::text("one two"))
+        }
+    }
+
+    fn sse_state() -> SseState {
+        let runtime = Arc::new(RuntimeState::new(Config {
+            database_path: Some(":memory:".to_string()),
+            ..Config::default()
+        }));
+        runtime.tool_registry.write().register(Arc::new(ChunkTool));
+        SseState {
+            runtime,
+            router: Arc::new(McpRouter::new()),
+            metrics: Metrics::new(),
+            peers: Default::default(),
+            sessions: Default::default(),
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    async fn body_text(response: HttpResponse) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    async fn start_session(state: &SseState, identity: Option<Arc<KeyIdentity>>) -> String {
+        let initialize = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "t", "version": "1"}}
+        });
+        assert!(is_streamable(state, &HeaderMap::new(), &initialize));
+        let response = handle_post(state.clone(), HeaderMap::new(), identity, initialize).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let session_id = response.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+        let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["result"]["protocolVersion"], "2025-03-26");
+        session_id
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let state = sse_state();
+        let legacy = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05"}});
+        assert!(!is_streamable(&state, &HeaderMap::new(), &legacy));
+
+        let session_id = start_session(&state, None).await;
+        let session = headers(&[(SESSION_HEADER, &session_id)]);
+        assert!(is_streamable(&state, &session, &json!({})));
+
+        let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+        let missing = handle_post(state.clone(), HeaderMap::new(), None, list.clone()).await;
+        assert_eq!(missing.status(), StatusCode::BAD_REQUEST);
+        let unknown = handle_post(state.clone(), headers(&[(SESSION_HEADER, "nope")]), None, list.clone()).await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let accepted = handle_post(state.clone(), session.clone(), None, notification).await;
+        assert_eq!(accepted.status(), StatusCode::ACCEPTED);
+
+        let batch = json!([list, {"jsonrpc": "2.0", "id": 3, "method": "ping"}]);
+        let response = handle_post(state.clone(), session.clone(), None, batch).await;
+        let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body[0]["id"], 2);
+        assert!(body[0]["result"]["tools"].is_array());
+        assert_eq!(body[1]["id"], 3);
+
+        assert_eq!(handle_delete(state.clone(), session.clone(), None).await.status(), StatusCode::OK);
+        assert_eq!(handle_delete(state.clone(), session, None).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_session_belongs_to_its_key() {
+        let state = sse_state();
+        let key = |hash: &str| {
+            Some(Arc::new(KeyIdentity { name: hash.to_string(), key_hash: hash.to_string(), scopes: vec!["*".to_string()] }))
+        };
+        let session_id = start_session(&state, key("alice")).await;
+        let session = headers(&[(SESSION_HEADER, &session_id)]);
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+
+        for other in [key("mallory"), None] {
+            let response = handle_post(state.clone(), session.clone(), other.clone(), ping.clone()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            assert_eq!(handle_delete(state.clone(), session.clone(), other).await.status(), StatusCode::NOT_FOUND);
+        }
+        let response = handle_post(state.clone(), session.clone(), key("alice"), ping).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(handle_delete(state.clone(), session, key("alice")).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_progress_upgrades_to_sse() {
+        let state = sse_state();
+        let session_id = start_session(&state, None).await;
+        let call = json!({
+            "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+            "params": {"name": "test.chunks", "arguments": {}, "_meta": {"progressToken": "p1"}}
+        });
+
+        // Without SSE in Accept the client gets plain JSON
+        let plain = handle_post(state.clone(), headers(&[(SESSION_HEADER, &session_id)]), None, call.clone()).await;
+        let body: Value = serde_json::from_str(&body_text(plain).await).unwrap();
+        assert_eq!(body["result"]["content"][0]["text"], "one two");
+
+        let accept = headers(&[(SESSION_HEADER, &session_id), ("accept", "application/json, text/event-stream")]);
+        let streamed = handle_post(state.clone(), accept, None, call).await;
+        assert_eq!(streamed.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let body = body_text(streamed).await;
+        let progress = body.find("notifications/progress").unwrap();
+        let result = body.find("\"result\"").unwrap();
+        assert!(progress < result);
+        assert_eq!(body.matches("event: message").count(), 3);
+    }
+}
@@ -0,0 +1,298 @@
+//! Inbound webhooks.
+//!
+//! `POST /hooks/{name}` runs the tool or saved workflow configured for the
+//! hook in `webhooks`, with the request payload as context, so GitHub,
+//! Stripe and similar services can trigger Aegis. Requests are verified
+//! with an HMAC signature keyed by a per-hook secret instead of an API key.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::core::config::{SignatureScheme, WebhookConfig};
+use crate::core::{RequestContext, RuntimeState};
+use crate::tools::caller;
+use crate::tools::extras::substitute_context;
+use crate::tools::registry::ToolOutput;
+
+/// How far a Stripe signature timestamp may be from now, in seconds.
+const STRIPE_TOLERANCE_SECS: i64 = 300;
+
+/// Headers never passed on to the hook.
+const PRIVATE_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Webhook routes, nested under `/hooks`.
+pub fn webhook_routes(state: Arc<RuntimeState>) -> Router {
+    Router::new()
+        .route("/:name", post(webhook_handler))
+        .with_state(state)
+}
+
+async fn webhook_handler(
+    State(state): State<Arc<RuntimeState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(hook) = state.config.webhooks.iter().find(|hook| hook.name == name).cloned() else {
+        return error(StatusCode::NOT_FOUND, format!("Unknown webhook: {}", name));
+    };
+
+    if let Some(secret) = &hook.secret {
+        let Some(key) = state.secrets.get(secret) else {
+            warn!("Webhook {} rejected: secret {} is not set", name, secret);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Webhook secret is not configured".to_string());
+        };
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = verify_signature(&hook, &headers, &body, key.as_bytes(), now) {
+            warn!("Webhook {} rejected: {}", name, e);
+            return error(StatusCode::UNAUTHORIZED, e);
+        }
+    } else if !hook.allow_unsigned {
+        return error(StatusCode::UNAUTHORIZED, "Webhook has no secret".to_string());
+    }
+
+    if state.shutdown.is_shutting_down() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down".to_string());
+    }
+
+    let payload = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()));
+    let Some((tool, args)) = hook_call(&hook, payload, header_map(&headers)) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "Webhook needs either 'tool' or 'workflow'".to_string());
+    };
+    info!("Webhook {} triggered {}", name, tool);
+
+    if hook.wait {
+        return match run(&state, &tool, args, hook.timeout_secs).await {
+            Ok(output) => {
+                let status = if output.is_error { StatusCode::BAD_GATEWAY } else { StatusCode::OK };
+                (status, Json(json!({ "hook": name, "tool": tool, "result": output }))).into_response()
+            }
+            Err(e) => error(StatusCode::BAD_GATEWAY, e),
+        };
+    }
+
+    let background = state.clone();
+    let timeout_secs = hook.timeout_secs;
+    let tool_name = tool.clone();
+    tokio::spawn(async move {
+        match run(&background, &tool_name, args, timeout_secs).await {
+            Ok(output) if !output.is_error => info!("Webhook {} completed", name),
+            Ok(output) => warn!("Webhook {}: {} returned an error: {:?}", name, tool_name, output.content),
+            Err(e) => warn!("Webhook {} failed: {}", name, e),
+        }
+    });
+    (StatusCode::ACCEPTED, Json(json!({ "accepted": true, "tool": tool }))).into_response()
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Request headers by lowercase name, without credentials.
+fn header_map(headers: &HeaderMap) -> Value {
+    let map: serde_json::Map<String, Value> = headers
+        .iter()
+        .filter(|(name, _)| !PRIVATE_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.as_str().to_string(), json!(value.to_str().ok()?))))
+        .collect();
+    Value::Object(map)
+}
+
+/// The tool call a request makes: `args` with `{{payload.*}}` and
+/// `{{headers.*}}` filled in. Workflows also get `hook`, `payload` and
+/// `headers` as inputs; tools without `args` get the payload itself.
+fn hook_call(hook: &WebhookConfig, payload: Value, headers: Value) -> Option<(String, Value)> {
+    let context: HashMap<String, Value> = HashMap::from([
+        ("hook".to_string(), json!(hook.name)),
+        ("payload".to_string(), payload),
+        ("headers".to_string(), headers),
+    ]);
+    let args = substitute_context(&hook.args, &context);
+
+    match (&hook.tool, &hook.workflow) {
+        (Some(tool), _) => {
+            let args = match args {
+                Value::Null => context["payload"].clone(),
+                Value::Object(map) if map.is_empty() => context["payload"].clone(),
+                args => args,
+            };
+            Some((tool.clone(), args))
+        }
+        (None, Some(workflow)) => {
+            let mut inputs = match args {
+                Value::Object(map) => map,
+                _ => serde_json::Map::new(),
+            };
+            for (key, value) in context {
+                inputs.entry(key).or_insert(value);
+            }
+            Some((
+                "workflow.execute".to_string(),
+                json!({ "name": workflow, "inputs": inputs }),
+            ))
+        }
+        (None, None) => None,
+    }
+}
+
+async fn run(state: &Arc<RuntimeState>, tool_name: &str, args: Value, timeout_secs: u64) -> Result<ToolOutput, String> {
+    let tool = state
+        .tool_registry
+        .read()
+        .get(tool_name)
+        .cloned()
+        .ok_or_else(|| format!("tool not found: {}", tool_name))?;
+
+    let _running = state.shutdown.begin_call();
+    // Hooks are configured by the operator and run as the server
+    let call = caller::with_context(RequestContext::default(), tool.execute(args, state.clone()));
+    tokio::time::timeout(Duration::from_secs(timeout_secs), call)
+        .await
+        .map_err(|_| format!("{} timed out after {}s", tool_name, timeout_secs))?
+        .map_err(|e| format!("{}: {}", tool_name, e))
+}
+
+/// Checks the request signature of `hook` against `key`.
+fn verify_signature(
+    hook: &WebhookConfig,
+    headers: &HeaderMap,
+    body: &[u8],
+    key: &[u8],
+    now: i64,
+) -> Result<(), String> {
+    let header = match hook.signature {
+        SignatureScheme::Github => "x-hub-signature-256",
+        SignatureScheme::Stripe => "stripe-signature",
+        SignatureScheme::HmacSha256 => hook.signature_header.as_deref().unwrap_or("x-signature-256"),
+    };
+    let value = headers
+        .get(header)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| format!("Missing {} header", header))?;
+
+    match hook.signature {
+        SignatureScheme::Github | SignatureScheme::HmacSha256 => {
+            let hex_digest = value.strip_prefix("sha256=").unwrap_or(value);
+            if hook.signature == SignatureScheme::Github && hex_digest.len() == value.len() {
+                return Err("Signature must start with sha256=".to_string());
+            }
+            check_hmac(key, &[body], hex_digest)
+        }
+        SignatureScheme::Stripe => {
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for part in value.split(',') {
+                match part.trim().split_once('=') {
+                    Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+                    Some(("v1", signature)) => signatures.push(signature),
+                    _ => {}
+                }
+            }
+            let timestamp = timestamp.ok_or_else(|| "Signature has no timestamp".to_string())?;
+            if (now - timestamp).abs() > STRIPE_TOLERANCE_SECS {
+                return Err("Signature timestamp is too old".to_string());
+            }
+            let signed_prefix = format!("{}.", timestamp);
+            if signatures
+                .iter()
+                .any(|signature| check_hmac(key, &[signed_prefix.as_bytes(), body], signature).is_ok())
+            {
+                Ok(())
+            } else {
+                Err("Signature mismatch".to_string())
+            }
+        }
+    }
+}
+
+/// Compares an HMAC-SHA256 of `parts` with a hex digest in constant time.
+fn check_hmac(key: &[u8], parts: &[&[u8]], hex_digest: &str) -> Result<(), String> {
+    let expected = hex::decode(hex_digest).map_err(|_| "Signature is not hex".to_string())?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| e.to_string())?;
+    for part in parts {
+        mac.update(part);
+    }
+    mac.verify_slice(&expected).map_err(|_| "Signature mismatch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(key: &[u8], data: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(data);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn hook(signature: SignatureScheme) -> WebhookConfig {
+        serde_json::from_value(json!({
+            "name": "deploy",
+            "workflow": "redeploy",
+            "args": {"branch": "{{payload.ref}}", "event": "{{headers.x-github-event}}"},
+            "secret": "DEPLOY_SECRET",
+            "signature": signature
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_github_signature() {
+        let body = br#"{"ref":"main"}"#;
+        let hook = hook(SignatureScheme::Github);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-hub-signature-256", format!("sha256={}", sign(b"s3cret", body)).parse().unwrap());
+
+        assert!(verify_signature(&hook, &headers, body, b"s3cret", 0).is_ok());
+        assert_eq!(verify_signature(&hook, &headers, body, b"other", 0).unwrap_err(), "Signature mismatch");
+        assert!(verify_signature(&hook, &HeaderMap::new(), body, b"s3cret", 0).is_err());
+    }
+
+    #[test]
+    fn test_stripe_signature() {
+        let body = br#"{"type":"invoice.paid"}"#;
+        let hook = hook(SignatureScheme::Stripe);
+        let signature = sign(b"whsec", format!("1700000000.{}", std::str::from_utf8(body).unwrap()).as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert("stripe-signature", format!("t=1700000000,v1=00,v1={}", signature).parse().unwrap());
+
+        assert!(verify_signature(&hook, &headers, body, b"whsec", 1700000100).is_ok());
+        assert_eq!(
+            verify_signature(&hook, &headers, body, b"whsec", 1700001000).unwrap_err(),
+            "Signature timestamp is too old"
+        );
+    }
+
+    #[test]
+    fn test_hook_call() {
+        let headers = json!({"x-github-event": "push"});
+        let (tool, args) = hook_call(&hook(SignatureScheme::Github), json!({"ref": "main"}), headers.clone()).unwrap();
+        assert_eq!(tool, "workflow.execute");
+        assert_eq!(args["inputs"]["branch"], "main");
+        assert_eq!(args["inputs"]["event"], "push");
+        assert_eq!(args["inputs"]["payload"], json!({"ref": "main"}));
+
+        let hook = WebhookConfig {
+            tool: Some("echo".to_string()),
+            workflow: None,
+            args: Value::Null,
+            ..hook(SignatureScheme::Github)
+        };
+        let (tool, args) = hook_call(&hook, json!({"text": "hi"}), headers).unwrap();
+        assert_eq!((tool.as_str(), args), ("echo", json!({"text": "hi"})));
+    }
+}
@@ -0,0 +1,547 @@
+//! Release checks and self-update.
+//!
+//! Releases are the GitHub releases of `update.repository`. Each carries an
+//! archive per platform, `aegis-<os>-<arch>.tar.gz` (`.zip` on Windows)
+//! with the `aegis` binary inside, and a `SHA256SUMS` file listing the
+//! archives' checksums, signed with minisign in `SHA256SUMS.minisig`.
+//! `aegis self-update` downloads the archive for the running platform,
+//! refuses it unless the signature verifies with the public key built into
+//! the binary and the archive's checksum matches, and swaps the new binary
+//! in place of the running one with a rename, so an interrupted update
+//! never leaves a half-written executable behind.
+//!
+//! Release builds embed the key from `AEGIS_UPDATE_PUBLIC_KEY` at compile
+//! time; a binary built without it can check for releases but not install
+//! them. Signatures are made with `minisign -S -l`, the format that signs
+//! the file itself rather than its BLAKE2b hash.
+//!
+//! With `update.check` on, the server also looks for a newer release when
+//! it starts; one that is found shows up in the log, in `aegis info` and
+//! as a banner on the dashboard.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::core::config::UpdateConfig;
+use crate::core::RuntimeState;
+
+/// Name of the checksum file of a release.
+pub const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Name of the minisign signature of the checksum file.
+pub const SIGNATURE_ASSET: &str = "SHA256SUMS.minisig";
+
+/// Minisign public key that release checksums are signed with, set when
+/// the binary is built.
+pub const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("AEGIS_UPDATE_PUBLIC_KEY");
+
+/// Largest archive downloaded (the binary is a few tens of MB).
+const MAX_DOWNLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Errors from checking for and installing updates.
+#[derive(Debug, Error)]
+pub enum UpdateError {
+    #[error("Release request failed: {0}")]
+    Request(String),
+
+    #[error("Release {version} has no {asset}")]
+    MissingAsset { version: String, asset: String },
+
+    #[error("Invalid signature of {CHECKSUMS_ASSET}: {0}")]
+    Signature(String),
+
+    #[error("Checksum mismatch for {0}; the download was discarded")]
+    Checksum(String),
+
+    #[error("Invalid archive: {0}")]
+    Archive(String),
+
+    #[error("Failed to install the update: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A published release.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    /// Version, without a leading "v".
+    #[serde(rename = "tag_name", deserialize_with = "version_from_tag")]
+    pub version: String,
+    /// Release page.
+    #[serde(rename = "html_url")]
+    pub url: String,
+    #[serde(default, skip_serializing)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+fn version_from_tag<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let tag = String::deserialize(deserializer)?;
+    Ok(tag.trim_start_matches('v').to_string())
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset, UpdateError> {
+        self.assets.iter().find(|asset| asset.name == name).ok_or_else(|| UpdateError::MissingAsset {
+            version: self.version.clone(),
+            asset: name.to_string(),
+        })
+    }
+}
+
+/// The version of the running binary.
+pub fn current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Whether `candidate` is a later version than `current`. Versions are
+/// compared by their numeric parts; a release beats a pre-release
+/// ("1.2.0-rc.1") of the same version.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    fn parse(version: &str) -> (Vec<u64>, bool) {
+        let version = version.trim_start_matches('v');
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, _)) => (numbers, true),
+            None => (version, false),
+        };
+        (numbers.split('.').map(|part| part.parse().unwrap_or(0)).collect(), pre)
+    }
+    let (candidate, candidate_pre) = parse(candidate);
+    let (current, current_pre) = parse(current);
+    let width = candidate.len().max(current.len());
+    let pad = |mut parts: Vec<u64>| {
+        parts.resize(width, 0);
+        parts
+    };
+    match pad(candidate).cmp(&pad(current)) {
+        std::cmp::Ordering::Equal => current_pre && !candidate_pre,
+        ordering => ordering.is_gt(),
+    }
+}
+
+/// Archive name of the release for the running platform.
+pub fn asset_name() -> String {
+    let extension = if cfg!(windows) { "zip" } else { "tar.gz" };
+    format!("aegis-{}-{}.{}", std::env::consts::OS, std::env::consts::ARCH, extension)
+}
+
+/// Name of the binary inside the archive.
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "aegis.exe"
+    } else {
+        "aegis"
+    }
+}
+
+/// Looks up and downloads releases.
+#[derive(Debug)]
+pub struct Updater {
+    config: UpdateConfig,
+    client: reqwest::Client,
+    public_key: Option<String>,
+}
+
+impl Updater {
+    /// Creates an updater for the configured repository.
+    pub fn new(config: &UpdateConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(format!("aegis/{}", current_version()))
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { config: config.clone(), client, public_key: UPDATE_PUBLIC_KEY.map(str::to_string) }
+    }
+
+    #[cfg(test)]
+    fn with_public_key(mut self, public_key: &str) -> Self {
+        self.public_key = Some(public_key.to_string());
+        self
+    }
+
+    /// The latest release.
+    pub async fn latest(&self) -> Result<Release, UpdateError> {
+        let url = format!(
+            "{}/repos/{}/releases/latest",
+            self.config.api_url.trim_end_matches('/'),
+            self.config.repository
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header("Accept", "application/vnd.github+json")
+            .timeout(Duration::from_secs(15))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| UpdateError::Request(e.to_string()))?;
+        response.json().await.map_err(|e| UpdateError::Request(e.to_string()))
+    }
+
+    /// The latest release, if it is newer than the running binary.
+    pub async fn check(&self) -> Result<Option<Release>, UpdateError> {
+        let release = self.latest().await?;
+        Ok(is_newer(&release.version, current_version()).then_some(release))
+    }
+
+    /// Downloads the binary of a release for the running platform,
+    /// checked against the release's signed checksums.
+    pub async fn download(&self, release: &Release) -> Result<Vec<u8>, UpdateError> {
+        let public_key = self
+            .public_key
+            .as_deref()
+            .ok_or_else(|| UpdateError::Signature("this build has no update signing key".to_string()))?;
+        let name = asset_name();
+        let archive_url = &release.asset(&name)?.browser_download_url;
+        let sums_url = &release.asset(CHECKSUMS_ASSET)?.browser_download_url;
+        let signature_url = &release.asset(SIGNATURE_ASSET)?.browser_download_url;
+
+        let sums = self.fetch(sums_url).await?;
+        let signature = String::from_utf8_lossy(&self.fetch(signature_url).await?).into_owned();
+        verify_signature(&sums, &signature, public_key)?;
+        let sums = String::from_utf8_lossy(&sums).into_owned();
+        let archive = self.fetch(archive_url).await?;
+        verify_checksum(&archive, &sums, &name)?;
+        extract_binary(&archive, &name)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, UpdateError> {
+        debug!("Downloading {}", url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| UpdateError::Request(e.to_string()))?;
+        read_limited(response, url, MAX_DOWNLOAD_BYTES).await
+    }
+}
+
+/// Reads a response body, failing as soon as it grows past `limit` bytes
+/// whether or not the server sent its length.
+async fn read_limited(mut response: reqwest::Response, url: &str, limit: usize) -> Result<Vec<u8>, UpdateError> {
+    let too_large = || UpdateError::Request(format!("{} is larger than {} bytes", url, limit));
+    if response.content_length().is_some_and(|length| length as usize > limit) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| UpdateError::Request(e.to_string()))? {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Checks a minisign signature of `sums` with `public_key`, given as the
+/// key file or just its base64 line. Both the signature of the file and
+/// the one over its trusted comment must verify.
+pub fn verify_signature(sums: &[u8], signature: &str, public_key: &str) -> Result<(), UpdateError> {
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    let invalid = |reason: &str| UpdateError::Signature(reason.to_string());
+    let decode = |line: &str| base64::engine::general_purpose::STANDARD.decode(line.trim()).ok();
+
+    // "Ed", key id, Ed25519 key
+    let key = public_key
+        .lines()
+        .rfind(|line| !line.trim().is_empty() && !line.starts_with("untrusted comment:"))
+        .and_then(decode)
+        .filter(|key| key.len() == 42 && key.starts_with(b"Ed"))
+        .ok_or_else(|| invalid("the public key is malformed"))?;
+    let (key_id, key) = key[2..].split_at(8);
+
+    let mut lines = signature.lines().filter(|line| !line.starts_with("untrusted comment:"));
+    // Algorithm, key id, signature of the file
+    let file_signature = lines
+        .next()
+        .and_then(decode)
+        .filter(|signature| signature.len() == 74)
+        .ok_or_else(|| invalid("the signature is malformed"))?;
+    let trusted_comment = lines
+        .next()
+        .and_then(|line| line.strip_prefix("trusted comment: "))
+        .ok_or_else(|| invalid("the signature has no trusted comment"))?;
+    let comment_signature = lines
+        .next()
+        .and_then(decode)
+        .filter(|signature| signature.len() == 64)
+        .ok_or_else(|| invalid("the trusted comment is not signed"))?;
+
+    match &file_signature[..2] {
+        b"Ed" => {}
+        b"ED" => return Err(invalid("prehashed signatures are not supported; sign with `minisign -S -l`")),
+        _ => return Err(invalid("unknown signature algorithm")),
+    }
+    if &file_signature[2..10] != key_id {
+        return Err(invalid("signed with a different key"));
+    }
+    let key = UnparsedPublicKey::new(&ED25519, key);
+    let file_signature = &file_signature[10..];
+    key.verify(sums, file_signature).map_err(|_| invalid("the signature does not match"))?;
+    let signed_comment = [file_signature, trusted_comment.as_bytes()].concat();
+    key.verify(&signed_comment, &comment_signature)
+        .map_err(|_| invalid("the trusted comment signature does not match"))
+}
+
+/// Checks `archive` against its line in a `SHA256SUMS` file
+/// ("<hex digest>  <name>").
+pub fn verify_checksum(archive: &[u8], sums: &str, name: &str) -> Result<(), UpdateError> {
+    let expected = sums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, file)| file.trim().trim_start_matches('*') == name)
+        .map(|(digest, _)| digest.to_lowercase())
+        .ok_or_else(|| UpdateError::Checksum(format!("{} (not listed in {})", name, CHECKSUMS_ASSET)))?;
+    if hex::encode(Sha256::digest(archive)) == expected {
+        Ok(())
+    } else {
+        Err(UpdateError::Checksum(name.to_string()))
+    }
+}
+
+/// Takes the binary out of a release archive.
+fn extract_binary(archive: &[u8], name: &str) -> Result<Vec<u8>, UpdateError> {
+    let wanted = binary_name();
+    let is_binary = |path: &Path| path.file_name().is_some_and(|file| file == wanted);
+    let mut binary = Vec::new();
+
+    if name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).map_err(|e| UpdateError::Archive(e.to_string()))?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).map_err(|e| UpdateError::Archive(e.to_string()))?;
+            if file.is_file() && file.enclosed_name().is_some_and(|path| is_binary(&path)) {
+                file.read_to_end(&mut binary)?;
+                return Ok(binary);
+            }
+        }
+    } else {
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+        for entry in tar.entries().map_err(|e| UpdateError::Archive(e.to_string()))? {
+            let mut entry = entry.map_err(|e| UpdateError::Archive(e.to_string()))?;
+            let path = entry.path().map_err(|e| UpdateError::Archive(e.to_string()))?.into_owned();
+            if entry.header().entry_type().is_file() && is_binary(&path) {
+                entry.read_to_end(&mut binary)?;
+                return Ok(binary);
+            }
+        }
+    }
+    Err(UpdateError::Archive(format!("{} has no {}", name, wanted)))
+}
+
+/// Replaces the executable at `target` with `binary`. The new binary is
+/// written next to it and renamed over it. Windows can't replace a running
+/// executable, so there the old one is moved aside to `<name>.old` first.
+pub fn install(binary: &[u8], target: &Path) -> Result<(), UpdateError> {
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "executable path has no file name"))?
+        .to_string_lossy()
+        .into_owned();
+    let staged = target.with_file_name(format!(".{}.new", file_name));
+    std::fs::write(&staged, binary)?;
+    let permissions = std::fs::metadata(target)?.permissions();
+    std::fs::set_permissions(&staged, permissions)?;
+
+    if cfg!(windows) {
+        let old = target.with_file_name(format!("{}.old", file_name));
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(target, &old)?;
+    }
+    if let Err(e) = std::fs::rename(&staged, target) {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// The path of the running executable, with symlinks resolved so the
+/// real binary is replaced rather than the link.
+pub fn current_executable() -> Result<PathBuf, UpdateError> {
+    Ok(std::env::current_exe()?.canonicalize()?)
+}
+
+/// Looks for a newer release in the background, if `update.check` is on,
+/// and records it in the state for the dashboard.
+pub fn start_check(state: &Arc<RuntimeState>) {
+    if !state.config.update.check {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        match Updater::new(&state.config.update).check().await {
+            Ok(Some(release)) => {
+                info!(
+                    "Aegis {} is available (running {}); update with `aegis self-update`",
+                    release.version,
+                    current_version()
+                );
+                *state.available_update.write() = Some(release);
+            }
+            Ok(None) => debug!("Aegis {} is the latest release", current_version()),
+            Err(e) => debug!("Update check failed: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+    use serde_json::json;
+
+    #[test]
+    fn test_version_order() {
+        assert!(is_newer("0.3.0", "0.2.9"));
+        assert!(is_newer("v1.10.0", "1.9.3"));
+        assert!(is_newer("1.2.0", "1.2.0-rc.1"));
+        assert!(!is_newer("1.2.0-rc.1", "1.2.0"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(!is_newer("0.2.0", "0.2.1"));
+    }
+
+    fn release_archive(binary: &[u8]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(binary.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        tar.append_data(&mut header, format!("aegis-1.0.0/{}", binary_name()), binary).unwrap();
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// A minisign key pair as (public key file, signing function).
+    fn minisign_key() -> (String, impl Fn(&[u8]) -> String) {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+        let base64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key_id = *b"12345678";
+        let public_key = format!(
+            "untrusted comment: minisign public key\n{}\n",
+            base64(&[b"Ed".as_slice(), &key_id, pair.public_key().as_ref()].concat())
+        );
+        let sign = move |file: &[u8]| {
+            let signature = pair.sign(file);
+            let comment = "timestamp:1700000000\tfile:SHA256SUMS";
+            let comment_signature = pair.sign(&[signature.as_ref(), comment.as_bytes()].concat());
+            format!(
+                "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+                base64(&[b"Ed".as_slice(), &key_id, signature.as_ref()].concat()),
+                comment,
+                base64(comment_signature.as_ref())
+            )
+        };
+        (public_key, sign)
+    }
+
+    #[test]
+    fn test_signature() {
+        let (public_key, sign) = minisign_key();
+        let signature = sign(b"sums");
+        assert!(verify_signature(b"sums", &signature, &public_key).is_ok());
+        assert!(verify_signature(b"sums", &signature, public_key.lines().nth(1).unwrap()).is_ok());
+        assert!(matches!(verify_signature(b"other sums", &signature, &public_key), Err(UpdateError::Signature(_))));
+
+        // Another key, or an edited trusted comment, is refused
+        let (other_key, _) = minisign_key();
+        assert!(verify_signature(b"sums", &signature, &other_key).is_err());
+        let edited = signature.replace("file:SHA256SUMS", "file:other");
+        assert!(verify_signature(b"sums", &edited, &public_key).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_download_and_install() {
+        let archive = release_archive(b"new binary");
+        let sums = format!("{}  {}\n", hex::encode(Sha256::digest(&archive)), asset_name());
+        let (public_key, sign) = minisign_key();
+        let signature = sign(sums.as_bytes());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let latest = json!({
+            "tag_name": "v99.0.0",
+            "html_url": format!("{}/releases/v99.0.0", base),
+            "assets": [
+                {"name": asset_name(), "browser_download_url": format!("{}/archive", base)},
+                {"name": CHECKSUMS_ASSET, "browser_download_url": format!("{}/sums", base)},
+                {"name": SIGNATURE_ASSET, "browser_download_url": format!("{}/sums.minisig", base)},
+            ]
+        });
+        let app = Router::new()
+            .route("/repos/acme/aegis/releases/latest", get(move || async move { Json(latest) }))
+            .route("/archive", get(move || async move { archive }))
+            .route("/sums", get(move || async move { sums }))
+            .route("/sums.minisig", get(move || async move { signature }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = UpdateConfig { check: true, repository: "acme/aegis".to_string(), api_url: base };
+        let release = Updater::new(&config).check().await.unwrap().expect("99.0.0 is newer");
+        assert_eq!(release.version, "99.0.0");
+
+        // Checksums signed with another key are refused
+        let (other_key, _) = minisign_key();
+        let updater = Updater::new(&config).with_public_key(&other_key);
+        assert!(matches!(updater.download(&release).await, Err(UpdateError::Signature(_))));
+
+        let updater = Updater::new(&config).with_public_key(&public_key);
+        let binary = updater.download(&release).await.unwrap();
+        assert_eq!(binary, b"new binary");
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("aegis");
+        std::fs::write(&target, b"old binary").unwrap();
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755)).unwrap();
+        install(&binary, &target).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new binary");
+        assert_eq!(std::fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A tampered download is refused
+        let other = release_archive(b"evil binary");
+        assert!(matches!(verify_checksum(&other, &format!("{}  {}", hex::encode(Sha256::digest(b"x")), asset_name()), &asset_name()), Err(UpdateError::Checksum(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_limit_without_content_length() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A chunked body never says how large it is up front
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/archive", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = socket.read(&mut request).await;
+                let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n".to_vec();
+                for _ in 0..4 {
+                    response.extend_from_slice(b"8\r\n12345678\r\n");
+                }
+                response.extend_from_slice(b"0\r\n\r\n");
+                let _ = socket.write_all(&response).await;
+            }
+        });
+
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.content_length(), None);
+        assert!(matches!(read_limited(response, &url, 20).await, Err(UpdateError::Request(_))));
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(read_limited(response, &url, 32).await.unwrap().len(), 32);
+    }
+}